@@ -73,11 +73,46 @@ pub enum Command {
         /// When `None`, the server keeps existing secrets for this app.
         #[serde(default)]
         secrets: Option<HashMap<String, String>>,
+
+        /// Digest of everything that defines this deploy (see
+        /// [`compute_deploy_idempotency_key`]). When it matches the app's last
+        /// successful deploy of the same version, the server returns that
+        /// result instead of running another rolling update.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        idempotency_key: Option<String>,
+
+        /// How running instances are replaced when the app already has some.
+        #[serde(default, skip_serializing_if = "DeployStrategy::is_rolling")]
+        strategy: DeployStrategy,
+
+        /// Canary percentage (1-99). When set, the new build first serves
+        /// only this share of requests for a hold period, then takes over.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        canary: Option<u8>,
+    },
+
+    /// Dry-run validation of a release directory: checks routes, the
+    /// `app.json` manifest, runtime resolution, and the launch command
+    /// without starting processes or mutating server state.
+    ValidateConfig {
+        path: String,
+        routes: Vec<String>,
+        /// Extra env vars the release would be deployed with.
+        #[serde(default)]
+        env: HashMap<String, String>,
     },
 
     /// Update the desired minimum number of instances for an app.
     Scale { app: String, instances: u8 },
 
+    /// Restart an app: one instance when `instance_id` is set, otherwise a
+    /// rolling restart of every instance
+    Restart {
+        app: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance_id: Option<String>,
+    },
+
     /// Stop an app
     Stop { app: String },
 
@@ -87,18 +122,98 @@ pub enum Command {
     /// Get status of an app
     Status { app: String },
 
+    /// Like `Status`, but keeps the connection open and pushes a fresh
+    /// `AppStatus` line whenever the app's instances change state
+    StatusStream { app: String },
+
+    /// Recent logs for an app: instance output (`app` scope) interleaved
+    /// with Tako lifecycle events (`tako` scope), oldest first.
+    Tail {
+        app: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lines: Option<usize>,
+    },
+
+    /// Request counts, error rate, and p50/p95 latency per app, or for one
+    /// app when `app` is set
+    Metrics {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app: Option<String>,
+    },
+
+    /// Environment an app's instances start with: vars plus secrets. Secret
+    /// values are redacted unless `reveal` is set and the server allows it.
+    Env {
+        app: String,
+        #[serde(default)]
+        reveal: bool,
+    },
+
     /// List all apps
     List,
 
     /// List release/build history for an app
     ListReleases { app: String },
 
-    /// Roll back an app to a previously deployed release/build
-    Rollback { app: String, version: String },
+    /// Recent deploy attempts for an app (newest first) with their outcome
+    /// and instance counts.
+    History {
+        app: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+
+    /// Roll back an app to a previously deployed release/build. Without a
+    /// version, rolls back to the most recently replaced one.
+    Rollback {
+        app: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+    },
+
+    /// Cut an app over to a build that is already running alongside the
+    /// current one: make it the current version and drain every other build.
+    Promote { app: String, version: String },
 
     /// List all configured routes (all apps)
     Routes,
 
+    /// Resolve a host and path to the app that would serve it, with the
+    /// winning route pattern. Nothing is proxied.
+    TestRoute { host: String, path: String },
+
+    /// Set an explicit priority for one of an app's routes. Among matching
+    /// routes a higher priority wins; equal priorities fall back to
+    /// specificity. Priority 0 is the default and clears the override.
+    SetRoutePriority {
+        app: String,
+        route: String,
+        priority: i32,
+    },
+
+    /// Change how long an app's instances may finish in-flight requests
+    /// before being stopped. Persisted and used by every later drain.
+    SetDrainTimeout { app: String, seconds: u32 },
+
+    /// Serve a fixed page for an app whenever it has no healthy instances
+    /// (stopped, errored, or every instance down). `status` defaults to 503
+    /// and `body` to a built-in page; `enabled: false` clears it.
+    SetMaintenance {
+        app: String,
+        enabled: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<u16>,
+    },
+
+    /// Take an app out of rotation without stopping it: its instances keep
+    /// running and finish in-flight requests, but receive no new ones.
+    Drain { app: String },
+
+    /// Put a drained app back into rotation.
+    Undrain { app: String },
+
     /// Update secrets for an app
     UpdateSecrets {
         app: String,
@@ -117,6 +232,49 @@ pub enum Command {
     /// Exit upgrading mode for the lock owner.
     ExitUpgrading { owner: String },
 
+    /// Validate a PEM certificate/key pair for a domain without installing it.
+    /// Returns the issuer and validity window when the key matches the
+    /// certificate and the domain is covered by its SANs.
+    CheckCert {
+        cert_pem: String,
+        key_pem: String,
+        domain: String,
+    },
+
+    /// Install a PEM certificate/key pair for a domain (e.g. a purchased cert
+    /// under `--no-acme`). Validated like `CheckCert`, rejected when expired,
+    /// and served on the next TLS handshake without a restart.
+    ImportCert {
+        domain: String,
+        cert_pem: String,
+        key_pem: String,
+    },
+
+    /// List the certificates the server holds with their expiry.
+    Certs,
+
+    /// Renew a held ACME certificate now, regardless of its expiry.
+    RenewCert { domain: String },
+
+    /// Point-in-time view of apps, routes, certificates, and server mode.
+    /// Secrets are included only when `include_secrets` is set.
+    Snapshot {
+        #[serde(default)]
+        include_secrets: bool,
+    },
+
+    /// Dump every persisted app registration as a [`StateExport`] document
+    /// for backups and server migrations. Secret values are not included.
+    Export,
+
+    /// Recreate apps from an [`Export`](Command::Export) document. Apps that
+    /// already exist are skipped unless `replace` is set.
+    Import {
+        doc: StateExport,
+        #[serde(default)]
+        replace: bool,
+    },
+
     /// Inject an ACME challenge token (for testing HTTP-01 challenge serving).
     InjectChallengeToken {
         token: String,
@@ -308,6 +466,45 @@ pub struct HelloResponse {
     pub capabilities: Vec<String>,
 }
 
+/// How a deploy replaces an app's running instances.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeployStrategy {
+    /// Start new instances one at a time, draining an old one after each
+    #[default]
+    Rolling,
+    /// Start the whole new build alongside the old one, switch traffic once
+    /// every new instance is healthy, then drain the old build
+    BlueGreen,
+}
+
+impl DeployStrategy {
+    pub fn is_rolling(&self) -> bool {
+        *self == Self::Rolling
+    }
+}
+
+/// Token-bucket request limit the edge proxy applies to an app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// Sustained rate; the bucket refills this many tokens per second
+    pub requests_per_second: u32,
+    /// Bucket size, i.e. how many requests may arrive at once. Unset uses
+    /// `requests_per_second`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+    /// Give each client IP its own bucket instead of sharing one per app
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub per_client_ip: bool,
+}
+
+impl RateLimit {
+    pub fn burst(&self) -> u32 {
+        self.burst.unwrap_or(self.requests_per_second).max(1)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum UpgradeMode {
@@ -326,6 +523,8 @@ pub struct ServerRuntimeInfo {
     pub no_acme: bool,
     pub acme_staging: bool,
     #[serde(default)]
+    pub acme_directory: Option<String>,
+    #[serde(default)]
     pub acme_email: Option<String>,
     pub renewal_interval_hours: u64,
     #[serde(default)]
@@ -336,6 +535,22 @@ pub struct ServerRuntimeInfo {
     pub metrics_port: Option<u16>,
     #[serde(default)]
     pub server_name: Option<String>,
+    /// Certificates that are close to expiry or failed to renew
+    #[serde(default)]
+    pub cert_warnings: Vec<CertWarning>,
+}
+
+/// Outstanding problem with one of the server's certificates. Cleared once
+/// the certificate renews.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertWarning {
+    pub domain: String,
+    /// Set when the cert is inside the expiry warning window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in_days: Option<i64>,
+    /// Error from the most recent failed renewal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renewal_error: Option<String>,
 }
 
 /// Response from the server
@@ -395,6 +610,15 @@ pub struct AppStatus {
     pub state: AppState,
 
     pub last_error: Option<String>,
+    /// Current secrets generation; bumped by every secrets change
+    #[serde(default)]
+    pub secrets_generation: u64,
+    /// Some instance still runs with secrets older than `secrets_generation`
+    #[serde(default)]
+    pub secrets_stale: bool,
+    /// Resource usage summed across instances that could be sampled
+    #[serde(default)]
+    pub usage: ResourceUsage,
 }
 
 /// Runtime status for a specific build/version of an app.
@@ -413,6 +637,34 @@ pub struct InstanceStatus {
     pub pid: Option<u32>,
     pub uptime_secs: u64,
     pub requests_total: u64,
+    /// Secrets generation the instance was launched with
+    #[serde(default)]
+    pub secrets_generation: u64,
+    /// Resource usage of the instance process, when it could be sampled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
+}
+
+/// Process resource usage sampled from the OS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Resident set size in bytes
+    pub rss_bytes: u64,
+    /// User + system CPU time consumed since the process started
+    pub cpu_time_ms: u64,
+}
+
+impl ResourceUsage {
+    /// Sum the usage of every instance that reported one.
+    pub fn total<'a>(instances: impl IntoIterator<Item = &'a InstanceStatus>) -> Self {
+        instances
+            .into_iter()
+            .filter_map(|instance| instance.usage)
+            .fold(Self::default(), |total, usage| Self {
+                rss_bytes: total.rss_bytes.saturating_add(usage.rss_bytes),
+                cpu_time_ms: total.cpu_time_ms.saturating_add(usage.cpu_time_ms),
+            })
+    }
 }
 
 /// App state
@@ -488,6 +740,94 @@ pub struct ListReleasesResponse {
     pub releases: Vec<ReleaseInfo>,
 }
 
+/// Outcome of a recorded deploy attempt.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeployResult {
+    Succeeded,
+    Failed,
+    RolledBack,
+}
+
+/// A single deploy attempt returned by `history`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeployHistoryEntry {
+    pub version: String,
+    pub deployed_at_unix_secs: i64,
+    pub result: DeployResult,
+    pub new_instances: u32,
+    pub old_instances: u32,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Format version written by `export`; `import` rejects newer documents.
+pub const STATE_EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Document produced by `export` and consumed by `import`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateExport {
+    pub format_version: u32,
+    pub apps: Vec<ExportedApp>,
+}
+
+/// One app registration in a [`StateExport`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExportedApp {
+    pub name: String,
+    pub environment: String,
+    /// Release the app was running; its release directory must exist on
+    /// the importing server.
+    pub version: String,
+    pub min_instances: u32,
+    pub max_instances: u32,
+    pub drain_timeout_secs: u64,
+    pub routes: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub route_priorities: BTreeMap<String, i32>,
+    /// Names of the secrets the app had. Values stay on the source server;
+    /// re-send them with `update_secrets` after importing elsewhere.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_names: Vec<String>,
+    /// Secrets stored as `file:` or `env:` references. These name where the
+    /// value lives on the server rather than the value, so they are exported
+    /// as written and stored again on import.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secret_refs: BTreeMap<String, String>,
+}
+
+impl ExportedApp {
+    /// Deployment id (`name/environment`) the app is registered under.
+    pub fn deployment_id(&self) -> String {
+        deployment_app_id(&self.name, &self.environment)
+    }
+}
+
+/// Response payload for `history`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeployHistoryResponse {
+    pub app: String,
+    pub entries: Vec<DeployHistoryEntry>,
+}
+
+/// A single log line returned by `tail`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TailLogEntry {
+    /// RFC 3339 UTC timestamp with millisecond precision.
+    pub timestamp: String,
+    /// `app` for instance stdout/stderr, `tako` for lifecycle events.
+    pub scope: String,
+    pub instance_id: String,
+    pub message: String,
+}
+
+/// Response payload for `tail`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TailResponse {
+    pub app: String,
+    pub entries: Vec<TailLogEntry>,
+}
+
 /// Compute a stable SHA-256 hash of a secrets map.
 ///
 /// The hash is computed over sorted key-value pairs to ensure deterministic
@@ -507,6 +847,28 @@ pub fn compute_secrets_hash(secrets: &HashMap<String, String>) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Compute the idempotency key for deploying one artifact: release version,
+/// artifact SHA-256, secrets hash, and routes (order-insensitive).
+pub fn compute_deploy_idempotency_key(
+    version: &str,
+    artifact_sha256: &str,
+    secrets_hash: &str,
+    routes: &[String],
+) -> String {
+    let mut routes: Vec<&String> = routes.iter().collect();
+    routes.sort();
+    routes.dedup();
+    let mut hasher = Sha256::new();
+    for field in [version, artifact_sha256, secrets_hash]
+        .into_iter()
+        .chain(routes.into_iter().map(String::as_str))
+    {
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -550,6 +912,9 @@ mod tests {
                 "API_KEY".to_string(),
                 "secret123".to_string(),
             )])),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         };
         let json = serde_json::to_string(&cmd).unwrap();
         assert!(json.contains(r#""command":"deploy""#));
@@ -623,6 +988,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_metrics_command_roundtrip() {
+        let cmd: Command = serde_json::from_str(r#"{"command":"metrics"}"#).unwrap();
+        assert!(matches!(cmd, Command::Metrics { app: None }));
+
+        let cmd = Command::Metrics {
+            app: Some("my-app".to_string()),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"command":"metrics","app":"my-app"}"#);
+    }
+
+    #[test]
+    fn test_env_command_defaults_to_redacted() {
+        let cmd: Command = serde_json::from_str(r#"{"command":"env","app":"my-app"}"#).unwrap();
+        match cmd {
+            Command::Env { app, reveal } => {
+                assert_eq!(app, "my-app");
+                assert!(!reveal);
+            }
+            _ => panic!("Expected env command"),
+        }
+    }
+
+    #[test]
+    fn test_restart_command_roundtrip() {
+        let json = r#"{"command":"restart","app":"my-app"}"#;
+        let cmd: Command = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&cmd).unwrap(), json);
+
+        let cmd = Command::Restart {
+            app: "my-app".to_string(),
+            instance_id: Some("a1b2".to_string()),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""instance_id":"a1b2""#));
+        match serde_json::from_str::<Command>(&json).unwrap() {
+            Command::Restart { app, instance_id } => {
+                assert_eq!(app, "my-app");
+                assert_eq!(instance_id.as_deref(), Some("a1b2"));
+            }
+            _ => panic!("Expected restart command"),
+        }
+    }
+
     #[test]
     fn test_hello_roundtrip() {
         let cmd = Command::Hello {
@@ -643,6 +1053,74 @@ mod tests {
         assert!(json.contains(r#""command":"routes""#));
     }
 
+    #[test]
+    fn test_test_route_command_serialization() {
+        let cmd = Command::TestRoute {
+            host: "api.example.com".to_string(),
+            path: "/v1/users".to_string(),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(
+            json,
+            r#"{"command":"test_route","host":"api.example.com","path":"/v1/users"}"#
+        );
+    }
+
+    #[test]
+    fn test_certs_command_serialization() {
+        let cmd = Command::Certs;
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"command":"certs"}"#);
+    }
+
+    #[test]
+    fn test_snapshot_command_serialization() {
+        let cmd = Command::Snapshot {
+            include_secrets: true,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"command":"snapshot","include_secrets":true}"#);
+
+        let parsed: Command = serde_json::from_str(r#"{"command":"snapshot"}"#).unwrap();
+        assert!(matches!(
+            parsed,
+            Command::Snapshot {
+                include_secrets: false
+            }
+        ));
+    }
+
+    #[test]
+    fn test_export_import_command_serialization() {
+        let json = serde_json::to_string(&Command::Export).unwrap();
+        assert_eq!(json, r#"{"command":"export"}"#);
+
+        let parsed: Command = serde_json::from_str(
+            r#"{"command":"import","doc":{"format_version":1,"apps":[{"name":"web","environment":"production","version":"v3","min_instances":0,"max_instances":4,"drain_timeout_secs":30,"routes":["example.com"]}]}}"#,
+        )
+        .unwrap();
+        let Command::Import { doc, replace } = parsed else {
+            panic!("expected import");
+        };
+        assert!(!replace);
+        assert_eq!(doc.apps[0].deployment_id(), "web/production");
+        assert!(doc.apps[0].route_priorities.is_empty());
+        assert!(doc.apps[0].secret_names.is_empty());
+        assert!(doc.apps[0].secret_refs.is_empty());
+    }
+
+    #[test]
+    fn test_set_route_priority_command_serialization() {
+        let cmd = Command::SetRoutePriority {
+            app: "my-app".to_string(),
+            route: "example.com/api/*".to_string(),
+            priority: -5,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"set_route_priority""#));
+        assert!(json.contains(r#""priority":-5"#));
+    }
+
     #[test]
     fn test_server_info_command_serialization() {
         let cmd = Command::ServerInfo;
@@ -670,6 +1148,51 @@ mod tests {
         assert!(json.contains(r#""owner":"controller-a""#));
     }
 
+    #[test]
+    fn test_status_stream_command_serialization() {
+        let cmd = Command::StatusStream {
+            app: "my-app".to_string(),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"command":"status_stream","app":"my-app"}"#);
+    }
+
+    #[test]
+    fn test_tail_command_serialization() {
+        let cmd = Command::Tail {
+            app: "my-app".to_string(),
+            lines: Some(50),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"tail""#));
+        assert!(json.contains(r#""lines":50"#));
+
+        let parsed: Command = serde_json::from_str(r#"{"command":"tail","app":"my-app"}"#).unwrap();
+        assert!(matches!(parsed, Command::Tail { lines: None, .. }));
+    }
+
+    #[test]
+    fn test_history_command_serialization() {
+        let cmd = Command::History {
+            app: "my-app".to_string(),
+            limit: Some(5),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"history""#));
+        assert!(json.contains(r#""limit":5"#));
+
+        let entry = DeployHistoryEntry {
+            version: "v2".to_string(),
+            deployed_at_unix_secs: 1_700_000_000,
+            result: DeployResult::RolledBack,
+            new_instances: 0,
+            old_instances: 2,
+            error: Some("health check timed out".to_string()),
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""result":"rolled_back""#));
+    }
+
     #[test]
     fn test_list_releases_command_serialization() {
         let cmd = Command::ListReleases {
@@ -684,7 +1207,7 @@ mod tests {
     fn test_rollback_command_serialization() {
         let cmd = Command::Rollback {
             app: "my-app".to_string(),
-            version: "abc1234".to_string(),
+            version: Some("abc1234".to_string()),
         };
         let json = serde_json::to_string(&cmd).unwrap();
         assert!(json.contains(r#""command":"rollback""#));
@@ -692,6 +1215,149 @@ mod tests {
         assert!(json.contains(r#""version":"abc1234""#));
     }
 
+    #[test]
+    fn test_rollback_command_without_version_deserializes() {
+        let json = r#"{"command":"rollback","app":"my-app"}"#;
+        let cmd: Command = serde_json::from_str(json).unwrap();
+        match cmd {
+            Command::Rollback { app, version } => {
+                assert_eq!(app, "my-app");
+                assert!(version.is_none());
+            }
+            _ => panic!("Expected rollback command"),
+        }
+    }
+
+    #[test]
+    fn test_set_drain_timeout_command_serialization() {
+        let cmd = Command::SetDrainTimeout {
+            app: "my-app".to_string(),
+            seconds: 90,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"set_drain_timeout""#));
+        assert!(json.contains(r#""seconds":90"#));
+    }
+
+    #[test]
+    fn test_rate_limit_burst_defaults_to_rate() {
+        let limit: RateLimit = serde_json::from_str(r#"{"requests_per_second":20}"#).unwrap();
+        assert_eq!(limit.burst(), 20);
+        assert!(!limit.per_client_ip);
+        assert_eq!(
+            serde_json::to_string(&limit).unwrap(),
+            r#"{"requests_per_second":20}"#
+        );
+
+        let limit: RateLimit =
+            serde_json::from_str(r#"{"requests_per_second":5,"burst":50,"per_client_ip":true}"#)
+                .unwrap();
+        assert_eq!(limit.burst(), 50);
+        assert!(limit.per_client_ip);
+    }
+
+    #[test]
+    fn test_set_maintenance_command_serialization() {
+        let cmd = Command::SetMaintenance {
+            app: "my-app".to_string(),
+            enabled: true,
+            body: None,
+            status: Some(502),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"set_maintenance""#));
+        assert!(json.contains(r#""status":502"#));
+        assert!(!json.contains("body"));
+
+        let parsed: Command =
+            serde_json::from_str(r#"{"command":"set_maintenance","app":"my-app","enabled":false}"#)
+                .unwrap();
+        assert!(matches!(
+            parsed,
+            Command::SetMaintenance {
+                enabled: false,
+                body: None,
+                status: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_drain_commands_serialization() {
+        let json = serde_json::to_string(&Command::Drain {
+            app: "my-app".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"command":"drain","app":"my-app"}"#);
+
+        let parsed: Command =
+            serde_json::from_str(r#"{"command":"undrain","app":"my-app"}"#).unwrap();
+        assert!(matches!(parsed, Command::Undrain { app } if app == "my-app"));
+    }
+
+    #[test]
+    fn test_promote_command_serialization() {
+        let cmd = Command::Promote {
+            app: "my-app".to_string(),
+            version: "v2".to_string(),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"promote""#));
+        assert!(json.contains(r#""version":"v2""#));
+    }
+
+    #[test]
+    fn test_validate_config_command_deserialization_defaults_env() {
+        let json = r#"{
+            "command":"validate_config",
+            "path":"/tmp/release",
+            "routes":["example.com"]
+        }"#;
+        let cmd: Command = serde_json::from_str(json).unwrap();
+        match cmd {
+            Command::ValidateConfig { path, routes, env } => {
+                assert_eq!(path, "/tmp/release");
+                assert_eq!(routes, vec!["example.com".to_string()]);
+                assert!(env.is_empty());
+            }
+            _ => panic!("Expected validate_config command"),
+        }
+    }
+
+    #[test]
+    fn test_check_cert_command_serialization() {
+        let cmd = Command::CheckCert {
+            cert_pem: "CERT".to_string(),
+            key_pem: "KEY".to_string(),
+            domain: "example.com".to_string(),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"check_cert""#));
+        assert!(json.contains(r#""domain":"example.com""#));
+    }
+
+    #[test]
+    fn test_import_cert_command_serialization() {
+        let cmd = Command::ImportCert {
+            domain: "example.com".to_string(),
+            cert_pem: "CERT".to_string(),
+            key_pem: "KEY".to_string(),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""command":"import_cert""#));
+        assert!(json.contains(r#""key_pem":"KEY""#));
+    }
+
+    #[test]
+    fn test_renew_cert_command_serialization() {
+        let cmd = Command::RenewCert {
+            domain: "example.com".to_string(),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"command":"renew_cert","domain":"example.com"}"#);
+    }
+
     #[test]
     fn test_delete_command_serialization() {
         let cmd = Command::Delete {
@@ -757,6 +1423,38 @@ mod tests {
 
         let status: AppStatus = serde_json::from_value(value).unwrap();
         assert!(status.builds.is_empty());
+        assert_eq!(status.usage, ResourceUsage::default());
+    }
+
+    #[test]
+    fn test_resource_usage_total_skips_unsampled_instances() {
+        let instance = |usage| InstanceStatus {
+            id: "i".to_string(),
+            state: InstanceState::Healthy,
+            pid: Some(1),
+            uptime_secs: 0,
+            requests_total: 0,
+            secrets_generation: 0,
+            usage,
+        };
+        let instances = [
+            instance(Some(ResourceUsage {
+                rss_bytes: 100,
+                cpu_time_ms: 5,
+            })),
+            instance(None),
+            instance(Some(ResourceUsage {
+                rss_bytes: 50,
+                cpu_time_ms: 7,
+            })),
+        ];
+        assert_eq!(
+            ResourceUsage::total(&instances),
+            ResourceUsage {
+                rss_bytes: 150,
+                cpu_time_ms: 12,
+            }
+        );
     }
 
     #[test]
@@ -882,6 +1580,78 @@ mod tests {
         assert_ne!(compute_secrets_hash(&a), compute_secrets_hash(&b));
     }
 
+    #[test]
+    fn test_deploy_idempotency_key_round_trips_and_is_optional() {
+        let cmd = Command::Deploy {
+            app: "my-app".to_string(),
+            version: "v1".to_string(),
+            path: "/opt/tako/apps/my-app/releases/v1".to_string(),
+            routes: vec!["example.com".to_string()],
+            secrets: None,
+            idempotency_key: Some("abc".to_string()),
+            strategy: Default::default(),
+            canary: None,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains(r#""idempotency_key":"abc""#));
+        match serde_json::from_str(&json).unwrap() {
+            Command::Deploy {
+                idempotency_key, ..
+            } => assert_eq!(idempotency_key.as_deref(), Some("abc")),
+            _ => panic!("Expected deploy command"),
+        }
+
+        let without_key = Command::Deploy {
+            app: "my-app".to_string(),
+            version: "v1".to_string(),
+            path: "/opt/tako/apps/my-app/releases/v1".to_string(),
+            routes: vec![],
+            secrets: None,
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
+        };
+        let json = serde_json::to_string(&without_key).unwrap();
+        assert!(!json.contains("idempotency_key"));
+        assert!(!json.contains("strategy"));
+    }
+
+    #[test]
+    fn test_deploy_command_blue_green_strategy_roundtrip() {
+        let json = r#"{"command":"deploy","app":"my-app","version":"v1","path":"/p","routes":[],"strategy":"blue_green"}"#;
+        match serde_json::from_str(json).unwrap() {
+            Command::Deploy { strategy, .. } => assert_eq!(strategy, DeployStrategy::BlueGreen),
+            _ => panic!("Expected deploy command"),
+        }
+    }
+
+    #[test]
+    fn test_deploy_idempotency_key_covers_every_input() {
+        let routes = vec!["a.example.com".to_string(), "b.example.com".to_string()];
+        let base = compute_deploy_idempotency_key("v1", "digest", "secrets", &routes);
+        let reordered = vec!["b.example.com".to_string(), "a.example.com".to_string()];
+        assert_eq!(
+            base,
+            compute_deploy_idempotency_key("v1", "digest", "secrets", &reordered)
+        );
+        assert_ne!(
+            base,
+            compute_deploy_idempotency_key("v2", "digest", "secrets", &routes)
+        );
+        assert_ne!(
+            base,
+            compute_deploy_idempotency_key("v1", "other", "secrets", &routes)
+        );
+        assert_ne!(
+            base,
+            compute_deploy_idempotency_key("v1", "digest", "rotated", &routes)
+        );
+        assert_ne!(
+            base,
+            compute_deploy_idempotency_key("v1", "digest", "secrets", &routes[..1])
+        );
+    }
+
     #[test]
     fn test_deploy_with_none_secrets_keeps_existing() {
         let cmd = Command::Deploy {
@@ -890,6 +1660,9 @@ mod tests {
             path: "/opt/tako/apps/my-app/releases/v1".to_string(),
             routes: vec!["example.com".to_string()],
             secrets: None,
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         };
         let json = serde_json::to_string(&cmd).unwrap();
         let parsed: Command = serde_json::from_str(&json).unwrap();
@@ -945,17 +1718,24 @@ mod tests {
             https_port: 443,
             no_acme: false,
             acme_staging: false,
+            acme_directory: None,
             acme_email: None,
             renewal_interval_hours: 12,
             dns_provider: None,
             standby: false,
             metrics_port: Some(9898),
             server_name: Some("la".to_string()),
+            cert_warnings: vec![CertWarning {
+                domain: "example.com".to_string(),
+                expires_in_days: Some(5),
+                renewal_error: None,
+            }],
         };
         let json = serde_json::to_string(&info).unwrap();
         let parsed: ServerRuntimeInfo = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.pid, 42);
         assert!(parsed.dns_provider.is_none());
         assert_eq!(parsed.server_name.as_deref(), Some("la"));
+        assert_eq!(parsed.cert_warnings, info.cert_warnings);
     }
 }
@@ -192,27 +192,54 @@ fn resolve_pm(ctx: &PluginContext, default: PackageManager) -> PackageManager {
 
 // ── Package manager → commands ─────────────────────────────────────
 
-fn pm_install_production(pm: &PackageManager) -> String {
+/// Production install command. With `frozen` (the package manager's lockfile
+/// is present), installs refuse to rewrite the lockfile.
+fn pm_install_production(pm: &PackageManager, frozen: bool) -> String {
     match pm {
+        PackageManager::Bun if frozen => "bun install --production --frozen-lockfile".to_string(),
         PackageManager::Bun => "bun install --production".to_string(),
-        PackageManager::Npm => {
-            "npm ci --omit=dev 2>/dev/null || npm install --omit=dev".to_string()
+        // The lockfile is looked up from the app dir, but the install runs in
+        // the install dir; `npm ci` fails there without one.
+        PackageManager::Npm if frozen => {
+            "if [ -f package-lock.json ]; then npm ci --omit=dev; else npm install --omit=dev; fi"
+                .to_string()
         }
+        PackageManager::Npm => "npm install --omit=dev".to_string(),
         PackageManager::Pnpm => {
             // npm ships with node (full distribution extracted). Install pnpm if needed.
             // Falls back gracefully if workspace:* deps can't resolve on server
             // (the build artifact includes node_modules from the local build).
-            "command -v pnpm >/dev/null 2>&1 || npm install -g pnpm 2>/dev/null; pnpm install --prod 2>/dev/null || true"
-                .to_string()
+            let flags = if frozen {
+                "--prod --frozen-lockfile"
+            } else {
+                "--prod"
+            };
+            format!(
+                "command -v pnpm >/dev/null 2>&1 || npm install -g pnpm 2>/dev/null; pnpm install {flags} 2>/dev/null || true"
+            )
         }
         PackageManager::Yarn => {
-            "command -v yarn >/dev/null 2>&1 || npm install -g yarn 2>/dev/null; yarn install --production 2>/dev/null || true"
-                .to_string()
+            let flags = if frozen {
+                "--production --frozen-lockfile"
+            } else {
+                "--production"
+            };
+            format!(
+                "command -v yarn >/dev/null 2>&1 || npm install -g yarn 2>/dev/null; yarn install {flags} 2>/dev/null || true"
+            )
         }
         PackageManager::Deno => "deno install --frozen 2>/dev/null || true".to_string(),
     }
 }
 
+/// Whether `pm`'s lockfile exists at the JS project root for `project_dir`.
+fn has_pm_lockfile(pm: &PackageManager, project_dir: &Path) -> bool {
+    let root = find_js_project_root(project_dir);
+    pm_lockfiles(pm)
+        .iter()
+        .any(|name| root.join(name).is_file())
+}
+
 fn pm_install_dev(pm: &PackageManager) -> String {
     match pm {
         PackageManager::Bun => "bun install".to_string(),
@@ -300,13 +327,13 @@ fn js_dev_command_deno() -> Vec<String> {
     ]
 }
 
-fn build_package_manager_def(pm: &PackageManager) -> PackageManagerDef {
+fn build_package_manager_def(pm: &PackageManager, frozen: bool) -> PackageManagerDef {
     PackageManagerDef {
         id: pm.id().to_string(),
         name: None,
         lockfiles: pm_lockfiles(pm),
         add: Some(pm_add_command(pm)),
-        install: Some(pm_install_production(pm)),
+        install: Some(pm_install_production(pm, frozen)),
         development: Some(PackageManagerDevDef {
             install: Some(pm_install_dev(pm)),
         }),
@@ -390,7 +417,7 @@ fn download_def_for(id: &str) -> Option<DownloadDef> {
 pub struct BunPlugin;
 
 impl BunPlugin {
-    fn build_def(&self, pm: &PackageManager) -> RuntimeDef {
+    fn build_def(&self, pm: &PackageManager, frozen: bool) -> RuntimeDef {
         let mut envs = js_production_envs();
         envs.environments
             .entry("production".to_string())
@@ -430,7 +457,7 @@ impl BunPlugin {
                 ],
             },
             envs,
-            package_manager: build_package_manager_def(pm),
+            package_manager: build_package_manager_def(pm, frozen),
             download: download_def_for("bun"),
         }
     }
@@ -447,11 +474,12 @@ impl RuntimePlugin for BunPlugin {
 
     fn runtime_def(&self, ctx: &PluginContext) -> RuntimeDef {
         let pm = resolve_pm(ctx, PackageManager::Bun);
-        self.build_def(&pm)
+        let frozen = has_pm_lockfile(&pm, ctx.project_dir);
+        self.build_def(&pm, frozen)
     }
 
     fn default_runtime_def(&self) -> RuntimeDef {
-        self.build_def(&PackageManager::Bun)
+        self.build_def(&PackageManager::Bun, false)
     }
 }
 
@@ -460,7 +488,7 @@ impl RuntimePlugin for BunPlugin {
 pub struct NodePlugin;
 
 impl NodePlugin {
-    fn build_def(&self, pm: &PackageManager) -> RuntimeDef {
+    fn build_def(&self, pm: &PackageManager, frozen: bool) -> RuntimeDef {
         RuntimeDef {
             id: "node".to_string(),
             language: "javascript".to_string(),
@@ -495,7 +523,7 @@ impl NodePlugin {
                 ],
             },
             envs: js_production_envs(),
-            package_manager: build_package_manager_def(pm),
+            package_manager: build_package_manager_def(pm, frozen),
             download: download_def_for("node"),
         }
     }
@@ -512,11 +540,12 @@ impl RuntimePlugin for NodePlugin {
 
     fn runtime_def(&self, ctx: &PluginContext) -> RuntimeDef {
         let pm = resolve_pm(ctx, PackageManager::Pnpm);
-        self.build_def(&pm)
+        let frozen = has_pm_lockfile(&pm, ctx.project_dir);
+        self.build_def(&pm, frozen)
     }
 
     fn default_runtime_def(&self) -> RuntimeDef {
-        self.build_def(&PackageManager::Pnpm)
+        self.build_def(&PackageManager::Pnpm, false)
     }
 }
 
@@ -525,7 +554,7 @@ impl RuntimePlugin for NodePlugin {
 pub struct DenoPlugin;
 
 impl DenoPlugin {
-    fn build_def(&self, pm: &PackageManager) -> RuntimeDef {
+    fn build_def(&self, pm: &PackageManager, frozen: bool) -> RuntimeDef {
         let mut envs = js_production_envs();
         envs.environments
             .entry("production".to_string())
@@ -575,7 +604,7 @@ impl DenoPlugin {
                 ],
             },
             envs,
-            package_manager: build_package_manager_def(pm),
+            package_manager: build_package_manager_def(pm, frozen),
             download: download_def_for("deno"),
         }
     }
@@ -592,199 +621,16 @@ impl RuntimePlugin for DenoPlugin {
 
     fn runtime_def(&self, ctx: &PluginContext) -> RuntimeDef {
         let pm = resolve_pm(ctx, PackageManager::Deno);
-        self.build_def(&pm)
+        let frozen = has_pm_lockfile(&pm, ctx.project_dir);
+        self.build_def(&pm, frozen)
     }
 
     fn default_runtime_def(&self) -> RuntimeDef {
-        self.build_def(&PackageManager::Deno)
+        self.build_def(&PackageManager::Deno, false)
     }
 }
 
 // ── Tests ──────────────────────────────────────────────────────────
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn default_ctx(dir: &Path) -> PluginContext<'_> {
-        PluginContext {
-            project_dir: dir,
-            package_manager: None,
-        }
-    }
-
-    #[test]
-    fn bun_plugin_default_uses_bun_pm() {
-        let def = BunPlugin.default_runtime_def();
-        assert_eq!(def.package_manager.id, "bun");
-        assert_eq!(
-            def.package_manager.install.as_deref(),
-            Some("bun install --production")
-        );
-    }
-
-    #[test]
-    fn node_plugin_default_uses_pnpm() {
-        let def = NodePlugin.default_runtime_def();
-        assert_eq!(def.package_manager.id, "pnpm");
-        let install = def.package_manager.install.as_deref().unwrap();
-        assert!(install.contains("pnpm"), "install should mention pnpm");
-    }
-
-    #[test]
-    fn node_plugin_detects_npm_from_lockfile() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        std::fs::write(tmp.path().join("package-lock.json"), "{}").unwrap();
-        let ctx = default_ctx(tmp.path());
-        let def = NodePlugin.runtime_def(&ctx);
-        assert_eq!(def.package_manager.id, "npm");
-        assert!(
-            def.package_manager
-                .install
-                .as_deref()
-                .unwrap()
-                .contains("npm")
-        );
-    }
-
-    #[test]
-    fn node_plugin_detects_yarn_from_lockfile() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        std::fs::write(tmp.path().join("yarn.lock"), "").unwrap();
-        let ctx = default_ctx(tmp.path());
-        let def = NodePlugin.runtime_def(&ctx);
-        assert_eq!(def.package_manager.id, "yarn");
-    }
-
-    #[test]
-    fn node_plugin_respects_package_manager_override() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        // Lockfile says npm, but override says yarn
-        std::fs::write(tmp.path().join("package-lock.json"), "{}").unwrap();
-        let ctx = PluginContext {
-            project_dir: tmp.path(),
-            package_manager: Some("yarn"),
-        };
-        let def = NodePlugin.runtime_def(&ctx);
-        assert_eq!(def.package_manager.id, "yarn");
-    }
-
-    #[test]
-    fn detect_pm_reads_package_manager_field() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        std::fs::write(
-            tmp.path().join("package.json"),
-            r#"{"packageManager": "pnpm@9.1.0"}"#,
-        )
-        .unwrap();
-        assert_eq!(
-            detect_package_manager(tmp.path()),
-            Some(PackageManager::Pnpm)
-        );
-    }
-
-    #[test]
-    fn detect_pm_falls_back_to_lockfile() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        std::fs::write(tmp.path().join("bun.lock"), "").unwrap();
-        assert_eq!(
-            detect_package_manager(tmp.path()),
-            Some(PackageManager::Bun)
-        );
-    }
-
-    #[test]
-    fn detect_pm_returns_none_when_no_signals() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        assert_eq!(detect_package_manager(tmp.path()), None);
-    }
-
-    #[test]
-    fn find_js_project_root_returns_project_dir_when_lockfile_present() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        std::fs::write(tmp.path().join("bun.lock"), "").unwrap();
-        assert_eq!(find_js_project_root(tmp.path()), tmp.path());
-    }
-
-    #[test]
-    fn find_js_project_root_walks_up_to_lockfile() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        let root = tmp.path().join("monorepo");
-        let app_dir = root.join("apps/web");
-        std::fs::create_dir_all(&app_dir).unwrap();
-        std::fs::write(root.join("bun.lock"), "").unwrap();
-        assert_eq!(find_js_project_root(&app_dir), root);
-    }
-
-    #[test]
-    fn find_js_project_root_falls_back_to_project_dir_when_no_lockfile() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        let app_dir = tmp.path().join("app");
-        std::fs::create_dir_all(&app_dir).unwrap();
-        assert_eq!(find_js_project_root(&app_dir), app_dir);
-    }
-
-    #[test]
-    fn find_js_project_root_finds_nearest_not_highest_lockfile() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        let root = tmp.path().join("root");
-        let sub = root.join("sub");
-        let app_dir = sub.join("app");
-        std::fs::create_dir_all(&app_dir).unwrap();
-        std::fs::write(root.join("bun.lock"), "").unwrap();
-        std::fs::write(sub.join("bun.lock"), "").unwrap();
-        // Finds the nearest (innermost) lockfile ancestor
-        assert_eq!(find_js_project_root(&app_dir), sub);
-    }
-
-    #[test]
-    fn deno_plugin_has_allow_write() {
-        let def = DenoPlugin.default_runtime_def();
-        assert!(
-            def.server
-                .launch_args
-                .contains(&"--allow-write".to_string())
-        );
-        assert!(def.preset.start.contains(&"--allow-write".to_string()));
-    }
-
-    #[test]
-    fn all_plugins_have_download_def() {
-        assert!(BunPlugin.default_runtime_def().download.is_some());
-        assert!(NodePlugin.default_runtime_def().download.is_some());
-        assert!(DenoPlugin.default_runtime_def().download.is_some());
-    }
-
-    #[test]
-    fn all_plugins_have_main_placeholder_in_launch_args() {
-        for def in [
-            BunPlugin.default_runtime_def(),
-            NodePlugin.default_runtime_def(),
-            DenoPlugin.default_runtime_def(),
-        ] {
-            assert!(
-                def.server.launch_args.contains(&"{main}".to_string()),
-                "plugin {} launch_args should contain {{main}}",
-                def.id
-            );
-        }
-    }
-
-    #[test]
-    fn runtime_def_for_uses_plugin() {
-        // runtime_def_for(id, None) should return the plugin's default_runtime_def
-        for id in ["bun", "node", "deno"] {
-            let def = crate::runtime_def_for(id, None).unwrap();
-            let plugin = crate::plugin::plugin_for_id(id).unwrap();
-            let plugin_def = plugin.default_runtime_def();
-
-            assert_eq!(def.id, plugin_def.id, "{id}: id");
-            assert_eq!(def.preset.start, plugin_def.preset.start, "{id}: start");
-            assert_eq!(
-                def.package_manager.id, plugin_def.package_manager.id,
-                "{id}: pm"
-            );
-            assert!(def.download.is_some(), "{id}: download from TOML");
-        }
-    }
-}
+mod tests;
@@ -0,0 +1,256 @@
+use super::*;
+
+fn default_ctx(dir: &Path) -> PluginContext<'_> {
+    PluginContext {
+        project_dir: dir,
+        package_manager: None,
+    }
+}
+
+#[test]
+fn bun_plugin_default_uses_bun_pm() {
+    let def = BunPlugin.default_runtime_def();
+    assert_eq!(def.package_manager.id, "bun");
+    assert_eq!(
+        def.package_manager.install.as_deref(),
+        Some("bun install --production")
+    );
+}
+
+#[test]
+fn node_plugin_default_uses_pnpm() {
+    let def = NodePlugin.default_runtime_def();
+    assert_eq!(def.package_manager.id, "pnpm");
+    let install = def.package_manager.install.as_deref().unwrap();
+    assert!(install.contains("pnpm"), "install should mention pnpm");
+}
+
+#[test]
+fn node_plugin_detects_npm_from_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("package-lock.json"), "{}").unwrap();
+    let ctx = default_ctx(tmp.path());
+    let def = NodePlugin.runtime_def(&ctx);
+    assert_eq!(def.package_manager.id, "npm");
+    assert!(
+        def.package_manager
+            .install
+            .as_deref()
+            .unwrap()
+            .contains("npm")
+    );
+}
+
+#[test]
+fn node_plugin_detects_yarn_from_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("yarn.lock"), "").unwrap();
+    let ctx = default_ctx(tmp.path());
+    let def = NodePlugin.runtime_def(&ctx);
+    assert_eq!(def.package_manager.id, "yarn");
+}
+
+#[test]
+fn node_plugin_respects_package_manager_override() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    // Lockfile says npm, but override says yarn
+    std::fs::write(tmp.path().join("package-lock.json"), "{}").unwrap();
+    let ctx = PluginContext {
+        project_dir: tmp.path(),
+        package_manager: Some("yarn"),
+    };
+    let def = NodePlugin.runtime_def(&ctx);
+    assert_eq!(def.package_manager.id, "yarn");
+}
+
+#[test]
+fn detect_pm_reads_package_manager_field() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        tmp.path().join("package.json"),
+        r#"{"packageManager": "pnpm@9.1.0"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        detect_package_manager(tmp.path()),
+        Some(PackageManager::Pnpm)
+    );
+}
+
+#[test]
+fn detect_pm_falls_back_to_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("bun.lock"), "").unwrap();
+    assert_eq!(
+        detect_package_manager(tmp.path()),
+        Some(PackageManager::Bun)
+    );
+}
+
+#[test]
+fn detect_pm_returns_none_when_no_signals() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    assert_eq!(detect_package_manager(tmp.path()), None);
+}
+
+#[test]
+fn find_js_project_root_returns_project_dir_when_lockfile_present() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("bun.lock"), "").unwrap();
+    assert_eq!(find_js_project_root(tmp.path()), tmp.path());
+}
+
+#[test]
+fn find_js_project_root_walks_up_to_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let root = tmp.path().join("monorepo");
+    let app_dir = root.join("apps/web");
+    std::fs::create_dir_all(&app_dir).unwrap();
+    std::fs::write(root.join("bun.lock"), "").unwrap();
+    assert_eq!(find_js_project_root(&app_dir), root);
+}
+
+#[test]
+fn find_js_project_root_falls_back_to_project_dir_when_no_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let app_dir = tmp.path().join("app");
+    std::fs::create_dir_all(&app_dir).unwrap();
+    assert_eq!(find_js_project_root(&app_dir), app_dir);
+}
+
+#[test]
+fn find_js_project_root_finds_nearest_not_highest_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    let sub = root.join("sub");
+    let app_dir = sub.join("app");
+    std::fs::create_dir_all(&app_dir).unwrap();
+    std::fs::write(root.join("bun.lock"), "").unwrap();
+    std::fs::write(sub.join("bun.lock"), "").unwrap();
+    // Finds the nearest (innermost) lockfile ancestor
+    assert_eq!(find_js_project_root(&app_dir), sub);
+}
+
+#[test]
+fn deno_plugin_has_allow_write() {
+    let def = DenoPlugin.default_runtime_def();
+    assert!(
+        def.server
+            .launch_args
+            .contains(&"--allow-write".to_string())
+    );
+    assert!(def.preset.start.contains(&"--allow-write".to_string()));
+}
+
+#[test]
+fn all_plugins_have_download_def() {
+    assert!(BunPlugin.default_runtime_def().download.is_some());
+    assert!(NodePlugin.default_runtime_def().download.is_some());
+    assert!(DenoPlugin.default_runtime_def().download.is_some());
+}
+
+#[test]
+fn all_plugins_have_main_placeholder_in_launch_args() {
+    for def in [
+        BunPlugin.default_runtime_def(),
+        NodePlugin.default_runtime_def(),
+        DenoPlugin.default_runtime_def(),
+    ] {
+        assert!(
+            def.server.launch_args.contains(&"{main}".to_string()),
+            "plugin {} launch_args should contain {{main}}",
+            def.id
+        );
+    }
+}
+
+#[test]
+fn runtime_def_for_uses_plugin() {
+    // runtime_def_for(id, None) should return the plugin's default_runtime_def
+    for id in ["bun", "node", "deno"] {
+        let def = crate::runtime_def_for(id, None).unwrap();
+        let plugin = crate::plugin::plugin_for_id(id).unwrap();
+        let plugin_def = plugin.default_runtime_def();
+
+        assert_eq!(def.id, plugin_def.id, "{id}: id");
+        assert_eq!(def.preset.start, plugin_def.preset.start, "{id}: start");
+        assert_eq!(
+            def.package_manager.id, plugin_def.package_manager.id,
+            "{id}: pm"
+        );
+        assert!(def.download.is_some(), "{id}: download from TOML");
+    }
+}
+
+#[test]
+fn bun_install_args_use_frozen_lockfile_when_lock_exists() {
+    assert_eq!(
+        pm_install_production(&PackageManager::Bun, true),
+        "bun install --production --frozen-lockfile"
+    );
+    assert_eq!(
+        pm_install_production(&PackageManager::Bun, false),
+        "bun install --production"
+    );
+}
+
+#[test]
+fn npm_install_args_use_ci_when_lock_exists() {
+    let locked = pm_install_production(&PackageManager::Npm, true);
+    assert!(locked.contains("then npm ci --omit=dev;"));
+    assert!(locked.contains("else npm install --omit=dev;"));
+    assert_eq!(
+        pm_install_production(&PackageManager::Npm, false),
+        "npm install --omit=dev"
+    );
+}
+
+#[test]
+fn npm_install_falls_back_to_plain_install_without_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("package.json"), r#"{"name":"web"}"#).unwrap();
+    let ctx = PluginContext {
+        project_dir: tmp.path(),
+        package_manager: Some("npm"),
+    };
+    let def = NodePlugin.runtime_def(&ctx);
+    assert_eq!(
+        def.package_manager.install.as_deref(),
+        Some("npm install --omit=dev")
+    );
+}
+
+#[test]
+fn pnpm_install_args_use_frozen_lockfile_when_lock_exists() {
+    assert!(
+        pm_install_production(&PackageManager::Pnpm, true)
+            .contains("pnpm install --prod --frozen-lockfile")
+    );
+    let unlocked = pm_install_production(&PackageManager::Pnpm, false);
+    assert!(unlocked.contains("pnpm install --prod 2>/dev/null"));
+}
+
+#[test]
+fn yarn_install_args_use_frozen_lockfile_when_lock_exists() {
+    assert!(
+        pm_install_production(&PackageManager::Yarn, true)
+            .contains("yarn install --production --frozen-lockfile")
+    );
+    let unlocked = pm_install_production(&PackageManager::Yarn, false);
+    assert!(unlocked.contains("yarn install --production 2>/dev/null"));
+}
+
+#[test]
+fn runtime_def_install_is_frozen_when_workspace_root_has_lockfile() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("package-lock.json"), "{}").unwrap();
+    let app_dir = tmp.path().join("apps/web");
+    std::fs::create_dir_all(&app_dir).unwrap();
+    let ctx = PluginContext {
+        project_dir: &app_dir,
+        package_manager: Some("npm"),
+    };
+    let def = NodePlugin.runtime_def(&ctx);
+    let install = def.package_manager.install.unwrap();
+    assert!(install.contains("then npm ci --omit=dev;"), "{install}");
+}
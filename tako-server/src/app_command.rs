@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 /// Join `base/subpath` and verify the result stays within `base`.
@@ -51,6 +51,42 @@ pub(crate) struct ReleaseManifest {
     /// Path from the archive root to where deps should be installed (lockfile dir). Empty = archive root.
     #[serde(default)]
     pub install_dir: String,
+    /// Log 1 in N successful requests. Absent = log every request.
+    #[serde(default)]
+    pub access_log_sample_rate: Option<u32>,
+    /// Client request headers stripped at the edge. Absent = strip nothing.
+    #[serde(default)]
+    pub strip_request_headers: Vec<String>,
+    /// Replacement spawns allowed per restart window. Absent = server default.
+    #[serde(default)]
+    pub max_restart_attempts: Option<u32>,
+    /// Cap on the delay before respawning a failed instance, in seconds.
+    /// Absent = server default.
+    #[serde(default)]
+    pub max_restart_backoff: Option<u32>,
+    /// Response buffering limit in bytes. Absent = stream responses.
+    #[serde(default)]
+    pub response_buffer_bytes: Option<u32>,
+    /// Request body limit in bytes. Absent = server-wide limit.
+    #[serde(default)]
+    pub max_body_bytes: Option<u64>,
+    /// Request header limit in bytes. Absent = no per-app limit.
+    #[serde(default)]
+    pub max_header_bytes: Option<u32>,
+    /// Proxy token-bucket limit. Absent = unlimited.
+    #[serde(default)]
+    pub rate_limit: Option<tako_core::RateLimit>,
+    /// Retry POST/PATCH on another instance after a failed attempt.
+    #[serde(default)]
+    pub retry_non_idempotent: bool,
+    /// One-shot command (e.g. migrations) run in the app dir after the
+    /// production install and before any instance starts.
+    #[serde(default)]
+    pub predeploy: Vec<String>,
+    /// Expected SHA-256 (hex) of release files, keyed by path from the
+    /// archive root. Absent = no integrity check.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
 }
 
 pub(crate) fn load_release_manifest(release_dir: &Path) -> Result<ReleaseManifest, String> {
@@ -1,33 +1,95 @@
 use crate::SIGNAL_PARENT_ON_READY_ENV;
-use crate::tls::AcmeClient;
+use crate::defaults::CERT_EXPIRY_WARNING_WINDOW;
+use crate::tls::{
+    AcmeClient, CertEvent, CertManager, OcspError, expiring_soon_events, fetch_staple,
+};
 use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
-
-pub(crate) async fn certificate_renewal_task(acme_client: Arc<AcmeClient>, interval: Duration) {
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+
+/// How often to look for OCSP staples that are missing or due for refresh.
+/// Each staple's own refresh time comes from its `nextUpdate`.
+const OCSP_REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub(crate) async fn certificate_renewal_task(
+    acme_client: Arc<AcmeClient>,
+    interval: Duration,
+    event_tx: mpsc::Sender<CertEvent>,
+) {
     tracing::info!(
         interval_hours = interval.as_secs() / 3600,
         "Starting certificate renewal task"
     );
 
+    let mut renewal_tick =
+        tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    let mut ocsp_tick = tokio::time::interval(OCSP_REFRESH_CHECK_INTERVAL);
+
     loop {
-        tokio::time::sleep(interval).await;
-        tracing::info!("Checking for certificates needing renewal…");
-
-        let results = acme_client.check_renewals().await;
-        for result in results {
-            match result {
-                Ok(cert) => {
-                    tracing::info!(
-                        domain = %cert.domain,
-                        expires_in_days = cert.days_until_expiry(),
-                        "Certificate renewed successfully"
-                    );
-                }
-                Err(e) => {
-                    tracing::error!("Certificate renewal failed: {}", e);
+        tokio::select! {
+            _ = renewal_tick.tick() => {
+                run_certificate_renewals(&acme_client, &event_tx).await;
+                refresh_ocsp_staples(acme_client.cert_manager()).await;
+            }
+            _ = ocsp_tick.tick() => {
+                refresh_ocsp_staples(acme_client.cert_manager()).await;
+            }
+        }
+    }
+}
+
+async fn run_certificate_renewals(acme_client: &AcmeClient, event_tx: &mpsc::Sender<CertEvent>) {
+    tracing::info!("Checking for certificates needing renewal…");
+
+    let results = acme_client.check_renewals().await;
+    for (domain, result) in results {
+        let event = match result {
+            Ok(cert) => {
+                tracing::info!(
+                    domain = %cert.domain,
+                    expires_in_days = cert.days_until_expiry(),
+                    "Certificate renewed successfully"
+                );
+                CertEvent::Renewed { domain }
+            }
+            Err(e) => {
+                tracing::error!(domain = %domain, "Certificate renewal failed: {}", e);
+                CertEvent::RenewalFailed {
+                    domain,
+                    error: e.to_string(),
                 }
             }
+        };
+        let _ = event_tx.send(event).await;
+    }
+
+    let certs = acme_client.cert_manager().list_certs();
+    let now = SystemTime::now();
+    for cert in &certs {
+        if let Some(expires_at) = cert.expires_at {
+            crate::metrics::set_cert_expiry(&cert.domain, expires_at, now);
+        }
+    }
+    for event in expiring_soon_events(&certs, now, CERT_EXPIRY_WARNING_WINDOW) {
+        let _ = event_tx.send(event).await;
+    }
+}
+
+async fn refresh_ocsp_staples(cert_manager: &CertManager) {
+    for cert in cert_manager.certs_needing_ocsp_refresh(SystemTime::now()) {
+        match fetch_staple(&cert.cert_path).await {
+            Ok(staple) => {
+                tracing::debug!(domain = %cert.domain, "OCSP response refreshed");
+                cert_manager.set_ocsp_staple(&cert, staple);
+            }
+            // Certs without an OCSP responder (or issuer in the chain) can't be stapled.
+            Err(e @ (OcspError::NoResponder | OcspError::MissingIssuer)) => {
+                tracing::debug!(domain = %cert.domain, "Skipping OCSP stapling: {}", e);
+            }
+            Err(e) => {
+                tracing::warn!(domain = %cert.domain, "OCSP refresh failed: {}", e);
+            }
         }
     }
 }
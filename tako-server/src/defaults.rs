@@ -8,5 +8,42 @@ pub const HEALTH_STARTUP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
 pub const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+/// Upper bound accepted by `set_drain_timeout`.
+pub const MAX_DRAIN_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+/// Maintenance page defaults and the largest body `set_maintenance` accepts.
+pub const DEFAULT_MAINTENANCE_STATUS: u16 = 503;
+pub const DEFAULT_MAINTENANCE_BODY: &str = "<!doctype html><html><head><title>Down for maintenance</title></head><body><h1>Down for maintenance</h1><p>Please try again shortly.</p></body></html>";
+pub const MAX_MAINTENANCE_BODY_BYTES: usize = 64 * 1024;
+/// Replacement spawns allowed within `RESTART_ATTEMPT_WINDOW` before an app
+/// is marked errored and left stopped until the next deploy or scale.
+pub const DEFAULT_MAX_RESTART_ATTEMPTS: u32 = 5;
+pub const RESTART_ATTEMPT_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// Spawns that fail in a row (before any instance reports ready) before an
+/// app is marked errored and no longer respawned automatically.
+pub const MAX_CONSECUTIVE_SPAWN_FAILURES: u32 = 3;
+/// First delay before respawning a failed instance; doubles with each
+/// consecutive failure up to the app's `max_restart_backoff`.
+pub const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(1);
+pub const DEFAULT_MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+/// An app that goes this long without a failure starts over at the base delay.
+pub const RESTART_BACKOFF_RESET_AFTER: Duration = Duration::from_secs(60);
+/// How often a ready instance's process is checked for exiting on its own.
+pub const INSTANCE_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long a canary deploy sends part of the traffic to the new build
+/// before switching all of it over.
+pub const CANARY_HOLD_PERIOD: Duration = Duration::from_secs(60);
+/// `status_stream` re-sends the current status this often without changes,
+/// so a client that went away is noticed on the failed write.
+pub const STATUS_STREAM_KEEPALIVE: Duration = Duration::from_secs(30);
+/// Boot retries `tako.db` initialization this many times while another
+/// process (e.g. the outgoing server during an upgrade) holds the lock.
+pub const STATE_STORE_INIT_ATTEMPTS: u32 = 3;
+pub const STATE_STORE_INIT_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// How long SIGTERM waits for in-flight requests before killing instances.
+pub const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+pub const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Certs this close to expiry after a renewal pass raise an operator warning.
+pub const CERT_EXPIRY_WARNING_WINDOW: Duration = Duration::from_secs(14 * 24 * 60 * 60);
 pub const IDLE_CHECK_INTERVAL_DEBUG: Duration = Duration::from_secs(1);
 pub const IDLE_CHECK_INTERVAL_RELEASE: Duration = Duration::from_secs(30);
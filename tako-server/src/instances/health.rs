@@ -278,6 +278,48 @@ fn app_has_starting_instance(app: &App) -> bool {
         .any(|i| matches!(i.state(), InstanceState::Starting | InstanceState::Ready))
 }
 
+/// Wait until `required` of the app's instances are Healthy and have passed
+/// a health-checker probe. Fails early once too few instances are left
+/// running to get there.
+pub async fn wait_for_probed_instances(
+    app: &App,
+    required: usize,
+    wait: Duration,
+) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        let instances = app.get_instances();
+        let probed = instances
+            .iter()
+            .filter(|i| i.state() == InstanceState::Healthy && i.probe_passed())
+            .count();
+        if probed >= required {
+            return Ok(());
+        }
+        let running = instances
+            .iter()
+            .filter(|i| {
+                matches!(
+                    i.state(),
+                    InstanceState::Starting | InstanceState::Ready | InstanceState::Healthy
+                )
+            })
+            .count();
+        if running < required {
+            return Err(format!(
+                "only {running} of {required} instances are still running"
+            ));
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!(
+                "{probed} of {required} instances passed a health check within {}s",
+                wait.as_secs()
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
 async fn probe_instance_health(
     instance: &Instance,
     health_host: &str,
@@ -444,6 +486,30 @@ mod tests {
         assert!(!app_has_starting_instance(&app));
     }
 
+    #[tokio::test]
+    async fn test_wait_for_probed_instances_requires_a_passed_probe() {
+        let app = create_test_app();
+        let instance = app.allocate_instance();
+        instance.set_state(InstanceState::Healthy);
+
+        let err = wait_for_probed_instances(&app, 1, Duration::from_millis(150))
+            .await
+            .unwrap_err();
+        assert!(err.contains("0 of 1"), "{err}");
+
+        instance.record_heartbeat();
+        wait_for_probed_instances(&app, 1, Duration::from_millis(150))
+            .await
+            .unwrap();
+
+        // A second required instance that already died fails without waiting.
+        app.allocate_instance().set_state(InstanceState::Stopped);
+        let err = wait_for_probed_instances(&app, 2, Duration::from_secs(30))
+            .await
+            .unwrap_err();
+        assert!(err.contains("only 1 of 2"), "{err}");
+    }
+
     #[test]
     fn test_effective_probe_concurrency_never_zero() {
         assert_eq!(HealthChecker::effective_probe_concurrency(0), 1);
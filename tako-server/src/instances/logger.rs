@@ -6,10 +6,15 @@
 //!
 //! A bounded mpsc channel provides backpressure: if the app logs faster than
 //! disk can absorb, lines are dropped rather than blocking the app process.
+//!
+//! Tako's own lifecycle events for the app (instance started/ready/unhealthy/
+//! replaced) flow through the same writer tagged `[tako]`, so `read_recent_logs`
+//! can return both scopes as one timestamp-ordered stream.
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
+use tako_core::TailLogEntry;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc;
 
@@ -29,11 +34,12 @@ pub struct LogEntry {
     pub line: String,
 }
 
-/// Which pipe produced the line.
+/// Which pipe produced the line, or `Tako` for server lifecycle events.
 #[derive(Clone, Copy)]
 pub enum LogStream {
     Stdout,
     Stderr,
+    Tako,
 }
 
 impl LogStream {
@@ -41,6 +47,7 @@ impl LogStream {
         match self {
             Self::Stdout => "out",
             Self::Stderr => "err",
+            Self::Tako => "tako",
         }
     }
 }
@@ -60,6 +67,15 @@ impl AppLogHandle {
         }
     }
 
+    /// Record a Tako lifecycle event for an instance of this app.
+    pub fn lifecycle(&self, instance_id: &str, message: impl Into<String>) {
+        self.try_send(LogEntry {
+            instance_id: instance_id.to_string(),
+            stream: LogStream::Tako,
+            line: message.into(),
+        });
+    }
+
     /// Number of lines dropped due to backpressure since the last reset.
     pub fn dropped_count(&self) -> u64 {
         self.dropped.load(Ordering::Relaxed)
@@ -278,6 +294,46 @@ async fn open_append(path: &Path) -> std::io::Result<tokio::fs::File> {
         .await
 }
 
+/// Read up to `limit` of the most recent log lines from `log_dir` (rotated
+/// file first), merged across scopes in timestamp order.
+pub fn read_recent_logs(log_dir: &Path, limit: usize) -> Vec<TailLogEntry> {
+    let mut entries: Vec<TailLogEntry> = ["previous.log", "current.log"]
+        .iter()
+        .filter_map(|name| std::fs::read_to_string(log_dir.join(name)).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(parse_log_line)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // Timestamps are fixed-width RFC 3339, so lexical order is time order.
+    // The sort is stable: same-millisecond lines keep their write order.
+    entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    let skip = entries.len().saturating_sub(limit);
+    entries.split_off(skip)
+}
+
+/// Parse a `{ts} [{stream}] [{instance}] {line}` record written by `AppLogWriter`.
+fn parse_log_line(line: &str) -> Option<TailLogEntry> {
+    let (timestamp, rest) = line.split_once(' ')?;
+    let rest = rest.strip_prefix('[')?;
+    let (label, rest) = rest.split_once("] [")?;
+    let (instance_id, message) = rest.split_once("] ")?;
+    let scope = match label {
+        "tako" => "tako",
+        "out" | "err" => "app",
+        _ => return None,
+    };
+    Some(TailLogEntry {
+        timestamp: timestamp.to_string(),
+        scope: scope.to_string(),
+        instance_id: instance_id.to_string(),
+        message: message.to_string(),
+    })
+}
+
 /// Create a no-op log handle for tests. Entries are sent to a channel that is
 /// immediately dropped, so writes silently succeed without touching disk.
 pub fn noop_log_handle() -> AppLogHandle {
@@ -379,6 +435,76 @@ mod tests {
         drop(handle);
     }
 
+    #[tokio::test]
+    async fn lifecycle_events_interleave_with_app_lines_in_timestamp_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = spawn_app_logger("tail-app", dir.path().to_path_buf());
+
+        handle.try_send(LogEntry {
+            instance_id: "i1".into(),
+            stream: LogStream::Stdout,
+            line: "booting".into(),
+        });
+        handle.lifecycle("i1", "instance ready");
+        handle.try_send(LogEntry {
+            instance_id: "i1".into(),
+            stream: LogStream::Stderr,
+            line: "request failed".into(),
+        });
+        handle.lifecycle("i1", "instance unhealthy");
+
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        drop(handle);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let entries = read_recent_logs(dir.path(), 100);
+        let scoped: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|e| (e.scope.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            scoped,
+            vec![
+                ("app", "booting"),
+                ("tako", "instance ready"),
+                ("app", "request failed"),
+                ("tako", "instance unhealthy"),
+            ]
+        );
+        assert!(entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    }
+
+    #[test]
+    fn read_recent_logs_merges_rotated_files_by_timestamp_and_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("previous.log"),
+            "2026-01-01T00:00:01.000Z [out] [i1] one\n\
+             2026-01-01T00:00:03.000Z [tako] [i1] instance replaced by i2 (unhealthy)\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("current.log"),
+            "2026-01-01T00:00:02.000Z [err] [i1] two\n\
+             not a log record\n\
+             2026-01-01T00:00:04.000Z [out] [i2] four\n",
+        )
+        .unwrap();
+
+        let messages: Vec<String> = read_recent_logs(dir.path(), 3)
+            .into_iter()
+            .map(|e| format!("{}:{}:{}", e.scope, e.instance_id, e.message))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "app:i1:two",
+                "tako:i1:instance replaced by i2 (unhealthy)",
+                "app:i2:four",
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn log_pipe_forwards_lines() {
         let dir = tempfile::tempdir().unwrap();
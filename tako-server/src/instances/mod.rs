@@ -5,15 +5,20 @@
 mod health;
 pub mod logger;
 mod network;
+mod restarts;
 mod rolling;
 mod spawner;
+mod usage;
 
 pub use health::*;
-pub use logger::{AppLogHandle, LogStream, log_pipe, spawn_app_logger};
+pub use logger::{AppLogHandle, LogStream, log_pipe, read_recent_logs, spawn_app_logger};
 pub use network::*;
+pub use restarts::RestartBudget;
 pub use rolling::*;
 pub use spawner::*;
+pub use usage::sample_process_usage;
 
+use crate::secret_refs::SecretRefPolicy;
 use crate::socket::{AppState, InstanceState, InstanceStatus};
 use dashmap::DashMap;
 use parking_lot::RwLock;
@@ -21,7 +26,7 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::process::Child;
 use tokio::sync::mpsc;
@@ -76,6 +81,44 @@ pub struct AppConfig {
     pub startup_timeout: Duration,
     /// Idle timeout (for on-demand scaling)
     pub idle_timeout: Duration,
+    /// Access log sampling: log 1 in N successful requests (5xx always logged)
+    pub access_log_sample_rate: u32,
+    /// Client request headers removed at the edge before forwarding headers are added
+    pub strip_request_headers: Vec<String>,
+    /// How long a stopping instance may finish in-flight requests
+    pub drain_timeout: Duration,
+    /// Replacement spawns allowed within `RESTART_ATTEMPT_WINDOW` before
+    /// the app is marked errored and no longer respawned
+    pub max_restart_attempts: u32,
+    /// Upper bound for the exponential delay before respawning a failed instance
+    pub max_restart_backoff: Duration,
+    /// Upstream response bodies up to this size are buffered so a slow client
+    /// does not hold an instance; larger bodies stream. 0 = always stream.
+    pub response_buffer_bytes: u32,
+    /// Requests with larger bodies get 413 before reaching an instance.
+    /// 0 = server-wide limit only.
+    pub max_body_bytes: u64,
+    /// Requests whose header fields exceed this size get 431. 0 = no limit
+    /// beyond the HTTP parser's own.
+    pub max_header_bytes: u32,
+    /// Requests over this token-bucket limit get 429 at the proxy.
+    pub rate_limit: Option<tako_core::RateLimit>,
+    /// Let the proxy retry non-idempotent requests (POST, PATCH) on another
+    /// instance when the first one fails before responding.
+    pub retry_non_idempotent: bool,
+    /// Bumped whenever `secrets` change. Instances remember the generation
+    /// they started with, so status can flag ones running old secrets.
+    pub secrets_generation: u64,
+    /// Served by the proxy while the app has no healthy instances.
+    pub maintenance: Option<MaintenancePage>,
+}
+
+/// Fixed response served in place of an app that is down for maintenance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenancePage {
+    pub status: u16,
+    /// HTML body
+    pub body: String,
 }
 
 impl AppConfig {
@@ -104,6 +147,18 @@ impl Default for AppConfig {
             health_check_interval: crate::defaults::HEALTH_CHECK_INTERVAL,
             startup_timeout: Duration::from_secs(30),
             idle_timeout: crate::defaults::DEFAULT_IDLE_TIMEOUT,
+            access_log_sample_rate: 1,
+            strip_request_headers: Vec::new(),
+            drain_timeout: crate::defaults::DEFAULT_DRAIN_TIMEOUT,
+            max_restart_attempts: crate::defaults::DEFAULT_MAX_RESTART_ATTEMPTS,
+            max_restart_backoff: crate::defaults::DEFAULT_MAX_RESTART_BACKOFF,
+            response_buffer_bytes: 0,
+            max_body_bytes: 0,
+            max_header_bytes: 0,
+            rate_limit: None,
+            retry_non_idempotent: false,
+            secrets_generation: 0,
+            maintenance: None,
         }
     }
 }
@@ -114,6 +169,8 @@ pub struct Instance {
     pub id: String,
     /// Build version this instance was launched from
     build_version: String,
+    /// Secrets generation this instance was launched with
+    secrets_generation: u64,
     /// Shared secret for internal status and secret-delivery requests.
     internal_token: String,
     /// Upstream endpoint and runtime cleanup metadata.
@@ -135,16 +192,24 @@ pub struct Instance {
     last_request_ms: AtomicU64,
     /// Last health-check heartbeat time as millis since UNIX_EPOCH
     last_heartbeat_ms: AtomicU64,
+    /// Whether a health-check probe has passed at least once
+    probe_passed: AtomicBool,
 
     /// Log handle for forwarding stdout/stderr to the app log writer.
     log_handle: AppLogHandle,
 }
 
 impl Instance {
-    pub fn new(id: String, build_version: String, log_handle: AppLogHandle) -> Self {
+    pub fn new(
+        id: String,
+        build_version: String,
+        secrets_generation: u64,
+        log_handle: AppLogHandle,
+    ) -> Self {
         Self {
             id,
             build_version,
+            secrets_generation,
             internal_token: generate_internal_token(),
             upstream: RwLock::new(None),
             process: RwLock::new(None),
@@ -155,6 +220,7 @@ impl Instance {
             in_flight: AtomicU64::new(0),
             last_request_ms: AtomicU64::new(now_unix_millis()),
             last_heartbeat_ms: AtomicU64::new(now_unix_millis()),
+            probe_passed: AtomicBool::new(false),
             log_handle,
         }
     }
@@ -176,6 +242,10 @@ impl Instance {
         &self.build_version
     }
 
+    pub fn secrets_generation(&self) -> u64 {
+        self.secrets_generation
+    }
+
     pub fn port(&self) -> Option<u16> {
         self.endpoint().map(|endpoint| endpoint.port())
     }
@@ -258,6 +328,12 @@ impl Instance {
     pub fn record_heartbeat(&self) {
         self.last_heartbeat_ms
             .store(now_unix_millis(), Ordering::Relaxed);
+        self.probe_passed.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the health checker has seen this instance answer a probe.
+    pub fn probe_passed(&self) -> bool {
+        self.probe_passed.load(Ordering::Relaxed)
     }
 
     pub fn status(&self) -> InstanceStatus {
@@ -267,9 +343,19 @@ impl Instance {
             pid: self.pid(),
             uptime_secs: self.uptime().as_secs(),
             requests_total: self.requests_total(),
+            secrets_generation: self.secrets_generation,
+            usage: self.pid().and_then(sample_process_usage),
         }
     }
 
+    /// Exit status of the process if it has exited. `Err` once the handle
+    /// is gone, i.e. the instance was killed or never started.
+    pub(crate) fn try_exit_status(&self) -> Result<Option<std::process::ExitStatus>, ()> {
+        let mut process = self.process.write();
+        let child = process.as_mut().ok_or(())?;
+        child.try_wait().map_err(|_| ())
+    }
+
     /// Check if process is still running
     pub async fn is_alive(&self) -> bool {
         let mut process = self.process.write();
@@ -334,15 +420,80 @@ pub struct App {
     instance_tx: mpsc::Sender<InstanceEvent>,
     /// Shared log handle for all instances of this app
     log_handle: AppLogHandle,
+    /// Replacement spawns after crashes or failed health checks
+    restart_budget: parking_lot::Mutex<RestartBudget>,
+    /// Which instances receive traffic (narrowed during blue/green and canary deploys)
+    routing: RwLock<BuildRouting>,
+    /// Request counter used to split traffic during a canary
+    canary_samples: AtomicU64,
+    /// Out of rotation: instances keep running but none are routed to
+    drained: AtomicBool,
+    /// Set by a promote to end the running canary hold early
+    canary_promoted: AtomicBool,
+}
+
+/// Deploy-time traffic filter applied on top of instance health.
+///
+/// A new build is tracked by the ids of the instances staged for it rather
+/// than by version, so redeploying the live version stages fresh instances
+/// without touching the ones already serving.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BuildRouting {
+    /// Every healthy instance receives traffic
+    #[default]
+    All,
+    /// These staged instances are held back from traffic
+    Except(Vec<String>),
+    /// Only these staged instances receive traffic
+    Only(Vec<String>),
+    /// `percent` of requests go to these staged instances, the rest to the others
+    Canary { instances: Vec<String>, percent: u8 },
+}
+
+impl BuildRouting {
+    fn routes(&self, instance_id: &str) -> bool {
+        match self {
+            Self::All | Self::Canary { .. } => true,
+            Self::Except(held) => !held.iter().any(|id| id == instance_id),
+            Self::Only(live) => live.iter().any(|id| id == instance_id),
+        }
+    }
+}
+
+/// Whether the `sample`th request goes to the canary build. Each run of 100
+/// consecutive samples sends exactly `percent` to the canary, spread out
+/// rather than in one burst.
+pub(crate) fn canary_takes_request(percent: u8, sample: u64) -> bool {
+    // 37 is coprime with 100, so this permutes every block of 100 samples.
+    (sample % 100) * 37 % 100 < u64::from(percent)
 }
 
 /// Events for instance lifecycle
 #[derive(Debug)]
 pub enum InstanceEvent {
-    Started { app: String, instance_id: String },
-    Ready { app: String, instance_id: String },
-    Unhealthy { app: String, instance_id: String },
-    Stopped { app: String, instance_id: String },
+    Started {
+        app: String,
+        instance_id: String,
+    },
+    Ready {
+        app: String,
+        instance_id: String,
+    },
+    Unhealthy {
+        app: String,
+        instance_id: String,
+    },
+    Stopped {
+        app: String,
+        instance_id: String,
+    },
+    /// The process exited on its own (not killed by Tako)
+    Exited {
+        app: String,
+        instance_id: String,
+        code: Option<i32>,
+        signal: Option<i32>,
+    },
 }
 
 impl App {
@@ -358,6 +509,11 @@ impl App {
             last_error: RwLock::new(None),
             instance_tx,
             log_handle,
+            restart_budget: parking_lot::Mutex::new(RestartBudget::default()),
+            routing: RwLock::new(BuildRouting::All),
+            canary_samples: AtomicU64::new(0),
+            drained: AtomicBool::new(false),
+            canary_promoted: AtomicBool::new(false),
         }
     }
 
@@ -389,31 +545,160 @@ impl App {
         self.last_error.read().clone()
     }
 
+    /// Record a replacement spawn; `false` once the app's restart budget is spent.
+    pub fn try_restart(&self) -> bool {
+        let max_attempts = self.config.read().max_restart_attempts;
+        self.restart_budget.lock().try_restart(
+            Instant::now(),
+            max_attempts,
+            crate::defaults::RESTART_ATTEMPT_WINDOW,
+        )
+    }
+
+    /// Delay before the next replacement spawn; grows with consecutive failures.
+    pub fn next_restart_backoff(&self) -> Duration {
+        let max_backoff = self.config.read().max_restart_backoff;
+        self.restart_budget.lock().next_backoff(
+            Instant::now(),
+            crate::defaults::RESTART_BACKOFF_BASE,
+            max_backoff,
+            crate::defaults::RESTART_BACKOFF_RESET_AFTER,
+        )
+    }
+
+    /// Record a spawn that failed before becoming ready; `true` once the
+    /// app hit `MAX_CONSECUTIVE_SPAWN_FAILURES` and respawning stopped.
+    pub fn record_spawn_failure(&self) -> bool {
+        self.restart_budget
+            .lock()
+            .record_spawn_failure(crate::defaults::MAX_CONSECUTIVE_SPAWN_FAILURES)
+    }
+
+    pub fn record_spawn_success(&self) {
+        self.restart_budget.lock().record_spawn_success();
+    }
+
+    /// Whether respawning stopped until a deploy or explicit action
+    pub fn restarts_exhausted(&self) -> bool {
+        self.restart_budget.lock().is_exhausted()
+    }
+
+    pub fn reset_restart_budget(&self) {
+        self.restart_budget.lock().reset();
+    }
+
+    /// Append a `tako`-scoped lifecycle event to this app's log stream.
+    pub fn log_lifecycle(&self, instance_id: &str, message: impl Into<String>) {
+        self.log_handle.lifecycle(instance_id, message);
+    }
+
+    pub fn routing(&self) -> BuildRouting {
+        self.routing.read().clone()
+    }
+
+    /// Restrict which instances the load balancer may pick from.
+    pub fn set_routing(&self, routing: BuildRouting) {
+        *self.routing.write() = routing;
+    }
+
+    pub fn is_drained(&self) -> bool {
+        self.drained.load(Ordering::Relaxed)
+    }
+
+    /// Take the app out of (or back into) rotation. Draining leaves every
+    /// instance running; the load balancer just stops picking them.
+    pub fn set_drained(&self, drained: bool) {
+        self.drained.store(drained, Ordering::Relaxed);
+    }
+
+    /// Ask the canary deploy in progress to skip the rest of its hold and
+    /// switch all traffic to the canary build.
+    pub fn promote_canary(&self) {
+        self.canary_promoted.store(true, Ordering::Relaxed);
+    }
+
+    /// Consume a pending [`Self::promote_canary`] request.
+    pub fn take_canary_promotion(&self) -> bool {
+        self.canary_promoted.swap(false, Ordering::Relaxed)
+    }
+
+    /// The instance filter for one proxied request. A canary resolves to
+    /// either the canary instances or the rest, falling back to every
+    /// healthy instance when the chosen side has none.
+    ///
+    /// Each call counts as one request toward the canary split, so only the
+    /// load balancer calls this, once per request it routes.
+    pub fn request_routing(&self) -> BuildRouting {
+        let routing = self.routing.read().clone();
+        let BuildRouting::Canary { instances, percent } = routing else {
+            return routing;
+        };
+        let sample = self.canary_samples.fetch_add(1, Ordering::Relaxed);
+        let chosen = if canary_takes_request(percent, sample) {
+            BuildRouting::Only(instances)
+        } else {
+            BuildRouting::Except(instances)
+        };
+        let has_healthy = self.instances.iter().any(|entry| {
+            entry.value().state() == InstanceState::Healthy && chosen.routes(&entry.value().id)
+        });
+        if has_healthy {
+            chosen
+        } else {
+            BuildRouting::All
+        }
+    }
+
+    fn is_routable(routing: &BuildRouting, instance: &Instance) -> bool {
+        instance.state() == InstanceState::Healthy && routing.routes(&instance.id)
+    }
+
     /// Get a healthy instance for load balancing
     pub fn get_healthy_instance(&self) -> Option<Arc<Instance>> {
+        if self.is_drained() {
+            return None;
+        }
+        let routing = self.routing();
         self.instances
             .iter()
-            .find(|entry| entry.value().state() == InstanceState::Healthy)
+            .find(|entry| Self::is_routable(&routing, entry.value()))
             .map(|entry| entry.value().clone())
     }
 
-    /// Get all healthy instances
+    /// Get all healthy instances. During a canary both sides count.
     pub fn get_healthy_instances(&self) -> Vec<Arc<Instance>> {
+        self.get_routed_instances(&self.routing())
+    }
+
+    /// Get the healthy instances `routing` lets through, as returned by
+    /// [`Self::request_routing`] for one request.
+    pub fn get_routed_instances(&self, routing: &BuildRouting) -> Vec<Arc<Instance>> {
+        if self.is_drained() {
+            return Vec::new();
+        }
         self.instances
             .iter()
-            .filter(|entry| entry.value().state() == InstanceState::Healthy)
+            .filter(|entry| Self::is_routable(routing, entry.value()))
             .map(|entry| entry.value().clone())
             .collect()
     }
 
-    /// Pick the healthy instance with the lowest externally provided load value.
-    pub fn get_least_loaded_healthy_instance<F>(&self, mut load_for: F) -> Option<Arc<Instance>>
+    /// Pick the healthy instance `routing` lets through with the lowest
+    /// externally provided load value.
+    pub fn get_least_loaded_healthy_instance<F>(
+        &self,
+        routing: &BuildRouting,
+        mut load_for: F,
+    ) -> Option<Arc<Instance>>
     where
         F: FnMut(&str) -> u64,
     {
+        if self.is_drained() {
+            return None;
+        }
         self.instances
             .iter()
-            .filter(|entry| entry.value().state() == InstanceState::Healthy)
+            .filter(|entry| Self::is_routable(routing, entry.value()))
             .min_by_key(|entry| load_for(&entry.value().id))
             .map(|entry| entry.value().clone())
     }
@@ -446,6 +731,7 @@ impl App {
         let instance = Arc::new(Instance::new(
             id.clone(),
             config.version.clone(),
+            config.secrets_generation,
             self.log_handle.clone(),
         ));
         self.instances.insert(id, instance.clone());
@@ -490,6 +776,17 @@ impl AppManager {
         }
     }
 
+    /// Resolve secret references for spawned instances under `secret_refs`.
+    pub(crate) fn with_secret_refs(mut self, secret_refs: SecretRefPolicy) -> Self {
+        let internal_socket = tako_workflows::internal_socket_path(&self.data_dir);
+        self.spawner = Arc::new(
+            Spawner::new()
+                .with_internal_socket(internal_socket)
+                .with_secret_refs(secret_refs),
+        );
+        self
+    }
+
     /// Take the event receiver (can only be called once)
     pub fn take_event_receiver(&self) -> Option<mpsc::Receiver<InstanceEvent>> {
         self.event_rx.write().take()
@@ -498,8 +795,7 @@ impl AppManager {
     /// Register a new app
     pub fn register_app(&self, config: AppConfig) -> Arc<App> {
         let name = config.deployment_id();
-        let log_dir = self.data_dir.join("apps").join(&name).join("logs");
-        let log_handle = spawn_app_logger(&name, log_dir);
+        let log_handle = spawn_app_logger(&name, self.log_dir(&name));
         let app = Arc::new(App::new(config, self.event_tx.clone(), log_handle));
         self.apps.insert(name, app.clone());
         app
@@ -510,6 +806,11 @@ impl AppManager {
         self.apps.get(name).map(|entry| entry.value().clone())
     }
 
+    /// Directory holding an app's captured `current.log`/`previous.log`
+    pub fn log_dir(&self, app_name: &str) -> PathBuf {
+        self.data_dir.join("apps").join(app_name).join("logs")
+    }
+
     pub fn data_dir(&self) -> &PathBuf {
         &self.data_dir
     }
@@ -579,6 +880,9 @@ pub enum InstanceError {
 
     #[error("Health check failed: {0}")]
     HealthCheckFailed(String),
+
+    #[error("Failed to resolve {0}")]
+    SecretResolution(String),
 }
 
 #[cfg(test)]
@@ -588,7 +892,7 @@ mod tests {
 
     #[test]
     fn test_instance_state_transitions() {
-        let instance = Instance::new("test-1".to_string(), "v1".to_string(), noop_log_handle());
+        let instance = Instance::new("test-1".to_string(), "v1".to_string(), 0, noop_log_handle());
         assert_eq!(instance.state(), InstanceState::Starting);
 
         instance.set_state(InstanceState::Ready);
@@ -600,7 +904,7 @@ mod tests {
 
     #[test]
     fn test_instance_request_tracking() {
-        let instance = Instance::new("test-1".to_string(), "v1".to_string(), noop_log_handle());
+        let instance = Instance::new("test-1".to_string(), "v1".to_string(), 0, noop_log_handle());
         assert_eq!(instance.requests_total(), 0);
 
         instance.request_started();
@@ -655,9 +959,103 @@ mod tests {
         assert_eq!(v2_instance.build_version(), "v2");
     }
 
+    #[test]
+    fn test_build_routing_filters_healthy_instances() {
+        let (tx, _rx) = mpsc::channel(16);
+        let config = AppConfig {
+            name: "test-app".to_string(),
+            version: "v1".to_string(),
+            ..Default::default()
+        };
+        let app = App::new(config, tx, noop_log_handle());
+        let v1_instance = app.allocate_instance();
+        v1_instance.set_state(InstanceState::Healthy);
+
+        let mut next = app.config.read().clone();
+        next.version = "v2".to_string();
+        app.update_config(next);
+        let v2_instance = app.allocate_instance();
+        v2_instance.set_state(InstanceState::Healthy);
+        assert_eq!(app.get_healthy_instances().len(), 2);
+
+        app.set_routing(BuildRouting::Except(vec![v2_instance.id.clone()]));
+        let routed = app.get_healthy_instances();
+        assert_eq!(routed.len(), 1);
+        assert_eq!(routed[0].id, v1_instance.id);
+
+        app.set_routing(BuildRouting::Only(vec![v2_instance.id.clone()]));
+        assert_eq!(app.get_healthy_instance().unwrap().id, v2_instance.id);
+        assert_eq!(
+            app.get_least_loaded_healthy_instance(&app.routing(), |_| 0)
+                .unwrap()
+                .id,
+            v2_instance.id
+        );
+    }
+
+    #[test]
+    fn test_canary_takes_exact_share_of_each_hundred_samples() {
+        for percent in [1u8, 10, 25, 50, 99] {
+            let taken = (0..1000)
+                .filter(|sample| canary_takes_request(percent, *sample))
+                .count();
+            assert_eq!(taken, usize::from(percent) * 10, "percent {percent}");
+        }
+        // Spread out: a 50% canary alternates instead of taking a burst.
+        assert!((0..10).any(|sample| canary_takes_request(50, sample)));
+        assert!((0..10).any(|sample| !canary_takes_request(50, sample)));
+    }
+
+    #[test]
+    fn test_canary_routing_splits_traffic_by_build() {
+        let (tx, _rx) = mpsc::channel(16);
+        let config = AppConfig {
+            name: "test-app".to_string(),
+            version: "v1".to_string(),
+            ..Default::default()
+        };
+        let app = App::new(config, tx, noop_log_handle());
+        for _ in 0..2 {
+            app.allocate_instance().set_state(InstanceState::Healthy);
+        }
+        let mut next = app.config.read().clone();
+        next.version = "v2".to_string();
+        app.update_config(next);
+        let canary = app.allocate_instance();
+        canary.set_state(InstanceState::Healthy);
+
+        app.set_routing(BuildRouting::Canary {
+            instances: vec![canary.id.clone()],
+            percent: 20,
+        });
+        // Lookups outside request routing leave the split alone.
+        assert_eq!(app.get_healthy_instances().len(), 3);
+        assert_eq!(app.canary_samples.load(Ordering::Relaxed), 0);
+
+        let mut to_canary = 0;
+        for _ in 0..1000 {
+            let picked = app.get_routed_instances(&app.request_routing());
+            assert!(!picked.is_empty());
+            let builds: Vec<&str> = picked.iter().map(|i| i.build_version()).collect();
+            if builds == ["v2"] {
+                to_canary += 1;
+            } else {
+                assert!(builds.iter().all(|build| *build == "v1"));
+            }
+        }
+        assert_eq!(to_canary, 200);
+
+        // With the canary down, every request falls back to the stable build.
+        canary.set_state(InstanceState::Unhealthy);
+        for _ in 0..100 {
+            let picked = app.get_routed_instances(&app.request_routing());
+            assert!(picked.iter().all(|i| i.build_version() == "v1"));
+        }
+    }
+
     #[test]
     fn test_instance_internal_token_is_stable() {
-        let instance = Instance::new("test-1".to_string(), "v1".to_string(), noop_log_handle());
+        let instance = Instance::new("test-1".to_string(), "v1".to_string(), 0, noop_log_handle());
         let token = instance.internal_token().to_string();
         assert!(!token.is_empty());
         assert_eq!(instance.internal_token(), token);
@@ -665,7 +1063,7 @@ mod tests {
 
     #[test]
     fn test_instance_port_round_trips() {
-        let instance = Instance::new("test-1".to_string(), "v1".to_string(), noop_log_handle());
+        let instance = Instance::new("test-1".to_string(), "v1".to_string(), 0, noop_log_handle());
         assert_eq!(instance.port(), None);
         instance.set_port(48_123);
         assert_eq!(instance.port(), Some(48_123));
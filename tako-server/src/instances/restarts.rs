@@ -0,0 +1,195 @@
+//! Restart budget - stops respawning an app that keeps crashing, and backs
+//! off between the respawns it still allows
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Tracks replacement spawns for one app within a sliding window
+#[derive(Debug, Default)]
+pub struct RestartBudget {
+    attempts: VecDeque<Instant>,
+    exhausted: bool,
+    consecutive_failures: u32,
+    last_failure: Option<Instant>,
+    spawn_failures: u32,
+}
+
+/// Delay before a respawn after `failures` earlier consecutive failures:
+/// `base`, `2 * base`, `4 * base`, ... capped at `max`.
+pub fn restart_backoff_delay(failures: u32, base: Duration, max: Duration) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(failures)).min(max)
+}
+
+impl RestartBudget {
+    /// Record a restart at `now`. Returns `false`, and marks the budget
+    /// exhausted, when `max_attempts` restarts already happened within `window`.
+    pub fn try_restart(&mut self, now: Instant, max_attempts: u32, window: Duration) -> bool {
+        while self
+            .attempts
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= window)
+        {
+            self.attempts.pop_front();
+        }
+        if self.exhausted || self.attempts.len() >= max_attempts as usize {
+            self.exhausted = true;
+            return false;
+        }
+        self.attempts.push_back(now);
+        true
+    }
+
+    /// Record a failure at `now` and return how long to wait before the
+    /// replacement spawn. The streak starts over once `stable_after` has
+    /// passed since the previous failure.
+    pub fn next_backoff(
+        &mut self,
+        now: Instant,
+        base: Duration,
+        max: Duration,
+        stable_after: Duration,
+    ) -> Duration {
+        if self
+            .last_failure
+            .is_some_and(|at| now.duration_since(at) >= stable_after)
+        {
+            self.consecutive_failures = 0;
+        }
+        let delay = restart_backoff_delay(self.consecutive_failures, base, max);
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.last_failure = Some(now);
+        delay
+    }
+
+    /// Record a spawn that failed before the instance became ready. Returns
+    /// `true`, and marks the budget exhausted, once `threshold` spawns have
+    /// failed in a row.
+    pub fn record_spawn_failure(&mut self, threshold: u32) -> bool {
+        self.spawn_failures = self.spawn_failures.saturating_add(1);
+        if self.spawn_failures >= threshold {
+            self.exhausted = true;
+        }
+        self.exhausted
+    }
+
+    /// An instance reached ready; the consecutive spawn failure streak ends.
+    /// An exhausted budget stays exhausted until `reset`.
+    pub fn record_spawn_success(&mut self) {
+        self.spawn_failures = 0;
+    }
+
+    /// Whether restarts stopped until the next deploy or explicit action
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    pub fn reset(&mut self) {
+        self.attempts.clear();
+        self.exhausted = false;
+        self.consecutive_failures = 0;
+        self.last_failure = None;
+        self.spawn_failures = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(300);
+
+    #[test]
+    fn exhausts_after_max_attempts_within_window() {
+        let start = Instant::now();
+        let mut budget = RestartBudget::default();
+
+        assert!(budget.try_restart(start, 2, WINDOW));
+        assert!(budget.try_restart(start + Duration::from_secs(1), 2, WINDOW));
+        assert!(!budget.try_restart(start + Duration::from_secs(2), 2, WINDOW));
+        assert!(budget.is_exhausted());
+
+        // Once exhausted, only a reset allows restarts again.
+        assert!(!budget.try_restart(start + WINDOW * 2, 2, WINDOW));
+        budget.reset();
+        assert!(budget.try_restart(start + WINDOW * 2, 2, WINDOW));
+    }
+
+    #[test]
+    fn attempts_outside_window_do_not_count() {
+        let start = Instant::now();
+        let mut budget = RestartBudget::default();
+
+        assert!(budget.try_restart(start, 2, WINDOW));
+        assert!(budget.try_restart(start + Duration::from_secs(10), 2, WINDOW));
+        assert!(budget.try_restart(start + WINDOW, 2, WINDOW));
+        assert!(!budget.is_exhausted());
+    }
+
+    const BASE: Duration = Duration::from_secs(1);
+    const MAX: Duration = Duration::from_secs(30);
+    const STABLE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn backoff_delay_doubles_and_caps() {
+        let delays: Vec<u64> = (0..8)
+            .map(|failures| restart_backoff_delay(failures, BASE, MAX).as_secs())
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30, 30]);
+        assert_eq!(restart_backoff_delay(u32::MAX, BASE, MAX), MAX);
+    }
+
+    #[test]
+    fn backoff_grows_with_consecutive_failures() {
+        let start = Instant::now();
+        let mut budget = RestartBudget::default();
+
+        let delays: Vec<u64> = (0..4)
+            .map(|i| {
+                let now = start + Duration::from_secs(i * 5);
+                budget.next_backoff(now, BASE, MAX, STABLE).as_secs()
+            })
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn backoff_resets_after_stable_period() {
+        let start = Instant::now();
+        let mut budget = RestartBudget::default();
+
+        assert_eq!(budget.next_backoff(start, BASE, MAX, STABLE), BASE);
+        assert_eq!(
+            budget.next_backoff(start + Duration::from_secs(10), BASE, MAX, STABLE),
+            BASE * 2
+        );
+        let later = start + Duration::from_secs(10) + STABLE;
+        assert_eq!(budget.next_backoff(later, BASE, MAX, STABLE), BASE);
+
+        budget.next_backoff(later + Duration::from_secs(1), BASE, MAX, STABLE);
+        budget.reset();
+        assert_eq!(
+            budget.next_backoff(later + Duration::from_secs(2), BASE, MAX, STABLE),
+            BASE
+        );
+    }
+
+    #[test]
+    fn consecutive_spawn_failures_exhaust_budget() {
+        let mut budget = RestartBudget::default();
+
+        assert!(!budget.record_spawn_failure(3));
+        assert!(!budget.record_spawn_failure(3));
+        budget.record_spawn_success();
+        assert!(!budget.record_spawn_failure(3));
+        assert!(!budget.record_spawn_failure(3));
+        assert!(budget.record_spawn_failure(3));
+        assert!(budget.is_exhausted());
+
+        // A later ready instance does not lift the breaker; a reset does.
+        budget.record_spawn_success();
+        assert!(budget.is_exhausted());
+        budget.reset();
+        assert!(!budget.is_exhausted());
+        assert!(!budget.record_spawn_failure(3));
+    }
+}
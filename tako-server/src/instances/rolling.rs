@@ -1,6 +1,6 @@
 //! Rolling update - zero-downtime deployments
 
-use super::{App, AppConfig, Instance, InstanceError, InstanceState, Spawner};
+use super::{App, AppConfig, BuildRouting, Instance, InstanceError, InstanceState, Spawner};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
@@ -20,12 +20,22 @@ impl Default for RollingUpdateConfig {
     fn default() -> Self {
         Self {
             health_timeout: Duration::from_secs(30),
-            drain_timeout: Duration::from_secs(30),
+            drain_timeout: crate::defaults::DEFAULT_DRAIN_TIMEOUT,
             batch_size: 1,
         }
     }
 }
 
+impl RollingUpdateConfig {
+    /// Defaults, with the app's configured drain timeout
+    pub fn for_app(config: &AppConfig) -> Self {
+        Self {
+            drain_timeout: config.drain_timeout,
+            ..Self::default()
+        }
+    }
+}
+
 /// Result of a rolling update
 #[derive(Debug)]
 pub struct RollingUpdateResult {
@@ -176,6 +186,247 @@ impl RollingUpdater {
         })
     }
 
+    /// Perform a blue/green update
+    ///
+    /// 1. Start `target_count` new instances while traffic stays on the old build
+    /// 2. Wait until every new instance is healthy
+    /// 3. Switch traffic to the new build in one step
+    /// 4. Drain and stop the old instances
+    ///
+    /// If any new instance fails to become healthy, all new instances are
+    /// killed and the old build keeps serving as if nothing happened.
+    pub async fn blue_green_update(
+        &self,
+        app: &App,
+        new_config: AppConfig,
+        target_count: u32,
+    ) -> Result<RollingUpdateResult, InstanceError> {
+        let staged = match self.stage_build(app, new_config, target_count).await {
+            Ok(staged) => staged,
+            Err(e) => {
+                return Ok(RollingUpdateResult {
+                    success: false,
+                    new_instances: 0,
+                    old_instances: 0,
+                    error: Some(format!("Health check failed: {}", e)),
+                    rolled_back: true,
+                });
+            }
+        };
+
+        let stopped_count = self.switch_to_build(app, &staged).await?;
+
+        tracing::info!(
+            app = %app.name(),
+            new_instances = target_count,
+            stopped_instances = stopped_count,
+            "Blue/green update complete"
+        );
+
+        Ok(RollingUpdateResult {
+            success: true,
+            new_instances: target_count,
+            old_instances: stopped_count,
+            error: None,
+            rolled_back: false,
+        })
+    }
+
+    /// Perform a canary update
+    ///
+    /// Stages the new build like [`Self::blue_green_update`], then sends it
+    /// `percent` of requests for `hold`. If every new instance stays healthy
+    /// through the hold, traffic switches to the new build and the old
+    /// instances are drained; otherwise the new instances are killed.
+    pub async fn canary_update(
+        &self,
+        app: &App,
+        new_config: AppConfig,
+        target_count: u32,
+        percent: u8,
+        hold: Duration,
+    ) -> Result<RollingUpdateResult, InstanceError> {
+        let new_version = new_config.version.clone();
+        let staged = match self.stage_build(app, new_config, target_count).await {
+            Ok(staged) => staged,
+            Err(e) => {
+                return Ok(RollingUpdateResult {
+                    success: false,
+                    new_instances: 0,
+                    old_instances: 0,
+                    error: Some(format!("Health check failed: {}", e)),
+                    rolled_back: true,
+                });
+            }
+        };
+
+        tracing::info!(
+            app = %app.name(),
+            build = %new_version,
+            percent = percent,
+            hold_secs = hold.as_secs(),
+            "Canary receiving traffic"
+        );
+        // A promote left over from an earlier canary must not cut this one short.
+        app.take_canary_promotion();
+        app.set_routing(BuildRouting::Canary {
+            instances: staged.clone(),
+            percent,
+        });
+
+        if let Err(e) = self.hold_canary(app, &staged, hold).await {
+            tracing::error!(
+                app = %app.name(),
+                build = %new_version,
+                error = %e,
+                "Canary failed during hold, keeping current build"
+            );
+            app.set_routing(BuildRouting::All);
+            for id in &staged {
+                if let Some(instance) = app.get_instance(id) {
+                    let _ = instance.kill().await;
+                    app.remove_instance(id);
+                }
+            }
+            return Ok(RollingUpdateResult {
+                success: false,
+                new_instances: 0,
+                old_instances: 0,
+                error: Some(format!("Canary failed: {}", e)),
+                rolled_back: true,
+            });
+        }
+
+        let stopped_count = self.switch_to_build(app, &staged).await?;
+
+        tracing::info!(
+            app = %app.name(),
+            new_instances = target_count,
+            stopped_instances = stopped_count,
+            "Canary update complete"
+        );
+
+        Ok(RollingUpdateResult {
+            success: true,
+            new_instances: target_count,
+            old_instances: stopped_count,
+            error: None,
+            rolled_back: false,
+        })
+    }
+
+    /// Watch the `staged` canary instances for `hold`, failing as soon as
+    /// any of them is gone or unhealthy. A promote ends the hold early.
+    async fn hold_canary(
+        &self,
+        app: &App,
+        staged: &[String],
+        hold: Duration,
+    ) -> Result<(), InstanceError> {
+        let deadline = tokio::time::Instant::now() + hold;
+        let mut interval = tokio::time::interval(Duration::from_millis(100));
+
+        loop {
+            let all_healthy = staged.iter().all(|id| {
+                app.get_instance(id)
+                    .is_some_and(|i| i.state() == InstanceState::Healthy)
+            });
+            if !all_healthy {
+                return Err(InstanceError::HealthCheckFailed(
+                    "Canary instance became unhealthy during the hold".to_string(),
+                ));
+            }
+            if app.take_canary_promotion() {
+                tracing::info!(app = %app.name(), "Canary promoted before the end of its hold");
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(());
+            }
+            interval.tick().await;
+        }
+    }
+
+    /// Start `target_count` instances of `new_config` next to the running
+    /// ones, held back from traffic until [`Self::switch_to_build`].
+    /// Returns the ids of the staged instances.
+    ///
+    /// On failure the new instances are killed and traffic goes back to
+    /// every healthy instance, as after a failed rolling update.
+    pub async fn stage_build(
+        &self,
+        app: &App,
+        new_config: AppConfig,
+        target_count: u32,
+    ) -> Result<Vec<String>, InstanceError> {
+        let new_version = new_config.version.clone();
+
+        tracing::info!(
+            app = %app.name(),
+            old_count = app.get_instances().len(),
+            target_count = target_count,
+            "Starting blue/green update"
+        );
+
+        app.update_config(new_config);
+
+        let mut new_instances: Vec<Arc<Instance>> = Vec::new();
+        let mut staged: Vec<String> = Vec::new();
+        for _ in 0..target_count {
+            let instance = app.allocate_instance();
+            new_instances.push(instance.clone());
+            staged.push(instance.id.clone());
+            app.set_routing(BuildRouting::Except(staged.clone()));
+
+            if let Err(e) = self.start_and_wait_healthy(app, instance.clone()).await {
+                tracing::error!(
+                    app = %app.name(),
+                    instance = %instance.id,
+                    error = %e,
+                    "New instance failed health check, keeping current build"
+                );
+                for new_instance in &new_instances {
+                    let _ = new_instance.kill().await;
+                    app.remove_instance(&new_instance.id);
+                }
+                app.set_routing(BuildRouting::All);
+                return Err(e);
+            }
+        }
+
+        tracing::info!(
+            app = %app.name(),
+            build = %new_version,
+            instances = new_instances.len(),
+            "New build is healthy and staged"
+        );
+        Ok(staged)
+    }
+
+    /// Move all traffic to the `staged` instances, then drain and stop
+    /// every other instance. Returns how many were stopped.
+    pub async fn switch_to_build(
+        &self,
+        app: &App,
+        staged: &[String],
+    ) -> Result<u32, InstanceError> {
+        app.set_routing(BuildRouting::Only(staged.to_vec()));
+        let mut stopped = 0u32;
+        let mut result = Ok(());
+        for instance in app.get_instances() {
+            if staged.contains(&instance.id) {
+                continue;
+            }
+            if let Err(e) = self.drain_and_stop(app, &instance).await {
+                result = Err(e);
+                break;
+            }
+            stopped += 1;
+        }
+        app.set_routing(BuildRouting::All);
+        result.map(|()| stopped)
+    }
+
     /// Start an instance and wait for it to become healthy
     async fn start_and_wait_healthy(
         &self,
@@ -5,6 +5,7 @@ mod readiness;
 mod spawn_command;
 
 use super::{App, Instance, InstanceError, InstanceEvent, InstanceState};
+use crate::secret_refs::SecretRefPolicy;
 use health_probe::probe_endpoint_tcp;
 use readiness::wait_for_ready;
 use spawn_command::{
@@ -24,6 +25,8 @@ pub struct Spawner {
     /// every spawned instance as `TAKO_INTERNAL_SOCKET` so workflow `.enqueue()`
     /// and channel `.publish()` from app code work. `None` in tests.
     internal_socket: Option<PathBuf>,
+    /// Which secret references are resolved at spawn time.
+    secret_refs: SecretRefPolicy,
 }
 
 impl Spawner {
@@ -32,6 +35,7 @@ impl Spawner {
             #[cfg(unix)]
             app_user: resolve_app_user(),
             internal_socket: None,
+            secret_refs: SecretRefPolicy::default(),
         }
     }
 
@@ -39,6 +43,11 @@ impl Spawner {
         self.internal_socket = Some(path);
         self
     }
+
+    pub(crate) fn with_secret_refs(mut self, secret_refs: SecretRefPolicy) -> Self {
+        self.secret_refs = secret_refs;
+        self
+    }
 }
 
 impl Spawner {
@@ -58,6 +67,10 @@ impl Spawner {
         let extra_args = build_instance_args(&instance);
 
         let app_user = self.app_user;
+        let secrets = self
+            .secret_refs
+            .resolve_secrets(&config.secrets)
+            .map_err(InstanceError::SecretResolution)?;
 
         let (child, readiness_fd) = spawn_child_process(
             &config,
@@ -65,7 +78,7 @@ impl Spawner {
             &extra_args,
             app_user,
             instance.internal_token(),
-            &config.secrets,
+            &secrets,
         )
         .map_err(InstanceError::from)?;
 
@@ -102,11 +115,13 @@ impl Spawner {
                 let _ = app
                     .instance_tx
                     .send(InstanceEvent::Ready {
-                        app: app_name,
+                        app: app_name.clone(),
                         instance_id,
                     })
                     .await;
 
+                tokio::spawn(watch_for_exit(app.instance_tx.clone(), app_name, instance));
+
                 Ok(())
             }
             Ok(Err(e)) => {
@@ -165,6 +180,54 @@ impl Spawner {
     }
 }
 
+/// Wait for a ready instance's process to exit on its own and report it.
+/// Stops quietly once the process handle is taken, which is how Tako kills
+/// instances.
+async fn watch_for_exit(
+    instance_tx: tokio::sync::mpsc::Sender<InstanceEvent>,
+    app_name: String,
+    instance: Arc<Instance>,
+) {
+    loop {
+        tokio::time::sleep(crate::defaults::INSTANCE_EXIT_POLL_INTERVAL).await;
+        let status = match instance.try_exit_status() {
+            Ok(Some(status)) => status,
+            Ok(None) => continue,
+            Err(()) => return,
+        };
+        if matches!(
+            instance.state(),
+            InstanceState::Draining | InstanceState::Stopped
+        ) {
+            return;
+        }
+
+        instance.set_state(InstanceState::Stopped);
+        let (code, signal) = exit_code_and_signal(status);
+        let _ = instance_tx
+            .send(InstanceEvent::Exited {
+                app: app_name,
+                instance_id: instance.id.clone(),
+                code,
+                signal,
+            })
+            .await;
+        return;
+    }
+}
+
+fn exit_code_and_signal(status: std::process::ExitStatus) -> (Option<i32>, Option<i32>) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        (status.code(), status.signal())
+    }
+    #[cfg(not(unix))]
+    {
+        (status.code(), None)
+    }
+}
+
 impl Default for Spawner {
     fn default() -> Self {
         Self::new()
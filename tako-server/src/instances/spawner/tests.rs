@@ -517,3 +517,39 @@ async fn wait_for_ready_rejects_invalid_fd4_payload() {
     let err = wait_for_ready(instance, Some(read_end)).await.unwrap_err();
     assert!(err.to_string().contains("invalid port"));
 }
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawned_instance_reports_exit_after_becoming_ready() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let (instance_tx, mut instance_rx) = mpsc::channel(8);
+    let app = App::new(
+        AppConfig {
+            name: "test-app".to_string(),
+            path: temp.path().to_path_buf(),
+            command: vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                "echo 4000 >&4; sleep 0.2; exit 3".to_string(),
+            ],
+            ..Default::default()
+        },
+        instance_tx,
+        noop_log_handle(),
+    );
+    let instance = app.allocate_instance();
+    Spawner::new().spawn(&app, instance.clone()).await.unwrap();
+
+    let exited = tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(event) = instance_rx.recv().await {
+            if let InstanceEvent::Exited { code, signal, .. } = event {
+                return Some((code, signal));
+            }
+        }
+        None
+    })
+    .await
+    .expect("exit event");
+    assert_eq!(exited, Some((Some(3), None)));
+    assert_eq!(instance.state(), InstanceState::Stopped);
+}
@@ -0,0 +1,117 @@
+//! Per-process resource sampling (RSS and CPU time) for app instances.
+
+use crate::socket::ResourceUsage;
+
+/// Parse the contents of `/proc/<pid>/stat` into resource usage.
+///
+/// `utime`/`stime` are reported in clock ticks and `rss` in pages, so the
+/// caller passes the system's `page_size` and `clock_ticks` per second.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_proc_stat(
+    stat: &str,
+    page_size: u64,
+    clock_ticks: u64,
+) -> Option<ResourceUsage> {
+    // The command name (field 2) is wrapped in parentheses and may itself
+    // contain spaces or parentheses, so split on the last ')'.
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // `fields[0]` is field 3 (state); field N lives at index N - 3.
+    let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+    let utime = field(14)?;
+    let stime = field(15)?;
+    let rss_pages = field(24)?;
+    if clock_ticks == 0 {
+        return None;
+    }
+
+    Some(ResourceUsage {
+        rss_bytes: rss_pages.saturating_mul(page_size),
+        cpu_time_ms: utime.saturating_add(stime).saturating_mul(1000) / clock_ticks,
+    })
+}
+
+/// Sample the current usage of a running process. Returns `None` when the
+/// process is gone or the platform is unsupported.
+#[cfg(target_os = "linux")]
+pub fn sample_process_usage(pid: u32) -> Option<ResourceUsage> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    let clock_ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if page_size <= 0 || clock_ticks <= 0 {
+        return None;
+    }
+    parse_proc_stat(&stat, page_size as u64, clock_ticks as u64)
+}
+
+/// Sample the current usage of a running process. Returns `None` when the
+/// process is gone or the platform is unsupported.
+#[cfg(target_os = "macos")]
+#[allow(deprecated)]
+pub fn sample_process_usage(pid: u32) -> Option<ResourceUsage> {
+    let mut info: libc::proc_taskinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as libc::c_int;
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid as libc::c_int,
+            libc::PROC_PIDTASKINFO,
+            0,
+            &mut info as *mut _ as *mut libc::c_void,
+            size,
+        )
+    };
+    if written != size {
+        return None;
+    }
+
+    // Task times are in Mach absolute time units, not nanoseconds.
+    let mut timebase = libc::mach_timebase_info { numer: 0, denom: 0 };
+    if unsafe { libc::mach_timebase_info(&mut timebase) } != 0 || timebase.denom == 0 {
+        return None;
+    }
+    let cpu_ticks = info.pti_total_user.saturating_add(info.pti_total_system) as u128;
+    let cpu_nanos = cpu_ticks * timebase.numer as u128 / timebase.denom as u128;
+
+    Some(ResourceUsage {
+        rss_bytes: info.pti_resident_size,
+        cpu_time_ms: (cpu_nanos / 1_000_000) as u64,
+    })
+}
+
+/// Sample the current usage of a running process. Returns `None` when the
+/// process is gone or the platform is unsupported.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn sample_process_usage(_pid: u32) -> Option<ResourceUsage> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_proc_stat_extracts_rss_and_cpu_time() {
+        // comm contains a space and a ')' to exercise the last-paren split.
+        let stat = "4242 (bun (worker) x) S 1 4242 4242 0 -1 4194560 21010 0 12 0 \
+                    250 150 0 0 20 0 9 0 123456 1234567890 3000 18446744073709551615 \
+                    1 1 0 0 0 0 0 16781312 17414 0 0 0 17 3 0 0 0 0 0 0 0 0 0 0 0 0 0\n";
+
+        let usage = parse_proc_stat(stat, 4096, 100).unwrap();
+        assert_eq!(usage.rss_bytes, 3000 * 4096);
+        // (250 + 150) ticks at 100 Hz.
+        assert_eq!(usage.cpu_time_ms, 4000);
+    }
+
+    #[test]
+    fn parse_proc_stat_rejects_truncated_input() {
+        assert_eq!(parse_proc_stat("4242 (bun) S 1 4242", 4096, 100), None);
+        assert_eq!(parse_proc_stat("garbage", 4096, 100), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sample_process_usage_reads_own_process() {
+        let usage = sample_process_usage(std::process::id()).unwrap();
+        assert!(usage.rss_bytes > 0);
+    }
+}
@@ -7,7 +7,7 @@
 //! - Health-aware routing
 //! - On-demand instance spawning
 
-use crate::instances::{App, AppManager, Instance};
+use crate::instances::{App, AppManager, BuildRouting, Instance};
 use crate::socket::InstanceState;
 use dashmap::DashMap;
 use std::collections::hash_map::DefaultHasher;
@@ -57,16 +57,41 @@ impl AppLoadBalancer {
 
     /// Get an instance to handle a request, with optional client IP for sticky sessions
     pub fn get_instance_for_ip(&self, client_ip: Option<IpAddr>) -> Option<Arc<Instance>> {
+        let routing = self.app.request_routing();
         match self.strategy {
-            Strategy::RoundRobin => self.round_robin(),
-            Strategy::LeastConnections => self.least_connections(),
-            Strategy::IpHash => self.ip_hash(client_ip),
+            Strategy::RoundRobin => self.round_robin(&routing),
+            Strategy::LeastConnections => self.least_connections(&routing),
+            Strategy::IpHash => self.ip_hash(client_ip, &routing),
         }
     }
 
+    /// Get a healthy instance other than the `excluded` ones, round-robin.
+    ///
+    /// Retries honor the app's current routing, so staged instances stay
+    /// out of reach until cutover and a drained app has none to offer. A
+    /// retry belongs to a request already counted toward a canary split, so
+    /// during a canary it may land on either side.
+    pub fn get_instance_excluding(&self, excluded: &[String]) -> Option<Arc<Instance>> {
+        let routing = match self.app.routing() {
+            BuildRouting::Canary { .. } => BuildRouting::All,
+            routing => routing,
+        };
+        let healthy: Vec<_> = self
+            .app
+            .get_routed_instances(&routing)
+            .into_iter()
+            .filter(|instance| !excluded.contains(&instance.id))
+            .collect();
+        if healthy.is_empty() {
+            return None;
+        }
+        let idx = self.rr_counter.fetch_add(1, Ordering::Relaxed) % healthy.len();
+        Some(healthy[idx].clone())
+    }
+
     /// Get instance using round-robin
-    fn round_robin(&self) -> Option<Arc<Instance>> {
-        let healthy = self.app.get_healthy_instances();
+    fn round_robin(&self, routing: &BuildRouting) -> Option<Arc<Instance>> {
+        let healthy = self.app.get_routed_instances(routing);
         if healthy.is_empty() {
             return None;
         }
@@ -75,13 +100,14 @@ impl AppLoadBalancer {
     }
 
     /// Get instance with least active connections
-    fn least_connections(&self) -> Option<Arc<Instance>> {
-        self.app.get_least_loaded_healthy_instance(|instance_id| {
-            self.connections
-                .get(instance_id)
-                .map(|c| c.load(Ordering::Relaxed))
-                .unwrap_or(0)
-        })
+    fn least_connections(&self, routing: &BuildRouting) -> Option<Arc<Instance>> {
+        self.app
+            .get_least_loaded_healthy_instance(routing, |instance_id| {
+                self.connections
+                    .get(instance_id)
+                    .map(|c| c.load(Ordering::Relaxed))
+                    .unwrap_or(0)
+            })
     }
 
     /// Get instance using IP hash for sticky sessions
@@ -89,14 +115,14 @@ impl AppLoadBalancer {
     /// The same client IP will consistently route to the same instance
     /// (as long as the instance remains healthy). If no client IP is
     /// provided, falls back to round-robin.
-    fn ip_hash(&self, client_ip: Option<IpAddr>) -> Option<Arc<Instance>> {
+    fn ip_hash(&self, client_ip: Option<IpAddr>, routing: &BuildRouting) -> Option<Arc<Instance>> {
         // Fall back to round-robin if no IP provided
         let ip = match client_ip {
             Some(ip) => ip,
-            None => return self.round_robin(),
+            None => return self.round_robin(routing),
         };
 
-        let healthy = self.app.get_healthy_instances();
+        let healthy = self.app.get_routed_instances(routing);
         if healthy.is_empty() {
             return None;
         }
@@ -185,6 +211,21 @@ impl LoadBalancer {
         })
     }
 
+    /// Get a backend on a healthy instance not in `excluded`, for retrying a
+    /// request whose earlier instances failed
+    pub fn get_backend_excluding(&self, app_name: &str, excluded: &[String]) -> Option<Backend> {
+        let lb = self.app_lbs.get(app_name)?;
+        let instance = lb.get_instance_excluding(excluded)?;
+
+        lb.connection_started(&instance.id);
+
+        Some(Backend {
+            app_name: app_name.to_string(),
+            instance_id: instance.id.clone(),
+            endpoint: instance.endpoint(),
+        })
+    }
+
     /// Mark request completed
     pub fn request_completed(&self, app_name: &str, instance_id: &str) {
         if let Some(lb) = self.app_lbs.get(app_name) {
@@ -304,6 +345,56 @@ mod tests {
         assert_eq!(lb.active_connections(&i1.id), 1);
     }
 
+    #[test]
+    fn test_get_instance_excluding_skips_tried_instances() {
+        let app = create_test_app();
+        let i1 = app.allocate_instance();
+        let i2 = app.allocate_instance();
+        i1.set_state(InstanceState::Healthy);
+        i2.set_state(InstanceState::Healthy);
+
+        let lb = AppLoadBalancer::new(app, Strategy::RoundRobin);
+
+        for _ in 0..4 {
+            let instance = lb.get_instance_excluding(&[i1.id.clone()]).unwrap();
+            assert_eq!(instance.id, i2.id);
+        }
+        assert!(
+            lb.get_instance_excluding(&[i1.id.clone(), i2.id.clone()])
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_get_instance_excluding_follows_deploy_routing() {
+        let app = create_test_app();
+        let live = app.allocate_instance();
+        let staged = app.allocate_instance();
+        live.set_state(InstanceState::Healthy);
+        staged.set_state(InstanceState::Healthy);
+        app.set_routing(BuildRouting::Except(vec![staged.id.clone()]));
+
+        let lb = AppLoadBalancer::new(app.clone(), Strategy::RoundRobin);
+        for _ in 0..4 {
+            let instance = lb.get_instance_excluding(&[]).unwrap();
+            assert_eq!(instance.id, live.id);
+        }
+        // The staged instance is not a fallback for a failed live one.
+        assert!(lb.get_instance_excluding(&[live.id.clone()]).is_none());
+
+        app.set_routing(BuildRouting::Canary {
+            instances: vec![staged.id.clone()],
+            percent: 10,
+        });
+        let instance = lb.get_instance_excluding(&[live.id.clone()]).unwrap();
+        assert_eq!(instance.id, staged.id);
+
+        app.set_routing(BuildRouting::All);
+        app.set_drained(true);
+        assert!(lb.get_instance_excluding(&[live.id.clone()]).is_none());
+        assert!(lb.get_instance_excluding(&[]).is_none());
+    }
+
     #[test]
     fn test_no_healthy_instances() {
         let app = create_test_app();
@@ -314,6 +405,37 @@ mod tests {
         assert!(lb.get_instance().is_none());
     }
 
+    #[test]
+    fn test_drained_app_instances_are_not_selected() {
+        let app = create_test_app();
+        let i1 = app.allocate_instance();
+        let i2 = app.allocate_instance();
+        i1.set_state(InstanceState::Healthy);
+        i2.set_state(InstanceState::Healthy);
+        app.set_drained(true);
+
+        for strategy in [
+            Strategy::RoundRobin,
+            Strategy::LeastConnections,
+            Strategy::IpHash,
+        ] {
+            let lb = AppLoadBalancer::new(app.clone(), strategy);
+            assert!(lb.get_instance().is_none());
+            assert!(
+                lb.get_instance_for_ip(Some("10.0.0.1".parse().unwrap()))
+                    .is_none()
+            );
+            assert!(lb.get_instance_excluding(&[]).is_none());
+        }
+        // Still running, just out of rotation.
+        assert_eq!(app.get_instances().len(), 2);
+        assert_eq!(app.count_by_state(InstanceState::Healthy), 2);
+
+        app.set_drained(false);
+        let lb = AppLoadBalancer::new(app, Strategy::RoundRobin);
+        assert!(lb.get_instance().is_some());
+    }
+
     #[tokio::test]
     async fn test_global_load_balancer() {
         let manager = Arc::new(AppManager::new(PathBuf::from("/tmp/tako-test")));
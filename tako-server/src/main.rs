@@ -24,7 +24,9 @@ mod release_command;
 mod routing;
 mod runtime_events;
 mod scaling;
+mod secret_refs;
 mod server_state;
+mod shutdown;
 mod socket;
 mod startup;
 mod state_store;
@@ -82,6 +84,16 @@ pub struct Args {
     #[arg(long)]
     pub acme_staging: bool,
 
+    /// ACME directory URL for a non-Let's Encrypt CA (overrides --acme-staging)
+    #[arg(long)]
+    pub acme_directory: Option<String>,
+
+    /// Issue wildcard certificates with manual DNS-01 instead of lego: log
+    /// each `_acme-challenge` TXT record to create, then wait this many
+    /// seconds before the challenge is validated
+    #[arg(long, value_name = "SECS")]
+    pub manual_dns01_wait_secs: Option<u64>,
+
     /// Data directory for apps and certificates
     #[arg(long)]
     pub data_dir: Option<String>,
@@ -94,6 +106,15 @@ pub struct Args {
     #[arg(long, default_value_t = 12)]
     pub renewal_interval_hours: u64,
 
+    /// Renew certificates this many days before they expire
+    #[arg(long, value_name = "DAYS", default_value_t = 30)]
+    pub cert_renewal_days: u32,
+
+    /// Skip certificate renewals while the system clock reads earlier than a
+    /// certificate's validity window (pass `false` to renew anyway)
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    pub refuse_renewal_on_clock_skew: bool,
+
     /// Run as a hot standby: serve traffic with minimal scaling (max 1 instance
     /// per app), skip management socket and ACME. Monitors the primary
     /// server's socket — promotes to full mode if primary is unavailable,
@@ -101,10 +122,63 @@ pub struct Args {
     #[arg(long)]
     pub standby: bool,
 
+    /// Keep app registrations, secrets, and deploy history in memory only.
+    /// Nothing is restored after a restart.
+    #[arg(long)]
+    pub ephemeral: bool,
+
+    /// Allow the `env` management command to return secret values
+    #[arg(long)]
+    pub allow_secret_reveal: bool,
+
+    /// 32-byte key file that encrypts stored secrets (default: generated
+    /// `secret.key` in the data directory)
+    #[arg(long)]
+    pub state_key_file: Option<String>,
+
+    /// HTML file served (with a 503) to requests whose on-demand app did not
+    /// start in time (default: a built-in page)
+    #[arg(long)]
+    pub cold_start_page: Option<String>,
+
+    /// Directory `file:` secret references may read from; without it (and
+    /// without `--secret-ref-env`) every secret value is used literally
+    #[arg(long, value_name = "DIR")]
+    pub secret_ref_dir: Option<String>,
+
+    /// Environment variable `env:` secret references may read (repeatable)
+    #[arg(long = "secret-ref-env", value_name = "VAR")]
+    pub secret_ref_env: Vec<String>,
+
+    /// Extra domain suffix (e.g. `corp`) whose routes get self-signed
+    /// certificates instead of ACME, like `.local` and `.test` (repeatable)
+    #[arg(long = "private-domain-suffix", value_name = "SUFFIX")]
+    pub private_domain_suffixes: Vec<String>,
+
     /// Prometheus metrics port (default: 9898, set to 0 to disable)
     #[arg(long, default_value_t = 9898)]
     pub metrics_port: u16,
 
+    /// Write an access log line per proxied request (`combined` or `json`)
+    #[arg(long, value_enum)]
+    pub access_log: Option<proxy::AccessLogFormat>,
+
+    /// Access log file to append to (default: stdout)
+    #[arg(long, requires = "access_log")]
+    pub access_log_path: Option<String>,
+
+    /// Seconds an idle client keep-alive connection stays open between requests
+    #[arg(long, default_value_t = 60)]
+    pub client_keepalive_timeout_secs: u64,
+
+    /// Gzip/brotli-compress text-like app responses for clients that accept it
+    #[arg(long)]
+    pub compress_responses: bool,
+
+    /// Also listen for HTTP/HTTPS on IPv6 (`[::]`) next to IPv4
+    #[arg(long)]
+    pub listen_ipv6: bool,
+
     /// Extract a `.tar.zst` archive into a destination directory and exit.
     #[arg(long, hide = true)]
     pub extract_zstd_archive: Option<String>,
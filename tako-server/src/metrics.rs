@@ -7,12 +7,15 @@
 //! deployments are distinguishable in Grafana/Datadog without relying on
 //! scraper-side relabeling.
 
+use prometheus::core::Collector;
 use prometheus::{
     HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, register_histogram_vec,
     register_int_counter_vec, register_int_gauge_vec,
 };
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::sync::{LazyLock, OnceLock};
-use std::time::Instant;
+use std::time::{Instant, SystemTime};
 
 /// Server hostname, set once at startup via `init()`.
 static SERVER_LABEL: OnceLock<String> = OnceLock::new();
@@ -21,6 +24,11 @@ pub(crate) fn server() -> &'static str {
     SERVER_LABEL.get().map(|s| s.as_str()).unwrap_or("unknown")
 }
 
+/// Upper bounds (seconds) shared by the request latency histograms.
+const REQUEST_DURATION_BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
 /// Total HTTP requests handled by the proxy.
 pub static HTTP_REQUESTS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
     register_int_counter_vec!(
@@ -37,9 +45,7 @@ pub static HTTP_REQUEST_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock::new
             "tako_http_request_duration_seconds",
             "HTTP request duration in seconds"
         )
-        .buckets(vec![
-            0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
-        ]),
+        .buckets(REQUEST_DURATION_BUCKETS.to_vec()),
         &["server", "app"]
     )
     .unwrap()
@@ -53,9 +59,7 @@ pub static UPSTREAM_REQUEST_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock:
             "tako_upstream_request_duration_seconds",
             "Upstream request duration in seconds (proxy → origin → response headers)"
         )
-        .buckets(vec![
-            0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
-        ]),
+        .buckets(REQUEST_DURATION_BUCKETS.to_vec()),
         &["server", "app"]
     )
     .unwrap()
@@ -136,6 +140,18 @@ pub static INSTANCE_HEALTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
     .unwrap()
 });
 
+/// Seconds until each held certificate expires (negative once expired).
+pub static CERT_EXPIRY_SECONDS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        Opts::new(
+            "tako_cert_expiry_seconds",
+            "Seconds until the certificate expires"
+        ),
+        &["server", "domain"]
+    )
+    .unwrap()
+});
+
 /// Running instance count per app.
 pub static INSTANCES_RUNNING: LazyLock<IntGaugeVec> = LazyLock::new(|| {
     register_int_gauge_vec!(
@@ -242,6 +258,132 @@ pub fn set_instances_running(app: &str, count: i64) {
         .set(count);
 }
 
+/// Update the expiry gauge for a certificate.
+pub fn set_cert_expiry(domain: &str, expires_at: SystemTime, now: SystemTime) {
+    let seconds = match expires_at.duration_since(now) {
+        Ok(left) => left.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    CERT_EXPIRY_SECONDS
+        .with_label_values(&[server(), domain])
+        .set(seconds);
+}
+
+/// Request totals and latency percentiles for one app, as returned by the
+/// `metrics` command.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppRequestMetrics {
+    pub app: String,
+    pub requests: u64,
+    /// Requests answered with a 5xx status
+    pub errors: u64,
+    pub error_rate: f64,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+}
+
+impl AppRequestMetrics {
+    fn empty(app: &str) -> Self {
+        Self {
+            app: app.to_string(),
+            requests: 0,
+            errors: 0,
+            error_rate: 0.0,
+            p50_ms: None,
+            p95_ms: None,
+        }
+    }
+}
+
+/// Snapshot of request metrics per app, sorted by app name. `app` limits the
+/// result to that one app.
+pub fn app_request_metrics(app: Option<&str>) -> Vec<AppRequestMetrics> {
+    let mut by_app: BTreeMap<String, AppRequestMetrics> = BTreeMap::new();
+
+    for family in HTTP_REQUESTS_TOTAL.collect() {
+        for metric in family.get_metric() {
+            let Some(name) = label(metric, "app").filter(|name| app.is_none_or(|a| a == *name))
+            else {
+                continue;
+            };
+            let stats = by_app
+                .entry(name.to_string())
+                .or_insert_with(|| AppRequestMetrics::empty(name));
+            let count = metric.get_counter().get_value() as u64;
+            stats.requests += count;
+            if label(metric, "status") == Some("5xx") {
+                stats.errors += count;
+            }
+        }
+    }
+
+    for family in HTTP_REQUEST_DURATION_SECONDS.collect() {
+        for metric in family.get_metric() {
+            let Some(name) = label(metric, "app").filter(|name| app.is_none_or(|a| a == *name))
+            else {
+                continue;
+            };
+            let stats = by_app
+                .entry(name.to_string())
+                .or_insert_with(|| AppRequestMetrics::empty(name));
+            let buckets = histogram_buckets(metric.get_histogram());
+            let count = metric.get_histogram().get_sample_count();
+            stats.p50_ms = estimate_quantile(&buckets, count, 0.5).map(|s| s * 1000.0);
+            stats.p95_ms = estimate_quantile(&buckets, count, 0.95).map(|s| s * 1000.0);
+        }
+    }
+
+    by_app
+        .into_values()
+        .map(|mut stats| {
+            if stats.requests > 0 {
+                stats.error_rate = stats.errors as f64 / stats.requests as f64;
+            }
+            stats
+        })
+        .collect()
+}
+
+fn label<'a>(metric: &'a prometheus::proto::Metric, name: &str) -> Option<&'a str> {
+    metric
+        .get_label()
+        .iter()
+        .find(|pair| pair.get_name() == name)
+        .map(|pair| pair.get_value())
+}
+
+/// `(upper_bound, cumulative_count)` pairs of a collected histogram.
+fn histogram_buckets(histogram: &prometheus::proto::Histogram) -> Vec<(f64, u64)> {
+    histogram
+        .get_bucket()
+        .iter()
+        .map(|bucket| (bucket.get_upper_bound(), bucket.get_cumulative_count()))
+        .collect()
+}
+
+/// Estimate the `q` quantile from cumulative bucket counts by interpolating
+/// linearly inside the bucket that holds it, like Prometheus'
+/// `histogram_quantile`. Samples above the last bucket report its bound.
+fn estimate_quantile(buckets: &[(f64, u64)], count: u64, q: f64) -> Option<f64> {
+    if count == 0 {
+        return None;
+    }
+    let rank = q * count as f64;
+    let (mut lower, mut below) = (0.0, 0u64);
+    for &(upper, cumulative) in buckets {
+        if cumulative as f64 >= rank {
+            let in_bucket = (cumulative - below) as f64;
+            if in_bucket == 0.0 {
+                return Some(upper);
+            }
+            return Some(lower + (upper - lower) * (rank - below as f64) / in_bucket);
+        }
+        lower = upper;
+        below = cumulative;
+    }
+    Some(lower)
+}
+
 /// Initialize metrics with the server identity. Call once at startup.
 /// Uses the provided server name (from config file), falling back to hostname.
 pub fn init(server_name: Option<&str>) {
@@ -261,11 +403,13 @@ pub fn init(server_name: Option<&str>) {
     LazyLock::force(&TLS_HANDSHAKE_FAILURES_TOTAL);
     LazyLock::force(&INSTANCE_HEALTH);
     LazyLock::force(&INSTANCES_RUNNING);
+    LazyLock::force(&CERT_EXPIRY_SECONDS);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use prometheus::{Encoder, TextEncoder};
 
     #[test]
     fn test_status_class() {
@@ -321,6 +465,139 @@ mod tests {
         assert_eq!(after, before + 1);
     }
 
+    fn observed_buckets(latencies: &[f64]) -> (Vec<(f64, u64)>, u64) {
+        let histogram = prometheus::Histogram::with_opts(
+            HistogramOpts::new("test_latency", "test").buckets(REQUEST_DURATION_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        for latency in latencies {
+            histogram.observe(*latency);
+        }
+        let families = histogram.collect();
+        let metric = &families[0].get_metric()[0];
+        (
+            histogram_buckets(metric.get_histogram()),
+            metric.get_histogram().get_sample_count(),
+        )
+    }
+
+    #[test]
+    fn test_quantile_estimate_interpolates_within_bucket() {
+        // 90 fast requests (2ms) and 10 slow ones (300ms).
+        let mut latencies = vec![0.002; 90];
+        latencies.extend([0.3; 10]);
+        let (buckets, count) = observed_buckets(&latencies);
+
+        assert_eq!(count, 100);
+        let cumulative = |bound: f64| buckets.iter().find(|(b, _)| *b == bound).unwrap().1;
+        assert_eq!(cumulative(0.001), 0);
+        assert_eq!(cumulative(0.005), 90);
+        assert_eq!(cumulative(0.25), 90);
+        assert_eq!(cumulative(0.5), 100);
+
+        let p50 = estimate_quantile(&buckets, count, 0.5).unwrap();
+        assert!(
+            (p50 - (0.001 + 0.004 * 50.0 / 90.0)).abs() < 1e-9,
+            "p50 = {p50}"
+        );
+        let p95 = estimate_quantile(&buckets, count, 0.95).unwrap();
+        assert!((p95 - 0.375).abs() < 1e-9, "p95 = {p95}");
+    }
+
+    #[test]
+    fn test_quantile_estimate_edges() {
+        assert_eq!(estimate_quantile(&[(0.1, 0)], 0, 0.5), None);
+
+        let (buckets, count) = observed_buckets(&[30.0, 60.0]);
+        assert_eq!(estimate_quantile(&buckets, count, 0.95), Some(10.0));
+    }
+
+    #[test]
+    fn test_app_request_metrics_reports_error_rate_and_percentiles() {
+        init(Some("test-server"));
+        let app = "metrics-snapshot-app";
+        for status in ["2xx", "2xx", "2xx", "5xx"] {
+            HTTP_REQUESTS_TOTAL
+                .with_label_values(&[server(), app, status])
+                .inc();
+        }
+        for latency in [0.02, 0.02, 0.02, 0.02] {
+            HTTP_REQUEST_DURATION_SECONDS
+                .with_label_values(&[server(), app])
+                .observe(latency);
+        }
+
+        let snapshot = app_request_metrics(Some(app));
+        assert_eq!(snapshot.len(), 1);
+        let stats = &snapshot[0];
+        assert_eq!(stats.app, app);
+        assert_eq!(stats.requests, 4);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.error_rate, 0.25);
+        let p50 = stats.p50_ms.unwrap();
+        assert!(p50 > 10.0 && p50 <= 25.0, "p50 = {p50}");
+
+        assert!(
+            app_request_metrics(None)
+                .iter()
+                .any(|stats| stats.app == app)
+        );
+        assert!(app_request_metrics(Some("metrics-missing-app")).is_empty());
+    }
+
+    #[test]
+    fn test_exposition_format_has_labelled_sample_lines() {
+        init(Some("test-server"));
+        let now = SystemTime::now();
+        set_cert_expiry(
+            "expo.example.com",
+            now + std::time::Duration::from_secs(3600),
+            now,
+        );
+        HTTP_REQUESTS_TOTAL
+            .with_label_values(&[server(), "expo-app", "2xx"])
+            .inc_by(3);
+        HTTP_ACTIVE_CONNECTIONS
+            .with_label_values(&[server(), "expo-app"])
+            .set(2);
+        set_instance_health("expo-app", "i1", true);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&prometheus::gather(), &mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        for expected in [
+            "# TYPE tako_http_requests_total counter",
+            "# TYPE tako_http_active_connections gauge",
+            "# TYPE tako_instance_health gauge",
+            "# TYPE tako_cert_expiry_seconds gauge",
+            r#"tako_http_requests_total{app="expo-app",server="test-server",status="2xx"} 3"#,
+            r#"tako_http_active_connections{app="expo-app",server="test-server"} 2"#,
+            r#"tako_instance_health{app="expo-app",instance="i1",server="test-server"} 1"#,
+            r#"tako_cert_expiry_seconds{domain="expo.example.com",server="test-server"} 3600"#,
+        ] {
+            assert!(lines.contains(&expected), "missing line: {expected}");
+        }
+
+        // Every sample line is `name{key="value",...} number`.
+        for line in lines.iter().filter(|line| line.starts_with("tako_")) {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "bad value: {line}");
+            let (_, labels) = series.split_once('{').unwrap();
+            for pair in labels.strip_suffix('}').unwrap().split(',') {
+                let (key, value) = pair.split_once('=').unwrap();
+                assert!(!key.is_empty(), "bad label: {line}");
+                assert!(
+                    value.len() >= 2 && value.starts_with('"') && value.ends_with('"'),
+                    "bad label: {line}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_record_upstream_duration_observes_histogram() {
         init(Some("test-server"));
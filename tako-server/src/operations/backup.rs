@@ -0,0 +1,189 @@
+use crate::instances::AppConfig;
+use crate::release::{
+    release_app_path, validate_app_name, validate_deploy_routes, validate_release_version,
+};
+use crate::socket::Response;
+use crate::state_store::PersistedApp;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tako_core::{ExportedApp, STATE_EXPORT_FORMAT_VERSION, StateExport};
+
+impl crate::ServerState {
+    pub(crate) fn export_state(&self) -> Response {
+        let persisted = match self.state_store.load_apps() {
+            Ok(apps) => apps,
+            Err(e) => return Response::error(format!("Failed to read apps: {}", e)),
+        };
+
+        let mut apps = Vec::with_capacity(persisted.len());
+        for app in persisted {
+            let config = app.config;
+            let secrets = match self.state_store.get_secrets(&config.deployment_id()) {
+                Ok(secrets) => secrets,
+                Err(e) => return Response::error(format!("Failed to read secrets: {}", e)),
+            };
+            let mut secret_names: Vec<String> = secrets.keys().cloned().collect();
+            secret_names.sort();
+            let secret_refs: BTreeMap<String, String> = secrets
+                .into_iter()
+                .filter(|(_, value)| self.runtime.secret_refs.is_reference(value))
+                .collect();
+            apps.push(ExportedApp {
+                name: config.name,
+                environment: config.environment,
+                version: config.version,
+                min_instances: config.min_instances,
+                max_instances: config.max_instances,
+                drain_timeout_secs: config.drain_timeout.as_secs(),
+                routes: app.routes,
+                route_priorities: app.route_priorities.into_iter().collect(),
+                secret_names,
+                secret_refs,
+            });
+        }
+
+        let doc = StateExport {
+            format_version: STATE_EXPORT_FORMAT_VERSION,
+            apps,
+        };
+        match serde_json::to_value(&doc) {
+            Ok(data) => Response::ok(data),
+            Err(e) => Response::error(format!("Failed to encode export: {}", e)),
+        }
+    }
+
+    pub(crate) async fn import_state(&self, doc: StateExport, replace: bool) -> Response {
+        if doc.format_version > STATE_EXPORT_FORMAT_VERSION {
+            return Response::error(format!(
+                "Unsupported export format version {} (this server reads up to {})",
+                doc.format_version, STATE_EXPORT_FORMAT_VERSION
+            ));
+        }
+        tracing::info!(apps = doc.apps.len(), replace, "Importing apps");
+
+        let mut imported = Vec::new();
+        let mut skipped = Vec::new();
+        let mut failed = Vec::new();
+        for app in doc.apps {
+            let app_id = app.deployment_id();
+            if !replace && self.app_manager.get_app(&app_id).is_some() {
+                skipped.push(app_id);
+                continue;
+            }
+            match self.import_app(app, replace).await {
+                Ok(()) => imported.push(app_id),
+                Err(error) => {
+                    tracing::warn!(app = %app_id, "Import failed: {}", error);
+                    failed.push(serde_json::json!({ "app": app_id, "error": error }));
+                }
+            }
+        }
+
+        Response::ok(serde_json::json!({
+            "imported": imported,
+            "skipped": skipped,
+            "failed": failed,
+        }))
+    }
+
+    async fn import_app(&self, app: ExportedApp, replace: bool) -> Result<(), String> {
+        let app_id = app.deployment_id();
+        validate_app_name(&app_id)?;
+        validate_release_version(&app.version)?;
+        validate_deploy_routes(&app.routes)?;
+        let drain_timeout = Duration::from_secs(app.drain_timeout_secs);
+        if drain_timeout > crate::defaults::MAX_DRAIN_TIMEOUT {
+            return Err(format!(
+                "Drain timeout must be at most {}s",
+                crate::defaults::MAX_DRAIN_TIMEOUT.as_secs()
+            ));
+        }
+        if app.min_instances > app.max_instances {
+            return Err("min_instances must not exceed max_instances".to_string());
+        }
+        if let Some(name) = app
+            .secret_refs
+            .iter()
+            .find(|(_, value)| !self.runtime.secret_refs.is_reference(value))
+            .map(|(name, _)| name)
+        {
+            return Err(format!(
+                "Invalid secrets: '{}' is not a reference this server resolves \
+                 (tako-server --secret-ref-dir / --secret-ref-env)",
+                name
+            ));
+        }
+        let secret_refs: HashMap<String, String> = app.secret_refs.into_iter().collect();
+        if let Err(e) = self.runtime.secret_refs.resolve_secrets(&secret_refs) {
+            return Err(format!("Invalid secrets: failed to resolve {}", e));
+        }
+
+        let config = AppConfig {
+            name: app.name,
+            environment: app.environment,
+            version: app.version,
+            min_instances: app.min_instances,
+            max_instances: app.max_instances,
+            drain_timeout,
+            ..Default::default()
+        };
+        let release_path = release_app_path(&self.runtime.data_dir, &config);
+        if !release_path.join("app.json").is_file() {
+            return Err(format!(
+                "Release {} is not on this server (expected {})",
+                config.version,
+                release_path.display()
+            ));
+        }
+
+        let lock = self.get_deploy_lock(&app_id).await;
+        let Ok(_guard) = lock.try_lock() else {
+            return Err(format!("Deploy already in progress for app '{}'", app_id));
+        };
+        // Registration only warns about conflicting routes, which would
+        // leave the app unrouted while its routes still sit in the store.
+        self.routes
+            .read()
+            .await
+            .check_app_routes(&app_id, &app.routes)?;
+
+        if replace && self.app_manager.get_app(&app_id).is_some() {
+            if let Response::Error { message } = self.stop_app(&app_id).await {
+                return Err(message);
+            }
+            self.app_manager.remove_app(&app_id);
+            self.load_balancer.unregister_app(&app_id);
+            self.cold_start.reset(&app_id);
+            self.routes.write().await.remove_app_routes(&app_id);
+        }
+
+        if !secret_refs.is_empty() {
+            let mut secrets = self
+                .state_store
+                .get_secrets(&app_id)
+                .map_err(|e| format!("Failed to read secrets: {}", e))?;
+            secrets.extend(secret_refs);
+            self.state_store
+                .set_secrets(&app_id, &secrets)
+                .map_err(|e| format!("Failed to store secrets: {}", e))?;
+        }
+
+        self.register_persisted_app(PersistedApp {
+            config: config.clone(),
+            routes: app.routes.clone(),
+            route_priorities: app.route_priorities.clone().into_iter().collect(),
+        })
+        .await?;
+
+        // Persist the imported shape (not the standby-capped live config).
+        self.state_store
+            .upsert_app(&config, &app.routes)
+            .map_err(|e| format!("Failed to persist app: {}", e))?;
+        for (route, priority) in &app.route_priorities {
+            self.state_store
+                .set_route_priority(&config.name, &config.environment, route, *priority)
+                .map_err(|e| format!("Failed to persist route priority: {}", e))?;
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,23 @@
 use crate::app_command::env_vars_from_release_dir;
 use crate::instances::{
-    App, AppConfig, RollingUpdateConfig, RollingUpdater, target_new_instances_for_build,
+    App, AppConfig, BuildRouting, RollingUpdateConfig, RollingUpdater,
+    target_new_instances_for_build, wait_for_probed_instances,
 };
 use crate::release::{
     apply_release_runtime_to_config, ensure_app_runtime_data_dirs, inject_app_data_dir_env,
-    requested_deployment_identity, resolve_release_runtime_bin, validate_app_name,
-    validate_deploy_routes, validate_release_path_for_app, validate_release_version,
+    release_app_path, requested_deployment_identity, resolve_release_runtime_bin,
+    validate_app_name, validate_canary, validate_deploy_routes, validate_release_path_for_app,
+    validate_release_version,
 };
-use crate::socket::{AppState, Response};
+use crate::server_state::CompletedDeploy;
+use crate::socket::{AppState, InstanceState, Response};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use tako_core::{DeployHistoryEntry, DeployResult, DeployStrategy};
 
 impl crate::ServerState {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn deploy_app(
         &self,
         app_name: &str,
@@ -19,8 +25,17 @@ impl crate::ServerState {
         path: &str,
         routes: Vec<String>,
         secrets: Option<HashMap<String, String>>,
+        idempotency_key: Option<String>,
+        strategy: DeployStrategy,
+        canary: Option<u8>,
     ) -> Response {
-        tracing::info!(app = app_name, version = version, "Deploying app");
+        tracing::info!(
+            app = app_name,
+            version = version,
+            ?strategy,
+            ?canary,
+            "Deploying app"
+        );
 
         if let Err(msg) = validate_app_name(app_name) {
             return Response::error(msg);
@@ -31,6 +46,12 @@ impl crate::ServerState {
         if let Err(msg) = validate_deploy_routes(&routes) {
             return Response::error(msg);
         }
+        if let Err(msg) = validate_canary(canary, strategy) {
+            return Response::error(msg);
+        }
+        if let Err(msg) = self.routes.read().await.check_app_routes(app_name, &routes) {
+            return Response::error(format!("Deploy rejected: {}", msg));
+        }
         let release_path =
             match validate_release_path_for_app(&self.runtime.data_dir, app_name, path) {
                 Ok(value) => value,
@@ -52,6 +73,101 @@ impl crate::ServerState {
             }
         };
 
+        // Both the lookup and the record below happen while this deploy lock
+        // is held, so two requests with the same key can't both deploy.
+        if let Some(key) = idempotency_key.as_deref()
+            && let Some(previous) = self.completed_deploy_response(app_name, version, key)
+        {
+            tracing::info!(
+                app = app_name,
+                version = version,
+                "Deploy skipped: identical release is already live"
+            );
+            return Response::ok(previous);
+        }
+
+        let response = self
+            .deploy_release_locked(
+                app_name,
+                version,
+                release_path,
+                routes,
+                secrets,
+                strategy,
+                canary,
+            )
+            .await;
+        self.record_completed_deploy(app_name, version, idempotency_key, &response);
+        response
+    }
+
+    /// The stored result of the app's last successful deploy, marked
+    /// `duplicate`, when it used this key and its version is still live.
+    fn completed_deploy_response(
+        &self,
+        app_name: &str,
+        version: &str,
+        idempotency_key: &str,
+    ) -> Option<serde_json::Value> {
+        let app = self.app_manager.get_app(app_name)?;
+        if app.version() != version || !matches!(app.state(), AppState::Running | AppState::Idle) {
+            return None;
+        }
+        let completed = self.completed_deploys.lock();
+        let entry = completed.get(app_name)?;
+        if entry.version != version || entry.idempotency_key != idempotency_key {
+            return None;
+        }
+        let mut response = entry.response.clone();
+        if let Some(fields) = response.as_object_mut() {
+            fields.insert("duplicate".to_string(), serde_json::Value::Bool(true));
+        }
+        Some(response)
+    }
+
+    /// Drop the app's stored deploy result. After its secrets, routes, or
+    /// live build change, replaying that result would describe state that no
+    /// longer exists.
+    pub(crate) fn forget_completed_deploy(&self, app_name: &str) {
+        self.completed_deploys.lock().remove(app_name);
+    }
+
+    pub(crate) fn record_completed_deploy(
+        &self,
+        app_name: &str,
+        version: &str,
+        idempotency_key: Option<String>,
+        response: &Response,
+    ) {
+        let mut completed = self.completed_deploys.lock();
+        match (idempotency_key, response) {
+            (Some(idempotency_key), Response::Ok { data }) => {
+                completed.insert(
+                    app_name.to_string(),
+                    CompletedDeploy {
+                        idempotency_key,
+                        version: version.to_string(),
+                        response: data.clone(),
+                    },
+                );
+            }
+            _ => {
+                completed.remove(app_name);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn deploy_release_locked(
+        &self,
+        app_name: &str,
+        version: &str,
+        release_path: PathBuf,
+        routes: Vec<String>,
+        secrets: Option<HashMap<String, String>>,
+        strategy: DeployStrategy,
+        canary: Option<u8>,
+    ) -> Response {
         let env_vars = match env_vars_from_release_dir(&release_path) {
             Ok(vars) => vars,
             Err(error) => return Response::error(format!("Invalid app release: {}", error)),
@@ -63,6 +179,11 @@ impl crate::ServerState {
             }
         };
 
+        if let Some(new_secrets) = &secrets
+            && let Err(e) = self.runtime.secret_refs.resolve_secrets(new_secrets)
+        {
+            return Response::error(format!("Invalid secrets: failed to resolve {}", e));
+        }
         let secrets = if let Some(new_secrets) = secrets {
             if let Err(e) = self.state_store.set_secrets(app_name, &new_secrets) {
                 return Response::error(format!("Failed to store secrets: {}", e));
@@ -73,7 +194,10 @@ impl crate::ServerState {
         };
         let mut release_env = env_vars.clone();
         inject_app_data_dir_env(&mut release_env, &data_paths);
-        release_env.extend(secrets.clone());
+        match self.runtime.secret_refs.resolve_secrets(&secrets) {
+            Ok(resolved) => release_env.extend(resolved),
+            Err(e) => return Response::error(format!("Invalid secrets: failed to resolve {}", e)),
+        }
 
         let runtime_bin_path =
             match resolve_release_runtime_bin(&release_path, &self.runtime.data_dir).await {
@@ -91,7 +215,10 @@ impl crate::ServerState {
             if let Some(existing) = self.app_manager.get_app(app_name) {
                 let mut config = existing.config.read().clone();
                 config.version = version.to_string();
-                config.secrets = secrets;
+                if config.secrets != secrets {
+                    config.secrets = secrets;
+                    config.secrets_generation += 1;
+                }
                 if let Err(error) = apply_release_runtime_to_config(
                     &mut config,
                     release_path.clone(),
@@ -131,10 +258,13 @@ impl crate::ServerState {
 
         {
             let mut route_table = self.routes.write().await;
-            route_table.set_app_routes(app_name.to_string(), routes.clone());
+            if let Err(msg) = route_table.set_app_routes(app_name.to_string(), routes.clone()) {
+                return Response::error(format!("Deploy rejected: {}", msg));
+            }
         }
 
         app.clear_last_error();
+        app.reset_restart_budget();
 
         for route in &routes {
             let domain = route.split('/').next().unwrap_or(route);
@@ -154,6 +284,7 @@ impl crate::ServerState {
                         app.set_state(AppState::Running);
                         self.cold_start.reset(app_name);
                         self.persist_app_state(app_name).await;
+                        self.record_deploy(app_name, version, DeployResult::Succeeded, 1, 0, None);
                         Response::ok(serde_json::json!({
                             "status": "deployed",
                             "app": app_name,
@@ -166,14 +297,44 @@ impl crate::ServerState {
                     }
                     Err(e) => {
                         app.set_state(AppState::Error);
-                        Response::error(format!("Deploy failed: {}", e))
+                        let error = format!("Deploy failed: {}", e);
+                        self.record_deploy(
+                            app_name,
+                            version,
+                            DeployResult::Failed,
+                            0,
+                            0,
+                            Some(error.clone()),
+                        );
+                        Response::error(error)
                     }
                 }
             } else {
-                match self.app_manager.start_app(app_name).await {
+                // Instances report ready on fd 4 before anything has probed
+                // them; hold the deploy until the health checker agrees.
+                let started = match self.app_manager.start_app(app_name).await {
+                    Ok(()) => wait_for_probed_instances(
+                        &app,
+                        deploy_config.min_instances as usize,
+                        RollingUpdateConfig::for_app(&deploy_config).health_timeout,
+                    )
+                    .await
+                    .map_err(|e| format!("instances did not pass health checks: {e}")),
+                    Err(e) => Err(e.to_string()),
+                };
+                match started {
                     Ok(()) => {
                         app.set_state(AppState::Running);
                         self.persist_app_state(app_name).await;
+                        let started = app.get_instances().len() as u32;
+                        self.record_deploy(
+                            app_name,
+                            version,
+                            DeployResult::Succeeded,
+                            started,
+                            0,
+                            None,
+                        );
                         Response::ok(serde_json::json!({
                             "status": "deployed",
                             "app": app_name,
@@ -183,8 +344,21 @@ impl crate::ServerState {
                         }))
                     }
                     Err(e) => {
+                        for instance in app.get_instances() {
+                            let _ = instance.kill().await;
+                            app.remove_instance(&instance.id);
+                        }
                         app.set_state(AppState::Error);
-                        Response::error(format!("Deploy failed: {}", e))
+                        let error = format!("Deploy failed: {}", e);
+                        self.record_deploy(
+                            app_name,
+                            version,
+                            DeployResult::Failed,
+                            0,
+                            0,
+                            Some(error.clone()),
+                        );
+                        Response::error(error)
                     }
                 }
             }
@@ -192,18 +366,52 @@ impl crate::ServerState {
             let previous_state = app.state();
             app.set_state(AppState::Deploying);
 
-            let rolling_config = RollingUpdateConfig::default();
+            let rolling_config = RollingUpdateConfig::for_app(&deploy_config);
             let updater = RollingUpdater::new(self.app_manager.spawner().clone(), rolling_config);
             let target_new_instances = target_new_instances_for_build(
                 deploy_config.min_instances,
                 app.get_instances().len(),
             );
 
-            match updater
-                .update(&app, deploy_config.clone(), target_new_instances)
-                .await
-            {
+            let update = match (canary, strategy) {
+                (Some(percent), _) => {
+                    updater
+                        .canary_update(
+                            &app,
+                            deploy_config.clone(),
+                            target_new_instances,
+                            percent,
+                            crate::defaults::CANARY_HOLD_PERIOD,
+                        )
+                        .await
+                }
+                (None, DeployStrategy::Rolling) => {
+                    updater
+                        .update(&app, deploy_config.clone(), target_new_instances)
+                        .await
+                }
+                (None, DeployStrategy::BlueGreen) => {
+                    updater
+                        .blue_green_update(&app, deploy_config.clone(), target_new_instances)
+                        .await
+                }
+            };
+
+            match update {
                 Ok(result) => {
+                    let outcome = if result.success {
+                        DeployResult::Succeeded
+                    } else {
+                        DeployResult::RolledBack
+                    };
+                    self.record_deploy(
+                        app_name,
+                        version,
+                        outcome,
+                        result.new_instances,
+                        result.old_instances,
+                        result.error.clone(),
+                    );
                     if result.success {
                         if deploy_config.min_instances == 0 {
                             app.set_state(AppState::Running);
@@ -215,6 +423,8 @@ impl crate::ServerState {
                                 "version": version,
                                 "new_instances": result.new_instances,
                                 "old_instances": result.old_instances,
+                                "strategy": strategy,
+                                "canary": canary,
                                 "rolled_back": false,
                                 "on_demand": true,
                                 "startup_validated": true,
@@ -229,6 +439,8 @@ impl crate::ServerState {
                                 "version": version,
                                 "new_instances": result.new_instances,
                                 "old_instances": result.old_instances,
+                                "strategy": strategy,
+                                "canary": canary,
                                 "rolled_back": false
                             }))
                         }
@@ -247,9 +459,192 @@ impl crate::ServerState {
                 }
                 Err(e) => {
                     app.set_state(AppState::Error);
-                    Response::error(format!("Rolling update failed: {}", e))
+                    let error = match (canary, strategy) {
+                        (Some(_), _) => format!("Canary update failed: {}", e),
+                        (None, DeployStrategy::Rolling) => format!("Rolling update failed: {}", e),
+                        (None, DeployStrategy::BlueGreen) => {
+                            format!("Blue/green update failed: {}", e)
+                        }
+                    };
+                    self.record_deploy(
+                        app_name,
+                        version,
+                        DeployResult::Failed,
+                        0,
+                        0,
+                        Some(error.clone()),
+                    );
+                    Response::error(error)
+                }
+            }
+        }
+    }
+
+    /// Make an already-running build the app's current version and drain the
+    /// instances of every other build, so it receives all traffic.
+    ///
+    /// Promoting the build of a canary still in its hold ends the hold early;
+    /// the canary deploy then switches traffic and drains the stable build.
+    pub(crate) async fn promote_build(&self, app_name: &str, version: &str) -> Response {
+        let app = match self.app_manager.get_app(app_name) {
+            Some(app) => app,
+            None => return Response::error(format!("App not found: {}", app_name)),
+        };
+
+        let canary_live = match app.routing() {
+            BuildRouting::Canary { instances, .. } => instances.iter().any(|id| {
+                app.get_instance(id)
+                    .is_some_and(|i| i.build_version() == version)
+            }),
+            _ => false,
+        };
+        let stable_before = app
+            .get_instances()
+            .iter()
+            .filter(|i| i.build_version() != version)
+            .count() as u32;
+
+        let lock = self.get_deploy_lock(app_name).await;
+        let _guard = if canary_live {
+            // The canary deploy holds the lock until its hold ends.
+            app.promote_canary();
+            lock.lock().await
+        } else {
+            match lock.try_lock() {
+                Ok(guard) => guard,
+                Err(_) => {
+                    return Response::error(format!(
+                        "Deploy already in progress for app '{}'. Please wait and try again.",
+                        app_name
+                    ));
                 }
             }
+        };
+
+        let instances = app.get_instances();
+        if app.version() == version && instances.iter().all(|i| i.build_version() == version) {
+            if canary_live {
+                // The canary deploy finished the switch and recorded it.
+                tracing::info!(app = app_name, version = version, "Promoted canary build");
+                return Response::ok(serde_json::json!({
+                    "status": "promoted",
+                    "app": app_name,
+                    "version": version,
+                    "drained_instances": stable_before
+                }));
+            }
+            return Response::ok(serde_json::json!({
+                "status": "already_current",
+                "app": app_name,
+                "version": version,
+                "message": format!("Build '{}' is already the current version of '{}'", version, app_name),
+            }));
+        }
+
+        let promoted: Vec<String> = instances
+            .iter()
+            .filter(|i| i.build_version() == version && i.state() == InstanceState::Healthy)
+            .map(|i| i.id.clone())
+            .collect();
+        if promoted.is_empty() {
+            return Response::error(format!(
+                "Build '{}' has no healthy instances for app '{}'",
+                version, app_name
+            ));
+        }
+
+        // Future spawns (scaling, restarts) must launch the promoted build,
+        // so the config is rebuilt from its release like a deploy would.
+        let mut config = app.config.read().clone();
+        config.version = version.to_string();
+        let release_path = release_app_path(&self.runtime.data_dir, &config);
+        if !release_path.is_dir() {
+            return Response::error(format!(
+                "Release '{}' not found for app '{}'",
+                version, app_name
+            ));
+        }
+        let data_paths = match ensure_app_runtime_data_dirs(&self.runtime.data_dir, app_name) {
+            Ok(paths) => paths,
+            Err(error) => {
+                return Response::error(format!("Failed to create app data dirs: {error}"));
+            }
+        };
+        let runtime_bin_path =
+            match resolve_release_runtime_bin(&release_path, &self.runtime.data_dir).await {
+                Ok(bin) => bin,
+                Err(error) => return Response::error(format!("Invalid app release: {}", error)),
+            };
+        if let Err(error) =
+            apply_release_runtime_to_config(&mut config, release_path, runtime_bin_path.as_deref())
+        {
+            return Response::error(format!("Invalid app release: {}", error));
+        }
+        inject_app_data_dir_env(&mut config.env_vars, &data_paths);
+        app.update_config(config);
+        self.forget_completed_deploy(app_name);
+        self.persist_app_state(app_name).await;
+
+        tracing::info!(app = app_name, version = version, "Promoting build");
+        let rolling_config = RollingUpdateConfig::for_app(&app.config.read());
+        let updater = RollingUpdater::new(self.app_manager.spawner().clone(), rolling_config);
+        let new_instances = promoted.len() as u32;
+        match updater.switch_to_build(&app, &promoted).await {
+            Ok(drained) => {
+                self.record_deploy(
+                    app_name,
+                    version,
+                    DeployResult::Succeeded,
+                    new_instances,
+                    drained,
+                    None,
+                );
+                Response::ok(serde_json::json!({
+                    "status": "promoted",
+                    "app": app_name,
+                    "version": version,
+                    "drained_instances": drained
+                }))
+            }
+            Err(e) => {
+                let error = format!("Promotion failed while draining: {}", e);
+                self.record_deploy(
+                    app_name,
+                    version,
+                    DeployResult::Failed,
+                    new_instances,
+                    0,
+                    Some(error.clone()),
+                );
+                Response::error(error)
+            }
+        }
+    }
+
+    /// Append a deploy attempt to the app's history; failures are logged only.
+    fn record_deploy(
+        &self,
+        app_name: &str,
+        version: &str,
+        result: DeployResult,
+        new_instances: u32,
+        old_instances: u32,
+        error: Option<String>,
+    ) {
+        let deployed_at_unix_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let entry = DeployHistoryEntry {
+            version: version.to_string(),
+            deployed_at_unix_secs,
+            result,
+            new_instances,
+            old_instances,
+            error,
+        };
+        if let Err(e) = self.state_store.record_deploy(app_name, &entry) {
+            tracing::warn!(app = app_name, "Failed to record deploy history: {}", e);
         }
     }
 
@@ -1,8 +1,26 @@
 use crate::release::{validate_app_name, validate_release_version};
-use crate::socket::{Command, Response};
+use crate::socket::{Command, Reply, Response};
+use std::sync::Arc;
 use tako_core::{HelloResponse, PROTOCOL_VERSION};
 
 impl crate::ServerState {
+    /// Handle a command from the management socket, serving `status_stream`
+    /// as a stream of responses and everything else as a single one.
+    pub async fn handle_socket_command(self: &Arc<Self>, cmd: Command) -> Reply {
+        match cmd {
+            Command::StatusStream { app } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg).into();
+                }
+                if self.app_manager.get_app(&app).is_none() {
+                    return Response::error(format!("App not found: {}", app)).into();
+                }
+                Reply::Stream(self.status_stream(&app))
+            }
+            cmd => self.handle_command(cmd).await.into(),
+        }
+    }
+
     /// Handle a command from the management socket
     pub async fn handle_command(&self, cmd: Command) -> Response {
         match cmd {
@@ -18,6 +36,7 @@ impl crate::ServerState {
                         "server_runtime_info".to_string(),
                         "release_history".to_string(),
                         "rollback".to_string(),
+                        "status_stream".to_string(),
                     ],
                 };
 
@@ -58,6 +77,9 @@ impl crate::ServerState {
                 path,
                 routes,
                 secrets,
+                idempotency_key,
+                strategy,
+                canary,
             } => {
                 if let Err(msg) = validate_app_name(&app) {
                     return Response::error(msg);
@@ -68,8 +90,20 @@ impl crate::ServerState {
                 if let Some(resp) = self.reject_mutating_when_upgrading("deploy").await {
                     return resp;
                 }
-                self.deploy_app(&app, &version, &path, routes, secrets)
-                    .await
+                self.deploy_app(
+                    &app,
+                    &version,
+                    &path,
+                    routes,
+                    secrets,
+                    idempotency_key,
+                    strategy,
+                    canary,
+                )
+                .await
+            }
+            Command::ValidateConfig { path, routes, env } => {
+                self.validate_config(&path, &routes, &env)
             }
             Command::Scale { app, instances } => {
                 if let Err(msg) = validate_app_name(&app) {
@@ -80,6 +114,15 @@ impl crate::ServerState {
                 }
                 self.scale_app(&app, instances).await
             }
+            Command::Restart { app, instance_id } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                if let Some(resp) = self.reject_mutating_when_upgrading("restart").await {
+                    return resp;
+                }
+                self.restart_app(&app, instance_id.as_deref()).await
+            }
             Command::Stop { app } => {
                 if let Err(msg) = validate_app_name(&app) {
                     return Response::error(msg);
@@ -104,6 +147,29 @@ impl crate::ServerState {
                 }
                 self.get_status(&app).await
             }
+            Command::StatusStream { .. } => {
+                Response::error("status_stream is only served over the management socket")
+            }
+            Command::Tail { app, lines } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                self.tail_logs(&app, lines).await
+            }
+            Command::Metrics { app } => {
+                if let Some(app) = &app
+                    && let Err(msg) = validate_app_name(app)
+                {
+                    return Response::error(msg);
+                }
+                self.request_metrics(app.as_deref())
+            }
+            Command::Env { app, reveal } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                self.app_env(&app, reveal)
+            }
             Command::List => self.list_apps().await,
             Command::ListReleases { app } => {
                 if let Err(msg) = validate_app_name(&app) {
@@ -111,18 +177,102 @@ impl crate::ServerState {
                 }
                 self.list_releases(&app).await
             }
+            Command::History { app, limit } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                self.deploy_history(&app, limit).await
+            }
             Command::Routes => self.list_routes().await,
+            Command::TestRoute { host, path } => self.test_route(&host, &path).await,
+            Command::SetRoutePriority {
+                app,
+                route,
+                priority,
+            } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                if let Some(resp) = self
+                    .reject_mutating_when_upgrading("set-route-priority")
+                    .await
+                {
+                    return resp;
+                }
+                self.set_route_priority(&app, &route, priority).await
+            }
             Command::Rollback { app, version } => {
                 if let Err(msg) = validate_app_name(&app) {
                     return Response::error(msg);
                 }
-                if let Err(msg) = validate_release_version(&version) {
+                if let Some(version) = &version
+                    && let Err(msg) = validate_release_version(version)
+                {
                     return Response::error(msg);
                 }
                 if let Some(resp) = self.reject_mutating_when_upgrading("rollback").await {
                     return resp;
                 }
-                self.rollback_app(&app, &version).await
+                self.rollback_app(&app, version.as_deref()).await
+            }
+            Command::Promote { app, version } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                if let Err(msg) = validate_release_version(&version) {
+                    return Response::error(msg);
+                }
+                if let Some(resp) = self.reject_mutating_when_upgrading("promote").await {
+                    return resp;
+                }
+                self.promote_build(&app, &version).await
+            }
+            Command::SetDrainTimeout { app, seconds } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                if let Some(resp) = self
+                    .reject_mutating_when_upgrading("set-drain-timeout")
+                    .await
+                {
+                    return resp;
+                }
+                self.set_drain_timeout(&app, seconds).await
+            }
+            Command::SetMaintenance {
+                app,
+                enabled,
+                body,
+                status,
+            } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                if let Some(resp) = self
+                    .reject_mutating_when_upgrading("set-maintenance")
+                    .await
+                {
+                    return resp;
+                }
+                self.set_maintenance(&app, enabled, body, status).await
+            }
+            Command::Drain { app } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                if let Some(resp) = self.reject_mutating_when_upgrading("drain").await {
+                    return resp;
+                }
+                self.set_drained(&app, true)
+            }
+            Command::Undrain { app } => {
+                if let Err(msg) = validate_app_name(&app) {
+                    return Response::error(msg);
+                }
+                if let Some(resp) = self.reject_mutating_when_upgrading("undrain").await {
+                    return resp;
+                }
+                self.set_drained(&app, false)
             }
             Command::UpdateSecrets { app, secrets } => {
                 if let Err(msg) = validate_app_name(&app) {
@@ -172,6 +322,36 @@ impl crate::ServerState {
                 ),
                 Err(e) => Response::error(format!("Failed to exit upgrading mode: {}", e)),
             },
+            Command::CheckCert {
+                cert_pem,
+                key_pem,
+                domain,
+            } => self.check_certificate(&cert_pem, &key_pem, &domain),
+            Command::ImportCert {
+                domain,
+                cert_pem,
+                key_pem,
+            } => {
+                if let Some(resp) = self.reject_mutating_when_upgrading("import-cert").await {
+                    return resp;
+                }
+                self.import_certificate(&domain, &cert_pem, &key_pem)
+            }
+            Command::Certs => self.list_certificates().await,
+            Command::Snapshot { include_secrets } => self.snapshot(include_secrets).await,
+            Command::Export => self.export_state(),
+            Command::Import { doc, replace } => {
+                if let Some(resp) = self.reject_mutating_when_upgrading("import").await {
+                    return resp;
+                }
+                self.import_state(doc, replace).await
+            }
+            Command::RenewCert { domain } => {
+                if let Some(resp) = self.reject_mutating_when_upgrading("renew-cert").await {
+                    return resp;
+                }
+                self.renew_certificate(&domain).await
+            }
             Command::InjectChallengeToken {
                 token,
                 key_authorization,
@@ -1,4 +1,7 @@
-use crate::instances::{App, RollingUpdateConfig};
+use crate::instances::{
+    App, Instance, MaintenancePage, RollingUpdateConfig, RollingUpdater,
+    target_new_instances_for_build,
+};
 use crate::metrics;
 use crate::release::{app_root, requested_deployment_identity};
 use crate::socket::{AppState, InstanceState, Response};
@@ -15,6 +18,10 @@ impl crate::ServerState {
             .stop(app_name, Duration::from_secs(120))
             .await;
 
+        if let Some(app) = self.app_manager.get_app(app_name) {
+            drain_app_instances(&app).await;
+        }
+
         match self.app_manager.stop_app(app_name).await {
             Ok(()) => Response::ok(serde_json::json!({
                 "status": "stopped",
@@ -45,6 +52,7 @@ impl crate::ServerState {
             next_config.max_instances = next_config.min_instances.max(4);
         }
         app.update_config(next_config.clone());
+        app.reset_restart_budget();
 
         let running_before = app
             .get_instances()
@@ -120,25 +128,101 @@ impl crate::ServerState {
         }))
     }
 
+    /// Restart one instance by id, or roll every instance of the app when
+    /// `instance_id` is `None`.
+    pub(crate) async fn restart_app(&self, app_name: &str, instance_id: Option<&str>) -> Response {
+        let Some(app) = self.app_manager.get_app(app_name) else {
+            return Response::error(format!("App not found: {}", app_name));
+        };
+
+        let Some(instance_id) = instance_id else {
+            tracing::info!(app = app_name, "Restarting all instances");
+            return match self.rolling_restart(&app).await {
+                Ok(restarted) => Response::ok(serde_json::json!({
+                    "status": "restarted",
+                    "app": app_name,
+                    "restarted": restarted
+                })),
+                Err(e) => Response::error(e),
+            };
+        };
+
+        let Some(instance) = app.get_instance(instance_id) else {
+            return Response::error(format!(
+                "Instance '{}' not found in app '{}'",
+                instance_id, app_name
+            ));
+        };
+
+        tracing::info!(
+            app = app_name,
+            instance = instance_id,
+            "Restarting instance"
+        );
+        if let Err(e) = instance.kill().await {
+            tracing::warn!(
+                app = app_name,
+                instance = instance_id,
+                "Failed to kill instance: {}",
+                e
+            );
+        }
+        app.remove_instance(instance_id);
+        metrics::remove_instance_metrics(&app.name(), instance_id);
+
+        match crate::runtime_events::spawn_replacement_instance(self, &app, instance_id, "restart")
+            .await
+        {
+            Ok(new_instance) => {
+                crate::runtime_events::update_instance_count_metric(app_name, &app);
+                Response::ok(serde_json::json!({
+                    "status": "restarted",
+                    "app": app_name,
+                    "instance_id": instance_id,
+                    "new_instance_id": new_instance.id
+                }))
+            }
+            Err(e) => Response::error(format!("Restart failed: {}", e)),
+        }
+    }
+
+    /// Replace every running instance with a fresh one from the current
+    /// config. Returns `false` when the app had no instances to restart.
+    pub(crate) async fn rolling_restart(&self, app: &Arc<App>) -> Result<bool, String> {
+        if app.get_instances().is_empty() {
+            return Ok(false);
+        }
+
+        let config = app.config.read().clone();
+        let previous_state = app.state();
+        app.set_state(AppState::Deploying);
+        let rolling_config = RollingUpdateConfig::for_app(&config);
+        let updater = RollingUpdater::new(self.app_manager.spawner().clone(), rolling_config);
+        let target =
+            target_new_instances_for_build(config.min_instances, app.get_instances().len());
+        match updater.update(app, config, target).await {
+            Ok(result) if result.success => {
+                app.set_state(AppState::Running);
+                Ok(true)
+            }
+            Ok(result) => {
+                app.set_state(previous_state);
+                Err(format!("Rolling restart failed: {:?}", result.error))
+            }
+            Err(e) => {
+                app.set_state(AppState::Error);
+                Err(format!("Rolling restart failed: {}", e))
+            }
+        }
+    }
+
     pub(crate) async fn drain_and_stop_instance(
         &self,
         app: &Arc<App>,
-        instance: &Arc<crate::instances::Instance>,
+        instance: &Arc<Instance>,
     ) -> Result<(), String> {
         instance.set_state(InstanceState::Draining);
-        let deadline = tokio::time::Instant::now() + RollingUpdateConfig::default().drain_timeout;
-        while instance.in_flight() > 0 {
-            if tokio::time::Instant::now() >= deadline {
-                tracing::warn!(
-                    app = %app.name(),
-                    instance = %instance.id,
-                    in_flight = instance.in_flight(),
-                    "Scale drain timeout exceeded, forcing stop"
-                );
-                break;
-            }
-            tokio::time::sleep(Duration::from_millis(50)).await;
-        }
+        wait_for_drain(app, std::slice::from_ref(instance)).await;
 
         instance
             .kill()
@@ -149,6 +233,91 @@ impl crate::ServerState {
         Ok(())
     }
 
+    pub(crate) async fn set_drain_timeout(&self, app_name: &str, seconds: u32) -> Response {
+        let Some(app) = self.app_manager.get_app(app_name) else {
+            return Response::error(format!("App not found: {}", app_name));
+        };
+        let drain_timeout = Duration::from_secs(seconds.into());
+        if drain_timeout > crate::defaults::MAX_DRAIN_TIMEOUT {
+            return Response::error(format!(
+                "Drain timeout must be at most {} seconds",
+                crate::defaults::MAX_DRAIN_TIMEOUT.as_secs()
+            ));
+        }
+
+        let mut config = app.config.read().clone();
+        config.drain_timeout = drain_timeout;
+        app.update_config(config);
+        self.persist_app_state(app_name).await;
+
+        tracing::info!(app = app_name, seconds, "Drain timeout updated");
+        Response::ok(serde_json::json!({
+            "status": "updated",
+            "app": app_name,
+            "drain_timeout_secs": seconds
+        }))
+    }
+
+    pub(crate) async fn set_maintenance(
+        &self,
+        app_name: &str,
+        enabled: bool,
+        body: Option<String>,
+        status: Option<u16>,
+    ) -> Response {
+        let Some(app) = self.app_manager.get_app(app_name) else {
+            return Response::error(format!("App not found: {}", app_name));
+        };
+        let page = if enabled {
+            let status = status.unwrap_or(crate::defaults::DEFAULT_MAINTENANCE_STATUS);
+            if !(200..=599).contains(&status) {
+                return Response::error(format!(
+                    "Maintenance status must be between 200 and 599, got {}",
+                    status
+                ));
+            }
+            let body =
+                body.unwrap_or_else(|| crate::defaults::DEFAULT_MAINTENANCE_BODY.to_string());
+            if body.len() > crate::defaults::MAX_MAINTENANCE_BODY_BYTES {
+                return Response::error(format!(
+                    "Maintenance body must be at most {} bytes",
+                    crate::defaults::MAX_MAINTENANCE_BODY_BYTES
+                ));
+            }
+            Some(MaintenancePage { status, body })
+        } else {
+            None
+        };
+
+        let mut config = app.config.read().clone();
+        config.maintenance = page.clone();
+        app.update_config(config);
+        self.persist_app_state(app_name).await;
+
+        tracing::info!(app = app_name, enabled, "Maintenance page updated");
+        Response::ok(serde_json::json!({
+            "status": "updated",
+            "app": app_name,
+            "maintenance_status": page.map(|page| page.status),
+        }))
+    }
+
+    /// Take an app out of rotation (or put it back) without touching its
+    /// instances. Not persisted: a restarted server routes to every app.
+    pub(crate) fn set_drained(&self, app_name: &str, drained: bool) -> Response {
+        let Some(app) = self.app_manager.get_app(app_name) else {
+            return Response::error(format!("App not found: {}", app_name));
+        };
+        app.set_drained(drained);
+
+        tracing::info!(app = app_name, drained, "App rotation updated");
+        Response::ok(serde_json::json!({
+            "status": if drained { "drained" } else { "undrained" },
+            "app": app_name,
+            "instances": app.get_instances().len(),
+        }))
+    }
+
     pub(crate) async fn delete_app(&self, app_name: &str) -> Response {
         tracing::info!(app = app_name, "Deleting app");
 
@@ -159,13 +328,15 @@ impl crate::ServerState {
             .await;
 
         let mut existed = false;
-        if self.app_manager.get_app(app_name).is_some() {
+        if let Some(app) = self.app_manager.get_app(app_name) {
             existed = true;
+            drain_app_instances(&app).await;
             if let Err(e) = self.app_manager.stop_app(app_name).await {
                 return Response::error(format!("Delete failed: {}", e));
             }
             self.app_manager.remove_app(app_name);
         }
+        self.forget_completed_deploy(app_name);
 
         self.load_balancer.unregister_app(app_name);
         self.cold_start.reset(app_name);
@@ -212,3 +383,28 @@ impl crate::ServerState {
         }))
     }
 }
+
+/// Stop routing new requests to every instance of `app` and wait for their
+/// in-flight requests to finish, up to the app's drain timeout.
+async fn drain_app_instances(app: &App) {
+    let instances = app.get_instances();
+    for instance in &instances {
+        instance.set_state(InstanceState::Draining);
+    }
+    wait_for_drain(app, &instances).await;
+}
+
+async fn wait_for_drain(app: &App, instances: &[Arc<Instance>]) {
+    let deadline = tokio::time::Instant::now() + app.config.read().drain_timeout;
+    while instances.iter().any(|instance| instance.in_flight() > 0) {
+        if tokio::time::Instant::now() >= deadline {
+            tracing::warn!(
+                app = %app.name(),
+                in_flight = instances.iter().map(|i| i.in_flight()).sum::<u64>(),
+                "Drain timeout exceeded, forcing stop"
+            );
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
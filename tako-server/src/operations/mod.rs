@@ -1,7 +1,9 @@
+mod backup;
 mod deploy;
 mod dispatch;
 mod lifecycle;
 mod queries;
 mod releases;
+mod routes;
 mod secrets;
 mod tls;
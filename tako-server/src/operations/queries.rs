@@ -1,5 +1,15 @@
+use crate::instances::read_recent_logs;
 use crate::release::collect_running_build_statuses;
-use crate::socket::{AppStatus, InstanceStatus, Response};
+use crate::socket::{AppStatus, InstanceStatus, ResourceUsage, Response};
+use std::sync::Arc;
+use tako_core::{DeployHistoryResponse, TailResponse};
+use tokio::sync::{broadcast, mpsc};
+
+/// Lines returned by `tail` when the caller does not specify a count.
+const DEFAULT_TAIL_LINES: usize = 200;
+
+/// Entries returned by `history` when the caller does not specify a limit.
+const DEFAULT_HISTORY_LIMIT: u32 = 10;
 
 impl crate::ServerState {
     pub(crate) async fn get_status(&self, app_name: &str) -> Response {
@@ -12,6 +22,11 @@ impl crate::ServerState {
             app.get_instances().iter().map(|i| i.status()).collect();
         let builds = collect_running_build_statuses(&app);
 
+        let secrets_generation = app.config.read().secrets_generation;
+        let secrets_stale = instances
+            .iter()
+            .any(|instance| instance.secrets_generation < secrets_generation);
+        let usage = ResourceUsage::total(&instances);
         let status = AppStatus {
             name: app.name(),
             version: app.version(),
@@ -19,11 +34,91 @@ impl crate::ServerState {
             builds,
             state: app.state(),
             last_error: app.last_error(),
+            secrets_generation,
+            secrets_stale,
+            usage,
         };
 
         Response::ok(status)
     }
 
+    /// Send `app_name`'s status now and again after every instance or health
+    /// event for it, until the receiver is dropped or the app is removed.
+    pub(crate) fn status_stream(self: &Arc<Self>, app_name: &str) -> mpsc::Receiver<Response> {
+        let (tx, rx) = mpsc::channel(16);
+        let mut changes = self.status_changes.subscribe();
+        let state = self.clone();
+        let app_name = app_name.to_string();
+
+        tokio::spawn(async move {
+            loop {
+                let status = state.get_status(&app_name).await;
+                let app_gone = !status.is_ok();
+                if tx.send(status).await.is_err() || app_gone {
+                    return;
+                }
+
+                let keepalive = tokio::time::sleep(crate::defaults::STATUS_STREAM_KEEPALIVE);
+                tokio::pin!(keepalive);
+                loop {
+                    tokio::select! {
+                        _ = tx.closed() => return,
+                        _ = &mut keepalive => break,
+                        change = changes.recv() => match change {
+                            Ok(changed) if changed == app_name => break,
+                            Ok(_) => {}
+                            // Missed some notifications; the fresh status covers them.
+                            Err(broadcast::error::RecvError::Lagged(_)) => break,
+                            Err(broadcast::error::RecvError::Closed) => return,
+                        },
+                    }
+                }
+            }
+        });
+
+        rx
+    }
+
+    pub(crate) async fn tail_logs(&self, app_name: &str, lines: Option<usize>) -> Response {
+        if self.app_manager.get_app(app_name).is_none() {
+            return Response::error(format!("App not found: {}", app_name));
+        }
+
+        let log_dir = self.app_manager.log_dir(app_name);
+        let limit = lines.unwrap_or(DEFAULT_TAIL_LINES);
+        let entries = tokio::task::spawn_blocking(move || read_recent_logs(&log_dir, limit))
+            .await
+            .unwrap_or_default();
+
+        Response::ok(TailResponse {
+            app: app_name.to_string(),
+            entries,
+        })
+    }
+
+    pub(crate) async fn deploy_history(&self, app_name: &str, limit: Option<u32>) -> Response {
+        let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+        match self.state_store.deploy_history(app_name, limit) {
+            Ok(entries) => Response::ok(DeployHistoryResponse {
+                app: app_name.to_string(),
+                entries,
+            }),
+            Err(e) => Response::error(format!("Failed to read deploy history: {}", e)),
+        }
+    }
+
+    pub(crate) fn request_metrics(&self, app_name: Option<&str>) -> Response {
+        if let Some(app_name) = app_name
+            && self.app_manager.get_app(app_name).is_none()
+        {
+            return Response::error(format!("App not found: {}", app_name));
+        }
+
+        Response::ok(serde_json::json!({
+            "apps": crate::metrics::app_request_metrics(app_name)
+        }))
+    }
+
     pub(crate) async fn list_apps(&self) -> Response {
         let apps: Vec<serde_json::Value> = self
             .app_manager
@@ -31,11 +126,14 @@ impl crate::ServerState {
             .iter()
             .filter_map(|name| {
                 self.app_manager.get_app(name).map(|app| {
+                    let instances: Vec<InstanceStatus> =
+                        app.get_instances().iter().map(|i| i.status()).collect();
                     serde_json::json!({
                         "name": app.name(),
                         "version": app.version(),
                         "state": app.state(),
-                        "instances": app.get_instances().len()
+                        "instances": instances.len(),
+                        "usage": ResourceUsage::total(&instances)
                     })
                 })
             })
@@ -57,4 +155,60 @@ impl crate::ServerState {
             .collect();
         Response::ok(serde_json::json!({ "routes": routes }))
     }
+
+    /// Point-in-time view of apps, routes, certificates, and server mode.
+    ///
+    /// Holds the server-mode and route-table read locks while reading the
+    /// state store, so no deploy, route change, or mode switch lands midway.
+    pub(crate) async fn snapshot(&self, include_secrets: bool) -> Response {
+        let server_mode = self.server_mode.read().await;
+        let _route_table = self.routes.read().await;
+
+        let persisted = match self.state_store.load_apps() {
+            Ok(apps) => apps,
+            Err(e) => return Response::error(format!("Failed to read apps: {}", e)),
+        };
+        let upgrade_lock_owner = self.state_store.upgrade_lock_owner().ok().flatten();
+
+        let mut apps = Vec::with_capacity(persisted.len());
+        for app in persisted {
+            let config = &app.config;
+            let mut entry = serde_json::json!({
+                "name": config.name,
+                "environment": config.environment,
+                "version": config.version,
+                "min_instances": config.min_instances,
+                "max_instances": config.max_instances,
+                "routes": app.routes,
+                "route_priorities": app.route_priorities,
+            });
+            if include_secrets {
+                match self.state_store.get_secrets(&config.deployment_id()) {
+                    Ok(secrets) => entry["secrets"] = serde_json::json!(secrets),
+                    Err(e) => return Response::error(format!("Failed to read secrets: {}", e)),
+                }
+            }
+            apps.push(entry);
+        }
+
+        let mut certs = self.cert_manager.list_certs();
+        certs.sort_by(|a, b| a.domain.cmp(&b.domain));
+        let certs: Vec<serde_json::Value> = certs
+            .iter()
+            .map(|cert| {
+                serde_json::json!({
+                    "domain": cert.domain,
+                    "is_self_signed": cert.is_self_signed,
+                    "expires_in_days": cert.days_until_expiry(),
+                })
+            })
+            .collect();
+
+        Response::ok(serde_json::json!({
+            "server_mode": *server_mode,
+            "upgrade_lock_owner": upgrade_lock_owner,
+            "apps": apps,
+            "certs": certs,
+        }))
+    }
 }
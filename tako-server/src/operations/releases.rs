@@ -1,11 +1,14 @@
-use crate::app_command::env_vars_from_release_dir;
+use crate::app_command::{command_for_release_dir, env_vars_from_release_dir};
 use crate::release::{
     current_release_version, directory_modified_unix_secs, ensure_app_runtime_data_dirs,
     inject_app_data_dir_env, prepare_release_runtime, read_release_manifest_metadata,
-    validate_release_path_for_app,
+    resolve_release_runtime, validate_deploy_env, validate_deploy_routes,
+    validate_release_path_for_app, validate_release_runtime_manifest,
 };
 use crate::socket::Response;
-use tako_core::{ListReleasesResponse, ReleaseInfo};
+use std::collections::HashMap;
+use std::path::Path;
+use tako_core::{DeployStrategy, ListReleasesResponse, ReleaseInfo};
 
 impl crate::ServerState {
     pub(crate) async fn prepare_release(&self, app_name: &str, path: &str) -> Response {
@@ -20,7 +23,14 @@ impl crate::ServerState {
             Err(error) => return Response::error(format!("Invalid app release: {}", error)),
         };
 
-        let secrets = self.state_store.get_secrets(app_name).unwrap_or_default();
+        let secrets = match self
+            .runtime
+            .secret_refs
+            .resolve_secrets(&self.state_store.get_secrets(app_name).unwrap_or_default())
+        {
+            Ok(secrets) => secrets,
+            Err(e) => return Response::error(format!("Invalid secrets: failed to resolve {}", e)),
+        };
         let mut release_env = env_vars;
         release_env.extend(secrets);
         let data_paths = match ensure_app_runtime_data_dirs(&self.runtime.data_dir, app_name) {
@@ -35,6 +45,48 @@ impl crate::ServerState {
         }
     }
 
+    /// Dry-run a deploy: validate routes, env, and the release manifest and
+    /// resolve the launch command. Takes no deploy lock and never touches
+    /// the state store.
+    pub(crate) fn validate_config(
+        &self,
+        path: &str,
+        routes: &[String],
+        env: &HashMap<String, String>,
+    ) -> Response {
+        if let Err(msg) = validate_deploy_routes(routes) {
+            return Response::error(msg);
+        }
+        if let Err(msg) = validate_deploy_env(env) {
+            return Response::error(msg);
+        }
+        let release_path = Path::new(path);
+        if !release_path.is_dir() {
+            return Response::error(format!(
+                "Invalid release path: '{}' must be an existing directory",
+                path
+            ));
+        }
+
+        let runtime = match resolve_release_runtime(release_path) {
+            Ok(runtime) => runtime,
+            Err(error) => return Response::error(format!("Invalid app release: {}", error)),
+        };
+        if let Err(error) = validate_release_runtime_manifest(release_path) {
+            return Response::error(format!("Invalid app release: {}", error));
+        }
+        let command = match command_for_release_dir(release_path) {
+            Ok(command) => command,
+            Err(error) => return Response::error(format!("Invalid app release: {}", error)),
+        };
+
+        Response::ok(serde_json::json!({
+            "status": "valid",
+            "runtime": runtime,
+            "command": command,
+        }))
+    }
+
     pub(crate) async fn run_release(
         &self,
         app_name: &str,
@@ -72,7 +124,14 @@ impl crate::ServerState {
             Ok(vars) => vars,
             Err(error) => return Response::error(format!("Invalid app release: {}", error)),
         };
-        let secrets = self.state_store.get_secrets(app_name).unwrap_or_default();
+        let secrets = match self
+            .runtime
+            .secret_refs
+            .resolve_secrets(&self.state_store.get_secrets(app_name).unwrap_or_default())
+        {
+            Ok(secrets) => secrets,
+            Err(e) => return Response::error(format!("Invalid secrets: failed to resolve {}", e)),
+        };
         let data_paths = match ensure_app_runtime_data_dirs(&self.runtime.data_dir, app_name) {
             Ok(paths) => paths,
             Err(error) => {
@@ -173,13 +232,21 @@ impl crate::ServerState {
         })
     }
 
-    pub(crate) async fn rollback_app(&self, app_name: &str, version: &str) -> Response {
-        let _app = match self.app_manager.get_app(app_name) {
+    pub(crate) async fn rollback_app(&self, app_name: &str, version: Option<&str>) -> Response {
+        let app = match self.app_manager.get_app(app_name) {
             Some(app) => app,
             None => return Response::error(format!("App not found: {}", app_name)),
         };
 
         let app_root = self.runtime.data_dir.join("apps").join(app_name);
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => match self.previous_release_version(&app, &app_root) {
+                Ok(version) => version,
+                Err(msg) => return Response::error(msg),
+            },
+        };
+        let version = version.as_str();
         let target_path = app_root.join("releases").join(version);
 
         if !target_path.is_dir() {
@@ -206,9 +273,36 @@ impl crate::ServerState {
             &target_path.to_string_lossy(),
             routes,
             None,
+            None,
+            DeployStrategy::Rolling,
+            None,
         )
         .await
     }
+
+    /// Most recently replaced version whose release directory still exists.
+    pub(crate) fn previous_release_version(
+        &self,
+        app: &crate::instances::App,
+        app_root: &Path,
+    ) -> Result<String, String> {
+        let (name, environment, current) = {
+            let config = app.config.read();
+            (
+                config.name.clone(),
+                config.environment.clone(),
+                config.version.clone(),
+            )
+        };
+        let history = self
+            .state_store
+            .version_history(&name, &environment)
+            .map_err(|e| format!("Failed to read release history: {}", e))?;
+        history
+            .into_iter()
+            .find(|version| *version != current && app_root.join("releases").join(version).is_dir())
+            .ok_or_else(|| format!("No previous release recorded for app '{}'", app.name()))
+    }
 }
 
 fn tail_string(s: &str, max_bytes: usize) -> String {
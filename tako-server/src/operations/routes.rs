@@ -0,0 +1,69 @@
+use crate::socket::Response;
+
+impl crate::ServerState {
+    pub(crate) async fn set_route_priority(
+        &self,
+        app_name: &str,
+        route: &str,
+        priority: i32,
+    ) -> Response {
+        let Some(app) = self.app_manager.get_app(app_name) else {
+            return Response::error(format!("App not found: {}", app_name));
+        };
+
+        {
+            let mut route_table = self.routes.write().await;
+            if !route_table
+                .routes_for_app(app_name)
+                .iter()
+                .any(|r| r == route)
+            {
+                return Response::error(format!(
+                    "Route '{}' is not configured for app {}",
+                    route, app_name
+                ));
+            }
+            route_table.set_route_priority(app_name, route, priority);
+        }
+        self.forget_completed_deploy(app_name);
+
+        let (name, environment) = {
+            let config = app.config.read();
+            (config.name.clone(), config.environment.clone())
+        };
+        if let Err(e) = self
+            .state_store
+            .set_route_priority(&name, &environment, route, priority)
+        {
+            tracing::warn!(app = app_name, "Failed to persist route priority: {}", e);
+        }
+
+        tracing::info!(app = app_name, route, priority, "Route priority updated");
+        Response::ok(serde_json::json!({
+            "app": app_name,
+            "route": route,
+            "priority": priority
+        }))
+    }
+
+    /// Resolve a request to the route that would serve it, the way the proxy
+    /// does: port stripped from the host, highest priority then most specific.
+    pub(crate) async fn test_route(&self, host: &str, path: &str) -> Response {
+        let hostname = host.split(':').next().unwrap_or(host);
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{path}")
+        };
+
+        let route_table = self.routes.read().await;
+        let matched = route_table.matching_route(hostname, &path);
+        Response::ok(serde_json::json!({
+            "host": hostname,
+            "path": path,
+            "app": matched.map(|route| &route.app),
+            "pattern": matched.map(|route| &route.pattern),
+            "priority": matched.map(|route| route.priority),
+        }))
+    }
+}
@@ -1,7 +1,9 @@
-use crate::instances::{RollingUpdateConfig, RollingUpdater, target_new_instances_for_build};
 use crate::release::{release_app_path, resolve_release_runtime_bin};
-use crate::socket::{AppState, Response};
-use std::collections::HashMap;
+use crate::socket::Response;
+use std::collections::{BTreeMap, HashMap};
+
+/// Stand-in for secret values the `env` command doesn't reveal.
+const REDACTED: &str = "***";
 
 impl crate::ServerState {
     pub(crate) async fn update_secrets(
@@ -11,13 +13,19 @@ impl crate::ServerState {
     ) -> Response {
         tracing::info!(app = app_name, "Updating secrets");
 
+        if let Err(e) = self.runtime.secret_refs.resolve_secrets(&new_secrets) {
+            return Response::error(format!("Invalid secrets: failed to resolve {}", e));
+        }
+
         if let Err(e) = self.state_store.set_secrets(app_name, &new_secrets) {
             return Response::error(format!("Failed to store secrets: {}", e));
         }
+        self.forget_completed_deploy(app_name);
 
         if let Some(app) = self.app_manager.get_app(app_name) {
             let mut config = app.config.read().clone();
             config.secrets = new_secrets;
+            config.secrets_generation += 1;
             app.update_config(config.clone());
             self.persist_app_state(app_name).await;
 
@@ -30,36 +38,14 @@ impl crate::ServerState {
             self.sync_app_workflows(app_name, &release_path, runtime_bin_path.as_deref())
                 .await;
 
-            if !app.get_instances().is_empty() {
-                let previous_state = app.state();
-                app.set_state(AppState::Deploying);
-                let rolling_config = RollingUpdateConfig::default();
-                let updater =
-                    RollingUpdater::new(self.app_manager.spawner().clone(), rolling_config);
-                let target =
-                    target_new_instances_for_build(config.min_instances, app.get_instances().len());
-                match updater.update(&app, config, target).await {
-                    Ok(result) if result.success => {
-                        app.set_state(AppState::Running);
-                        return Response::ok(serde_json::json!({
-                            "status": "updated",
-                            "app": app_name,
-                            "restarted": true
-                        }));
-                    }
-                    Ok(result) => {
-                        app.set_state(previous_state);
-                        return Response::error(format!(
-                            "Rolling restart failed: {:?}",
-                            result.error
-                        ));
-                    }
-                    Err(e) => {
-                        app.set_state(AppState::Error);
-                        return Response::error(format!("Rolling restart failed: {}", e));
-                    }
-                }
-            }
+            return match self.rolling_restart(&app).await {
+                Ok(restarted) => Response::ok(serde_json::json!({
+                    "status": "updated",
+                    "app": app_name,
+                    "restarted": restarted
+                })),
+                Err(e) => Response::error(e),
+            };
         }
 
         Response::ok(serde_json::json!({
@@ -68,4 +54,33 @@ impl crate::ServerState {
             "restarted": false
         }))
     }
+
+    /// The app's vars merged with its secrets, secrets winning on conflict.
+    /// Secret values read `***` unless revealing is requested and allowed.
+    pub(crate) fn app_env(&self, app_name: &str, reveal: bool) -> Response {
+        if reveal && !self.runtime.allow_secret_reveal {
+            return Response::error(
+                "Revealing secrets is disabled; start tako-server with --allow-secret-reveal",
+            );
+        }
+        let Some(app) = self.app_manager.get_app(app_name) else {
+            return Response::error(format!("App not found: {}", app_name));
+        };
+
+        let config = app.config.read();
+        let mut env: BTreeMap<&str, &str> = config
+            .env_vars
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        for (key, value) in &config.secrets {
+            env.insert(key, if reveal { value } else { REDACTED });
+        }
+
+        Response::ok(serde_json::json!({
+            "app": app_name,
+            "env": env,
+            "revealed": reveal
+        }))
+    }
 }
@@ -1,6 +1,7 @@
 use crate::release::should_use_self_signed_route_cert;
+use crate::runtime_events::handle_cert_event;
 use crate::socket::Response;
-use crate::tls::CertInfo;
+use crate::tls::{CertEvent, CertInfo, check_cert_pair};
 
 impl crate::ServerState {
     pub async fn request_certificate(&self, domain: &str) -> Response {
@@ -21,6 +22,101 @@ impl crate::ServerState {
         }
     }
 
+    /// Renew one held certificate immediately, bypassing the expiry check.
+    pub(crate) async fn renew_certificate(&self, domain: &str) -> Response {
+        let acme_guard = self.acme_client.read().await;
+        let acme = match acme_guard.as_ref() {
+            Some(acme) => acme,
+            None => return Response::error("ACME is disabled".to_string()),
+        };
+        match self.cert_manager.get_cert(domain) {
+            Some(cert) if cert.is_self_signed => {
+                return Response::error(format!(
+                    "Certificate for {} is self-signed and not renewed through ACME",
+                    domain
+                ));
+            }
+            Some(_) => {}
+            None => return Response::error(format!("No certificate for domain: {}", domain)),
+        }
+
+        match acme.renew_certificate(domain).await {
+            Ok(cert) => {
+                handle_cert_event(
+                    self,
+                    CertEvent::Renewed {
+                        domain: domain.to_string(),
+                    },
+                );
+                Response::ok(serde_json::json!({
+                    "status": "renewed",
+                    "domain": domain,
+                    "expires_in_days": cert.days_until_expiry(),
+                }))
+            }
+            Err(e) => {
+                handle_cert_event(
+                    self,
+                    CertEvent::RenewalFailed {
+                        domain: domain.to_string(),
+                        error: e.to_string(),
+                    },
+                );
+                Response::error(format!("Certificate renewal failed: {}", e))
+            }
+        }
+    }
+
+    pub(crate) fn check_certificate(
+        &self,
+        cert_pem: &str,
+        key_pem: &str,
+        domain: &str,
+    ) -> Response {
+        match check_cert_pair(cert_pem, key_pem, domain) {
+            Ok(check) => Response::ok(check),
+            Err(e) => Response::error(format!("Certificate check failed: {}", e)),
+        }
+    }
+
+    pub(crate) fn import_certificate(
+        &self,
+        domain: &str,
+        cert_pem: &str,
+        key_pem: &str,
+    ) -> Response {
+        match self.cert_manager.import_cert(domain, cert_pem, key_pem) {
+            Ok(cert) => {
+                tracing::info!(domain = %cert.domain, "Imported certificate");
+                Response::ok(serde_json::json!({
+                    "status": "imported",
+                    "domain": cert.domain,
+                    "expires_in_days": cert.days_until_expiry(),
+                    "cert_path": cert.cert_path.to_string_lossy(),
+                }))
+            }
+            Err(e) => Response::error(format!("Certificate import failed: {}", e)),
+        }
+    }
+
+    pub(crate) async fn list_certificates(&self) -> Response {
+        let acme_enabled = self.acme_client.read().await.is_some();
+        let mut certs = self.cert_manager.list_certs();
+        certs.sort_by(|a, b| a.domain.cmp(&b.domain));
+        let certs: Vec<serde_json::Value> = certs
+            .iter()
+            .map(|cert| {
+                serde_json::json!({
+                    "domain": cert.domain,
+                    "is_self_signed": cert.is_self_signed,
+                    "expires_in_days": cert.days_until_expiry(),
+                    "acme": acme_enabled && !cert.is_self_signed,
+                })
+            })
+            .collect();
+        Response::ok(serde_json::json!({ "certs": certs }))
+    }
+
     pub(crate) async fn ensure_route_certificate(
         &self,
         app_name: &str,
@@ -31,7 +127,7 @@ impl crate::ServerState {
             return Some(existing);
         }
 
-        if should_use_self_signed_route_cert(domain) {
+        if should_use_self_signed_route_cert(domain, &self.runtime.private_domain_suffixes) {
             match self.cert_manager.get_or_create_self_signed_cert(domain) {
                 Ok(cert) => {
                     tracing::info!(
@@ -0,0 +1,134 @@
+use parking_lot::Mutex;
+use std::fs::OpenOptions;
+use std::io::{LineWriter, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::time::Duration;
+
+/// Per-app access log sampler: logs 1 in N requests, always logs 5xx.
+pub(super) struct AccessLogSampler {
+    counters: dashmap::DashMap<String, AtomicU64>,
+}
+
+impl AccessLogSampler {
+    pub(super) fn new() -> Self {
+        Self {
+            counters: dashmap::DashMap::new(),
+        }
+    }
+
+    pub(super) fn should_log(&self, app_name: &str, status: u16, sample_rate: u32) -> bool {
+        if status >= 500 || sample_rate <= 1 {
+            return true;
+        }
+        let seen = match self.counters.get(app_name) {
+            Some(counter) => counter.value().fetch_add(1, AtomicOrdering::Relaxed),
+            None => self
+                .counters
+                .entry(app_name.to_string())
+                .or_insert_with(|| AtomicU64::new(0))
+                .value()
+                .fetch_add(1, AtomicOrdering::Relaxed),
+        };
+        seen.is_multiple_of(u64::from(sample_rate))
+    }
+
+    pub(super) fn remove_app(&self, app_name: &str) {
+        self.counters.remove(app_name);
+    }
+}
+
+/// Line format for the proxy access log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AccessLogFormat {
+    /// Apache combined log format, followed by app, instance, and latency
+    Combined,
+    /// One JSON object per line
+    Json,
+}
+
+/// One completed request, as written to the access log
+pub(super) struct AccessLogRecord<'a> {
+    pub(super) time: chrono::DateTime<chrono::Utc>,
+    pub(super) client_ip: Option<IpAddr>,
+    pub(super) method: &'a str,
+    pub(super) uri: &'a str,
+    pub(super) version: &'a str,
+    pub(super) host: &'a str,
+    pub(super) status: u16,
+    pub(super) bytes_sent: usize,
+    pub(super) referer: Option<&'a str>,
+    pub(super) user_agent: Option<&'a str>,
+    pub(super) app: Option<&'a str>,
+    pub(super) instance: Option<&'a str>,
+    pub(super) latency: Duration,
+}
+
+impl AccessLogRecord<'_> {
+    pub(super) fn format(&self, format: AccessLogFormat) -> String {
+        match format {
+            AccessLogFormat::Combined => format!(
+                "{} - - [{}] \"{} {} {}\" {} {} \"{}\" \"{}\" app={} instance={} latency_ms={:.3}",
+                self.client_ip
+                    .map_or_else(|| "-".to_string(), |ip| ip.to_string()),
+                self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+                self.method,
+                self.uri,
+                self.version,
+                self.status,
+                self.bytes_sent,
+                self.referer.unwrap_or("-"),
+                self.user_agent.unwrap_or("-"),
+                self.app.unwrap_or("-"),
+                self.instance.unwrap_or("-"),
+                self.latency.as_secs_f64() * 1000.0,
+            ),
+            AccessLogFormat::Json => serde_json::json!({
+                "time": self.time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                "client_ip": self.client_ip,
+                "method": self.method,
+                "uri": self.uri,
+                "version": self.version,
+                "host": self.host,
+                "status": self.status,
+                "bytes_sent": self.bytes_sent,
+                "referer": self.referer,
+                "user_agent": self.user_agent,
+                "app": self.app,
+                "instance": self.instance,
+                "latency_ms": self.latency.as_secs_f64() * 1000.0,
+            })
+            .to_string(),
+        }
+    }
+}
+
+/// Writes access log lines to a file (appending) or stdout.
+pub(super) struct AccessLogWriter {
+    format: AccessLogFormat,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl AccessLogWriter {
+    pub(super) fn open(format: AccessLogFormat, path: Option<&Path>) -> std::io::Result<Self> {
+        let out: Box<dyn Write + Send> = match path {
+            Some(path) => Box::new(LineWriter::new(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            )),
+            None => Box::new(std::io::stdout()),
+        };
+        Ok(Self {
+            format,
+            out: Mutex::new(out),
+        })
+    }
+
+    pub(super) fn write(&self, record: &AccessLogRecord<'_>) {
+        let line = record.format(self.format);
+        let mut out = self.out.lock();
+        if let Err(e) = writeln!(out, "{}", line) {
+            tracing::warn!("Failed to write access log: {}", e);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use bytes::{Bytes, BytesMut};
+use pingora_http::ResponseHeader;
+
+/// Holds an upstream response body until the app finishes sending it, so
+/// the instance is freed even when the client reads slowly. Bodies larger
+/// than `limit` flush what was held and stream the rest.
+pub(super) struct ResponseBuffer {
+    limit: usize,
+    held: BytesMut,
+    streaming: bool,
+}
+
+impl ResponseBuffer {
+    /// Buffer for a response with these headers, or `None` when the response
+    /// must stream (protocol upgrades, server-sent events) or buffering is off.
+    pub(super) fn for_response(header: &ResponseHeader, limit: u32) -> Option<Self> {
+        if limit == 0 || header.status.as_u16() == 101 {
+            return None;
+        }
+        let is_event_stream = header
+            .headers
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if is_event_stream {
+            return None;
+        }
+        Some(Self {
+            limit: limit as usize,
+            held: BytesMut::new(),
+            streaming: false,
+        })
+    }
+
+    /// Take `body` into the buffer, handing the whole body back at end of
+    /// stream. Once the held bytes would pass the limit, everything held so
+    /// far is released with the current chunk and later chunks pass through.
+    pub(super) fn filter(&mut self, body: &mut Option<Bytes>, end_of_stream: bool) {
+        if self.streaming {
+            return;
+        }
+        if let Some(chunk) = body.take() {
+            self.held.extend_from_slice(&chunk);
+            if self.held.len() > self.limit {
+                self.streaming = true;
+                *body = Some(self.held.split().freeze());
+                return;
+            }
+        }
+        if end_of_stream && !self.held.is_empty() {
+            *body = Some(self.held.split().freeze());
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use super::config::CompressionConfig;
+use pingora_core::modules::http::HttpModules;
+use pingora_core::modules::http::compression::{ResponseCompression, ResponseCompressionBuilder};
+use pingora_core::protocols::http::compression::Algorithm;
+use pingora_http::ResponseHeader;
+use pingora_proxy::Session;
+
+/// Media types worth compressing. `+json` and `+xml` suffixes also match;
+/// images, video, fonts, and archives are already compressed.
+const COMPRESSIBLE_TYPES: &[&str] = &[
+    "application/javascript",
+    "application/json",
+    "application/wasm",
+    "application/xml",
+];
+
+/// Register the compression module. It starts enabled so the client's
+/// `Accept-Encoding` is parsed; each request then turns it off until
+/// `enable_for_response` decides the upstream response should be compressed.
+pub(super) fn init_modules(modules: &mut HttpModules, config: Option<&CompressionConfig>) {
+    modules.add_module(ResponseCompressionBuilder::enable(
+        config.map_or(0, |config| config.level),
+    ));
+}
+
+/// Turn compression off for responses Tako writes itself (errors, static
+/// assets, maintenance pages).
+pub(super) fn disable(session: &mut Session) {
+    if let Some(compression) = session
+        .downstream_modules_ctx
+        .get_mut::<ResponseCompression>()
+    {
+        compression.adjust_level(0);
+    }
+}
+
+/// Compress this upstream response with gzip or brotli when it qualifies.
+pub(super) fn enable_for_response(
+    session: &mut Session,
+    response: &ResponseHeader,
+    config: &CompressionConfig,
+) {
+    if !should_compress(response, config.min_size_bytes) {
+        return;
+    }
+    if let Some(compression) = session
+        .downstream_modules_ctx
+        .get_mut::<ResponseCompression>()
+    {
+        compression.adjust_algorithm_level(Algorithm::Gzip, config.level);
+        compression.adjust_algorithm_level(Algorithm::Brotli, config.level);
+    }
+}
+
+/// Whether a response should be compressed on the way to the client: it has
+/// a body, is not already encoded, has a compressible content type, and is
+/// not known to be smaller than `min_size_bytes`. Responses without a
+/// `Content-Length` are compressed.
+pub(super) fn should_compress(response: &ResponseHeader, min_size_bytes: u64) -> bool {
+    let status = response.status.as_u16();
+    if status < 200 || matches!(status, 204 | 206 | 304) {
+        return false;
+    }
+
+    let header = |name: &str| {
+        response
+            .headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    if header("content-encoding")
+        .is_some_and(|value| !value.trim().eq_ignore_ascii_case("identity"))
+    {
+        return false;
+    }
+    if header("cache-control").is_some_and(|value| {
+        value
+            .split(',')
+            .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"))
+    }) {
+        return false;
+    }
+    if !header("content-type").is_some_and(is_compressible_type) {
+        return false;
+    }
+    match header("content-length").map(|value| value.trim().parse::<u64>()) {
+        Some(Ok(length)) => length >= min_size_bytes,
+        Some(Err(_)) => false,
+        None => true,
+    }
+}
+
+fn is_compressible_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    // Event streams are flushed message by message; compressing would hold them.
+    if essence == "text/event-stream" {
+        return false;
+    }
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || COMPRESSIBLE_TYPES.contains(&essence.as_str())
+}
@@ -1,6 +1,8 @@
+use super::AccessLogFormat;
 use pingora_cache::MemCache;
 use pingora_cache::eviction::simple_lru;
 use pingora_cache::lock::{CacheKeyLockImpl, CacheLock};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -14,9 +16,30 @@ pub struct ProxyConfig {
     pub cert_dir: PathBuf,
     pub redirect_http_to_https: bool,
     pub response_cache: Option<ResponseCacheConfig>,
-    pub metrics_port: Option<u16>,
+    /// Admin listener serving Prometheus metrics. Kept off the public
+    /// ports; `None` disables it.
+    pub metrics_listen: Option<SocketAddr>,
+    /// Write one access log line per proxied request in this format.
+    pub access_log: Option<AccessLogFormat>,
+    /// Access log file, appended to. `None` writes to stdout.
+    pub access_log_path: Option<PathBuf>,
+    /// How long an idle HTTP/1.1 client connection is kept open between
+    /// requests. In-flight requests are not affected.
+    pub client_keepalive_timeout: Duration,
+    /// Compress proxied app responses for clients that accept gzip or
+    /// brotli. `None` leaves responses as the app sent them.
+    pub compression: Option<CompressionConfig>,
+    /// Also accept HTTP/HTTPS on IPv6 (`[::]`). Off by default; IPv4
+    /// (`0.0.0.0`) is always bound.
+    pub listen_ipv6: bool,
 }
 
+/// Loopback-only metrics listener used unless configured otherwise.
+const DEFAULT_METRICS_LISTEN: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 9898);
+
+/// Default idle keep-alive timeout for client connections.
+const DEFAULT_CLIENT_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Upstream response cache configuration
 #[derive(Debug, Clone)]
 pub struct ResponseCacheConfig {
@@ -25,6 +48,24 @@ pub struct ResponseCacheConfig {
     pub lock_timeout: Duration,
 }
 
+/// Response compression configuration
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    /// Applied to both gzip (1-9) and brotli (1-11)
+    pub level: u32,
+    /// Responses with a smaller `Content-Length` are sent uncompressed
+    pub min_size_bytes: u64,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            level: 5,
+            min_size_bytes: 1024,
+        }
+    }
+}
+
 impl Default for ResponseCacheConfig {
     fn default() -> Self {
         Self {
@@ -68,7 +109,12 @@ impl Default for ProxyConfig {
             cert_dir: PathBuf::from("/opt/tako/certs"),
             redirect_http_to_https: true,
             response_cache: Some(ResponseCacheConfig::default()),
-            metrics_port: Some(9898),
+            metrics_listen: Some(DEFAULT_METRICS_LISTEN),
+            access_log: None,
+            access_log_path: None,
+            client_keepalive_timeout: DEFAULT_CLIENT_KEEPALIVE_TIMEOUT,
+            compression: None,
+            listen_ipv6: false,
         }
     }
 }
@@ -83,7 +129,12 @@ impl ProxyConfig {
             cert_dir: PathBuf::from("./data/certs"),
             redirect_http_to_https: true,
             response_cache: Some(ResponseCacheConfig::default()),
-            metrics_port: Some(9898),
+            metrics_listen: Some(DEFAULT_METRICS_LISTEN),
+            access_log: None,
+            access_log_path: None,
+            client_keepalive_timeout: DEFAULT_CLIENT_KEEPALIVE_TIMEOUT,
+            compression: None,
+            listen_ipv6: false,
         }
     }
 }
@@ -1,9 +1,50 @@
+use pingora_core::protocols::http::ServerSession;
+use pingora_http::RequestHeader;
 use std::net::IpAddr;
 use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
+use std::time::Duration;
 
 pub(super) const MAX_REQUESTS_PER_IP: u32 = 2048;
 pub(crate) const MAX_REQUEST_BODY_BYTES: u64 = 128 * 1024 * 1024;
 
+/// Body limit for an app: its `max_body_bytes` when set (0 = unset), never
+/// above the server-wide `MAX_REQUEST_BODY_BYTES`.
+pub(super) fn request_body_limit(app_max_body_bytes: u64) -> u64 {
+    match app_max_body_bytes {
+        0 => MAX_REQUEST_BODY_BYTES,
+        limit => limit.min(MAX_REQUEST_BODY_BYTES),
+    }
+}
+
+/// Declared `Content-Length` of a request, if present and valid.
+pub(super) fn request_content_length(header: &RequestHeader) -> Option<u64> {
+    header
+        .headers
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+}
+
+/// Size of the request's header fields as sent: `name: value\r\n` per field.
+pub(super) fn request_header_bytes(header: &RequestHeader) -> usize {
+    header
+        .headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum()
+}
+
+/// Bound how long a client connection may sit idle before its next request.
+///
+/// HTTP/1.1 sessions default to unlimited keep-alive once a request is read;
+/// this replaces that with `idle_timeout` (whole seconds, at least 1). Sessions
+/// already marked non-reusable (`Connection: close`, shutdown) stay that way.
+pub(super) fn apply_client_keepalive(session: &mut ServerSession, idle_timeout: Duration) {
+    if session.get_keepalive().is_some() {
+        session.set_keepalive(Some(idle_timeout.as_secs().max(1)));
+    }
+}
+
 /// Per-IP concurrent request tracker for basic DDoS mitigation.
 pub(super) struct IpRequestTracker {
     connections: dashmap::DashMap<IpAddr, AtomicU32>,
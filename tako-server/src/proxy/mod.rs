@@ -4,14 +4,19 @@
 //! Supports TLS termination with automatic certificate management.
 //! Handles ACME HTTP-01 challenges for Let's Encrypt certificate issuance.
 
+mod access_log;
+mod buffering;
+mod compression;
 mod config;
 mod limits;
+mod rate_limit;
 mod request;
 mod server;
 mod service;
 mod static_files;
 
-pub use config::{ProxyConfig, ResponseCacheConfig};
+pub use access_log::AccessLogFormat;
+pub use config::{CompressionConfig, ProxyConfig, ResponseCacheConfig};
 #[allow(unused_imports)]
 pub use server::{ProxyBuilder, TlsConfig, build_server, build_server_with_acme};
 #[allow(unused_imports)]
@@ -22,9 +27,11 @@ use crate::lb::LoadBalancer;
 use crate::routing::RouteTable;
 use crate::scaling::ColdStartManager;
 use crate::tls::{ChallengeHandler, ChallengeTokens};
+use access_log::{AccessLogSampler, AccessLogWriter};
 use config::ResponseCacheRuntime;
 use limits::IpRequestTracker;
 use parking_lot::RwLock as SyncRwLock;
+use rate_limit::RateLimiter;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -35,13 +42,13 @@ use pingora_http::{RequestHeader, ResponseHeader};
 use pingora_proxy::ProxyHttp;
 #[cfg(test)]
 use request::{
-    build_proxy_cache_key, insert_body_headers, is_effective_request_https,
-    path_looks_like_static_asset, request_is_proxy_cacheable, response_cacheability,
-    should_assume_forwarded_private_request_https, should_redirect_http_request,
-    static_lookup_paths,
+    apply_forwarding_headers, build_proxy_cache_key, insert_body_headers,
+    is_effective_request_https, path_looks_like_static_asset, request_is_proxy_cacheable,
+    response_cacheability, should_assume_forwarded_private_request_https,
+    should_redirect_http_request, should_retry_upstream, static_lookup_paths,
 };
 #[cfg(test)]
-use service::BackendResolution;
+use service::{BackendResolution, serves_maintenance};
 
 pub(crate) use limits::MAX_REQUEST_BODY_BYTES;
 
@@ -68,6 +75,12 @@ pub struct TakoProxy {
     channel_stores: SyncRwLock<HashMap<String, Arc<ChannelStore>>>,
     /// Per-IP concurrent request limiter (DDoS mitigation)
     ip_tracker: IpRequestTracker,
+    /// Token buckets for apps with a `rate_limit`
+    rate_limiter: RateLimiter,
+    /// Per-app access log sampling counters
+    access_log: AccessLogSampler,
+    /// Access log output when `ProxyConfig::access_log` is set
+    access_log_writer: Option<AccessLogWriter>,
 }
 
 impl TakoProxy {
@@ -81,6 +94,7 @@ impl TakoProxy {
             .response_cache
             .as_ref()
             .map(ResponseCacheRuntime::new);
+        let access_log_writer = open_access_log(&config);
         Self {
             lb,
             routes,
@@ -91,6 +105,9 @@ impl TakoProxy {
             static_servers: SyncRwLock::new(HashMap::new()),
             channel_stores: SyncRwLock::new(HashMap::new()),
             ip_tracker: IpRequestTracker::new(),
+            rate_limiter: RateLimiter::new(),
+            access_log: AccessLogSampler::new(),
+            access_log_writer,
         }
     }
 
@@ -106,6 +123,7 @@ impl TakoProxy {
             .response_cache
             .as_ref()
             .map(ResponseCacheRuntime::new);
+        let access_log_writer = open_access_log(&config);
         Self {
             lb,
             routes,
@@ -116,6 +134,9 @@ impl TakoProxy {
             static_servers: SyncRwLock::new(HashMap::new()),
             channel_stores: SyncRwLock::new(HashMap::new()),
             ip_tracker: IpRequestTracker::new(),
+            rate_limiter: RateLimiter::new(),
+            access_log: AccessLogSampler::new(),
+            access_log_writer,
         }
     }
 
@@ -125,5 +146,21 @@ impl TakoProxy {
     }
 }
 
+fn open_access_log(config: &ProxyConfig) -> Option<AccessLogWriter> {
+    let format = config.access_log?;
+    let path = config.access_log_path.as_deref();
+    match AccessLogWriter::open(format, path) {
+        Ok(writer) => Some(writer),
+        Err(e) => {
+            tracing::warn!(
+                path = ?path,
+                "Failed to open access log, access logging disabled: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,102 @@
+use dashmap::DashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tako_core::RateLimit;
+
+/// Past this many buckets, ones that have refilled completely are dropped
+/// before a new one is added. A full bucket behaves like a fresh one, so
+/// pruning never changes a decision.
+const MAX_TRACKED_BUCKETS: usize = 100_000;
+
+/// Tokens refill continuously at `requests_per_second` up to `burst`; each
+/// request takes one.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+    /// When the bucket will be full again if left alone
+    full_at: Instant,
+}
+
+impl TokenBucket {
+    pub(super) fn full(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst()),
+            refilled_at: now,
+            full_at: now,
+        }
+    }
+
+    #[cfg(test)]
+    pub(super) fn tokens(&self) -> f64 {
+        self.tokens
+    }
+
+    /// Take a token, or return how long until the next one is available.
+    pub(super) fn try_take(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        let rate = f64::from(limit.requests_per_second.max(1));
+        let burst = f64::from(limit.burst());
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * rate).min(burst);
+        self.refilled_at = now;
+
+        let result = if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        };
+        self.full_at = now + Duration::from_secs_f64((burst - self.tokens) / rate);
+        result
+    }
+}
+
+/// `Retry-After` value for a request that must wait `wait`: whole seconds,
+/// rounded up, at least 1.
+pub(super) fn retry_after_secs(wait: Duration) -> u64 {
+    (wait.as_secs() + u64::from(wait.subsec_nanos() > 0)).max(1)
+}
+
+/// Per-app (optionally per-client-IP) token buckets shared by all requests.
+pub(super) struct RateLimiter {
+    buckets: DashMap<(String, Option<IpAddr>), TokenBucket>,
+}
+
+impl RateLimiter {
+    pub(super) fn new() -> Self {
+        Self {
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Admit a request to `app`, or return how long the client should wait.
+    pub(super) fn check(
+        &self,
+        app: &str,
+        client_ip: Option<IpAddr>,
+        limit: &RateLimit,
+    ) -> Result<(), Duration> {
+        self.check_at(app, client_ip, limit, Instant::now())
+    }
+
+    pub(super) fn check_at(
+        &self,
+        app: &str,
+        client_ip: Option<IpAddr>,
+        limit: &RateLimit,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let key = (app.to_string(), client_ip.filter(|_| limit.per_client_ip));
+        if self.buckets.len() >= MAX_TRACKED_BUCKETS && !self.buckets.contains_key(&key) {
+            self.buckets.retain(|_, bucket| bucket.full_at > now);
+        }
+        self.buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::full(limit, now))
+            .try_take(limit, now)
+    }
+
+    pub(super) fn remove_app(&self, app: &str) {
+        self.buckets.retain(|(name, _), _| name != app);
+    }
+}
@@ -2,13 +2,40 @@ use pingora_cache::cache_control::CacheControl;
 use pingora_cache::filters::{request_cacheable, resp_cacheable};
 use pingora_cache::{CacheKey, CacheMetaDefaults, RespCacheable};
 use pingora_core::prelude::*;
-use pingora_http::{RequestHeader, ResponseHeader};
+use pingora_http::{Method, RequestHeader, ResponseHeader};
 use pingora_proxy::Session;
 use std::net::IpAddr;
 use std::path::Path;
 use std::sync::OnceLock;
 use tokio::io::AsyncReadExt;
 
+/// Extra upstream attempts a request gets after its instance fails before
+/// responding.
+pub(super) const MAX_UPSTREAM_RETRIES: u32 = 2;
+
+/// Whether a request whose upstream attempt failed before a response may be
+/// sent to another instance. Only idempotent methods are retried unless the
+/// app opts in, and never more than `MAX_UPSTREAM_RETRIES` times.
+pub(super) fn should_retry_upstream(
+    method: &Method,
+    retry_non_idempotent: bool,
+    retries_so_far: u32,
+) -> bool {
+    if retries_so_far >= MAX_UPSTREAM_RETRIES {
+        return false;
+    }
+    retry_non_idempotent
+        || matches!(
+            *method,
+            Method::GET
+                | Method::HEAD
+                | Method::OPTIONS
+                | Method::TRACE
+                | Method::PUT
+                | Method::DELETE
+        )
+}
+
 pub(super) fn should_redirect_http_request(
     is_effective_https: bool,
     redirect_http_to_https: bool,
@@ -180,6 +207,37 @@ pub(super) fn client_ip_from_session(session: &Session) -> Option<IpAddr> {
         .map(|inet| inet.ip())
 }
 
+/// Rewrite the headers Tako owns on a request headed upstream. Configured
+/// `strip_headers` are dropped first so a client can't smuggle values the
+/// app would trust; Tako's own forwarding headers are then set from the
+/// connection itself.
+pub(super) fn apply_forwarding_headers(
+    upstream_request: &mut RequestHeader,
+    strip_headers: &[String],
+    is_https: bool,
+    client_ip: Option<IpAddr>,
+) {
+    for name in strip_headers {
+        let _ = upstream_request.remove_header(name.as_str());
+    }
+
+    let proto = if is_https { "https" } else { "http" };
+    upstream_request
+        .insert_header("X-Forwarded-Proto", proto)
+        .unwrap();
+
+    if let Some(ip) = client_ip {
+        upstream_request
+            .insert_header("X-Forwarded-For", ip.to_string())
+            .unwrap();
+    } else {
+        let _ = upstream_request.remove_header("X-Forwarded-For");
+    }
+
+    let _ = upstream_request.remove_header("Forwarded");
+    let _ = upstream_request.remove_header("X-Tako-Internal-Token");
+}
+
 pub(super) fn request_host(req: &pingora_http::RequestHeader) -> &str {
     req.uri
         .authority()
@@ -188,6 +246,14 @@ pub(super) fn request_host(req: &pingora_http::RequestHeader) -> &str {
         .unwrap_or("")
 }
 
+/// Build the upstream path-and-query for a rewritten path, keeping the query string.
+pub(super) fn rewritten_path_and_query(path: &str, query: Option<&str>) -> String {
+    match query {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    }
+}
+
 pub(super) fn path_looks_like_static_asset(path: &str) -> bool {
     let final_segment = path.rsplit_once('/').map_or(path, |(_, segment)| segment);
     final_segment.contains('.') && !final_segment.ends_with('.')
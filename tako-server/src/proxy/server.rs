@@ -3,11 +3,13 @@ use crate::proxy::{ProxyConfig, RouteTable, TakoProxy};
 use crate::scaling::ColdStartManager;
 use crate::tls::{
     CertInfo, CertManager, ChallengeTokens, SelfSignedGenerator, create_sni_callbacks,
+    enable_ocsp_stapling,
 };
 use pingora_core::listeners::TcpSocketOptions;
 use pingora_core::listeners::tls::TlsSettings;
 use pingora_core::prelude::*;
 use pingora_core::services::listening::Service as ListeningService;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -54,9 +56,11 @@ impl TlsConfig {
                 domain: domain.to_string(),
                 cert_path: self_signed.cert_path,
                 key_path: self_signed.key_path,
+                not_before: None,
                 expires_at: None,
                 is_wildcard: false,
                 is_self_signed: true,
+                ocsp: None,
             });
         }
 
@@ -115,20 +119,34 @@ pub fn build_server_with_acme(
         app.server_options = Some(opts);
     }
 
-    let listener_options = listener_socket_options();
-    proxy_service.add_tcp_with_settings(
-        &format!("0.0.0.0:{}", config.http_port),
-        listener_options.clone(),
-    );
+    let ipv6 = config.listen_ipv6 && {
+        let supported = port_is_free(SocketAddr::from((Ipv6Addr::LOCALHOST, 0)));
+        if !supported {
+            tracing::warn!("IPv6 listeners requested but IPv6 is unavailable on this host");
+        }
+        supported
+    };
+
+    for addr in listen_addrs(config.http_port, ipv6) {
+        proxy_service.add_tcp_with_settings(&addr.to_string(), listener_options_for(&addr));
+    }
 
     if config.enable_https {
-        if let Some(tls_settings) = create_tls_settings(&config, cert_manager)? {
+        // TLS settings are consumed by each listener, so build one set per address.
+        let mut enabled = false;
+        for addr in listen_addrs(config.https_port, ipv6) {
+            let Some(tls_settings) = create_tls_settings(&config, cert_manager.clone())? else {
+                break;
+            };
             proxy_service.add_tls_with_settings(
-                &format!("0.0.0.0:{}", config.https_port),
-                Some(listener_options),
+                &addr.to_string(),
+                Some(listener_options_for(&addr)),
                 tls_settings,
             );
-            tracing::info!(port = config.https_port, "HTTPS listener enabled");
+            enabled = true;
+        }
+        if enabled {
+            tracing::info!(port = config.https_port, ipv6, "HTTPS listener enabled");
         } else {
             tracing::warn!("HTTPS enabled but no certificates available");
         }
@@ -136,11 +154,11 @@ pub fn build_server_with_acme(
 
     server.add_service(proxy_service);
 
-    if let Some(metrics_port) = config.metrics_port {
+    if let Some(metrics_listen) = config.metrics_listen {
         let mut metrics_service = ListeningService::prometheus_http_service();
-        metrics_service.add_tcp(&format!("127.0.0.1:{}", metrics_port));
+        metrics_service.add_tcp(&metrics_listen.to_string());
         server.add_service(metrics_service);
-        tracing::info!(port = metrics_port, "Prometheus metrics listener enabled");
+        tracing::info!(addr = %metrics_listen, "Prometheus metrics listener enabled");
     }
 
     Ok(server)
@@ -152,6 +170,32 @@ pub(crate) fn listener_socket_options() -> TcpSocketOptions {
     options
 }
 
+/// Addresses the public listeners bind for `port`: every IPv4 interface, plus
+/// every IPv6 interface when `ipv6` is set.
+pub(crate) fn listen_addrs(port: u16, ipv6: bool) -> Vec<SocketAddr> {
+    let mut addrs = vec![SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))];
+    if ipv6 {
+        addrs.push(SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)));
+    }
+    addrs
+}
+
+/// IPv6 listeners are v6-only so they sit beside the IPv4 listener on the
+/// same port instead of also claiming its IPv4-mapped addresses.
+pub(crate) fn listener_options_for(addr: &SocketAddr) -> TcpSocketOptions {
+    let mut options = listener_socket_options();
+    if addr.is_ipv6() {
+        options.ipv6_only = Some(true);
+    }
+    options
+}
+
+/// Whether a TCP listener can be bound on `addr` right now. With port 0 this
+/// only checks that the address family is usable on this host.
+pub(crate) fn port_is_free(addr: SocketAddr) -> bool {
+    std::net::TcpListener::bind(addr).is_ok()
+}
+
 pub(crate) fn create_tls_settings(
     config: &ProxyConfig,
     cert_manager: Option<Arc<CertManager>>,
@@ -200,6 +244,12 @@ pub(crate) fn create_tls_settings(
                 format!("Failed to create TLS settings with SNI callbacks: {}", e),
             )
         })?;
+        enable_ocsp_stapling(&mut tls_settings).map_err(|e| {
+            Error::explain(
+                ErrorType::InternalError,
+                format!("Failed to enable OCSP stapling: {}", e),
+            )
+        })?;
 
         tls_settings.enable_h2();
 
@@ -1,9 +1,12 @@
 use super::super::TakoProxy;
+use crate::instances::{App, MaintenancePage};
 use crate::lb::Backend;
 use crate::scaling::WaitForReadyOutcome;
+use crate::socket::{AppState, InstanceState};
 
 pub(crate) enum BackendResolution {
     Ready(Backend),
+    Maintenance(MaintenancePage),
     StartupTimeout,
     StartupFailed,
     QueueFull,
@@ -21,9 +24,24 @@ impl TakoProxy {
             return BackendResolution::AppMissing;
         };
 
+        // Drained apps keep their instances but take no new requests, and
+        // must not cold start either.
+        if app.is_drained() {
+            return match app.config.read().maintenance.clone() {
+                Some(page) => BackendResolution::Maintenance(page),
+                None => BackendResolution::Unavailable,
+            };
+        }
+        if let Some(page) = maintenance_page(&app) {
+            return BackendResolution::Maintenance(page);
+        }
+
         if app.config.read().min_instances != 0 {
             return BackendResolution::Unavailable;
         }
+        if app.restarts_exhausted() {
+            return BackendResolution::StartupFailed;
+        }
 
         let begin = self.cold_start.begin(app_name);
         if begin.leader {
@@ -41,12 +59,14 @@ impl TakoProxy {
                     app.set_state(crate::socket::AppState::Error);
                     app.set_last_error(format!("Cold start failed: {}", e));
                     app.remove_instance(&instance.id);
+                    crate::runtime_events::record_spawn_failure(&app, &instance.id, &e);
                     cold_start.mark_failed(&app_name, "spawn_failed");
                 }
             });
         }
 
-        match self.cold_start.wait_for_ready_outcome(app_name).await {
+        let startup_timeout = app.config.read().startup_timeout;
+        match self.cold_start.wait_ready(app_name, startup_timeout).await {
             WaitForReadyOutcome::Ready => self
                 .lb
                 .get_backend(app_name)
@@ -58,3 +78,34 @@ impl TakoProxy {
         }
     }
 }
+
+/// The app's maintenance page, if it has one and cannot serve the request.
+fn maintenance_page(app: &App) -> Option<MaintenancePage> {
+    let (page, on_demand) = {
+        let config = app.config.read();
+        (config.maintenance.clone()?, config.min_instances == 0)
+    };
+    let healthy_instances = app
+        .get_instances()
+        .iter()
+        .filter(|instance| instance.state() == InstanceState::Healthy)
+        .count();
+    serves_maintenance(app.state(), healthy_instances, on_demand).then_some(page)
+}
+
+/// Whether an app with a maintenance page configured should get it instead
+/// of being routed. On-demand apps with no instances are left to cold start
+/// unless they were stopped or errored.
+pub(crate) fn serves_maintenance(
+    state: AppState,
+    healthy_instances: usize,
+    on_demand: bool,
+) -> bool {
+    if healthy_instances > 0 {
+        return false;
+    }
+    match state {
+        AppState::Stopped | AppState::Error => true,
+        _ => !on_demand,
+    }
+}
@@ -362,7 +362,8 @@ impl TakoProxy {
                     .write_channel_error(session, ChannelError::AuthUnavailable)
                     .await;
             }
-            BackendResolution::StartupFailed
+            BackendResolution::Maintenance(_)
+            | BackendResolution::StartupFailed
             | BackendResolution::QueueFull
             | BackendResolution::Unavailable
             | BackendResolution::AppMissing => {
@@ -3,18 +3,27 @@ mod channels;
 mod static_handler;
 
 pub(crate) use backend::BackendResolution;
+#[cfg(test)]
+pub(crate) use backend::serves_maintenance;
 
 use super::TakoProxy;
+use super::access_log::AccessLogRecord;
+use super::buffering::ResponseBuffer;
+use super::limits::{request_body_limit, request_content_length, request_header_bytes};
+use super::rate_limit::retry_after_secs;
 use super::request::{
-    build_proxy_cache_key, client_ip_from_session, insert_body_headers, is_effective_request_https,
-    path_looks_like_static_asset, request_host, request_is_proxy_cacheable, response_cacheability,
+    apply_forwarding_headers, build_proxy_cache_key, client_ip_from_session, insert_body_headers,
+    is_effective_request_https, path_looks_like_static_asset, request_host,
+    request_is_proxy_cacheable, response_cacheability, rewritten_path_and_query,
     should_assume_forwarded_private_request_https, should_redirect_http_request,
+    should_retry_upstream,
 };
 use crate::lb::Backend;
 use crate::metrics::RequestTimer;
 use async_trait::async_trait;
 use bytes::Bytes;
 use pingora_cache::{CacheKey, RespCacheable};
+use pingora_core::modules::http::HttpModules;
 use pingora_core::prelude::*;
 use pingora_core::upstreams::peer::HttpPeer;
 use pingora_http::{RequestHeader, ResponseHeader};
@@ -28,6 +37,75 @@ impl TakoProxy {
         self.routes.write().await.remove_app_routes(app_name);
         self.static_servers.write().remove(app_name);
         self.channel_stores.write().remove(app_name);
+        self.access_log.remove_app(app_name);
+        self.rate_limiter.remove_app(app_name);
+    }
+
+    /// Return the request's backend to the load balancer and instance. Runs
+    /// once per request: at the end of a buffered upstream body, or when the
+    /// request is logged.
+    pub(super) fn release_backend(&self, ctx: &mut RequestCtx) {
+        if ctx.backend_released {
+            return;
+        }
+        let Some(ref backend) = ctx.backend else {
+            return;
+        };
+        ctx.backend_released = true;
+        self.lb
+            .request_completed(&backend.app_name, &backend.instance_id);
+        if std::mem::take(&mut ctx.instance_request_started)
+            && let Some(app) = self.lb.app_manager().get_app(&backend.app_name)
+            && let Some(instance) = app.get_instance(&backend.instance_id)
+        {
+            instance.request_finished();
+        }
+    }
+
+    /// Move a request whose upstream attempt failed before any response to
+    /// another healthy instance, handing the failed one back. Returns false
+    /// (leaving the backend in place) when the request may not be retried or
+    /// no untried instance is left.
+    fn retry_on_another_instance(&self, session: &Session, ctx: &mut RequestCtx) -> bool {
+        let Some(failed) = ctx.backend.clone() else {
+            return false;
+        };
+        let Some(app) = self.lb.app_manager().get_app(&failed.app_name) else {
+            return false;
+        };
+        let retry_non_idempotent = app.config.read().retry_non_idempotent;
+        if !should_retry_upstream(
+            &session.req_header().method,
+            retry_non_idempotent,
+            ctx.upstream_retries,
+        ) {
+            return false;
+        }
+        ctx.tried_instances.push(failed.instance_id.clone());
+        let Some(next) = self
+            .lb
+            .get_backend_excluding(&failed.app_name, &ctx.tried_instances)
+        else {
+            return false;
+        };
+
+        self.lb
+            .request_completed(&failed.app_name, &failed.instance_id);
+        if std::mem::take(&mut ctx.instance_request_started)
+            && let Some(instance) = app.get_instance(&failed.instance_id)
+        {
+            instance.request_finished();
+        }
+        tracing::debug!(
+            app = %failed.app_name,
+            failed = %failed.instance_id,
+            next = %next.instance_id,
+            "Retrying request on another instance"
+        );
+        ctx.upstream_retries += 1;
+        ctx.upstream_start = None;
+        ctx.backend = Some(next);
+        true
     }
 }
 
@@ -35,13 +113,29 @@ pub struct RequestCtx {
     pub(super) backend: Option<Backend>,
     pub(super) is_https: bool,
     pub(super) matched_route_path: Option<String>,
+    /// Rewritten upstream path when the matched route strips its prefix.
+    pub(super) upstream_path: Option<String>,
     pub(super) request_timer: Option<RequestTimer>,
     /// Client IP for per-IP rate limit tracking (released in logging phase)
     pub(super) client_ip: Option<IpAddr>,
     /// Accumulated request body bytes (for chunked transfer size enforcement)
     pub(super) body_bytes_received: u64,
+    /// Body size limit for the routed app (server-wide limit until routed)
+    pub(super) max_body_bytes: u64,
     /// Set when the upstream request is sent; observed when response headers arrive.
     pub(super) upstream_start: Option<Instant>,
+    /// Holds the upstream body when the app buffers responses.
+    pub(super) response_buffer: Option<ResponseBuffer>,
+    /// Set once the backend has been handed back (see `release_backend`).
+    pub(super) backend_released: bool,
+    /// Whether the backend's instance counted this request as in flight.
+    pub(super) instance_request_started: bool,
+    /// Instances that already failed this request, and how many times it
+    /// was moved to another one.
+    pub(super) tried_instances: Vec<String>,
+    pub(super) upstream_retries: u32,
+    /// When the request arrived, for access log latency
+    pub(super) started_at: Instant,
 }
 
 #[async_trait]
@@ -53,14 +147,31 @@ impl ProxyHttp for TakoProxy {
             backend: None,
             is_https: false,
             matched_route_path: None,
+            upstream_path: None,
             request_timer: None,
             client_ip: None,
             body_bytes_received: 0,
+            max_body_bytes: super::MAX_REQUEST_BODY_BYTES,
             upstream_start: None,
+            response_buffer: None,
+            backend_released: false,
+            instance_request_started: false,
+            tried_instances: Vec::new(),
+            upstream_retries: 0,
+            started_at: Instant::now(),
         }
     }
 
+    fn init_downstream_modules(&self, modules: &mut HttpModules) {
+        super::compression::init_modules(modules, self.config.compression.as_ref());
+    }
+
     async fn request_filter(&self, session: &mut Session, ctx: &mut Self::CTX) -> Result<bool> {
+        super::limits::apply_client_keepalive(session, self.config.client_keepalive_timeout);
+        if self.config.compression.is_some() {
+            super::compression::disable(session);
+        }
+
         if let Some(ip) = client_ip_from_session(session) {
             if !self.ip_tracker.try_acquire(ip) {
                 let body = "Too Many Requests";
@@ -76,13 +187,8 @@ impl ProxyHttp for TakoProxy {
             ctx.client_ip = Some(ip);
         }
 
-        if let Some(cl) = session
-            .req_header()
-            .headers
-            .get("content-length")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse::<u64>().ok())
-            && cl > super::MAX_REQUEST_BODY_BYTES
+        if request_content_length(session.req_header())
+            .is_some_and(|len| len > super::MAX_REQUEST_BODY_BYTES)
         {
             let body = "Payload Too Large";
             let mut header = ResponseHeader::build(413, None)?;
@@ -188,6 +294,63 @@ impl ProxyHttp for TakoProxy {
         };
         let app_name = route_match.app;
         ctx.matched_route_path = route_match.path;
+        if route_match.upstream_path != path {
+            ctx.upstream_path = Some(route_match.upstream_path);
+        }
+
+        let (app_max_body_bytes, max_header_bytes, rate_limit) = self
+            .lb
+            .app_manager()
+            .get_app(&app_name)
+            .map(|app| {
+                let config = app.config.read();
+                (
+                    config.max_body_bytes,
+                    config.max_header_bytes,
+                    config.rate_limit,
+                )
+            })
+            .unwrap_or_default();
+        ctx.max_body_bytes = request_body_limit(app_max_body_bytes);
+
+        if max_header_bytes > 0
+            && request_header_bytes(session.req_header()) > max_header_bytes as usize
+        {
+            let body = "Request Header Fields Too Large";
+            let mut header = ResponseHeader::build(431, None)?;
+            insert_body_headers(&mut header, "text/plain", body)?;
+            session
+                .write_response_header(Box::new(header), false)
+                .await?;
+            session.write_response_body(Some(body.into()), true).await?;
+            return Ok(true);
+        }
+
+        if request_content_length(session.req_header()).is_some_and(|len| len > ctx.max_body_bytes)
+        {
+            let body = "Payload Too Large";
+            let mut header = ResponseHeader::build(413, None)?;
+            insert_body_headers(&mut header, "text/plain", body)?;
+            session
+                .write_response_header(Box::new(header), false)
+                .await?;
+            session.write_response_body(Some(body.into()), true).await?;
+            return Ok(true);
+        }
+
+        if let Some(ref limit) = rate_limit
+            && let Err(wait) = self.rate_limiter.check(&app_name, ctx.client_ip, limit)
+        {
+            let body = "Too Many Requests";
+            let mut header = ResponseHeader::build(429, None)?;
+            header.insert_header("Retry-After", retry_after_secs(wait).to_string())?;
+            insert_body_headers(&mut header, "text/plain", body)?;
+            session
+                .write_response_header(Box::new(header), false)
+                .await?;
+            session.write_response_body(Some(body.into()), true).await?;
+            return Ok(true);
+        }
 
         if self
             .try_handle_channel_request(session, ctx, &app_name, &path, &host)
@@ -211,14 +374,34 @@ impl ProxyHttp for TakoProxy {
 
         let backend = match self.resolve_backend(&app_name).await {
             BackendResolution::Ready(backend) => backend,
+            BackendResolution::Maintenance(page) => {
+                let mut header = ResponseHeader::build(page.status, None)?;
+                header.insert_header("Cache-Control", "no-store")?;
+                insert_body_headers(&mut header, "text/html; charset=utf-8", &page.body)?;
+                session
+                    .write_response_header(Box::new(header), false)
+                    .await?;
+                session
+                    .write_response_body(Some(page.body.into()), true)
+                    .await?;
+                return Ok(true);
+            }
             BackendResolution::StartupTimeout => {
-                let body = "App startup timed out";
-                let mut header = ResponseHeader::build(504, None)?;
-                insert_body_headers(&mut header, "text/plain", body)?;
+                let accept = session
+                    .req_header()
+                    .headers
+                    .get("accept")
+                    .and_then(|value| value.to_str().ok());
+                let page = self.cold_start.unavailable_page(&app_name, accept);
+                let mut header = ResponseHeader::build(503, None)?;
+                header.insert_header("Retry-After", page.retry_after_secs.to_string())?;
+                insert_body_headers(&mut header, page.content_type, &page.body)?;
                 session
                     .write_response_header(Box::new(header), false)
                     .await?;
-                session.write_response_body(Some(body.into()), true).await?;
+                session
+                    .write_response_body(Some(page.body.into()), true)
+                    .await?;
                 return Ok(true);
             }
             BackendResolution::StartupFailed => {
@@ -280,7 +463,7 @@ impl ProxyHttp for TakoProxy {
     ) -> Result<()> {
         if let Some(data) = body {
             ctx.body_bytes_received += data.len() as u64;
-            if ctx.body_bytes_received > super::MAX_REQUEST_BODY_BYTES {
+            if ctx.body_bytes_received > ctx.max_body_bytes {
                 return Err(Error::explain(
                     ErrorType::InvalidHTTPHeader,
                     "Request body exceeds maximum allowed size",
@@ -368,33 +551,83 @@ impl ProxyHttp for TakoProxy {
         Ok(Box::new(peer))
     }
 
+    fn fail_to_connect(
+        &self,
+        session: &mut Session,
+        _peer: &HttpPeer,
+        ctx: &mut Self::CTX,
+        mut e: Box<Error>,
+    ) -> Box<Error> {
+        if self.retry_on_another_instance(session, ctx) {
+            e.set_retry(true);
+        }
+        e
+    }
+
+    fn error_while_proxy(
+        &self,
+        peer: &HttpPeer,
+        session: &mut Session,
+        e: Box<Error>,
+        ctx: &mut Self::CTX,
+        client_reused: bool,
+    ) -> Box<Error> {
+        let mut e = e.more_context(format!("Peer: {}", peer));
+        let retry_buffer_truncated = session.as_ref().retry_buffer_truncated();
+        // `upstream_start` is cleared once response headers arrive; after
+        // that part of the response may already be with the client.
+        if ctx.upstream_start.is_some()
+            && !retry_buffer_truncated
+            && self.retry_on_another_instance(session, ctx)
+        {
+            e.set_retry(true);
+            return e;
+        }
+        e.retry
+            .decide_reuse(client_reused && !retry_buffer_truncated);
+        e
+    }
+
     async fn upstream_request_filter(
         &self,
         session: &mut Session,
         upstream_request: &mut RequestHeader,
         ctx: &mut Self::CTX,
     ) -> Result<()> {
-        let proto = if ctx.is_https { "https" } else { "http" };
-        upstream_request
-            .insert_header("X-Forwarded-Proto", proto)
-            .unwrap();
+        let app = ctx
+            .backend
+            .as_ref()
+            .and_then(|backend| self.lb.app_manager().get_app(&backend.app_name));
+        let strip_headers = app
+            .as_ref()
+            .map(|app| app.config.read().strip_request_headers.clone())
+            .unwrap_or_default();
+        apply_forwarding_headers(
+            upstream_request,
+            &strip_headers,
+            ctx.is_https,
+            client_ip_from_session(session),
+        );
 
-        if let Some(ip) = client_ip_from_session(session) {
-            upstream_request
-                .insert_header("X-Forwarded-For", ip.to_string())
-                .unwrap();
-        } else {
-            let _ = upstream_request.remove_header("X-Forwarded-For");
+        if let Some(upstream_path) = ctx.upstream_path.as_deref() {
+            let path_and_query =
+                rewritten_path_and_query(upstream_path, upstream_request.uri.query());
+            let uri = path_and_query.parse().map_err(|e| {
+                Error::explain(
+                    ErrorType::InvalidHTTPHeader,
+                    format!("Invalid rewritten upstream path '{path_and_query}': {e}"),
+                )
+            })?;
+            upstream_request.set_uri(uri);
         }
 
-        let _ = upstream_request.remove_header("Forwarded");
-        let _ = upstream_request.remove_header("X-Tako-Internal-Token");
-
         if let Some(ref backend) = ctx.backend
-            && let Some(app) = self.lb.app_manager().get_app(&backend.app_name)
+            && let Some(app) = app
             && let Some(instance) = app.get_instance(&backend.instance_id)
+            && !ctx.instance_request_started
         {
             instance.request_started();
+            ctx.instance_request_started = true;
         }
 
         ctx.upstream_start = Some(Instant::now());
@@ -405,7 +638,7 @@ impl ProxyHttp for TakoProxy {
     async fn upstream_response_filter(
         &self,
         _session: &mut Session,
-        _upstream_response: &mut ResponseHeader,
+        upstream_response: &mut ResponseHeader,
         ctx: &mut Self::CTX,
     ) -> Result<()> {
         if let (Some(start), Some(backend)) = (ctx.upstream_start.take(), ctx.backend.as_ref()) {
@@ -414,25 +647,43 @@ impl ProxyHttp for TakoProxy {
                 start.elapsed().as_secs_f64(),
             );
         }
+        let buffer_limit = ctx
+            .backend
+            .as_ref()
+            .and_then(|backend| self.lb.app_manager().get_app(&backend.app_name))
+            .map(|app| app.config.read().response_buffer_bytes)
+            .unwrap_or(0);
+        ctx.response_buffer = ResponseBuffer::for_response(upstream_response, buffer_limit);
         Ok(())
     }
 
     async fn response_filter(
         &self,
-        _session: &mut Session,
-        _upstream_response: &mut ResponseHeader,
+        session: &mut Session,
+        upstream_response: &mut ResponseHeader,
         _ctx: &mut Self::CTX,
     ) -> Result<()> {
+        if let Some(ref compression) = self.config.compression {
+            super::compression::enable_for_response(session, upstream_response, compression);
+        }
         Ok(())
     }
 
     fn upstream_response_body_filter(
         &self,
         _session: &mut Session,
-        _body: &mut Option<Bytes>,
-        _end_of_stream: bool,
-        _ctx: &mut Self::CTX,
+        body: &mut Option<Bytes>,
+        end_of_stream: bool,
+        ctx: &mut Self::CTX,
     ) -> Result<Option<Duration>> {
+        if let Some(buffer) = ctx.response_buffer.as_mut() {
+            buffer.filter(body, end_of_stream);
+            // The app has sent everything; free its instance while the
+            // buffered body is still being written to the client.
+            if end_of_stream {
+                self.release_backend(ctx);
+            }
+        }
         Ok(None)
     }
 
@@ -441,26 +692,31 @@ impl ProxyHttp for TakoProxy {
             self.ip_tracker.release(ip);
         }
 
-        if let Some(ref backend) = ctx.backend {
-            self.lb
-                .request_completed(&backend.app_name, &backend.instance_id);
-
-            if let Some(app) = self.lb.app_manager().get_app(&backend.app_name)
-                && let Some(instance) = app.get_instance(&backend.instance_id)
-            {
-                instance.request_finished();
-            }
-        }
-
         let status = session
             .response_written()
             .map(|r| r.status.as_u16())
             .unwrap_or(0);
 
+        self.release_backend(ctx);
+
+        let mut log_request = true;
+        if let Some(ref backend) = ctx.backend
+            && let Some(app) = self.lb.app_manager().get_app(&backend.app_name)
+        {
+            let sample_rate = app.config.read().access_log_sample_rate;
+            log_request = self
+                .access_log
+                .should_log(&backend.app_name, status, sample_rate);
+        }
+
         if let Some(timer) = ctx.request_timer.take() {
             timer.finish(status);
         }
 
+        if !log_request {
+            return;
+        }
+
         let host = request_host(session.req_header());
         let host = if host.is_empty() { "-" } else { host };
 
@@ -475,5 +731,32 @@ impl ProxyHttp for TakoProxy {
             https = ctx.is_https,
             "Request completed"
         );
+
+        if let Some(ref writer) = self.access_log_writer {
+            let req = session.req_header();
+            let header = |name: &str| req.headers.get(name).and_then(|v| v.to_str().ok());
+            let uri = req
+                .uri
+                .path_and_query()
+                .map(|pq| pq.as_str())
+                .unwrap_or(path);
+            // `http::Version`'s Debug form is the protocol label, e.g. `HTTP/1.1`.
+            let version = format!("{:?}", req.version);
+            writer.write(&AccessLogRecord {
+                time: chrono::Utc::now(),
+                client_ip: client_ip_from_session(session),
+                method,
+                uri,
+                version: &version,
+                host,
+                status,
+                bytes_sent: session.body_bytes_sent(),
+                referer: header("referer"),
+                user_agent: header("user-agent"),
+                app: ctx.backend.as_ref().map(|b| b.app_name.as_str()),
+                instance: ctx.backend.as_ref().map(|b| b.instance_id.as_str()),
+                latency: ctx.started_at.elapsed(),
+            });
+        }
     }
 }
@@ -1,13 +1,21 @@
+use super::access_log::AccessLogRecord;
+use super::buffering::ResponseBuffer;
+use super::compression::should_compress;
+use super::rate_limit::{RateLimiter, TokenBucket, retry_after_secs};
 use super::request::{
     forwarded_header_has_proto, forwarded_header_proto_is_https, is_request_forwarded_https,
-    strip_route_prefix_for_static_lookup, x_forwarded_proto_is_https,
+    rewritten_path_and_query, strip_route_prefix_for_static_lookup, x_forwarded_proto_is_https,
+};
+use super::server::{
+    create_tls_settings, listen_addrs, listener_options_for, listener_socket_options, port_is_free,
 };
-use super::server::{create_tls_settings, listener_socket_options};
 use super::*;
-use crate::instances::{AppConfig, AppManager};
+use crate::instances::{AppConfig, AppManager, MaintenancePage};
 use crate::scaling::ColdStartConfig;
-use crate::socket::InstanceState;
+use crate::socket::{AppState, InstanceState};
+use bytes::Bytes;
 use parking_lot::RwLock;
+use pingora_http::Method;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -53,6 +61,7 @@ fn test_proxy_config_default() {
     assert!(!config.dev_mode);
     assert!(config.redirect_http_to_https);
     assert!(config.response_cache.is_some());
+    assert_eq!(config.client_keepalive_timeout, Duration::from_secs(60));
 }
 
 #[test]
@@ -72,6 +81,50 @@ fn listener_socket_options_enable_reuseport() {
     assert_eq!(options.so_reuseport, Some(true));
 }
 
+#[test]
+fn listen_addrs_bind_ipv6_only_when_enabled() {
+    assert_eq!(
+        listen_addrs(80, false),
+        vec!["0.0.0.0:80".parse::<std::net::SocketAddr>().unwrap()]
+    );
+    let addrs = listen_addrs(443, true);
+    assert_eq!(
+        addrs,
+        vec![
+            "0.0.0.0:443".parse::<std::net::SocketAddr>().unwrap(),
+            "[::]:443".parse().unwrap(),
+        ]
+    );
+
+    let v4 = listener_options_for(&addrs[0]);
+    assert_eq!(v4.ipv6_only, None);
+    assert_eq!(v4.so_reuseport, Some(true));
+    let v6 = listener_options_for(&addrs[1]);
+    assert_eq!(v6.ipv6_only, Some(true));
+    assert_eq!(v6.so_reuseport, Some(true));
+}
+
+#[test]
+fn port_is_free_detects_taken_ports_in_both_families() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    for ip in [
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(Ipv6Addr::LOCALHOST),
+    ] {
+        // Skip families the host has disabled (e.g. IPv6 off in CI).
+        if !port_is_free(SocketAddr::new(ip, 0)) {
+            assert!(ip.is_ipv6(), "IPv4 loopback should always be bindable");
+            continue;
+        }
+        let listener = std::net::TcpListener::bind(SocketAddr::new(ip, 0)).unwrap();
+        let taken = listener.local_addr().unwrap();
+        assert!(!port_is_free(taken), "{taken} is held by a listener");
+        drop(listener);
+        assert!(port_is_free(taken), "{taken} should be free once released");
+    }
+}
+
 #[test]
 fn test_tls_config_development() {
     let temp = TempDir::new().unwrap();
@@ -296,6 +349,39 @@ fn test_forwarded_header_parsing_handles_quotes_and_multiple_entries() {
     ));
 }
 
+#[test]
+fn test_forwarding_headers_strip_configured_client_headers() {
+    let mut request = RequestHeader::build("GET", b"/", None).expect("build request");
+    request
+        .insert_header("X-Forwarded-For", "6.6.6.6")
+        .expect("insert header");
+    request
+        .insert_header("X-Forwarded-Host", "evil.example")
+        .expect("insert header");
+    request
+        .insert_header("X-Real-IP", "6.6.6.6")
+        .expect("insert header");
+
+    let strip = vec![
+        "x-forwarded-for".to_string(),
+        "X-Forwarded-Host".to_string(),
+    ];
+    let client_ip: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+    apply_forwarding_headers(&mut request, &strip, true, Some(client_ip));
+
+    let header = |name: &str| {
+        request
+            .headers
+            .get(name)
+            .map(|v| v.to_str().unwrap().to_string())
+    };
+    assert_eq!(header("x-forwarded-for").as_deref(), Some("203.0.113.7"));
+    assert_eq!(header("x-forwarded-proto").as_deref(), Some("https"));
+    assert_eq!(header("x-forwarded-host"), None);
+    // Headers outside the list pass through untouched.
+    assert_eq!(header("x-real-ip").as_deref(), Some("6.6.6.6"));
+}
+
 #[test]
 fn body_headers_include_content_type_and_length() {
     let mut header = ResponseHeader::build(404, None).expect("build header");
@@ -353,6 +439,15 @@ fn test_strip_route_prefix_for_static_lookup_with_prefix_star() {
     assert_eq!(stripped, Some("/v2/app.js".to_string()));
 }
 
+#[test]
+fn test_rewritten_path_and_query_keeps_query_string() {
+    assert_eq!(rewritten_path_and_query("/users", None), "/users");
+    assert_eq!(
+        rewritten_path_and_query("/users", Some("page=2&q=a")),
+        "/users?page=2&q=a"
+    );
+}
+
 #[test]
 fn test_static_lookup_paths_includes_prefix_stripped_candidate() {
     let candidates =
@@ -382,6 +477,7 @@ async fn resolve_backend_waits_for_ready_on_on_demand_apps() {
     let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig {
         startup_timeout: Duration::from_secs(1),
         max_queued_requests: 100,
+        ..Default::default()
     }));
     let proxy = TakoProxy::new(lb, routes, ProxyConfig::default(), cold_start.clone());
 
@@ -416,6 +512,7 @@ async fn resolve_backend_returns_startup_timeout_after_wait_timeout() {
     let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig {
         startup_timeout: Duration::from_millis(25),
         max_queued_requests: 100,
+        ..Default::default()
     }));
     let proxy = TakoProxy::new(lb, routes, ProxyConfig::default(), cold_start.clone());
 
@@ -441,6 +538,7 @@ async fn resolve_backend_returns_startup_failed_when_cold_start_fails() {
     let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig {
         startup_timeout: Duration::from_secs(1),
         max_queued_requests: 100,
+        ..Default::default()
     }));
     let proxy = TakoProxy::new(lb, routes, ProxyConfig::default(), cold_start.clone());
 
@@ -471,6 +569,7 @@ async fn resolve_backend_returns_queue_full_when_cold_start_queue_is_full() {
     let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig {
         startup_timeout: Duration::from_secs(1),
         max_queued_requests: 1,
+        ..Default::default()
     }));
     let proxy = Arc::new(TakoProxy::new(
         lb,
@@ -513,6 +612,93 @@ async fn resolve_backend_returns_unavailable_for_non_on_demand_apps_without_back
     assert!(matches!(resolution, BackendResolution::Unavailable));
 }
 
+#[test]
+fn maintenance_is_served_only_when_the_app_cannot_take_the_request() {
+    // Healthy instances always get the traffic.
+    for state in [AppState::Running, AppState::Error, AppState::Stopped] {
+        assert!(!serves_maintenance(state, 1, false));
+        assert!(!serves_maintenance(state, 2, true));
+    }
+    // Stopped or errored apps are down regardless of scaling mode.
+    for on_demand in [false, true] {
+        assert!(serves_maintenance(AppState::Stopped, 0, on_demand));
+        assert!(serves_maintenance(AppState::Error, 0, on_demand));
+    }
+    // An always-on app with nothing healthy is down.
+    assert!(serves_maintenance(AppState::Running, 0, false));
+    assert!(serves_maintenance(AppState::Deploying, 0, false));
+    // An on-demand app without instances is cold started instead.
+    assert!(!serves_maintenance(AppState::Idle, 0, true));
+    assert!(!serves_maintenance(AppState::Running, 0, true));
+}
+
+#[tokio::test]
+async fn resolve_backend_serves_maintenance_page_for_stopped_app() {
+    let manager = Arc::new(AppManager::new(PathBuf::from("/tmp/tako-test")));
+    let lb = Arc::new(LoadBalancer::new(manager.clone()));
+    let page = MaintenancePage {
+        status: 503,
+        body: "<h1>Back soon</h1>".to_string(),
+    };
+    let app = manager.register_app(AppConfig {
+        name: "test-app".to_string(),
+        version: "v1".to_string(),
+        min_instances: 0,
+        maintenance: Some(page.clone()),
+        ..Default::default()
+    });
+    app.set_state(AppState::Stopped);
+    lb.register_app(app);
+
+    let routes = Arc::new(tokio::sync::RwLock::new(RouteTable::default()));
+    let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig::default()));
+    let proxy = TakoProxy::new(lb, routes, ProxyConfig::default(), cold_start);
+
+    let resolution = proxy.resolve_backend("test-app").await;
+    assert!(matches!(resolution, BackendResolution::Maintenance(served) if served == page));
+}
+
+#[tokio::test]
+async fn resolve_backend_keeps_drained_apps_out_of_rotation() {
+    let manager = Arc::new(AppManager::new(PathBuf::from("/tmp/tako-test")));
+    let lb = Arc::new(LoadBalancer::new(manager.clone()));
+    let app = manager.register_app(AppConfig {
+        name: "test-app".to_string(),
+        version: "v1".to_string(),
+        min_instances: 0,
+        ..Default::default()
+    });
+    let instance = app.allocate_instance();
+    instance.set_state(InstanceState::Healthy);
+    app.set_drained(true);
+    lb.register_app(app.clone());
+
+    let routes = Arc::new(tokio::sync::RwLock::new(RouteTable::default()));
+    let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig::default()));
+    let proxy = TakoProxy::new(lb, routes, ProxyConfig::default(), cold_start);
+
+    // On-demand, yet no cold start: the running instance is just held back.
+    let resolution = proxy.resolve_backend("test-app").await;
+    assert!(matches!(resolution, BackendResolution::Unavailable));
+    assert_eq!(app.get_instances().len(), 1);
+
+    let page = MaintenancePage {
+        status: 503,
+        body: "<h1>Draining</h1>".to_string(),
+    };
+    let mut config = app.config.read().clone();
+    config.maintenance = Some(page.clone());
+    app.update_config(config);
+    let resolution = proxy.resolve_backend("test-app").await;
+    assert!(matches!(resolution, BackendResolution::Maintenance(served) if served == page));
+
+    app.set_drained(false);
+    let resolution = proxy.resolve_backend("test-app").await;
+    assert!(
+        matches!(resolution, BackendResolution::Ready(backend) if backend.instance_id == instance.id)
+    );
+}
+
 #[tokio::test]
 async fn resolve_backend_returns_app_missing_when_app_not_registered() {
     let manager = Arc::new(AppManager::new(PathBuf::from("/tmp/tako-test")));
@@ -533,7 +719,9 @@ async fn load_balancer_cleanup_removes_stale_routes_for_app() {
     let routes = Arc::new(tokio::sync::RwLock::new(RouteTable::default()));
     {
         let mut table = routes.write().await;
-        table.set_app_routes("test-app".to_string(), vec!["test.example.com".to_string()]);
+        table
+            .set_app_routes("test-app".to_string(), vec!["test.example.com".to_string()])
+            .unwrap();
         assert_eq!(
             table.select("test.example.com", "/"),
             Some("test-app".to_string())
@@ -607,3 +795,545 @@ fn test_proxy_builder_with_acme() {
     let builder = ProxyBuilder::new(lb).acme_tokens(tokens);
     assert!(builder.acme_tokens.is_some());
 }
+
+#[test]
+fn access_log_sampler_logs_one_in_n_successes_and_every_server_error() {
+    let sampler = AccessLogSampler::new();
+
+    let logged_ok = (0..100)
+        .filter(|_| sampler.should_log("app", 200, 10))
+        .count();
+    let logged_errors = (0..100)
+        .filter(|_| sampler.should_log("app", 500, 10))
+        .count();
+
+    assert_eq!(logged_ok, 10);
+    assert_eq!(logged_errors, 100);
+}
+
+#[test]
+fn access_log_sampler_logs_everything_when_rate_is_one() {
+    let sampler = AccessLogSampler::new();
+    assert!((0..20).all(|_| sampler.should_log("app", 200, 1)));
+}
+
+fn sample_access_log_record() -> AccessLogRecord<'static> {
+    use chrono::TimeZone;
+
+    AccessLogRecord {
+        time: chrono::Utc.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap(),
+        client_ip: Some("203.0.113.9".parse().unwrap()),
+        method: "GET",
+        uri: "/api/items?page=2",
+        version: "HTTP/1.1",
+        host: "api.example.com",
+        status: 200,
+        bytes_sent: 512,
+        referer: None,
+        user_agent: Some("curl/8.5.0"),
+        app: Some("my-app/production"),
+        instance: Some("a1b2c3"),
+        latency: Duration::from_micros(12_345),
+    }
+}
+
+#[test]
+fn access_log_combined_line() {
+    assert_eq!(
+        sample_access_log_record().format(AccessLogFormat::Combined),
+        r#"203.0.113.9 - - [04/Mar/2026:05:06:07 +0000] "GET /api/items?page=2 HTTP/1.1" 200 512 "-" "curl/8.5.0" app=my-app/production instance=a1b2c3 latency_ms=12.345"#
+    );
+}
+
+#[test]
+fn access_log_json_line() {
+    let line = sample_access_log_record().format(AccessLogFormat::Json);
+    assert!(!line.contains('\n'));
+    let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "time": "2026-03-04T05:06:07.000Z",
+            "client_ip": "203.0.113.9",
+            "method": "GET",
+            "uri": "/api/items?page=2",
+            "version": "HTTP/1.1",
+            "host": "api.example.com",
+            "status": 200,
+            "bytes_sent": 512,
+            "referer": null,
+            "user_agent": "curl/8.5.0",
+            "app": "my-app/production",
+            "instance": "a1b2c3",
+            "latency_ms": 12.345,
+        })
+    );
+}
+
+/// Serve one request on a fresh HTTP/1.1 session with the client keep-alive
+/// applied, then hand back the reused downstream session the way Pingora does.
+async fn serve_one_request_and_reuse(
+    idle_timeout: Duration,
+) -> (
+    tokio::io::DuplexStream,
+    pingora_core::protocols::http::ServerSession,
+) {
+    use pingora_core::apps::HttpPersistentSettings;
+    use pingora_core::protocols::http::ServerSession;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (mut client, server) = tokio::io::duplex(4096);
+    let mut session = ServerSession::new_http1(Box::new(server));
+
+    client
+        .write_all(b"GET / HTTP/1.1\r\nHost: app.test\r\n\r\n")
+        .await
+        .unwrap();
+    assert!(session.read_request().await.unwrap());
+    super::limits::apply_client_keepalive(&mut session, idle_timeout);
+
+    let mut header = ResponseHeader::build(200, None).unwrap();
+    header.insert_header("Content-Length", "0").unwrap();
+    session
+        .write_response_header(Box::new(header))
+        .await
+        .unwrap();
+
+    let settings = HttpPersistentSettings::for_session(&session);
+    let stream = session
+        .finish()
+        .await
+        .unwrap()
+        .expect("connection should be reusable");
+    let mut reused = ServerSession::new_http1(stream);
+    settings.apply_to_session(&mut reused);
+
+    let mut buf = [0u8; 256];
+    let n = client.read(&mut buf).await.unwrap();
+    assert!(String::from_utf8_lossy(&buf[..n]).starts_with("HTTP/1.1 200"));
+
+    (client, reused)
+}
+
+#[tokio::test]
+async fn client_keepalive_timeout_closes_idle_connection() {
+    let (_client, mut reused) = serve_one_request_and_reuse(Duration::from_secs(1)).await;
+
+    let started = std::time::Instant::now();
+    let got_request = tokio::time::timeout(Duration::from_secs(5), reused.read_request())
+        .await
+        .expect("idle connection should be closed by the keep-alive timeout")
+        .unwrap();
+
+    assert!(!got_request);
+    assert!(started.elapsed() >= Duration::from_millis(900));
+}
+
+#[tokio::test]
+async fn client_keepalive_timeout_keeps_active_connection_open() {
+    use tokio::io::AsyncWriteExt;
+
+    let (mut client, mut reused) = serve_one_request_and_reuse(Duration::from_secs(1)).await;
+
+    let writer = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        client
+            .write_all(b"GET /next HTTP/1.1\r\nHost: app.test\r\n\r\n")
+            .await
+            .unwrap();
+        client
+    });
+
+    assert!(reused.read_request().await.unwrap());
+    assert_eq!(reused.req_header().uri.path(), "/next");
+    drop(writer.await.unwrap());
+}
+
+#[tokio::test]
+async fn client_keepalive_timeout_respects_connection_close() {
+    use pingora_core::protocols::http::ServerSession;
+    use tokio::io::AsyncWriteExt;
+
+    let (mut client, server) = tokio::io::duplex(4096);
+    let mut session = ServerSession::new_http1(Box::new(server));
+    client
+        .write_all(b"GET / HTTP/1.1\r\nHost: app.test\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    assert!(session.read_request().await.unwrap());
+
+    super::limits::apply_client_keepalive(&mut session, Duration::from_secs(30));
+    assert_eq!(session.get_keepalive(), None);
+}
+
+fn response_header(content_type: &str) -> ResponseHeader {
+    let mut header = ResponseHeader::build(200, None).unwrap();
+    header.insert_header("Content-Type", content_type).unwrap();
+    header
+}
+
+fn rate_limit(requests_per_second: u32, burst: u32, per_client_ip: bool) -> tako_core::RateLimit {
+    tako_core::RateLimit {
+        requests_per_second,
+        burst: Some(burst),
+        per_client_ip,
+    }
+}
+
+#[test]
+fn upstream_retry_is_limited_to_idempotent_methods_by_default() {
+    for method in [
+        Method::GET,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::PUT,
+        Method::DELETE,
+    ] {
+        assert!(should_retry_upstream(&method, false, 0), "{method}");
+    }
+    for method in [Method::POST, Method::PATCH] {
+        assert!(!should_retry_upstream(&method, false, 0), "{method}");
+        assert!(should_retry_upstream(&method, true, 0), "{method}");
+    }
+}
+
+#[test]
+fn upstream_retry_stops_after_the_bound() {
+    use super::request::MAX_UPSTREAM_RETRIES;
+
+    for retries in 0..MAX_UPSTREAM_RETRIES {
+        assert!(should_retry_upstream(&Method::GET, false, retries));
+    }
+    assert!(!should_retry_upstream(
+        &Method::GET,
+        false,
+        MAX_UPSTREAM_RETRIES
+    ));
+    assert!(!should_retry_upstream(
+        &Method::POST,
+        true,
+        MAX_UPSTREAM_RETRIES
+    ));
+}
+
+#[test]
+fn token_bucket_refills_at_rate_up_to_burst() {
+    let limit = rate_limit(10, 5, false);
+    let start = std::time::Instant::now();
+    let mut bucket = TokenBucket::full(&limit, start);
+    assert_eq!(bucket.tokens(), 5.0);
+
+    for _ in 0..5 {
+        bucket.try_take(&limit, start).unwrap();
+    }
+    // Empty: the next token arrives after 1/10 s.
+    let wait = bucket.try_take(&limit, start).unwrap_err();
+    assert_eq!(wait, Duration::from_millis(100));
+
+    // 250ms refills 2.5 tokens.
+    let later = start + Duration::from_millis(250);
+    bucket.try_take(&limit, later).unwrap();
+    assert!((bucket.tokens() - 1.5).abs() < 1e-9);
+
+    // A long pause never overfills the bucket.
+    let much_later = later + Duration::from_secs(60);
+    bucket.try_take(&limit, much_later).unwrap();
+    assert_eq!(bucket.tokens(), 4.0);
+}
+
+#[test]
+fn retry_after_rounds_up_to_whole_seconds() {
+    assert_eq!(retry_after_secs(Duration::from_millis(1)), 1);
+    assert_eq!(retry_after_secs(Duration::from_secs(2)), 2);
+    assert_eq!(retry_after_secs(Duration::from_millis(2001)), 3);
+    assert_eq!(retry_after_secs(Duration::ZERO), 1);
+}
+
+#[test]
+fn rate_limiter_rejects_burst_beyond_bucket_per_app() {
+    let limiter = RateLimiter::new();
+    let limit = rate_limit(1, 3, false);
+    let now = std::time::Instant::now();
+    let ip_a = Some("10.0.0.1".parse().unwrap());
+    let ip_b = Some("10.0.0.2".parse().unwrap());
+
+    // Shared bucket: three requests from any client, then 429.
+    assert!(limiter.check_at("web", ip_a, &limit, now).is_ok());
+    assert!(limiter.check_at("web", ip_b, &limit, now).is_ok());
+    assert!(limiter.check_at("web", ip_a, &limit, now).is_ok());
+    let wait = limiter.check_at("web", ip_b, &limit, now).unwrap_err();
+    assert_eq!(retry_after_secs(wait), 1);
+
+    // Other apps have their own bucket.
+    assert!(limiter.check_at("api", ip_a, &limit, now).is_ok());
+
+    // One second later one more request fits.
+    let later = now + Duration::from_secs(1);
+    assert!(limiter.check_at("web", ip_a, &limit, later).is_ok());
+    assert!(limiter.check_at("web", ip_a, &limit, later).is_err());
+
+    limiter.remove_app("web");
+    assert!(limiter.check_at("web", ip_a, &limit, later).is_ok());
+}
+
+#[test]
+fn rate_limiter_gives_each_client_ip_its_own_bucket_when_configured() {
+    let limiter = RateLimiter::new();
+    let limit = rate_limit(1, 1, true);
+    let now = std::time::Instant::now();
+    let ip_a = Some("10.0.0.1".parse().unwrap());
+    let ip_b = Some("10.0.0.2".parse().unwrap());
+
+    assert!(limiter.check_at("web", ip_a, &limit, now).is_ok());
+    assert!(limiter.check_at("web", ip_a, &limit, now).is_err());
+    assert!(limiter.check_at("web", ip_b, &limit, now).is_ok());
+}
+
+fn compressible_response(content_type: &str, content_length: Option<u64>) -> ResponseHeader {
+    let mut header = ResponseHeader::build(200, None).unwrap();
+    header.insert_header("content-type", content_type).unwrap();
+    if let Some(length) = content_length {
+        header
+            .insert_header("content-length", length.to_string())
+            .unwrap();
+    }
+    header
+}
+
+#[test]
+fn compresses_text_like_content_types_only() {
+    for content_type in [
+        "text/html; charset=utf-8",
+        "text/css",
+        "application/json",
+        "application/javascript",
+        "application/problem+json",
+        "image/svg+xml",
+        "Application/JSON",
+    ] {
+        let header = compressible_response(content_type, Some(4096));
+        assert!(should_compress(&header, 1024), "{content_type}");
+    }
+    for content_type in [
+        "image/png",
+        "video/mp4",
+        "application/zip",
+        "application/octet-stream",
+        "font/woff2",
+        "text/event-stream",
+    ] {
+        let header = compressible_response(content_type, Some(4096));
+        assert!(!should_compress(&header, 1024), "{content_type}");
+    }
+
+    let mut untyped = ResponseHeader::build(200, None).unwrap();
+    untyped.insert_header("content-length", "4096").unwrap();
+    assert!(!should_compress(&untyped, 1024));
+}
+
+#[test]
+fn compression_respects_size_threshold() {
+    assert!(!should_compress(
+        &compressible_response("text/html", Some(1023)),
+        1024
+    ));
+    assert!(should_compress(
+        &compressible_response("text/html", Some(1024)),
+        1024
+    ));
+    // Streamed bodies of unknown length are compressed.
+    assert!(should_compress(
+        &compressible_response("text/html", None),
+        1024
+    ));
+}
+
+#[test]
+fn compression_skips_encoded_and_bodiless_responses() {
+    let mut encoded = compressible_response("application/json", Some(4096));
+    encoded.insert_header("content-encoding", "br").unwrap();
+    assert!(!should_compress(&encoded, 1024));
+
+    let mut identity = compressible_response("application/json", Some(4096));
+    identity
+        .insert_header("content-encoding", "identity")
+        .unwrap();
+    assert!(should_compress(&identity, 1024));
+
+    let mut no_transform = compressible_response("text/html", Some(4096));
+    no_transform
+        .insert_header("cache-control", "public, no-transform")
+        .unwrap();
+    assert!(!should_compress(&no_transform, 1024));
+
+    for status in [101, 204, 206, 304] {
+        let mut header = compressible_response("text/html", Some(4096));
+        header.set_status(status).unwrap();
+        assert!(!should_compress(&header, 1024), "{status}");
+    }
+}
+
+#[test]
+fn holds_body_until_end_of_stream() {
+    let mut buffer = ResponseBuffer::for_response(&response_header("text/html"), 1024).unwrap();
+
+    let mut body = Some(Bytes::from_static(b"hello "));
+    buffer.filter(&mut body, false);
+    assert_eq!(body, None);
+
+    let mut body = Some(Bytes::from_static(b"world"));
+    buffer.filter(&mut body, true);
+    assert_eq!(body, Some(Bytes::from_static(b"hello world")));
+}
+
+#[test]
+fn streams_once_limit_is_exceeded() {
+    let mut buffer = ResponseBuffer::for_response(&response_header("text/html"), 8).unwrap();
+
+    let mut body = Some(Bytes::from_static(b"abcde"));
+    buffer.filter(&mut body, false);
+    assert_eq!(body, None);
+
+    let mut body = Some(Bytes::from_static(b"fghij"));
+    buffer.filter(&mut body, false);
+    assert_eq!(body, Some(Bytes::from_static(b"abcdefghij")));
+
+    let mut body = Some(Bytes::from_static(b"klm"));
+    buffer.filter(&mut body, true);
+    assert_eq!(body, Some(Bytes::from_static(b"klm")));
+}
+
+#[test]
+fn event_streams_and_disabled_limit_are_not_buffered() {
+    assert!(ResponseBuffer::for_response(&response_header("text/event-stream"), 1024).is_none());
+    assert!(ResponseBuffer::for_response(&response_header("text/html"), 0).is_none());
+}
+
+#[tokio::test]
+async fn buffered_response_releases_instance_before_slow_client_reads() {
+    let manager = Arc::new(AppManager::new(PathBuf::from("/tmp/tako-test")));
+    let lb = Arc::new(LoadBalancer::new(manager.clone()));
+    let app = manager.register_app(AppConfig {
+        name: "test-app".to_string(),
+        version: "v1".to_string(),
+        response_buffer_bytes: 1024,
+        ..Default::default()
+    });
+    lb.register_app(app.clone());
+    let instance = app.allocate_instance();
+    instance.set_state(InstanceState::Healthy);
+
+    let routes = Arc::new(tokio::sync::RwLock::new(RouteTable::default()));
+    let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig::default()));
+    let proxy = TakoProxy::new(lb.clone(), routes, ProxyConfig::default(), cold_start);
+
+    // The client end is never read: the downstream write is as slow as it gets.
+    let (_client, server) = tokio::io::duplex(64);
+    let mut session = pingora_proxy::Session::new_h1(Box::new(server));
+    let mut ctx = proxy.new_ctx();
+    ctx.backend = lb.get_backend("test-app");
+    instance.request_started();
+
+    let mut header = response_header("text/html");
+    proxy
+        .upstream_response_filter(&mut session, &mut header, &mut ctx)
+        .await
+        .unwrap();
+
+    let mut body = Some(Bytes::from_static(b"first "));
+    proxy
+        .upstream_response_body_filter(&mut session, &mut body, false, &mut ctx)
+        .unwrap();
+    assert_eq!(body, None);
+    assert_eq!(instance.in_flight(), 1);
+
+    let mut body = Some(Bytes::from_static(b"last"));
+    proxy
+        .upstream_response_body_filter(&mut session, &mut body, true, &mut ctx)
+        .unwrap();
+    assert_eq!(body, Some(Bytes::from_static(b"first last")));
+    assert_eq!(instance.in_flight(), 0);
+
+    // Finishing the request later must not release the backend twice.
+    proxy.release_backend(&mut ctx);
+    assert_eq!(instance.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn request_over_app_body_limit_is_rejected_before_upstream() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let upstream = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let upstream_port = upstream.local_addr().unwrap().port();
+    let upstream_requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let accepted = upstream_requests.clone();
+    tokio::spawn(async move {
+        while upstream.accept().await.is_ok() {
+            accepted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    });
+
+    let manager = Arc::new(AppManager::new(PathBuf::from("/tmp/tako-test")));
+    let lb = Arc::new(LoadBalancer::new(manager.clone()));
+    let app = manager.register_app(AppConfig {
+        name: "test-app".to_string(),
+        version: "v1".to_string(),
+        max_body_bytes: 1024,
+        max_header_bytes: 256,
+        ..Default::default()
+    });
+    lb.register_app(app.clone());
+    let instance = app.allocate_instance();
+    instance.set_port(upstream_port);
+    instance.set_state(InstanceState::Healthy);
+
+    let mut route_table = RouteTable::default();
+    route_table
+        .set_app_routes("test-app".to_string(), vec!["app.test".to_string()])
+        .unwrap();
+    let routes = Arc::new(tokio::sync::RwLock::new(route_table));
+    let cold_start = Arc::new(ColdStartManager::new(ColdStartConfig::default()));
+    let config = ProxyConfig {
+        redirect_http_to_https: false,
+        ..ProxyConfig::default()
+    };
+    let proxy = TakoProxy::new(lb, routes, config, cold_start);
+
+    for (request, status) in [
+        (
+            "POST /upload HTTP/1.1\r\nHost: app.test\r\nContent-Length: 4096\r\n\r\n".to_string(),
+            "413",
+        ),
+        (
+            format!(
+                "GET / HTTP/1.1\r\nHost: app.test\r\nCookie: {}\r\n\r\n",
+                "a".repeat(512)
+            ),
+            "431",
+        ),
+    ] {
+        let (mut client, server) = tokio::io::duplex(8192);
+        let mut session = pingora_proxy::Session::new_h1(Box::new(server));
+        client.write_all(request.as_bytes()).await.unwrap();
+        assert!(session.read_request().await.unwrap());
+
+        let mut ctx = proxy.new_ctx();
+        assert!(proxy.request_filter(&mut session, &mut ctx).await.unwrap());
+        assert!(ctx.backend.is_none());
+
+        let mut buf = [0u8; 256];
+        let n = client.read(&mut buf).await.unwrap();
+        let response = String::from_utf8_lossy(&buf[..n]);
+        assert!(
+            response.starts_with(&format!("HTTP/1.1 {status}")),
+            "got: {response}"
+        );
+    }
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        upstream_requests.load(std::sync::atomic::Ordering::SeqCst),
+        0
+    );
+    assert_eq!(instance.in_flight(), 0);
+}
@@ -3,7 +3,7 @@ use crate::app_command::{
 };
 use crate::instances::AppConfig;
 use crate::socket::{AppState, BuildStatus, InstanceState, InstanceStatus};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::process::ExitStatus;
 use std::time::Duration;
@@ -114,6 +114,23 @@ pub(crate) fn apply_release_runtime_to_config(
     config.command = command_from_manifest(&manifest, &release_path, runtime_bin)?;
     config.env_vars = manifest.env_vars;
     config.idle_timeout = Duration::from_secs(u64::from(manifest.idle_timeout));
+    config.access_log_sample_rate = manifest.access_log_sample_rate.unwrap_or(1).max(1);
+    config.strip_request_headers = manifest.strip_request_headers;
+    config.max_restart_attempts = manifest
+        .max_restart_attempts
+        .unwrap_or(crate::defaults::DEFAULT_MAX_RESTART_ATTEMPTS)
+        .max(1);
+    config.max_restart_backoff = manifest
+        .max_restart_backoff
+        .map(|secs| Duration::from_secs(u64::from(secs)))
+        .unwrap_or(crate::defaults::DEFAULT_MAX_RESTART_BACKOFF);
+    config.response_buffer_bytes = manifest.response_buffer_bytes.unwrap_or(0);
+    config.max_body_bytes = manifest.max_body_bytes.unwrap_or(0);
+    config.max_header_bytes = manifest.max_header_bytes.unwrap_or(0);
+    config.rate_limit = manifest
+        .rate_limit
+        .filter(|limit| limit.requests_per_second > 0);
+    config.retry_non_idempotent = manifest.retry_non_idempotent;
     config.path = safe_subdir(&release_path, &manifest.app_dir)
         .map_err(|e| format!("Invalid app_dir in manifest: {e}"))?;
     Ok(())
@@ -196,6 +213,14 @@ pub(crate) async fn prepare_release_runtime(
         ));
     }
 
+    if !manifest.files.is_empty() {
+        let dir = release_dir.to_path_buf();
+        let files = manifest.files.clone();
+        tokio::task::spawn_blocking(move || verify_release_files(&dir, &files))
+            .await
+            .map_err(|e| format!("release verification task failed: {e}"))??;
+    }
+
     if manifest.runtime_version.is_none() {
         tracing::warn!(runtime = %runtime, "Could not detect runtime version; using latest. Pin a version with runtime_version in tako.toml");
     }
@@ -269,9 +294,97 @@ pub(crate) async fn prepare_release_runtime(
         }
     }
 
+    run_predeploy_command(
+        &manifest.predeploy,
+        &app_dir,
+        &install_env,
+        crate::release_command::RELEASE_COMMAND_TIMEOUT,
+    )
+    .await?;
+
     Ok(runtime_bin)
 }
 
+/// Check each file in the manifest's `files` digest map against its SHA-256
+/// on disk, so a partial or corrupt upload fails before install or launch.
+pub(crate) fn verify_release_files(
+    release_dir: &Path,
+    files: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    for (relative, expected) in files {
+        let path = safe_subdir(release_dir, relative)
+            .map_err(|e| format!("Invalid file path in manifest: {e}"))?;
+        let actual = match sha256_file(&path) {
+            Ok(actual) => actual,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(format!(
+                    "release integrity check failed: '{relative}' is missing"
+                ));
+            }
+            Err(e) => {
+                return Err(format!(
+                    "release integrity check failed: could not read '{relative}': {e}"
+                ));
+            }
+        };
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(format!(
+                "release integrity check failed: '{relative}' has sha256 {actual}, expected {}",
+                expected.trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Run the manifest's `predeploy` command, killing it once `limit` passes.
+/// Like the release command it must not hang a deploy, so it shares the
+/// release command's time limit.
+async fn run_predeploy_command(
+    command: &[String],
+    app_dir: &Path,
+    env: &HashMap<String, String>,
+    limit: Duration,
+) -> Result<(), String> {
+    let Some((program, args)) = command.split_first() else {
+        return Ok(());
+    };
+    tracing::info!(app_dir = %app_dir.display(), "Running predeploy command: {}", command.join(" "));
+    let mut cmd = TokioCommand::new(program);
+    cmd.args(args)
+        .current_dir(app_dir)
+        .envs(env)
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    drop_privileges_if_root(&mut cmd);
+    let output = match tokio::time::timeout(limit, cmd.output()).await {
+        Ok(output) => output.map_err(|e| format!("Failed to run predeploy command: {e}"))?,
+        Err(_) => {
+            return Err(format!(
+                "predeploy command timed out after {}s",
+                limit.as_secs()
+            ));
+        }
+    };
+    if !output.status.success() {
+        return Err(format_process_failure(
+            "predeploy command",
+            output.status,
+            &output.stdout,
+            &output.stderr,
+        ));
+    }
+    Ok(())
+}
+
 pub(crate) async fn resolve_release_runtime_bin(
     release_dir: &Path,
     data_dir: &Path,
@@ -326,14 +439,18 @@ fn format_process_failure(
     }
 }
 
-pub(crate) fn is_private_local_hostname(domain: &str) -> bool {
-    let host = domain
+fn normalize_route_host(domain: &str) -> String {
+    domain
         .split(':')
         .next()
         .unwrap_or(domain)
         .trim()
         .trim_end_matches('.')
-        .to_ascii_lowercase();
+        .to_ascii_lowercase()
+}
+
+pub(crate) fn is_private_local_hostname(domain: &str) -> bool {
+    let host = normalize_route_host(domain);
 
     if host.is_empty() {
         return false;
@@ -352,8 +469,29 @@ pub(crate) fn is_private_local_hostname(domain: &str) -> bool {
         || host.ends_with(".home.arpa")
 }
 
-pub(crate) fn should_use_self_signed_route_cert(domain: &str) -> bool {
-    is_private_local_hostname(domain)
+/// Normalize an operator-supplied private suffix (`.corp`, `*.corp`,
+/// `Corp.`) to its bare lowercase form. `None` when nothing is left.
+pub(crate) fn normalize_private_domain_suffix(suffix: &str) -> Option<String> {
+    let suffix = suffix
+        .trim()
+        .trim_start_matches('*')
+        .trim_matches('.')
+        .to_ascii_lowercase();
+    (!suffix.is_empty()).then_some(suffix)
+}
+
+/// Whether a route domain gets a self-signed certificate instead of ACME:
+/// built-in private/local names plus any operator-configured suffixes
+/// (already normalized with [`normalize_private_domain_suffix`]).
+pub(crate) fn should_use_self_signed_route_cert(domain: &str, extra_suffixes: &[String]) -> bool {
+    if is_private_local_hostname(domain) {
+        return true;
+    }
+    let host = normalize_route_host(domain);
+    extra_suffixes.iter().any(|suffix| {
+        host.strip_suffix(suffix.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.ends_with('.'))
+    })
 }
 
 fn validate_app_name_segment(app_name: &str) -> Result<(), String> {
@@ -454,6 +592,36 @@ pub(crate) fn validate_release_path_for_app(
     Ok(release_path)
 }
 
+/// Check that the manifest file the runtime reads (e.g. `package.json` for
+/// JavaScript runtimes) exists in the release app directory.
+pub(crate) fn validate_release_runtime_manifest(release_dir: &Path) -> Result<(), String> {
+    let manifest = load_release_manifest(release_dir)?;
+    let app_dir = safe_subdir(release_dir, &manifest.app_dir)?;
+    let Some(def) = tako_runtime::runtime_def_for(&manifest.runtime, None) else {
+        return Ok(());
+    };
+    if let Some(main_manifest) = def.entrypoint.manifest
+        && !app_dir.join(&main_manifest.file).is_file()
+    {
+        return Err(format!(
+            "release is missing {} in {}",
+            main_manifest.file,
+            app_dir.display()
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_deploy_env(env: &HashMap<String, String>) -> Result<(), String> {
+    match env
+        .keys()
+        .find(|key| key.is_empty() || key.contains('=') || key.contains('\0'))
+    {
+        Some(key) => Err(format!("Invalid env var name: '{}'", key)),
+        None => Ok(()),
+    }
+}
+
 pub(crate) fn validate_deploy_routes(routes: &[String]) -> Result<(), String> {
     if routes.is_empty() {
         return Err("Deploy rejected: app must define at least one route".to_string());
@@ -464,6 +632,27 @@ pub(crate) fn validate_deploy_routes(routes: &[String]) -> Result<(), String> {
     Ok(())
 }
 
+pub(crate) fn validate_canary(
+    canary: Option<u8>,
+    strategy: tako_core::DeployStrategy,
+) -> Result<(), String> {
+    let Some(percent) = canary else {
+        return Ok(());
+    };
+    if !(1..=99).contains(&percent) {
+        return Err(format!(
+            "Deploy rejected: canary percentage must be between 1 and 99, got {percent}"
+        ));
+    }
+    if strategy != tako_core::DeployStrategy::Rolling {
+        return Err(
+            "Deploy rejected: canary deploys can't be combined with the blue_green strategy"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -495,6 +684,146 @@ mod tests {
         assert!(paths.tako.is_dir());
     }
 
+    fn write_predeploy_release(release_dir: &Path, predeploy: &[&str]) {
+        std::fs::write(
+            release_dir.join("app.json"),
+            serde_json::json!({
+                "runtime": "custom",
+                "main": "index.js",
+                "idle_timeout": 300,
+                "predeploy": predeploy,
+            })
+            .to_string(),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn prepare_release_runtime_runs_predeploy_with_deploy_env() {
+        let temp = TempDir::new().unwrap();
+        write_predeploy_release(
+            temp.path(),
+            &["/bin/sh", "-c", "printf '%s' \"$MIGRATION\" > migrated"],
+        );
+        let env = HashMap::from([("MIGRATION".to_string(), "done".to_string())]);
+
+        prepare_release_runtime(temp.path(), &env, &temp.path().join("data"))
+            .await
+            .unwrap();
+
+        let marker = std::fs::read_to_string(temp.path().join("migrated")).unwrap();
+        assert_eq!(marker, "done");
+    }
+
+    #[tokio::test]
+    async fn prepare_release_runtime_fails_when_predeploy_exits_non_zero() {
+        let temp = TempDir::new().unwrap();
+        write_predeploy_release(
+            temp.path(),
+            &["/bin/sh", "-c", "echo 'migration failed' >&2; exit 1"],
+        );
+
+        let err = prepare_release_runtime(temp.path(), &HashMap::new(), &temp.path().join("data"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err, "predeploy command (exit code 1): migration failed");
+    }
+
+    #[tokio::test]
+    async fn predeploy_command_is_killed_after_its_time_limit() {
+        let temp = TempDir::new().unwrap();
+        let command = ["/bin/sh", "-c", "sleep 5; touch finished"].map(String::from);
+
+        let err = run_predeploy_command(
+            &command,
+            temp.path(),
+            &HashMap::new(),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.starts_with("predeploy command timed out"), "{err}");
+        assert!(!temp.path().join("finished").exists());
+    }
+
+    fn write_release_with_digests(release_dir: &Path, files: serde_json::Value) {
+        std::fs::create_dir_all(release_dir.join("dist")).unwrap();
+        std::fs::write(release_dir.join("dist/index.js"), "console.log('ok')").unwrap();
+        std::fs::write(
+            release_dir.join("app.json"),
+            serde_json::json!({
+                "runtime": "custom",
+                "main": "dist/index.js",
+                "idle_timeout": 300,
+                "files": files,
+            })
+            .to_string(),
+        )
+        .unwrap();
+    }
+
+    fn sha256_hex(contents: &[u8]) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(contents))
+    }
+
+    #[tokio::test]
+    async fn prepare_release_runtime_accepts_matching_file_digests() {
+        let temp = TempDir::new().unwrap();
+        write_release_with_digests(
+            temp.path(),
+            serde_json::json!({ "dist/index.js": sha256_hex(b"console.log('ok')") }),
+        );
+
+        prepare_release_runtime(temp.path(), &HashMap::new(), &temp.path().join("data"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn prepare_release_runtime_rejects_tampered_file() {
+        let temp = TempDir::new().unwrap();
+        let expected = sha256_hex(b"console.log('original')");
+        write_release_with_digests(
+            temp.path(),
+            serde_json::json!({ "dist/index.js": expected }),
+        );
+
+        let err = prepare_release_runtime(temp.path(), &HashMap::new(), &temp.path().join("data"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            format!(
+                "release integrity check failed: 'dist/index.js' has sha256 {}, expected {expected}",
+                sha256_hex(b"console.log('ok')")
+            )
+        );
+    }
+
+    #[test]
+    fn verify_release_files_names_missing_file() {
+        let temp = TempDir::new().unwrap();
+        let files = BTreeMap::from([("dist/server.js".to_string(), sha256_hex(b"x"))]);
+
+        let err = verify_release_files(temp.path(), &files).unwrap_err();
+
+        assert_eq!(
+            err,
+            "release integrity check failed: 'dist/server.js' is missing"
+        );
+    }
+
+    #[test]
+    fn verify_release_files_rejects_paths_outside_release() {
+        let temp = TempDir::new().unwrap();
+        let files = BTreeMap::from([("../../etc/passwd".to_string(), sha256_hex(b"x"))]);
+        assert!(verify_release_files(temp.path(), &files).is_err());
+    }
+
     #[test]
     fn inject_app_data_dir_env_sets_tako_data_dir() {
         let mut env = HashMap::new();
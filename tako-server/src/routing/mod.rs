@@ -0,0 +1,406 @@
+//! Server-side routing: match incoming requests (Host + path) to an app.
+//!
+//! This is intentionally pure logic (no Pingora types) to keep it easy to test.
+//!
+//! A path-prefixed pattern may end with ` -> strip` (for example
+//! `example.com/api/* -> strip`) to forward requests with the matched prefix
+//! removed, so the app sees `/users` instead of `/api/users`. It may also end
+//! with ` -> prepend /v1` to forward `/foo` as `/v1/foo`; both directives can
+//! be chained (`example.com/api/* -> strip -> prepend /v1`), and stripping
+//! always happens first.
+//!
+//! Routes can carry an explicit priority (default 0). Among matching routes,
+//! a higher priority wins; equal priorities fall back to specificity (exact
+//! host over wildcard, then longer path prefix, then exact path over glob),
+//! and remaining ties go to the app name, then the pattern, in sort order.
+//! Two apps can't register an identical pattern.
+
+/// Separator between a route pattern and its upstream path directives.
+pub const ROUTE_DIRECTIVE_SEPARATOR: &str = " -> ";
+
+/// Directive that enables prefix stripping for the upstream request path.
+pub const STRIP_DIRECTIVE: &str = "strip";
+
+/// Directive (followed by a path) that prepends to the upstream request path.
+pub const PREPEND_DIRECTIVE: &str = "prepend";
+
+/// Upstream path rewrites parsed from a route pattern's directives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteDirectives {
+    pub strip_prefix: bool,
+    pub prepend: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteEntry {
+    pub app: String,
+    pub pattern: String,
+    pub priority: i32,
+}
+
+/// (host score, path prefix length, exact-path bonus); larger is more specific.
+pub type RouteSpecificity = (u8, usize, u8);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledRouteEntry {
+    pub app: String,
+    /// The route as configured, including any directives.
+    pub pattern: String,
+    pub host: String,
+    pub path: Option<String>,
+    pub strip_prefix: bool,
+    pub prepend: Option<String>,
+    pub priority: i32,
+    pub specificity: RouteSpecificity,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct RouteTable {
+    app_routes: std::collections::HashMap<String, Vec<String>>,
+    route_priorities: std::collections::HashMap<String, std::collections::HashMap<String, i32>>,
+    compiled: Vec<CompiledRouteEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectedRoute {
+    pub app: String,
+    pub path: Option<String>,
+    /// Path to forward upstream: the request path, rewritten by the matched
+    /// route's ` -> strip` / ` -> prepend` directives.
+    pub upstream_path: String,
+}
+
+impl RouteTable {
+    /// Replace an app's routes, dropping priorities of patterns it no longer
+    /// has. Rejected, leaving the table unchanged, when another app already
+    /// serves an identical pattern.
+    pub fn set_app_routes(&mut self, app: String, routes: Vec<String>) -> Result<(), String> {
+        self.check_app_routes(&app, &routes)?;
+        if let Some(priorities) = self.route_priorities.get_mut(&app) {
+            priorities.retain(|pattern, _| routes.contains(pattern));
+        }
+        self.app_routes.insert(app, routes);
+        self.rebuild();
+        Ok(())
+    }
+
+    /// Fail when any of `routes` is identical to a route of another app.
+    ///
+    /// Patterns compare without their directives and with the host
+    /// case-insensitive, the way requests are matched.
+    pub fn check_app_routes(&self, app: &str, routes: &[String]) -> Result<(), String> {
+        for route in routes.iter().filter(|route| !route.is_empty()) {
+            let key = route_conflict_key(route);
+            let mut owners: Vec<&String> = self
+                .app_routes
+                .iter()
+                .filter(|(other, patterns)| {
+                    other.as_str() != app && patterns.iter().any(|p| route_conflict_key(p) == key)
+                })
+                .map(|(other, _)| other)
+                .collect();
+            owners.sort();
+            if let Some(owner) = owners.first() {
+                return Err(format!(
+                    "Route '{}' conflicts with the same route on app '{}'",
+                    route, owner
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove_app_routes(&mut self, app: &str) {
+        self.app_routes.remove(app);
+        self.route_priorities.remove(app);
+        self.rebuild();
+    }
+
+    pub fn routes_for_app(&self, app: &str) -> Vec<String> {
+        self.app_routes.get(app).cloned().unwrap_or_default()
+    }
+
+    pub fn set_route_priority(&mut self, app: &str, pattern: &str, priority: i32) {
+        let priorities = self.route_priorities.entry(app.to_string()).or_default();
+        if priority == 0 {
+            priorities.remove(pattern);
+        } else {
+            priorities.insert(pattern.to_string(), priority);
+        }
+        self.rebuild();
+    }
+
+    pub fn route_priority(&self, app: &str, pattern: &str) -> i32 {
+        self.route_priorities
+            .get(app)
+            .and_then(|priorities| priorities.get(pattern))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn select(&self, host: &str, path: &str) -> Option<String> {
+        self.select_with_route(host, path)
+            .map(|selected| selected.app)
+    }
+
+    pub fn select_with_route(&self, host: &str, path: &str) -> Option<SelectedRoute> {
+        select_route_for_request_compiled(&self.compiled, host, path)
+    }
+
+    /// The route that wins for a request, with its configured pattern and
+    /// priority, for explaining routing decisions.
+    pub fn matching_route(&self, host: &str, path: &str) -> Option<&CompiledRouteEntry> {
+        match_route_compiled(&self.compiled, host, path)
+    }
+
+    fn rebuild(&mut self) {
+        let mut entries = Vec::new();
+
+        for (app, patterns) in &self.app_routes {
+            for pattern in patterns {
+                if pattern.is_empty() {
+                    continue;
+                }
+                entries.push(RouteEntry {
+                    app: app.clone(),
+                    pattern: pattern.clone(),
+                    priority: self.route_priority(app, pattern),
+                });
+            }
+        }
+
+        self.compiled = compile_routes(&entries);
+    }
+}
+
+pub fn compile_routes(routes: &[RouteEntry]) -> Vec<CompiledRouteEntry> {
+    let mut compiled = Vec::with_capacity(routes.len());
+    for entry in routes {
+        if entry.pattern.is_empty() {
+            continue;
+        }
+
+        let (pattern, directives) = split_route_directives(&entry.pattern);
+        let (pattern_host, pattern_path) = split_route(pattern);
+        compiled.push(CompiledRouteEntry {
+            app: entry.app.clone(),
+            pattern: entry.pattern.clone(),
+            host: pattern_host.to_string(),
+            path: pattern_path.map(|p| p.to_string()),
+            strip_prefix: directives.strip_prefix && pattern_path.is_some(),
+            prepend: directives.prepend.filter(|_| pattern_path.is_some()),
+            priority: entry.priority,
+            specificity: route_specificity(pattern),
+        });
+    }
+
+    // Highest priority first, then most specific; app name and pattern break
+    // ties so the winner doesn't depend on the order routes were added.
+    compiled.sort_by(|a, b| {
+        (b.priority, b.specificity)
+            .cmp(&(a.priority, a.specificity))
+            .then_with(|| a.app.cmp(&b.app))
+            .then_with(|| a.pattern.cmp(&b.pattern))
+    });
+    compiled
+}
+
+pub fn select_app_for_request_compiled(
+    routes: &[CompiledRouteEntry],
+    host: &str,
+    path: &str,
+) -> Option<String> {
+    select_route_for_request_compiled(routes, host, path).map(|selected| selected.app)
+}
+
+pub fn select_route_for_request_compiled(
+    routes: &[CompiledRouteEntry],
+    host: &str,
+    path: &str,
+) -> Option<SelectedRoute> {
+    let entry = match_route_compiled(routes, host, path)?;
+    let mut upstream_path = match &entry.path {
+        Some(p) if entry.strip_prefix => strip_route_prefix(path, p),
+        _ => path.to_string(),
+    };
+    if let Some(prefix) = &entry.prepend {
+        upstream_path.insert_str(0, prefix);
+    }
+    Some(SelectedRoute {
+        app: entry.app.clone(),
+        path: entry.path.clone(),
+        upstream_path,
+    })
+}
+
+/// First compiled route (highest priority, then most specific) matching a request.
+pub fn match_route_compiled<'a>(
+    routes: &'a [CompiledRouteEntry],
+    host: &str,
+    path: &str,
+) -> Option<&'a CompiledRouteEntry> {
+    routes.iter().find(|entry| {
+        hostname_matches(&entry.host, host)
+            && entry.path.as_ref().is_none_or(|p| path_matches(p, path))
+    })
+}
+
+/// Select the best matching app for a request (uncompiled reference implementation, tests only).
+#[cfg(test)]
+fn select_app_for_request(routes: &[RouteEntry], host: &str, path: &str) -> Option<String> {
+    let mut best: Option<(&RouteEntry, (i32, RouteSpecificity))> = None;
+
+    for entry in routes {
+        if !route_matches(&entry.pattern, host, path) {
+            continue;
+        }
+
+        let spec = (entry.priority, route_specificity(&entry.pattern));
+        match best {
+            None => best = Some((entry, spec)),
+            Some((_, best_spec)) => {
+                if spec > best_spec {
+                    best = Some((entry, spec));
+                }
+            }
+        }
+    }
+
+    best.map(|(e, _)| e.app.clone())
+}
+
+fn route_matches(pattern: &str, host: &str, path: &str) -> bool {
+    if pattern.is_empty() {
+        return false;
+    }
+    let (pattern, _) = split_route_directives(pattern);
+    let (pattern_host, pattern_path) = split_route(pattern);
+    if !hostname_matches(pattern_host, host) {
+        return false;
+    }
+    match pattern_path {
+        None => true,
+        Some(p) => path_matches(p, path),
+    }
+}
+
+fn route_specificity(pattern: &str) -> RouteSpecificity {
+    if pattern.is_empty() {
+        return (0, 0, 0);
+    }
+    let (pattern_host, pattern_path) = split_route(pattern);
+
+    let host_score: u8 = if pattern_host.starts_with("*.") { 1 } else { 2 };
+
+    let (path_len, exact_bonus) = match pattern_path {
+        None => (0, 0),
+        Some(p) => {
+            if let Some(prefix) = p.strip_suffix("/*") {
+                (prefix.len(), 0)
+            } else if p.ends_with('*') {
+                let prefix = &p[..p.len().saturating_sub(1)];
+                (prefix.len(), 0)
+            } else {
+                (normalize_exact_path(p).len(), 1)
+            }
+        }
+    };
+
+    (host_score, path_len, exact_bonus)
+}
+
+/// Split trailing ` -> strip` / ` -> prepend <path>` directives off a route pattern.
+///
+/// Unrecognized directives are ignored; patterns are validated at deploy time.
+pub fn split_route_directives(pattern: &str) -> (&str, RouteDirectives) {
+    let mut parts = pattern.split(ROUTE_DIRECTIVE_SEPARATOR);
+    let base = parts.next().unwrap_or(pattern).trim_end();
+    let mut directives = RouteDirectives::default();
+    for directive in parts {
+        let directive = directive.trim();
+        if directive == STRIP_DIRECTIVE {
+            directives.strip_prefix = true;
+        } else if let Some(prefix) = directive.strip_prefix(PREPEND_DIRECTIVE)
+            && let Some(prefix) = prefix.strip_prefix(' ')
+        {
+            let prefix = prefix.trim().trim_end_matches('/');
+            if prefix.starts_with('/') {
+                directives.prepend = Some(prefix.to_string());
+            }
+        }
+    }
+    (base, directives)
+}
+
+/// Remove the literal prefix of a path pattern from a matched request path.
+///
+/// The result always starts with `/` (`/api/*` on `/api` yields `/`).
+fn strip_route_prefix(path: &str, pattern_path: &str) -> String {
+    let prefix = pattern_path
+        .strip_suffix("/*")
+        .or_else(|| pattern_path.strip_suffix('*'))
+        .unwrap_or_else(|| normalize_exact_path(pattern_path));
+    let prefix = prefix.trim_end_matches('/');
+    let rest = path.strip_prefix(prefix).unwrap_or(path);
+    if rest.is_empty() {
+        "/".to_string()
+    } else if rest.starts_with('/') {
+        rest.to_string()
+    } else {
+        format!("/{rest}")
+    }
+}
+
+/// A route reduced to what decides which requests it matches: directives
+/// dropped, host lowercased.
+fn route_conflict_key(route: &str) -> (String, Option<&str>) {
+    let (pattern, _) = split_route_directives(route);
+    let (host, path) = split_route(pattern);
+    (host.to_ascii_lowercase(), path)
+}
+
+fn split_route(route: &str) -> (&str, Option<&str>) {
+    match route.find('/') {
+        Some(idx) => (&route[..idx], Some(&route[idx..])),
+        None => (route, None),
+    }
+}
+
+fn hostname_matches(pattern: &str, hostname: &str) -> bool {
+    // RFC 7230 §2.7.1: host is case-insensitive
+    if let Some(suffix) = pattern.strip_prefix("*.") {
+        // *.example.com should not match example.com
+        if hostname.eq_ignore_ascii_case(suffix) {
+            return false;
+        }
+        // Check hostname ends with ".{suffix}" without allocating
+        hostname.len() > suffix.len()
+            && hostname.as_bytes()[hostname.len() - suffix.len() - 1] == b'.'
+            && hostname[hostname.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+    } else {
+        pattern.eq_ignore_ascii_case(hostname)
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    if let Some(prefix) = pattern.strip_suffix("/*") {
+        path.starts_with(prefix)
+            && (path.len() == prefix.len() || path[prefix.len()..].starts_with('/'))
+    } else if pattern.ends_with('*') {
+        let prefix = &pattern[..pattern.len().saturating_sub(1)];
+        path.starts_with(prefix)
+    } else {
+        normalize_exact_path(pattern) == normalize_exact_path(path)
+    }
+}
+
+fn normalize_exact_path(path: &str) -> &str {
+    if path == "/" {
+        return "/";
+    }
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() { "/" } else { trimmed }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,942 @@
+use super::*;
+use std::time::{Duration, Instant};
+
+fn route(app: &str, pattern: &str) -> RouteEntry {
+    RouteEntry {
+        app: app.to_string(),
+        pattern: pattern.to_string(),
+        priority: 0,
+    }
+}
+
+fn prioritized_route(app: &str, pattern: &str, priority: i32) -> RouteEntry {
+    RouteEntry {
+        priority,
+        ..route(app, pattern)
+    }
+}
+
+fn compiled(routes: &[RouteEntry]) -> Vec<CompiledRouteEntry> {
+    compile_routes(routes)
+}
+
+// ===========================================
+// Basic matching tests
+// ===========================================
+
+#[test]
+fn test_select_app_exact_host_beats_wildcard() {
+    let routes = vec![
+        route("wild", "*.example.com"),
+        route("exact", "api.example.com"),
+    ];
+    assert_eq!(
+        select_app_for_request(&routes, "api.example.com", "/"),
+        Some("exact".to_string())
+    );
+    assert_eq!(
+        select_app_for_request_compiled(&compiled(&routes), "api.example.com", "/"),
+        Some("exact".to_string())
+    );
+}
+
+#[test]
+fn test_select_app_longer_path_beats_shorter() {
+    let routes = vec![
+        route("short", "example.com/api/*"),
+        route("long", "example.com/api/v1/*"),
+    ];
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/api/v1/users"),
+        Some("long".to_string())
+    );
+    assert_eq!(
+        select_app_for_request_compiled(&compiled(&routes), "example.com", "/api/v1/users"),
+        Some("long".to_string())
+    );
+}
+
+#[test]
+fn test_select_app_no_match() {
+    let routes = vec![route("a", "api.example.com")];
+    assert_eq!(select_app_for_request(&routes, "example.com", "/"), None);
+    assert_eq!(
+        select_app_for_request_compiled(&compiled(&routes), "example.com", "/"),
+        None
+    );
+}
+
+// ===========================================
+// Empty pattern tests
+// ===========================================
+
+#[test]
+fn test_empty_pattern_matches_nothing() {
+    let routes = vec![route("catchall", "")];
+    assert_eq!(
+        select_app_for_request(&routes, "any.domain.com", "/any/path"),
+        None
+    );
+}
+
+#[test]
+fn test_specific_pattern_ignores_empty_pattern() {
+    let routes = vec![route("catchall", ""), route("specific", "api.example.com")];
+    assert_eq!(
+        select_app_for_request(&routes, "api.example.com", "/"),
+        Some("specific".to_string())
+    );
+    assert_eq!(select_app_for_request(&routes, "other.com", "/"), None);
+
+    assert_eq!(
+        select_app_for_request_compiled(&compiled(&routes), "api.example.com", "/"),
+        Some("specific".to_string())
+    );
+}
+
+#[test]
+fn test_empty_routes_returns_none() {
+    let routes: Vec<RouteEntry> = vec![];
+    assert_eq!(select_app_for_request(&routes, "example.com", "/"), None);
+}
+
+#[test]
+fn test_route_table_single_app_without_routes_matches_nothing() {
+    let mut table = RouteTable::default();
+    table.set_app_routes("app".to_string(), vec![]).unwrap();
+
+    assert_eq!(table.select("unknown.example.com", "/any/path"), None);
+}
+
+#[test]
+fn test_route_table_does_not_use_no_route_app_as_catchall_fallback() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("fallback".to_string(), vec![])
+        .unwrap();
+    table
+        .set_app_routes("api".to_string(), vec!["api.example.com".to_string()])
+        .unwrap();
+
+    assert_eq!(table.select("other.example.com", "/"), None);
+    assert_eq!(
+        table.select("api.example.com", "/"),
+        Some("api".to_string())
+    );
+}
+
+#[test]
+fn perf_smoke_compiled_route_selection_large_table() {
+    let route_count = 500usize;
+    let routes: Vec<RouteEntry> = (0..route_count)
+        .map(|idx| {
+            route(
+                &format!("app-{idx}"),
+                &format!("app-{idx}.example.com/api/*"),
+            )
+        })
+        .collect();
+    let compiled = compile_routes(&routes);
+    let hosts: Vec<String> = (0..route_count)
+        .map(|idx| format!("app-{idx}.example.com"))
+        .collect();
+    let expected_apps: Vec<String> = (0..route_count).map(|idx| format!("app-{idx}")).collect();
+
+    let start = Instant::now();
+    for iteration in 0..50_000usize {
+        let idx = iteration % route_count;
+        let selected = select_app_for_request_compiled(&compiled, &hosts[idx], "/api/ping");
+        assert_eq!(selected, Some(expected_apps[idx].clone()));
+    }
+    assert!(
+        start.elapsed() < Duration::from_secs(20),
+        "compiled route selection perf smoke threshold exceeded: {:?}",
+        start.elapsed()
+    );
+}
+
+#[test]
+fn test_route_table_ignores_multiple_no_route_apps() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("fallback-a".to_string(), vec![])
+        .unwrap();
+    table
+        .set_app_routes("fallback-b".to_string(), vec![])
+        .unwrap();
+    table
+        .set_app_routes("api".to_string(), vec!["api.example.com".to_string()])
+        .unwrap();
+
+    assert_eq!(table.select("other.example.com", "/"), None);
+    assert_eq!(
+        table.select("api.example.com", "/"),
+        Some("api".to_string())
+    );
+}
+
+#[test]
+fn test_route_table_remove_app_routes() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("api".to_string(), vec!["api.example.com".to_string()])
+        .unwrap();
+    table
+        .set_app_routes("web".to_string(), vec!["example.com".to_string()])
+        .unwrap();
+
+    table.remove_app_routes("api");
+
+    assert_eq!(table.routes_for_app("api"), Vec::<String>::new());
+    assert_eq!(
+        table.select("api.example.com", "/"),
+        None,
+        "removed app routes should no longer match"
+    );
+    assert_eq!(
+        table.select("example.com", "/"),
+        Some("web".to_string()),
+        "other apps should remain routable"
+    );
+}
+
+// ===========================================
+// Hostname matching tests
+// ===========================================
+
+#[test]
+fn test_hostname_exact_match() {
+    assert!(hostname_matches("api.example.com", "api.example.com"));
+    assert!(!hostname_matches("api.example.com", "www.example.com"));
+    assert!(!hostname_matches("api.example.com", "example.com"));
+}
+
+#[test]
+fn test_hostname_wildcard_match() {
+    assert!(hostname_matches("*.example.com", "api.example.com"));
+    assert!(hostname_matches("*.example.com", "www.example.com"));
+    assert!(hostname_matches("*.example.com", "deep.sub.example.com"));
+}
+
+#[test]
+fn test_hostname_wildcard_does_not_match_apex() {
+    // *.example.com should NOT match example.com
+    assert!(!hostname_matches("*.example.com", "example.com"));
+}
+
+#[test]
+fn test_hostname_wildcard_requires_subdomain() {
+    // *.example.com should not match otherexample.com
+    assert!(!hostname_matches("*.example.com", "otherexample.com"));
+    assert!(!hostname_matches("*.example.com", "fakeexample.com"));
+}
+
+#[test]
+fn test_hostname_matching_is_case_insensitive() {
+    // RFC 7230 §2.7.1: host is case-insensitive
+    assert!(hostname_matches("api.example.com", "API.Example.Com"));
+    assert!(hostname_matches("API.EXAMPLE.COM", "api.example.com"));
+    assert!(hostname_matches("Api.Example.Com", "api.example.com"));
+
+    // Wildcard patterns are also case-insensitive
+    assert!(hostname_matches("*.example.com", "API.Example.Com"));
+    assert!(hostname_matches("*.EXAMPLE.COM", "api.example.com"));
+
+    // Wildcard apex exclusion still works with mixed case
+    assert!(!hostname_matches("*.example.com", "Example.Com"));
+    assert!(!hostname_matches("*.EXAMPLE.COM", "example.com"));
+}
+
+#[test]
+fn test_case_insensitive_routing_end_to_end() {
+    let routes = vec![
+        route("api", "api.example.com"),
+        route("catchall", "*.example.com"),
+    ];
+    assert_eq!(
+        select_app_for_request(&routes, "API.Example.Com", "/"),
+        Some("api".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "Blog.Example.Com", "/"),
+        Some("catchall".to_string())
+    );
+
+    let c = compiled(&routes);
+    assert_eq!(
+        select_app_for_request_compiled(&c, "API.EXAMPLE.COM", "/"),
+        Some("api".to_string())
+    );
+    assert_eq!(
+        select_app_for_request_compiled(&c, "BLOG.EXAMPLE.COM", "/"),
+        Some("catchall".to_string())
+    );
+}
+
+// ===========================================
+// Path matching tests
+// ===========================================
+
+#[test]
+fn test_path_exact_match() {
+    assert!(path_matches("/api/users", "/api/users"));
+    assert!(path_matches("/api/users", "/api/users/"));
+    assert!(path_matches("/api/users/", "/api/users"));
+    assert!(path_matches("/api/users/", "/api/users/"));
+    assert!(!path_matches("/api/users", "/api/users/123"));
+}
+
+#[test]
+fn test_path_prefix_with_slash_star() {
+    // /api/* matches /api/anything but requires the path separator
+    assert!(path_matches("/api/*", "/api/users"));
+    assert!(path_matches("/api/*", "/api/users/123"));
+    assert!(path_matches("/api/*", "/api/"));
+    // Should match exact prefix too
+    assert!(path_matches("/api/*", "/api"));
+    // Should not match /apifoo (no separator)
+    assert!(!path_matches("/api/*", "/apifoo"));
+}
+
+#[test]
+fn test_path_prefix_with_star() {
+    // /api* matches anything starting with /api
+    assert!(path_matches("/api*", "/api"));
+    assert!(path_matches("/api*", "/api/"));
+    assert!(path_matches("/api*", "/api/users"));
+    assert!(path_matches("/api*", "/apiv2")); // Note: this matches unlike /*
+}
+
+#[test]
+fn test_path_none_matches_all() {
+    let routes = vec![route("app", "example.com")];
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/any/path"),
+        Some("app".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/"),
+        Some("app".to_string())
+    );
+}
+
+#[test]
+fn test_host_only_and_host_slash_star_match_equivalently() {
+    for candidate_path in ["/", "/api", "/api/v1/users"] {
+        assert!(
+            route_matches("example.com", "example.com", candidate_path),
+            "host-only route should match path {candidate_path}"
+        );
+        assert!(
+            route_matches("example.com/*", "example.com", candidate_path),
+            "host/* route should match path {candidate_path}"
+        );
+    }
+
+    assert_eq!(
+        route_specificity("example.com"),
+        route_specificity("example.com/*")
+    );
+}
+
+// ===========================================
+// Route specificity tests
+// ===========================================
+
+#[test]
+fn test_specificity_exact_path_beats_wildcard_path() {
+    let routes = vec![
+        route("wildcard", "example.com/api/*"),
+        route("exact", "example.com/api/users"),
+    ];
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/api/users"),
+        Some("exact".to_string())
+    );
+}
+
+#[test]
+fn test_specificity_host_beats_path_length() {
+    let routes = vec![
+        route("wildcard_host", "*.example.com/api/*"),
+        route("exact_host", "api.example.com/*"),
+    ];
+    // Exact host should win even with shorter path pattern
+    assert_eq!(
+        select_app_for_request(&routes, "api.example.com", "/api/v1/users"),
+        Some("exact_host".to_string())
+    );
+}
+
+#[test]
+fn test_specificity_scores() {
+    // Empty pattern
+    assert_eq!(route_specificity(""), (0, 0, 0));
+
+    // Host only (exact)
+    assert_eq!(route_specificity("example.com"), (2, 0, 0));
+
+    // Host only (wildcard)
+    assert_eq!(route_specificity("*.example.com"), (1, 0, 0));
+
+    // Exact host + exact path
+    assert_eq!(route_specificity("example.com/api"), (2, 4, 1));
+
+    // Exact host + wildcard path
+    assert_eq!(route_specificity("example.com/api/*"), (2, 4, 0));
+    assert_eq!(route_specificity("example.com/api*"), (2, 4, 0));
+
+    // Wildcard host + exact path
+    assert_eq!(route_specificity("*.example.com/api"), (1, 4, 1));
+}
+
+// ===========================================
+// Split route tests
+// ===========================================
+
+#[test]
+fn test_split_route_host_only() {
+    assert_eq!(split_route("example.com"), ("example.com", None));
+    assert_eq!(split_route("*.example.com"), ("*.example.com", None));
+}
+
+#[test]
+fn test_split_route_with_path() {
+    assert_eq!(
+        split_route("example.com/api"),
+        ("example.com", Some("/api"))
+    );
+    assert_eq!(
+        split_route("example.com/api/v1"),
+        ("example.com", Some("/api/v1"))
+    );
+}
+
+// ===========================================
+// Complex scenarios
+// ===========================================
+
+#[test]
+fn test_multiple_apps_different_paths() {
+    let routes = vec![
+        route("api", "example.com/api/*"),
+        route("admin", "example.com/admin/*"),
+        route("web", "example.com/*"),
+    ];
+
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/api/users"),
+        Some("api".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/admin/dashboard"),
+        Some("admin".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/about"),
+        Some("web".to_string())
+    );
+}
+
+#[test]
+fn test_multiple_apps_different_subdomains() {
+    let routes = vec![
+        route("api", "api.example.com"),
+        route("admin", "admin.example.com"),
+        route("catchall", "*.example.com"),
+    ];
+
+    assert_eq!(
+        select_app_for_request(&routes, "api.example.com", "/"),
+        Some("api".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "admin.example.com", "/"),
+        Some("admin".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "blog.example.com", "/"),
+        Some("catchall".to_string())
+    );
+}
+
+#[test]
+fn test_first_match_wins_on_equal_specificity() {
+    let routes = vec![
+        route("first", "example.com/api"),
+        route("second", "example.com/api"),
+    ];
+    // When specificity is equal, first route should win
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/api"),
+        Some("first".to_string())
+    );
+}
+
+#[test]
+fn test_complex_multi_level_routing() {
+    let routes = vec![
+        route("api-v2", "api.example.com/v2/*"),
+        route("api-v1", "api.example.com/v1/*"),
+        route("api-fallback", "api.example.com/*"),
+        route("web", "www.example.com/*"),
+        route("wildcard", "*.example.com"),
+    ];
+
+    // Most specific matches
+    assert_eq!(
+        select_app_for_request(&routes, "api.example.com", "/v2/users"),
+        Some("api-v2".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "api.example.com", "/v1/users"),
+        Some("api-v1".to_string())
+    );
+    // Fallback within api subdomain
+    assert_eq!(
+        select_app_for_request(&routes, "api.example.com", "/health"),
+        Some("api-fallback".to_string())
+    );
+    // www subdomain
+    assert_eq!(
+        select_app_for_request(&routes, "www.example.com", "/about"),
+        Some("web".to_string())
+    );
+    // Other subdomains hit wildcard
+    assert_eq!(
+        select_app_for_request(&routes, "blog.example.com", "/post/123"),
+        Some("wildcard".to_string())
+    );
+    // Completely different domain has no route
+    assert_eq!(select_app_for_request(&routes, "other.com", "/"), None);
+}
+
+// ===========================================
+// Edge cases
+// ===========================================
+
+#[test]
+fn test_trailing_slash_in_path() {
+    let routes = vec![route("app", "example.com/api")];
+    // Exact path routes normalize trailing slash, so /api and /api/ are equivalent.
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/api"),
+        Some("app".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/api/"),
+        Some("app".to_string())
+    );
+
+    let routes_with_slash = vec![route("app", "example.com/api/")];
+    assert_eq!(
+        select_app_for_request(&routes_with_slash, "example.com", "/api"),
+        Some("app".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes_with_slash, "example.com", "/api/"),
+        Some("app".to_string())
+    );
+}
+
+#[test]
+fn test_root_path() {
+    let routes = vec![route("app", "example.com/")];
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/"),
+        Some("app".to_string())
+    );
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/other"),
+        None
+    );
+}
+
+#[test]
+fn test_case_sensitivity() {
+    let routes = vec![route("app", "Example.Com/API")];
+    // Routing is case-sensitive
+    assert_eq!(
+        select_app_for_request(&routes, "Example.Com", "/API"),
+        Some("app".to_string())
+    );
+    assert_eq!(select_app_for_request(&routes, "example.com", "/api"), None);
+}
+
+#[test]
+fn test_route_table_select_with_route_returns_matched_path_pattern() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes(
+            "web".to_string(),
+            vec!["example.com/tanstack-start/*".to_string()],
+        )
+        .unwrap();
+
+    let matched = table
+        .select_with_route("example.com", "/tanstack-start/assets/main.js")
+        .expect("expected matching route");
+    assert_eq!(matched.app, "web");
+    assert_eq!(matched.path, Some("/tanstack-start/*".to_string()));
+}
+
+#[test]
+fn test_matching_route_exact_host_beats_overlapping_wildcard() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("tenants".to_string(), vec!["*.example.com/*".to_string()])
+        .unwrap();
+    table
+        .set_app_routes("api".to_string(), vec!["api.example.com".to_string()])
+        .unwrap();
+
+    let matched = table
+        .matching_route("api.example.com", "/users")
+        .expect("expected matching route");
+    assert_eq!(matched.app, "api");
+    assert_eq!(matched.pattern, "api.example.com");
+
+    let matched = table
+        .matching_route("shop.example.com", "/users")
+        .expect("expected matching route");
+    assert_eq!(matched.app, "tenants");
+    assert_eq!(matched.pattern, "*.example.com/*");
+    assert!(table.matching_route("example.com", "/").is_none());
+}
+
+#[test]
+fn test_matching_route_orders_path_globs_by_specificity() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes(
+            "web".to_string(),
+            vec![
+                "example.com/*".to_string(),
+                "example.com/api/*".to_string(),
+                "example.com/api/v1/* -> strip".to_string(),
+                "example.com/api/v1/health".to_string(),
+            ],
+        )
+        .unwrap();
+
+    let pattern = |path: &str| {
+        table
+            .matching_route("example.com", path)
+            .map(|m| m.pattern.clone())
+    };
+    assert_eq!(pattern("/about").as_deref(), Some("example.com/*"));
+    assert_eq!(pattern("/api/users").as_deref(), Some("example.com/api/*"));
+    assert_eq!(
+        pattern("/api/v1/users").as_deref(),
+        Some("example.com/api/v1/* -> strip")
+    );
+    assert_eq!(
+        pattern("/api/v1/health").as_deref(),
+        Some("example.com/api/v1/health")
+    );
+
+    assert_eq!(
+        table
+            .matching_route("example.com", "/api/v1/users")
+            .map(|m| m.app.as_str()),
+        table.select("example.com", "/api/v1/users").as_deref()
+    );
+}
+
+#[test]
+fn test_route_table_rejects_identical_pattern_on_another_app() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("web".to_string(), vec!["example.com/api/*".to_string()])
+        .unwrap();
+
+    let err = table
+        .set_app_routes(
+            "api".to_string(),
+            vec![
+                "api.example.com".to_string(),
+                "Example.com/api/* -> strip".to_string(),
+            ],
+        )
+        .unwrap_err();
+    assert!(err.contains("Example.com/api/* -> strip"), "got: {err}");
+    assert!(err.contains("'web'"), "got: {err}");
+    assert!(table.routes_for_app("api").is_empty());
+    assert_eq!(table.select("api.example.com", "/"), None);
+
+    // Re-registering an app's own routes, or overlapping but different
+    // patterns, is not a conflict.
+    table
+        .set_app_routes("web".to_string(), vec!["example.com/api/*".to_string()])
+        .unwrap();
+    table
+        .set_app_routes("api".to_string(), vec!["example.com/api/v1/*".to_string()])
+        .unwrap();
+    assert_eq!(
+        table.select("example.com", "/api/v1/users"),
+        Some("api".to_string())
+    );
+}
+
+#[test]
+fn test_route_table_breaks_specificity_ties_deterministically() {
+    // `/api*` and `/api/*` have the same specificity and both match `/api/x`.
+    for order in [["beta", "alpha"], ["alpha", "beta"]] {
+        let mut table = RouteTable::default();
+        for app in order {
+            let pattern = if app == "alpha" {
+                "example.com/api/*"
+            } else {
+                "example.com/api*"
+            };
+            table
+                .set_app_routes(app.to_string(), vec![pattern.to_string()])
+                .unwrap();
+        }
+        assert_eq!(
+            table.select("example.com", "/api/x"),
+            Some("alpha".to_string())
+        );
+        assert_eq!(
+            table.select("example.com", "/apix"),
+            Some("beta".to_string())
+        );
+    }
+}
+
+#[test]
+fn test_route_table_ranks_overlapping_routes_across_apps() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("tenants".to_string(), vec!["*.example.com".to_string()])
+        .unwrap();
+    table
+        .set_app_routes("site".to_string(), vec!["app.example.com".to_string()])
+        .unwrap();
+    table
+        .set_app_routes(
+            "docs".to_string(),
+            vec!["app.example.com/docs*".to_string()],
+        )
+        .unwrap();
+    table
+        .set_app_routes(
+            "api".to_string(),
+            vec!["app.example.com/docs/api/*".to_string()],
+        )
+        .unwrap();
+    table
+        .set_app_routes(
+            "health".to_string(),
+            vec!["app.example.com/docs/api/health".to_string()],
+        )
+        .unwrap();
+
+    let winner = |host: &str, path: &str| table.select(host, path);
+    assert_eq!(
+        winner("shop.example.com", "/docs"),
+        Some("tenants".to_string())
+    );
+    assert_eq!(winner("app.example.com", "/"), Some("site".to_string()));
+    assert_eq!(
+        winner("app.example.com", "/docs/intro"),
+        Some("docs".to_string())
+    );
+    assert_eq!(
+        winner("app.example.com", "/docs/api/users"),
+        Some("api".to_string())
+    );
+    assert_eq!(
+        winner("app.example.com", "/docs/api/health"),
+        Some("health".to_string())
+    );
+}
+
+// ===========================================
+// Prefix stripping
+// ===========================================
+
+#[test]
+fn test_select_with_route_exact_match_keeps_path() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("api".to_string(), vec!["example.com/health".to_string()])
+        .unwrap();
+
+    let matched = table
+        .select_with_route("example.com", "/health")
+        .expect("expected matching route");
+    assert_eq!(matched.app, "api");
+    assert_eq!(matched.upstream_path, "/health");
+}
+
+#[test]
+fn test_select_with_route_wildcard_strip_rewrites_path() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes(
+            "api".to_string(),
+            vec!["example.com/api/* -> strip".to_string()],
+        )
+        .unwrap();
+
+    let matched = table
+        .select_with_route("example.com", "/api/users/1")
+        .expect("expected matching route");
+    assert_eq!(matched.app, "api");
+    assert_eq!(matched.path, Some("/api/*".to_string()));
+    assert_eq!(matched.upstream_path, "/users/1");
+
+    let root = table
+        .select_with_route("example.com", "/api")
+        .expect("expected prefix itself to match");
+    assert_eq!(root.upstream_path, "/");
+    assert_eq!(table.select("example.com", "/apifoo"), None);
+}
+
+#[test]
+fn test_select_with_route_wildcard_without_strip_keeps_path() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("api".to_string(), vec!["example.com/api/*".to_string()])
+        .unwrap();
+
+    let matched = table
+        .select_with_route("example.com", "/api/users/1")
+        .expect("expected matching route");
+    assert_eq!(matched.upstream_path, "/api/users/1");
+}
+
+#[test]
+fn test_select_with_route_prepend_rewrites_path() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes(
+            "api".to_string(),
+            vec!["example.com/* -> prepend /v1".to_string()],
+        )
+        .unwrap();
+
+    let matched = table
+        .select_with_route("example.com", "/foo")
+        .expect("expected matching route");
+    assert_eq!(matched.path, Some("/*".to_string()));
+    assert_eq!(matched.upstream_path, "/v1/foo");
+}
+
+#[test]
+fn test_select_with_route_strip_then_prepend() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes(
+            "api".to_string(),
+            vec!["example.com/api/* -> strip -> prepend /v1/".to_string()],
+        )
+        .unwrap();
+
+    let matched = table
+        .select_with_route("example.com", "/api/users/1")
+        .expect("expected matching route");
+    assert_eq!(matched.upstream_path, "/v1/users/1");
+
+    let root = table
+        .select_with_route("example.com", "/api")
+        .expect("expected prefix itself to match");
+    assert_eq!(root.upstream_path, "/v1/");
+}
+
+#[test]
+fn test_strip_flag_does_not_change_specificity() {
+    assert_eq!(
+        compile_routes(&[route("api", "example.com/api/* -> strip")])[0].specificity,
+        route_specificity("example.com/api/*")
+    );
+    assert_eq!(
+        compile_routes(&[route("api", "example.com/api/* -> strip -> prepend /v1")])[0].specificity,
+        route_specificity("example.com/api/*")
+    );
+    assert!(route_matches(
+        "example.com/api/* -> strip",
+        "example.com",
+        "/api/users"
+    ));
+}
+
+#[test]
+fn test_higher_priority_beats_more_specific_overlapping_route() {
+    let routes = vec![
+        route("api", "example.com/api/*"),
+        prioritized_route("catchall", "example.com", 10),
+    ];
+    assert_eq!(
+        select_app_for_request(&routes, "example.com", "/api/users"),
+        Some("catchall".to_string())
+    );
+    assert_eq!(
+        select_app_for_request_compiled(&compiled(&routes), "example.com", "/api/users"),
+        Some("catchall".to_string())
+    );
+}
+
+#[test]
+fn test_equal_priority_falls_back_to_specificity() {
+    let routes = vec![
+        prioritized_route("catchall", "example.com", 5),
+        prioritized_route("api", "example.com/api/*", 5),
+    ];
+    assert_eq!(
+        select_app_for_request_compiled(&compiled(&routes), "example.com", "/api/users"),
+        Some("api".to_string())
+    );
+    assert_eq!(
+        select_app_for_request_compiled(&compiled(&routes), "example.com", "/other"),
+        Some("catchall".to_string())
+    );
+}
+
+#[test]
+fn test_route_table_priority_survives_route_updates() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("api".to_string(), vec!["example.com/api/*".to_string()])
+        .unwrap();
+    table
+        .set_app_routes("web".to_string(), vec!["example.com".to_string()])
+        .unwrap();
+    assert_eq!(
+        table.select("example.com", "/api/x"),
+        Some("api".to_string())
+    );
+
+    table.set_route_priority("web", "example.com", 1);
+    assert_eq!(
+        table.select("example.com", "/api/x"),
+        Some("web".to_string())
+    );
+
+    table
+        .set_app_routes("web".to_string(), vec!["example.com".to_string()])
+        .unwrap();
+    assert_eq!(table.route_priority("web", "example.com"), 1);
+    assert_eq!(
+        table.select("example.com", "/api/x"),
+        Some("web".to_string())
+    );
+}
+
+#[test]
+fn test_route_table_drops_priority_of_removed_route() {
+    let mut table = RouteTable::default();
+    table
+        .set_app_routes("web".to_string(), vec!["example.com".to_string()])
+        .unwrap();
+    table.set_route_priority("web", "example.com", 5);
+
+    table
+        .set_app_routes("web".to_string(), vec!["www.example.com".to_string()])
+        .unwrap();
+    table
+        .set_app_routes("web".to_string(), vec!["example.com".to_string()])
+        .unwrap();
+    assert_eq!(table.route_priority("web", "example.com"), 0);
+}
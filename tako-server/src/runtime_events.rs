@@ -1,12 +1,30 @@
 use crate::ServerState;
-use crate::instances::{App, HealthEvent, InstanceEvent};
+use crate::instances::{App, HealthEvent, Instance, InstanceError, InstanceEvent};
 use crate::scaling::IdleEvent;
 use crate::socket::{AppState, InstanceState};
+use crate::tls::CertEvent;
+use std::sync::Arc;
 
 pub(crate) async fn handle_instance_event(state: &ServerState, event: InstanceEvent) {
+    let app_name = match &event {
+        InstanceEvent::Started { app, .. }
+        | InstanceEvent::Ready { app, .. }
+        | InstanceEvent::Unhealthy { app, .. }
+        | InstanceEvent::Stopped { app, .. }
+        | InstanceEvent::Exited { app, .. } => app.clone(),
+    };
+    // Once for the transition itself and again after any replacement,
+    // which can wait out a restart backoff.
+    state.notify_status_change(&app_name);
+    handle_instance_event_inner(state, event).await;
+    state.notify_status_change(&app_name);
+}
+
+async fn handle_instance_event_inner(state: &ServerState, event: InstanceEvent) {
     match event {
         InstanceEvent::Started { app, instance_id } => {
             tracing::debug!(app = %app, instance = %instance_id, "Instance started");
+            log_lifecycle(state, &app, &instance_id, "instance started");
         }
         InstanceEvent::Ready { app, instance_id } => {
             tracing::info!(app = %app, instance = %instance_id, "Instance ready");
@@ -14,21 +32,74 @@ pub(crate) async fn handle_instance_event(state: &ServerState, event: InstanceEv
             crate::metrics::set_instance_health(&app, &instance_id, true);
 
             if let Some(app_ref) = state.app_manager.get_app(&app) {
+                app_ref.record_spawn_success();
                 app_ref.clear_last_error();
+                app_ref.log_lifecycle(&instance_id, "instance ready");
                 update_instance_count_metric(&app, &app_ref);
             }
         }
         InstanceEvent::Unhealthy { app, instance_id } => {
             tracing::warn!(app = %app, instance = %instance_id, "Instance unhealthy");
+            log_lifecycle(state, &app, &instance_id, "instance unhealthy");
             replace_instance_if_needed(state, &app, &instance_id, "unhealthy").await;
         }
         InstanceEvent::Stopped { app, instance_id } => {
             tracing::info!(app = %app, instance = %instance_id, "Instance stopped");
+            log_lifecycle(state, &app, &instance_id, "instance stopped");
+        }
+        InstanceEvent::Exited {
+            app,
+            instance_id,
+            code,
+            signal,
+        } => {
+            let reason = describe_exit(code, signal);
+            let clean = code == Some(0);
+            if clean {
+                tracing::info!(app = %app, instance = %instance_id, "Instance {}", reason);
+            } else {
+                tracing::warn!(app = %app, instance = %instance_id, "Instance {}", reason);
+            }
+            crate::metrics::set_instance_health(&app, &instance_id, false);
+            crate::metrics::remove_instance_metrics(&app, &instance_id);
+            if let Some(app_ref) = state.app_manager.get_app(&app) {
+                app_ref.log_lifecycle(&instance_id, format!("instance {reason}"));
+                if !clean {
+                    app_ref.set_last_error(format!("Instance {reason}"));
+                }
+                update_instance_count_metric(&app, &app_ref);
+            }
+            let replace_reason = if clean { "exited" } else { "crashed" };
+            replace_instance_if_needed(state, &app, &instance_id, replace_reason).await;
         }
     }
 }
 
+/// Human-readable exit reason for lifecycle logs, e.g. `exited with code 1`.
+pub(crate) fn describe_exit(code: Option<i32>, signal: Option<i32>) -> String {
+    match (code, signal) {
+        (Some(0), _) => "exited cleanly".to_string(),
+        (Some(code), _) => format!("exited with code {code}"),
+        (None, Some(signal)) => format!("killed by signal {signal}"),
+        (None, None) => "exited".to_string(),
+    }
+}
+
 pub(crate) async fn handle_health_event(state: &ServerState, event: HealthEvent) {
+    let app_name = match &event {
+        HealthEvent::Healthy { app, .. }
+        | HealthEvent::Unhealthy { app, .. }
+        | HealthEvent::Dead { app, .. }
+        | HealthEvent::Recovered { app, .. } => app.clone(),
+    };
+    // Once for the transition itself and again after any replacement,
+    // which can wait out a restart backoff.
+    state.notify_status_change(&app_name);
+    handle_health_event_inner(state, event).await;
+    state.notify_status_change(&app_name);
+}
+
+async fn handle_health_event_inner(state: &ServerState, event: HealthEvent) {
     match event {
         HealthEvent::Healthy { app, instance_id } => {
             tracing::info!(app = %app, instance = %instance_id, "Instance is healthy");
@@ -62,6 +133,12 @@ pub(crate) async fn handle_health_event(state: &ServerState, event: HealthEvent)
     }
 }
 
+fn log_lifecycle(state: &ServerState, app_name: &str, instance_id: &str, message: &str) {
+    if let Some(app) = state.app_manager.get_app(app_name) {
+        app.log_lifecycle(instance_id, message);
+    }
+}
+
 pub(crate) fn update_instance_count_metric(app_name: &str, app: &App) {
     let count = app
         .get_instances()
@@ -116,12 +193,35 @@ pub(crate) async fn handle_idle_event(state: &ServerState, event: IdleEvent) {
     }
 }
 
+pub(crate) fn handle_cert_event(state: &ServerState, event: CertEvent) {
+    let mut warnings = state.cert_warnings.write();
+    match event {
+        CertEvent::ExpiringSoon { domain, days } => {
+            tracing::warn!(domain = %domain, days = days, "Certificate is close to expiry");
+            let warning = warnings.entry(domain.clone()).or_default();
+            warning.domain = domain;
+            warning.expires_in_days = Some(days);
+        }
+        CertEvent::RenewalFailed { domain, error } => {
+            let warning = warnings.entry(domain.clone()).or_default();
+            warning.domain = domain;
+            warning.renewal_error = Some(error);
+        }
+        CertEvent::Renewed { domain } => {
+            warnings.remove(&domain);
+        }
+    }
+}
+
 async fn replace_instance_if_needed(
     state: &ServerState,
     app_name: &str,
     instance_id: &str,
     reason: &str,
 ) {
+    if state.is_shutting_down() {
+        return;
+    }
     let app = match state.app_manager.get_app(app_name) {
         Some(app) => app,
         None => {
@@ -183,17 +283,74 @@ async fn replace_instance_if_needed(
     }
     app.remove_instance(instance_id);
 
+    if app.restarts_exhausted() {
+        tracing::warn!(app = %app_name, instance = %instance_id, "Not replacing {} instance: respawning stopped", reason);
+        update_instance_count_metric(app_name, &app);
+        return;
+    }
+    if !app.try_restart() {
+        let max_attempts = app.config.read().max_restart_attempts;
+        let message = format!(
+            "Stopped restarting after {} failed restarts within {}s; redeploy or scale to retry",
+            max_attempts,
+            crate::defaults::RESTART_ATTEMPT_WINDOW.as_secs()
+        );
+        tracing::error!(app = %app_name, instance = %instance_id, "{}", message);
+        app.log_lifecycle(instance_id, "restart limit reached; not replacing instance");
+        app.set_state(AppState::Error);
+        app.set_last_error(message);
+        update_instance_count_metric(app_name, &app);
+        return;
+    }
+
+    let backoff = app.next_restart_backoff();
+    if !backoff.is_zero() {
+        tracing::info!(
+            app = %app_name,
+            instance = %instance_id,
+            delay_ms = backoff.as_millis() as u64,
+            "Delaying replacement of {} instance",
+            reason
+        );
+        app.log_lifecycle(instance_id, format!("restarting in {}s", backoff.as_secs()));
+        tokio::time::sleep(backoff).await;
+        // A deploy, stop, or shutdown during the wait owns the app now.
+        if state.is_shutting_down()
+            || app.state() == AppState::Stopped
+            || app.version() != current_version
+        {
+            return;
+        }
+    }
+
+    let _ = spawn_replacement_instance(state, &app, instance_id, reason).await;
+}
+
+/// Spawn a fresh instance from the app's current config in place of
+/// `old_instance_id`, which the caller has already stopped and removed.
+pub(crate) async fn spawn_replacement_instance(
+    state: &ServerState,
+    app: &Arc<App>,
+    old_instance_id: &str,
+    reason: &str,
+) -> Result<Arc<Instance>, InstanceError> {
+    let app_name = app.config.read().deployment_id();
     let new_instance = app.allocate_instance();
     let spawner = state.app_manager.spawner();
 
-    match spawner.spawn(&app, new_instance.clone()).await {
+    match spawner.spawn(app, new_instance.clone()).await {
         Ok(()) => {
             tracing::info!(
                 app = %app_name,
-                old_instance = %instance_id,
+                old_instance = %old_instance_id,
                 new_instance = %new_instance.id,
                 "Successfully spawned replacement instance"
             );
+            app.log_lifecycle(
+                old_instance_id,
+                format!("instance replaced by {} ({})", new_instance.id, reason),
+            );
+            Ok(new_instance)
         }
         Err(e) => {
             tracing::error!(
@@ -203,6 +360,25 @@ async fn replace_instance_if_needed(
                 e
             );
             app.remove_instance(&new_instance.id);
+            record_spawn_failure(app, &new_instance.id, &e);
+            Err(e)
         }
     }
 }
+
+/// Count a failed spawn toward the app's circuit breaker. Once tripped the
+/// app is marked errored and left alone until the next deploy or scale.
+pub(crate) fn record_spawn_failure(app: &App, instance_id: &str, error: &InstanceError) {
+    if !app.record_spawn_failure() {
+        return;
+    }
+    let message = format!(
+        "Stopped respawning after {} consecutive spawn failures (last: {}); redeploy or scale to retry",
+        crate::defaults::MAX_CONSECUTIVE_SPAWN_FAILURES,
+        error
+    );
+    tracing::error!(app = %app.name(), instance = %instance_id, "{}", message);
+    app.log_lifecycle(instance_id, "spawn failure limit reached; not respawning");
+    app.set_state(AppState::Error);
+    app.set_last_error(message);
+}
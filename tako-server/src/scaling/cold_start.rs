@@ -8,21 +8,80 @@ use tokio::sync::broadcast;
 /// Configuration for cold start handling
 #[derive(Debug, Clone)]
 pub struct ColdStartConfig {
-    /// Maximum time to wait for an instance to start
+    /// Maximum time a single request waits for an instance to start
     pub startup_timeout: Duration,
+    /// Deadline for the whole cold start, counted from when it began. Once
+    /// it passes the cold start is marked failed so the next request can
+    /// start a fresh one.
+    pub ready_timeout: Duration,
     /// Maximum number of requests to queue during cold start
     pub max_queued_requests: usize,
+    /// `Retry-After` sent with the 503 for a request that timed out
+    pub retry_after: Duration,
+    /// HTML served with that 503 instead of the built-in page
+    pub unavailable_html: Option<String>,
 }
 
 impl Default for ColdStartConfig {
     fn default() -> Self {
         Self {
             startup_timeout: Duration::from_secs(30),
+            ready_timeout: Duration::from_secs(30),
             max_queued_requests: 1000,
+            retry_after: Duration::from_secs(5),
+            unavailable_html: None,
         }
     }
 }
 
+const DEFAULT_UNAVAILABLE_HTML: &str = "<!doctype html>\n<html><head><meta charset=\"utf-8\"><title>Starting up</title></head>\n<body><h1>Starting up</h1><p>This app is taking longer than usual to start. Please try again in a few seconds.</p></body></html>\n";
+
+/// Response for a request whose cold start missed its deadline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnavailablePage {
+    pub content_type: &'static str,
+    pub body: String,
+    pub retry_after_secs: u64,
+}
+
+/// Whether a cold start that began at `started_at` has run past its deadline.
+pub fn cold_start_timed_out(started_at: Instant, now: Instant, config: &ColdStartConfig) -> bool {
+    now.saturating_duration_since(started_at) >= config.ready_timeout
+}
+
+/// Render the 503 body for `app_name`: JSON when the client asks for JSON
+/// (and not HTML), otherwise the configured or built-in HTML page.
+pub fn render_unavailable_page(
+    config: &ColdStartConfig,
+    app_name: &str,
+    accept: Option<&str>,
+) -> UnavailablePage {
+    let retry_after_secs = config.retry_after.as_secs().max(1);
+    let wants_json = accept
+        .is_some_and(|accept| accept.contains("application/json") && !accept.contains("text/html"));
+    if wants_json {
+        return UnavailablePage {
+            content_type: "application/json",
+            body: serde_json::json!({
+                "error": "app_starting",
+                "message": "App startup timed out",
+                "app": app_name,
+                "retry_after": retry_after_secs,
+            })
+            .to_string(),
+            retry_after_secs,
+        };
+    }
+    UnavailablePage {
+        content_type: "text/html; charset=utf-8",
+        body: config
+            .unavailable_html
+            .clone()
+            .unwrap_or_else(|| DEFAULT_UNAVAILABLE_HTML.to_string()),
+        retry_after_secs,
+    }
+}
+
 /// State of a cold start for an app
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColdStartState {
@@ -127,6 +186,20 @@ impl ColdStartManager {
     }
 
     pub async fn wait_for_ready_outcome(&self, app_name: &str) -> WaitForReadyOutcome {
+        self.wait_ready(app_name, self.config.startup_timeout).await
+    }
+
+    /// Wait for the in-flight cold start of `app_name` to finish, for at most
+    /// `timeout` (capped by the configured startup timeout).
+    ///
+    /// Every request for an idle app calls this after [`Self::begin`]: the
+    /// leader spawns one instance and all callers, leader included, share
+    /// the outcome of that single spawn.
+    /// Requests still waiting when the cold start's `ready_timeout` passes
+    /// mark it failed.
+    pub async fn wait_ready(&self, app_name: &str, timeout: Duration) -> WaitForReadyOutcome {
+        let mut timeout = timeout.min(self.config.startup_timeout);
+        let mut deadline_bound = false;
         let rx = {
             let mut apps = self.apps.lock();
             match apps.get_mut(app_name) {
@@ -135,6 +208,16 @@ impl ColdStartManager {
                     if cs.queued_waiters >= self.config.max_queued_requests {
                         return WaitForReadyOutcome::QueueFull;
                     }
+                    if let Some(started_at) = cs.started_at {
+                        let remaining = self
+                            .config
+                            .ready_timeout
+                            .saturating_sub(started_at.elapsed());
+                        if remaining <= timeout {
+                            timeout = remaining;
+                            deadline_bound = true;
+                        }
+                    }
                     cs.queued_waiters += 1;
                     cs.ready_tx.as_ref().map(|tx| tx.subscribe())
                 }
@@ -143,7 +226,7 @@ impl ColdStartManager {
         };
 
         let outcome = if let Some(mut rx) = rx {
-            match tokio::time::timeout(self.config.startup_timeout, rx.recv()).await {
+            match tokio::time::timeout(timeout, rx.recv()).await {
                 Ok(Ok(true)) => WaitForReadyOutcome::Ready,
                 Ok(Ok(false)) | Ok(Err(_)) => WaitForReadyOutcome::Failed,
                 Err(_) => WaitForReadyOutcome::Timeout,
@@ -152,14 +235,42 @@ impl ColdStartManager {
             WaitForReadyOutcome::Failed
         };
 
-        let mut apps = self.apps.lock();
-        if let Some(cs) = apps.get_mut(app_name) {
-            cs.queued_waiters = cs.queued_waiters.saturating_sub(1);
+        {
+            let mut apps = self.apps.lock();
+            if let Some(cs) = apps.get_mut(app_name) {
+                cs.queued_waiters = cs.queued_waiters.saturating_sub(1);
+            }
         }
 
+        if outcome == WaitForReadyOutcome::Timeout && deadline_bound {
+            self.fail_if_past_deadline(app_name);
+        }
         outcome
     }
 
+    /// Mark a still-running cold start failed once its `ready_timeout` has
+    /// passed, so it stops swallowing requests.
+    fn fail_if_past_deadline(&self, app_name: &str) {
+        let timed_out = {
+            let apps = self.apps.lock();
+            apps.get(app_name).is_some_and(|cs| {
+                cs.state == ColdStartState::Starting
+                    && cs
+                        .started_at
+                        .is_some_and(|t| cold_start_timed_out(t, Instant::now(), &self.config))
+            })
+        };
+        if timed_out {
+            tracing::warn!(app = %app_name, "Cold start missed its ready deadline");
+            self.mark_failed(app_name, "ready_timeout");
+        }
+    }
+
+    /// The 503 response for a request to `app_name` that timed out.
+    pub fn unavailable_page(&self, app_name: &str, accept: Option<&str>) -> UnavailablePage {
+        render_unavailable_page(&self.config, app_name, accept)
+    }
+
     /// Mark cold start as complete (instance is ready)
     pub fn mark_ready(&self, app_name: &str) {
         let mut apps = self.apps.lock();
@@ -215,7 +326,84 @@ mod tests {
     fn test_cold_start_config_defaults() {
         let config = ColdStartConfig::default();
         assert_eq!(config.startup_timeout, Duration::from_secs(30));
+        assert_eq!(config.ready_timeout, Duration::from_secs(30));
         assert_eq!(config.max_queued_requests, 1000);
+        assert_eq!(config.retry_after, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_cold_start_timed_out_after_ready_timeout() {
+        let config = ColdStartConfig {
+            ready_timeout: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let started_at = Instant::now();
+        assert!(!cold_start_timed_out(started_at, started_at, &config));
+        assert!(!cold_start_timed_out(
+            started_at,
+            started_at + Duration::from_secs(9),
+            &config
+        ));
+        assert!(cold_start_timed_out(
+            started_at,
+            started_at + Duration::from_secs(10),
+            &config
+        ));
+        // A clock reading from before the start never counts as late.
+        assert!(!cold_start_timed_out(
+            started_at + Duration::from_secs(1),
+            started_at,
+            &config
+        ));
+    }
+
+    #[test]
+    fn test_render_unavailable_page_negotiates_json_and_html() {
+        let config = ColdStartConfig::default();
+
+        let json = render_unavailable_page(&config, "my-app/production", Some("application/json"));
+        assert_eq!(json.content_type, "application/json");
+        assert_eq!(json.retry_after_secs, 5);
+        let body: serde_json::Value = serde_json::from_str(&json.body).unwrap();
+        assert_eq!(body["error"], "app_starting");
+        assert_eq!(body["app"], "my-app/production");
+        assert_eq!(body["retry_after"], 5);
+
+        let html = render_unavailable_page(
+            &config,
+            "my-app/production",
+            Some("text/html,application/json;q=0.9"),
+        );
+        assert_eq!(html.content_type, "text/html; charset=utf-8");
+        assert!(html.body.contains("<h1>Starting up</h1>"));
+        assert_eq!(
+            render_unavailable_page(&config, "my-app", None).body,
+            html.body
+        );
+
+        let custom = ColdStartConfig {
+            unavailable_html: Some("<p>Back soon</p>".to_string()),
+            retry_after: Duration::ZERO,
+            ..Default::default()
+        };
+        let page = render_unavailable_page(&custom, "my-app", Some("*/*"));
+        assert_eq!(page.body, "<p>Back soon</p>");
+        assert_eq!(page.retry_after_secs, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_ready_marks_cold_start_failed_past_ready_timeout() {
+        let manager = ColdStartManager::new(ColdStartConfig {
+            ready_timeout: Duration::from_millis(30),
+            ..Default::default()
+        });
+        assert!(manager.begin("my-app").leader);
+
+        let outcome = manager.wait_ready("my-app", Duration::from_secs(5)).await;
+        assert_eq!(outcome, WaitForReadyOutcome::Timeout);
+        assert!(!manager.is_cold_starting("my-app"));
+        // The next request starts a fresh cold start.
+        assert!(manager.begin("my-app").leader);
     }
 
     #[test]
@@ -314,11 +502,64 @@ mod tests {
         assert!(result);
     }
 
+    #[tokio::test]
+    async fn test_concurrent_cold_start_has_one_leader_and_releases_all_waiters() {
+        let manager = Arc::new(ColdStartManager::new(ColdStartConfig::default()));
+        let barrier = Arc::new(tokio::sync::Barrier::new(50));
+
+        let mut requests = Vec::new();
+        for _ in 0..50 {
+            let manager = manager.clone();
+            let barrier = barrier.clone();
+            requests.push(tokio::spawn(async move {
+                barrier.wait().await;
+                let begin = manager.begin("my-app");
+                let outcome = manager.wait_ready("my-app", Duration::from_secs(5)).await;
+                (begin.leader, outcome)
+            }));
+        }
+
+        // Release the waiters only once every request has queued up.
+        while manager
+            .apps
+            .lock()
+            .get("my-app")
+            .map(|cs| cs.queued_waiters)
+            != Some(50)
+        {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        manager.mark_ready("my-app");
+
+        let mut leaders = 0;
+        for request in requests {
+            let (leader, outcome) = request.await.unwrap();
+            leaders += usize::from(leader);
+            assert_eq!(outcome, WaitForReadyOutcome::Ready);
+        }
+        assert_eq!(leaders, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_ready_times_out_after_caller_timeout() {
+        let manager = ColdStartManager::new(ColdStartConfig::default());
+        manager.begin("my-app");
+
+        let outcome = manager
+            .wait_ready("my-app", Duration::from_millis(20))
+            .await;
+        assert_eq!(outcome, WaitForReadyOutcome::Timeout);
+        // The cold start itself is still running for the next request.
+        assert!(manager.is_cold_starting("my-app"));
+        assert!(!manager.begin("my-app").leader);
+    }
+
     #[tokio::test]
     async fn test_wait_for_ready_outcome_returns_queue_full_when_limit_reached() {
         let manager = Arc::new(ColdStartManager::new(ColdStartConfig {
             startup_timeout: Duration::from_secs(1),
             max_queued_requests: 1,
+            ..Default::default()
         }));
 
         manager.begin("my-app");
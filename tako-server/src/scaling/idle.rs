@@ -169,7 +169,7 @@ mod tests {
         let (tx, _rx) = mpsc::channel(16);
         let monitor = IdleMonitor::new(IdleConfig::default(), tx);
 
-        let instance = Instance::new("test-1".to_string(), "v1".to_string(), noop_log_handle());
+        let instance = Instance::new("test-1".to_string(), "v1".to_string(), 0, noop_log_handle());
         instance.set_state(InstanceState::Healthy);
 
         // Can't stop if at min_instances
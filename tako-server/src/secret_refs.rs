@@ -0,0 +1,240 @@
+//! Secret values given by reference
+//!
+//! References are off unless the operator enables them when starting
+//! tako-server; until then every secret value is used literally. With
+//! `--secret-ref-dir <dir>`, a value of `file:/abs/path` is read from a file
+//! under that directory, and with `--secret-ref-env <VAR>`, `env:VAR` reads
+//! that variable from tako-server's own environment. References are stored
+//! as written and only resolved when a process is launched, so the secret
+//! itself never passes through the management socket or the state store.
+//! Files under the server's data directory are never read, and a value
+//! starting with `literal:` is used verbatim without the prefix.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const FILE_PREFIX: &str = "file:";
+const ENV_PREFIX: &str = "env:";
+const LITERAL_PREFIX: &str = "literal:";
+
+/// Which references tako-server resolves. The default resolves none.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SecretRefPolicy {
+    /// Directory `file:` references must point into.
+    file_dir: Option<PathBuf>,
+    /// Variables `env:` references may read.
+    env_vars: Vec<String>,
+    /// Server data directory, never readable through `file:`.
+    data_dir: PathBuf,
+}
+
+impl SecretRefPolicy {
+    pub(crate) fn new(file_dir: Option<PathBuf>, env_vars: Vec<String>, data_dir: &Path) -> Self {
+        Self {
+            file_dir,
+            env_vars,
+            data_dir: data_dir.to_path_buf(),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.file_dir.is_some() || !self.env_vars.is_empty()
+    }
+
+    /// Whether `value` is a `file:` or `env:` reference rather than the
+    /// secret itself. Always false while references are disabled.
+    pub(crate) fn is_reference(&self, value: &str) -> bool {
+        self.enabled() && (value.starts_with(FILE_PREFIX) || value.starts_with(ENV_PREFIX))
+    }
+
+    /// Resolve every reference in `secrets` to its current value.
+    pub(crate) fn resolve_secrets(
+        &self,
+        secrets: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, String> {
+        secrets
+            .iter()
+            .map(|(key, value)| {
+                self.resolve_secret_value(value)
+                    .map(|resolved| (key.clone(), resolved))
+                    .map_err(|e| format!("secret '{}': {}", key, e))
+            })
+            .collect()
+    }
+
+    fn resolve_secret_value(&self, value: &str) -> Result<String, String> {
+        if !self.enabled() {
+            return Ok(value.to_string());
+        }
+        if let Some(literal) = value.strip_prefix(LITERAL_PREFIX) {
+            return Ok(literal.to_string());
+        }
+        if let Some(path) = value.strip_prefix(FILE_PREFIX) {
+            return self.read_file_reference(Path::new(path));
+        }
+        if let Some(name) = value.strip_prefix(ENV_PREFIX) {
+            if !self.env_vars.iter().any(|allowed| allowed == name) {
+                return Err(format!(
+                    "env var {} is not allowed (tako-server --secret-ref-env)",
+                    name
+                ));
+            }
+            return std::env::var(name).map_err(|_| format!("env var {} is not set", name));
+        }
+        Ok(value.to_string())
+    }
+
+    fn read_file_reference(&self, path: &Path) -> Result<String, String> {
+        let Some(file_dir) = &self.file_dir else {
+            return Err("file references are disabled (tako-server --secret-ref-dir)".to_string());
+        };
+        if !path.is_absolute() {
+            return Err(format!(
+                "file reference must be an absolute path: {}",
+                path.display()
+            ));
+        }
+        // Compare resolved paths so `..` and symlinks can't leave the directory.
+        let resolved = path
+            .canonicalize()
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let allowed = file_dir
+            .canonicalize()
+            .map_err(|e| format!("secret ref dir {}: {}", file_dir.display(), e))?;
+        if !resolved.starts_with(&allowed) {
+            return Err(format!(
+                "file reference {} is outside {}",
+                path.display(),
+                file_dir.display()
+            ));
+        }
+        let data_dir = self
+            .data_dir
+            .canonicalize()
+            .unwrap_or_else(|_| self.data_dir.clone());
+        if resolved.starts_with(&data_dir) {
+            return Err(format!(
+                "file reference {} is inside the server data directory",
+                path.display()
+            ));
+        }
+
+        let contents = std::fs::read_to_string(&resolved)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        // Files written by editors or `echo` end with a newline that is not
+        // part of the secret.
+        let contents = contents.strip_suffix('\n').unwrap_or(&contents);
+        let contents = contents.strip_suffix('\r').unwrap_or(contents);
+        Ok(contents.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets(value: &str) -> HashMap<String, String> {
+        HashMap::from([("KEY".to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn resolves_file_env_and_literal_values() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let refs = temp.path().join("refs");
+        std::fs::create_dir(&refs).unwrap();
+        let path = refs.join("db_password");
+        std::fs::write(&path, "hunter2\n").unwrap();
+        let policy = SecretRefPolicy::new(
+            Some(refs),
+            vec!["PATH".to_string()],
+            &temp.path().join("data"),
+        );
+        let secrets = HashMap::from([
+            (
+                "DB_PASSWORD".to_string(),
+                format!("file:{}", path.display()),
+            ),
+            ("SEARCH_PATH".to_string(), "env:PATH".to_string()),
+            ("PLAIN".to_string(), "value".to_string()),
+            ("ESCAPED".to_string(), "literal:file:not-a-ref".to_string()),
+        ]);
+
+        let resolved = policy.resolve_secrets(&secrets).unwrap();
+        assert_eq!(resolved["DB_PASSWORD"], "hunter2");
+        assert_eq!(resolved["SEARCH_PATH"], std::env::var("PATH").unwrap());
+        assert_eq!(resolved["PLAIN"], "value");
+        assert_eq!(resolved["ESCAPED"], "file:not-a-ref");
+    }
+
+    #[test]
+    fn references_are_literal_unless_enabled() {
+        let policy = SecretRefPolicy::default();
+        for value in ["file:/etc/hostname", "env:PATH", "literal:x"] {
+            assert_eq!(
+                policy.resolve_secrets(&secrets(value)).unwrap()["KEY"],
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unreadable_relative_or_unlisted_references() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let policy = SecretRefPolicy::new(
+            Some(temp.path().to_path_buf()),
+            vec!["TAKO_SECRET_REF_TEST_UNSET".to_string()],
+            &temp.path().join("data"),
+        );
+        for value in [
+            "file:relative/path",
+            "file:/nonexistent/tako-secret",
+            "env:TAKO_SECRET_REF_TEST_UNSET",
+            "env:PATH",
+        ] {
+            let err = policy.resolve_secrets(&secrets(value)).unwrap_err();
+            assert!(err.starts_with("secret 'KEY':"), "{err}");
+        }
+    }
+
+    #[test]
+    fn file_references_stay_inside_the_allowed_directory() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let refs = temp.path().join("refs");
+        std::fs::create_dir(&refs).unwrap();
+        let outside = temp.path().join("outside");
+        std::fs::write(&outside, "nope").unwrap();
+        let policy =
+            SecretRefPolicy::new(Some(refs.clone()), Vec::new(), &temp.path().join("data"));
+
+        let direct = format!("file:{}", outside.display());
+        let dotdot = format!("file:{}/../outside", refs.display());
+        for value in [direct, dotdot] {
+            let err = policy.resolve_secrets(&secrets(&value)).unwrap_err();
+            assert!(err.contains("is outside"), "{err}");
+        }
+
+        #[cfg(unix)]
+        {
+            let link = refs.join("link");
+            std::os::unix::fs::symlink(&outside, &link).unwrap();
+            let err = policy
+                .resolve_secrets(&secrets(&format!("file:{}", link.display())))
+                .unwrap_err();
+            assert!(err.contains("is outside"), "{err}");
+        }
+    }
+
+    #[test]
+    fn file_references_never_read_the_data_directory() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let data_dir = temp.path().join("data");
+        std::fs::create_dir(&data_dir).unwrap();
+        std::fs::write(data_dir.join("secret.key"), "device key").unwrap();
+        // Even an allowlisted directory that contains the data dir can't reach it.
+        let policy = SecretRefPolicy::new(Some(temp.path().to_path_buf()), Vec::new(), &data_dir);
+
+        let value = format!("file:{}", data_dir.join("secret.key").display());
+        let err = policy.resolve_secrets(&secrets(&value)).unwrap_err();
+        assert!(err.contains("server data directory"), "{err}");
+    }
+}
@@ -5,14 +5,19 @@ use crate::release::{
     ensure_app_runtime_data_dirs, inject_app_data_dir_env, resolve_release_runtime_bin,
 };
 use crate::routing::RouteTable;
+use crate::secret_refs::SecretRefPolicy;
+use crate::shutdown::{DrainCoordinator, DrainPhase};
 use crate::socket::{AppState, Response};
-use crate::state_store::{SqliteStateStore, StateStoreError, load_or_create_device_key};
+use crate::state_store::{
+    InMemoryStateStore, PersistedApp, SqliteStateStore, StateStore, StateStoreError,
+    load_device_key, load_or_create_device_key,
+};
 use crate::tls::{AcmeClient, CertManager, ChallengeTokens};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
-use tako_core::{ServerRuntimeInfo, UpgradeMode};
+use std::time::{Duration, Instant};
+use tako_core::{CertWarning, ServerRuntimeInfo, UpgradeMode};
 use tokio::sync::RwLock;
 
 #[derive(Debug, Clone)]
@@ -24,11 +29,27 @@ pub struct ServerRuntimeConfig {
     pub(crate) https_port: u16,
     pub(crate) no_acme: bool,
     pub(crate) acme_staging: bool,
+    pub(crate) acme_directory: Option<String>,
     pub(crate) renewal_interval_hours: u64,
     pub(crate) dns_provider: Option<String>,
     pub(crate) standby: bool,
     pub(crate) metrics_port: Option<u16>,
     pub(crate) server_name: Option<String>,
+    /// Keep server state in memory only; nothing survives a restart.
+    pub(crate) ephemeral: bool,
+    /// Let the `env` command return secret values when asked to reveal them.
+    pub(crate) allow_secret_reveal: bool,
+    /// Key that encrypts secrets at rest, instead of generating
+    /// `{data_dir}/secret.key`.
+    pub(crate) state_key_file: Option<PathBuf>,
+    /// HTML page served with the 503 when an on-demand app misses its
+    /// cold start deadline.
+    pub(crate) cold_start_page: Option<PathBuf>,
+    /// Extra domain suffixes (normalized, no leading dot) whose routes get
+    /// self-signed certificates instead of ACME.
+    pub(crate) private_domain_suffixes: Vec<String>,
+    /// Which `file:` / `env:` secret references are resolved (none by default).
+    pub(crate) secret_refs: SecretRefPolicy,
 }
 
 impl ServerRuntimeConfig {
@@ -41,11 +62,38 @@ impl ServerRuntimeConfig {
             https_port: 443,
             no_acme: false,
             acme_staging: false,
+            acme_directory: None,
             renewal_interval_hours: 12,
             dns_provider: None,
             standby: false,
             metrics_port: Some(9898),
             server_name: None,
+            ephemeral: false,
+            allow_secret_reveal: false,
+            state_key_file: None,
+            cold_start_page: None,
+            private_domain_suffixes: Vec::new(),
+            secret_refs: SecretRefPolicy::default(),
+        }
+    }
+
+    /// Cold start settings, with the operator's timeout page when one was
+    /// given and can be read.
+    pub(crate) fn cold_start_config(&self) -> crate::scaling::ColdStartConfig {
+        let unavailable_html = self.cold_start_page.as_ref().and_then(|path| {
+            std::fs::read_to_string(path)
+                .map_err(|e| {
+                    tracing::warn!(
+                        path = %path.display(),
+                        "Failed to read cold start page, using the built-in one: {}",
+                        e
+                    );
+                })
+                .ok()
+        });
+        crate::scaling::ColdStartConfig {
+            unavailable_html,
+            ..Default::default()
         }
     }
 
@@ -59,16 +107,31 @@ impl ServerRuntimeConfig {
             https_port: self.https_port,
             no_acme: self.no_acme,
             acme_staging: self.acme_staging,
+            acme_directory: self.acme_directory.clone(),
             acme_email: None,
             renewal_interval_hours: self.renewal_interval_hours,
             dns_provider: self.dns_provider.clone(),
             standby: self.standby,
             metrics_port: self.metrics_port,
             server_name: self.server_name.clone(),
+            cert_warnings: Vec::new(),
         }
     }
 }
 
+/// Pending status-change notifications before slow `status_stream`
+/// subscribers skip ahead to the latest status.
+const STATUS_CHANGE_CAPACITY: usize = 256;
+
+/// An app's last successful deploy, kept so a repeat of the same deploy
+/// (matching idempotency key) returns this result instead of redeploying.
+#[derive(Debug, Clone)]
+pub(crate) struct CompletedDeploy {
+    pub(crate) idempotency_key: String,
+    pub(crate) version: String,
+    pub(crate) response: serde_json::Value,
+}
+
 /// Server state shared across components
 pub struct ServerState {
     pub(crate) app_manager: Arc<AppManager>,
@@ -78,11 +141,19 @@ pub struct ServerState {
     pub(crate) challenge_tokens: ChallengeTokens,
     pub(crate) routes: Arc<RwLock<RouteTable>>,
     pub(crate) deploy_locks: RwLock<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// Last successful keyed deploy by app (in memory; cleared on restart).
+    pub(crate) completed_deploys: parking_lot::Mutex<HashMap<String, CompletedDeploy>>,
+    /// Names of apps whose instances just changed state; feeds `status_stream`.
+    pub(crate) status_changes: tokio::sync::broadcast::Sender<String>,
     pub(crate) cold_start: Arc<crate::scaling::ColdStartManager>,
-    pub(crate) state_store: Arc<SqliteStateStore>,
+    pub(crate) state_store: Arc<dyn StateStore>,
     pub(crate) server_mode: RwLock<UpgradeMode>,
     pub(crate) runtime: ServerRuntimeConfig,
     pub(crate) workflows: Arc<crate::workflows::WorkflowManager>,
+    /// Outstanding certificate warnings by domain, surfaced via `ServerInfo`
+    pub(crate) cert_warnings: parking_lot::RwLock<BTreeMap<String, CertWarning>>,
+    /// Graceful shutdown progress (see [`crate::shutdown`])
+    pub(crate) drain: parking_lot::Mutex<DrainCoordinator>,
 }
 
 impl ServerState {
@@ -109,11 +180,39 @@ impl ServerState {
         challenge_tokens: ChallengeTokens,
         runtime: ServerRuntimeConfig,
     ) -> Result<Self, StateStoreError> {
-        let app_manager = Arc::new(AppManager::new(data_dir.clone()));
+        let state_store: Arc<dyn StateStore> = if runtime.ephemeral {
+            Arc::new(InMemoryStateStore::new())
+        } else {
+            let device_key = match &runtime.state_key_file {
+                Some(path) => load_device_key(path)?,
+                None => load_or_create_device_key(&data_dir.join("secret.key"))?,
+            };
+            let store = SqliteStateStore::new(data_dir.join("tako.db"), device_key);
+            init_state_store(&store)?;
+            Arc::new(store)
+        };
+        Self::new_with_store(
+            data_dir,
+            cert_manager,
+            acme_client,
+            challenge_tokens,
+            runtime,
+            state_store,
+        )
+    }
+
+    pub(crate) fn new_with_store(
+        data_dir: PathBuf,
+        cert_manager: Arc<CertManager>,
+        acme_client: Option<Arc<AcmeClient>>,
+        challenge_tokens: ChallengeTokens,
+        runtime: ServerRuntimeConfig,
+        state_store: Arc<dyn StateStore>,
+    ) -> Result<Self, StateStoreError> {
+        let app_manager = Arc::new(
+            AppManager::new(data_dir.clone()).with_secret_refs(runtime.secret_refs.clone()),
+        );
         let load_balancer = Arc::new(LoadBalancer::new(app_manager.clone()));
-        let device_key = load_or_create_device_key(&data_dir.join("secret.key"))?;
-        let state_store = Arc::new(SqliteStateStore::new(data_dir.join("tako.db"), device_key));
-        state_store.init()?;
         let server_mode = state_store.server_mode()?;
         if server_mode == UpgradeMode::Upgrading {
             state_store.set_server_mode(UpgradeMode::Normal)?;
@@ -168,7 +267,7 @@ impl ServerState {
         if tokio::runtime::Handle::try_current().is_ok() {
             workflows
                 .start_socket()
-                .map_err(|error| StateStoreError::InvalidData(error.to_string()))?;
+                .map_err(|error| StateStoreError::Open(format!("internal socket: {error}")))?;
         }
 
         Ok(Self {
@@ -179,13 +278,17 @@ impl ServerState {
             challenge_tokens,
             routes: Arc::new(RwLock::new(RouteTable::default())),
             deploy_locks: RwLock::new(HashMap::new()),
+            completed_deploys: parking_lot::Mutex::new(HashMap::new()),
+            status_changes: tokio::sync::broadcast::channel(STATUS_CHANGE_CAPACITY).0,
             cold_start: Arc::new(crate::scaling::ColdStartManager::new(
-                crate::scaling::ColdStartConfig::default(),
+                runtime.cold_start_config(),
             )),
             state_store,
             server_mode: RwLock::new(server_mode),
             runtime,
             workflows,
+            cert_warnings: parking_lot::RwLock::new(BTreeMap::new()),
+            drain: parking_lot::Mutex::new(DrainCoordinator::default()),
         })
     }
 
@@ -257,10 +360,16 @@ impl ServerState {
     pub(crate) fn ensure_internal_socket_started(&self) -> Result<(), StateStoreError> {
         self.workflows
             .start_socket()
-            .map_err(|error| StateStoreError::InvalidData(error.to_string()))
+            .map_err(|error| StateStoreError::Open(format!("internal socket: {error}")))
     }
 
     pub(crate) async fn reject_mutating_when_upgrading(&self, command: &str) -> Option<Response> {
+        if self.is_shutting_down() {
+            return Some(Response::error(format!(
+                "Server is shutting down; '{}' is blocked.",
+                command
+            )));
+        }
         let mode = *self.server_mode.read().await;
         if mode == UpgradeMode::Upgrading {
             return Some(Response::error(format!(
@@ -271,9 +380,70 @@ impl ServerState {
         None
     }
 
+    /// Wake `status_stream` subscribers watching `app_name`.
+    pub(crate) fn notify_status_change(&self, app_name: &str) {
+        let _ = self.status_changes.send(app_name.to_string());
+    }
+
+    pub(crate) fn is_shutting_down(&self) -> bool {
+        self.drain.lock().phase() != DrainPhase::Running
+    }
+
+    /// Requests currently proxied to app instances.
+    pub(crate) fn in_flight_requests(&self) -> u64 {
+        self.app_manager
+            .list_apps()
+            .iter()
+            .filter_map(|name| self.app_manager.get_app(name))
+            .flat_map(|app| app.get_instances())
+            .map(|instance| instance.in_flight())
+            .sum()
+    }
+
+    /// Drain in-flight requests for up to `timeout`, then kill every instance
+    /// and flush app state. A call while shutdown is already underway only
+    /// shortens the deadline and returns immediately.
+    pub(crate) async fn shutdown(&self, timeout: Duration) {
+        if !self.drain.lock().begin(Instant::now(), timeout) {
+            return;
+        }
+        tracing::info!(
+            timeout_secs = timeout.as_secs(),
+            in_flight = self.in_flight_requests(),
+            "Draining before shutdown"
+        );
+        while self
+            .drain
+            .lock()
+            .poll(Instant::now(), self.in_flight_requests())
+            == DrainPhase::Draining
+        {
+            tokio::time::sleep(crate::defaults::SHUTDOWN_POLL_INTERVAL).await;
+        }
+
+        for app_name in self.app_manager.list_apps() {
+            let Some(app) = self.app_manager.get_app(&app_name) else {
+                continue;
+            };
+            for instance in app.get_instances() {
+                if let Err(e) = instance.kill().await {
+                    tracing::warn!(app = %app_name, instance = %instance.id, "Failed to kill instance: {}", e);
+                }
+            }
+        }
+        // No state flush here: every change is persisted when it happens, and
+        // after a reload the successor owns the database, so writing this
+        // process's view back would undo what it accepted during the drain.
+
+        self.drain.lock().finish();
+        tracing::info!("Shutdown complete");
+    }
+
     pub async fn runtime_info(&self) -> ServerRuntimeInfo {
         let mode = *self.server_mode.read().await;
-        self.runtime.to_runtime_info(mode)
+        let mut info = self.runtime.to_runtime_info(mode);
+        info.cert_warnings = self.cert_warnings.read().values().cloned().collect();
+        info
     }
 
     /// Reconcile workflow + channel runtime support for the active release.
@@ -346,7 +516,21 @@ impl ServerState {
         }
         let internal_socket = self.workflows.socket_path();
 
-        let secrets = self.state_store.get_secrets(app_name).unwrap_or_default();
+        let secrets = match self
+            .runtime
+            .secret_refs
+            .resolve_secrets(&self.state_store.get_secrets(app_name).unwrap_or_default())
+        {
+            Ok(secrets) => secrets,
+            Err(e) => {
+                tracing::warn!(
+                    app = app_name,
+                    "Workflow engine not started: failed to resolve {}",
+                    e
+                );
+                return;
+            }
+        };
 
         let app = app_name.to_string();
         let app_for_spec = app.clone();
@@ -388,70 +572,78 @@ impl ServerState {
         tracing::info!(apps = apps.len(), "Restoring apps from durable state");
 
         for persisted in apps {
-            let mut config = persisted.config.clone();
-            let app_name = config.deployment_id();
-            let routes = persisted.routes.clone();
-
-            if self.runtime.standby && config.min_instances > 1 {
-                config.min_instances = 1;
-                config.max_instances = config.max_instances.max(1);
+            let app_name = persisted.config.deployment_id();
+            if let Err(error) = self.register_persisted_app(persisted).await {
+                tracing::error!(app = %app_name, "{}", error);
             }
+        }
 
-            let should_start = config.min_instances > 0;
-            let release_path = release_app_path(&self.runtime.data_dir, &config);
-            if let Err(error) =
-                apply_release_runtime_to_config(&mut config, release_path.clone(), None)
-            {
-                tracing::error!(app = %app_name, "Failed to restore app config: {}", error);
-                continue;
-            }
-            match ensure_app_runtime_data_dirs(&self.runtime.data_dir, &app_name) {
-                Ok(paths) => inject_app_data_dir_env(&mut config.env_vars, &paths),
-                Err(error) => {
-                    tracing::error!(app = %app_name, "Failed to prepare app data dirs: {}", error);
-                    continue;
-                }
-            }
-            config.secrets = self.state_store.get_secrets(&app_name).unwrap_or_else(|e| {
-                tracing::warn!(app = %app_name, "Failed to read secrets: {}", e);
-                HashMap::new()
-            });
+        Ok(())
+    }
 
-            let app = self.app_manager.register_app(config.clone());
-            self.load_balancer.register_app(app.clone());
+    /// Register an app from its persisted shape and start it (or leave it
+    /// idle when it scales to zero). Used at boot and by `import`.
+    pub(crate) async fn register_persisted_app(
+        &self,
+        persisted: PersistedApp,
+    ) -> Result<(), String> {
+        let mut config = persisted.config;
+        let app_name = config.deployment_id();
+
+        if self.runtime.standby && config.min_instances > 1 {
+            config.min_instances = 1;
+            config.max_instances = config.max_instances.max(1);
+        }
 
-            {
-                let mut route_table = self.routes.write().await;
-                route_table.set_app_routes(app_name.clone(), routes);
+        let should_start = config.min_instances > 0;
+        let release_path = release_app_path(&self.runtime.data_dir, &config);
+        apply_release_runtime_to_config(&mut config, release_path.clone(), None)
+            .map_err(|error| format!("Failed to restore app config: {error}"))?;
+        let paths = ensure_app_runtime_data_dirs(&self.runtime.data_dir, &app_name)
+            .map_err(|error| format!("Failed to prepare app data dirs: {error}"))?;
+        inject_app_data_dir_env(&mut config.env_vars, &paths);
+        config.secrets = self.state_store.get_secrets(&app_name).unwrap_or_else(|e| {
+            tracing::warn!(app = %app_name, "Failed to read secrets: {}", e);
+            HashMap::new()
+        });
+
+        let app = self.app_manager.register_app(config.clone());
+        self.load_balancer.register_app(app.clone());
+
+        {
+            let mut route_table = self.routes.write().await;
+            if let Err(e) = route_table.set_app_routes(app_name.clone(), persisted.routes) {
+                tracing::warn!(app = %app_name, "Skipping restored routes: {}", e);
             }
+            for (route, priority) in &persisted.route_priorities {
+                route_table.set_route_priority(&app_name, route, *priority);
+            }
+        }
 
-            let runtime_bin_path =
-                resolve_release_runtime_bin(&release_path, &self.runtime.data_dir)
-                    .await
-                    .ok()
-                    .flatten();
-            self.sync_app_workflows(&app_name, &release_path, runtime_bin_path.as_deref())
-                .await;
-
-            if should_start {
-                match self.app_manager.start_app(&app_name).await {
-                    Ok(()) => {
-                        app.set_state(AppState::Running);
-                        tracing::info!(app = %app_name, "Restored and started app");
-                    }
-                    Err(e) => {
-                        app.set_state(AppState::Error);
-                        app.set_last_error(format!("Restore startup failed: {}", e));
-                        tracing::error!(app = %app_name, "Failed to start restored app: {}", e);
-                    }
+        let runtime_bin_path = resolve_release_runtime_bin(&release_path, &self.runtime.data_dir)
+            .await
+            .ok()
+            .flatten();
+        self.sync_app_workflows(&app_name, &release_path, runtime_bin_path.as_deref())
+            .await;
+
+        if should_start {
+            match self.app_manager.start_app(&app_name).await {
+                Ok(()) => {
+                    app.set_state(AppState::Running);
+                    tracing::info!(app = %app_name, "Restored and started app");
+                }
+                Err(e) => {
+                    app.set_state(AppState::Error);
+                    app.set_last_error(format!("Restore startup failed: {}", e));
+                    tracing::error!(app = %app_name, "Failed to start restored app: {}", e);
                 }
-            } else {
-                app.set_state(AppState::Idle);
-                self.cold_start.reset(&app_name);
-                tracing::info!(app = %app_name, "Restored on-demand app in idle state");
             }
+        } else {
+            app.set_state(AppState::Idle);
+            self.cold_start.reset(&app_name);
+            tracing::info!(app = %app_name, "Restored on-demand app in idle state");
         }
-
         Ok(())
     }
 
@@ -469,3 +661,18 @@ impl ServerState {
         }
     }
 }
+
+/// Initialize `tako.db`, retrying while another process holds its lock.
+fn init_state_store(store: &SqliteStateStore) -> Result<(), StateStoreError> {
+    let mut attempt = 1;
+    loop {
+        match store.init() {
+            Err(e) if e.is_retryable() && attempt < crate::defaults::STATE_STORE_INIT_ATTEMPTS => {
+                tracing::warn!(attempt, "State store is busy, retrying: {}", e);
+                std::thread::sleep(crate::defaults::STATE_STORE_INIT_RETRY_DELAY);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
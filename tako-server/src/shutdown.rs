@@ -0,0 +1,147 @@
+//! Graceful shutdown on SIGTERM/SIGINT
+//!
+//! A stop signal moves the server from `Running` to `Draining`: listeners
+//! close, mutating commands are rejected, and in-flight requests get until
+//! the deadline to finish. Once they have (or time runs out) the server is
+//! `Stopping` while instances are killed and state is flushed, then `Stopped`.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainPhase {
+    Running,
+    Draining,
+    Stopping,
+    Stopped,
+}
+
+#[derive(Debug)]
+pub struct DrainCoordinator {
+    phase: DrainPhase,
+    deadline: Option<Instant>,
+}
+
+impl Default for DrainCoordinator {
+    fn default() -> Self {
+        Self {
+            phase: DrainPhase::Running,
+            deadline: None,
+        }
+    }
+}
+
+impl DrainCoordinator {
+    pub fn phase(&self) -> DrainPhase {
+        self.phase
+    }
+
+    /// Start draining with `timeout` to finish in-flight requests. Returns
+    /// `false` when shutdown is already underway; a second signal can only
+    /// pull the deadline in.
+    pub fn begin(&mut self, now: Instant, timeout: Duration) -> bool {
+        let deadline = now + timeout;
+        match self.phase {
+            DrainPhase::Running => {
+                self.phase = DrainPhase::Draining;
+                self.deadline = Some(deadline);
+                true
+            }
+            DrainPhase::Draining => {
+                self.deadline = self.deadline.map(|d| d.min(deadline));
+                false
+            }
+            DrainPhase::Stopping | DrainPhase::Stopped => false,
+        }
+    }
+
+    /// Move from `Draining` to `Stopping` once nothing is in flight or the
+    /// deadline has passed.
+    pub fn poll(&mut self, now: Instant, in_flight: u64) -> DrainPhase {
+        if self.phase == DrainPhase::Draining
+            && (in_flight == 0 || self.deadline.is_some_and(|d| now >= d))
+        {
+            self.phase = DrainPhase::Stopping;
+        }
+        self.phase
+    }
+
+    /// Instances are stopped and state is flushed.
+    pub fn finish(&mut self) {
+        if self.phase == DrainPhase::Stopping {
+            self.phase = DrainPhase::Stopped;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn drains_until_in_flight_requests_finish() {
+        let start = Instant::now();
+        let mut drain = DrainCoordinator::default();
+        assert_eq!(drain.poll(start, 0), DrainPhase::Running);
+
+        assert!(drain.begin(start, TIMEOUT));
+        assert_eq!(
+            drain.poll(start + Duration::from_secs(1), 3),
+            DrainPhase::Draining
+        );
+        assert_eq!(
+            drain.poll(start + Duration::from_secs(2), 0),
+            DrainPhase::Stopping
+        );
+
+        drain.finish();
+        assert_eq!(drain.phase(), DrainPhase::Stopped);
+        assert!(!drain.begin(start + Duration::from_secs(3), TIMEOUT));
+        assert_eq!(drain.phase(), DrainPhase::Stopped);
+    }
+
+    #[test]
+    fn deadline_stops_draining_with_requests_in_flight() {
+        let start = Instant::now();
+        let mut drain = DrainCoordinator::default();
+        drain.begin(start, TIMEOUT);
+
+        assert_eq!(
+            drain.poll(start + TIMEOUT - Duration::from_millis(1), 1),
+            DrainPhase::Draining
+        );
+        assert_eq!(drain.poll(start + TIMEOUT, 1), DrainPhase::Stopping);
+    }
+
+    #[test]
+    fn second_signal_only_shortens_the_deadline() {
+        let start = Instant::now();
+        let mut drain = DrainCoordinator::default();
+        assert!(drain.begin(start, TIMEOUT));
+        assert!(!drain.begin(start + Duration::from_secs(1), Duration::ZERO));
+        assert_eq!(
+            drain.poll(start + Duration::from_secs(1), 5),
+            DrainPhase::Stopping
+        );
+
+        let mut drain = DrainCoordinator::default();
+        drain.begin(start, Duration::from_secs(5));
+        drain.begin(start, TIMEOUT);
+        assert_eq!(
+            drain.poll(start + Duration::from_secs(5), 5),
+            DrainPhase::Stopping
+        );
+    }
+
+    #[test]
+    fn finish_requires_stopping() {
+        let mut drain = DrainCoordinator::default();
+        drain.finish();
+        assert_eq!(drain.phase(), DrainPhase::Running);
+
+        drain.begin(Instant::now(), TIMEOUT);
+        drain.finish();
+        assert_eq!(drain.phase(), DrainPhase::Draining);
+    }
+}
@@ -5,21 +5,38 @@
 //! - stop: Stop an app
 //! - delete: Delete an app from runtime state
 //! - status: Get app status
+//! - status_stream: Push app status on every instance state change
 //! - list: List all apps
 //! - update_secrets: Update an app's secrets and apply by rolling restart
 //! - server_info/enter_upgrading/exit_upgrading: Upgrade orchestration primitives
 
 use std::future::Future;
 use std::path::{Path, PathBuf};
+use tokio::io::BufReader;
 use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc;
 
-use tako_socket::serve_jsonl_connection;
+use tako_socket::{read_json_line, write_json_line};
 
 // Re-export protocol types from tako-core for shared use
 pub use tako_core::{
-    AppState, AppStatus, BuildStatus, Command, InstanceState, InstanceStatus, Response,
+    AppState, AppStatus, BuildStatus, Command, InstanceState, InstanceStatus, ResourceUsage,
+    Response,
 };
 
+/// What a command handler sends back: a single response, or a stream of
+/// responses that takes over the rest of the connection.
+pub enum Reply {
+    Once(Response),
+    Stream(mpsc::Receiver<Response>),
+}
+
+impl From<Response> for Reply {
+    fn from(response: Response) -> Self {
+        Self::Once(response)
+    }
+}
+
 /// Management socket server.
 ///
 /// Binds a pid-specific socket (`tako-{pid}.sock`) and atomically swaps a
@@ -64,13 +81,12 @@ impl SocketServer {
     /// runtime work (ACME init, state restore, etc.), ensuring the new process
     /// takes over the management socket within milliseconds of starting.
     pub fn bind(&self) -> Result<std::os::unix::net::UnixListener, std::io::Error> {
-        // Remove stale pid-specific socket file if present
-        let _ = std::fs::remove_file(&self.actual_path);
-
         if let Some(parent) = self.actual_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
+        remove_stale_socket(&self.actual_path)?;
+
         let std_listener = std::os::unix::net::UnixListener::bind(&self.actual_path)?;
         std_listener.set_nonblocking(true)?;
 
@@ -105,13 +121,14 @@ impl SocketServer {
     /// Run the accept loop on a pre-bound std listener, dispatching each
     /// connection to `handler`. Converts to tokio internally (must be called
     /// from within a Tokio runtime context).
-    pub async fn serve<F, Fut>(
+    pub async fn serve<F, Fut, R>(
         std_listener: std::os::unix::net::UnixListener,
         handler: F,
     ) -> Result<(), std::io::Error>
     where
         F: Fn(Command) -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = Response> + Send + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        R: Into<Reply>,
     {
         let listener = UnixListener::from_std(std_listener)?;
         let handler = std::sync::Arc::new(handler);
@@ -134,36 +151,88 @@ impl SocketServer {
     }
 
     /// Start listening for commands (convenience wrapper: bind + serve).
-    pub async fn run<F, Fut>(&self, handler: F) -> Result<(), std::io::Error>
+    pub async fn run<F, Fut, R>(&self, handler: F) -> Result<(), std::io::Error>
     where
         F: Fn(Command) -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = Response> + Send + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        R: Into<Reply>,
     {
         let listener = self.bind()?;
         Self::serve(listener, handler).await
     }
 }
 
-async fn handle_connection<F, Fut>(
+/// Clear a socket file left behind by a crashed process so `bind` doesn't
+/// fail with `EADDRINUSE`.
+///
+/// Unconditionally unlinking would cut off clients of a server that is still
+/// listening, so probe it first and refuse if anything answers.
+fn remove_stale_socket(path: &Path) -> Result<(), std::io::Error> {
+    if !path.exists() {
+        return Ok(());
+    }
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::AddrInUse,
+            format!("management socket already in use at {}", path.display()),
+        )),
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::NotConnected
+                    | std::io::ErrorKind::ConnectionReset
+            ) =>
+        {
+            tracing::info!(path = %path.display(), "Removing stale management socket");
+            std::fs::remove_file(path)
+        }
+        // Leave anything else to `bind`, which reports a clearer error.
+        Err(_) => Ok(()),
+    }
+}
+
+async fn handle_connection<F, Fut, R>(
     stream: UnixStream,
     handler: std::sync::Arc<F>,
 ) -> Result<(), std::io::Error>
 where
     F: Fn(Command) -> Fut + Send + Sync + 'static,
-    Fut: Future<Output = Response> + Send + 'static,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Into<Reply>,
 {
-    serve_jsonl_connection(
-        stream,
-        move |cmd| {
-            let handler = handler.clone();
-            async move {
-                tracing::debug!("Received command: {:?}", cmd);
-                handler(cmd).await
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    loop {
+        let cmd = match read_json_line::<_, Command>(&mut reader).await {
+            Ok(Some(cmd)) => cmd,
+            Ok(None) => break,
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                let resp = Response::error(format!("Invalid command: {}", e));
+                let _ = write_json_line(&mut writer, &resp).await;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        tracing::debug!("Received command: {:?}", cmd);
+
+        match handler(cmd).await.into() {
+            Reply::Once(resp) => write_json_line(&mut writer, &resp).await?,
+            Reply::Stream(mut frames) => {
+                // Dropping `frames` on a failed write tells the producer the
+                // client went away.
+                while let Some(frame) = frames.recv().await {
+                    if write_json_line(&mut writer, &frame).await.is_err() {
+                        break;
+                    }
+                }
+                return Ok(());
             }
-        },
-        |e| Response::error(format!("Invalid command: {}", e)),
-    )
-    .await
+        }
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -201,12 +270,18 @@ mod tests {
                 path,
                 routes,
                 secrets,
+                idempotency_key,
+                strategy,
+                canary,
             } => {
                 assert_eq!(app, "my-app");
                 assert_eq!(version, "1.0.0");
                 assert!(path.contains("releases"));
                 assert_eq!(routes.len(), 2);
                 assert!(secrets.is_none());
+                assert!(idempotency_key.is_none());
+                assert_eq!(strategy, tako_core::DeployStrategy::Rolling);
+                assert!(canary.is_none());
             }
             _ => panic!("Expected Deploy command"),
         }
@@ -304,7 +379,7 @@ mod tests {
         match cmd {
             Command::Rollback { app, version } => {
                 assert_eq!(app, "my-app");
-                assert_eq!(version, "abc1234");
+                assert_eq!(version.as_deref(), Some("abc1234"));
             }
             _ => panic!("Expected Rollback command"),
         }
@@ -383,6 +458,39 @@ mod tests {
         server_task.await.unwrap().unwrap();
     }
 
+    #[tokio::test]
+    async fn test_handle_connection_writes_each_stream_frame() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+
+        let handler = Arc::new(|_cmd: Command| async move {
+            let (tx, rx) = mpsc::channel(4);
+            for n in 0..2 {
+                tx.send(Response::ok(serde_json::json!({ "frame": n })))
+                    .await
+                    .unwrap();
+            }
+            Reply::Stream(rx)
+        });
+        let server_task = tokio::spawn(handle_connection(server, handler));
+
+        client
+            .write_all(b"{\"command\":\"status_stream\",\"app\":\"a\"}\n")
+            .await
+            .unwrap();
+
+        let mut raw = Vec::new();
+        client.read_to_end(&mut raw).await.unwrap();
+        let frames: Vec<Response> = String::from_utf8(raw)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(frames.len(), 2);
+        assert!(frames.iter().all(Response::is_ok));
+
+        server_task.await.unwrap().unwrap();
+    }
+
     #[tokio::test]
     #[cfg(unix)]
     async fn test_run_creates_pid_socket_and_symlink() {
@@ -451,4 +559,36 @@ mod tests {
         server_task.abort();
         let _ = server_task.await;
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_bind_replaces_stale_socket_file() {
+        let temp = TempDir::new().unwrap();
+        let server = SocketServer::new(temp.path().join("tako.sock").to_string_lossy().to_string());
+        // A listener dropped without unlinking leaves an unconnectable socket file,
+        // as after a crash.
+        match std::os::unix::net::UnixListener::bind(&server.actual_path) {
+            Ok(stale) => drop(stale),
+            Err(_) => return,
+        }
+        assert!(server.actual_path.exists());
+
+        let listener = server.bind().unwrap();
+        assert!(std::os::unix::net::UnixStream::connect(&server.actual_path).is_ok());
+        drop(listener);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_bind_refuses_when_live_server_present() {
+        let temp = TempDir::new().unwrap();
+        let server = SocketServer::new(temp.path().join("tako.sock").to_string_lossy().to_string());
+        let Ok(_live) = std::os::unix::net::UnixListener::bind(&server.actual_path) else {
+            return;
+        };
+
+        let err = server.bind().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+        assert!(std::os::unix::net::UnixStream::connect(&server.actual_path).is_ok());
+    }
 }
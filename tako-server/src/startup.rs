@@ -5,10 +5,17 @@ use crate::boot::{
 use crate::instances::{HealthChecker, HealthConfig};
 use crate::metrics;
 use crate::proxy::{self, ProxyConfig};
-use crate::runtime_events::{handle_health_event, handle_idle_event, handle_instance_event};
+use crate::release::normalize_private_domain_suffix;
+use crate::runtime_events::{
+    handle_cert_event, handle_health_event, handle_idle_event, handle_instance_event,
+};
 use crate::scaling::{IdleConfig, IdleMonitor};
+use crate::secret_refs::SecretRefPolicy;
 use crate::socket::SocketServer;
-use crate::tls::{AcmeClient, AcmeConfig, CertManager, CertManagerConfig, ChallengeTokens};
+use crate::tls::{
+    AcmeClient, AcmeConfig, CertEvent, CertManager, CertManagerConfig, ChallengeTokens,
+    DnsChallengeProvider, ManualDnsProvider,
+};
 use crate::{Args, ServerRuntimeConfig, ServerState};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -53,8 +60,10 @@ struct StandbyPromotionConfig {
     state: Arc<ServerState>,
     cert_manager: Arc<CertManager>,
     acme_staging: bool,
+    acme_directory: Option<String>,
     acme_email: Option<String>,
     dns_provider: Option<String>,
+    manual_dns01_wait: Option<Duration>,
     no_acme: bool,
     renewal_interval_hours: u64,
     data_dir: PathBuf,
@@ -64,8 +73,10 @@ struct StandbyPromotionConfig {
 struct AcmeInitConfig {
     standby: bool,
     acme_staging: bool,
+    acme_directory: Option<String>,
     acme_email: Option<String>,
     dns_provider: Option<String>,
+    manual_dns01_wait: Option<Duration>,
     no_acme: bool,
     account_dir: PathBuf,
     data_dir: PathBuf,
@@ -122,13 +133,15 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
 
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: cert_dir.clone(),
+        renewal_days: args.cert_renewal_days,
+        refuse_renewal_on_clock_skew: args.refuse_renewal_on_clock_skew,
         ..Default::default()
     }));
     if let Err(e) = cert_manager.init() {
         tracing::warn!("Failed to initialize certificate manager: {}", e);
     }
 
-    let (_socket_server, socket_listener) = if standby {
+    let (socket_server, socket_listener) = if standby {
         (None, None)
     } else {
         let server = SocketServer::new(&socket);
@@ -140,6 +153,7 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
 
     let server_config = read_server_config(&data_dir);
     let config_dns_provider = server_config.dns.as_ref().map(|d| d.provider.clone());
+    let manual_dns01_wait = args.manual_dns01_wait_secs.map(Duration::from_secs);
     let challenge_tokens: ChallengeTokens = Arc::new(parking_lot::RwLock::new(HashMap::new()));
 
     let acme_client = init_acme_client(
@@ -147,8 +161,10 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
         AcmeInitConfig {
             standby,
             acme_staging: args.acme_staging,
+            acme_directory: args.acme_directory.clone(),
             acme_email: server_config.acme_email.clone(),
             dns_provider: config_dns_provider.clone(),
+            manual_dns01_wait,
             no_acme: args.no_acme,
             account_dir: acme_dir,
             data_dir: data_dir.clone(),
@@ -165,8 +181,12 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
         https_port: args.tls_port,
         no_acme: args.no_acme,
         acme_staging: args.acme_staging,
+        acme_directory: args.acme_directory.clone(),
         renewal_interval_hours: args.renewal_interval_hours,
-        dns_provider: config_dns_provider.clone(),
+        // Reported so wildcard deploys pass the CLI's DNS provider check.
+        dns_provider: config_dns_provider
+            .clone()
+            .or_else(|| manual_dns01_wait.map(|_| "manual".to_string())),
         standby,
         metrics_port: if args.metrics_port == 0 {
             None
@@ -179,6 +199,20 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
                 .and_then(|h| h.into_string().ok())
                 .filter(|h| !h.is_empty())
         }),
+        ephemeral: args.ephemeral,
+        allow_secret_reveal: args.allow_secret_reveal,
+        state_key_file: args.state_key_file.as_ref().map(PathBuf::from),
+        cold_start_page: args.cold_start_page.as_ref().map(PathBuf::from),
+        private_domain_suffixes: args
+            .private_domain_suffixes
+            .iter()
+            .filter_map(|suffix| normalize_private_domain_suffix(suffix))
+            .collect(),
+        secret_refs: SecretRefPolicy::new(
+            args.secret_ref_dir.as_ref().map(PathBuf::from),
+            args.secret_ref_env.clone(),
+            &data_dir,
+        ),
     };
 
     let challenge_tokens_for_promote = challenge_tokens.clone();
@@ -199,7 +233,12 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     spawn_instance_event_bridge(&rt, state.clone());
     spawn_health_monitoring(&rt, state.clone());
     spawn_idle_monitoring(&rt, state.clone());
-    spawn_certificate_renewals(&rt, &acme_client, args.renewal_interval_hours);
+    spawn_certificate_renewals(
+        &rt,
+        state.clone(),
+        &acme_client,
+        args.renewal_interval_hours,
+    );
     spawn_management_socket(&rt, state.clone(), socket_listener);
 
     if standby {
@@ -210,8 +249,10 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
                 state: state.clone(),
                 cert_manager: cert_manager.clone(),
                 acme_staging: args.acme_staging,
+                acme_directory: args.acme_directory.clone(),
                 acme_email: server_config.acme_email.clone(),
                 dns_provider: config_dns_provider,
+                manual_dns01_wait,
                 no_acme: args.no_acme,
                 renewal_interval_hours: args.renewal_interval_hours,
                 data_dir: data_dir.clone(),
@@ -228,11 +269,18 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
         cert_dir,
         redirect_http_to_https: true,
         response_cache: Some(proxy::ResponseCacheConfig::default()),
-        metrics_port: if args.metrics_port == 0 {
-            None
-        } else {
-            Some(args.metrics_port)
-        },
+        metrics_listen: (args.metrics_port != 0).then(|| {
+            std::net::SocketAddr::from((std::net::Ipv4Addr::LOCALHOST, args.metrics_port))
+        }),
+        access_log: args.access_log,
+        access_log_path: args.access_log_path.as_ref().map(PathBuf::from),
+        client_keepalive_timeout: std::time::Duration::from_secs(
+            args.client_keepalive_timeout_secs.max(1),
+        ),
+        compression: args
+            .compress_responses
+            .then(proxy::CompressionConfig::default),
+        listen_ipv6: args.listen_ipv6,
     };
 
     tracing::info!("Starting HTTP proxy on port {}", args.port);
@@ -241,6 +289,7 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     spawn_reload_signal_handlers(&rt, exe);
+    spawn_shutdown_signal_handler(&rt, state.clone(), socket_server);
 
     metrics::init(state.runtime_config().server_name.as_deref());
 
@@ -254,12 +303,48 @@ pub(crate) fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     )?;
 
     sd_notify_ready();
-    server.run_forever();
-
-    #[allow(unreachable_code)]
+    // The shutdown handler exits the process once instances are stopped.
+    server.run(pingora_core::server::RunArgs {
+        #[cfg(unix)]
+        shutdown_signal: Box::new(DrainOnStopSignal),
+    });
     Ok(())
 }
 
+/// Pingora shutdown trigger that treats SIGINT like SIGTERM: listeners stop
+/// and in-flight requests keep running, so `spawn_shutdown_signal_handler`
+/// decides when the process exits. SIGQUIT keeps its graceful-upgrade meaning.
+#[cfg(unix)]
+struct DrainOnStopSignal;
+
+#[cfg(unix)]
+#[async_trait::async_trait]
+impl pingora_core::server::ShutdownSignalWatch for DrainOnStopSignal {
+    async fn recv(&self) -> pingora_core::server::ShutdownSignal {
+        use pingora_core::server::ShutdownSignal;
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let (Ok(mut quit), Ok(mut terminate), Ok(mut interrupt)) = (
+            signal(SignalKind::quit()),
+            signal(SignalKind::terminate()),
+            signal(SignalKind::interrupt()),
+        ) else {
+            tracing::error!("Failed to register proxy shutdown signal handlers");
+            return std::future::pending().await;
+        };
+        tokio::select! {
+            _ = quit.recv() => ShutdownSignal::GracefulUpgrade,
+            _ = terminate.recv() => ShutdownSignal::GracefulTerminate,
+            _ = interrupt.recv() => ShutdownSignal::GracefulTerminate,
+        }
+    }
+}
+
+/// In-process DNS-01 provider for `--manual-dns01-wait-secs`, if set.
+fn manual_dns_challenge_provider(wait: Option<Duration>) -> Option<Arc<dyn DnsChallengeProvider>> {
+    wait.map(|wait| Arc::new(ManualDnsProvider::with_wait(wait)) as Arc<dyn DnsChallengeProvider>)
+}
+
 fn init_acme_client(rt: &Runtime, config: AcmeInitConfig) -> Option<Arc<AcmeClient>> {
     if config.no_acme || config.standby {
         if config.standby {
@@ -273,9 +358,11 @@ fn init_acme_client(rt: &Runtime, config: AcmeInitConfig) -> Option<Arc<AcmeClie
     let client = Arc::new(AcmeClient::with_tokens(
         AcmeConfig {
             staging: config.acme_staging,
+            directory_url: config.acme_directory.clone(),
             email: config.acme_email,
             account_dir: config.account_dir,
             dns_provider: config.dns_provider,
+            dns_challenge_provider: manual_dns_challenge_provider(config.manual_dns01_wait),
             data_dir: config.data_dir,
             ..Default::default()
         },
@@ -288,7 +375,9 @@ fn init_acme_client(rt: &Runtime, config: AcmeInitConfig) -> Option<Arc<AcmeClie
         tracing::warn!("Continuing without ACME - certificates must be managed manually");
         None
     } else {
-        if config.acme_staging {
+        if let Some(directory) = &config.acme_directory {
+            tracing::info!(directory = %directory, "ACME client initialized with custom directory");
+        } else if config.acme_staging {
             tracing::warn!(
                 "Using Let's Encrypt STAGING environment - certificates will NOT be trusted!"
             );
@@ -400,17 +489,31 @@ fn spawn_idle_monitoring(rt: &Runtime, state: Arc<ServerState>) {
 
 fn spawn_certificate_renewals(
     rt: &Runtime,
+    state: Arc<ServerState>,
     acme_client: &Option<Arc<AcmeClient>>,
     renewal_interval_hours: u64,
 ) {
     if let Some(acme) = acme_client {
+        let _guard = rt.enter();
         rt.spawn(certificate_renewal_task(
             acme.clone(),
             Duration::from_secs(renewal_interval_hours * 3600),
+            spawn_cert_event_handler(state),
         ));
     }
 }
 
+/// Spawn the consumer for renewal-task certificate events; must run inside a runtime.
+fn spawn_cert_event_handler(state: Arc<ServerState>) -> mpsc::Sender<CertEvent> {
+    let (cert_event_tx, mut cert_event_rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(event) = cert_event_rx.recv().await {
+            handle_cert_event(&state, event);
+        }
+    });
+    cert_event_tx
+}
+
 fn spawn_management_socket(
     rt: &Runtime,
     state: Arc<ServerState>,
@@ -420,7 +523,7 @@ fn spawn_management_socket(
         rt.spawn(async move {
             if let Err(e) = SocketServer::serve(socket_listener, move |cmd| {
                 let state = state.clone();
-                async move { state.handle_command(cmd).await }
+                async move { state.handle_socket_command(cmd).await }
             })
             .await
             {
@@ -476,7 +579,7 @@ fn spawn_standby_monitor(rt: &Runtime, config: StandbyPromotionConfig) {
                                 tokio::spawn(async move {
                                     if let Err(e) = SocketServer::serve(listener, move |cmd| {
                                         let state = socket_state.clone();
-                                        async move { state.handle_command(cmd).await }
+                                        async move { state.handle_socket_command(cmd).await }
                                     })
                                     .await
                                     {
@@ -499,9 +602,13 @@ fn spawn_standby_monitor(rt: &Runtime, config: StandbyPromotionConfig) {
                             let client = Arc::new(AcmeClient::with_tokens(
                                 AcmeConfig {
                                     staging: config.acme_staging,
+                                    directory_url: config.acme_directory.clone(),
                                     email: config.acme_email.clone(),
                                     account_dir: config.data_dir.join("acme"),
                                     dns_provider: config.dns_provider.clone(),
+                                    dns_challenge_provider: manual_dns_challenge_provider(
+                                        config.manual_dns01_wait,
+                                    ),
                                     data_dir: config.data_dir.clone(),
                                     ..Default::default()
                                 },
@@ -514,6 +621,7 @@ fn spawn_standby_monitor(rt: &Runtime, config: StandbyPromotionConfig) {
                                     tokio::spawn(certificate_renewal_task(
                                         client.clone(),
                                         Duration::from_secs(config.renewal_interval_hours * 3600),
+                                        spawn_cert_event_handler(config.state.clone()),
                                     ));
                                     config.state.set_acme_client(client).await;
                                 }
@@ -587,6 +695,56 @@ fn spawn_reload_signal_handlers(rt: &Runtime, startup_exe: Option<PathBuf>) {
     }
 }
 
+/// SIGTERM drains in-flight requests for up to `SHUTDOWN_DRAIN_TIMEOUT`;
+/// SIGINT (or a second signal) skips the wait. Either way instances are
+/// killed, state is flushed, and the management socket is removed before exit.
+fn spawn_shutdown_signal_handler(
+    rt: &Runtime,
+    state: Arc<ServerState>,
+    socket_server: Option<SocketServer>,
+) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        rt.spawn(async move {
+            let (mut sigterm, mut sigint) = match (
+                signal(SignalKind::terminate()),
+                signal(SignalKind::interrupt()),
+            ) {
+                (Ok(sigterm), Ok(sigint)) => (sigterm, sigint),
+                (Err(err), _) | (_, Err(err)) => {
+                    tracing::error!("Failed to register shutdown signal handlers: {err}");
+                    return;
+                }
+            };
+            let timeout = tokio::select! {
+                _ = sigterm.recv() => {
+                    tracing::info!("SIGTERM received — draining in-flight requests");
+                    crate::defaults::SHUTDOWN_DRAIN_TIMEOUT
+                }
+                _ = sigint.recv() => {
+                    tracing::info!("SIGINT received — shutting down");
+                    Duration::ZERO
+                }
+            };
+
+            let shutdown = state.shutdown(timeout);
+            tokio::pin!(shutdown);
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = sigterm.recv() => state.shutdown(Duration::ZERO).await,
+                    _ = sigint.recv() => state.shutdown(Duration::ZERO).await,
+                }
+            }
+
+            drop(socket_server);
+            std::process::exit(0);
+        });
+    }
+}
+
 #[cfg(all(test, unix))]
 mod tests {
     use super::*;
@@ -0,0 +1,250 @@
+use super::{
+    DEPLOY_HISTORY_LIMIT, PersistedApp, StateStore, StateStoreError, UPGRADE_LOCK_STALE_SECS,
+    VERSION_HISTORY_LIMIT,
+};
+use crate::instances::{AppConfig, MaintenancePage};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use tako_core::{DeployHistoryEntry, UpgradeMode};
+
+/// Process-local state store; everything is lost when the server exits.
+///
+/// Mirrors the SQLite store's semantics (history limits, cascading deletes,
+/// stale upgrade locks) so either backend can sit behind `ServerState`.
+#[derive(Default)]
+pub struct InMemoryStateStore {
+    inner: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    /// Keyed by `(name, environment)` so iteration matches `load_apps` order.
+    apps: BTreeMap<(String, String), StoredApp>,
+    deploy_history: HashMap<String, Vec<DeployHistoryEntry>>,
+    secrets: HashMap<String, HashMap<String, String>>,
+    server_mode: Option<UpgradeMode>,
+    upgrade_lock: Option<(String, i64)>,
+}
+
+struct StoredApp {
+    version: String,
+    min_instances: u32,
+    max_instances: u32,
+    drain_timeout: std::time::Duration,
+    maintenance: Option<MaintenancePage>,
+    routes: Vec<String>,
+    route_priorities: HashMap<String, i32>,
+    /// Oldest first; `version_history` reverses it.
+    version_history: Vec<String>,
+}
+
+impl InMemoryStateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateStore for InMemoryStateStore {
+    fn upsert_app(&self, config: &AppConfig, routes: &[String]) -> Result<(), StateStoreError> {
+        let mut routes = routes.to_vec();
+        routes.sort();
+        routes.dedup();
+
+        let mut state = self.inner.lock();
+        let key = (config.name.clone(), config.environment.clone());
+        let app = state.apps.entry(key).or_insert_with(|| StoredApp {
+            version: config.version.clone(),
+            min_instances: config.min_instances,
+            max_instances: config.max_instances,
+            drain_timeout: config.drain_timeout,
+            maintenance: config.maintenance.clone(),
+            routes: Vec::new(),
+            route_priorities: HashMap::new(),
+            version_history: Vec::new(),
+        });
+        if app.version != config.version {
+            let previous = std::mem::replace(&mut app.version, config.version.clone());
+            app.version_history.push(previous);
+            let excess = app
+                .version_history
+                .len()
+                .saturating_sub(VERSION_HISTORY_LIMIT as usize);
+            app.version_history.drain(..excess);
+        }
+        app.min_instances = config.min_instances;
+        app.max_instances = config.max_instances;
+        app.drain_timeout = config.drain_timeout;
+        app.maintenance = config.maintenance.clone();
+        app.route_priorities
+            .retain(|route, _| routes.contains(route));
+        app.routes = routes;
+        Ok(())
+    }
+
+    fn delete_app(&self, name: &str, environment: &str) -> Result<(), StateStoreError> {
+        let mut state = self.inner.lock();
+        state.secrets.remove(&format!("{name}/{environment}"));
+        state
+            .apps
+            .remove(&(name.to_string(), environment.to_string()));
+        Ok(())
+    }
+
+    fn load_apps(&self) -> Result<Vec<PersistedApp>, StateStoreError> {
+        let state = self.inner.lock();
+        Ok(state
+            .apps
+            .iter()
+            .map(|((name, environment), app)| PersistedApp {
+                config: AppConfig {
+                    name: name.clone(),
+                    environment: environment.clone(),
+                    version: app.version.clone(),
+                    min_instances: app.min_instances,
+                    max_instances: app.max_instances,
+                    drain_timeout: app.drain_timeout,
+                    maintenance: app.maintenance.clone(),
+                    ..Default::default()
+                },
+                routes: app.routes.clone(),
+                route_priorities: app.route_priorities.clone(),
+            })
+            .collect())
+    }
+
+    fn version_history(
+        &self,
+        name: &str,
+        environment: &str,
+    ) -> Result<Vec<String>, StateStoreError> {
+        let state = self.inner.lock();
+        Ok(state
+            .apps
+            .get(&(name.to_string(), environment.to_string()))
+            .map(|app| app.version_history.iter().rev().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn record_deploy(&self, app: &str, entry: &DeployHistoryEntry) -> Result<(), StateStoreError> {
+        let mut state = self.inner.lock();
+        let entries = state.deploy_history.entry(app.to_string()).or_default();
+        entries.push(entry.clone());
+        let excess = entries.len().saturating_sub(DEPLOY_HISTORY_LIMIT as usize);
+        entries.drain(..excess);
+        Ok(())
+    }
+
+    fn deploy_history(
+        &self,
+        app: &str,
+        limit: u32,
+    ) -> Result<Vec<DeployHistoryEntry>, StateStoreError> {
+        let state = self.inner.lock();
+        Ok(state
+            .deploy_history
+            .get(app)
+            .map(|entries| entries.iter().rev().take(limit as usize).cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn set_route_priority(
+        &self,
+        name: &str,
+        environment: &str,
+        route: &str,
+        priority: i32,
+    ) -> Result<(), StateStoreError> {
+        let mut state = self.inner.lock();
+        let Some(app) = state
+            .apps
+            .get_mut(&(name.to_string(), environment.to_string()))
+        else {
+            return Err(StateStoreError::Query(format!(
+                "unknown app: {name}/{environment}"
+            )));
+        };
+        if priority == 0 {
+            app.route_priorities.remove(route);
+        } else {
+            app.route_priorities.insert(route.to_string(), priority);
+        }
+        Ok(())
+    }
+
+    fn set_server_mode(&self, mode: UpgradeMode) -> Result<(), StateStoreError> {
+        self.inner.lock().server_mode = Some(mode);
+        Ok(())
+    }
+
+    fn server_mode(&self) -> Result<UpgradeMode, StateStoreError> {
+        Ok(self.inner.lock().server_mode.unwrap_or(UpgradeMode::Normal))
+    }
+
+    fn try_acquire_upgrade_lock(&self, owner: &str) -> Result<bool, StateStoreError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let mut state = self.inner.lock();
+        let acquired = match &state.upgrade_lock {
+            Some((existing, _)) if existing == owner => true,
+            Some((_, acquired_at)) if now - acquired_at > UPGRADE_LOCK_STALE_SECS => {
+                state.upgrade_lock = Some((owner.to_string(), now));
+                true
+            }
+            Some(_) => false,
+            None => {
+                state.upgrade_lock = Some((owner.to_string(), now));
+                true
+            }
+        };
+        Ok(acquired)
+    }
+
+    fn release_upgrade_lock(&self, owner: &str) -> Result<bool, StateStoreError> {
+        let mut state = self.inner.lock();
+        match &state.upgrade_lock {
+            Some((existing, _)) if existing == owner => {
+                state.upgrade_lock = None;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn upgrade_lock_owner(&self) -> Result<Option<String>, StateStoreError> {
+        Ok(self
+            .inner
+            .lock()
+            .upgrade_lock
+            .as_ref()
+            .map(|(owner, _)| owner.clone()))
+    }
+
+    fn set_secrets(
+        &self,
+        app: &str,
+        secrets: &HashMap<String, String>,
+    ) -> Result<(), StateStoreError> {
+        self.inner
+            .lock()
+            .secrets
+            .insert(app.to_string(), secrets.clone());
+        Ok(())
+    }
+
+    fn get_secrets(&self, app: &str) -> Result<HashMap<String, String>, StateStoreError> {
+        Ok(self
+            .inner
+            .lock()
+            .secrets
+            .get(app)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn delete_secrets(&self, app: &str) -> Result<(), StateStoreError> {
+        self.inner.lock().secrets.remove(app);
+        Ok(())
+    }
+}
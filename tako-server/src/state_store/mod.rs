@@ -0,0 +1,212 @@
+mod memory;
+mod sqlite;
+
+pub use memory::InMemoryStateStore;
+pub use sqlite::SqliteStateStore;
+
+use crate::instances::AppConfig;
+use std::collections::HashMap;
+use std::path::Path;
+use tako_core::{DeployHistoryEntry, UpgradeMode};
+
+/// Number of previously deployed versions kept per app for rollback.
+const VERSION_HISTORY_LIMIT: i64 = 3;
+
+/// Number of deploy attempts kept per app for `history`.
+const DEPLOY_HISTORY_LIMIT: i64 = 100;
+
+/// Stale lock threshold: locks older than this are force-acquired.
+const UPGRADE_LOCK_STALE_SECS: i64 = 600; // 10 minutes
+
+#[derive(Debug, Clone)]
+pub struct PersistedApp {
+    pub config: AppConfig,
+    pub routes: Vec<String>,
+    /// Explicit route priorities keyed by route pattern (unset = 0).
+    pub route_priorities: HashMap<String, i32>,
+}
+
+/// Why a state store operation failed, so callers can tell a transient
+/// lock ([`StateStoreError::Busy`]) from damage that needs an operator.
+#[derive(Debug, thiserror::Error)]
+pub enum StateStoreError {
+    /// The database file, its directory, or the device key could not be
+    /// opened or created.
+    #[error("open state store: {0}")]
+    Open(String),
+
+    /// A schema migration failed; the schema version is left unchanged.
+    #[error("schema migration failed: {0}")]
+    Migration(String),
+
+    /// The database was written by a newer server.
+    #[error("unsupported schema version: {found}")]
+    UnsupportedSchemaVersion { found: i32 },
+
+    /// A value could not be encoded or encrypted for storage.
+    #[error("serialization failed: {0}")]
+    Serialization(String),
+
+    /// Another connection held the database lock past the busy timeout.
+    #[error("database busy: {0}")]
+    Busy(String),
+
+    /// Stored data could not be decoded. `table` is `"database"` when the
+    /// file itself is damaged.
+    #[error("corrupt data in {table}: {detail}")]
+    Corrupt { table: &'static str, detail: String },
+
+    /// Any other failed statement, e.g. a constraint violation.
+    #[error("query failed: {0}")]
+    Query(String),
+}
+
+impl StateStoreError {
+    /// Whether the same operation may succeed if retried shortly.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, StateStoreError::Busy(_))
+    }
+}
+
+impl From<rusqlite::Error> for StateStoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+
+        match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => {
+                StateStoreError::Busy(e.to_string())
+            }
+            Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => {
+                StateStoreError::Corrupt {
+                    table: "database",
+                    detail: e.to_string(),
+                }
+            }
+            Some(ErrorCode::CannotOpen) => StateStoreError::Open(e.to_string()),
+            _ => StateStoreError::Query(e.to_string()),
+        }
+    }
+}
+
+/// Durable server state as seen by `ServerState`.
+///
+/// [`SqliteStateStore`] is the production backend; [`InMemoryStateStore`]
+/// backs `--ephemeral` servers and tests that don't need a database file.
+pub trait StateStore: Send + Sync {
+    /// Insert or replace an app's registration and routes. A version change
+    /// appends the previous version to the app's version history.
+    fn upsert_app(&self, config: &AppConfig, routes: &[String]) -> Result<(), StateStoreError>;
+
+    /// Remove an app together with its routes, priorities, and secrets.
+    fn delete_app(&self, name: &str, environment: &str) -> Result<(), StateStoreError>;
+
+    /// All registered apps ordered by name and environment.
+    fn load_apps(&self) -> Result<Vec<PersistedApp>, StateStoreError>;
+
+    /// Previously deployed versions for an app, most recent first (at most
+    /// [`VERSION_HISTORY_LIMIT`] entries).
+    fn version_history(
+        &self,
+        name: &str,
+        environment: &str,
+    ) -> Result<Vec<String>, StateStoreError>;
+
+    /// Append a deploy attempt to an app's history (keeps the most recent
+    /// [`DEPLOY_HISTORY_LIMIT`] entries).
+    fn record_deploy(&self, app: &str, entry: &DeployHistoryEntry) -> Result<(), StateStoreError>;
+
+    /// Most recent deploy attempts for an app, newest first.
+    fn deploy_history(
+        &self,
+        app: &str,
+        limit: u32,
+    ) -> Result<Vec<DeployHistoryEntry>, StateStoreError>;
+
+    /// Persist an explicit route priority. Priority 0 clears the override.
+    fn set_route_priority(
+        &self,
+        name: &str,
+        environment: &str,
+        route: &str,
+        priority: i32,
+    ) -> Result<(), StateStoreError>;
+
+    fn set_server_mode(&self, mode: UpgradeMode) -> Result<(), StateStoreError>;
+
+    fn server_mode(&self) -> Result<UpgradeMode, StateStoreError>;
+
+    /// Take the single-owner upgrade lock. Re-acquiring as the current owner
+    /// succeeds; a lock held longer than [`UPGRADE_LOCK_STALE_SECS`] is taken over.
+    fn try_acquire_upgrade_lock(&self, owner: &str) -> Result<bool, StateStoreError>;
+
+    /// Release the upgrade lock if `owner` holds it.
+    fn release_upgrade_lock(&self, owner: &str) -> Result<bool, StateStoreError>;
+
+    fn upgrade_lock_owner(&self) -> Result<Option<String>, StateStoreError>;
+
+    fn set_secrets(
+        &self,
+        app: &str,
+        secrets: &HashMap<String, String>,
+    ) -> Result<(), StateStoreError>;
+
+    /// Secrets for an app; empty when none are stored.
+    fn get_secrets(&self, app: &str) -> Result<HashMap<String, String>, StateStoreError>;
+
+    fn delete_secrets(&self, app: &str) -> Result<(), StateStoreError>;
+}
+
+/// Read an existing 256-bit key, e.g. one provisioned with `--state-key-file`.
+pub fn load_device_key(path: &Path) -> Result<[u8; 32], StateStoreError> {
+    let key_bytes = std::fs::read(path)
+        .map_err(|e| StateStoreError::Open(format!("read device key {}: {e}", path.display())))?;
+    if key_bytes.len() != 32 {
+        return Err(StateStoreError::Open(format!(
+            "device key must be 32 bytes, got {}",
+            key_bytes.len()
+        )));
+    }
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&key_bytes);
+    Ok(key)
+}
+
+/// Load or generate a 256-bit device encryption key.
+///
+/// On first call, generates a random key and writes it to `path` with 0600
+/// permissions. On subsequent calls, reads the existing key from disk.
+pub fn load_or_create_device_key(path: &Path) -> Result<[u8; 32], StateStoreError> {
+    if path.exists() {
+        load_device_key(path)
+    } else {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| StateStoreError::Open(format!("create key dir: {e}")))?;
+        }
+        let mut key = [0u8; 32];
+        openssl::rand::rand_bytes(&mut key)
+            .map_err(|e| StateStoreError::Open(format!("generate device key: {e}")))?;
+        #[cfg(unix)]
+        {
+            use std::io::Write;
+            use std::os::unix::fs::OpenOptionsExt;
+            let mut f = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(path)
+                .map_err(|e| StateStoreError::Open(format!("write device key: {e}")))?;
+            f.write_all(&key)
+                .map_err(|e| StateStoreError::Open(format!("write device key: {e}")))?;
+        }
+        #[cfg(not(unix))]
+        {
+            std::fs::write(path, &key)
+                .map_err(|e| StateStoreError::Open(format!("write device key: {e}")))?;
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,801 @@
+use super::{
+    DEPLOY_HISTORY_LIMIT, PersistedApp, StateStore, StateStoreError, UPGRADE_LOCK_STALE_SECS,
+    VERSION_HISTORY_LIMIT,
+};
+use crate::instances::{AppConfig, MaintenancePage};
+use openssl::symm::{Cipher, decrypt_aead, encrypt_aead};
+use rusqlite::OptionalExtension;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tako_core::{DeployHistoryEntry, DeployResult, UpgradeMode};
+
+/// A schema migration step, run inside its own transaction.
+type Migration = fn(&rusqlite::Connection) -> rusqlite::Result<()>;
+
+/// Ordered schema migrations. Entry `i` upgrades a database from version
+/// `i + 1` to `i + 2`; version 1 is the base schema from `create_base_schema`.
+/// Append new steps at the end — never reorder or edit shipped ones.
+const MIGRATIONS: &[Migration] = &[
+    |conn| conn.execute_batch(APP_SECRETS_TABLE_SQL),
+    |conn| conn.execute_batch(ROUTE_PRIORITIES_TABLE_SQL),
+    |conn| conn.execute_batch(VERSION_HISTORY_TABLE_SQL),
+    |conn| conn.execute_batch(DEPLOY_HISTORY_TABLE_SQL),
+    // NULL means the server default drain timeout.
+    |conn| conn.execute_batch("ALTER TABLE apps ADD COLUMN drain_timeout_secs INTEGER;"),
+    // NULL status means maintenance mode is off.
+    |conn| {
+        conn.execute_batch(
+            "ALTER TABLE apps ADD COLUMN maintenance_status INTEGER;
+             ALTER TABLE apps ADD COLUMN maintenance_body TEXT;",
+        )
+    },
+];
+
+pub const STATE_SCHEMA_VERSION: i32 = 1 + MIGRATIONS.len() as i32;
+
+const BASE_SCHEMA_SQL: &str = "CREATE TABLE IF NOT EXISTS apps (
+    name TEXT NOT NULL,
+    environment TEXT NOT NULL,
+    version TEXT NOT NULL,
+    min_instances INTEGER NOT NULL,
+    max_instances INTEGER NOT NULL,
+    PRIMARY KEY (name, environment)
+);
+
+CREATE TABLE IF NOT EXISTS app_routes (
+    name TEXT NOT NULL,
+    environment TEXT NOT NULL,
+    route TEXT NOT NULL,
+    PRIMARY KEY (name, environment, route),
+    FOREIGN KEY(name, environment) REFERENCES apps(name, environment) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS server_state (
+    id INTEGER PRIMARY KEY CHECK(id = 1),
+    server_mode TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS upgrade_lock (
+    id INTEGER PRIMARY KEY CHECK(id = 1),
+    owner TEXT NOT NULL,
+    acquired_at_unix_secs INTEGER NOT NULL
+);";
+
+const APP_SECRETS_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS app_secrets (
+    app TEXT NOT NULL PRIMARY KEY,
+    encrypted_data BLOB NOT NULL
+);";
+
+const ROUTE_PRIORITIES_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS route_priorities (
+    name TEXT NOT NULL,
+    environment TEXT NOT NULL,
+    route TEXT NOT NULL,
+    priority INTEGER NOT NULL,
+    PRIMARY KEY (name, environment, route),
+    FOREIGN KEY(name, environment) REFERENCES apps(name, environment) ON DELETE CASCADE
+);";
+
+const VERSION_HISTORY_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS app_version_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    environment TEXT NOT NULL,
+    version TEXT NOT NULL,
+    FOREIGN KEY(name, environment) REFERENCES apps(name, environment) ON DELETE CASCADE
+);";
+
+// Keyed by deployment id (like `app_secrets`) so failed first deploys are
+// recorded even though the app never reached the `apps` table.
+const DEPLOY_HISTORY_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS deploy_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    app TEXT NOT NULL,
+    version TEXT NOT NULL,
+    deployed_at_unix_secs INTEGER NOT NULL,
+    result TEXT NOT NULL,
+    new_instances INTEGER NOT NULL,
+    old_instances INTEGER NOT NULL,
+    error TEXT
+);";
+
+pub struct SqliteStateStore {
+    path: PathBuf,
+    encryption_key: [u8; 32],
+}
+
+impl SqliteStateStore {
+    pub fn new(path: PathBuf, encryption_key: [u8; 32]) -> Self {
+        Self {
+            path,
+            encryption_key,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn init(&self) -> Result<(), StateStoreError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| StateStoreError::Open(format!("create db parent: {e}")))?;
+        }
+
+        let conn = self.open_connection()?;
+        let version: i32 = conn
+            .query_row("PRAGMA user_version;", [], |row| row.get(0))
+            .map_err(StateStoreError::from)?;
+
+        if version > STATE_SCHEMA_VERSION {
+            return Err(StateStoreError::UnsupportedSchemaVersion { found: version });
+        }
+
+        // Version 0 is either a fresh file or a database created before
+        // versioning; the base schema is idempotent so both land on version 1.
+        if version == 0 {
+            run_in_transaction(&conn, |tx| {
+                create_base_schema(tx)?;
+                set_schema_version(tx, 1)
+            })
+            .map_err(|e| migration_error(1, e))?;
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            let target = index as i32 + 2;
+            if version.max(1) < target {
+                run_in_transaction(&conn, |tx| {
+                    migration(tx).map_err(StateStoreError::from)?;
+                    set_schema_version(tx, target)
+                })
+                .map_err(|e| migration_error(target, e))?;
+            }
+        }
+
+        self.ensure_default_rows(&conn)?;
+        Ok(())
+    }
+
+    pub(super) fn open_connection(&self) -> Result<rusqlite::Connection, StateStoreError> {
+        let conn = rusqlite::Connection::open(&self.path)
+            .map_err(|e| StateStoreError::Open(format!("{}: {e}", self.path.display())))?;
+        conn.execute_batch(
+            "PRAGMA busy_timeout = 5000;
+             PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             PRAGMA foreign_keys = ON;
+             PRAGMA temp_store = MEMORY;
+             PRAGMA wal_autocheckpoint = 1000;
+             PRAGMA journal_size_limit = 67108864;
+             PRAGMA trusted_schema = OFF;",
+        )
+        .map_err(StateStoreError::from)?;
+        Ok(conn)
+    }
+
+    fn ensure_default_rows(&self, conn: &rusqlite::Connection) -> Result<(), StateStoreError> {
+        conn.execute(
+            "INSERT INTO server_state (id, server_mode)
+             VALUES (1, 'normal')
+             ON CONFLICT(id) DO NOTHING;",
+            [],
+        )
+        .map_err(StateStoreError::from)?;
+
+        Ok(())
+    }
+}
+
+impl StateStore for SqliteStateStore {
+    fn upsert_app(&self, config: &AppConfig, routes: &[String]) -> Result<(), StateStoreError> {
+        let conn = self.open_connection()?;
+        let tx = write_transaction(&conn)?;
+        upsert_app_on(&tx, config, routes)?;
+
+        tx.commit().map_err(StateStoreError::from)?;
+        Ok(())
+    }
+
+    fn delete_app(&self, name: &str, environment: &str) -> Result<(), StateStoreError> {
+        let conn = self.open_connection()?;
+        // Delete secrets for this app to prevent leaking to a future app with the same name.
+        let secret_key = format!("{name}/{environment}");
+        conn.execute("DELETE FROM app_secrets WHERE app = ?1;", [&secret_key])
+            .map_err(StateStoreError::from)?;
+        conn.execute(
+            "DELETE FROM apps WHERE name = ?1 AND environment = ?2;",
+            rusqlite::params![name, environment],
+        )
+        .map_err(StateStoreError::from)?;
+        Ok(())
+    }
+
+    fn load_apps(&self) -> Result<Vec<PersistedApp>, StateStoreError> {
+        let conn = self.open_connection()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT
+                    name, environment, version, min_instances, max_instances, drain_timeout_secs,
+                    maintenance_status, maintenance_body
+                 FROM apps
+                 ORDER BY name, environment;",
+            )
+            .map_err(StateStoreError::from)?;
+
+        let mut apps = Vec::new();
+        let mut rows = stmt.query([]).map_err(StateStoreError::from)?;
+
+        while let Some(row) = rows.next().map_err(StateStoreError::from)? {
+            let name: String = row.get(0).map_err(row_error("apps"))?;
+            let environment: String = row.get(1).map_err(row_error("apps"))?;
+            let version: String = row.get(2).map_err(row_error("apps"))?;
+            let min_instances: i64 = row.get(3).map_err(row_error("apps"))?;
+            let max_instances: i64 = row.get(4).map_err(row_error("apps"))?;
+            let drain_timeout_secs: Option<i64> = row.get(5).map_err(row_error("apps"))?;
+            let maintenance_status: Option<i64> = row.get(6).map_err(row_error("apps"))?;
+            let maintenance_body: Option<String> = row.get(7).map_err(row_error("apps"))?;
+
+            let mut routes_stmt = conn
+                .prepare(
+                    "SELECT route FROM app_routes
+                     WHERE name = ?1 AND environment = ?2
+                     ORDER BY route;",
+                )
+                .map_err(StateStoreError::from)?;
+            let routes: Vec<String> = routes_stmt
+                .query_map(rusqlite::params![&name, &environment], |r| r.get(0))
+                .map_err(StateStoreError::from)?
+                .collect::<Result<Vec<String>, _>>()
+                .map_err(row_error("app_routes"))?;
+
+            let mut priorities_stmt = conn
+                .prepare(
+                    "SELECT route, priority FROM route_priorities
+                     WHERE name = ?1 AND environment = ?2;",
+                )
+                .map_err(StateStoreError::from)?;
+            let route_priorities: HashMap<String, i32> = priorities_stmt
+                .query_map(rusqlite::params![&name, &environment], |r| {
+                    Ok((r.get(0)?, r.get(1)?))
+                })
+                .map_err(StateStoreError::from)?
+                .collect::<Result<HashMap<String, i32>, _>>()
+                .map_err(row_error("route_priorities"))?;
+
+            let config = AppConfig {
+                name,
+                environment,
+                version,
+                min_instances: to_u32(min_instances, "apps", "min_instances")?,
+                max_instances: to_u32(max_instances, "apps", "max_instances")?,
+                drain_timeout: match drain_timeout_secs {
+                    Some(secs) => {
+                        Duration::from_secs(to_u32(secs, "apps", "drain_timeout_secs")?.into())
+                    }
+                    None => crate::defaults::DEFAULT_DRAIN_TIMEOUT,
+                },
+                maintenance: match maintenance_status {
+                    Some(status) => Some(MaintenancePage {
+                        status: u16::try_from(status).map_err(|_| StateStoreError::Corrupt {
+                            table: "apps",
+                            detail: format!("maintenance_status {status} is out of range"),
+                        })?,
+                        body: maintenance_body.unwrap_or_default(),
+                    }),
+                    None => None,
+                },
+                ..Default::default()
+            };
+
+            apps.push(PersistedApp {
+                config,
+                routes,
+                route_priorities,
+            });
+        }
+
+        Ok(apps)
+    }
+
+    fn version_history(
+        &self,
+        name: &str,
+        environment: &str,
+    ) -> Result<Vec<String>, StateStoreError> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT version FROM app_version_history
+                 WHERE name = ?1 AND environment = ?2
+                 ORDER BY id DESC;",
+            )
+            .map_err(StateStoreError::from)?;
+        stmt.query_map(rusqlite::params![name, environment], |r| r.get(0))
+            .map_err(StateStoreError::from)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(row_error("app_version_history"))
+    }
+
+    fn record_deploy(&self, app: &str, entry: &DeployHistoryEntry) -> Result<(), StateStoreError> {
+        let conn = self.open_connection()?;
+        let tx = write_transaction(&conn)?;
+        tx.execute(
+            "INSERT INTO deploy_history (
+                app, version, deployed_at_unix_secs, result, new_instances, old_instances, error
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);",
+            rusqlite::params![
+                app,
+                &entry.version,
+                entry.deployed_at_unix_secs,
+                deploy_result_to_str(entry.result),
+                entry.new_instances as i64,
+                entry.old_instances as i64,
+                &entry.error,
+            ],
+        )
+        .map_err(StateStoreError::from)?;
+        tx.execute(
+            "DELETE FROM deploy_history
+             WHERE app = ?1 AND id NOT IN (
+                SELECT id FROM deploy_history WHERE app = ?1 ORDER BY id DESC LIMIT ?2
+             );",
+            rusqlite::params![app, DEPLOY_HISTORY_LIMIT],
+        )
+        .map_err(StateStoreError::from)?;
+        tx.commit().map_err(StateStoreError::from)?;
+        Ok(())
+    }
+
+    fn deploy_history(
+        &self,
+        app: &str,
+        limit: u32,
+    ) -> Result<Vec<DeployHistoryEntry>, StateStoreError> {
+        let conn = self.open_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT version, deployed_at_unix_secs, result, new_instances, old_instances, error
+                 FROM deploy_history
+                 WHERE app = ?1
+                 ORDER BY id DESC
+                 LIMIT ?2;",
+            )
+            .map_err(StateStoreError::from)?;
+        let mut rows = stmt
+            .query(rusqlite::params![app, limit])
+            .map_err(StateStoreError::from)?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next().map_err(StateStoreError::from)? {
+            let corrupt = row_error("deploy_history");
+            let result: String = row.get(2).map_err(&corrupt)?;
+            entries.push(DeployHistoryEntry {
+                version: row.get(0).map_err(&corrupt)?,
+                deployed_at_unix_secs: row.get(1).map_err(&corrupt)?,
+                result: deploy_result_from_str(&result)?,
+                new_instances: to_u32(
+                    row.get(3).map_err(&corrupt)?,
+                    "deploy_history",
+                    "new_instances",
+                )?,
+                old_instances: to_u32(
+                    row.get(4).map_err(&corrupt)?,
+                    "deploy_history",
+                    "old_instances",
+                )?,
+                error: row.get(5).map_err(&corrupt)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn set_route_priority(
+        &self,
+        name: &str,
+        environment: &str,
+        route: &str,
+        priority: i32,
+    ) -> Result<(), StateStoreError> {
+        let conn = self.open_connection()?;
+        if priority == 0 {
+            conn.execute(
+                "DELETE FROM route_priorities
+                 WHERE name = ?1 AND environment = ?2 AND route = ?3;",
+                rusqlite::params![name, environment, route],
+            )
+            .map_err(StateStoreError::from)?;
+        } else {
+            conn.execute(
+                "INSERT INTO route_priorities (name, environment, route, priority)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(name, environment, route) DO UPDATE SET
+                    priority = excluded.priority;",
+                rusqlite::params![name, environment, route, priority],
+            )
+            .map_err(StateStoreError::from)?;
+        }
+        Ok(())
+    }
+
+    fn set_server_mode(&self, mode: UpgradeMode) -> Result<(), StateStoreError> {
+        let conn = self.open_connection()?;
+        conn.execute(
+            "UPDATE server_state SET server_mode = ?1 WHERE id = 1;",
+            rusqlite::params![server_mode_to_str(mode)],
+        )
+        .map_err(StateStoreError::from)?;
+        Ok(())
+    }
+
+    fn server_mode(&self) -> Result<UpgradeMode, StateStoreError> {
+        let conn = self.open_connection()?;
+        let mode_str: Option<String> = conn
+            .query_row(
+                "SELECT server_mode FROM server_state WHERE id = 1;",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StateStoreError::from)?;
+
+        match mode_str {
+            Some(s) => server_mode_from_str(&s),
+            None => Ok(UpgradeMode::Normal),
+        }
+    }
+
+    fn try_acquire_upgrade_lock(&self, owner: &str) -> Result<bool, StateStoreError> {
+        let conn = self.open_connection()?;
+        let tx = write_transaction(&conn)?;
+
+        let existing: Option<(String, i64)> = tx
+            .query_row(
+                "SELECT owner, acquired_at_unix_secs FROM upgrade_lock WHERE id = 1;",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(StateStoreError::from)?;
+
+        let now: i64 = tx
+            .query_row("SELECT CAST(strftime('%s','now') AS INTEGER);", [], |row| {
+                row.get(0)
+            })
+            .map_err(StateStoreError::from)?;
+
+        let acquired = match existing {
+            Some((ref existing_owner, _)) if existing_owner == owner => true,
+            Some((_, acquired_at)) if now - acquired_at > UPGRADE_LOCK_STALE_SECS => {
+                // Stale lock — force-acquire by replacing it.
+                tx.execute(
+                    "UPDATE upgrade_lock SET owner = ?1, acquired_at_unix_secs = ?2 WHERE id = 1;",
+                    rusqlite::params![owner, now],
+                )
+                .map_err(StateStoreError::from)?;
+                true
+            }
+            Some(_) => false,
+            None => {
+                tx.execute(
+                    "INSERT INTO upgrade_lock (id, owner, acquired_at_unix_secs)
+                     VALUES (1, ?1, CAST(strftime('%s','now') AS INTEGER));",
+                    rusqlite::params![owner],
+                )
+                .map_err(StateStoreError::from)?;
+                true
+            }
+        };
+
+        tx.commit().map_err(StateStoreError::from)?;
+        Ok(acquired)
+    }
+
+    fn release_upgrade_lock(&self, owner: &str) -> Result<bool, StateStoreError> {
+        let conn = self.open_connection()?;
+        let tx = write_transaction(&conn)?;
+
+        let existing: Option<String> = tx
+            .query_row("SELECT owner FROM upgrade_lock WHERE id = 1;", [], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(StateStoreError::from)?;
+
+        let released = match existing {
+            Some(existing) if existing == owner => {
+                tx.execute("DELETE FROM upgrade_lock WHERE id = 1;", [])
+                    .map_err(StateStoreError::from)?;
+                true
+            }
+            _ => false,
+        };
+
+        tx.commit().map_err(StateStoreError::from)?;
+        Ok(released)
+    }
+
+    fn upgrade_lock_owner(&self) -> Result<Option<String>, StateStoreError> {
+        let conn = self.open_connection()?;
+        conn.query_row("SELECT owner FROM upgrade_lock WHERE id = 1;", [], |row| {
+            row.get(0)
+        })
+        .optional()
+        .map_err(StateStoreError::from)
+    }
+
+    fn set_secrets(
+        &self,
+        app: &str,
+        secrets: &HashMap<String, String>,
+    ) -> Result<(), StateStoreError> {
+        let json = serde_json::to_vec(secrets)
+            .map_err(|e| StateStoreError::Serialization(format!("serialize secrets: {e}")))?;
+        let encrypted = encrypt_blob(&self.encryption_key, &json)?;
+        let conn = self.open_connection()?;
+        conn.execute(
+            "INSERT INTO app_secrets (app, encrypted_data)
+             VALUES (?1, ?2)
+             ON CONFLICT(app) DO UPDATE SET encrypted_data = excluded.encrypted_data;",
+            rusqlite::params![app, encrypted],
+        )
+        .map_err(StateStoreError::from)?;
+        Ok(())
+    }
+
+    fn get_secrets(&self, app: &str) -> Result<HashMap<String, String>, StateStoreError> {
+        let conn = self.open_connection()?;
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT encrypted_data FROM app_secrets WHERE app = ?1;",
+                [app],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StateStoreError::from)?;
+
+        match blob {
+            Some(encrypted) => {
+                let json = decrypt_blob(&self.encryption_key, &encrypted)?;
+                serde_json::from_slice(&json).map_err(|e| StateStoreError::Corrupt {
+                    table: "app_secrets",
+                    detail: format!("deserialize secrets: {e}"),
+                })
+            }
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    fn delete_secrets(&self, app: &str) -> Result<(), StateStoreError> {
+        let conn = self.open_connection()?;
+        conn.execute("DELETE FROM app_secrets WHERE app = ?1;", [app])
+            .map_err(StateStoreError::from)?;
+        Ok(())
+    }
+}
+
+fn create_base_schema(conn: &rusqlite::Connection) -> Result<(), StateStoreError> {
+    conn.execute_batch(BASE_SCHEMA_SQL)
+        .map_err(StateStoreError::from)
+}
+
+fn set_schema_version(conn: &rusqlite::Connection, version: i32) -> Result<(), StateStoreError> {
+    conn.execute_batch(&format!("PRAGMA user_version = {version};"))
+        .map_err(StateStoreError::from)
+}
+
+/// Start a write transaction. `BEGIN IMMEDIATE` takes the write lock up
+/// front, so a concurrent writer waits out `busy_timeout`; a deferred
+/// transaction that reads first fails with `SQLITE_BUSY` straight away when
+/// another connection commits in between.
+fn write_transaction(
+    conn: &rusqlite::Connection,
+) -> Result<rusqlite::Transaction<'_>, StateStoreError> {
+    rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)
+        .map_err(StateStoreError::from)
+}
+
+/// Run `step` in a transaction; a failing step leaves the schema version untouched.
+fn run_in_transaction(
+    conn: &rusqlite::Connection,
+    step: impl FnOnce(&rusqlite::Connection) -> Result<(), StateStoreError>,
+) -> Result<(), StateStoreError> {
+    let tx = write_transaction(conn)?;
+    step(&tx)?;
+    tx.commit().map_err(StateStoreError::from)
+}
+
+fn encrypt_blob(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, StateStoreError> {
+    let cipher = Cipher::aes_256_gcm();
+    let mut nonce = [0u8; 12];
+    openssl::rand::rand_bytes(&mut nonce)
+        .map_err(|e| StateStoreError::Serialization(format!("generate nonce: {e}")))?;
+    let mut tag = [0u8; 16];
+    let ciphertext = encrypt_aead(cipher, key, Some(&nonce), &[], plaintext, &mut tag)
+        .map_err(|e| StateStoreError::Serialization(format!("encrypt: {e}")))?;
+    let mut blob = Vec::with_capacity(12 + 16 + ciphertext.len());
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&tag);
+    blob.extend_from_slice(&ciphertext);
+    Ok(blob)
+}
+
+fn decrypt_blob(key: &[u8; 32], blob: &[u8]) -> Result<Vec<u8>, StateStoreError> {
+    if blob.len() < 28 {
+        return Err(StateStoreError::Corrupt {
+            table: "app_secrets",
+            detail: "encrypted blob too short".to_string(),
+        });
+    }
+    let cipher = Cipher::aes_256_gcm();
+    let nonce = &blob[..12];
+    let tag = &blob[12..28];
+    let ciphertext = &blob[28..];
+    decrypt_aead(cipher, key, Some(nonce), &[], ciphertext, tag).map_err(|e| {
+        StateStoreError::Corrupt {
+            table: "app_secrets",
+            detail: format!("decrypt secrets (wrong state key or corrupted data): {e}"),
+        }
+    })
+}
+
+fn upsert_app_on(
+    conn: &rusqlite::Connection,
+    config: &AppConfig,
+    routes: &[String],
+) -> Result<(), StateStoreError> {
+    let previous_version: Option<String> = conn
+        .query_row(
+            "SELECT version FROM apps WHERE name = ?1 AND environment = ?2;",
+            rusqlite::params![&config.name, &config.environment],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(StateStoreError::from)?;
+
+    conn.execute(
+        "INSERT INTO apps (
+            name, environment, version, min_instances, max_instances, drain_timeout_secs,
+            maintenance_status, maintenance_body
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(name, environment) DO UPDATE SET
+            version = excluded.version,
+            min_instances = excluded.min_instances,
+            max_instances = excluded.max_instances,
+            drain_timeout_secs = excluded.drain_timeout_secs,
+            maintenance_status = excluded.maintenance_status,
+            maintenance_body = excluded.maintenance_body;",
+        rusqlite::params![
+            &config.name,
+            &config.environment,
+            &config.version,
+            config.min_instances as i64,
+            config.max_instances as i64,
+            config.drain_timeout.as_secs() as i64,
+            config.maintenance.as_ref().map(|page| page.status as i64),
+            config.maintenance.as_ref().map(|page| page.body.as_str()),
+        ],
+    )
+    .map_err(StateStoreError::from)?;
+
+    if let Some(previous) = previous_version.filter(|v| *v != config.version) {
+        record_version_history_on(conn, &config.name, &config.environment, &previous)?;
+    }
+
+    conn.execute(
+        "DELETE FROM app_routes WHERE name = ?1 AND environment = ?2;",
+        rusqlite::params![&config.name, &config.environment],
+    )
+    .map_err(StateStoreError::from)?;
+
+    for route in routes {
+        conn.execute(
+            "INSERT INTO app_routes (name, environment, route) VALUES (?1, ?2, ?3);",
+            rusqlite::params![&config.name, &config.environment, route],
+        )
+        .map_err(StateStoreError::from)?;
+    }
+
+    // Priorities for routes the app no longer has would otherwise linger and
+    // come back if the pattern is ever redeployed.
+    conn.execute(
+        "DELETE FROM route_priorities
+         WHERE name = ?1 AND environment = ?2 AND route NOT IN (
+            SELECT route FROM app_routes WHERE name = ?1 AND environment = ?2
+         );",
+        rusqlite::params![&config.name, &config.environment],
+    )
+    .map_err(StateStoreError::from)?;
+
+    Ok(())
+}
+
+fn record_version_history_on(
+    conn: &rusqlite::Connection,
+    name: &str,
+    environment: &str,
+    version: &str,
+) -> Result<(), StateStoreError> {
+    conn.execute(
+        "INSERT INTO app_version_history (name, environment, version) VALUES (?1, ?2, ?3);",
+        rusqlite::params![name, environment, version],
+    )
+    .map_err(StateStoreError::from)?;
+    conn.execute(
+        "DELETE FROM app_version_history
+         WHERE name = ?1 AND environment = ?2 AND id NOT IN (
+            SELECT id FROM app_version_history
+            WHERE name = ?1 AND environment = ?2
+            ORDER BY id DESC
+            LIMIT ?3
+         );",
+        rusqlite::params![name, environment, VERSION_HISTORY_LIMIT],
+    )
+    .map_err(StateStoreError::from)?;
+    Ok(())
+}
+
+/// Map a failure to decode a row of `table` to [`StateStoreError::Corrupt`];
+/// errors from stepping the query itself keep their usual mapping.
+fn row_error(table: &'static str) -> impl Fn(rusqlite::Error) -> StateStoreError {
+    move |e| match e {
+        rusqlite::Error::FromSqlConversionFailure(..)
+        | rusqlite::Error::InvalidColumnType(..)
+        | rusqlite::Error::IntegralValueOutOfRange(..) => StateStoreError::Corrupt {
+            table,
+            detail: e.to_string(),
+        },
+        other => other.into(),
+    }
+}
+
+/// A busy database is worth retrying; anything else means the step failed.
+fn migration_error(target: i32, e: StateStoreError) -> StateStoreError {
+    match e {
+        StateStoreError::Busy(_) => e,
+        other => StateStoreError::Migration(format!("to version {target}: {other}")),
+    }
+}
+
+fn to_u32(value: i64, table: &'static str, field: &str) -> Result<u32, StateStoreError> {
+    u32::try_from(value).map_err(|_| StateStoreError::Corrupt {
+        table,
+        detail: format!("field '{field}' out of range for u32: {value}"),
+    })
+}
+
+fn deploy_result_to_str(result: DeployResult) -> &'static str {
+    match result {
+        DeployResult::Succeeded => "succeeded",
+        DeployResult::Failed => "failed",
+        DeployResult::RolledBack => "rolled_back",
+    }
+}
+
+fn deploy_result_from_str(value: &str) -> Result<DeployResult, StateStoreError> {
+    match value {
+        "succeeded" => Ok(DeployResult::Succeeded),
+        "failed" => Ok(DeployResult::Failed),
+        "rolled_back" => Ok(DeployResult::RolledBack),
+        other => Err(StateStoreError::Corrupt {
+            table: "deploy_history",
+            detail: format!("unknown deploy result: {other}"),
+        }),
+    }
+}
+
+fn server_mode_to_str(mode: UpgradeMode) -> &'static str {
+    match mode {
+        UpgradeMode::Normal => "normal",
+        UpgradeMode::Upgrading => "upgrading",
+    }
+}
+
+fn server_mode_from_str(value: &str) -> Result<UpgradeMode, StateStoreError> {
+    match value {
+        "normal" => Ok(UpgradeMode::Normal),
+        "upgrading" => Ok(UpgradeMode::Upgrading),
+        other => Err(StateStoreError::Corrupt {
+            table: "server_state",
+            detail: format!("unknown server_mode value: {other}"),
+        }),
+    }
+}
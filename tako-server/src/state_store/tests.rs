@@ -0,0 +1,778 @@
+use super::sqlite::STATE_SCHEMA_VERSION;
+use super::*;
+use tako_core::DeployResult;
+use tempfile::TempDir;
+
+const TEST_KEY: [u8; 32] = [0xAA; 32];
+
+fn temp_store() -> (TempDir, SqliteStateStore) {
+    let temp = TempDir::new().unwrap();
+    let store = SqliteStateStore::new(temp.path().join("tako.db"), TEST_KEY);
+    (temp, store)
+}
+
+fn sample_config() -> AppConfig {
+    AppConfig {
+        name: "my-app".to_string(),
+        environment: "production".to_string(),
+        version: "v1".to_string(),
+        min_instances: 2,
+        max_instances: 4,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn init_creates_schema() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let conn = store.open_connection().unwrap();
+    let user_version: i32 = conn
+        .query_row("PRAGMA user_version;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(user_version, STATE_SCHEMA_VERSION);
+
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(apps);")
+        .unwrap()
+        .query_map([], |row| row.get(1))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        columns,
+        vec![
+            "name".to_string(),
+            "environment".to_string(),
+            "version".to_string(),
+            "min_instances".to_string(),
+            "max_instances".to_string(),
+            "drain_timeout_secs".to_string(),
+            "maintenance_status".to_string(),
+            "maintenance_body".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn init_rejects_newer_unknown_schema() {
+    let (_temp, store) = temp_store();
+    let conn = store.open_connection().unwrap();
+    conn.execute_batch("PRAGMA user_version = 999;").unwrap();
+    drop(conn);
+
+    let err = store.init().unwrap_err();
+    match err {
+        StateStoreError::UnsupportedSchemaVersion { found } => assert_eq!(found, 999),
+        _ => panic!("unexpected error: {err}"),
+    }
+}
+
+#[test]
+fn init_reports_open_error_when_db_directory_cannot_be_created() {
+    let temp = TempDir::new().unwrap();
+    let blocker = temp.path().join("not-a-dir");
+    std::fs::write(&blocker, b"").unwrap();
+    let store = SqliteStateStore::new(blocker.join("tako.db"), TEST_KEY);
+
+    let err = store.init().unwrap_err();
+    assert!(matches!(err, StateStoreError::Open(_)), "{err:?}");
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn init_reports_corrupt_database_file() {
+    let (temp, store) = temp_store();
+    std::fs::write(temp.path().join("tako.db"), vec![0x5a; 4096]).unwrap();
+
+    let err = store.init().unwrap_err();
+    assert!(
+        matches!(
+            err,
+            StateStoreError::Corrupt {
+                table: "database",
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn init_reports_failed_migration_and_keeps_schema_version() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+    // Pretend the last migration never ran; re-adding its column fails.
+    let previous = STATE_SCHEMA_VERSION - 1;
+    let conn = store.open_connection().unwrap();
+    conn.execute_batch(&format!("PRAGMA user_version = {previous};"))
+        .unwrap();
+    drop(conn);
+
+    let err = store.init().unwrap_err();
+    assert!(matches!(err, StateStoreError::Migration(_)), "{err:?}");
+
+    let conn = store.open_connection().unwrap();
+    let user_version: i32 = conn
+        .query_row("PRAGMA user_version;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(user_version, previous);
+}
+
+#[test]
+fn busy_and_locked_sqlite_errors_map_to_retryable_busy() {
+    for code in [rusqlite::ffi::SQLITE_BUSY, rusqlite::ffi::SQLITE_LOCKED] {
+        let err = StateStoreError::from(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(code),
+            None,
+        ));
+        assert!(matches!(err, StateStoreError::Busy(_)), "{err:?}");
+        assert!(err.is_retryable());
+    }
+
+    let err = StateStoreError::from(rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+        None,
+    ));
+    assert!(matches!(err, StateStoreError::Query(_)), "{err:?}");
+}
+
+#[test]
+fn undecodable_rows_report_corrupt_table() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+    store.upsert_app(&sample_config(), &[]).unwrap();
+
+    let conn = store.open_connection().unwrap();
+    conn.execute_batch(
+        "UPDATE apps SET min_instances = -1;
+         INSERT INTO deploy_history
+            (app, version, deployed_at_unix_secs, result, new_instances, old_instances)
+         VALUES ('my-app/production', 'v1', 0, 'exploded', 1, 0);",
+    )
+    .unwrap();
+    drop(conn);
+
+    let err = store.load_apps().unwrap_err();
+    assert!(
+        matches!(err, StateStoreError::Corrupt { table: "apps", .. }),
+        "{err:?}"
+    );
+    let err = store.deploy_history("my-app/production", 10).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            StateStoreError::Corrupt {
+                table: "deploy_history",
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn concurrent_upserts_from_separate_connections_do_not_hit_lock_errors() {
+    let (temp, store) = temp_store();
+    store.init().unwrap();
+    let db_path = temp.path().join("tako.db");
+    let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+
+    let handles: Vec<_> = ["app-a", "app-b"]
+        .into_iter()
+        .map(|name| {
+            let store = SqliteStateStore::new(db_path.clone(), TEST_KEY);
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                let mut cfg = sample_config();
+                cfg.name = name.to_string();
+                for version in 0..20 {
+                    cfg.version = format!("v{version}");
+                    store
+                        .upsert_app(&cfg, &[format!("{name}.example.com")])
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let apps = store.load_apps().unwrap();
+    assert_eq!(apps.len(), 2);
+    assert!(apps.iter().all(|app| app.config.version == "v19"));
+}
+
+#[test]
+fn upsert_and_load_round_trip() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let cfg = sample_config();
+    let routes = vec![
+        "api.example.com".to_string(),
+        "example.com/api/*".to_string(),
+    ];
+    store.upsert_app(&cfg, &routes).unwrap();
+
+    let apps = store.load_apps().unwrap();
+    assert_eq!(apps.len(), 1);
+
+    let app = &apps[0];
+    assert_eq!(app.config.name, "my-app");
+    assert_eq!(app.config.environment, "production");
+    assert_eq!(app.config.version, "v1");
+    // env_vars and secrets are loaded from files by the caller after restore
+    assert!(app.config.env_vars.is_empty());
+    assert!(app.config.secrets.is_empty());
+    assert_eq!(app.config.min_instances, 2);
+    assert_eq!(app.config.max_instances, 4);
+    assert_eq!(
+        app.routes,
+        vec![
+            "api.example.com".to_string(),
+            "example.com/api/*".to_string()
+        ]
+    );
+}
+
+#[test]
+fn maintenance_page_round_trips_and_clears() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let mut cfg = sample_config();
+    let page = crate::instances::MaintenancePage {
+        status: 502,
+        body: "<h1>Back soon</h1>".to_string(),
+    };
+    cfg.maintenance = Some(page.clone());
+    store.upsert_app(&cfg, &[]).unwrap();
+    assert_eq!(store.load_apps().unwrap()[0].config.maintenance, Some(page));
+
+    cfg.maintenance = None;
+    store.upsert_app(&cfg, &[]).unwrap();
+    assert_eq!(store.load_apps().unwrap()[0].config.maintenance, None);
+}
+
+#[test]
+fn route_priorities_persist_across_route_upserts() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let cfg = sample_config();
+    let routes = vec!["api.example.com".to_string()];
+    store.upsert_app(&cfg, &routes).unwrap();
+    store
+        .set_route_priority("my-app", "production", "api.example.com", 10)
+        .unwrap();
+    store.upsert_app(&cfg, &routes).unwrap();
+
+    let apps = store.load_apps().unwrap();
+    assert_eq!(
+        apps[0].route_priorities,
+        HashMap::from([("api.example.com".to_string(), 10)])
+    );
+
+    store
+        .set_route_priority("my-app", "production", "api.example.com", 0)
+        .unwrap();
+    let apps = store.load_apps().unwrap();
+    assert!(apps[0].route_priorities.is_empty());
+}
+
+#[test]
+fn upsert_drops_priorities_of_removed_routes() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let cfg = sample_config();
+    store
+        .upsert_app(
+            &cfg,
+            &["api.example.com".to_string(), "www.example.com".to_string()],
+        )
+        .unwrap();
+    store
+        .set_route_priority("my-app", "production", "api.example.com", 10)
+        .unwrap();
+    store
+        .set_route_priority("my-app", "production", "www.example.com", 3)
+        .unwrap();
+    store
+        .upsert_app(&cfg, &["www.example.com".to_string()])
+        .unwrap();
+
+    let apps = store.load_apps().unwrap();
+    assert_eq!(
+        apps[0].route_priorities,
+        HashMap::from([("www.example.com".to_string(), 3)])
+    );
+}
+
+#[test]
+fn upsert_records_bounded_version_history() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let mut cfg = sample_config();
+    for version in ["v1", "v2", "v2", "v3", "v4", "v5"] {
+        cfg.version = version.to_string();
+        store.upsert_app(&cfg, &[]).unwrap();
+    }
+
+    assert_eq!(
+        store.version_history("my-app", "production").unwrap(),
+        vec!["v4".to_string(), "v3".to_string(), "v2".to_string()]
+    );
+
+    store.delete_app("my-app", "production").unwrap();
+    assert!(
+        store
+            .version_history("my-app", "production")
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn deploy_history_returns_recorded_entries_newest_first() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let entries = [
+        DeployHistoryEntry {
+            version: "v1".to_string(),
+            deployed_at_unix_secs: 1_700_000_000,
+            result: DeployResult::Succeeded,
+            new_instances: 2,
+            old_instances: 0,
+            error: None,
+        },
+        DeployHistoryEntry {
+            version: "v2".to_string(),
+            deployed_at_unix_secs: 1_700_000_100,
+            result: DeployResult::RolledBack,
+            new_instances: 0,
+            old_instances: 2,
+            error: Some("health check timed out".to_string()),
+        },
+        DeployHistoryEntry {
+            version: "v3".to_string(),
+            deployed_at_unix_secs: 1_700_000_200,
+            result: DeployResult::Failed,
+            new_instances: 0,
+            old_instances: 0,
+            error: Some("Deploy failed: exit code 1".to_string()),
+        },
+    ];
+    for entry in &entries {
+        store.record_deploy("my-app/production", entry).unwrap();
+    }
+    store
+        .record_deploy("other-app/production", &entries[0])
+        .unwrap();
+
+    let history = store.deploy_history("my-app/production", 10).unwrap();
+    assert_eq!(history, entries.iter().rev().cloned().collect::<Vec<_>>());
+
+    let limited = store.deploy_history("my-app/production", 2).unwrap();
+    let versions: Vec<&str> = limited.iter().map(|e| e.version.as_str()).collect();
+    assert_eq!(versions, vec!["v3", "v2"]);
+}
+
+#[test]
+fn delete_app_removes_persisted_app() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let cfg = sample_config();
+    let routes = vec!["api.example.com".to_string()];
+    store.upsert_app(&cfg, &routes).unwrap();
+
+    store.delete_app("my-app", "production").unwrap();
+
+    let apps = store.load_apps().unwrap();
+    assert!(apps.is_empty());
+}
+
+#[test]
+fn server_mode_defaults_to_normal() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+    assert_eq!(store.server_mode().unwrap(), UpgradeMode::Normal);
+}
+
+#[test]
+fn server_mode_round_trip_persists() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    store.set_server_mode(UpgradeMode::Upgrading).unwrap();
+    assert_eq!(store.server_mode().unwrap(), UpgradeMode::Upgrading);
+
+    // Verify persistence across new connection/process.
+    let reopened = SqliteStateStore::new(store.path().to_path_buf(), TEST_KEY);
+    reopened.init().unwrap();
+    assert_eq!(reopened.server_mode().unwrap(), UpgradeMode::Upgrading);
+
+    reopened.set_server_mode(UpgradeMode::Normal).unwrap();
+    assert_eq!(reopened.server_mode().unwrap(), UpgradeMode::Normal);
+}
+
+#[test]
+fn upgrade_lock_is_single_owner() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    assert!(store.try_acquire_upgrade_lock("controller-a").unwrap());
+    assert!(!store.try_acquire_upgrade_lock("controller-b").unwrap());
+    assert!(store.try_acquire_upgrade_lock("controller-a").unwrap());
+}
+
+#[test]
+fn upgrade_lock_release_requires_owner() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+    assert!(store.try_acquire_upgrade_lock("controller-a").unwrap());
+
+    assert!(!store.release_upgrade_lock("controller-b").unwrap());
+    assert!(store.release_upgrade_lock("controller-a").unwrap());
+    assert!(store.try_acquire_upgrade_lock("controller-b").unwrap());
+}
+
+#[test]
+fn upgrade_lock_force_acquires_stale_lock() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+    assert!(store.try_acquire_upgrade_lock("controller-a").unwrap());
+
+    // Backdate the lock to make it stale.
+    let conn = store.open_connection().unwrap();
+    let stale_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+        - UPGRADE_LOCK_STALE_SECS
+        - 1;
+    conn.execute(
+        "UPDATE upgrade_lock SET acquired_at_unix_secs = ?1 WHERE id = 1;",
+        rusqlite::params![stale_time],
+    )
+    .unwrap();
+
+    // A different owner can now force-acquire the stale lock.
+    assert!(store.try_acquire_upgrade_lock("controller-b").unwrap());
+    assert_eq!(
+        store.upgrade_lock_owner().unwrap().as_deref(),
+        Some("controller-b")
+    );
+}
+
+#[test]
+fn upgrade_lock_owner_cleared_allows_new_owner() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    // Simulate: owner-a acquires lock then crashes (no release).
+    assert!(store.try_acquire_upgrade_lock("owner-a").unwrap());
+    assert_eq!(
+        store.upgrade_lock_owner().unwrap().as_deref(),
+        Some("owner-a")
+    );
+
+    // Manual cleanup (as server startup would do): read owner, release.
+    if let Some(owner) = store.upgrade_lock_owner().unwrap() {
+        assert!(store.release_upgrade_lock(&owner).unwrap());
+    }
+
+    // New owner can acquire immediately without waiting for stale timeout.
+    assert!(store.try_acquire_upgrade_lock("owner-b").unwrap());
+    assert_eq!(
+        store.upgrade_lock_owner().unwrap().as_deref(),
+        Some("owner-b")
+    );
+}
+
+#[test]
+fn set_and_get_secrets_round_trip() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let secrets = HashMap::from([
+        ("API_KEY".to_string(), "secret123".to_string()),
+        ("DB_URL".to_string(), "postgres://db".to_string()),
+    ]);
+    store.set_secrets("my-app", &secrets).unwrap();
+
+    let loaded = store.get_secrets("my-app").unwrap();
+    assert_eq!(loaded.get("API_KEY"), Some(&"secret123".to_string()));
+    assert_eq!(loaded.get("DB_URL"), Some(&"postgres://db".to_string()));
+}
+
+#[test]
+fn get_secrets_returns_empty_when_not_set() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let loaded = store.get_secrets("nonexistent").unwrap();
+    assert!(loaded.is_empty());
+}
+
+#[test]
+fn set_secrets_overwrites_previous() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let secrets1 = HashMap::from([("KEY".to_string(), "value1".to_string())]);
+    store.set_secrets("my-app", &secrets1).unwrap();
+
+    let secrets2 = HashMap::from([("KEY".to_string(), "value2".to_string())]);
+    store.set_secrets("my-app", &secrets2).unwrap();
+
+    let loaded = store.get_secrets("my-app").unwrap();
+    assert_eq!(loaded.get("KEY"), Some(&"value2".to_string()));
+}
+
+#[test]
+fn delete_secrets_removes_app_secrets() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let secrets = HashMap::from([("KEY".to_string(), "value".to_string())]);
+    store.set_secrets("my-app", &secrets).unwrap();
+
+    store.delete_secrets("my-app").unwrap();
+
+    let loaded = store.get_secrets("my-app").unwrap();
+    assert!(loaded.is_empty());
+}
+
+#[test]
+fn secrets_are_encrypted_at_rest() {
+    let (_temp, store) = temp_store();
+    store.init().unwrap();
+
+    let secrets = HashMap::from([("API_KEY".to_string(), "supersecret".to_string())]);
+    store.set_secrets("my-app", &secrets).unwrap();
+
+    // Read raw blob from SQLite — should not contain plaintext
+    let conn = store.open_connection().unwrap();
+    let raw: Vec<u8> = conn
+        .query_row(
+            "SELECT encrypted_data FROM app_secrets WHERE app = ?1;",
+            ["my-app"],
+            |row| row.get(0),
+        )
+        .unwrap();
+    let raw_str = String::from_utf8_lossy(&raw);
+    assert!(!raw_str.contains("supersecret"));
+    assert!(!raw_str.contains("API_KEY"));
+}
+
+#[test]
+fn secrets_encrypted_with_wrong_key_cannot_be_read() {
+    let temp = TempDir::new().unwrap();
+    let db_path = temp.path().join("state.sqlite3");
+
+    let store1 = SqliteStateStore::new(db_path.clone(), [0x11; 32]);
+    store1.init().unwrap();
+    let secrets = HashMap::from([("KEY".to_string(), "value".to_string())]);
+    store1.set_secrets("my-app", &secrets).unwrap();
+
+    let store2 = SqliteStateStore::new(db_path, [0x22; 32]);
+    store2.init().unwrap();
+    let err = store2.get_secrets("my-app").unwrap_err();
+    assert!(err.to_string().contains("wrong state key"), "{err}");
+    assert!(
+        matches!(
+            err,
+            StateStoreError::Corrupt {
+                table: "app_secrets",
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn migrate_v1_to_v2_adds_secrets_table() {
+    let temp = TempDir::new().unwrap();
+    let db_path = temp.path().join("state.sqlite3");
+
+    // Create a v1 database manually
+    {
+        let store = SqliteStateStore::new(db_path.clone(), TEST_KEY);
+        let conn = store.open_connection().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE apps (
+                name TEXT NOT NULL,
+                environment TEXT NOT NULL,
+                version TEXT NOT NULL,
+                min_instances INTEGER NOT NULL,
+                max_instances INTEGER NOT NULL,
+                PRIMARY KEY (name, environment)
+            );
+            CREATE TABLE app_routes (
+                name TEXT NOT NULL,
+                environment TEXT NOT NULL,
+                route TEXT NOT NULL,
+                PRIMARY KEY (name, environment, route),
+                FOREIGN KEY(name, environment) REFERENCES apps(name, environment) ON DELETE CASCADE
+            );
+            CREATE TABLE server_state (
+                id INTEGER PRIMARY KEY CHECK(id = 1),
+                server_mode TEXT NOT NULL
+            );
+            CREATE TABLE upgrade_lock (
+                id INTEGER PRIMARY KEY CHECK(id = 1),
+                owner TEXT NOT NULL,
+                acquired_at_unix_secs INTEGER NOT NULL
+            );
+            INSERT INTO server_state (id, server_mode) VALUES (1, 'normal');
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+    }
+
+    // Open with current code — should migrate to v2
+    let store = SqliteStateStore::new(db_path, TEST_KEY);
+    store.init().unwrap();
+
+    // Verify migration: app_secrets table exists and works
+    let secrets = HashMap::from([("KEY".to_string(), "value".to_string())]);
+    store.set_secrets("test-app", &secrets).unwrap();
+    let loaded = store.get_secrets("test-app").unwrap();
+    assert_eq!(loaded.get("KEY"), Some(&"value".to_string()));
+    assert!(store.deploy_history("test-app", 10).unwrap().is_empty());
+
+    // Verify version bumped
+    let conn = store.open_connection().unwrap();
+    let version: i32 = conn
+        .query_row("PRAGMA user_version;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, STATE_SCHEMA_VERSION);
+}
+
+#[test]
+fn init_upgrades_unversioned_database_and_keeps_data() {
+    let (_temp, store) = temp_store();
+    {
+        let conn = store.open_connection().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE apps (
+                name TEXT NOT NULL,
+                environment TEXT NOT NULL,
+                version TEXT NOT NULL,
+                min_instances INTEGER NOT NULL,
+                max_instances INTEGER NOT NULL,
+                PRIMARY KEY (name, environment)
+            );
+            INSERT INTO apps (name, environment, version, min_instances, max_instances)
+            VALUES ('legacy', 'production', 'v0', 1, 1);",
+        )
+        .unwrap();
+    }
+
+    store.init().unwrap();
+    // A second run finds nothing left to migrate.
+    store.init().unwrap();
+
+    let apps = store.load_apps().unwrap();
+    assert_eq!(apps.len(), 1);
+    assert_eq!(apps[0].config.name, "legacy");
+    assert_eq!(apps[0].config.version, "v0");
+    assert_eq!(
+        apps[0].config.drain_timeout,
+        crate::defaults::DEFAULT_DRAIN_TIMEOUT
+    );
+    assert_eq!(apps[0].config.maintenance, None);
+    assert!(store.deploy_history("legacy", 10).unwrap().is_empty());
+
+    let conn = store.open_connection().unwrap();
+    let version: i32 = conn
+        .query_row("PRAGMA user_version;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, STATE_SCHEMA_VERSION);
+}
+
+#[test]
+fn load_or_create_device_key_generates_and_persists() {
+    let temp = TempDir::new().unwrap();
+    let key_path = temp.path().join("secret.key");
+
+    let key1 = load_or_create_device_key(&key_path).unwrap();
+    let key2 = load_or_create_device_key(&key_path).unwrap();
+    assert_eq!(key1, key2);
+
+    let raw = std::fs::read(&key_path).unwrap();
+    assert_eq!(raw.len(), 32);
+}
+
+#[test]
+fn load_device_key_requires_existing_32_byte_file() {
+    let temp = TempDir::new().unwrap();
+    let key_path = temp.path().join("state.key");
+    assert!(load_device_key(&key_path).is_err());
+    assert!(!key_path.exists());
+
+    std::fs::write(&key_path, [0x33; 16]).unwrap();
+    assert!(load_device_key(&key_path).is_err());
+
+    std::fs::write(&key_path, [0x33; 32]).unwrap();
+    assert_eq!(load_device_key(&key_path).unwrap(), [0x33; 32]);
+}
+
+#[test]
+#[cfg(unix)]
+fn load_or_create_device_key_sets_mode_0600() {
+    use std::os::unix::fs::PermissionsExt;
+    let temp = TempDir::new().unwrap();
+    let key_path = temp.path().join("secret.key");
+
+    load_or_create_device_key(&key_path).unwrap();
+
+    let mode = std::fs::metadata(&key_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn in_memory_store_matches_history_and_lock_semantics() {
+    let store = InMemoryStateStore::new();
+    let mut cfg = sample_config();
+    for version in ["v1", "v2", "v3", "v4", "v5"] {
+        cfg.version = version.to_string();
+        store
+            .upsert_app(
+                &cfg,
+                &["b.example.com".to_string(), "a.example.com".to_string()],
+            )
+            .unwrap();
+    }
+    assert_eq!(
+        store.version_history("my-app", "production").unwrap(),
+        vec!["v4", "v3", "v2"]
+    );
+    let apps = store.load_apps().unwrap();
+    assert_eq!(apps[0].config.version, "v5");
+    assert_eq!(apps[0].routes, vec!["a.example.com", "b.example.com"]);
+
+    assert!(store.try_acquire_upgrade_lock("a").unwrap());
+    assert!(!store.try_acquire_upgrade_lock("b").unwrap());
+    assert!(!store.release_upgrade_lock("b").unwrap());
+    assert!(store.release_upgrade_lock("a").unwrap());
+    assert_eq!(store.upgrade_lock_owner().unwrap(), None);
+
+    store.delete_app("my-app", "production").unwrap();
+    assert!(store.load_apps().unwrap().is_empty());
+    assert!(
+        store
+            .version_history("my-app", "production")
+            .unwrap()
+            .is_empty()
+    );
+}
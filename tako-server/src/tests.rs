@@ -2,17 +2,22 @@ use super::boot::{
     install_rustls_crypto_provider, read_server_config, should_signal_parent_on_ready,
 };
 use super::release::{
-    resolve_release_runtime, should_use_self_signed_route_cert, validate_app_name,
-    validate_deploy_routes,
+    normalize_private_domain_suffix, resolve_release_runtime, should_use_self_signed_route_cert,
+    validate_app_name, validate_canary, validate_deploy_routes,
 };
 use super::{
     SIGNAL_PARENT_ON_READY_ENV, ServerRuntimeConfig, ServerState, extract_zstd_archive,
     run_extract_archive_mode,
 };
-use crate::instances::AppConfig;
-use crate::runtime_events::{handle_idle_event, handle_instance_event};
-use crate::socket::{AppState, Command, InstanceState, Response};
-use crate::tls::{CertManager, CertManagerConfig, ChallengeTokens};
+use crate::instances::{AppConfig, BuildRouting, RollingUpdateConfig, RollingUpdater};
+use crate::runtime_events::{handle_cert_event, handle_idle_event, handle_instance_event};
+use crate::secret_refs::SecretRefPolicy;
+use crate::shutdown::DrainPhase;
+use crate::socket::{AppState, AppStatus, Command, InstanceState, Response};
+use crate::state_store::{InMemoryStateStore, StateStore};
+use crate::tls::{
+    AcmeClient, AcmeConfig, CertEvent, CertInfo, CertManager, CertManagerConfig, ChallengeTokens,
+};
 use clap::Parser;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -28,6 +33,33 @@ fn empty_challenge_tokens() -> ChallengeTokens {
     Arc::new(parking_lot::RwLock::new(HashMap::new()))
 }
 
+/// Boot a server on `data_dir`. Without `store` it opens the SQLite store
+/// there; passing one lets an in-memory store outlive a simulated restart.
+fn boot_state(
+    data_dir: &Path,
+    cert_manager: Arc<CertManager>,
+    store: Option<Arc<dyn StateStore>>,
+) -> ServerState {
+    let Some(store) = store else {
+        return ServerState::new(
+            data_dir.to_path_buf(),
+            cert_manager,
+            None,
+            empty_challenge_tokens(),
+        )
+        .unwrap();
+    };
+    ServerState::new_with_store(
+        data_dir.to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+        ServerRuntimeConfig::for_defaults(data_dir.to_path_buf()),
+        store,
+    )
+    .unwrap()
+}
+
 fn write_release_manifest(
     release_dir: &Path,
     runtime: &str,
@@ -200,6 +232,24 @@ fn run_extract_archive_mode_requires_destination_flag() {
     assert!(err.contains("--extract-dest"));
 }
 
+#[test]
+fn cert_renewal_flags_default_to_a_30_day_margin_that_refuses_skewed_clocks() {
+    let args = super::Args::try_parse_from(["tako-server"]).unwrap();
+    assert_eq!(args.cert_renewal_days, 30);
+    assert!(args.refuse_renewal_on_clock_skew);
+
+    let args = super::Args::try_parse_from([
+        "tako-server",
+        "--cert-renewal-days",
+        "14",
+        "--refuse-renewal-on-clock-skew",
+        "false",
+    ])
+    .unwrap();
+    assert_eq!(args.cert_renewal_days, 14);
+    assert!(!args.refuse_renewal_on_clock_skew);
+}
+
 #[test]
 fn install_rustls_crypto_provider_is_idempotent() {
     install_rustls_crypto_provider();
@@ -258,6 +308,21 @@ fn validate_deploy_routes_rejects_empty_route_entry() {
     assert!(err.contains("non-empty"));
 }
 
+#[test]
+fn validate_canary_accepts_partial_percentages_with_rolling_strategy() {
+    use tako_core::DeployStrategy;
+    assert!(validate_canary(None, DeployStrategy::BlueGreen).is_ok());
+    assert!(validate_canary(Some(1), DeployStrategy::Rolling).is_ok());
+    assert!(validate_canary(Some(99), DeployStrategy::Rolling).is_ok());
+
+    let err = validate_canary(Some(0), DeployStrategy::Rolling).unwrap_err();
+    assert!(err.contains("between 1 and 99"));
+    let err = validate_canary(Some(100), DeployStrategy::Rolling).unwrap_err();
+    assert!(err.contains("between 1 and 99"));
+    let err = validate_canary(Some(10), DeployStrategy::BlueGreen).unwrap_err();
+    assert!(err.contains("blue_green"));
+}
+
 #[test]
 fn validate_app_name_accepts_app_env_identifier() {
     assert!(validate_app_name("my-app/staging").is_ok());
@@ -285,6 +350,9 @@ async fn deploy_rejects_invalid_app_name() {
             path: temp.path().to_string_lossy().to_string(),
             routes: vec!["api.example.com".to_string()],
             secrets: Some(HashMap::new()),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         })
         .await;
 
@@ -319,6 +387,9 @@ async fn deploy_rejects_release_path_outside_managed_root() {
             path: outside_release.to_string_lossy().to_string(),
             routes: vec!["api.example.com".to_string()],
             secrets: Some(HashMap::new()),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         })
         .await;
 
@@ -361,6 +432,9 @@ async fn deploy_rejects_invalid_release_version() {
             path: release_dir.to_string_lossy().to_string(),
             routes: vec!["api.example.com".to_string()],
             secrets: Some(HashMap::new()),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         })
         .await;
 
@@ -376,17 +450,41 @@ async fn deploy_rejects_invalid_release_version() {
 #[test]
 fn private_route_domains_prefer_self_signed_certs() {
     assert!(should_use_self_signed_route_cert(
-        "tako-bun-server.orb.local"
+        "tako-bun-server.orb.local",
+        &[]
     ));
-    assert!(should_use_self_signed_route_cert("localhost"));
-    assert!(should_use_self_signed_route_cert("api.localhost"));
-    assert!(should_use_self_signed_route_cert("my-service"));
+    assert!(should_use_self_signed_route_cert("localhost", &[]));
+    assert!(should_use_self_signed_route_cert("api.localhost", &[]));
+    assert!(should_use_self_signed_route_cert("my-service", &[]));
 }
 
 #[test]
 fn public_route_domains_do_not_prefer_self_signed_certs() {
-    assert!(!should_use_self_signed_route_cert("api.example.com"));
-    assert!(!should_use_self_signed_route_cert("example.com"));
+    assert!(!should_use_self_signed_route_cert("api.example.com", &[]));
+    assert!(!should_use_self_signed_route_cert("example.com", &[]));
+}
+
+#[test]
+fn configured_private_suffixes_prefer_self_signed_certs() {
+    let suffixes: Vec<String> = ["*.corp", ".Internal.Example.Net."]
+        .iter()
+        .filter_map(|suffix| normalize_private_domain_suffix(suffix))
+        .collect();
+    assert_eq!(suffixes, vec!["corp", "internal.example.net"]);
+
+    assert!(should_use_self_signed_route_cert("app.corp", &suffixes));
+    assert!(should_use_self_signed_route_cert("API.Corp:443", &suffixes));
+    assert!(should_use_self_signed_route_cert(
+        "svc.internal.example.net",
+        &suffixes
+    ));
+    assert!(!should_use_self_signed_route_cert("app.notcorp", &suffixes));
+    assert!(!should_use_self_signed_route_cert(
+        "api.example.com",
+        &suffixes
+    ));
+    assert!(!should_use_self_signed_route_cert("app.corp", &[]));
+    assert_eq!(normalize_private_domain_suffix(" *. "), None);
 }
 
 #[test]
@@ -406,6 +504,81 @@ fn resolve_release_runtime_reads_manifest_runtime() {
     );
 }
 
+#[tokio::test]
+async fn validate_config_reports_runtime_and_command_for_bun_release() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    let release_dir = temp.path().join("release");
+    std::fs::create_dir_all(&release_dir).unwrap();
+    write_release_manifest(&release_dir, "bun", "index.ts", &[], None, 300);
+    std::fs::write(release_dir.join("package.json"), r#"{"name":"app"}"#).unwrap();
+    std::fs::write(release_dir.join("index.ts"), "export default {}").unwrap();
+
+    let response = state
+        .handle_command(Command::ValidateConfig {
+            path: release_dir.to_string_lossy().to_string(),
+            routes: vec!["api.example.com".to_string()],
+            env: HashMap::new(),
+        })
+        .await;
+
+    let Response::Ok { data } = response else {
+        panic!("expected valid release, got: {response:?}");
+    };
+    assert_eq!(data["status"], "valid");
+    assert_eq!(data["runtime"], "bun");
+    let command = data["command"].as_array().unwrap();
+    assert!(
+        command
+            .iter()
+            .any(|arg| arg.as_str().unwrap().ends_with("index.ts")),
+        "got: {command:?}"
+    );
+    assert!(state.app_manager.list_apps().is_empty());
+}
+
+#[tokio::test]
+async fn validate_config_rejects_bun_release_without_package_json() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    let release_dir = temp.path().join("release");
+    std::fs::create_dir_all(&release_dir).unwrap();
+    write_release_manifest(&release_dir, "bun", "index.ts", &[], None, 300);
+
+    let response = state
+        .handle_command(Command::ValidateConfig {
+            path: release_dir.to_string_lossy().to_string(),
+            routes: vec!["api.example.com".to_string()],
+            env: HashMap::new(),
+        })
+        .await;
+
+    let Response::Error { message } = response else {
+        panic!("expected missing package.json to be rejected");
+    };
+    assert!(message.contains("package.json"), "got: {message}");
+}
+
 #[test]
 fn bun_runtime_has_install_script() {
     let runtime = tako_runtime::runtime_def_for("bun", None).unwrap();
@@ -497,67 +670,53 @@ async fn ensure_route_certificate_generates_self_signed_for_private_domain() {
 }
 
 #[tokio::test]
-async fn delete_command_removes_runtime_registration_and_routes() {
+async fn ensure_route_certificate_self_signs_configured_private_suffix() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-    let state = ServerState::new(
+    cert_manager.init().unwrap();
+    let runtime = ServerRuntimeConfig {
+        private_domain_suffixes: vec!["corp".to_string()],
+        ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
+    };
+    let state = ServerState::new_with_runtime(
         temp.path().to_path_buf(),
-        cert_manager,
+        cert_manager.clone(),
         None,
         empty_challenge_tokens(),
+        runtime,
     )
     .unwrap();
 
-    let app_root = temp.path().join("apps").join("my-app");
-    let release_dir = app_root.join("releases").join("v1");
-    std::fs::create_dir_all(&release_dir).unwrap();
-    std::fs::create_dir_all(app_root.join("data/app")).unwrap();
-    std::fs::create_dir_all(app_root.join("data/tako")).unwrap();
-
-    let config = AppConfig {
-        name: "my-app".to_string(),
-        version: "v1".to_string(),
-        path: release_dir.clone(),
-        command: vec![
-            "/bin/sh".to_string(),
-            "-lc".to_string(),
-            "exit 0".to_string(),
-        ],
-        min_instances: 0,
-        ..Default::default()
-    };
-
-    let app = state.app_manager.register_app(config);
-    state.load_balancer.register_app(app);
-    {
-        let mut route_table = state.routes.write().await;
-        route_table.set_app_routes("my-app".to_string(), vec!["api.example.com".to_string()]);
-    }
-
-    let response = state
-        .handle_command(Command::Delete {
-            app: "my-app".to_string(),
-        })
-        .await;
-    assert!(matches!(response, Response::Ok { .. }));
-    assert!(state.app_manager.get_app("my-app").is_none());
-    assert!(!app_root.exists());
+    let cert = state
+        .ensure_route_certificate("my-app", "app.corp")
+        .await
+        .expect("configured private suffix should get a generated cert");
+    assert!(cert.is_self_signed);
 
-    let route_table = state.routes.read().await;
-    assert!(route_table.routes_for_app("my-app").is_empty());
-    assert_eq!(route_table.select("api.example.com", "/"), None);
+    // Public domains still go to ACME, which is disabled here.
+    assert!(
+        state
+            .ensure_route_certificate("my-app", "api.example.com")
+            .await
+            .is_none()
+    );
+    assert!(cert_manager.get_cert_for_host("api.example.com").is_none());
 }
 
 #[tokio::test]
-async fn delete_command_is_idempotent_for_missing_app() {
+async fn certs_command_lists_self_signed_cert() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
+    cert_manager.init().unwrap();
+    cert_manager
+        .get_or_create_self_signed_cert("tako-bun-server.orb.local")
+        .unwrap();
     let state = ServerState::new(
         temp.path().to_path_buf(),
         cert_manager,
@@ -566,22 +725,32 @@ async fn delete_command_is_idempotent_for_missing_app() {
     )
     .unwrap();
 
-    let response = state
-        .handle_command(Command::Delete {
-            app: "missing-app".to_string(),
-        })
-        .await;
-    assert!(matches!(response, Response::Ok { .. }));
-    assert!(state.app_manager.get_app("missing-app").is_none());
+    let response = state.handle_command(Command::Certs).await;
+
+    let Response::Ok { data } = response else {
+        panic!("expected cert listing, got: {response:?}");
+    };
+    let certs = data["certs"].as_array().unwrap();
+    let cert = certs
+        .iter()
+        .find(|c| c["domain"] == "tako-bun-server.orb.local")
+        .expect("self-signed cert should be listed");
+    assert_eq!(cert["is_self_signed"], true);
+    assert_eq!(cert["acme"], false);
+    assert!(cert["expires_in_days"].as_i64().unwrap() > 0);
 }
 
 #[tokio::test]
-async fn delete_command_rejects_invalid_app_name() {
+async fn snapshot_contains_deployed_apps_routes_and_server_mode() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
+    cert_manager.init().unwrap();
+    cert_manager
+        .get_or_create_self_signed_cert("api.localhost")
+        .unwrap();
     let state = ServerState::new(
         temp.path().to_path_buf(),
         cert_manager,
@@ -590,98 +759,162 @@ async fn delete_command_rejects_invalid_app_name() {
     )
     .unwrap();
 
+    for (name, route) in [("api", "api.localhost"), ("web", "web.localhost")] {
+        let app_id = format!("{name}/production");
+        let app = state.app_manager.register_app(AppConfig {
+            name: name.to_string(),
+            environment: "production".to_string(),
+            version: "v1".to_string(),
+            min_instances: 0,
+            max_instances: 2,
+            ..Default::default()
+        });
+        state.load_balancer.register_app(app);
+        state
+            .routes
+            .write()
+            .await
+            .set_app_routes(app_id.clone(), vec![route.to_string()])
+            .unwrap();
+        state
+            .state_store
+            .set_secrets(
+                &app_id,
+                &HashMap::from([("API_KEY".to_string(), format!("{name}-secret"))]),
+            )
+            .unwrap();
+        state.persist_app_state(&app_id).await;
+    }
+    state
+        .handle_command(Command::EnterUpgrading {
+            owner: "upgrader".to_string(),
+        })
+        .await;
+
     let response = state
-        .handle_command(Command::Delete {
-            app: "../bad".to_string(),
+        .handle_command(Command::Snapshot {
+            include_secrets: false,
         })
         .await;
+    let Response::Ok { data } = response else {
+        panic!("expected snapshot, got: {response:?}");
+    };
+    assert_eq!(data["server_mode"], "upgrading");
+    assert_eq!(data["upgrade_lock_owner"], "upgrader");
+    let apps = data["apps"].as_array().unwrap();
+    assert_eq!(apps.len(), 2);
+    assert_eq!(apps[0]["name"], "api");
+    assert_eq!(apps[0]["version"], "v1");
+    assert_eq!(apps[0]["routes"], serde_json::json!(["api.localhost"]));
+    assert_eq!(apps[1]["name"], "web");
+    assert_eq!(apps[1]["routes"], serde_json::json!(["web.localhost"]));
+    assert!(apps[0].get("secrets").is_none());
+    assert_eq!(data["certs"][0]["domain"], "api.localhost");
 
-    let Response::Error { message } = response else {
-        panic!("expected invalid app name to be rejected");
+    let response = state
+        .handle_command(Command::Snapshot {
+            include_secrets: true,
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected snapshot, got: {response:?}");
     };
-    assert!(message.contains("Invalid app name"), "got: {message}");
+    assert_eq!(data["apps"][1]["secrets"]["API_KEY"], "web-secret");
 }
 
 #[tokio::test]
-async fn upgrading_mode_blocks_mutating_commands() {
+async fn renew_cert_command_renews_held_domain_through_acme() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
+    cert_manager.add_cert(CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: temp.path().join("fullchain.pem"),
+        key_path: temp.path().join("privkey.pem"),
+        not_before: None,
+        expires_at: Some(std::time::SystemTime::now() + Duration::from_secs(60 * 86400)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+    // Never initialized, so any ACME order fails with "not registered".
+    let acme = AcmeClient::new(
+        AcmeConfig {
+            account_dir: temp.path().join("acme"),
+            data_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        },
+        cert_manager.clone(),
+    );
     let state = ServerState::new(
         temp.path().to_path_buf(),
         cert_manager,
-        None,
+        Some(Arc::new(acme)),
         empty_challenge_tokens(),
     )
     .unwrap();
-    state.set_server_mode(UpgradeMode::Upgrading).await.unwrap();
 
+    // Far from expiry, yet the renewal is still attempted.
     let response = state
-        .handle_command(Command::Delete {
-            app: "my-app".to_string(),
+        .handle_command(Command::RenewCert {
+            domain: "example.com".to_string(),
         })
         .await;
+    let Response::Error { message } = response else {
+        panic!("expected renewal error, got: {response:?}");
+    };
+    assert!(message.contains("ACME account not registered"), "{message}");
+    let warnings = state.runtime_info().await.cert_warnings;
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].domain, "example.com");
 
+    let response = state
+        .handle_command(Command::RenewCert {
+            domain: "other.example.com".to_string(),
+        })
+        .await;
     let Response::Error { message } = response else {
-        panic!("expected blocked mutating command while upgrading");
+        panic!("expected unknown domain error, got: {response:?}");
     };
-    assert!(message.contains("Server is upgrading"));
-    assert!(message.contains("delete"));
+    assert!(message.contains("No certificate for domain"), "{message}");
 }
 
 #[tokio::test]
-async fn server_mode_resets_upgrading_on_boot() {
+async fn renew_cert_command_rejected_when_acme_disabled() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-
-    let state_a = ServerState::new(
+    let state = ServerState::new(
         temp.path().to_path_buf(),
-        cert_manager.clone(),
+        cert_manager,
         None,
         empty_challenge_tokens(),
     )
     .unwrap();
-    state_a
-        .set_server_mode(UpgradeMode::Upgrading)
-        .await
-        .unwrap();
-    // Simulate an upgrade lock left behind by a crashed CLI.
-    assert!(state_a.try_enter_upgrading("crashed-cli").await.unwrap());
-    drop(state_a);
 
-    // On restart, stale Upgrading mode AND orphaned lock should be cleared.
-    let state_b = ServerState::new(
-        temp.path().to_path_buf(),
-        cert_manager.clone(),
-        None,
-        empty_challenge_tokens(),
-    )
-    .unwrap();
-    assert_eq!(*state_b.server_mode.read().await, UpgradeMode::Normal);
-    // A new owner should be able to acquire immediately (no 10-min stale wait).
-    assert!(state_b.try_enter_upgrading("new-cli").await.unwrap());
+    let response = state
+        .handle_command(Command::RenewCert {
+            domain: "example.com".to_string(),
+        })
+        .await;
+    let Response::Error { message } = response else {
+        panic!("expected error, got: {response:?}");
+    };
+    assert_eq!(message, "ACME is disabled");
 }
 
 #[tokio::test]
-async fn upgrading_lock_allows_single_owner() {
+async fn cert_events_are_surfaced_in_server_info_until_renewed() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-    let state_a = ServerState::new(
-        temp.path().to_path_buf(),
-        cert_manager.clone(),
-        None,
-        empty_challenge_tokens(),
-    )
-    .unwrap();
-    let state_b = ServerState::new(
+    let state = ServerState::new(
         temp.path().to_path_buf(),
         cert_manager,
         None,
@@ -689,32 +922,334 @@ async fn upgrading_lock_allows_single_owner() {
     )
     .unwrap();
 
-    assert!(state_a.try_enter_upgrading("controller-a").await.unwrap());
-    assert!(!state_b.try_enter_upgrading("controller-b").await.unwrap());
-    assert!(state_a.exit_upgrading("controller-a").await.unwrap());
-    assert!(state_b.try_enter_upgrading("controller-b").await.unwrap());
+    handle_cert_event(
+        &state,
+        CertEvent::RenewalFailed {
+            domain: "example.com".to_string(),
+            error: "rate limited".to_string(),
+        },
+    );
+    handle_cert_event(
+        &state,
+        CertEvent::ExpiringSoon {
+            domain: "example.com".to_string(),
+            days: 5,
+        },
+    );
+
+    let info = state.runtime_info().await;
+    assert_eq!(info.cert_warnings.len(), 1);
+    assert_eq!(info.cert_warnings[0].domain, "example.com");
+    assert_eq!(info.cert_warnings[0].expires_in_days, Some(5));
+    assert_eq!(
+        info.cert_warnings[0].renewal_error.as_deref(),
+        Some("rate limited")
+    );
+
+    handle_cert_event(
+        &state,
+        CertEvent::Renewed {
+            domain: "example.com".to_string(),
+        },
+    );
+    assert!(state.runtime_info().await.cert_warnings.is_empty());
 }
 
 #[tokio::test]
-async fn server_info_command_reports_runtime_config() {
+async fn delete_command_removes_runtime_registration_and_routes() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-    let runtime = ServerRuntimeConfig {
-        pid: std::process::id(),
-        socket: "/var/run/tako/tako-custom.sock".to_string(),
-        data_dir: temp.path().to_path_buf(),
-        http_port: 8080,
-        https_port: 8443,
-        no_acme: true,
-        acme_staging: false,
-        renewal_interval_hours: 24,
-        dns_provider: None,
-        standby: false,
-        metrics_port: Some(9898),
-        server_name: Some("test-server".to_string()),
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app_root = temp.path().join("apps").join("my-app");
+    let release_dir = app_root.join("releases").join("v1");
+    std::fs::create_dir_all(&release_dir).unwrap();
+    std::fs::create_dir_all(app_root.join("data/app")).unwrap();
+    std::fs::create_dir_all(app_root.join("data/tako")).unwrap();
+
+    let config = AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: release_dir.clone(),
+        command: vec![
+            "/bin/sh".to_string(),
+            "-lc".to_string(),
+            "exit 0".to_string(),
+        ],
+        min_instances: 0,
+        ..Default::default()
+    };
+
+    let app = state.app_manager.register_app(config);
+    state.load_balancer.register_app(app);
+    {
+        let mut route_table = state.routes.write().await;
+        route_table
+            .set_app_routes("my-app".to_string(), vec!["api.example.com".to_string()])
+            .unwrap();
+    }
+
+    let response = state
+        .handle_command(Command::Delete {
+            app: "my-app".to_string(),
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }));
+    assert!(state.app_manager.get_app("my-app").is_none());
+    assert!(!app_root.exists());
+
+    let route_table = state.routes.read().await;
+    assert!(route_table.routes_for_app("my-app").is_empty());
+    assert_eq!(route_table.select("api.example.com", "/"), None);
+}
+
+#[tokio::test]
+async fn test_route_command_reports_winning_app_and_pattern() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    {
+        let mut route_table = state.routes.write().await;
+        route_table
+            .set_app_routes("tenants".to_string(), vec!["*.example.com".to_string()])
+            .unwrap();
+        route_table
+            .set_app_routes(
+                "api".to_string(),
+                vec!["api.example.com/v1/* -> strip".to_string()],
+            )
+            .unwrap();
+    }
+
+    let response = state
+        .handle_command(Command::TestRoute {
+            host: "api.example.com:443".to_string(),
+            path: "/v1/users".to_string(),
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected ok response, got {response:?}");
+    };
+    assert_eq!(data["host"], "api.example.com");
+    assert_eq!(data["app"], "api");
+    assert_eq!(data["pattern"], "api.example.com/v1/* -> strip");
+    assert_eq!(data["priority"], 0);
+
+    let response = state
+        .handle_command(Command::TestRoute {
+            host: "api.example.com".to_string(),
+            path: "v2".to_string(),
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected ok response, got {response:?}");
+    };
+    assert_eq!(data["path"], "/v2");
+    assert_eq!(data["app"], "tenants");
+    assert_eq!(data["pattern"], "*.example.com");
+
+    let response = state
+        .handle_command(Command::TestRoute {
+            host: "example.org".to_string(),
+            path: "/".to_string(),
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected ok response, got {response:?}");
+    };
+    assert!(data["app"].is_null());
+    assert!(data["pattern"].is_null());
+}
+
+#[tokio::test]
+async fn delete_command_is_idempotent_for_missing_app() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let response = state
+        .handle_command(Command::Delete {
+            app: "missing-app".to_string(),
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }));
+    assert!(state.app_manager.get_app("missing-app").is_none());
+}
+
+#[tokio::test]
+async fn delete_command_rejects_invalid_app_name() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let response = state
+        .handle_command(Command::Delete {
+            app: "../bad".to_string(),
+        })
+        .await;
+
+    let Response::Error { message } = response else {
+        panic!("expected invalid app name to be rejected");
+    };
+    assert!(message.contains("Invalid app name"), "got: {message}");
+}
+
+#[tokio::test]
+async fn upgrading_mode_blocks_mutating_commands() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    state.set_server_mode(UpgradeMode::Upgrading).await.unwrap();
+
+    let response = state
+        .handle_command(Command::Delete {
+            app: "my-app".to_string(),
+        })
+        .await;
+
+    let Response::Error { message } = response else {
+        panic!("expected blocked mutating command while upgrading");
+    };
+    assert!(message.contains("Server is upgrading"));
+    assert!(message.contains("delete"));
+}
+
+#[tokio::test]
+async fn server_mode_resets_upgrading_on_boot() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+
+    let state_a = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager.clone(),
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    state_a
+        .set_server_mode(UpgradeMode::Upgrading)
+        .await
+        .unwrap();
+    // Simulate an upgrade lock left behind by a crashed CLI.
+    assert!(state_a.try_enter_upgrading("crashed-cli").await.unwrap());
+    drop(state_a);
+
+    // On restart, stale Upgrading mode AND orphaned lock should be cleared.
+    let state_b = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager.clone(),
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    assert_eq!(*state_b.server_mode.read().await, UpgradeMode::Normal);
+    // A new owner should be able to acquire immediately (no 10-min stale wait).
+    assert!(state_b.try_enter_upgrading("new-cli").await.unwrap());
+}
+
+#[tokio::test]
+async fn upgrading_lock_allows_single_owner() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state_a = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager.clone(),
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    let state_b = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    assert!(state_a.try_enter_upgrading("controller-a").await.unwrap());
+    assert!(!state_b.try_enter_upgrading("controller-b").await.unwrap());
+    assert!(state_a.exit_upgrading("controller-a").await.unwrap());
+    assert!(state_b.try_enter_upgrading("controller-b").await.unwrap());
+}
+
+#[tokio::test]
+async fn server_info_command_reports_runtime_config() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let runtime = ServerRuntimeConfig {
+        pid: std::process::id(),
+        socket: "/var/run/tako/tako-custom.sock".to_string(),
+        data_dir: temp.path().to_path_buf(),
+        http_port: 8080,
+        https_port: 8443,
+        no_acme: true,
+        acme_staging: false,
+        acme_directory: None,
+        renewal_interval_hours: 24,
+        dns_provider: None,
+        standby: false,
+        metrics_port: Some(9898),
+        server_name: Some("test-server".to_string()),
+        ephemeral: false,
+        allow_secret_reveal: false,
+        state_key_file: None,
+        cold_start_page: None,
+        private_domain_suffixes: Vec::new(),
+        secret_refs: SecretRefPolicy::default(),
     };
     let state = ServerState::new_with_runtime(
         temp.path().to_path_buf(),
@@ -886,6 +1421,9 @@ async fn deploy_without_secrets_keeps_existing() {
             path: release_dir.to_string_lossy().to_string(),
             routes: vec!["keep.localhost".to_string()],
             secrets: None,
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         })
         .await;
 
@@ -896,6 +1434,16 @@ async fn deploy_without_secrets_keeps_existing() {
 
 #[tokio::test]
 async fn restore_from_state_store_rehydrates_apps_routes_and_secrets() {
+    check_restore_rehydrates_apps_routes_and_secrets(None).await;
+}
+
+#[tokio::test]
+async fn restore_from_state_store_rehydrates_apps_routes_and_secrets_in_memory() {
+    check_restore_rehydrates_apps_routes_and_secrets(Some(Arc::new(InMemoryStateStore::new())))
+        .await;
+}
+
+async fn check_restore_rehydrates_apps_routes_and_secrets(store: Option<Arc<dyn StateStore>>) {
     let temp = TempDir::new().unwrap();
     let app_id = "my-app/production";
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
@@ -903,13 +1451,7 @@ async fn restore_from_state_store_rehydrates_apps_routes_and_secrets() {
         ..Default::default()
     }));
 
-    let state_a = ServerState::new(
-        temp.path().to_path_buf(),
-        cert_manager.clone(),
-        None,
-        empty_challenge_tokens(),
-    )
-    .unwrap();
+    let state_a = boot_state(temp.path(), cert_manager.clone(), store.clone());
     let release_dir = temp
         .path()
         .join("apps")
@@ -954,24 +1496,20 @@ async fn restore_from_state_store_rehydrates_apps_routes_and_secrets() {
     state_a.load_balancer.register_app(app);
     {
         let mut route_table = state_a.routes.write().await;
-        route_table.set_app_routes(
-            app_id.to_string(),
-            vec![
-                "api.example.com".to_string(),
-                "example.com/api/*".to_string(),
-            ],
-        );
+        route_table
+            .set_app_routes(
+                app_id.to_string(),
+                vec![
+                    "api.example.com".to_string(),
+                    "example.com/api/*".to_string(),
+                ],
+            )
+            .unwrap();
     }
     state_a.persist_app_state(app_id).await;
     drop(state_a);
 
-    let state_b = ServerState::new(
-        temp.path().to_path_buf(),
-        cert_manager,
-        None,
-        empty_challenge_tokens(),
-    )
-    .unwrap();
+    let state_b = boot_state(temp.path(), cert_manager, store);
     state_b.restore_from_state_store().await.unwrap();
 
     let restored = state_b.app_manager.get_app(app_id).expect("app restored");
@@ -992,6 +1530,467 @@ async fn restore_from_state_store_rehydrates_apps_routes_and_secrets() {
     );
 }
 
+#[tokio::test]
+async fn export_then_import_recreates_apps_and_routes() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let store: Arc<dyn StateStore> = Arc::new(InMemoryStateStore::new());
+    for (name, version, shared_route) in [
+        ("api", "v2", "example.com/api/*"),
+        ("web", "v1", "example.com"),
+    ] {
+        let release_dir = temp
+            .path()
+            .join("apps")
+            .join(name)
+            .join("production")
+            .join("releases")
+            .join(version);
+        std::fs::create_dir_all(&release_dir).unwrap();
+        write_release_manifest(
+            &release_dir,
+            "node",
+            "index.js",
+            &["/bin/sh", "-lc", "sleep 600"],
+            Some("true"),
+            300,
+        );
+        let config = AppConfig {
+            name: name.to_string(),
+            environment: "production".to_string(),
+            version: version.to_string(),
+            min_instances: 0,
+            max_instances: 3,
+            drain_timeout: Duration::from_secs(45),
+            ..Default::default()
+        };
+        let routes = vec![format!("{name}.example.com"), shared_route.to_string()];
+        store.upsert_app(&config, &routes).unwrap();
+    }
+    store
+        .set_route_priority("web", "production", "example.com", 5)
+        .unwrap();
+    let secrets = HashMap::from([("API_KEY".to_string(), "secret".to_string())]);
+    store.set_secrets("web/production", &secrets).unwrap();
+
+    let source = boot_state(temp.path(), cert_manager.clone(), Some(store));
+    source.restore_from_state_store().await.unwrap();
+    let Response::Ok { data } = source.handle_command(Command::Export).await else {
+        panic!("expected export to succeed");
+    };
+    let mut exported: tako_core::StateExport = serde_json::from_value(data).unwrap();
+    assert_eq!(exported.apps.len(), 2);
+    assert_eq!(exported.apps[1].secret_names, vec!["API_KEY".to_string()]);
+    assert!(
+        !serde_json::to_string(&exported)
+            .unwrap()
+            .contains("secret\""),
+        "secret values must not be exported"
+    );
+    drop(source);
+
+    // A wiped server that still has the release files.
+    let target = boot_state(
+        temp.path(),
+        cert_manager,
+        Some(Arc::new(InMemoryStateStore::new())),
+    );
+    let response = target
+        .handle_command(Command::Import {
+            doc: exported.clone(),
+            replace: false,
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected import to succeed: {response:?}");
+    };
+    assert_eq!(data["imported"].as_array().unwrap().len(), 2, "{data}");
+    assert!(data["failed"].as_array().unwrap().is_empty(), "{data}");
+
+    let Response::Ok { data } = target.handle_command(Command::Export).await else {
+        panic!("expected export to succeed");
+    };
+    let reexported: tako_core::StateExport = serde_json::from_value(data).unwrap();
+    // Secret values stay behind, so the names don't carry over either.
+    exported.apps[1].secret_names.clear();
+    assert_eq!(reexported, exported);
+
+    let web = target.app_manager.get_app("web/production").unwrap();
+    assert_eq!(web.state(), AppState::Idle);
+    let route_table = target.routes.read().await;
+    assert_eq!(
+        route_table.routes_for_app("web/production"),
+        vec!["example.com".to_string(), "web.example.com".to_string()]
+    );
+    assert_eq!(
+        route_table.route_priority("web/production", "example.com"),
+        5
+    );
+    drop(route_table);
+
+    let response = target
+        .handle_command(Command::Import {
+            doc: reexported,
+            replace: false,
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected import to succeed: {response:?}");
+    };
+    assert_eq!(data["skipped"].as_array().unwrap().len(), 2, "{data}");
+}
+
+fn write_import_release(data_dir: &Path, name: &str, version: &str) {
+    let release_dir = data_dir
+        .join("apps")
+        .join(name)
+        .join("production")
+        .join("releases")
+        .join(version);
+    std::fs::create_dir_all(&release_dir).unwrap();
+    write_release_manifest(
+        &release_dir,
+        "node",
+        "index.js",
+        &["/bin/sh", "-lc", "sleep 600"],
+        Some("true"),
+        300,
+    );
+}
+
+fn exported_app(name: &str, routes: &[&str]) -> tako_core::ExportedApp {
+    tako_core::ExportedApp {
+        name: name.to_string(),
+        environment: "production".to_string(),
+        version: "v1".to_string(),
+        min_instances: 0,
+        max_instances: 1,
+        drain_timeout_secs: 30,
+        routes: routes.iter().map(|route| route.to_string()).collect(),
+        route_priorities: Default::default(),
+        secret_names: Vec::new(),
+        secret_refs: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn import_fails_apps_whose_routes_conflict() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    write_import_release(temp.path(), "api", "v1");
+    write_import_release(temp.path(), "web", "v1");
+    let state = boot_state(
+        temp.path(),
+        cert_manager,
+        Some(Arc::new(InMemoryStateStore::new())),
+    );
+
+    let doc = tako_core::StateExport {
+        format_version: tako_core::STATE_EXPORT_FORMAT_VERSION,
+        apps: vec![
+            exported_app("api", &["example.com"]),
+            exported_app("web", &["example.com"]),
+        ],
+    };
+    let response = state
+        .handle_command(Command::Import {
+            doc,
+            replace: false,
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected import to respond: {response:?}");
+    };
+    assert_eq!(data["imported"], serde_json::json!(["api/production"]));
+    assert_eq!(data["failed"][0]["app"], "web/production", "{data}");
+    assert!(
+        data["failed"][0]["error"]
+            .as_str()
+            .unwrap()
+            .contains("conflicts with the same route on app 'api/production'"),
+        "{data}"
+    );
+
+    assert!(state.app_manager.get_app("web/production").is_none());
+    let stored: Vec<String> = state
+        .state_store
+        .load_apps()
+        .unwrap()
+        .into_iter()
+        .map(|app| app.config.deployment_id())
+        .collect();
+    assert_eq!(stored, ["api/production"]);
+}
+
+#[tokio::test]
+async fn export_and_import_carry_secret_references() {
+    let temp = TempDir::new().unwrap();
+    let refs = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    write_import_release(temp.path(), "web", "v1");
+    let secret_file = refs.path().join("database_url");
+    std::fs::write(&secret_file, "postgres://db\n").unwrap();
+    let reference = format!("file:{}", secret_file.display());
+    let with_refs = |store: Arc<dyn StateStore>| {
+        let runtime = ServerRuntimeConfig {
+            secret_refs: SecretRefPolicy::new(
+                Some(refs.path().to_path_buf()),
+                Vec::new(),
+                temp.path(),
+            ),
+            ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
+        };
+        ServerState::new_with_store(
+            temp.path().to_path_buf(),
+            cert_manager.clone(),
+            None,
+            empty_challenge_tokens(),
+            runtime,
+            store,
+        )
+        .unwrap()
+    };
+
+    let store: Arc<dyn StateStore> = Arc::new(InMemoryStateStore::new());
+    let config = AppConfig {
+        name: "web".to_string(),
+        environment: "production".to_string(),
+        version: "v1".to_string(),
+        min_instances: 0,
+        max_instances: 1,
+        drain_timeout: Duration::from_secs(30),
+        ..Default::default()
+    };
+    store
+        .upsert_app(&config, &["web.example.com".to_string()])
+        .unwrap();
+    store
+        .set_secrets(
+            "web/production",
+            &HashMap::from([
+                ("DATABASE_URL".to_string(), reference.clone()),
+                ("API_KEY".to_string(), "plain-value".to_string()),
+            ]),
+        )
+        .unwrap();
+    let source = with_refs(store);
+    let Response::Ok { data } = source.handle_command(Command::Export).await else {
+        panic!("expected export to succeed");
+    };
+    let exported: tako_core::StateExport = serde_json::from_value(data).unwrap();
+    let app = &exported.apps[0];
+    assert_eq!(app.secret_names, ["API_KEY", "DATABASE_URL"]);
+    assert_eq!(
+        app.secret_refs,
+        std::collections::BTreeMap::from([("DATABASE_URL".to_string(), reference.clone())])
+    );
+    assert!(
+        !serde_json::to_string(&exported)
+            .unwrap()
+            .contains("plain-value")
+    );
+
+    let target = with_refs(Arc::new(InMemoryStateStore::new()));
+    let response = target
+        .handle_command(Command::Import {
+            doc: exported.clone(),
+            replace: false,
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    assert_eq!(
+        target.state_store.get_secrets("web/production").unwrap(),
+        HashMap::from([("DATABASE_URL".to_string(), reference)])
+    );
+
+    // Without references enabled the value would be stored as a literal.
+    let literal = boot_state(
+        temp.path(),
+        cert_manager.clone(),
+        Some(Arc::new(InMemoryStateStore::new())),
+    );
+    let response = literal
+        .handle_command(Command::Import {
+            doc: exported,
+            replace: false,
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected import to respond: {response:?}");
+    };
+    assert!(
+        data["failed"][0]["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid secrets"),
+        "{data}"
+    );
+    assert!(literal.app_manager.get_app("web/production").is_none());
+}
+
+#[tokio::test]
+async fn set_route_priority_reorders_selection_and_survives_restore() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state_a = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager.clone(),
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    for (name, route) in [("api", "example.com/api/*"), ("web", "example.com")] {
+        let app_id = format!("{name}/production");
+        let release_dir = temp
+            .path()
+            .join("apps")
+            .join(name)
+            .join("production")
+            .join("releases")
+            .join("v1");
+        std::fs::create_dir_all(&release_dir).unwrap();
+        write_release_manifest(
+            &release_dir,
+            "node",
+            "index.js",
+            &["/bin/sh", "-lc", "sleep 600"],
+            Some("true"),
+            300,
+        );
+        let app = state_a.app_manager.register_app(AppConfig {
+            name: name.to_string(),
+            environment: "production".to_string(),
+            version: "v1".to_string(),
+            path: release_dir,
+            min_instances: 0,
+            ..Default::default()
+        });
+        state_a.load_balancer.register_app(app);
+        state_a
+            .routes
+            .write()
+            .await
+            .set_app_routes(app_id.clone(), vec![route.to_string()])
+            .unwrap();
+        state_a.persist_app_state(&app_id).await;
+    }
+    assert_eq!(
+        state_a.routes.read().await.select("example.com", "/api/x"),
+        Some("api/production".to_string())
+    );
+
+    let response = state_a
+        .handle_command(Command::SetRoutePriority {
+            app: "web/production".to_string(),
+            route: "example.com".to_string(),
+            priority: 10,
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    assert_eq!(
+        state_a.routes.read().await.select("example.com", "/api/x"),
+        Some("web/production".to_string())
+    );
+
+    let unknown = state_a
+        .handle_command(Command::SetRoutePriority {
+            app: "web/production".to_string(),
+            route: "other.example.com".to_string(),
+            priority: 1,
+        })
+        .await;
+    assert!(matches!(unknown, Response::Error { .. }));
+    drop(state_a);
+
+    let state_b = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    state_b.restore_from_state_store().await.unwrap();
+    assert_eq!(
+        state_b.routes.read().await.select("example.com", "/api/x"),
+        Some("web/production".to_string())
+    );
+}
+
+#[tokio::test]
+async fn rollback_without_version_targets_previously_deployed_release() {
+    let temp = TempDir::new().unwrap();
+    let app_id = "my-app/production";
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    let app_root = temp.path().join("apps").join(app_id);
+    for version in ["v1", "v2"] {
+        std::fs::create_dir_all(app_root.join("releases").join(version)).unwrap();
+    }
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        environment: "production".to_string(),
+        version: "v1".to_string(),
+        path: app_root.join("releases").join("v1"),
+        ..Default::default()
+    });
+    state
+        .routes
+        .write()
+        .await
+        .set_app_routes(app_id.to_string(), vec!["api.example.com".to_string()])
+        .unwrap();
+    state.persist_app_state(app_id).await;
+
+    let response = state
+        .handle_command(Command::Rollback {
+            app: app_id.to_string(),
+            version: None,
+        })
+        .await;
+    let Response::Error { message } = response else {
+        panic!("expected rollback without history to fail");
+    };
+    assert!(message.contains("No previous release"), "got: {message}");
+
+    let mut next = app.config.read().clone();
+    next.version = "v2".to_string();
+    next.path = app_root.join("releases").join("v2");
+    app.update_config(next);
+    state.persist_app_state(app_id).await;
+
+    assert_eq!(
+        state.previous_release_version(&app, &app_root).unwrap(),
+        "v1"
+    );
+    assert_eq!(
+        state.routes.read().await.routes_for_app(app_id),
+        vec!["api.example.com".to_string()]
+    );
+}
+
 #[tokio::test]
 async fn restore_from_state_store_restarts_internal_socket_for_apps_with_workflows() {
     let temp = TempDir::new().unwrap();
@@ -1470,7 +2469,9 @@ async fn scale_command_persists_zero_instances_across_restore() {
     state_a.load_balancer.register_app(app.clone());
     {
         let mut route_table = state_a.routes.write().await;
-        route_table.set_app_routes("my-app".to_string(), vec!["api.example.com".to_string()]);
+        route_table
+            .set_app_routes("my-app".to_string(), vec!["api.example.com".to_string()])
+            .unwrap();
     }
 
     let first = app.allocate_instance();
@@ -1549,7 +2550,9 @@ async fn deploy_preserves_scaled_instance_count() {
     state.load_balancer.register_app(app.clone());
     {
         let mut route_table = state.routes.write().await;
-        route_table.set_app_routes("my-app".to_string(), vec!["api.example.com".to_string()]);
+        route_table
+            .set_app_routes("my-app".to_string(), vec!["api.example.com".to_string()])
+            .unwrap();
     }
 
     let old_instance = app.allocate_instance();
@@ -1575,6 +2578,9 @@ async fn deploy_preserves_scaled_instance_count() {
             path: broken_release.to_string_lossy().to_string(),
             routes: vec!["api.example.com".to_string()],
             secrets: Some(HashMap::new()),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         })
         .await;
 
@@ -1584,18 +2590,22 @@ async fn deploy_preserves_scaled_instance_count() {
 
 #[tokio::test]
 async fn delete_command_removes_persisted_state_for_next_boot() {
+    check_delete_removes_persisted_state_for_next_boot(None).await;
+}
+
+#[tokio::test]
+async fn delete_command_removes_persisted_state_for_next_boot_in_memory() {
+    check_delete_removes_persisted_state_for_next_boot(Some(Arc::new(InMemoryStateStore::new())))
+        .await;
+}
+
+async fn check_delete_removes_persisted_state_for_next_boot(store: Option<Arc<dyn StateStore>>) {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-    let state_a = ServerState::new(
-        temp.path().to_path_buf(),
-        cert_manager.clone(),
-        None,
-        empty_challenge_tokens(),
-    )
-    .unwrap();
+    let state_a = boot_state(temp.path(), cert_manager.clone(), store.clone());
 
     let release_dir = temp
         .path()
@@ -1627,10 +2637,12 @@ async fn delete_command_removes_persisted_state_for_next_boot() {
     state_a.load_balancer.register_app(app);
     {
         let mut route_table = state_a.routes.write().await;
-        route_table.set_app_routes(
-            "my-app/production".to_string(),
-            vec!["api.example.com".to_string()],
-        );
+        route_table
+            .set_app_routes(
+                "my-app/production".to_string(),
+                vec!["api.example.com".to_string()],
+            )
+            .unwrap();
     }
     state_a.persist_app_state("my-app/production").await;
 
@@ -1641,13 +2653,7 @@ async fn delete_command_removes_persisted_state_for_next_boot() {
         .await;
     assert!(matches!(response, Response::Ok { .. }));
 
-    let state_b = ServerState::new(
-        temp.path().to_path_buf(),
-        cert_manager,
-        None,
-        empty_challenge_tokens(),
-    )
-    .unwrap();
+    let state_b = boot_state(temp.path(), cert_manager, store);
     state_b.restore_from_state_store().await.unwrap();
     assert!(state_b.app_manager.get_app("my-app/production").is_none());
 }
@@ -1687,6 +2693,9 @@ async fn deploy_on_demand_validates_startup_and_fails_for_unhealthy_build() {
             path: release_dir.to_string_lossy().to_string(),
             routes: vec!["broken.localhost".to_string()],
             secrets: Some(HashMap::new()),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         })
         .await;
 
@@ -1694,38 +2703,276 @@ async fn deploy_on_demand_validates_startup_and_fails_for_unhealthy_build() {
         matches!(response, Response::Error { .. }),
         "expected startup validation failure for on-demand deploy: {response:?}"
     );
+
+    let history = state
+        .handle_command(Command::History {
+            app: "broken-app".to_string(),
+            limit: None,
+        })
+        .await;
+    let Response::Ok { data } = history else {
+        panic!("expected history response: {history:?}");
+    };
+    let history: tako_core::DeployHistoryResponse = serde_json::from_value(data).unwrap();
+    assert_eq!(history.entries.len(), 1);
+    assert_eq!(history.entries[0].version, "v1");
+    assert_eq!(history.entries[0].result, tako_core::DeployResult::Failed);
+    assert!(history.entries[0].error.is_some());
 }
 
-// TODO: This test needs a rewrite to work with the plugin-derived launch
-// command. The fake bun script exits immediately because the spawner's
-// binary resolution doesn't find the fake bun via the manifest's PATH.
-// The deploy lifecycle is fully covered by e2e tests (e2e/fixtures/).
 #[tokio::test]
-#[ignore = "needs rewrite for plugin architecture"]
-async fn deploy_on_demand_keeps_one_warm_instance_after_successful_deploy() {
-    if !python3_ok() || !python3_can_bind_loopback_tcp() {
-        return;
-    }
-
+async fn deploy_fails_when_new_instances_never_pass_a_health_check() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-    let runtime = ServerRuntimeConfig {
-        socket: "/tmp/tako-warm.sock".to_string(),
-        ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
-    };
-    let state = ServerState::new_with_runtime(
+    let state = ServerState::new(
         temp.path().to_path_buf(),
         cert_manager,
         None,
         empty_challenge_tokens(),
-        runtime,
     )
     .unwrap();
 
-    let fake_bin_dir = temp.path().join("bin");
+    let release_dir = temp
+        .path()
+        .join("apps")
+        .join("flaky-app")
+        .join("releases")
+        .join("v1");
+    std::fs::create_dir_all(&release_dir).unwrap();
+    // Reports ready on fd 4, then dies before any probe can pass.
+    write_release_manifest(
+        &release_dir,
+        "node",
+        "index.js",
+        &["/bin/sh", "-c", "echo 4000 >&4; sleep 0.3; exit 1"],
+        Some("true"),
+        300,
+    );
+
+    let response = state
+        .handle_command(Command::Deploy {
+            app: "flaky-app".to_string(),
+            version: "v1".to_string(),
+            path: release_dir.to_string_lossy().to_string(),
+            routes: vec!["flaky.localhost".to_string()],
+            secrets: Some(HashMap::new()),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
+        })
+        .await;
+
+    let Response::Error { message } = response else {
+        panic!("expected deploy to fail: {response:?}");
+    };
+    assert!(message.contains("did not pass health checks"), "{message}");
+    let app = state.app_manager.get_app("flaky-app").unwrap();
+    assert_eq!(app.state(), AppState::Error);
+    assert!(app.get_instances().is_empty());
+}
+
+fn live_release_state(temp: &TempDir, app_json: &str) -> (ServerState, std::path::PathBuf) {
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let release_dir = temp
+        .path()
+        .join("apps")
+        .join("my-app")
+        .join("releases")
+        .join("v1");
+    std::fs::create_dir_all(&release_dir).unwrap();
+    std::fs::write(release_dir.join("app.json"), app_json).unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: release_dir.clone(),
+        min_instances: 1,
+        max_instances: 4,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    app.set_state(AppState::Running);
+    app.allocate_instance().set_state(InstanceState::Healthy);
+    (state, release_dir)
+}
+
+fn keyed_deploy(release_dir: &Path, key: &str) -> Command {
+    Command::Deploy {
+        app: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: release_dir.to_string_lossy().to_string(),
+        routes: vec!["api.example.com".to_string()],
+        secrets: None,
+        idempotency_key: Some(key.to_string()),
+        strategy: Default::default(),
+        canary: None,
+    }
+}
+
+#[tokio::test]
+async fn deploy_with_same_idempotency_key_is_a_noop_while_release_is_live() {
+    let temp = TempDir::new().unwrap();
+    let (state, release_dir) = live_release_state(
+        &temp,
+        r#"{"runtime":"custom","main":"index.js","idle_timeout":300}"#,
+    );
+    state.record_completed_deploy(
+        "my-app",
+        "v1",
+        Some("key-1".to_string()),
+        &Response::ok(serde_json::json!({
+            "status": "deployed",
+            "app": "my-app",
+            "version": "v1",
+            "new_instances": 1,
+            "old_instances": 1,
+        })),
+    );
+
+    for _ in 0..2 {
+        let response = state
+            .handle_command(keyed_deploy(&release_dir, "key-1"))
+            .await;
+        let Response::Ok { data } = response else {
+            panic!("expected duplicate deploy to succeed: {response:?}");
+        };
+        assert_eq!(data["duplicate"], Value::Bool(true));
+        assert_eq!(data["version"], "v1");
+        assert_eq!(data["new_instances"], 1);
+    }
+
+    let app = state.app_manager.get_app("my-app").unwrap();
+    assert_eq!(app.get_instances().len(), 1);
+    assert_eq!(app.state(), AppState::Running);
+    let history = state
+        .handle_command(Command::History {
+            app: "my-app".to_string(),
+            limit: None,
+        })
+        .await;
+    let Response::Ok { data } = history else {
+        panic!("expected history response: {history:?}");
+    };
+    let history: tako_core::DeployHistoryResponse = serde_json::from_value(data).unwrap();
+    assert!(
+        history.entries.is_empty(),
+        "no-op deploy must not be recorded"
+    );
+}
+
+#[tokio::test]
+async fn deploy_with_new_idempotency_key_runs_and_forgets_previous_key_on_failure() {
+    let temp = TempDir::new().unwrap();
+    let (state, release_dir) = live_release_state(
+        &temp,
+        r#"{"runtime":"custom","main":"","idle_timeout":300}"#,
+    );
+    state.record_completed_deploy(
+        "my-app",
+        "v1",
+        Some("key-1".to_string()),
+        &Response::ok(serde_json::json!({ "status": "deployed", "version": "v1" })),
+    );
+
+    let response = state
+        .handle_command(keyed_deploy(&release_dir, "key-2"))
+        .await;
+    let Response::Error { message } = response else {
+        panic!("expected the new key to run a real deploy: {response:?}");
+    };
+    assert!(message.contains("Invalid app release"), "got: {message}");
+
+    let response = state
+        .handle_command(keyed_deploy(&release_dir, "key-1"))
+        .await;
+    assert!(
+        matches!(response, Response::Error { .. }),
+        "failed deploy must clear the stored key: {response:?}"
+    );
+}
+
+#[tokio::test]
+async fn secret_and_route_changes_forget_the_stored_deploy_result() {
+    let temp = TempDir::new().unwrap();
+    let (state, _release_dir) = live_release_state(
+        &temp,
+        r#"{"runtime":"custom","main":"index.js","idle_timeout":300}"#,
+    );
+    let completed = Response::ok(serde_json::json!({ "status": "deployed", "version": "v1" }));
+    state
+        .routes
+        .write()
+        .await
+        .set_app_routes("my-app".to_string(), vec!["api.example.com".to_string()])
+        .unwrap();
+
+    state.record_completed_deploy("my-app", "v1", Some("key-1".to_string()), &completed);
+    let response = state
+        .handle_command(Command::SetRoutePriority {
+            app: "my-app".to_string(),
+            route: "api.example.com".to_string(),
+            priority: 5,
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    assert!(!state.completed_deploys.lock().contains_key("my-app"));
+
+    // An app that isn't running takes the secrets update without a restart.
+    state.record_completed_deploy("other-app", "v1", Some("key-1".to_string()), &completed);
+    let response = state
+        .handle_command(Command::UpdateSecrets {
+            app: "other-app".to_string(),
+            secrets: HashMap::from([("API_KEY".to_string(), "rotated".to_string())]),
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    assert!(!state.completed_deploys.lock().contains_key("other-app"));
+}
+
+// TODO: This test needs a rewrite to work with the plugin-derived launch
+// command. The fake bun script exits immediately because the spawner's
+// binary resolution doesn't find the fake bun via the manifest's PATH.
+// The deploy lifecycle is fully covered by e2e tests (e2e/fixtures/).
+#[tokio::test]
+#[ignore = "needs rewrite for plugin architecture"]
+async fn deploy_on_demand_keeps_one_warm_instance_after_successful_deploy() {
+    if !python3_ok() || !python3_can_bind_loopback_tcp() {
+        return;
+    }
+
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let runtime = ServerRuntimeConfig {
+        socket: "/tmp/tako-warm.sock".to_string(),
+        ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
+    };
+    let state = ServerState::new_with_runtime(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+        runtime,
+    )
+    .unwrap();
+
+    let fake_bin_dir = temp.path().join("bin");
     std::fs::create_dir_all(&fake_bin_dir).unwrap();
     let fake_bun = fake_bin_dir.join("bun");
     let fake_server_py = temp.path().join("server.py");
@@ -1844,6 +3091,9 @@ HTTPServer(("127.0.0.1", port), Handler).serve_forever()
             path: release_dir.to_string_lossy().to_string(),
             routes: vec!["warm.localhost".to_string()],
             secrets: Some(HashMap::new()),
+            idempotency_key: None,
+            strategy: Default::default(),
+            canary: None,
         })
         .await;
     assert!(
@@ -1969,7 +3219,7 @@ async fn instance_ready_event_sets_health_metric() {
 }
 
 #[tokio::test]
-async fn status_includes_running_builds_for_each_version() {
+async fn app_stops_respawning_after_max_restart_attempts() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
@@ -1982,51 +3232,51 @@ async fn status_includes_running_builds_for_each_version() {
         empty_challenge_tokens(),
     )
     .unwrap();
+    let mut events = state.app_manager.take_event_receiver().unwrap();
 
     let app = state.app_manager.register_app(AppConfig {
-        name: "my-app".to_string(),
+        name: "crashy".to_string(),
         version: "v1".to_string(),
-        min_instances: 0,
+        path: temp.path().to_path_buf(),
+        command: vec!["sh".to_string(), "-c".to_string(), "exit 1".to_string()],
+        min_instances: 1,
+        max_restart_attempts: 2,
+        max_restart_backoff: Duration::ZERO,
         ..Default::default()
     });
+    app.set_state(AppState::Running);
 
-    let old = app.allocate_instance();
-    old.set_state(InstanceState::Healthy);
-
-    let mut cfg = app.config.read().clone();
-    cfg.version = "v2".to_string();
-    app.update_config(cfg);
-
-    let new = app.allocate_instance();
-    new.set_state(InstanceState::Healthy);
-
-    let response = state
-        .handle_command(Command::Status {
-            app: "my-app".to_string(),
-        })
+    let mut spawns = 0;
+    for _ in 0..4 {
+        let crashed = app.allocate_instance();
+        crashed.set_state(InstanceState::Unhealthy);
+        handle_instance_event(
+            &state,
+            crate::instances::InstanceEvent::Unhealthy {
+                app: "crashy".to_string(),
+                instance_id: crashed.id.clone(),
+            },
+        )
         .await;
+        while let Ok(event) = events.try_recv() {
+            if matches!(event, crate::instances::InstanceEvent::Started { .. }) {
+                spawns += 1;
+            }
+        }
+    }
 
-    let Response::Ok { data } = response else {
-        panic!("expected ok status response");
-    };
-
-    let builds = data
-        .get("builds")
-        .and_then(Value::as_array)
-        .expect("status should include builds");
-    let versions: Vec<&str> = builds
-        .iter()
-        .filter_map(|b| b.get("version").and_then(Value::as_str))
-        .collect();
+    assert_eq!(spawns, 2, "only the allowed restarts should spawn");
+    assert_eq!(app.state(), AppState::Error);
     assert!(
-        versions.contains(&"v1") && versions.contains(&"v2"),
-        "expected status to include both running builds: {data}"
+        app.last_error()
+            .is_some_and(|e| e.contains("Stopped restarting after 2 failed restarts"))
     );
+    assert!(app.restarts_exhausted());
+    assert!(app.get_instances().is_empty());
 }
 
-// CodeQL[rust/cleartext-logging]: hardcoded fixture secrets in tests; set_secrets encrypts at rest and update_secrets logs only app name.
 #[tokio::test]
-async fn run_release_executes_command_in_release_dir() {
+async fn app_stops_respawning_after_consecutive_spawn_failures() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
@@ -2039,78 +3289,288 @@ async fn run_release_executes_command_in_release_dir() {
         empty_challenge_tokens(),
     )
     .unwrap();
+    let mut events = state.app_manager.take_event_receiver().unwrap();
 
-    // App name uses '/' separator; filesystem encodes this as two directory components.
-    let release_dir = temp
-        .path()
-        .join("apps")
-        .join("my-app")
-        .join("production")
-        .join("releases")
-        .join("abc1234");
-    std::fs::create_dir_all(&release_dir).unwrap();
-    let manifest = serde_json::json!({
-        "runtime": "bun",
-        "main": "index.ts",
-        "idle_timeout": 300,
-        "app_dir": "",
-        "env_vars": {"NODE_ENV": "production"},
+    // The process starts but exits before reporting ready, so every spawn fails.
+    let app = state.app_manager.register_app(AppConfig {
+        name: "broken".to_string(),
+        version: "v1".to_string(),
+        path: temp.path().to_path_buf(),
+        command: vec!["sh".to_string(), "-c".to_string(), "exit 1".to_string()],
+        min_instances: 1,
+        max_restart_attempts: 100,
+        max_restart_backoff: Duration::ZERO,
+        ..Default::default()
     });
-    std::fs::write(
-        release_dir.join("app.json"),
-        serde_json::to_vec_pretty(&manifest).unwrap(),
-    )
-    .unwrap();
+    app.set_state(AppState::Running);
 
-    // Seed secrets for the app so the run_release handler can pick them up.
-    state
-        .state_store
-        .set_secrets(
-            "my-app/production",
-            &HashMap::from([("DATABASE_URL".to_string(), "postgres://x".to_string())]),
+    let mut spawns = 0;
+    for _ in 0..6 {
+        let crashed = app.allocate_instance();
+        crashed.set_state(InstanceState::Unhealthy);
+        handle_instance_event(
+            &state,
+            crate::instances::InstanceEvent::Unhealthy {
+                app: "broken".to_string(),
+                instance_id: crashed.id.clone(),
+            },
         )
-        .unwrap();
-
-    let response = state
-        .handle_command(Command::RunRelease {
-            app: "my-app/production".to_string(),
-            version: "abc1234".to_string(),
-            path: release_dir.to_string_lossy().to_string(),
-            command_line: "printf %s \"$NODE_ENV-$DATABASE_URL\" > out.txt".to_string(),
-            vars: HashMap::new(),
-            secrets: HashMap::new(),
-        })
         .await;
+        while let Ok(event) = events.try_recv() {
+            if matches!(event, crate::instances::InstanceEvent::Started { .. }) {
+                spawns += 1;
+            }
+        }
+    }
 
-    let data = match response {
-        Response::Ok { data } => data,
-        Response::Error { message } => panic!("expected ok, got: {message}"),
-    };
-    assert_eq!(data.get("exit_code").and_then(|v| v.as_i64()), Some(0));
-
-    let written = std::fs::read_to_string(release_dir.join("out.txt")).unwrap();
-    assert_eq!(written, "production-postgres://x");
+    assert_eq!(
+        spawns,
+        crate::defaults::MAX_CONSECUTIVE_SPAWN_FAILURES,
+        "no spawns should be attempted once the breaker trips"
+    );
+    assert_eq!(app.state(), AppState::Error);
+    assert!(
+        app.last_error()
+            .is_some_and(|e| e.contains("consecutive spawn failures"))
+    );
+    assert!(app.restarts_exhausted());
+    assert!(app.get_instances().is_empty());
 }
 
-// CodeQL[rust/cleartext-logging]: hardcoded fixture secrets in tests; set_secrets encrypts at rest and update_secrets logs only app name.
 #[tokio::test]
-async fn run_release_returns_error_on_nonzero_exit() {
+async fn status_stream_pushes_status_after_instance_event() {
     let temp = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-    let state = ServerState::new(
-        temp.path().to_path_buf(),
-        cert_manager,
-        None,
-        empty_challenge_tokens(),
-    )
-    .unwrap();
+    let state = Arc::new(
+        ServerState::new(
+            temp.path().to_path_buf(),
+            cert_manager,
+            None,
+            empty_challenge_tokens(),
+        )
+        .unwrap(),
+    );
 
-    let release_dir = temp
-        .path()
-        .join("apps")
+    let app = state.app_manager.register_app(AppConfig {
+        name: "watch-app".to_string(),
+        version: "v1".to_string(),
+        min_instances: 1,
+        ..Default::default()
+    });
+    app.set_state(AppState::Running);
+    let instance = app.allocate_instance();
+
+    let crate::socket::Reply::Stream(mut frames) = state
+        .handle_socket_command(Command::StatusStream {
+            app: "watch-app".to_string(),
+        })
+        .await
+    else {
+        panic!("status_stream should reply with a stream");
+    };
+    let next_status = |frame: Option<Response>| -> AppStatus {
+        match frame {
+            Some(Response::Ok { data }) => serde_json::from_value(data).unwrap(),
+            other => panic!("expected a status frame, got {other:?}"),
+        }
+    };
+
+    let initial = next_status(frames.recv().await);
+    assert_eq!(initial.instances.len(), 1);
+    assert_ne!(initial.instances[0].state, InstanceState::Healthy);
+
+    instance.set_state(InstanceState::Healthy);
+    handle_instance_event(
+        &state,
+        crate::instances::InstanceEvent::Ready {
+            app: "watch-app".to_string(),
+            instance_id: instance.id.clone(),
+        },
+    )
+    .await;
+
+    let pushed = next_status(
+        tokio::time::timeout(Duration::from_secs(5), frames.recv())
+            .await
+            .expect("status change should push a frame"),
+    );
+    assert_eq!(pushed.instances[0].state, InstanceState::Healthy);
+}
+
+#[tokio::test]
+async fn status_stream_rejects_unknown_app() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = Arc::new(
+        ServerState::new(
+            temp.path().to_path_buf(),
+            cert_manager,
+            None,
+            empty_challenge_tokens(),
+        )
+        .unwrap(),
+    );
+
+    let reply = state
+        .handle_socket_command(Command::StatusStream {
+            app: "missing".to_string(),
+        })
+        .await;
+    let crate::socket::Reply::Once(response) = reply else {
+        panic!("unknown app should get a single error response");
+    };
+    assert_eq!(response.error_message(), Some("App not found: missing"));
+}
+
+#[tokio::test]
+async fn tail_returns_lifecycle_events_for_app() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "tail-app".to_string(),
+        version: "v1".to_string(),
+        min_instances: 0,
+        ..Default::default()
+    });
+    let instance = app.allocate_instance();
+
+    for event in [
+        crate::instances::InstanceEvent::Started {
+            app: "tail-app".to_string(),
+            instance_id: instance.id.clone(),
+        },
+        crate::instances::InstanceEvent::Ready {
+            app: "tail-app".to_string(),
+            instance_id: instance.id.clone(),
+        },
+    ] {
+        handle_instance_event(&state, event).await;
+    }
+    // Let the app log writer drain its channel and flush.
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+
+    let response = state
+        .handle_command(Command::Tail {
+            app: "tail-app".to_string(),
+            lines: None,
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected tail to succeed: {response:?}");
+    };
+    let tail: tako_core::TailResponse = serde_json::from_value(data).unwrap();
+    let messages: Vec<(&str, &str)> = tail
+        .entries
+        .iter()
+        .map(|e| (e.scope.as_str(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        vec![("tako", "instance started"), ("tako", "instance ready")]
+    );
+
+    let missing = state
+        .handle_command(Command::Tail {
+            app: "missing-app".to_string(),
+            lines: None,
+        })
+        .await;
+    assert!(matches!(missing, Response::Error { .. }));
+}
+
+#[tokio::test]
+async fn status_includes_running_builds_for_each_version() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        min_instances: 0,
+        ..Default::default()
+    });
+
+    let old = app.allocate_instance();
+    old.set_state(InstanceState::Healthy);
+
+    let mut cfg = app.config.read().clone();
+    cfg.version = "v2".to_string();
+    app.update_config(cfg);
+
+    let new = app.allocate_instance();
+    new.set_state(InstanceState::Healthy);
+
+    let response = state
+        .handle_command(Command::Status {
+            app: "my-app".to_string(),
+        })
+        .await;
+
+    let Response::Ok { data } = response else {
+        panic!("expected ok status response");
+    };
+
+    let builds = data
+        .get("builds")
+        .and_then(Value::as_array)
+        .expect("status should include builds");
+    let versions: Vec<&str> = builds
+        .iter()
+        .filter_map(|b| b.get("version").and_then(Value::as_str))
+        .collect();
+    assert!(
+        versions.contains(&"v1") && versions.contains(&"v2"),
+        "expected status to include both running builds: {data}"
+    );
+}
+
+// CodeQL[rust/cleartext-logging]: hardcoded fixture secrets in tests; set_secrets encrypts at rest and update_secrets logs only app name.
+#[tokio::test]
+async fn run_release_executes_command_in_release_dir() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    // App name uses '/' separator; filesystem encodes this as two directory components.
+    let release_dir = temp
+        .path()
+        .join("apps")
         .join("my-app")
         .join("production")
         .join("releases")
@@ -2121,6 +3581,7 @@ async fn run_release_returns_error_on_nonzero_exit() {
         "main": "index.ts",
         "idle_timeout": 300,
         "app_dir": "",
+        "env_vars": {"NODE_ENV": "production"},
     });
     std::fs::write(
         release_dir.join("app.json"),
@@ -2128,65 +3589,210 @@ async fn run_release_returns_error_on_nonzero_exit() {
     )
     .unwrap();
 
+    // Seed secrets for the app so the run_release handler can pick them up.
+    state
+        .state_store
+        .set_secrets(
+            "my-app/production",
+            &HashMap::from([("DATABASE_URL".to_string(), "postgres://x".to_string())]),
+        )
+        .unwrap();
+
     let response = state
         .handle_command(Command::RunRelease {
             app: "my-app/production".to_string(),
             version: "abc1234".to_string(),
             path: release_dir.to_string_lossy().to_string(),
-            command_line: "echo boom 1>&2; exit 3".to_string(),
+            command_line: "printf %s \"$NODE_ENV-$DATABASE_URL\" > out.txt".to_string(),
             vars: HashMap::new(),
             secrets: HashMap::new(),
         })
         .await;
 
-    let Response::Error { message } = response else {
-        panic!("expected error response, got: {response:?}");
+    let data = match response {
+        Response::Ok { data } => data,
+        Response::Error { message } => panic!("expected ok, got: {message}"),
     };
-    assert!(
-        message.contains('3'),
-        "expected exit code in message: {message}"
-    );
-    assert!(
-        message.contains("boom"),
-        "expected stderr in message: {message}"
-    );
+    assert_eq!(data.get("exit_code").and_then(|v| v.as_i64()), Some(0));
+
+    let written = std::fs::read_to_string(release_dir.join("out.txt")).unwrap();
+    assert_eq!(written, "production-postgres://x");
 }
 
 #[tokio::test]
-async fn run_release_rejects_path_outside_release_root() {
+async fn run_release_resolves_secret_references() {
     let temp = TempDir::new().unwrap();
+    let refs = TempDir::new().unwrap();
     let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
         cert_dir: temp.path().join("certs"),
         ..Default::default()
     }));
-    let state = ServerState::new(
+    let runtime = ServerRuntimeConfig {
+        secret_refs: SecretRefPolicy::new(Some(refs.path().to_path_buf()), Vec::new(), temp.path()),
+        ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
+    };
+    let state = ServerState::new_with_runtime(
         temp.path().to_path_buf(),
         cert_manager,
         None,
         empty_challenge_tokens(),
+        runtime,
+    )
+    .unwrap();
+
+    let release_dir = temp
+        .path()
+        .join("apps")
+        .join("my-app")
+        .join("production")
+        .join("releases")
+        .join("abc1234");
+    std::fs::create_dir_all(&release_dir).unwrap();
+    let manifest = serde_json::json!({
+        "runtime": "bun",
+        "main": "index.ts",
+        "idle_timeout": 300,
+        "app_dir": "",
+    });
+    std::fs::write(
+        release_dir.join("app.json"),
+        serde_json::to_vec_pretty(&manifest).unwrap(),
     )
     .unwrap();
 
+    let secret_file = refs.path().join("database_url");
+    std::fs::write(&secret_file, "postgres://resolved\n").unwrap();
+    state
+        .state_store
+        .set_secrets(
+            "my-app/production",
+            &HashMap::from([(
+                "DATABASE_URL".to_string(),
+                format!("file:{}", secret_file.display()),
+            )]),
+        )
+        .unwrap();
+
+    let run = |command_line: &str| Command::RunRelease {
+        app: "my-app/production".to_string(),
+        version: "abc1234".to_string(),
+        path: release_dir.to_string_lossy().to_string(),
+        command_line: command_line.to_string(),
+        vars: HashMap::new(),
+        secrets: HashMap::new(),
+    };
     let response = state
-        .handle_command(Command::RunRelease {
-            app: "my-app/production".to_string(),
-            version: "abc1234".to_string(),
-            path: "/etc/passwd".to_string(),
-            command_line: "true".to_string(),
-            vars: HashMap::new(),
-            secrets: HashMap::new(),
-        })
+        .handle_command(run("printf %s \"$DATABASE_URL\" > out.txt"))
         .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    let written = std::fs::read_to_string(release_dir.join("out.txt")).unwrap();
+    assert_eq!(written, "postgres://resolved");
 
-    assert!(matches!(response, Response::Error { .. }));
+    // A reference that no longer resolves stops the release before it runs.
+    std::fs::remove_file(&secret_file).unwrap();
+    let response = state.handle_command(run("touch ran.txt")).await;
+    let Response::Error { message } = response else {
+        panic!("expected unresolved reference to fail: {response:?}");
+    };
+    assert!(message.starts_with("Invalid secrets"), "got: {message}");
+    assert!(!release_dir.join("ran.txt").exists());
 }
 
-#[test]
-fn read_server_config_from_json() {
-    let dir = TempDir::new().unwrap();
-    std::fs::write(
-        dir.path().join("config.json"),
-        r#"{"server_name":"prod","dns":{"provider":"cloudflare"}}"#,
+// CodeQL[rust/cleartext-logging]: hardcoded fixture secrets in tests; set_secrets encrypts at rest and update_secrets logs only app name.
+#[tokio::test]
+async fn run_release_returns_error_on_nonzero_exit() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let release_dir = temp
+        .path()
+        .join("apps")
+        .join("my-app")
+        .join("production")
+        .join("releases")
+        .join("abc1234");
+    std::fs::create_dir_all(&release_dir).unwrap();
+    let manifest = serde_json::json!({
+        "runtime": "bun",
+        "main": "index.ts",
+        "idle_timeout": 300,
+        "app_dir": "",
+    });
+    std::fs::write(
+        release_dir.join("app.json"),
+        serde_json::to_vec_pretty(&manifest).unwrap(),
+    )
+    .unwrap();
+
+    let response = state
+        .handle_command(Command::RunRelease {
+            app: "my-app/production".to_string(),
+            version: "abc1234".to_string(),
+            path: release_dir.to_string_lossy().to_string(),
+            command_line: "echo boom 1>&2; exit 3".to_string(),
+            vars: HashMap::new(),
+            secrets: HashMap::new(),
+        })
+        .await;
+
+    let Response::Error { message } = response else {
+        panic!("expected error response, got: {response:?}");
+    };
+    assert!(
+        message.contains('3'),
+        "expected exit code in message: {message}"
+    );
+    assert!(
+        message.contains("boom"),
+        "expected stderr in message: {message}"
+    );
+}
+
+#[tokio::test]
+async fn run_release_rejects_path_outside_release_root() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let response = state
+        .handle_command(Command::RunRelease {
+            app: "my-app/production".to_string(),
+            version: "abc1234".to_string(),
+            path: "/etc/passwd".to_string(),
+            command_line: "true".to_string(),
+            vars: HashMap::new(),
+            secrets: HashMap::new(),
+        })
+        .await;
+
+    assert!(matches!(response, Response::Error { .. }));
+}
+
+#[test]
+fn read_server_config_from_json() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"server_name":"prod","dns":{"provider":"cloudflare"}}"#,
     )
     .unwrap();
     let config = read_server_config(dir.path());
@@ -2201,3 +3807,992 @@ fn read_server_config_returns_defaults_when_missing() {
     assert!(config.server_name.is_none());
     assert!(config.dns.is_none());
 }
+
+#[tokio::test]
+async fn promote_drains_stable_build_and_makes_canary_current() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let releases = temp.path().join("apps").join("canary-app").join("releases");
+    for version in ["v1", "v2"] {
+        let release_dir = releases.join(version);
+        std::fs::create_dir_all(&release_dir).unwrap();
+        std::fs::write(
+            release_dir.join("app.json"),
+            r#"{"runtime":"node","main":"index.js","idle_timeout":300,"start":["/bin/sh","-lc","exit 0"]}"#,
+        )
+        .unwrap();
+    }
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "canary-app".to_string(),
+        version: "v1".to_string(),
+        min_instances: 2,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    for _ in 0..2 {
+        app.allocate_instance().set_state(InstanceState::Healthy);
+    }
+    // Launch one canary instance from v2 while v1 stays current.
+    let mut canary_config = app.config.read().clone();
+    canary_config.version = "v2".to_string();
+    app.update_config(canary_config);
+    app.allocate_instance().set_state(InstanceState::Healthy);
+    let mut stable_config = app.config.read().clone();
+    stable_config.version = "v1".to_string();
+    app.update_config(stable_config);
+
+    let response = state
+        .handle_command(Command::Promote {
+            app: "canary-app".to_string(),
+            version: "v2".to_string(),
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected promote to succeed: {response:?}");
+    };
+    assert_eq!(data["status"], "promoted");
+    assert_eq!(data["drained_instances"], 2);
+
+    assert_eq!(app.version(), "v2");
+    let instances = app.get_instances();
+    assert_eq!(instances.len(), 1);
+    assert_eq!(instances[0].build_version(), "v2");
+    let persisted = state.state_store.load_apps().unwrap();
+    assert_eq!(persisted[0].config.version, "v2");
+
+    let response = state
+        .handle_command(Command::Promote {
+            app: "canary-app".to_string(),
+            version: "v2".to_string(),
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected repeated promote to be a no-op: {response:?}");
+    };
+    assert_eq!(data["status"], "already_current");
+    assert_eq!(app.get_instances().len(), 1);
+}
+
+#[tokio::test]
+async fn promote_ends_live_canary_hold_and_drains_stable_build() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: temp.path().to_path_buf(),
+        command: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo 4000 >&4; exec sleep 60".to_string(),
+        ],
+        min_instances: 1,
+        max_instances: 1,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    app.allocate_instance().set_state(InstanceState::Healthy);
+
+    let updater = RollingUpdater::new(
+        state.app_manager.spawner(),
+        RollingUpdateConfig::for_app(&app.config.read()),
+    );
+    let mut next = app.config.read().clone();
+    next.version = "v2".to_string();
+    let lock = state.get_deploy_lock("my-app").await;
+
+    // The canary deploy holds the deploy lock for the whole hold, like a
+    // real deploy; promote has to end the hold to get in.
+    let deploy = async {
+        let _guard = lock.lock().await;
+        updater
+            .canary_update(&app, next, 1, 25, Duration::from_secs(30))
+            .await
+            .unwrap()
+    };
+    let promote = async {
+        while !matches!(app.routing(), BuildRouting::Canary { .. }) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        state
+            .handle_command(Command::Promote {
+                app: "my-app".to_string(),
+                version: "v2".to_string(),
+            })
+            .await
+    };
+    let (result, response) = tokio::time::timeout(Duration::from_secs(10), async {
+        tokio::join!(deploy, promote)
+    })
+    .await
+    .expect("promote should end the canary hold");
+
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.old_instances, 1);
+    let Response::Ok { data } = response else {
+        panic!("expected promote to succeed: {response:?}");
+    };
+    assert_eq!(data["status"], "promoted");
+    assert_eq!(data["drained_instances"], 1);
+
+    assert_eq!(app.version(), "v2");
+    assert_eq!(app.routing(), BuildRouting::All);
+    let instances = app.get_instances();
+    assert_eq!(instances.len(), 1);
+    assert_eq!(instances[0].build_version(), "v2");
+
+    let _ = instances[0].kill().await;
+}
+
+#[tokio::test]
+async fn ephemeral_server_keeps_state_out_of_data_dir() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let runtime = ServerRuntimeConfig {
+        ephemeral: true,
+        ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
+    };
+    let state = ServerState::new_with_runtime(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+        runtime,
+    )
+    .unwrap();
+
+    let secrets = HashMap::from([("KEY".to_string(), "value".to_string())]);
+    state.state_store.set_secrets("my-app", &secrets).unwrap();
+    assert_eq!(state.state_store.get_secrets("my-app").unwrap(), secrets);
+    assert!(!temp.path().join("tako.db").exists());
+    assert!(!temp.path().join("secret.key").exists());
+}
+
+#[tokio::test]
+async fn set_drain_timeout_is_persisted_and_used_by_next_drain() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = boot_state(temp.path(), cert_manager, None);
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "drain-app".to_string(),
+        version: "v1".to_string(),
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    let instance = app.allocate_instance();
+    instance.set_state(InstanceState::Healthy);
+    // A request that never finishes would hold the default 30s drain open.
+    instance.request_started();
+
+    let response = state
+        .handle_command(Command::SetDrainTimeout {
+            app: "drain-app".to_string(),
+            seconds: 1,
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected set_drain_timeout to succeed: {response:?}");
+    };
+    assert_eq!(data["drain_timeout_secs"], 1);
+
+    let persisted = state.state_store.load_apps().unwrap();
+    assert_eq!(persisted[0].config.drain_timeout, Duration::from_secs(1));
+
+    let started = std::time::Instant::now();
+    let response = tokio::time::timeout(
+        Duration::from_secs(10),
+        state.handle_command(Command::Stop {
+            app: "drain-app".to_string(),
+        }),
+    )
+    .await
+    .expect("stop should drain within the configured timeout");
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert!(app.get_instances().is_empty());
+}
+
+#[tokio::test]
+async fn set_drain_timeout_rejects_values_over_limit() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = boot_state(temp.path(), cert_manager, None);
+    state.app_manager.register_app(AppConfig {
+        name: "drain-app".to_string(),
+        version: "v1".to_string(),
+        ..Default::default()
+    });
+
+    let response = state
+        .handle_command(Command::SetDrainTimeout {
+            app: "drain-app".to_string(),
+            seconds: 7200,
+        })
+        .await;
+    assert!(matches!(response, Response::Error { .. }), "{response:?}");
+}
+
+#[tokio::test]
+async fn drain_stops_routing_but_keeps_instances_running() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = boot_state(temp.path(), cert_manager, None);
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "drain-app".to_string(),
+        version: "v1".to_string(),
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    let instance = app.allocate_instance();
+    instance.set_state(InstanceState::Healthy);
+
+    let response = state
+        .handle_command(Command::Drain {
+            app: "drain-app".to_string(),
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected drain to succeed: {response:?}");
+    };
+    assert_eq!(data["status"], "drained");
+    assert!(state.load_balancer.get_backend("drain-app").is_none());
+    assert_eq!(app.get_instances().len(), 1);
+    assert_eq!(instance.state(), InstanceState::Healthy);
+
+    let response = state
+        .handle_command(Command::Undrain {
+            app: "drain-app".to_string(),
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    let backend = state.load_balancer.get_backend("drain-app").unwrap();
+    assert_eq!(backend.instance_id, instance.id);
+
+    let response = state
+        .handle_command(Command::Drain {
+            app: "missing-app".to_string(),
+        })
+        .await;
+    assert!(matches!(response, Response::Error { .. }), "{response:?}");
+}
+
+#[tokio::test]
+async fn shutdown_blocks_mutating_commands_and_finishes_when_idle() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        state.shutdown(Duration::from_secs(30)),
+    )
+    .await
+    .expect("idle server should not wait for the drain deadline");
+    assert_eq!(state.drain.lock().phase(), DrainPhase::Stopped);
+
+    let response = state
+        .handle_command(Command::Stop {
+            app: "my-app".to_string(),
+        })
+        .await;
+    let Response::Error { message } = response else {
+        panic!("expected stop to be rejected during shutdown: {response:?}");
+    };
+    assert!(message.contains("shutting down"), "got: {message}");
+}
+
+#[tokio::test]
+async fn shutdown_does_not_overwrite_state_written_by_a_successor() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let stale = AppConfig {
+        name: "my-app".to_string(),
+        environment: "production".to_string(),
+        version: "v1".to_string(),
+        min_instances: 0,
+        ..Default::default()
+    };
+    let app = state.app_manager.register_app(stale.clone());
+    state.load_balancer.register_app(app);
+    state
+        .routes
+        .write()
+        .await
+        .set_app_routes(
+            "my-app/production".to_string(),
+            vec!["old.example.com".to_string()],
+        )
+        .unwrap();
+
+    // A reloaded server deployed v2 while this process was draining.
+    let current = AppConfig {
+        version: "v2".to_string(),
+        ..stale
+    };
+    state
+        .state_store
+        .upsert_app(&current, &["new.example.com".to_string()])
+        .unwrap();
+
+    state.shutdown(Duration::ZERO).await;
+
+    let apps = state.state_store.load_apps().unwrap();
+    assert_eq!(apps.len(), 1);
+    assert_eq!(apps[0].config.version, "v2");
+    assert_eq!(apps[0].routes, vec!["new.example.com".to_string()]);
+}
+
+#[tokio::test]
+async fn restart_with_instance_id_replaces_only_that_instance() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: temp.path().to_path_buf(),
+        command: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo 4000 >&4; exec sleep 60".to_string(),
+        ],
+        min_instances: 3,
+        max_instances: 3,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+
+    let ids: Vec<String> = (0..3)
+        .map(|_| {
+            let instance = app.allocate_instance();
+            instance.set_state(InstanceState::Healthy);
+            instance.id.clone()
+        })
+        .collect();
+
+    let response = state
+        .handle_command(Command::Restart {
+            app: "my-app".to_string(),
+            instance_id: Some(ids[1].clone()),
+        })
+        .await;
+    let Response::Ok { data } = response else {
+        panic!("expected restart to succeed: {response:?}");
+    };
+    let new_id = data["new_instance_id"].as_str().unwrap().to_string();
+
+    let mut remaining: Vec<String> = app.get_instances().iter().map(|i| i.id.clone()).collect();
+    remaining.sort();
+    let mut expected = vec![ids[0].clone(), ids[2].clone(), new_id.clone()];
+    expected.sort();
+    assert_eq!(remaining, expected);
+    assert_ne!(new_id, ids[1]);
+
+    let missing = state
+        .handle_command(Command::Restart {
+            app: "my-app".to_string(),
+            instance_id: Some(ids[1].clone()),
+        })
+        .await;
+    assert!(matches!(missing, Response::Error { .. }));
+
+    let _ = app.get_instance(&new_id).unwrap().kill().await;
+}
+
+#[tokio::test]
+async fn blue_green_update_stages_new_build_before_switching_traffic() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: temp.path().to_path_buf(),
+        command: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo 4000 >&4; exec sleep 60".to_string(),
+        ],
+        min_instances: 2,
+        max_instances: 2,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    for _ in 0..2 {
+        app.allocate_instance().set_state(InstanceState::Healthy);
+    }
+
+    let updater = RollingUpdater::new(
+        state.app_manager.spawner(),
+        RollingUpdateConfig::for_app(&app.config.read()),
+    );
+    let mut next = app.config.read().clone();
+    next.version = "v2".to_string();
+    let staged = updater.stage_build(&app, next, 2).await.unwrap();
+
+    let builds = |instances: Vec<Arc<crate::instances::Instance>>| {
+        let mut builds: Vec<String> = instances
+            .iter()
+            .map(|i| i.build_version().to_string())
+            .collect();
+        builds.sort();
+        builds
+    };
+    assert_eq!(builds(app.get_instances()), ["v1", "v1", "v2", "v2"]);
+    assert_eq!(builds(app.get_healthy_instances()), ["v1", "v1"]);
+
+    let stopped = updater.switch_to_build(&app, &staged).await.unwrap();
+    assert_eq!(stopped, 2);
+    assert_eq!(builds(app.get_instances()), ["v2", "v2"]);
+    assert_eq!(builds(app.get_healthy_instances()), ["v2", "v2"]);
+    assert_eq!(app.routing(), BuildRouting::All);
+
+    for instance in app.get_instances() {
+        let _ = instance.kill().await;
+    }
+}
+
+#[tokio::test]
+async fn blue_green_redeploy_of_live_version_replaces_the_old_instances() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: temp.path().to_path_buf(),
+        command: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo 4000 >&4; exec sleep 60".to_string(),
+        ],
+        min_instances: 2,
+        max_instances: 2,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    let mut old_ids: Vec<String> = (0..2)
+        .map(|_| {
+            let instance = app.allocate_instance();
+            instance.set_state(InstanceState::Healthy);
+            instance.id.clone()
+        })
+        .collect();
+    old_ids.sort();
+    let ids = |instances: Vec<Arc<crate::instances::Instance>>| {
+        let mut ids: Vec<String> = instances.iter().map(|i| i.id.clone()).collect();
+        ids.sort();
+        ids
+    };
+
+    let updater = RollingUpdater::new(
+        state.app_manager.spawner(),
+        RollingUpdateConfig::for_app(&app.config.read()),
+    );
+    let same_version = app.config.read().clone();
+    let mut staged = updater.stage_build(&app, same_version, 2).await.unwrap();
+    staged.sort();
+
+    // The live instances of the same version keep serving while staged.
+    assert_eq!(app.get_instances().len(), 4);
+    assert_eq!(ids(app.get_healthy_instances()), old_ids);
+
+    let stopped = updater.switch_to_build(&app, &staged).await.unwrap();
+    assert_eq!(stopped, 2);
+    assert_eq!(ids(app.get_instances()), staged);
+    assert_eq!(ids(app.get_healthy_instances()), staged);
+
+    for instance in app.get_instances() {
+        let _ = instance.kill().await;
+    }
+}
+
+#[tokio::test]
+async fn canary_update_promotes_new_build_after_healthy_hold() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: temp.path().to_path_buf(),
+        command: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo 4000 >&4; exec sleep 60".to_string(),
+        ],
+        min_instances: 1,
+        max_instances: 1,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    app.allocate_instance().set_state(InstanceState::Healthy);
+
+    let updater = RollingUpdater::new(
+        state.app_manager.spawner(),
+        RollingUpdateConfig::for_app(&app.config.read()),
+    );
+    let mut next = app.config.read().clone();
+    next.version = "v2".to_string();
+    let result = updater
+        .canary_update(&app, next, 1, 25, Duration::from_millis(300))
+        .await
+        .unwrap();
+
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.old_instances, 1);
+    let instances = app.get_instances();
+    assert_eq!(instances.len(), 1);
+    assert_eq!(instances[0].build_version(), "v2");
+    assert_eq!(app.routing(), BuildRouting::All);
+
+    let _ = instances[0].kill().await;
+}
+
+#[tokio::test]
+async fn failed_canary_hold_kills_only_the_staged_instances() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        version: "v1".to_string(),
+        path: temp.path().to_path_buf(),
+        command: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo 4000 >&4; exec sleep 60".to_string(),
+        ],
+        min_instances: 1,
+        max_instances: 1,
+        ..Default::default()
+    });
+    state.load_balancer.register_app(app.clone());
+    let live = app.allocate_instance();
+    live.set_state(InstanceState::Healthy);
+
+    let updater = RollingUpdater::new(
+        state.app_manager.spawner(),
+        RollingUpdateConfig::for_app(&app.config.read()),
+    );
+    // Same version as the live instance, so only the staged id tells them apart.
+    let same_version = app.config.read().clone();
+    let (result, ()) = tokio::join!(
+        updater.canary_update(&app, same_version, 1, 25, Duration::from_secs(30)),
+        async {
+            for _ in 0..500 {
+                if let BuildRouting::Canary { instances, .. } = app.routing() {
+                    app.get_instance(&instances[0])
+                        .unwrap()
+                        .set_state(InstanceState::Unhealthy);
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            panic!("canary never started receiving traffic");
+        }
+    );
+
+    let result = result.unwrap();
+    assert!(!result.success);
+    assert!(result.rolled_back);
+    let instances = app.get_instances();
+    assert_eq!(instances.len(), 1);
+    assert_eq!(instances[0].id, live.id);
+    assert_eq!(app.routing(), BuildRouting::All);
+}
+
+#[test]
+fn state_key_file_encrypts_secrets_instead_of_generated_key() {
+    let temp = TempDir::new().unwrap();
+    let key_file = temp.path().join("provisioned.key");
+    std::fs::write(&key_file, [0x42; 32]).unwrap();
+    let data_dir = temp.path().join("data");
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: data_dir.join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new_with_runtime(
+        data_dir.clone(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+        ServerRuntimeConfig {
+            state_key_file: Some(key_file.clone()),
+            ..ServerRuntimeConfig::for_defaults(data_dir.clone())
+        },
+    )
+    .unwrap();
+    let secrets = HashMap::from([("KEY".to_string(), "value".to_string())]);
+    state.state_store.set_secrets("my-app", &secrets).unwrap();
+
+    assert!(!data_dir.join("secret.key").exists());
+    let reopened = crate::state_store::SqliteStateStore::new(data_dir.join("tako.db"), [0x42; 32]);
+    assert_eq!(reopened.get_secrets("my-app").unwrap(), secrets);
+}
+
+#[tokio::test]
+async fn env_redacts_secrets_unless_reveal_is_requested_and_allowed() {
+    let temp = TempDir::new().unwrap();
+    let new_state = |allow_secret_reveal: bool| {
+        let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+            cert_dir: temp.path().join("certs"),
+            ..Default::default()
+        }));
+        let state = ServerState::new_with_runtime(
+            temp.path().to_path_buf(),
+            cert_manager,
+            None,
+            empty_challenge_tokens(),
+            ServerRuntimeConfig {
+                allow_secret_reveal,
+                ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
+            },
+        )
+        .unwrap();
+        state.app_manager.register_app(AppConfig {
+            name: "my-app".to_string(),
+            env_vars: HashMap::from([
+                ("LOG_LEVEL".to_string(), "info".to_string()),
+                ("API_KEY".to_string(), "from-vars".to_string()),
+            ]),
+            secrets: HashMap::from([("API_KEY".to_string(), "s3cret".to_string())]),
+            ..Default::default()
+        });
+        state
+    };
+    let env_of = |response: Response| match response {
+        Response::Ok { data } => data["env"].clone(),
+        Response::Error { message } => panic!("env failed: {message}"),
+    };
+
+    let state = new_state(false);
+    let redacted = env_of(
+        state
+            .handle_command(Command::Env {
+                app: "my-app".to_string(),
+                reveal: false,
+            })
+            .await,
+    );
+    assert_eq!(
+        redacted,
+        serde_json::json!({ "API_KEY": "***", "LOG_LEVEL": "info" })
+    );
+    let refused = state
+        .handle_command(Command::Env {
+            app: "my-app".to_string(),
+            reveal: true,
+        })
+        .await;
+    assert!(matches!(refused, Response::Error { .. }));
+    drop(state);
+
+    let state = new_state(true);
+    let revealed = env_of(
+        state
+            .handle_command(Command::Env {
+                app: "my-app".to_string(),
+                reveal: true,
+            })
+            .await,
+    );
+    assert_eq!(
+        revealed,
+        serde_json::json!({ "API_KEY": "s3cret", "LOG_LEVEL": "info" })
+    );
+}
+
+#[tokio::test]
+async fn secrets_generation_bumps_on_update_and_flags_stale_instances() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+    let app = state.app_manager.register_app(AppConfig {
+        name: "my-app".to_string(),
+        path: temp.path().to_path_buf(),
+        ..Default::default()
+    });
+
+    for expected in 1..=2 {
+        let response = state
+            .handle_command(Command::UpdateSecrets {
+                app: "my-app".to_string(),
+                secrets: HashMap::from([("TOKEN".to_string(), format!("v{expected}"))]),
+            })
+            .await;
+        assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+        assert_eq!(app.config.read().secrets_generation, expected);
+    }
+
+    let instance = app.allocate_instance();
+    instance.set_state(InstanceState::Healthy);
+    assert_eq!(instance.secrets_generation(), 2);
+
+    let status = |response: Response| match response {
+        Response::Ok { data } => serde_json::from_value::<AppStatus>(data).unwrap(),
+        Response::Error { message } => panic!("status failed: {message}"),
+    };
+    let current = status(
+        state
+            .handle_command(Command::Status {
+                app: "my-app".to_string(),
+            })
+            .await,
+    );
+    assert_eq!(current.secrets_generation, 2);
+    assert!(!current.secrets_stale);
+
+    // Secrets changed but the instance was not replaced.
+    let mut config = app.config.read().clone();
+    config.secrets_generation += 1;
+    app.update_config(config);
+    let stale = status(
+        state
+            .handle_command(Command::Status {
+                app: "my-app".to_string(),
+            })
+            .await,
+    );
+    assert!(stale.secrets_stale);
+    assert_eq!(stale.instances[0].secrets_generation, 2);
+}
+
+#[tokio::test]
+async fn secret_file_references_are_stored_unresolved() {
+    let temp = TempDir::new().unwrap();
+    let refs = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let runtime = ServerRuntimeConfig {
+        secret_refs: SecretRefPolicy::new(Some(refs.path().to_path_buf()), Vec::new(), temp.path()),
+        ..ServerRuntimeConfig::for_defaults(temp.path().to_path_buf())
+    };
+    let state = ServerState::new_with_runtime(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+        runtime,
+    )
+    .unwrap();
+    let secret_file = refs.path().join("db_password");
+    std::fs::write(&secret_file, "hunter2\n").unwrap();
+    let reference = format!("file:{}", secret_file.display());
+
+    let response = state
+        .handle_command(Command::UpdateSecrets {
+            app: "my-app".to_string(),
+            secrets: HashMap::from([("DB_PASSWORD".to_string(), reference.clone())]),
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+
+    let stored = state.state_store.get_secrets("my-app").unwrap();
+    assert_eq!(stored["DB_PASSWORD"], reference);
+    assert_eq!(
+        state.runtime.secret_refs.resolve_secrets(&stored).unwrap()["DB_PASSWORD"],
+        "hunter2"
+    );
+
+    let missing = state
+        .handle_command(Command::UpdateSecrets {
+            app: "my-app".to_string(),
+            secrets: HashMap::from([(
+                "DB_PASSWORD".to_string(),
+                format!("file:{}", refs.path().join("missing").display()),
+            )]),
+        })
+        .await;
+    assert!(matches!(missing, Response::Error { .. }));
+    assert_eq!(
+        state.state_store.get_secrets("my-app").unwrap()["DB_PASSWORD"],
+        reference
+    );
+}
+
+#[tokio::test]
+async fn secret_file_references_cannot_read_the_data_directory() {
+    let temp = TempDir::new().unwrap();
+    let data_dir = temp.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: data_dir.join("certs"),
+        ..Default::default()
+    }));
+    // The allowed directory contains the data directory; it stays off limits.
+    let runtime = ServerRuntimeConfig {
+        secret_refs: SecretRefPolicy::new(Some(temp.path().to_path_buf()), Vec::new(), &data_dir),
+        ..ServerRuntimeConfig::for_defaults(data_dir.clone())
+    };
+    let state = ServerState::new_with_runtime(
+        data_dir.clone(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+        runtime,
+    )
+    .unwrap();
+
+    let response = state
+        .handle_command(Command::UpdateSecrets {
+            app: "my-app".to_string(),
+            secrets: HashMap::from([(
+                "KEY".to_string(),
+                format!("file:{}", data_dir.join("tako.db").display()),
+            )]),
+        })
+        .await;
+    let Response::Error { message } = response else {
+        panic!("expected data dir reference to be rejected: {response:?}");
+    };
+    assert!(message.contains("server data directory"), "got: {message}");
+}
+
+#[tokio::test]
+async fn secret_values_are_literal_without_reference_opt_in() {
+    let temp = TempDir::new().unwrap();
+    let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    }));
+    let state = ServerState::new(
+        temp.path().to_path_buf(),
+        cert_manager,
+        None,
+        empty_challenge_tokens(),
+    )
+    .unwrap();
+
+    let value = format!("file:{}", temp.path().join("secret.key").display());
+    let response = state
+        .handle_command(Command::UpdateSecrets {
+            app: "my-app".to_string(),
+            secrets: HashMap::from([("KEY".to_string(), value.clone())]),
+        })
+        .await;
+    assert!(matches!(response, Response::Ok { .. }), "{response:?}");
+    let stored = state.state_store.get_secrets("my-app").unwrap();
+    assert_eq!(
+        state.runtime.secret_refs.resolve_secrets(&stored).unwrap()["KEY"],
+        value
+    );
+}
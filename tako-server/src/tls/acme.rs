@@ -2,8 +2,10 @@
 //!
 //! Uses instant-acme for the ACME protocol implementation.
 //! Supports HTTP-01 challenges for non-wildcard domains and
-//! DNS-01 challenges (via lego) for wildcard certificates.
+//! DNS-01 challenges for wildcard certificates, either through a
+//! [`DnsChallengeProvider`] or by shelling out to lego.
 
+use super::dns01::{DnsChallengeProvider, DnsTxtRecords, dns01_record_name};
 use super::manager::{CertError, CertInfo, CertManager};
 use instant_acme::{
     Account, AuthorizationStatus, ChallengeType, Identifier, NewAccount, NewOrder, OrderStatus,
@@ -11,7 +13,7 @@ use instant_acme::{
 };
 use parking_lot::RwLock;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
@@ -58,6 +60,12 @@ pub enum AcmeError {
     #[error("HTTP-01 challenge not available")]
     NoHttp01Challenge,
 
+    #[error("DNS-01 challenge not available")]
+    NoDns01Challenge,
+
+    #[error("DNS provider error: {0}")]
+    DnsProvider(String),
+
     #[error("Wildcard certificate requires a DNS provider (dns.provider not set in config.json)")]
     NoDnsProvider,
 
@@ -70,6 +78,8 @@ pub enum AcmeError {
 pub struct AcmeConfig {
     /// Use Let's Encrypt staging (for testing)
     pub staging: bool,
+    /// Custom ACME directory (e.g. ZeroSSL or an internal CA); overrides `staging`
+    pub directory_url: Option<String>,
     /// Contact email for ACME account
     pub email: Option<String>,
     /// Directory to store ACME account credentials
@@ -82,6 +92,8 @@ pub struct AcmeConfig {
     pub check_delay: Duration,
     /// DNS provider for lego DNS-01 challenges (e.g. "cloudflare", "route53")
     pub dns_provider: Option<String>,
+    /// In-process DNS-01 provider; takes precedence over `dns_provider` for wildcards
+    pub dns_challenge_provider: Option<Arc<dyn DnsChallengeProvider>>,
     /// Server data directory (lego stores state under `<data_dir>/lego/`)
     pub data_dir: PathBuf,
 }
@@ -90,24 +102,30 @@ impl Default for AcmeConfig {
     fn default() -> Self {
         Self {
             staging: false,
+            directory_url: None,
             email: None,
             account_dir: PathBuf::from("/opt/tako/acme"),
             timeout: Duration::from_secs(300),
             max_attempts: 30,
             check_delay: Duration::from_secs(5),
             dns_provider: None,
+            dns_challenge_provider: None,
             data_dir: PathBuf::from("/opt/tako"),
         }
     }
 }
 
+const LETS_ENCRYPT_PRODUCTION: &str = "https://acme-v02.api.letsencrypt.org/directory";
+const LETS_ENCRYPT_STAGING: &str = "https://acme-staging-v02.api.letsencrypt.org/directory";
+
 impl AcmeConfig {
-    /// Get the ACME directory URL
-    pub fn directory_url(&self) -> String {
-        if self.staging {
-            "https://acme-staging-v02.api.letsencrypt.org/directory".to_string()
-        } else {
-            "https://acme-v02.api.letsencrypt.org/directory".to_string()
+    /// ACME directory URL: explicit `directory_url`, else Let's Encrypt
+    /// staging or production
+    pub fn resolved_directory_url(&self) -> String {
+        match &self.directory_url {
+            Some(url) => url.clone(),
+            None if self.staging => LETS_ENCRYPT_STAGING.to_string(),
+            None => LETS_ENCRYPT_PRODUCTION.to_string(),
         }
     }
 }
@@ -162,8 +180,18 @@ impl AcmeClient {
 
         let credentials_path = self.config.account_dir.join("credentials.json");
 
-        // Try to load existing account
-        if credentials_path.exists() {
+        // Try to load existing account, unless it was registered with another CA
+        let directory_url = self.config.resolved_directory_url();
+        let saved_directory = saved_account_directory(&self.config.account_dir);
+        if saved_directory
+            .as_deref()
+            .is_some_and(|saved| saved != directory_url)
+        {
+            tracing::info!(
+                directory = %directory_url,
+                "ACME directory changed, registering a new account"
+            );
+        } else if credentials_path.exists() {
             match self.load_account(&credentials_path).await {
                 Ok(account) => {
                     tracing::info!("Loaded existing ACME account");
@@ -207,6 +235,7 @@ impl AcmeClient {
             "created_timestamp": now,
             "email": self.config.email,
             "staging": self.config.staging,
+            "directory_url": directory_url,
             "id": account.id(),
         });
         {
@@ -270,7 +299,7 @@ impl AcmeClient {
 
         let (account, credentials) = Account::builder()
             .map_err(AcmeError::Acme)?
-            .create(&new_account, self.config.directory_url(), None)
+            .create(&new_account, self.config.resolved_directory_url(), None)
             .await?;
 
         Ok((account, credentials))
@@ -278,7 +307,8 @@ impl AcmeClient {
 
     /// Request a certificate for a domain.
     ///
-    /// Wildcard domains (starting with `*.`) use DNS-01 via lego.
+    /// Wildcard domains (starting with `*.`) use DNS-01, through the
+    /// configured [`DnsChallengeProvider`] if any, otherwise via lego.
     /// All other domains use HTTP-01 via instant-acme.
     pub async fn request_certificate(&self, domain: &str) -> Result<CertInfo, AcmeError> {
         // Validate domain
@@ -286,17 +316,87 @@ impl AcmeClient {
             return Err(AcmeError::InvalidDomain(domain.to_string()));
         }
 
+        let mut txt_records = None;
         if domain.starts_with("*.") {
-            return self.request_certificate_dns01(domain).await;
+            match &self.config.dns_challenge_provider {
+                Some(provider) => txt_records = Some(DnsTxtRecords::new(provider.clone())),
+                None => return self.request_certificate_dns01(domain).await,
+            }
+        }
+
+        tracing::info!(domain = domain, "Requesting certificate via ACME");
+
+        let issued = self.order_certificate(domain, txt_records.as_mut()).await;
+
+        // Clean up challenge tokens and TXT records whether or not the order succeeded
+        self.clear_domain_tokens(domain);
+        if let Some(records) = txt_records {
+            records.remove_all().await;
+        }
+
+        let (cert_chain, private_key_pem) = issued?;
+
+        // Save certificate and key
+        let domain_dir = self.cert_manager.domain_cert_dir(domain);
+        std::fs::create_dir_all(&domain_dir)?;
+
+        let cert_path = domain_dir.join("fullchain.pem");
+        let key_path = domain_dir.join("privkey.pem");
+
+        // Write certificate chain
+        std::fs::write(&cert_path, &cert_chain)?;
+
+        // Write private key (already in PEM format)
+        std::fs::write(&key_path, &private_key_pem)?;
+
+        // Set restrictive permissions on key
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
         }
 
+        // Parse validity window from certificate
+        let validity = parse_cert_validity(&cert_chain);
+
+        let cert_info = CertInfo {
+            domain: domain.to_string(),
+            cert_path: cert_path.clone(),
+            key_path: key_path.clone(),
+            not_before: validity.map(|(not_before, _)| not_before),
+            expires_at: validity.map(|(_, not_after)| not_after),
+            is_wildcard: domain.starts_with("*."),
+            is_self_signed: false,
+            ocsp: None,
+        };
+
+        // Add to cert manager
+        self.cert_manager.add_cert(cert_info.clone());
+
+        tracing::info!(
+            domain = domain,
+            cert_path = %cert_path.display(),
+            expires_in_days = cert_info.days_until_expiry(),
+            "Certificate issued successfully"
+        );
+
+        Ok(cert_info)
+    }
+
+    /// Run an ACME order for `domain` and return `(certificate chain, private key)` PEMs.
+    ///
+    /// Pending authorizations are answered over DNS-01 when `txt_records` is
+    /// given, otherwise over HTTP-01. Callers clean up challenge state.
+    async fn order_certificate(
+        &self,
+        domain: &str,
+        mut txt_records: Option<&mut DnsTxtRecords>,
+    ) -> Result<(String, String), AcmeError> {
         let account = {
             let guard = self.account.read();
             guard.clone().ok_or(AcmeError::NotRegistered)?
         };
 
-        tracing::info!(domain = domain, "Requesting certificate via ACME");
-
         // Create order
         let identifiers = [Identifier::Dns(domain.to_string())];
         let new_order = NewOrder::new(&identifiers);
@@ -310,6 +410,25 @@ impl AcmeClient {
 
             match auth.status {
                 AuthorizationStatus::Pending => {
+                    if let Some(records) = txt_records.as_deref_mut() {
+                        let mut challenge = auth
+                            .challenge(ChallengeType::Dns01)
+                            .ok_or(AcmeError::NoDns01Challenge)?;
+                        let record_name = dns01_record_name(domain);
+                        records
+                            .publish(&record_name, &challenge.key_authorization().dns_value())
+                            .await?;
+
+                        tracing::info!(
+                            domain = domain,
+                            record = %record_name,
+                            "DNS-01 challenge TXT record published"
+                        );
+
+                        challenge.set_ready().await?;
+                        continue;
+                    }
+
                     // Get HTTP-01 challenge
                     let mut challenge = auth
                         .challenge(ChallengeType::Http01)
@@ -364,8 +483,6 @@ impl AcmeClient {
                 tracing::info!(domain = domain, "Order ready, finalizing");
             }
             OrderStatus::Invalid => {
-                self.clear_domain_tokens(domain);
-
                 // Re-fetch authorizations to capture the challenge error detail
                 // from Let's Encrypt (e.g. DNS resolution failures, wrong content).
                 let mut detail = String::from("Order became invalid");
@@ -393,14 +510,10 @@ impl AcmeClient {
                 return Err(AcmeError::ChallengeFailed(detail));
             }
             status => {
-                self.clear_domain_tokens(domain);
                 return Err(AcmeError::OrderNotReady(format!("{:?}", status)));
             }
         }
 
-        // Clean up challenge tokens
-        self.clear_domain_tokens(domain);
-
         // Finalize order - this generates a CSR internally with rcgen
         // Returns the private key as a PEM string
         let private_key_pem = order.finalize().await?;
@@ -408,49 +521,7 @@ impl AcmeClient {
         // Poll for certificate with retry policy
         let cert_chain = order.poll_certificate(&retry_policy).await?;
 
-        // Save certificate and key
-        let domain_dir = self.cert_manager.domain_cert_dir(domain);
-        std::fs::create_dir_all(&domain_dir)?;
-
-        let cert_path = domain_dir.join("fullchain.pem");
-        let key_path = domain_dir.join("privkey.pem");
-
-        // Write certificate chain
-        std::fs::write(&cert_path, &cert_chain)?;
-
-        // Write private key (already in PEM format)
-        std::fs::write(&key_path, &private_key_pem)?;
-
-        // Set restrictive permissions on key
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
-        }
-
-        // Parse expiry from certificate
-        let expires_at = parse_cert_expiry(&cert_chain);
-
-        let cert_info = CertInfo {
-            domain: domain.to_string(),
-            cert_path: cert_path.clone(),
-            key_path: key_path.clone(),
-            expires_at,
-            is_wildcard: domain.starts_with("*."),
-            is_self_signed: false,
-        };
-
-        // Add to cert manager
-        self.cert_manager.add_cert(cert_info.clone());
-
-        tracing::info!(
-            domain = domain,
-            cert_path = %cert_path.display(),
-            expires_in_days = cert_info.days_until_expiry(),
-            "Certificate issued successfully"
-        );
-
-        Ok(cert_info)
+        Ok((cert_chain, private_key_pem))
     }
 
     /// Request a wildcard certificate using DNS-01 challenge via lego.
@@ -493,10 +564,8 @@ impl AcmeClient {
 
         cmd.arg("--accept-tos");
 
-        if self.config.staging {
-            cmd.arg("--server")
-                .arg("https://acme-staging-v02.api.letsencrypt.org/directory");
-        }
+        cmd.arg("--server")
+            .arg(self.config.resolved_directory_url());
 
         cmd.arg(lego_action);
 
@@ -548,15 +617,17 @@ impl AcmeClient {
         }
 
         let cert_pem = std::fs::read_to_string(&cert_path)?;
-        let expires_at = parse_cert_expiry(&cert_pem);
+        let validity = parse_cert_validity(&cert_pem);
 
         let cert_info = CertInfo {
             domain: domain.to_string(),
             cert_path: cert_path.clone(),
             key_path: key_path.clone(),
-            expires_at,
+            not_before: validity.map(|(not_before, _)| not_before),
+            expires_at: validity.map(|(_, not_after)| not_after),
             is_wildcard: true,
             is_self_signed: false,
+            ocsp: None,
         };
 
         self.cert_manager.add_cert(cert_info.clone());
@@ -600,8 +671,8 @@ impl AcmeClient {
         self.config.staging
     }
 
-    /// Run renewal check for all certificates
-    pub async fn check_renewals(&self) -> Vec<Result<CertInfo, AcmeError>> {
+    /// Run renewal check for all certificates, returning each attempt by domain
+    pub async fn check_renewals(&self) -> Vec<(String, Result<CertInfo, AcmeError>)> {
         let certs_to_renew = self.cert_manager.get_certs_needing_renewal();
         let mut results = Vec::new();
 
@@ -612,7 +683,7 @@ impl AcmeClient {
                 "Certificate needs renewal"
             );
             let result = self.renew_certificate(&cert.domain).await;
-            results.push(result);
+            results.push((cert.domain, result));
         }
 
         results
@@ -622,6 +693,18 @@ impl AcmeClient {
     pub fn config(&self) -> &AcmeConfig {
         &self.config
     }
+
+    /// Get the certificate manager this client issues into
+    pub fn cert_manager(&self) -> &Arc<CertManager> {
+        &self.cert_manager
+    }
+}
+
+/// Directory URL recorded in `account.json` when the saved account was created
+fn saved_account_directory(account_dir: &Path) -> Option<String> {
+    let raw = std::fs::read_to_string(account_dir.join("account.json")).ok()?;
+    let info: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    info.get("directory_url")?.as_str().map(str::to_string)
 }
 
 const LEGO_VERSION: &str = "4.33.0";
@@ -732,22 +815,28 @@ async fn ensure_lego_installed(data_dir: &std::path::Path) -> Result<PathBuf, Ac
     Ok(lego_bin)
 }
 
-/// Parse certificate expiry from PEM data
-fn parse_cert_expiry(pem_data: &str) -> Option<std::time::SystemTime> {
+/// Parse certificate validity window (`not_before`, `not_after`) from PEM data
+fn parse_cert_validity(pem_data: &str) -> Option<(std::time::SystemTime, std::time::SystemTime)> {
     use x509_parser::prelude::*;
 
+    let to_system_time = |time: ASN1Time| {
+        let timestamp = time.timestamp();
+        if timestamp < 0 {
+            return None;
+        }
+        std::time::UNIX_EPOCH.checked_add(std::time::Duration::from_secs(timestamp as u64))
+    };
+
     // Find the first certificate in the chain
     for pem in Pem::iter_from_buffer(pem_data.as_bytes()).flatten() {
         if pem.label == "CERTIFICATE"
             && let Ok((_, cert)) = parse_x509_certificate(&pem.contents)
         {
-            let not_after = cert.validity().not_after;
-            let timestamp = not_after.timestamp();
-            if timestamp < 0 {
-                return None;
-            }
-            return std::time::UNIX_EPOCH
-                .checked_add(std::time::Duration::from_secs(timestamp as u64));
+            let validity = cert.validity();
+            return Some((
+                to_system_time(validity.not_before)?,
+                to_system_time(validity.not_after)?,
+            ));
         }
     }
 
@@ -811,12 +900,18 @@ mod tests {
     }
 
     #[test]
-    fn test_directory_url() {
+    fn test_directory_url_precedence() {
         let mut config = AcmeConfig::default();
-        assert!(config.directory_url().contains("acme-v02"));
+        assert_eq!(config.resolved_directory_url(), LETS_ENCRYPT_PRODUCTION);
 
         config.staging = true;
-        assert!(config.directory_url().contains("staging"));
+        assert_eq!(config.resolved_directory_url(), LETS_ENCRYPT_STAGING);
+
+        config.directory_url = Some("https://acme.zerossl.com/v2/DV90".to_string());
+        assert_eq!(
+            config.resolved_directory_url(),
+            "https://acme.zerossl.com/v2/DV90"
+        );
     }
 
     #[test]
@@ -877,10 +972,10 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_cert_expiry() {
+    fn test_parse_cert_validity() {
         // Test with a sample certificate (this would need a real cert to fully test)
         let invalid_pem = "not a valid certificate";
-        assert!(parse_cert_expiry(invalid_pem).is_none());
+        assert!(parse_cert_validity(invalid_pem).is_none());
     }
 
     // Certificate renewal tests
@@ -903,11 +998,13 @@ mod tests {
             domain: "expiring.example.com".to_string(),
             cert_path: temp.path().join("cert.pem"),
             key_path: temp.path().join("key.pem"),
+            not_before: None,
             expires_at: Some(
                 std::time::SystemTime::now() + std::time::Duration::from_secs(86400 * 15),
             ),
             is_wildcard: false,
             is_self_signed: false,
+            ocsp: None,
         });
 
         // Verify the cert manager sees this cert as needing renewal
@@ -926,11 +1023,13 @@ mod tests {
             domain: "localhost".to_string(),
             cert_path: temp.path().join("cert.pem"),
             key_path: temp.path().join("key.pem"),
+            not_before: None,
             expires_at: Some(
                 std::time::SystemTime::now() + std::time::Duration::from_secs(86400 * 5),
             ),
             is_wildcard: false,
             is_self_signed: true, // Self-signed should be skipped
+            ocsp: None,
         });
 
         // Verify self-signed certs are not in renewal list
@@ -948,11 +1047,13 @@ mod tests {
             domain: "fresh.example.com".to_string(),
             cert_path: temp.path().join("cert.pem"),
             key_path: temp.path().join("key.pem"),
+            not_before: None,
             expires_at: Some(
                 std::time::SystemTime::now() + std::time::Duration::from_secs(86400 * 60),
             ),
             is_wildcard: false,
             is_self_signed: false,
+            ocsp: None,
         });
 
         // Should not need renewal
@@ -977,19 +1078,21 @@ mod tests {
     fn test_acme_config_with_custom_values() {
         let config = AcmeConfig {
             staging: true,
+            directory_url: None,
             email: Some("admin@example.com".to_string()),
             account_dir: PathBuf::from("/custom/path"),
             timeout: Duration::from_secs(600),
             max_attempts: 50,
             check_delay: Duration::from_secs(10),
             dns_provider: Some("cloudflare".to_string()),
+            dns_challenge_provider: None,
             data_dir: PathBuf::from("/custom/data"),
         };
 
         assert!(config.staging);
         assert_eq!(config.email, Some("admin@example.com".to_string()));
         assert_eq!(config.max_attempts, 50);
-        assert!(config.directory_url().contains("staging"));
+        assert!(config.resolved_directory_url().contains("staging"));
         assert_eq!(config.dns_provider, Some("cloudflare".to_string()));
     }
 
@@ -1022,6 +1125,31 @@ mod tests {
         assert!(matches!(result, Err(AcmeError::LegoDns01Failed(_))));
     }
 
+    #[tokio::test]
+    async fn test_wildcard_prefers_dns_challenge_provider_over_lego() {
+        let temp = TempDir::new().unwrap();
+        let cert_manager = Arc::new(CertManager::new(CertManagerConfig {
+            cert_dir: temp.path().join("certs"),
+            ..Default::default()
+        }));
+        let provider = crate::tls::ManualDnsProvider::new(Arc::new(|_, _| {
+            panic!("no TXT record should be requested without an ACME account")
+        }));
+        let acme_config = AcmeConfig {
+            dns_provider: Some("cloudflare".to_string()),
+            dns_challenge_provider: Some(Arc::new(provider)),
+            account_dir: temp.path().join("acme"),
+            data_dir: temp.path().join("data"),
+            ..Default::default()
+        };
+        let acme = AcmeClient::new(acme_config, cert_manager);
+
+        // Goes through the in-process ACME order (which needs an account), not lego
+        let result = acme.request_certificate("*.example.com").await;
+        assert!(matches!(result, Err(AcmeError::NotRegistered)));
+        assert!(!temp.path().join("data").join("lego").exists());
+    }
+
     #[test]
     fn test_challenge_handler_extracts_token() {
         let tokens: ChallengeTokens = Arc::new(RwLock::new(HashMap::new()));
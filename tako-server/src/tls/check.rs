@@ -0,0 +1,149 @@
+//! Certificate pair validation - inspect a PEM cert/key without installing it
+
+use super::CertError;
+use openssl::pkey::PKey;
+use openssl::x509::X509;
+use serde::Serialize;
+use x509_parser::prelude::*;
+
+/// Summary of a validated certificate/key pair
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CertCheck {
+    /// Domain that was checked against the certificate SANs
+    pub domain: String,
+    /// Issuer distinguished name
+    pub issuer: String,
+    /// Subject distinguished name
+    pub subject: String,
+    /// DNS names listed in the subjectAltName extension
+    pub sans: Vec<String>,
+    /// Start of the validity window (unix seconds)
+    pub not_before: i64,
+    /// End of the validity window (unix seconds)
+    pub not_after: i64,
+}
+
+/// Parse a PEM certificate chain and private key, confirm the key belongs to
+/// the leaf certificate, and confirm `domain` is covered by its SANs.
+pub fn check_cert_pair(
+    cert_pem: &str,
+    key_pem: &str,
+    domain: &str,
+) -> Result<CertCheck, CertError> {
+    let leaf = X509::stack_from_pem(cert_pem.as_bytes())
+        .map_err(|e| CertError::ParseError(format!("invalid certificate PEM: {}", e)))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| CertError::ParseError("No certificate found in PEM data".to_string()))?;
+    let key = PKey::private_key_from_pem(key_pem.as_bytes())
+        .map_err(|e| CertError::ParseError(format!("invalid private key PEM: {}", e)))?;
+
+    let public_key = leaf
+        .public_key()
+        .map_err(|e| CertError::ParseError(e.to_string()))?;
+    if !public_key.public_eq(&key) {
+        return Err(CertError::LoadError(
+            "private key does not match certificate".to_string(),
+        ));
+    }
+
+    let der = leaf
+        .to_der()
+        .map_err(|e| CertError::ParseError(e.to_string()))?;
+    let (_, cert) =
+        X509Certificate::from_der(&der).map_err(|e| CertError::ParseError(e.to_string()))?;
+
+    let sans: Vec<String> = cert
+        .subject_alternative_name()
+        .map_err(|e| CertError::ParseError(e.to_string()))?
+        .map(|ext| {
+            ext.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if !sans.iter().any(|san| san_covers_domain(san, domain)) {
+        return Err(CertError::LoadError(format!(
+            "domain '{}' is not listed in certificate SANs ({})",
+            domain,
+            sans.join(", ")
+        )));
+    }
+
+    Ok(CertCheck {
+        domain: domain.to_string(),
+        issuer: cert.issuer().to_string(),
+        subject: cert.subject().to_string(),
+        sans,
+        not_before: cert.validity().not_before.timestamp(),
+        not_after: cert.validity().not_after.timestamp(),
+    })
+}
+
+/// Match a SAN DNS name against a domain (`*.example.com` covers one label).
+fn san_covers_domain(san: &str, domain: &str) -> bool {
+    if san.eq_ignore_ascii_case(domain) {
+        return true;
+    }
+    match (san.strip_prefix("*."), domain.split_once('.')) {
+        (Some(suffix), Some((label, rest))) => {
+            !label.is_empty() && !label.starts_with('*') && rest.eq_ignore_ascii_case(suffix)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, KeyPair};
+
+    fn generate_pair(sans: &[&str]) -> (String, String) {
+        let params =
+            CertificateParams::new(sans.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap();
+        let key = KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+        (cert.pem(), key.serialize_pem())
+    }
+
+    #[test]
+    fn test_check_cert_pair_accepts_matching_pair() {
+        let (cert_pem, key_pem) = generate_pair(&["example.com", "*.example.com"]);
+
+        let check = check_cert_pair(&cert_pem, &key_pem, "api.example.com").unwrap();
+        assert_eq!(check.domain, "api.example.com");
+        assert_eq!(check.sans, vec!["example.com", "*.example.com"]);
+        assert!(check.not_after > check.not_before);
+    }
+
+    #[test]
+    fn test_check_cert_pair_rejects_mismatched_key() {
+        let (cert_pem, _) = generate_pair(&["example.com"]);
+        let (_, other_key_pem) = generate_pair(&["example.com"]);
+
+        let err = check_cert_pair(&cert_pem, &other_key_pem, "example.com").unwrap_err();
+        assert!(err.to_string().contains("does not match"), "got: {err}");
+    }
+
+    #[test]
+    fn test_check_cert_pair_rejects_domain_outside_sans() {
+        let (cert_pem, key_pem) = generate_pair(&["example.com"]);
+
+        let err = check_cert_pair(&cert_pem, &key_pem, "other.com").unwrap_err();
+        assert!(err.to_string().contains("not listed"), "got: {err}");
+    }
+
+    #[test]
+    fn test_san_wildcard_covers_single_label_only() {
+        assert!(san_covers_domain("*.example.com", "api.example.com"));
+        assert!(!san_covers_domain("*.example.com", "example.com"));
+        assert!(!san_covers_domain("*.example.com", "a.b.example.com"));
+        assert!(san_covers_domain("Example.com", "example.COM"));
+    }
+}
@@ -0,0 +1,264 @@
+//! DNS-01 challenge providers for wildcard certificates
+//!
+//! A [`DnsChallengeProvider`] publishes and removes the `_acme-challenge` TXT
+//! records that prove control of a domain. [`DnsTxtRecords`] tracks what was
+//! published during one order so every record is removed afterwards, whether
+//! or not issuance succeeded.
+
+use super::acme::AcmeError;
+use async_trait::async_trait;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Publishes DNS-01 TXT records with a DNS provider.
+#[async_trait]
+pub trait DnsChallengeProvider: Send + Sync {
+    /// Create a TXT record `name` with `value`; return once it is published.
+    async fn set_txt(&self, name: &str, value: &str) -> Result<(), AcmeError>;
+
+    /// Remove the TXT record `name` with `value`.
+    async fn remove_txt(&self, name: &str, value: &str) -> Result<(), AcmeError>;
+}
+
+impl std::fmt::Debug for dyn DnsChallengeProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DnsChallengeProvider")
+    }
+}
+
+/// TXT record name for a domain's DNS-01 challenge.
+///
+/// Wildcard orders are validated against the base domain, so `*.example.com`
+/// uses `_acme-challenge.example.com`.
+pub fn dns01_record_name(domain: &str) -> String {
+    let base = domain.strip_prefix("*.").unwrap_or(domain);
+    format!("_acme-challenge.{}", base.trim_end_matches('.'))
+}
+
+/// TXT records published during one certificate order.
+pub struct DnsTxtRecords {
+    provider: Arc<dyn DnsChallengeProvider>,
+    published: Vec<(String, String)>,
+}
+
+impl DnsTxtRecords {
+    pub fn new(provider: Arc<dyn DnsChallengeProvider>) -> Self {
+        Self {
+            provider,
+            published: Vec::new(),
+        }
+    }
+
+    /// Publish a TXT record and remember it for cleanup.
+    pub async fn publish(&mut self, name: &str, value: &str) -> Result<(), AcmeError> {
+        self.provider.set_txt(name, value).await?;
+        self.published.push((name.to_string(), value.to_string()));
+        Ok(())
+    }
+
+    /// Remove every published record. Failures are logged, not returned, so
+    /// cleanup never masks the outcome of the order itself.
+    pub async fn remove_all(self) {
+        for (name, value) in &self.published {
+            if let Err(e) = self.provider.remove_txt(name, value).await {
+                tracing::warn!(record = %name, "Failed to remove DNS-01 TXT record: {}", e);
+            }
+        }
+    }
+}
+
+/// Asks an operator to confirm a TXT record is published; `true` to proceed.
+pub type DnsConfirmCallback =
+    Arc<dyn Fn(String, String) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync>;
+
+/// Provider for DNS hosts without an API: logs the TXT record to create and
+/// waits for the operator to confirm it through `confirm`.
+pub struct ManualDnsProvider {
+    confirm: DnsConfirmCallback,
+}
+
+impl ManualDnsProvider {
+    pub fn new(confirm: DnsConfirmCallback) -> Self {
+        Self { confirm }
+    }
+
+    /// Give the operator a fixed `wait` to publish each record instead of an
+    /// explicit confirmation (`tako-server --manual-dns01-wait-secs`).
+    pub fn with_wait(wait: Duration) -> Self {
+        Self::new(Arc::new(move |_, _| {
+            Box::pin(async move {
+                tokio::time::sleep(wait).await;
+                true
+            })
+        }))
+    }
+}
+
+#[async_trait]
+impl DnsChallengeProvider for ManualDnsProvider {
+    async fn set_txt(&self, name: &str, value: &str) -> Result<(), AcmeError> {
+        tracing::warn!(
+            record = %name,
+            value = %value,
+            "Create DNS TXT record {} with value \"{}\" and confirm once it has propagated",
+            name,
+            value
+        );
+        if (self.confirm)(name.to_string(), value.to_string()).await {
+            Ok(())
+        } else {
+            Err(AcmeError::DnsProvider(format!(
+                "TXT record {} was not confirmed",
+                name
+            )))
+        }
+    }
+
+    async fn remove_txt(&self, name: &str, value: &str) -> Result<(), AcmeError> {
+        tracing::info!(
+            record = %name,
+            "DNS TXT record {} (\"{}\") is no longer needed and can be removed",
+            name,
+            value
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    #[derive(Default)]
+    struct RecordingProvider {
+        ops: Mutex<Vec<String>>,
+        fail_set_for: Option<String>,
+    }
+
+    #[async_trait]
+    impl DnsChallengeProvider for RecordingProvider {
+        async fn set_txt(&self, name: &str, value: &str) -> Result<(), AcmeError> {
+            if self.fail_set_for.as_deref() == Some(name) {
+                return Err(AcmeError::DnsProvider("api down".to_string()));
+            }
+            self.ops.lock().push(format!("set {name} {value}"));
+            Ok(())
+        }
+
+        async fn remove_txt(&self, name: &str, value: &str) -> Result<(), AcmeError> {
+            self.ops.lock().push(format!("remove {name} {value}"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn record_name_uses_base_domain_for_wildcards() {
+        assert_eq!(
+            dns01_record_name("*.example.com"),
+            "_acme-challenge.example.com"
+        );
+        assert_eq!(
+            dns01_record_name("api.example.com."),
+            "_acme-challenge.api.example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn published_records_are_removed_after_order() {
+        let provider = Arc::new(RecordingProvider::default());
+        let mut records = DnsTxtRecords::new(provider.clone());
+
+        records
+            .publish("_acme-challenge.example.com", "a")
+            .await
+            .unwrap();
+        records
+            .publish("_acme-challenge.example.com", "b")
+            .await
+            .unwrap();
+        records.remove_all().await;
+
+        assert_eq!(
+            *provider.ops.lock(),
+            vec![
+                "set _acme-challenge.example.com a",
+                "set _acme-challenge.example.com b",
+                "remove _acme-challenge.example.com a",
+                "remove _acme-challenge.example.com b",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_publish_is_not_removed() {
+        let provider = Arc::new(RecordingProvider {
+            fail_set_for: Some("_acme-challenge.other.com".to_string()),
+            ..Default::default()
+        });
+        let mut records = DnsTxtRecords::new(provider.clone());
+
+        records
+            .publish("_acme-challenge.example.com", "a")
+            .await
+            .unwrap();
+        assert!(
+            records
+                .publish("_acme-challenge.other.com", "b")
+                .await
+                .is_err()
+        );
+        records.remove_all().await;
+
+        assert_eq!(
+            *provider.ops.lock(),
+            vec![
+                "set _acme-challenge.example.com a",
+                "remove _acme-challenge.example.com a",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn manual_provider_waits_for_confirmation() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_in_callback = seen.clone();
+        let provider = ManualDnsProvider::new(Arc::new(move |name, value| {
+            seen_in_callback.lock().push((name, value));
+            Box::pin(async { true })
+        }));
+
+        provider
+            .set_txt("_acme-challenge.example.com", "token")
+            .await
+            .unwrap();
+        assert_eq!(
+            *seen.lock(),
+            vec![(
+                "_acme-challenge.example.com".to_string(),
+                "token".to_string()
+            )]
+        );
+
+        let declined = ManualDnsProvider::new(Arc::new(|_, _| Box::pin(async { false })));
+        let err = declined
+            .set_txt("_acme-challenge.example.com", "token")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AcmeError::DnsProvider(_)), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn manual_provider_with_wait_proceeds_after_the_wait() {
+        let provider = ManualDnsProvider::with_wait(Duration::from_millis(50));
+        let started = std::time::Instant::now();
+
+        provider
+            .set_txt("_acme-challenge.example.com", "token")
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,97 @@
+//! Certificate expiry alerts raised by the renewal task
+//!
+//! Renewal normally replaces a certificate well before it expires, so a cert
+//! that is still inside the warning window after a renewal pass means
+//! renewals have been failing and an operator should look.
+
+use super::CertInfo;
+use std::time::{Duration, SystemTime};
+
+/// Certificate events from the renewal task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertEvent {
+    /// Certificate expires within the warning window
+    ExpiringSoon { domain: String, days: i64 },
+    /// Renewal attempt failed
+    RenewalFailed { domain: String, error: String },
+    /// Certificate renewed; clears earlier warnings
+    Renewed { domain: String },
+}
+
+/// `ExpiringSoon` events for ACME-managed certs expiring within `window` of `now`.
+pub fn expiring_soon_events(
+    certs: &[CertInfo],
+    now: SystemTime,
+    window: Duration,
+) -> Vec<CertEvent> {
+    certs
+        .iter()
+        .filter(|cert| !cert.is_self_signed)
+        .filter_map(|cert| {
+            let expires_at = cert.expires_at?;
+            if expires_at > now + window {
+                return None;
+            }
+            let days = match expires_at.duration_since(now) {
+                Ok(left) => (left.as_secs() / 86400) as i64,
+                Err(e) => -((e.duration().as_secs() / 86400) as i64),
+            };
+            Some(CertEvent::ExpiringSoon {
+                domain: cert.domain.clone(),
+                days,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const DAY: Duration = Duration::from_secs(86400);
+
+    fn cert(domain: &str, expires_at: SystemTime, is_self_signed: bool) -> CertInfo {
+        CertInfo {
+            domain: domain.to_string(),
+            cert_path: PathBuf::from("/certs/fullchain.pem"),
+            key_path: PathBuf::from("/certs/privkey.pem"),
+            not_before: None,
+            expires_at: Some(expires_at),
+            is_wildcard: false,
+            is_self_signed,
+            ocsp: None,
+        }
+    }
+
+    #[test]
+    fn warns_for_certs_inside_window() {
+        let now = SystemTime::now();
+        let certs = vec![
+            cert("soon.example.com", now + 3 * DAY + DAY / 2, false),
+            cert("fresh.example.com", now + 60 * DAY, false),
+            cert("expired.example.com", now - 2 * DAY, false),
+        ];
+
+        assert_eq!(
+            expiring_soon_events(&certs, now, 14 * DAY),
+            vec![
+                CertEvent::ExpiringSoon {
+                    domain: "soon.example.com".to_string(),
+                    days: 3,
+                },
+                CertEvent::ExpiringSoon {
+                    domain: "expired.example.com".to_string(),
+                    days: -2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_self_signed_certs() {
+        let now = SystemTime::now();
+        let certs = vec![cert("localhost", now + DAY, true)];
+        assert!(expiring_soon_events(&certs, now, 14 * DAY).is_empty());
+    }
+}
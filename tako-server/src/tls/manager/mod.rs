@@ -0,0 +1,504 @@
+//! Certificate manager - handles certificate lifecycle
+
+use super::{OcspStaple, SelfSignedGenerator};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use x509_parser::prelude::*;
+
+/// Errors that can occur during certificate management
+#[derive(Debug, Error)]
+pub enum CertError {
+    #[error("Certificate not found for domain: {0}")]
+    NotFound(String),
+
+    #[error("Certificate expired for domain: {0}")]
+    Expired(String),
+
+    #[error("Failed to load certificate: {0}")]
+    LoadError(String),
+
+    #[error("Failed to parse certificate: {0}")]
+    ParseError(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Information about a certificate
+#[derive(Debug, Clone)]
+pub struct CertInfo {
+    /// Domain the certificate is for
+    pub domain: String,
+    /// Path to certificate file
+    pub cert_path: PathBuf,
+    /// Path to private key file
+    pub key_path: PathBuf,
+    /// When the certificate becomes valid
+    pub not_before: Option<SystemTime>,
+    /// When the certificate expires
+    pub expires_at: Option<SystemTime>,
+    /// Whether this is a wildcard certificate
+    pub is_wildcard: bool,
+    /// Whether this is self-signed (dev mode)
+    pub is_self_signed: bool,
+    /// Cached OCSP response stapled to handshakes
+    pub ocsp: Option<Arc<OcspStaple>>,
+}
+
+impl CertInfo {
+    /// Check if certificate is expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|exp| SystemTime::now() > exp)
+            .unwrap_or(false)
+    }
+
+    /// Check if certificate needs renewal (expires within `margin`)
+    pub fn needs_renewal(&self, margin: Duration) -> bool {
+        self.renewal_decision(SystemTime::now(), margin) != RenewalDecision::NotNeeded
+    }
+
+    /// Evaluate the certificate's validity window against `now` and a safety margin
+    pub fn renewal_decision(&self, now: SystemTime, margin: Duration) -> RenewalDecision {
+        renewal_decision(now, self.not_before, self.expires_at, margin)
+    }
+
+    /// Days until expiry
+    pub fn days_until_expiry(&self) -> Option<i64> {
+        self.expires_at
+            .map(|exp| match exp.duration_since(SystemTime::now()) {
+                Ok(duration) => (duration.as_secs() / 86400) as i64,
+                Err(e) => -(e.duration().as_secs() as i64 / 86400),
+            })
+    }
+}
+
+/// How far the system clock may lag a certificate's `not_before` before it is
+/// treated as skewed rather than ordinary drift.
+const CLOCK_SKEW_TOLERANCE: Duration = Duration::from_secs(60 * 60);
+
+/// Outcome of checking a certificate against the renewal safety margin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenewalDecision {
+    /// Valid for longer than the safety margin
+    NotNeeded,
+    /// Expires within the safety margin (or already expired)
+    Renew,
+    /// System time predates the certificate's validity window, so expiry
+    /// calculations against the local clock cannot be trusted
+    ClockSkew,
+}
+
+/// Decide whether a certificate with the given validity window needs renewal at `now`.
+pub fn renewal_decision(
+    now: SystemTime,
+    not_before: Option<SystemTime>,
+    expires_at: Option<SystemTime>,
+    margin: Duration,
+) -> RenewalDecision {
+    let Some(expires_at) = expires_at else {
+        return RenewalDecision::NotNeeded;
+    };
+
+    if let Some(not_before) = not_before
+        && now + CLOCK_SKEW_TOLERANCE < not_before
+    {
+        return RenewalDecision::ClockSkew;
+    }
+
+    if now + margin > expires_at {
+        RenewalDecision::Renew
+    } else {
+        RenewalDecision::NotNeeded
+    }
+}
+
+/// Certificate manager configuration
+#[derive(Debug, Clone)]
+pub struct CertManagerConfig {
+    /// Directory to store certificates
+    pub cert_dir: PathBuf,
+    /// How often to check for certificate renewal
+    pub check_interval: Duration,
+    /// Renew certificates this many days before expiry
+    pub renewal_days: u32,
+    /// Skip renewal when the system clock predates a certificate's validity window
+    pub refuse_renewal_on_clock_skew: bool,
+}
+
+impl CertManagerConfig {
+    /// Safety margin before expiry at which renewal starts
+    pub fn renewal_margin(&self) -> Duration {
+        Duration::from_secs(u64::from(self.renewal_days) * 24 * 60 * 60)
+    }
+}
+
+impl Default for CertManagerConfig {
+    fn default() -> Self {
+        Self {
+            cert_dir: PathBuf::from("/opt/tako/certs"),
+            check_interval: Duration::from_secs(24 * 60 * 60), // 24 hours
+            renewal_days: 30,
+            refuse_renewal_on_clock_skew: true,
+        }
+    }
+}
+
+/// Manages certificates for all domains
+pub struct CertManager {
+    config: CertManagerConfig,
+    /// Cached certificate info by domain
+    certs: RwLock<HashMap<String, CertInfo>>,
+}
+
+impl CertManager {
+    pub fn new(config: CertManagerConfig) -> Self {
+        Self {
+            config,
+            certs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Initialize by loading existing certificates
+    pub fn init(&self) -> Result<(), CertError> {
+        std::fs::create_dir_all(&self.config.cert_dir)?;
+        self.load_all_certs()?;
+        Ok(())
+    }
+
+    /// Load all certificates from disk
+    fn load_all_certs(&self) -> Result<(), CertError> {
+        let mut certs = self.certs.write();
+
+        if !self.config.cert_dir.exists() {
+            return Ok(());
+        }
+
+        for entry in std::fs::read_dir(&self.config.cert_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if path.is_dir() {
+                let domain = path.file_name().unwrap().to_string_lossy().to_string();
+                if let Ok(cert_info) = self.load_cert_info(&domain) {
+                    certs.insert(domain, cert_info);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load certificate info for a domain
+    fn load_cert_info(&self, domain: &str) -> Result<CertInfo, CertError> {
+        let domain_dir = self.config.cert_dir.join(domain);
+        let cert_path = domain_dir.join("fullchain.pem");
+        let key_path = domain_dir.join("privkey.pem");
+
+        if !cert_path.exists() || !key_path.exists() {
+            return Err(CertError::NotFound(domain.to_string()));
+        }
+
+        // Read PEM once and derive all certificate metadata from the same bytes
+        let pem_data = std::fs::read(&cert_path)?;
+        let (not_before, expires_at) = match Self::parse_cert_validity_from_bytes(&pem_data) {
+            Ok((not_before, expires_at)) => (Some(not_before), Some(expires_at)),
+            Err(_) => (None, None),
+        };
+        let is_self_signed = Self::check_self_signed_from_bytes(&pem_data).unwrap_or(false);
+
+        Ok(CertInfo {
+            domain: domain.to_string(),
+            cert_path,
+            key_path,
+            not_before,
+            expires_at,
+            is_wildcard: domain.starts_with("*."),
+            is_self_signed,
+            ocsp: None,
+        })
+    }
+
+    /// Parse certificate validity window (`not_before`, `not_after`) from PEM bytes
+    fn parse_cert_validity_from_bytes(
+        pem_data: &[u8],
+    ) -> Result<(SystemTime, SystemTime), CertError> {
+        for pem in Pem::iter_from_buffer(pem_data) {
+            let pem = pem.map_err(|e| CertError::ParseError(e.to_string()))?;
+
+            if pem.label == "CERTIFICATE" {
+                let (_, cert) = X509Certificate::from_der(&pem.contents)
+                    .map_err(|e| CertError::ParseError(e.to_string()))?;
+
+                let validity = cert.validity();
+                return Ok((
+                    asn1_to_system_time(validity.not_before)?,
+                    asn1_to_system_time(validity.not_after)?,
+                ));
+            }
+        }
+
+        Err(CertError::ParseError(
+            "No certificate found in PEM file".to_string(),
+        ))
+    }
+
+    /// Check if certificate is self-signed from PEM bytes
+    fn check_self_signed_from_bytes(pem_data: &[u8]) -> Result<bool, CertError> {
+        for pem in Pem::iter_from_buffer(pem_data) {
+            let pem = pem.map_err(|e| CertError::ParseError(e.to_string()))?;
+
+            if pem.label == "CERTIFICATE" {
+                let (_, cert) = X509Certificate::from_der(&pem.contents)
+                    .map_err(|e| CertError::ParseError(e.to_string()))?;
+
+                // Self-signed certificates have the same issuer and subject
+                return Ok(cert.issuer() == cert.subject());
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Get certificate for a domain
+    pub fn get_cert(&self, domain: &str) -> Option<CertInfo> {
+        let certs = self.certs.read();
+        certs.get(domain).cloned()
+    }
+
+    /// Get certificate for a domain, falling back to wildcard
+    pub fn get_cert_for_host(&self, host: &str) -> Option<CertInfo> {
+        let certs = self.certs.read();
+
+        // Try exact match first
+        if let Some(cert) = certs.get(host) {
+            return Some(cert.clone());
+        }
+
+        // Try wildcard match
+        if let Some(dot_pos) = host.find('.') {
+            let wildcard = format!("*.{}", &host[dot_pos + 1..]);
+            if let Some(cert) = certs.get(&wildcard) {
+                return Some(cert.clone());
+            }
+        }
+
+        None
+    }
+
+    /// Add a certificate
+    pub fn add_cert(&self, cert_info: CertInfo) {
+        let mut certs = self.certs.write();
+        certs.insert(cert_info.domain.clone(), cert_info);
+    }
+
+    /// Remove a certificate
+    pub fn remove_cert(&self, domain: &str) -> Option<CertInfo> {
+        let mut certs = self.certs.write();
+        certs.remove(domain)
+    }
+
+    /// List all certificates
+    pub fn list_certs(&self) -> Vec<CertInfo> {
+        let certs = self.certs.read();
+        certs.values().cloned().collect()
+    }
+
+    /// Get certificates that need renewal
+    pub fn get_certs_needing_renewal(&self) -> Vec<CertInfo> {
+        self.certs_needing_renewal_at(SystemTime::now())
+    }
+
+    fn certs_needing_renewal_at(&self, now: SystemTime) -> Vec<CertInfo> {
+        let margin = self.config.renewal_margin();
+        let certs = self.certs.read();
+        certs
+            .values()
+            .filter(|c| !c.is_self_signed)
+            .filter(|c| match c.renewal_decision(now, margin) {
+                RenewalDecision::NotNeeded => false,
+                RenewalDecision::Renew => true,
+                RenewalDecision::ClockSkew => {
+                    tracing::warn!(
+                        domain = %c.domain,
+                        "System clock is earlier than the certificate's validity start; check time sync"
+                    );
+                    if self.config.refuse_renewal_on_clock_skew {
+                        false
+                    } else {
+                        c.expires_at.is_some_and(|exp| now + margin > exp)
+                    }
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Get CA-issued certificates whose OCSP staple is missing or due for refresh
+    pub fn certs_needing_ocsp_refresh(&self, now: SystemTime) -> Vec<CertInfo> {
+        let certs = self.certs.read();
+        certs
+            .values()
+            .filter(|c| !c.is_self_signed && !c.is_expired())
+            .filter(|c| c.ocsp.as_ref().is_none_or(|s| s.next_fetch_at() <= now))
+            .cloned()
+            .collect()
+    }
+
+    /// Attach an OCSP staple fetched for `cert`.
+    ///
+    /// Ignored when the domain's certificate was replaced (e.g. renewed) while
+    /// the response was in flight, since it would not match the new cert.
+    pub fn set_ocsp_staple(&self, cert: &CertInfo, staple: OcspStaple) -> bool {
+        let mut certs = self.certs.write();
+        match certs.get_mut(&cert.domain) {
+            Some(current)
+                if current.cert_path == cert.cert_path
+                    && current.not_before == cert.not_before
+                    && current.expires_at == cert.expires_at =>
+            {
+                current.ocsp = Some(Arc::new(staple));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get certificate directory
+    pub fn cert_dir(&self) -> &Path {
+        &self.config.cert_dir
+    }
+
+    /// Get domain certificate directory
+    pub fn domain_cert_dir(&self, domain: &str) -> PathBuf {
+        self.config.cert_dir.join(domain)
+    }
+
+    /// Install an operator-supplied certificate chain and key for `domain`.
+    ///
+    /// The pair must match, cover `domain`, and not be expired. Files are
+    /// written into the standard domain layout and the cert is served on the
+    /// next handshake without a restart.
+    pub fn import_cert(
+        &self,
+        domain: &str,
+        cert_pem: &str,
+        key_pem: &str,
+    ) -> Result<CertInfo, CertError> {
+        let domain = domain.trim();
+        if !is_valid_cert_domain(domain) {
+            return Err(CertError::LoadError(format!(
+                "invalid certificate domain '{}'",
+                domain
+            )));
+        }
+
+        let check = super::check_cert_pair(cert_pem, key_pem, domain)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        if check.not_after <= now {
+            return Err(CertError::Expired(domain.to_string()));
+        }
+
+        let domain_dir = self.domain_cert_dir(domain);
+        std::fs::create_dir_all(&domain_dir)?;
+        write_file_atomically(&domain_dir.join("privkey.pem"), key_pem.as_bytes(), true)?;
+        write_file_atomically(
+            &domain_dir.join("fullchain.pem"),
+            cert_pem.as_bytes(),
+            false,
+        )?;
+
+        let cert_info = self.load_cert_info(domain)?;
+        self.add_cert(cert_info.clone());
+        Ok(cert_info)
+    }
+
+    /// Get or create a self-signed certificate stored in the standard domain layout.
+    ///
+    /// This keeps private/local domains usable over HTTPS even when ACME cannot issue for them.
+    pub fn get_or_create_self_signed_cert(&self, domain: &str) -> Result<CertInfo, CertError> {
+        let domain = domain.trim();
+        if domain.is_empty() {
+            return Err(CertError::LoadError("domain must not be empty".to_string()));
+        }
+
+        if let Some(existing) = self.get_cert(domain) {
+            return Ok(existing);
+        }
+
+        let domain_dir = self.domain_cert_dir(domain);
+        let cert_path = domain_dir.join("fullchain.pem");
+        let key_path = domain_dir.join("privkey.pem");
+
+        if cert_path.exists() && key_path.exists() {
+            let cert_info = self.load_cert_info(domain)?;
+            self.add_cert(cert_info.clone());
+            return Ok(cert_info);
+        }
+
+        let generator = SelfSignedGenerator::new(self.config.cert_dir.clone());
+        let generated = generator
+            .get_or_create_for_domain(domain)
+            .map_err(|e| CertError::LoadError(format!("self-signed generation failed: {}", e)))?;
+
+        std::fs::create_dir_all(&domain_dir)?;
+        std::fs::copy(&generated.cert_path, &cert_path)?;
+        std::fs::copy(&generated.key_path, &key_path)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        let cert_info = self.load_cert_info(domain)?;
+        self.add_cert(cert_info.clone());
+        Ok(cert_info)
+    }
+}
+
+/// Hostname or `*.`-prefixed wildcard; also guards its use as a directory name.
+fn is_valid_cert_domain(domain: &str) -> bool {
+    let host = domain.strip_prefix("*.").unwrap_or(domain);
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Write via a temp file and rename so a concurrent handshake never reads a
+/// half-written PEM.
+fn write_file_atomically(path: &Path, contents: &[u8], private: bool) -> Result<(), CertError> {
+    let tmp_path = path.with_extension("pem.tmp");
+    std::fs::write(&tmp_path, contents)?;
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Convert an X.509 time to `SystemTime`, rejecting pre-epoch values
+fn asn1_to_system_time(time: ASN1Time) -> Result<SystemTime, CertError> {
+    let timestamp = time.timestamp();
+    if timestamp < 0 {
+        return Err(CertError::ParseError(
+            "certificate has negative validity timestamp".to_string(),
+        ));
+    }
+    Ok(UNIX_EPOCH + Duration::from_secs(timestamp as u64))
+}
+
+#[cfg(test)]
+mod tests;
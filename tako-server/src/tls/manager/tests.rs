@@ -0,0 +1,746 @@
+use super::*;
+use tempfile::TempDir;
+
+const DEFAULT_MARGIN: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[test]
+fn test_cert_info_is_expired() {
+    let cert = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() - Duration::from_secs(86400)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(cert.is_expired());
+}
+
+#[test]
+fn test_cert_info_not_expired() {
+    let cert = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 60)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(!cert.is_expired());
+}
+
+#[test]
+fn test_cert_info_needs_renewal() {
+    let cert = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 20)), // 20 days
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(cert.needs_renewal(DEFAULT_MARGIN));
+}
+
+#[test]
+fn test_cert_manager_creation() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+    manager.init().unwrap();
+}
+
+#[test]
+fn test_add_and_get_cert() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+
+    let cert = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: temp.path().join("cert.pem"),
+        key_path: temp.path().join("key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 90)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+
+    manager.add_cert(cert.clone());
+
+    let retrieved = manager.get_cert("example.com").unwrap();
+    assert_eq!(retrieved.domain, "example.com");
+}
+
+#[test]
+fn test_wildcard_fallback() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+
+    let cert = CertInfo {
+        domain: "*.example.com".to_string(),
+        cert_path: temp.path().join("cert.pem"),
+        key_path: temp.path().join("key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 90)),
+        is_wildcard: true,
+        is_self_signed: false,
+        ocsp: None,
+    };
+
+    manager.add_cert(cert);
+
+    // Should find wildcard for subdomain
+    let retrieved = manager.get_cert_for_host("api.example.com").unwrap();
+    assert_eq!(retrieved.domain, "*.example.com");
+
+    // Should not find for different domain
+    assert!(manager.get_cert_for_host("other.com").is_none());
+}
+
+#[test]
+fn test_list_certs() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+
+    manager.add_cert(CertInfo {
+        domain: "a.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: None,
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+
+    manager.add_cert(CertInfo {
+        domain: "b.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: None,
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+
+    let certs = manager.list_certs();
+    assert_eq!(certs.len(), 2);
+}
+
+// Certificate renewal tests
+
+#[test]
+fn test_cert_does_not_need_renewal_when_far_from_expiry() {
+    let cert = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 60)), // 60 days
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(!cert.needs_renewal(DEFAULT_MARGIN));
+}
+
+#[test]
+fn test_cert_needs_renewal_at_30_day_boundary() {
+    // Exactly 30 days - should need renewal
+    let cert_at_boundary = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 30)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    // At exactly 30 days, now + 30 days > exp is false (equal), so doesn't need renewal
+    // But 29 days should trigger renewal
+    let cert_29_days = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 29)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(cert_29_days.needs_renewal(DEFAULT_MARGIN));
+
+    // 31 days should not need renewal
+    let cert_31_days = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 31)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(!cert_31_days.needs_renewal(DEFAULT_MARGIN));
+    let _ = cert_at_boundary; // silence unused warning
+}
+
+#[test]
+fn test_expired_cert_needs_renewal() {
+    let cert = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() - Duration::from_secs(86400)), // Expired yesterday
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(cert.is_expired());
+    assert!(cert.needs_renewal(DEFAULT_MARGIN));
+}
+
+#[test]
+fn test_renewal_decision_across_expiry_offsets_and_margins() {
+    const DAY: u64 = 86400;
+    let now = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+    let not_before = Some(now - Duration::from_secs(60 * DAY));
+
+    // (days until expiry, margin in days, expected)
+    let cases: &[(i64, u64, RenewalDecision)] = &[
+        (60, 30, RenewalDecision::NotNeeded),
+        (31, 30, RenewalDecision::NotNeeded),
+        (30, 30, RenewalDecision::NotNeeded),
+        (29, 30, RenewalDecision::Renew),
+        (0, 30, RenewalDecision::Renew),
+        (-1, 30, RenewalDecision::Renew),
+        (10, 7, RenewalDecision::NotNeeded),
+        (6, 7, RenewalDecision::Renew),
+        (1, 0, RenewalDecision::NotNeeded),
+        (-1, 0, RenewalDecision::Renew),
+        (45, 60, RenewalDecision::Renew),
+    ];
+
+    for &(days_left, margin_days, expected) in cases {
+        let expires_at = if days_left >= 0 {
+            now + Duration::from_secs(days_left as u64 * DAY)
+        } else {
+            now - Duration::from_secs(days_left.unsigned_abs() * DAY)
+        };
+        let decision = renewal_decision(
+            now,
+            not_before,
+            Some(expires_at),
+            Duration::from_secs(margin_days * DAY),
+        );
+        assert_eq!(
+            decision, expected,
+            "expires in {days_left}d with {margin_days}d margin"
+        );
+    }
+}
+
+#[test]
+fn test_renewal_decision_without_expiry_is_not_needed() {
+    let now = SystemTime::now();
+    assert_eq!(
+        renewal_decision(now, None, None, DEFAULT_MARGIN),
+        RenewalDecision::NotNeeded
+    );
+}
+
+#[test]
+fn test_renewal_decision_flags_clock_before_validity_window() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+    let not_before = now + Duration::from_secs(86400 * 365);
+    let expires_at = not_before + Duration::from_secs(86400 * 90);
+
+    assert_eq!(
+        renewal_decision(now, Some(not_before), Some(expires_at), DEFAULT_MARGIN),
+        RenewalDecision::ClockSkew
+    );
+}
+
+#[test]
+fn test_renewal_decision_tolerates_small_drift_before_not_before() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+    let not_before = now + Duration::from_secs(5 * 60);
+    let expires_at = not_before + Duration::from_secs(86400 * 90);
+
+    assert_eq!(
+        renewal_decision(now, Some(not_before), Some(expires_at), DEFAULT_MARGIN),
+        RenewalDecision::NotNeeded
+    );
+}
+
+#[test]
+fn test_get_certs_needing_renewal_uses_configured_margin() {
+    let temp = TempDir::new().unwrap();
+    let manager = CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        renewal_days: 7,
+        ..Default::default()
+    });
+
+    manager.add_cert(CertInfo {
+        domain: "soon.example.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 5)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+    manager.add_cert(CertInfo {
+        domain: "later.example.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 20)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+
+    let needing_renewal = manager.get_certs_needing_renewal();
+    assert_eq!(needing_renewal.len(), 1);
+    assert_eq!(needing_renewal[0].domain, "soon.example.com");
+}
+
+#[test]
+fn test_clock_skew_refuses_renewal_unless_disabled() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+    // Clock reads a year before issuance and the cert looks expired against
+    // a margin larger than its whole lifetime.
+    let skewed = CertInfo {
+        domain: "skewed.example.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: Some(now + Duration::from_secs(86400 * 365)),
+        expires_at: Some(now + Duration::from_secs(86400 * 380)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+
+    let temp = TempDir::new().unwrap();
+    let refusing = CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        renewal_days: 400,
+        ..Default::default()
+    });
+    refusing.add_cert(skewed.clone());
+    assert!(refusing.certs_needing_renewal_at(now).is_empty());
+
+    let permissive = CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        renewal_days: 400,
+        refuse_renewal_on_clock_skew: false,
+        ..Default::default()
+    });
+    permissive.add_cert(skewed);
+    assert_eq!(permissive.certs_needing_renewal_at(now).len(), 1);
+}
+
+#[test]
+fn test_days_until_expiry_calculation() {
+    // Test positive days
+    let cert_future = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 45)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    let days = cert_future.days_until_expiry().unwrap();
+    assert!((44..=45).contains(&days), "Expected ~45 days, got {}", days);
+
+    // Test negative days (expired)
+    let cert_past = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: Some(SystemTime::now() - Duration::from_secs(86400 * 5)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    let days = cert_past.days_until_expiry().unwrap();
+    assert!((-6..=-4).contains(&days), "Expected ~-5 days, got {}", days);
+
+    // Test None expiry
+    let cert_no_expiry = CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::from("/tmp/cert.pem"),
+        key_path: PathBuf::from("/tmp/key.pem"),
+        not_before: None,
+        expires_at: None,
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    };
+    assert!(cert_no_expiry.days_until_expiry().is_none());
+}
+
+#[test]
+fn test_get_certs_needing_renewal_filters_self_signed() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+
+    // Self-signed cert expiring soon - should NOT be in renewal list
+    manager.add_cert(CertInfo {
+        domain: "dev.local".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 10)),
+        is_wildcard: false,
+        is_self_signed: true,
+        ocsp: None,
+    });
+
+    // Real cert expiring soon - SHOULD be in renewal list
+    manager.add_cert(CertInfo {
+        domain: "prod.example.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 10)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+
+    // Real cert not expiring soon - should NOT be in renewal list
+    manager.add_cert(CertInfo {
+        domain: "other.example.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 60)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+
+    let needing_renewal = manager.get_certs_needing_renewal();
+    assert_eq!(needing_renewal.len(), 1);
+    assert_eq!(needing_renewal[0].domain, "prod.example.com");
+}
+
+#[test]
+fn test_get_certs_needing_renewal_empty_when_all_fresh() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+
+    // All certs have plenty of time
+    for i in 0..5 {
+        manager.add_cert(CertInfo {
+            domain: format!("domain{}.com", i),
+            cert_path: PathBuf::new(),
+            key_path: PathBuf::new(),
+            not_before: None,
+            expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 90)),
+            is_wildcard: false,
+            is_self_signed: false,
+            ocsp: None,
+        });
+    }
+
+    let needing_renewal = manager.get_certs_needing_renewal();
+    assert!(needing_renewal.is_empty());
+}
+
+#[test]
+fn test_get_or_create_self_signed_cert_creates_domain_layout_and_caches_cert() {
+    let temp = TempDir::new().unwrap();
+    let cert_dir = temp.path().to_path_buf();
+    let config = CertManagerConfig {
+        cert_dir: cert_dir.clone(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+    manager.init().unwrap();
+
+    let domain = "tako-bun-server.orb.local";
+    let cert = manager.get_or_create_self_signed_cert(domain).unwrap();
+
+    assert_eq!(cert.domain, domain);
+    assert!(cert.is_self_signed);
+    assert_eq!(cert.cert_path, cert_dir.join(domain).join("fullchain.pem"));
+    assert_eq!(cert.key_path, cert_dir.join(domain).join("privkey.pem"));
+    assert!(cert.cert_path.exists());
+    assert!(cert.key_path.exists());
+    let (not_before, expires_at) = (cert.not_before.unwrap(), cert.expires_at.unwrap());
+    assert!(not_before < expires_at);
+
+    let cached = manager.get_cert_for_host(domain).unwrap();
+    assert_eq!(cached.domain, domain);
+    assert!(cached.is_self_signed);
+}
+
+#[test]
+fn test_get_or_create_self_signed_cert_is_discoverable_after_restart() {
+    let temp = TempDir::new().unwrap();
+    let cert_dir = temp.path().to_path_buf();
+    let config = CertManagerConfig {
+        cert_dir: cert_dir.clone(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config.clone());
+    manager.init().unwrap();
+    manager
+        .get_or_create_self_signed_cert("tako-bun-server.orb.local")
+        .unwrap();
+
+    let reloaded = CertManager::new(config);
+    reloaded.init().unwrap();
+    let cert = reloaded
+        .get_cert_for_host("tako-bun-server.orb.local")
+        .expect("cert should load from persisted cert dir");
+    assert!(cert.is_self_signed);
+    assert_eq!(cert.domain, "tako-bun-server.orb.local");
+}
+
+#[test]
+fn test_remove_cert() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+
+    manager.add_cert(CertInfo {
+        domain: "example.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: None,
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    });
+
+    assert!(manager.get_cert("example.com").is_some());
+
+    let removed = manager.remove_cert("example.com");
+    assert!(removed.is_some());
+    assert_eq!(removed.unwrap().domain, "example.com");
+
+    assert!(manager.get_cert("example.com").is_none());
+}
+
+#[test]
+fn test_wildcard_cert_renewal_detection() {
+    let temp = TempDir::new().unwrap();
+    let config = CertManagerConfig {
+        cert_dir: temp.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = CertManager::new(config);
+
+    // Wildcard cert expiring soon
+    manager.add_cert(CertInfo {
+        domain: "*.example.com".to_string(),
+        cert_path: PathBuf::new(),
+        key_path: PathBuf::new(),
+        not_before: None,
+        expires_at: Some(SystemTime::now() + Duration::from_secs(86400 * 15)),
+        is_wildcard: true,
+        is_self_signed: false,
+        ocsp: None,
+    });
+
+    let needing_renewal = manager.get_certs_needing_renewal();
+    assert_eq!(needing_renewal.len(), 1);
+    assert!(needing_renewal[0].is_wildcard);
+    assert_eq!(needing_renewal[0].domain, "*.example.com");
+}
+
+fn generate_pair(sans: &[&str]) -> (String, String) {
+    let params =
+        rcgen::CertificateParams::new(sans.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+            .unwrap();
+    let key = rcgen::KeyPair::generate().unwrap();
+    let cert = params.self_signed(&key).unwrap();
+    (cert.pem(), key.serialize_pem())
+}
+
+fn temp_manager(temp: &TempDir) -> CertManager {
+    let manager = CertManager::new(CertManagerConfig {
+        cert_dir: temp.path().join("certs"),
+        ..Default::default()
+    });
+    manager.init().unwrap();
+    manager
+}
+
+#[test]
+fn test_import_cert_is_served_without_reload() {
+    let temp = TempDir::new().unwrap();
+    let manager = temp_manager(&temp);
+    let (cert_pem, key_pem) = generate_pair(&["shop.example.com"]);
+
+    let imported = manager
+        .import_cert("shop.example.com", &cert_pem, &key_pem)
+        .unwrap();
+
+    let served = manager.get_cert_for_host("shop.example.com").unwrap();
+    assert_eq!(served.cert_path, imported.cert_path);
+    assert!(served.expires_at.is_some());
+    assert_eq!(
+        std::fs::read_to_string(&served.cert_path).unwrap(),
+        cert_pem
+    );
+    assert_eq!(std::fs::read_to_string(&served.key_path).unwrap(), key_pem);
+}
+
+#[test]
+fn test_import_cert_rejects_mismatched_key() {
+    let temp = TempDir::new().unwrap();
+    let manager = temp_manager(&temp);
+    let (cert_pem, _) = generate_pair(&["shop.example.com"]);
+    let (_, other_key_pem) = generate_pair(&["shop.example.com"]);
+
+    let err = manager
+        .import_cert("shop.example.com", &cert_pem, &other_key_pem)
+        .unwrap_err();
+
+    assert!(err.to_string().contains("does not match"), "got: {err}");
+    assert!(manager.get_cert_for_host("shop.example.com").is_none());
+    assert!(!manager.domain_cert_dir("shop.example.com").exists());
+}
+
+#[test]
+fn test_import_cert_rejects_expired_cert() {
+    let temp = TempDir::new().unwrap();
+    let manager = temp_manager(&temp);
+    let mut params = rcgen::CertificateParams::new(vec!["old.example.com".to_string()]).unwrap();
+    params.not_before = rcgen::date_time_ymd(2020, 1, 1);
+    params.not_after = rcgen::date_time_ymd(2021, 1, 1);
+    let key = rcgen::KeyPair::generate().unwrap();
+    let cert = params.self_signed(&key).unwrap();
+
+    let err = manager
+        .import_cert("old.example.com", &cert.pem(), &key.serialize_pem())
+        .unwrap_err();
+
+    assert!(matches!(err, CertError::Expired(_)), "got: {err}");
+}
+
+#[test]
+fn test_import_cert_rejects_path_like_domain() {
+    let temp = TempDir::new().unwrap();
+    let manager = temp_manager(&temp);
+    let (cert_pem, key_pem) = generate_pair(&["example.com"]);
+
+    let err = manager
+        .import_cert("../example.com", &cert_pem, &key_pem)
+        .unwrap_err();
+
+    assert!(
+        err.to_string().contains("invalid certificate domain"),
+        "got: {err}"
+    );
+}
+
+fn ca_cert(domain: &str) -> CertInfo {
+    CertInfo {
+        domain: domain.to_string(),
+        cert_path: PathBuf::from(format!("/certs/{domain}/fullchain.pem")),
+        key_path: PathBuf::from(format!("/certs/{domain}/privkey.pem")),
+        not_before: Some(SystemTime::now() - Duration::from_secs(86400)),
+        expires_at: Some(SystemTime::now() + Duration::from_secs(60 * 86400)),
+        is_wildcard: false,
+        is_self_signed: false,
+        ocsp: None,
+    }
+}
+
+#[test]
+fn test_ocsp_refresh_selects_unstapled_and_due_certs() {
+    let manager = CertManager::new(CertManagerConfig::default());
+    let now = SystemTime::now();
+    let fresh = ca_cert("fresh.example.com");
+    let due = ca_cert("due.example.com");
+    manager.add_cert(fresh.clone());
+    manager.add_cert(due.clone());
+    manager.add_cert(ca_cert("missing.example.com"));
+    manager.add_cert(CertInfo {
+        is_self_signed: true,
+        ..ca_cert("local.example.com")
+    });
+
+    let staple = |fetched_at: SystemTime| OcspStaple {
+        der: vec![1],
+        fetched_at,
+        next_update: Some(fetched_at + Duration::from_secs(4 * 86400)),
+    };
+    assert!(manager.set_ocsp_staple(&fresh, staple(now)));
+    assert!(manager.set_ocsp_staple(&due, staple(now - Duration::from_secs(3 * 86400))));
+
+    let mut due: Vec<String> = manager
+        .certs_needing_ocsp_refresh(now)
+        .into_iter()
+        .map(|c| c.domain)
+        .collect();
+    due.sort();
+    assert_eq!(due, vec!["due.example.com", "missing.example.com"]);
+}
+
+#[test]
+fn test_set_ocsp_staple_ignores_replaced_cert() {
+    let manager = CertManager::new(CertManagerConfig::default());
+    let original = ca_cert("example.com");
+    manager.add_cert(CertInfo {
+        expires_at: Some(SystemTime::now() + Duration::from_secs(90 * 86400)),
+        ..original.clone()
+    });
+
+    let staple = OcspStaple {
+        der: vec![1],
+        fetched_at: SystemTime::now(),
+        next_update: None,
+    };
+    assert!(!manager.set_ocsp_staple(&original, staple));
+    assert!(manager.get_cert("example.com").unwrap().ocsp.is_none());
+}
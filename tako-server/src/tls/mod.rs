@@ -2,20 +2,38 @@
 //!
 //! Handles:
 //! - ACME (Let's Encrypt) certificate issuance via HTTP-01 challenge
+//! - DNS-01 challenge providers for wildcard certificates
 //! - Certificate lifecycle management with automatic renewal
+//! - Expiry alerts when renewals keep failing
 //! - Self-signed certificates for development
+//! - Validation of operator-supplied cert/key pairs
+//! - OCSP response fetching for stapling
 //! - SNI-based certificate selection
 
 mod acme;
+mod check;
+mod dns01;
+mod expiry;
 mod manager;
+mod ocsp;
 mod self_signed;
 mod sni;
 
 #[allow(unused_imports)]
 pub use acme::{AcmeClient, AcmeConfig, AcmeError, ChallengeHandler, ChallengeTokens};
 #[allow(unused_imports)]
+pub use check::{CertCheck, check_cert_pair};
+#[allow(unused_imports)]
+pub use dns01::{
+    DnsChallengeProvider, DnsConfirmCallback, DnsTxtRecords, ManualDnsProvider, dns01_record_name,
+};
+#[allow(unused_imports)]
+pub use expiry::{CertEvent, expiring_soon_events};
+#[allow(unused_imports)]
 pub use manager::{CertError, CertInfo, CertManager, CertManagerConfig};
 #[allow(unused_imports)]
+pub use ocsp::{OcspError, OcspStaple, fetch_staple, next_fetch_time};
+#[allow(unused_imports)]
 pub use self_signed::{SelfSignedCert, SelfSignedError, SelfSignedGenerator};
 #[allow(unused_imports)]
-pub use sni::{SniCertResolver, create_sni_callbacks};
+pub use sni::{SniCertResolver, create_sni_callbacks, enable_ocsp_stapling};
@@ -0,0 +1,253 @@
+//! OCSP stapling - fetches and validates OCSP responses for managed certificates
+//!
+//! Responses are fetched from the responder named in the leaf certificate's
+//! Authority Information Access extension, cached on the `CertInfo`, and
+//! stapled to TLS handshakes by the SNI resolver.
+
+use openssl::hash::MessageDigest;
+use openssl::ocsp::{OcspCertId, OcspCertStatus, OcspRequest, OcspResponse, OcspResponseStatus};
+use openssl::x509::X509;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Refresh interval used when a response carries no `nextUpdate`.
+const DEFAULT_REFRESH: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Never refetch sooner than this after a successful fetch.
+const MIN_REFRESH: Duration = Duration::from_secs(60 * 60);
+
+/// Tolerated clock skew when validating `thisUpdate`/`nextUpdate`.
+const VALIDITY_LEEWAY_SECS: u32 = 5 * 60;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum OcspError {
+    #[error("Failed to read certificate: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Certificate chain has no issuer certificate")]
+    MissingIssuer,
+
+    #[error("Certificate has no OCSP responder URL")]
+    NoResponder,
+
+    #[error("OpenSSL error: {0}")]
+    OpenSsl(#[from] openssl::error::ErrorStack),
+
+    #[error("OCSP request failed: {0}")]
+    Http(String),
+
+    #[error("Invalid OCSP response: {0}")]
+    InvalidResponse(String),
+}
+
+/// A validated OCSP response ready to be stapled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcspStaple {
+    /// DER-encoded OCSP response
+    pub der: Vec<u8>,
+    /// When the response was fetched
+    pub fetched_at: SystemTime,
+    /// Responder's `nextUpdate`, if present
+    pub next_update: Option<SystemTime>,
+}
+
+impl OcspStaple {
+    /// Whether the response may still be served at `now`
+    pub fn is_fresh_at(&self, now: SystemTime) -> bool {
+        self.next_update.is_none_or(|next| now < next)
+    }
+
+    /// When this response should be refetched
+    pub fn next_fetch_at(&self) -> SystemTime {
+        next_fetch_time(self.fetched_at, self.next_update)
+    }
+}
+
+/// Compute when to refetch an OCSP response fetched at `fetched_at`.
+///
+/// Refreshes halfway through the response's remaining validity so a failed
+/// fetch still leaves time to retry before `nextUpdate`, but never sooner than
+/// [`MIN_REFRESH`] unless `nextUpdate` itself is closer.
+pub fn next_fetch_time(fetched_at: SystemTime, next_update: Option<SystemTime>) -> SystemTime {
+    let Some(next_update) = next_update else {
+        return fetched_at + DEFAULT_REFRESH;
+    };
+    let Ok(remaining) = next_update.duration_since(fetched_at) else {
+        return fetched_at;
+    };
+    fetched_at + (remaining / 2).max(MIN_REFRESH).min(remaining)
+}
+
+/// Fetch and validate an OCSP response for the certificate chain at `cert_path`.
+pub async fn fetch_staple(cert_path: &Path) -> Result<OcspStaple, OcspError> {
+    let pem = std::fs::read(cert_path)?;
+    let chain = X509::stack_from_pem(&pem)?;
+    let (leaf, issuer) = match chain.as_slice() {
+        [leaf, issuer, ..] => (leaf, issuer),
+        _ => return Err(OcspError::MissingIssuer),
+    };
+    let responder = leaf
+        .ocsp_responders()?
+        .iter()
+        .next()
+        .map(|url| url.to_string())
+        .ok_or(OcspError::NoResponder)?;
+
+    let mut request = OcspRequest::new()?;
+    request.add_id(OcspCertId::from_cert(MessageDigest::sha1(), leaf, issuer)?)?;
+    let request_der = request.to_der()?;
+
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| OcspError::Http(e.to_string()))?;
+    let response = client
+        .post(&responder)
+        .header("content-type", "application/ocsp-request")
+        .body(request_der)
+        .send()
+        .await
+        .map_err(|e| OcspError::Http(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(OcspError::Http(format!(
+            "{} returned {}",
+            responder,
+            response.status()
+        )));
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| OcspError::Http(e.to_string()))?;
+
+    let id = OcspCertId::from_cert(MessageDigest::sha1(), leaf, issuer)?;
+    parse_response(&body, &id, SystemTime::now())
+}
+
+/// Validate a DER-encoded OCSP response for `id` and wrap it as a staple.
+fn parse_response(
+    der: &[u8],
+    id: &OcspCertId,
+    fetched_at: SystemTime,
+) -> Result<OcspStaple, OcspError> {
+    let response = OcspResponse::from_der(der)?;
+    if response.status() != OcspResponseStatus::SUCCESSFUL {
+        return Err(OcspError::InvalidResponse(format!(
+            "responder status {}",
+            response.status().as_raw()
+        )));
+    }
+    let basic = response.basic()?;
+    let status = basic
+        .find_status(id)
+        .ok_or_else(|| OcspError::InvalidResponse("certificate not in response".to_string()))?;
+    if status.status != OcspCertStatus::GOOD {
+        return Err(OcspError::InvalidResponse(format!(
+            "certificate status {}",
+            status.status.as_raw()
+        )));
+    }
+    status.check_validity(VALIDITY_LEEWAY_SECS, None)?;
+
+    let next_update = status
+        .next_update()
+        .map(|t| parse_generalized_time(&t.to_string()))
+        .transpose()?;
+
+    Ok(OcspStaple {
+        der: der.to_vec(),
+        fetched_at,
+        next_update,
+    })
+}
+
+/// Parse OpenSSL's printed GeneralizedTime (e.g. `Mar  5 12:00:00 2026 GMT`).
+fn parse_generalized_time(printed: &str) -> Result<SystemTime, OcspError> {
+    // Drop fractional seconds, which OpenSSL prints as `12:00:00.123`.
+    let normalized: String = match printed.find('.') {
+        Some(dot) => {
+            let rest = &printed[dot..];
+            let end = rest.find(' ').unwrap_or(rest.len());
+            format!("{}{}", &printed[..dot], &rest[end..])
+        }
+        None => printed.to_string(),
+    };
+    let parsed = chrono::NaiveDateTime::parse_from_str(normalized.trim(), "%b %e %H:%M:%S %Y GMT")
+        .map_err(|e| OcspError::InvalidResponse(format!("bad nextUpdate '{printed}': {e}")))?;
+    let secs = u64::try_from(parsed.and_utc().timestamp())
+        .map_err(|_| OcspError::InvalidResponse(format!("nextUpdate before epoch: {printed}")))?;
+    Ok(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn next_fetch_is_halfway_to_next_update() {
+        let fetched = at(1_000_000);
+        let next_update = fetched + 7 * 24 * HOUR;
+        assert_eq!(
+            next_fetch_time(fetched, Some(next_update)),
+            fetched + 84 * HOUR
+        );
+    }
+
+    #[test]
+    fn next_fetch_waits_at_least_min_refresh() {
+        let fetched = at(1_000_000);
+        let next_update = fetched + Duration::from_secs(90 * 60);
+        assert_eq!(next_fetch_time(fetched, Some(next_update)), fetched + HOUR);
+    }
+
+    #[test]
+    fn next_fetch_never_passes_next_update() {
+        let fetched = at(1_000_000);
+        let next_update = fetched + Duration::from_secs(10 * 60);
+        assert_eq!(next_fetch_time(fetched, Some(next_update)), next_update);
+    }
+
+    #[test]
+    fn next_fetch_is_immediate_when_next_update_already_passed() {
+        let fetched = at(1_000_000);
+        assert_eq!(next_fetch_time(fetched, Some(at(999_000))), fetched);
+    }
+
+    #[test]
+    fn next_fetch_uses_default_without_next_update() {
+        let fetched = at(1_000_000);
+        assert_eq!(next_fetch_time(fetched, None), fetched + DEFAULT_REFRESH);
+    }
+
+    #[test]
+    fn staple_freshness_follows_next_update() {
+        let staple = OcspStaple {
+            der: vec![],
+            fetched_at: at(0),
+            next_update: Some(at(100)),
+        };
+        assert!(staple.is_fresh_at(at(99)));
+        assert!(!staple.is_fresh_at(at(100)));
+    }
+
+    #[test]
+    fn parses_openssl_generalized_time() {
+        assert_eq!(
+            parse_generalized_time("Mar  5 12:00:00 2026 GMT").unwrap(),
+            at(1_772_712_000)
+        );
+        assert_eq!(
+            parse_generalized_time("Mar 15 12:00:00.250 2026 GMT").unwrap(),
+            at(1_773_576_000)
+        );
+    }
+}
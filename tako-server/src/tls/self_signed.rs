@@ -220,6 +220,27 @@ mod tests {
         assert!(key_content.contains("BEGIN PRIVATE KEY"));
     }
 
+    #[test]
+    fn generated_cert_uses_ecdsa_p256_key() {
+        let temp = TempDir::new().unwrap();
+        let generator = SelfSignedGenerator::new(temp.path());
+        let cert = generator.get_or_create_for_domain("example.test").unwrap();
+
+        let key_pem = std::fs::read(&cert.key_path).unwrap();
+        let key = openssl::pkey::PKey::private_key_from_pem(&key_pem).unwrap();
+        let curve = key
+            .ec_key()
+            .expect("self-signed key should be ECDSA")
+            .group()
+            .curve_name();
+        assert_eq!(curve, Some(openssl::nid::Nid::X9_62_PRIME256V1));
+
+        let cert_pem = std::fs::read(&cert.cert_path).unwrap();
+        let x509 = openssl::x509::X509::from_pem(&cert_pem).unwrap();
+        assert!(x509.public_key().unwrap().public_eq(&key));
+        assert!(x509.verify(&key).unwrap());
+    }
+
     #[test]
     fn test_reuse_existing_cert() {
         let temp = TempDir::new().unwrap();
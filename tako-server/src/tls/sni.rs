@@ -249,6 +249,12 @@ impl TlsAccept for SniCertResolver {
                                 "Failed to set private key: {}", e
                             );
                         }
+                        if let Some(staple) = &cert_info.ocsp
+                            && staple.is_fresh_at(std::time::SystemTime::now())
+                            && let Err(e) = ssl.set_ocsp_status(&staple.der)
+                        {
+                            tracing::warn!(hostname = %sni_hostname, "Failed to staple OCSP response: {}", e);
+                        }
                     }
                     Err(e) => {
                         tracing::error!(
@@ -282,6 +288,14 @@ impl TlsAccept for SniCertResolver {
     }
 }
 
+/// OpenSSL only sends a staple set in `certificate_callback` when the context
+/// has a status callback; this one staples whenever a response was set.
+pub fn enable_ocsp_stapling(
+    builder: &mut openssl::ssl::SslAcceptorBuilder,
+) -> Result<(), openssl::error::ErrorStack> {
+    builder.set_status_callback(|ssl| Ok(ssl.ocsp_status().is_some()))
+}
+
 /// Create TLS callbacks for SNI-based certificate selection
 pub fn create_sni_callbacks(cert_manager: Arc<CertManager>) -> Box<dyn TlsAccept + Send + Sync> {
     Box::new(SniCertResolver::new(cert_manager))
@@ -2,6 +2,7 @@ use crate::control::State;
 use crate::paths;
 use std::fs::File;
 use std::io::{Read as _, Seek, Write as _};
+use std::net::{IpAddr, SocketAddr};
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
@@ -11,18 +12,27 @@ pub(super) const SHORT_DEV_DOMAIN: &str = "test";
 pub(super) const LOCAL_DNS_LISTEN_ADDR: &str = "127.0.0.1:53535";
 pub(super) const DEV_LOOPBACK_ADDR: &str = "127.77.0.1";
 pub(super) const HTTP_REDIRECT_LISTEN_ADDR: &str = "127.0.0.1:47830";
+pub(super) const DEFAULT_IDLE_EXIT_DELAY: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone)]
 pub(super) struct Args {
     pub(super) listen_addr: String,
     pub(super) dns_ip: String,
+    /// How long the server waits after its last app unregisters before
+    /// exiting; `None` keeps it running.
+    pub(super) idle_exit_delay: Option<Duration>,
 }
 
 pub(super) fn parse_args() -> Args {
+    parse_args_from(std::env::args().skip(1))
+}
+
+pub(super) fn parse_args_from(args: impl IntoIterator<Item = String>) -> Args {
     let mut listen_addr = "127.0.0.1:47831".to_string();
     let mut dns_ip = DEV_LOOPBACK_ADDR.to_string();
+    let mut idle_exit_delay = Some(DEFAULT_IDLE_EXIT_DELAY);
 
-    let mut it = std::env::args().skip(1);
+    let mut it = args.into_iter();
     while let Some(arg) = it.next() {
         match arg.as_str() {
             "--listen" => {
@@ -39,6 +49,11 @@ pub(super) fn parse_args() -> Args {
                     dns_ip = v;
                 }
             }
+            "--idle-exit-ms" => {
+                if let Some(ms) = it.next().and_then(|v| v.trim().parse::<u64>().ok()) {
+                    idle_exit_delay = (ms > 0).then(|| Duration::from_millis(ms));
+                }
+            }
             _ => {}
         }
     }
@@ -46,6 +61,7 @@ pub(super) fn parse_args() -> Args {
     Args {
         listen_addr,
         dns_ip,
+        idle_exit_delay,
     }
 }
 
@@ -123,6 +139,44 @@ pub(crate) fn advertised_https_port(state: &State) -> u16 {
     }
 }
 
+/// Loopback addresses where the local forwarder (launchd dev proxy on macOS,
+/// iptables redirect on Linux) exposes 443 and 80.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LocalForwardingProbe {
+    pub(crate) https: SocketAddr,
+    pub(crate) http: SocketAddr,
+}
+
+impl LocalForwardingProbe {
+    /// Forwarding only exists for the dedicated dev loopback address.
+    pub(crate) fn for_advertised_ip(advertised_ip: &str) -> Option<Self> {
+        if advertised_ip != DEV_LOOPBACK_ADDR {
+            return None;
+        }
+        let ip: IpAddr = advertised_ip.parse().ok()?;
+        Some(Self {
+            https: SocketAddr::new(ip, 443),
+            http: SocketAddr::new(ip, 80),
+        })
+    }
+
+    /// Whether the HTTPS and HTTP forwards currently accept connections.
+    pub(crate) async fn run(self) -> (bool, bool) {
+        tokio::join!(tcp_reachable(self.https), tcp_reachable(self.http))
+    }
+}
+
+async fn tcp_reachable(addr: SocketAddr) -> bool {
+    matches!(
+        tokio::time::timeout(
+            Duration::from_millis(150),
+            tokio::net::TcpStream::connect(addr)
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
 pub(super) fn default_socket_path() -> PathBuf {
     paths::tako_data_dir()
         .unwrap_or_else(|_| PathBuf::from("."))
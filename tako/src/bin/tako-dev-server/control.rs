@@ -8,14 +8,14 @@ use tokio::sync::watch;
 
 use crate::process::{
     app_name_for, broadcast_app_status, broadcast_dev_event, forward_child_log_line,
-    kill_app_process, monitor_handoff_pid, push_scoped_log, push_user_action,
-    spawn_and_monitor_app,
+    monitor_handoff_pid, push_scoped_log, push_user_action, spawn_and_monitor_app,
+    stop_app_process,
 };
 use crate::protocol::{self, AppInfo, Request, Response};
 use crate::route_pattern::split_route_pattern;
 use crate::state;
 use crate::state::RuntimeApp;
-use crate::{advertised_https_port, app_short_host, default_hosts};
+use crate::{LocalForwardingProbe, advertised_https_port, app_short_host, default_hosts};
 use tako_socket::{read_json_line, write_json_line};
 
 fn sanitize_app_name(name: &str) -> String {
@@ -120,11 +120,15 @@ pub(crate) struct State {
     pub(crate) routes: crate::proxy::Routes,
     pub(crate) local_dns_enabled: bool,
     pub(crate) local_dns_port: u16,
+    /// Shared with the DNS listener; `Some` once `main` starts it.
+    pub(crate) dns_answer_ip: Option<crate::local_dns::AnswerIp>,
 
     pub(crate) listen_port: u16,
     pub(crate) listen_addr: String,
     pub(crate) advertised_ip: String,
     pub(crate) control_clients: u32,
+    /// Probed on `Info` to report whether local 443/80 forwarding is active.
+    pub(crate) local_forwarding_probe: Option<LocalForwardingProbe>,
 
     pub(crate) lan_enabled: bool,
     pub(crate) lan_ip: Option<String>,
@@ -132,6 +136,8 @@ pub(crate) struct State {
 
     pub(crate) db: Option<state::DevStateStore>,
     pub(crate) apps: std::collections::HashMap<String, RuntimeApp>,
+    /// Config paths of apps whose old process is still exiting.
+    pub(crate) stopping: std::collections::HashSet<String>,
 
     /// Path to the shared Tako internal unix socket. `Some` once
     /// `workflows.start_socket()` succeeds in main; injected into every
@@ -146,6 +152,9 @@ pub(crate) struct State {
     /// exists while there's real work, and every wake re-spawns it
     /// (picking up whatever code the user just edited, no watcher needed).
     pub(crate) workflows: Option<Arc<tako_workflows::WorkflowManager>>,
+    /// Delay before exiting once no apps are registered; `None` disables
+    /// the idle exit.
+    pub(crate) idle_exit_delay: Option<Duration>,
 }
 
 impl State {
@@ -167,8 +176,10 @@ impl State {
             routes,
             local_dns_enabled,
             local_dns_port,
+            dns_answer_ip: None,
             listen_port,
             listen_addr,
+            local_forwarding_probe: LocalForwardingProbe::for_advertised_ip(&advertised_ip),
             advertised_ip,
             control_clients: 0,
             lan_enabled: false,
@@ -176,8 +187,20 @@ impl State {
             mdns: None,
             db: None,
             apps: std::collections::HashMap::new(),
+            stopping: std::collections::HashSet::new(),
             internal_socket: None,
             workflows: None,
+            idle_exit_delay: Some(crate::bootstrap::DEFAULT_IDLE_EXIT_DELAY),
+        }
+    }
+
+    /// Switch the IP clients should reach the daemon on. Local DNS answers
+    /// follow immediately (1s TTL), as does the forwarding probe.
+    pub(crate) fn set_advertised_ip(&mut self, ip: std::net::Ipv4Addr) {
+        self.advertised_ip = ip.to_string();
+        self.local_forwarding_probe = LocalForwardingProbe::for_advertised_ip(&self.advertised_ip);
+        if let Some(answer_ip) = &self.dns_answer_ip {
+            answer_ip.set(ip);
         }
     }
 
@@ -187,7 +210,10 @@ impl State {
             .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
     }
 
-    fn schedule_idle_exit(&mut self) {
+    pub(crate) fn schedule_idle_exit(&mut self) {
+        let Some(delay) = self.idle_exit_delay else {
+            return;
+        };
         let generation = self
             .idle_generation
             .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
@@ -195,7 +221,7 @@ impl State {
         let shutdown_tx = self.shutdown_tx.clone();
         let idle_generation = self.idle_generation.clone();
         tokio::spawn(async move {
-            tokio::time::sleep(Duration::from_secs(2)).await;
+            tokio::time::sleep(delay).await;
             if idle_generation.load(std::sync::atomic::Ordering::SeqCst) == generation {
                 let _ = shutdown_tx.send(true);
             }
@@ -253,6 +279,13 @@ pub(crate) async fn handle_client(
 
         let resp = match req {
             Request::Ping => Response::Pong,
+            Request::Hello { client_version } => {
+                tracing::debug!(client_version = %client_version, "client hello");
+                Response::Hello {
+                    server_version: env!("CARGO_PKG_VERSION").to_string(),
+                    protocol: tako::dev::DEV_PROTOCOL_VERSION,
+                }
+            }
             Request::SubscribeEvents => {
                 let rx = {
                     let s = state.lock().unwrap();
@@ -285,7 +318,11 @@ pub(crate) async fn handle_client(
                 }
                 return Ok(());
             }
-            Request::SubscribeLogs { config_path, after } => {
+            Request::SubscribeLogs {
+                config_path,
+                after,
+                follow,
+            } => {
                 let log_buffer = {
                     let s = state.lock().unwrap();
                     s.apps.get(&config_path).map(|a| a.log_buffer.clone())
@@ -326,6 +363,9 @@ pub(crate) async fn handle_client(
                         return Ok(());
                     }
                 }
+                if !follow {
+                    return Ok(());
+                }
 
                 let mut disconnect_probe = [0_u8; 1];
                 loop {
@@ -372,14 +412,9 @@ pub(crate) async fn handle_client(
                 let app_name = sanitize_app_name(&app_name);
                 let route_id = format!("reg:{}", config_path);
 
-                {
-                    let s = state.lock().unwrap();
-                    if let Some(existing) = s.apps.get(&config_path)
-                        && let Some(pid) = existing.pid
-                    {
-                        kill_app_process(pid);
-                    }
-                }
+                // Taking the pid first keeps the exit monitor from reporting
+                // the old process exiting as a crash.
+                stop_app_process(&state, &config_path, false).await;
 
                 // Everything before the await must happen under the
                 // `std::sync::Mutex` guard; scope the guard so the
@@ -567,14 +602,9 @@ pub(crate) async fn handle_client(
                 }
             }
             Request::UnregisterApp { config_path } => {
-                let mut s = state.lock().unwrap();
+                stop_app_process(&state, &config_path, false).await;
 
-                if let Some(app) = s.apps.get(&config_path)
-                    && let Some(pid) = app.pid
-                {
-                    kill_app_process(pid);
-                    state::remove_pid_file(&app.project_dir, &config_path);
-                }
+                let mut s = state.lock().unwrap();
 
                 let app_name = if let Some(app) = s.apps.remove(&config_path) {
                     if let Some(ref mut mdns) = s.mdns {
@@ -607,16 +637,7 @@ pub(crate) async fn handle_client(
                 Response::AppUnregistered { config_path }
             }
             Request::RestartApp { config_path } => {
-                {
-                    let mut s = state.lock().unwrap();
-                    if let Some(app) = s.apps.get_mut(&config_path) {
-                        if let Some(pid) = app.pid.take() {
-                            kill_app_process(pid);
-                            state::remove_pid_file(&app.project_dir, &config_path);
-                        }
-                        app.is_idle = true;
-                    }
-                }
+                stop_app_process(&state, &config_path, true).await;
 
                 let log_buffer = {
                     let s = state.lock().unwrap();
@@ -803,6 +824,27 @@ pub(crate) async fn handle_client(
                     .collect();
                 Response::RegisteredApps { apps }
             }
+            Request::Health => {
+                let s = state.lock().unwrap();
+                let apps = s
+                    .apps
+                    .iter()
+                    .map(|(config_path, a)| protocol::AppHealth {
+                        config_path: config_path.clone(),
+                        app_name: a.name.clone(),
+                        pid: a.pid,
+                        alive: a.pid.is_some_and(state::pid_is_alive),
+                        last_log_ms_ago: a
+                            .log_buffer
+                            .last_push()
+                            .map(|at| at.elapsed().as_millis() as u64),
+                    })
+                    .collect();
+                Response::Health { apps }
+            }
+            Request::DescribeRoutes => Response::Routes {
+                routes: state.lock().unwrap().routes.describe(),
+            },
             Request::ListApps => {
                 let s = state.lock().unwrap();
                 let apps = s
@@ -819,6 +861,11 @@ pub(crate) async fn handle_client(
                 Response::Apps { apps }
             }
             Request::Info => {
+                let probe = state.lock().unwrap().local_forwarding_probe;
+                let (local_https_forwarding, local_http_redirect_forwarding) = match probe {
+                    Some(probe) => probe.run().await,
+                    None => (false, false),
+                };
                 let s = state.lock().unwrap();
                 Response::Info {
                     info: protocol::DevInfo {
@@ -830,10 +877,21 @@ pub(crate) async fn handle_client(
                         control_clients: s.control_clients,
                         lan_enabled: s.lan_enabled,
                         lan_ip: s.lan_ip.clone(),
+                        local_https_forwarding,
+                        local_http_redirect_forwarding,
                     },
                 }
             }
             Request::ToggleLan { enabled } => handle_toggle_lan(&state, enabled).await,
+            Request::SetAdvertisedIp { ip } => match ip.parse::<std::net::Ipv4Addr>() {
+                Ok(addr) => {
+                    state.lock().unwrap().set_advertised_ip(addr);
+                    Response::AdvertisedIpSet { ip }
+                }
+                Err(_) => Response::Error {
+                    message: format!("invalid IPv4 address: {ip}"),
+                },
+            },
             Request::StopServer => {
                 let s = state.lock().unwrap();
                 let _ = s.shutdown_tx.send(true);
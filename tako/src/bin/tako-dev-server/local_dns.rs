@@ -3,15 +3,21 @@
 //! Answers queries directly from the current app host table.
 
 use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use hickory_proto::{
     op::{Message, MessageType, ResponseCode},
-    rr::{DNSClass, Name, RData, Record, RecordType, rdata::A},
+    rr::{
+        DNSClass, Name, RData, Record, RecordType,
+        rdata::{A, SOA},
+    },
 };
 
 use tokio::net::UdpSocket;
 
-const DNS_TTL_SECS: u32 = 30;
+/// Kept at 1s so clients pick up a new answer IP almost immediately.
+const DNS_TTL_SECS: u32 = 1;
 const DEV_SUFFIX: &str = ".test";
 const SCOPED_DEV_SUFFIX: &str = ".tako.test";
 
@@ -24,6 +30,25 @@ struct ParsedDnsQuery {
     qclass: DNSClass,
 }
 
+/// IPv4 that `A` answers point at. Cloned handles share the value, so the
+/// daemon can switch it at runtime without restarting the listener.
+#[derive(Debug, Clone)]
+pub struct AnswerIp(Arc<AtomicU32>);
+
+impl AnswerIp {
+    pub fn new(ip: Ipv4Addr) -> Self {
+        Self(Arc::new(AtomicU32::new(u32::from(ip))))
+    }
+
+    pub fn get(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, ip: Ipv4Addr) {
+        self.0.store(u32::from(ip), Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LocalDns {
     listen_addr: SocketAddr,
@@ -77,6 +102,32 @@ fn response_with_record(
     }
 }
 
+/// Zone apex that owns `host` — `tako.test` for the scoped suffix, else `test`.
+fn zone_apex(host: &str) -> &'static str {
+    let h = host.to_ascii_lowercase();
+    if h == "tako.test" || h.ends_with(SCOPED_DEV_SUFFIX) {
+        "tako.test."
+    } else {
+        "test."
+    }
+}
+
+/// SOA for the authority section of NODATA answers. Without it resolvers
+/// cannot negative-cache (RFC 2308) and re-ask for AAAA on every lookup.
+fn zone_soa(host: &str) -> Option<Record> {
+    let apex = Name::from_ascii(zone_apex(host)).ok()?;
+    let soa = SOA::new(
+        apex.clone(),
+        Name::from_ascii("hostmaster.tako.test.").ok()?,
+        1,
+        3600,
+        600,
+        86400,
+        DNS_TTL_SECS,
+    );
+    Some(Record::from_rdata(apex, DNS_TTL_SECS, RData::SOA(soa)))
+}
+
 fn build_dns_response(packet: &[u8], loopback_ip: Ipv4Addr) -> Option<Vec<u8>> {
     let q = parse_dns_query(packet)?;
     let mut response = Message::new();
@@ -108,12 +159,18 @@ fn build_dns_response(packet: &[u8], loopback_ip: Ipv4Addr) -> Option<Vec<u8>> {
         }
     }
 
+    if response.answers().is_empty()
+        && let Some(soa) = zone_soa(&q.qname)
+    {
+        response.add_name_server(soa);
+    }
+
     response.to_vec().ok()
 }
 
 pub async fn start(
     listen_addr: &str,
-    loopback_ip: Ipv4Addr,
+    answer_ip: AnswerIp,
 ) -> Result<LocalDns, Box<dyn std::error::Error>> {
     let socket = UdpSocket::bind(listen_addr).await?;
     let bound = socket.local_addr()?;
@@ -130,7 +187,7 @@ pub async fn start(
             };
 
             let packet = &buf[..len];
-            let Some(resp) = build_dns_response(packet, loopback_ip) else {
+            let Some(resp) = build_dns_response(packet, answer_ip.get()) else {
                 continue;
             };
 
@@ -213,6 +270,34 @@ mod tests {
         assert_eq!(ancount(&resp), 0);
     }
 
+    #[test]
+    fn aaaa_nodata_carries_zone_soa_for_negative_caching() {
+        let q = build_query("app.test", DNS_TYPE_AAAA);
+        let resp = build_dns_response(&q, Ipv4Addr::new(127, 77, 0, 1)).expect("response");
+        let msg = Message::from_vec(&resp).expect("parse");
+        assert_eq!(msg.response_code(), ResponseCode::NoError);
+        assert!(msg.authoritative());
+        assert!(msg.answers().is_empty());
+        assert_eq!(msg.name_servers().len(), 1);
+        let soa = &msg.name_servers()[0];
+        assert_eq!(soa.record_type(), RecordType::SOA);
+        assert_eq!(soa.name().to_ascii(), "test.");
+
+        let q = build_query("app.tako.test", DNS_TYPE_AAAA);
+        let resp = build_dns_response(&q, Ipv4Addr::new(127, 77, 0, 1)).expect("response");
+        let msg = Message::from_vec(&resp).expect("parse");
+        assert_eq!(msg.name_servers()[0].name().to_ascii(), "tako.test.");
+    }
+
+    #[test]
+    fn a_answer_has_no_authority_section() {
+        let q = build_query("app.test", DNS_TYPE_A);
+        let resp = build_dns_response(&q, Ipv4Addr::new(127, 77, 0, 1)).expect("response");
+        let msg = Message::from_vec(&resp).expect("parse");
+        assert_eq!(msg.answers().len(), 1);
+        assert!(msg.name_servers().is_empty());
+    }
+
     #[test]
     fn returns_only_a_record_for_any_query() {
         let q = build_query("app.test", 255);
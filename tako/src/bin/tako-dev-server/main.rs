@@ -34,7 +34,8 @@ use bootstrap::{
     listen_port_from_addr, parse_args,
 };
 pub(crate) use bootstrap::{
-    advertised_https_port, app_short_host, default_hosts, ensure_tcp_listener_can_bind,
+    LocalForwardingProbe, advertised_https_port, app_short_host, default_hosts,
+    ensure_tcp_listener_can_bind,
 };
 use protocol::DevEvent;
 use protocol::Response;
@@ -121,8 +122,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let listen_addr = args.listen_addr;
     let listen_port = listen_port_from_addr(&listen_addr);
 
-    let loopback_ip = args.dns_ip.parse::<std::net::Ipv4Addr>()?;
-    let local_dns = local_dns::start(LOCAL_DNS_LISTEN_ADDR, loopback_ip).await?;
+    let dns_answer_ip = local_dns::AnswerIp::new(args.dns_ip.parse()?);
+    let local_dns = local_dns::start(LOCAL_DNS_LISTEN_ADDR, dns_answer_ip.clone()).await?;
     tracing::info!(listen = %local_dns.listen_addr(), "local DNS server listening");
 
     let sock = default_socket_path();
@@ -209,6 +210,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         args.dns_ip,
     );
     st.internal_socket = internal_socket_path;
+    st.dns_answer_ip = Some(dns_answer_ip);
+    st.idle_exit_delay = args.idle_exit_delay;
     st.workflows = Some(workflows.clone());
 
     // Open the SQLite state store (persistent registrations only; runtime state is in-memory).
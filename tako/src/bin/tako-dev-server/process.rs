@@ -11,6 +11,9 @@ use crate::state;
 use tokio::io::AsyncBufReadExt;
 
 const APP_READINESS_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a stopping or restarting app gets to exit after SIGTERM before it
+/// is killed.
+pub(crate) const APP_SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
 
 pub(crate) async fn monitor_handoff_pid(
     state: Arc<Mutex<State>>,
@@ -73,6 +76,97 @@ pub(crate) fn kill_app_process(pid: u32) {
     }
 }
 
+/// Send SIGTERM to an app's process group, give it `grace` to exit, then
+/// SIGKILL whatever is left. Returns whether the app had to be force-killed.
+pub(crate) async fn terminate_app_process(pid: u32, grace: Duration) -> bool {
+    if pid == 0 {
+        return false;
+    }
+    unsafe {
+        libc::kill(-(pid as i32), libc::SIGTERM);
+        libc::kill(pid as i32, libc::SIGTERM);
+    }
+
+    let deadline = tokio::time::Instant::now() + grace;
+    let mut exited = !state::pid_is_alive(pid);
+    while !exited && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        exited = !state::pid_is_alive(pid);
+    }
+
+    if exited {
+        // Leftover children in the app's group still go. A group id can't be
+        // handed out again while the group has members, so only signal it if
+        // it still exists.
+        unsafe {
+            if libc::kill(-(pid as i32), 0) == 0 {
+                libc::kill(-(pid as i32), libc::SIGKILL);
+            }
+        }
+    } else {
+        tracing::warn!(
+            pid = pid,
+            grace_ms = grace.as_millis() as u64,
+            "app ignored SIGTERM; killing"
+        );
+        kill_app_process(pid);
+    }
+    !exited
+}
+
+/// Clears an app's stopping mark when its stop finishes or is abandoned.
+struct StoppingGuard<'a> {
+    state: &'a Arc<Mutex<State>>,
+    config_path: &'a str,
+}
+
+impl Drop for StoppingGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut s) = self.state.lock() {
+            s.stopping.remove(self.config_path);
+        }
+    }
+}
+
+/// Take the app's running process and terminate it (see
+/// [`terminate_app_process`]). `mark_idle` also marks the app idle in the
+/// same step.
+///
+/// The app stays marked stopping until the old process is gone, so
+/// wake-on-request doesn't spawn a second copy next to it, and another
+/// register, restart, or unregister of the same app waits here for its turn.
+pub(crate) async fn stop_app_process(
+    state: &Arc<Mutex<State>>,
+    config_path: &str,
+    mark_idle: bool,
+) {
+    let running = loop {
+        {
+            let mut s = state.lock().unwrap();
+            if !s.stopping.contains(config_path) {
+                let running = s.apps.get_mut(config_path).and_then(|app| {
+                    if mark_idle {
+                        app.is_idle = true;
+                    }
+                    app.pid.take().map(|pid| (pid, app.project_dir.clone()))
+                });
+                if running.is_some() {
+                    s.stopping.insert(config_path.to_string());
+                }
+                break running;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    };
+    let Some((pid, project_dir)) = running else {
+        return;
+    };
+
+    let _stopping = StoppingGuard { state, config_path };
+    terminate_app_process(pid, APP_SHUTDOWN_GRACE).await;
+    state::remove_pid_file(&project_dir, config_path);
+}
+
 async fn kill_and_reap_app_process(
     child: &mut tokio::process::Child,
     pid: Option<u32>,
@@ -376,6 +470,47 @@ pub(crate) fn build_spawn_env(
     env
 }
 
+/// Resolve the command and env for one app spawn.
+///
+/// `{host}` and `{port}` in command args expand to the `HOST`/`PORT` handed
+/// to the app. `PORT` is normally `0` (the app binds any port and reports it
+/// on fd 4), so a command that asks for `{port}` gets a free loopback port
+/// reserved up front instead, keeping the flag and `PORT` in agreement.
+pub(crate) fn resolve_spawn_command(
+    app: &state::RuntimeApp,
+    internal_socket: Option<&std::path::Path>,
+) -> std::io::Result<(Vec<String>, std::collections::HashMap<String, String>)> {
+    let mut env = build_spawn_env(app, internal_socket);
+    if app.command.iter().any(|arg| arg.contains("{port}")) {
+        env.insert(
+            tako_core::instance_env::PORT_ENV.to_string(),
+            reserve_loopback_port()?.to_string(),
+        );
+    }
+    let command = expand_command_placeholders(&app.command, &env);
+    Ok((command, env))
+}
+
+fn expand_command_placeholders(
+    command: &[String],
+    env: &std::collections::HashMap<String, String>,
+) -> Vec<String> {
+    let lookup = |key: &str| env.get(key).map(String::as_str).unwrap_or_default();
+    let port = lookup(tako_core::instance_env::PORT_ENV);
+    let host = lookup(tako_core::instance_env::HOST_ENV);
+    command
+        .iter()
+        .map(|arg| arg.replace("{port}", port).replace("{host}", host))
+        .collect()
+}
+
+fn reserve_loopback_port() -> std::io::Result<u16> {
+    // The probe listener closes on drop; the app binds the port moments later.
+    Ok(std::net::TcpListener::bind(("127.0.0.1", 0))?
+        .local_addr()?
+        .port())
+}
+
 async fn spawn_app(
     project_dir: &str,
     app: &state::RuntimeApp,
@@ -384,6 +519,7 @@ async fn spawn_app(
     if app.command.is_empty() {
         return Err("app has empty command".into());
     }
+    let (command, env) = resolve_spawn_command(app, internal_socket)?;
 
     // Create the fd 4 readiness pipe: child writes its bound port, parent reads it.
     #[cfg(unix)]
@@ -391,9 +527,9 @@ async fn spawn_app(
     #[cfg(unix)]
     let write_raw: Option<std::os::fd::RawFd> = readiness_pipe.as_ref().map(|(_, w)| w.as_raw_fd());
 
-    let mut cmd = tokio::process::Command::new(&app.command[0]);
-    if app.command.len() > 1 {
-        cmd.args(&app.command[1..]);
+    let mut cmd = tokio::process::Command::new(&command[0]);
+    if command.len() > 1 {
+        cmd.args(&command[1..]);
     }
     cmd.current_dir(project_dir)
         .stdin(std::process::Stdio::null())
@@ -430,7 +566,7 @@ async fn spawn_app(
         cmd.env("PATH", format!("{}:{current_path}", bin_dir.display()));
     }
 
-    for (k, v) in build_spawn_env(app, internal_socket) {
+    for (k, v) in env {
         cmd.env(k, v);
     }
 
@@ -511,8 +647,9 @@ pub(crate) async fn handle_wake_on_request(state: Arc<Mutex<State>>, host: Strin
         let found = s
             .apps
             .iter()
-            .find(|(_, a)| {
-                if !a.is_idle {
+            .find(|(config_path, a)| {
+                // A stopping app is restarted or removed by whoever stops it.
+                if !a.is_idle || s.stopping.contains(config_path.as_str()) {
                     return false;
                 }
                 a.hosts.iter().any(|route_pattern| {
@@ -677,18 +814,34 @@ pub(crate) async fn stale_app_cleanup_loop(state: Arc<Mutex<State>>) {
     let mut ticker = tokio::time::interval(Duration::from_secs(60));
     loop {
         ticker.tick().await;
-        let mut s = state.lock().unwrap();
-        if let Some(db) = &s.db
-            && let Ok(removed) = db.cleanup_stale()
-        {
-            for config_path in &removed {
-                s.apps.remove(config_path);
-                let route_id = format!("reg:{}", config_path);
-                s.routes.remove_app(&route_id);
-            }
-            if !removed.is_empty() {
-                tracing::info!(count = removed.len(), "cleaned up stale app registrations");
-            }
-        }
+        cleanup_stale_apps(&state);
+    }
+}
+
+/// Drop registrations whose config file no longer exists, telling subscribed
+/// clients with `RegistrationExpired` so they don't wait on a dead app.
+pub(crate) fn cleanup_stale_apps(state: &Arc<Mutex<State>>) -> Vec<String> {
+    let mut s = state.lock().unwrap();
+    let Some(Ok(removed)) = s.db.as_ref().map(|db| db.cleanup_stale()) else {
+        return Vec::new();
+    };
+    for config_path in &removed {
+        let app_name = s
+            .apps
+            .remove(config_path)
+            .map(|app| app.name)
+            .unwrap_or_default();
+        let route_id = format!("reg:{}", config_path);
+        s.routes.remove_app(&route_id);
+        s.events.broadcast(Response::Event {
+            event: DevEvent::RegistrationExpired {
+                config_path: config_path.clone(),
+                app_name,
+            },
+        });
+    }
+    if !removed.is_empty() {
+        tracing::info!(count = removed.len(), "cleaned up stale app registrations");
     }
+    removed
 }
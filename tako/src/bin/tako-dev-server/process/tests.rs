@@ -22,6 +22,55 @@ async fn kill_and_reap_app_process_waits_for_killed_child() {
     assert!(status.is_some());
 }
 
+#[cfg(unix)]
+fn spawn_sigterm_trap(trap: &str) -> (u32, tokio::task::JoinHandle<std::process::ExitStatus>) {
+    let mut command = tokio::process::Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("trap {trap} TERM; while :; do sleep 0.05; done"))
+        .process_group(0);
+    let mut child = command.spawn().unwrap();
+    let pid = child.id().unwrap();
+    // Reap the child as soon as it exits, like the app exit monitor does.
+    let waiter = tokio::spawn(async move { child.wait().await.unwrap() });
+    (pid, waiter)
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn terminate_app_process_lets_app_exit_within_grace() {
+    let (pid, waiter) = spawn_sigterm_trap("'exit 0'");
+    // Give the shell time to install its trap.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let forced = terminate_app_process(pid, Duration::from_secs(2)).await;
+
+    assert!(
+        !forced,
+        "app that handles SIGTERM should not be force-killed"
+    );
+    let status = waiter.await.unwrap();
+    assert_eq!(status.code(), Some(0));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn terminate_app_process_kills_app_that_ignores_sigterm() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let (pid, waiter) = spawn_sigterm_trap("''");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let forced = terminate_app_process(pid, Duration::from_millis(200)).await;
+
+    assert!(forced, "app that ignores SIGTERM should be force-killed");
+    let status = tokio::time::timeout(Duration::from_secs(2), waiter)
+        .await
+        .expect("killed app should be reaped")
+        .unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+}
+
 #[cfg(unix)]
 #[tokio::test]
 async fn readiness_times_out_when_route_becomes_active_without_fd4_signal() {
@@ -106,3 +155,74 @@ fn readiness_failure_message_uses_client_hint() {
 
     assert_eq!(readiness_failure_message(&app), "custom readiness hint");
 }
+
+#[cfg(unix)]
+fn stopping_test_state(
+    project_dir: &std::path::Path,
+    pid: Option<u32>,
+) -> (Arc<Mutex<State>>, String) {
+    let (shutdown_tx, _shutdown_rx) = watch::channel(false);
+    let state = Arc::new(Mutex::new(State::new(
+        shutdown_tx,
+        Routes::default(),
+        EventsHub::default(),
+        true,
+        53535,
+        8443,
+        "127.0.0.1:8443".to_string(),
+        "127.0.0.1".to_string(),
+    )));
+    let config_path = format!("{}/tako.toml", project_dir.display());
+    state.lock().unwrap().apps.insert(
+        config_path.clone(),
+        RuntimeApp {
+            project_dir: project_dir.to_string_lossy().to_string(),
+            name: "stopping".to_string(),
+            variant: None,
+            hosts: vec!["stopping.test".to_string()],
+            upstream_port: 0,
+            is_idle: pid.is_none(),
+            command: vec!["sh".to_string(), "-c".to_string(), "sleep 60".to_string()],
+            env: std::collections::HashMap::new(),
+            log_buffer: crate::state::LogBuffer::new(),
+            pid,
+            client_pid: None,
+            readiness_failure_hint: None,
+        },
+    );
+    (state, config_path)
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn stop_app_process_keeps_app_stopping_until_old_process_exits() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let (pid, waiter) = spawn_sigterm_trap("'sleep 0.3; exit 0'");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let (state, config_path) = stopping_test_state(tmp.path(), Some(pid));
+
+    let stop = tokio::spawn({
+        let state = state.clone();
+        let config_path = config_path.clone();
+        async move { stop_app_process(&state, &config_path, true).await }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    {
+        let s = state.lock().unwrap();
+        assert!(s.stopping.contains(&config_path));
+        let app = s.apps.get(&config_path).unwrap();
+        assert!(app.is_idle);
+        assert!(app.pid.is_none());
+    }
+
+    // A wake while the old process is exiting must not start a second one.
+    handle_wake_on_request(state.clone(), "stopping.test".to_string(), "/".to_string()).await;
+    assert!(state.lock().unwrap().apps[&config_path].is_idle);
+
+    tokio::time::timeout(Duration::from_secs(5), stop)
+        .await
+        .expect("stop should finish within the grace period")
+        .unwrap();
+    assert_eq!(waiter.await.unwrap().code(), Some(0));
+    assert!(state.lock().unwrap().stopping.is_empty());
+}
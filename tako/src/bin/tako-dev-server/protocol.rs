@@ -4,6 +4,10 @@ use serde::{Deserialize, Serialize};
 #[serde(tag = "type")]
 pub enum Request {
     Ping,
+    /// Version handshake; answered with `Response::Hello`.
+    Hello {
+        client_version: String,
+    },
     Info,
     /// Register a persistent app by config path.
     RegisterApp {
@@ -52,27 +56,47 @@ pub enum Request {
         config_path: String,
         client_id: u32,
     },
-    /// Subscribe to an app's log stream.
+    /// Subscribe to an app's log stream. With `follow: false` the stream
+    /// ends after the buffered backlog instead of waiting for new lines.
     SubscribeLogs {
         config_path: String,
         #[serde(default)]
         after: Option<u64>,
+        #[serde(default = "default_follow")]
+        follow: bool,
     },
     /// Toggle LAN mode (expose dev server on the local network).
     ToggleLan {
         enabled: bool,
     },
+    /// Change the advertised IPv4 (and local DNS answers) without a restart.
+    SetAdvertisedIp {
+        ip: String,
+    },
     /// List all registered apps.
     ListRegisteredApps,
+    /// Per-app process liveness and log recency.
+    Health,
+    /// The proxy's live route table, for debugging host routing.
+    DescribeRoutes,
     ListApps,
     SubscribeEvents,
     StopServer,
 }
 
+fn default_follow() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(tag = "type")]
 pub enum Response {
     Pong,
+    Hello {
+        server_version: String,
+        /// The daemon's `DEV_PROTOCOL_VERSION`
+        protocol: u32,
+    },
     Apps {
         apps: Vec<AppInfo>,
     },
@@ -101,6 +125,12 @@ pub enum Response {
     RegisteredApps {
         apps: Vec<RegisteredAppInfo>,
     },
+    Health {
+        apps: Vec<AppHealth>,
+    },
+    Routes {
+        routes: Vec<RouteInfo>,
+    },
     Subscribed,
     Event {
         event: DevEvent,
@@ -118,6 +148,9 @@ pub enum Response {
         #[serde(default, skip_serializing_if = "Option::is_none")]
         ca_url: Option<String>,
     },
+    AdvertisedIpSet {
+        ip: String,
+    },
     Stopping,
     Error {
         message: String,
@@ -188,6 +221,12 @@ pub enum DevEvent {
         app_name: String,
         message: String,
     },
+    /// The daemon dropped a registration on its own (its config file is
+    /// gone); the owning client should stop or register again.
+    RegistrationExpired {
+        config_path: String,
+        app_name: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -204,6 +243,29 @@ pub struct RegisteredAppInfo {
     pub client_pid: Option<u32>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AppHealth {
+    pub config_path: String,
+    pub app_name: String,
+    pub pid: Option<u32>,
+    /// The app has a pid and that process still exists.
+    pub alive: bool,
+    /// Milliseconds since the app's last log line; `None` before the first.
+    pub last_log_ms_ago: Option<u64>,
+}
+
+/// One entry of the proxy route table, in match order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RouteInfo {
+    /// Route pattern as registered (`host` or `host/path`)
+    pub pattern: String,
+    /// Route owner, `reg:{config_path}` for registered apps
+    pub app_id: String,
+    pub upstream_port: u16,
+    /// Whether the app is running; inactive routes wake it on request
+    pub active: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AppInfo {
     pub app_name: String,
@@ -232,6 +294,12 @@ pub struct DevInfo {
     pub lan_enabled: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub lan_ip: Option<String>,
+    /// Local :443 forwarding to the daemon proxy accepts connections.
+    #[serde(default)]
+    pub local_https_forwarding: bool,
+    /// Local :80 forwarding to the HTTPS redirect accepts connections.
+    #[serde(default)]
+    pub local_http_redirect_forwarding: bool,
 }
 
 #[cfg(test)]
@@ -251,6 +319,27 @@ mod tests {
         assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), resp);
     }
 
+    #[test]
+    fn serde_roundtrip_hello() {
+        let req = Request::Hello {
+            client_version: "0.1.0".to_string(),
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(json, r#"{"type":"Hello","client_version":"0.1.0"}"#);
+        assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), req);
+
+        let resp = Response::Hello {
+            server_version: "0.1.0".to_string(),
+            protocol: 1,
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"Hello","server_version":"0.1.0","protocol":1}"#
+        );
+        assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), resp);
+    }
+
     #[test]
     fn serde_roundtrip_stop() {
         let req = Request::StopServer;
@@ -307,6 +396,8 @@ mod tests {
                 control_clients: 1,
                 lan_enabled: false,
                 lan_ip: None,
+                local_https_forwarding: true,
+                local_http_redirect_forwarding: false,
             },
         };
         let json = serde_json::to_string(&resp).unwrap();
@@ -418,6 +509,36 @@ mod tests {
         assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), resp);
     }
 
+    #[test]
+    fn serde_roundtrip_set_advertised_ip() {
+        let req = Request::SetAdvertisedIp {
+            ip: "127.0.0.1".to_string(),
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(json, r#"{"type":"SetAdvertisedIp","ip":"127.0.0.1"}"#);
+        assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), req);
+    }
+
+    #[test]
+    fn serde_roundtrip_health() {
+        let req = Request::Health;
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(json, r#"{"type":"Health"}"#);
+        assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), req);
+
+        let resp = Response::Health {
+            apps: vec![AppHealth {
+                config_path: "/proj/tako.toml".to_string(),
+                app_name: "app".to_string(),
+                pid: Some(111),
+                alive: false,
+                last_log_ms_ago: Some(1500),
+            }],
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), resp);
+    }
+
     #[test]
     fn serde_roundtrip_app_status_changed_event() {
         let resp = Response::Event {
@@ -458,6 +579,21 @@ mod tests {
         assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), resp);
     }
 
+    #[test]
+    fn subscribe_logs_follows_by_default() {
+        let req: Request =
+            serde_json::from_str(r#"{"type":"SubscribeLogs","config_path":"/proj/tako.toml"}"#)
+                .unwrap();
+        assert_eq!(
+            req,
+            Request::SubscribeLogs {
+                config_path: "/proj/tako.toml".to_string(),
+                after: None,
+                follow: true,
+            }
+        );
+    }
+
     #[test]
     fn serde_roundtrip_toggle_lan() {
         let req = Request::ToggleLan { enabled: true };
@@ -503,6 +639,10 @@ mod tests {
                 app_name: "app".to_string(),
                 message: "spawn failed".to_string(),
             },
+            DevEvent::RegistrationExpired {
+                config_path: "/proj/tako.toml".to_string(),
+                app_name: "app".to_string(),
+            },
         ] {
             let resp = Response::Event { event };
             let json = serde_json::to_string(&resp).unwrap();
@@ -35,13 +35,21 @@ fn normalize_exact_path(path: &str) -> &str {
     if trimmed.is_empty() { "/" } else { trimmed }
 }
 
-fn route_specificity(pattern: &str) -> (u8, usize, u8) {
+/// (host kind, wildcard suffix length, path prefix length, exact path bonus)
+type RouteSpecificity = (u8, usize, usize, u8);
+
+fn route_specificity(pattern: &str) -> RouteSpecificity {
     if pattern.is_empty() {
-        return (0, 0, 0);
+        return (0, 0, 0, 0);
     }
     let (pattern_host, pattern_path) = split_route_pattern(pattern);
 
-    let host_score: u8 = if pattern_host.starts_with("*.") { 1 } else { 2 };
+    // Exact hosts beat any wildcard; among wildcards a longer suffix
+    // (`*.api.app.test` over `*.app.test`) is the more specific match.
+    let (host_score, wildcard_len): (u8, usize) = match pattern_host.strip_prefix("*.") {
+        Some(suffix) => (1, suffix.len()),
+        None => (2, 0),
+    };
 
     let (path_len, exact_bonus) = match pattern_path {
         None => (0, 0),
@@ -57,7 +65,7 @@ fn route_specificity(pattern: &str) -> (u8, usize, u8) {
         }
     };
 
-    (host_score, path_len, exact_bonus)
+    (host_score, wildcard_len, path_len, exact_bonus)
 }
 
 // ---------------------------------------------------------------------------
@@ -66,10 +74,11 @@ fn route_specificity(pattern: &str) -> (u8, usize, u8) {
 
 #[derive(Clone, Debug)]
 struct CompiledRoute {
+    pattern: String,
     host: String,
     path: Option<String>,
     app_id: String,
-    specificity: (u8, usize, u8),
+    specificity: RouteSpecificity,
 }
 
 // ---------------------------------------------------------------------------
@@ -185,6 +194,25 @@ impl Routes {
             .collect()
     }
 
+    /// The live route table in match order (most specific first), for
+    /// debugging how a host resolves.
+    pub fn describe(&self) -> Vec<protocol::RouteInfo> {
+        let compiled = self.compiled.lock().unwrap();
+        let apps = self.apps.lock().unwrap();
+        compiled
+            .iter()
+            .filter_map(|entry| {
+                let app = apps.get(&entry.app_id)?;
+                Some(protocol::RouteInfo {
+                    pattern: entry.pattern.clone(),
+                    app_id: entry.app_id.clone(),
+                    upstream_port: app.upstream_port,
+                    active: app.active,
+                })
+            })
+            .collect()
+    }
+
     pub async fn wait_for_active(&self, app_id: &str, timeout: std::time::Duration) -> bool {
         let notify = {
             let apps = self.apps.lock().unwrap();
@@ -217,6 +245,7 @@ impl Routes {
                 }
                 let (host, path) = split_route_pattern(pattern);
                 entries.push(CompiledRoute {
+                    pattern: pattern.clone(),
                     host: host.to_string(),
                     path: path.map(|p| p.to_string()),
                     app_id: app_id.clone(),
@@ -385,6 +414,141 @@ mod tests {
         assert!(routes.lookup("foo.other.test", "/").is_none());
     }
 
+    #[test]
+    fn describe_lists_routes_in_match_order() {
+        let routes = Routes::default();
+        routes.set_routes(
+            "web".to_string(),
+            vec!["*.app.test".to_string()],
+            3000,
+            true,
+        );
+        routes.set_routes(
+            "api".to_string(),
+            vec!["api.app.test/v1/*".to_string()],
+            4000,
+            false,
+        );
+
+        let described = routes.describe();
+        let summary: Vec<(&str, &str, u16, bool)> = described
+            .iter()
+            .map(|r| {
+                (
+                    r.pattern.as_str(),
+                    r.app_id.as_str(),
+                    r.upstream_port,
+                    r.active,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("api.app.test/v1/*", "api", 4000, false),
+                ("*.app.test", "web", 3000, true),
+            ]
+        );
+
+        routes.remove_app("api");
+        assert_eq!(routes.describe().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn websocket_upgrade_round_trips_through_proxy() {
+        use pingora_core::apps::HttpServerApp;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Minimal WebSocket upstream: check the handshake, switch protocols,
+        // then echo frames back byte for byte.
+        let upstream = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = upstream.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = upstream.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap().to_ascii_lowercase();
+            assert!(request.contains("upgrade: websocket"), "{request}");
+            assert!(
+                request.contains("sec-websocket-key: dghlihnhbxbszsbub25jzq=="),
+                "{request}"
+            );
+            stream
+                .write_all(
+                    b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                      Connection: Upgrade\r\n\
+                      Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n",
+                )
+                .await
+                .unwrap();
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                stream.write_all(&buf[..n]).await.unwrap();
+            }
+        });
+
+        let routes = Routes::default();
+        routes.set_routes("app".to_string(), vec!["app.test".to_string()], port, true);
+        let temp = tempfile::TempDir::new().unwrap();
+        let (events, _events_rx) = tokio::sync::mpsc::unbounded_channel();
+        let proxy = DevProxy {
+            routes,
+            events,
+            channels: crate::dev_channels::DevChannelStore::new(temp.path().join("channels.db")),
+        };
+        let conf = Arc::new(pingora_core::server::configuration::ServerConf::default());
+        let app = Arc::new(pingora_proxy::http_proxy(&conf, proxy));
+
+        let (mut client, server) = tokio::io::duplex(8192);
+        let (_shutdown_tx, shutdown) = tokio::sync::watch::channel(false);
+        tokio::spawn(async move {
+            let session = pingora_core::protocols::http::ServerSession::new_http1(Box::new(server));
+            app.process_new_http(session, &shutdown).await;
+        });
+
+        client
+            .write_all(
+                b"GET /socket HTTP/1.1\r\nHost: app.test\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Version: 13\r\n\
+                  Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !response.ends_with(b"\r\n\r\n") {
+            let n = client.read(&mut buf).await.unwrap();
+            assert!(n > 0, "proxy closed before the handshake finished");
+            response.extend_from_slice(&buf[..n]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"), "{response}");
+        assert!(
+            response.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="),
+            "{response}"
+        );
+
+        // Masked text frame carrying "hi".
+        let frame = [0x81, 0x82, 0x01, 0x02, 0x03, 0x04, b'h' ^ 0x01, b'i' ^ 0x02];
+        client.write_all(&frame).await.unwrap();
+        let mut echoed = [0u8; 8];
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.read_exact(&mut echoed),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(echoed, frame);
+    }
+
     #[test]
     fn activate_with_port_updates_port_and_marks_active() {
         let routes = Routes::default();
@@ -488,6 +652,29 @@ mod tests {
         assert_eq!(hit.0, "wildcard");
     }
 
+    #[test]
+    fn lookup_longer_wildcard_suffix_beats_shorter() {
+        let routes = Routes::default();
+        routes.set_routes(
+            "tenants".to_string(),
+            vec!["*.app.test".to_string()],
+            3001,
+            true,
+        );
+        routes.set_routes(
+            "api".to_string(),
+            vec!["*.api.app.test".to_string()],
+            3002,
+            true,
+        );
+        routes.set_routes("web".to_string(), vec!["app.test".to_string()], 3003, true);
+
+        assert_eq!(routes.lookup("v1.api.app.test", "/").unwrap().0, "api");
+        assert_eq!(routes.lookup("api.app.test", "/").unwrap().0, "tenants");
+        assert_eq!(routes.lookup("app.test", "/").unwrap().0, "web");
+        assert!(routes.lookup("app.other.test", "/").is_none());
+    }
+
     #[test]
     fn lookup_matches_local_alias_of_exact_host_route() {
         let routes = Routes::default();
@@ -567,5 +754,7 @@ mod tests {
         assert!(route_specificity("app.test/api/v1/*") > route_specificity("app.test/api/*"));
         // exact path > wildcard path of same length
         assert!(route_specificity("app.test/api") > route_specificity("app.test/api/*"));
+        // longer wildcard suffix > shorter wildcard suffix
+        assert!(route_specificity("*.api.app.test") > route_specificity("*.app.test/api/*"));
     }
 }
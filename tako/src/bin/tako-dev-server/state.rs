@@ -3,7 +3,7 @@ use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 #[cfg(test)]
 use rusqlite::OptionalExtension;
@@ -42,6 +42,7 @@ struct LogBufferInner {
     next_id: u64,
     capacity: usize,
     subscribers: Vec<mpsc::UnboundedSender<LogEntry>>,
+    last_push: Option<Instant>,
 }
 
 /// Thread-safe, clonable log ring buffer.
@@ -67,6 +68,7 @@ impl LogBuffer {
                 next_id: 0,
                 capacity: LOG_BUFFER_CAPACITY,
                 subscribers: Vec::new(),
+                last_push: None,
             })),
         }
     }
@@ -85,6 +87,7 @@ impl LogBuffer {
             inner.entries.pop_front();
         }
         inner.entries.push_back(entry.clone());
+        inner.last_push = Some(Instant::now());
         inner
             .subscribers
             .retain(|tx| tx.send(entry.clone()).is_ok());
@@ -122,6 +125,11 @@ impl LogBuffer {
         (backlog, rx, truncated)
     }
 
+    /// When the most recent line was pushed, if any. Survives `clear`.
+    pub fn last_push(&self) -> Option<Instant> {
+        self.inner.lock().unwrap().last_push
+    }
+
     /// Clear all entries. Preserves the ID counter so cursor-based resumption
     /// still works across clears. Existing subscribers remain connected.
     pub fn clear(&self) {
@@ -186,6 +194,11 @@ pub fn read_pid_file(project_dir: &str, config_path: &str) -> Option<u32> {
         .ok()
 }
 
+/// Whether a process with this pid exists (signal 0 probes without sending).
+pub fn pid_is_alive(pid: u32) -> bool {
+    unsafe { libc::kill(pid as i32, 0) == 0 }
+}
+
 /// Kill any orphaned app process from a previous server run and clean up
 /// the PID file. Called on startup for each registered project.
 pub fn kill_orphaned_process(project_dir: &str, config_path: &str) {
@@ -196,9 +209,7 @@ pub fn kill_orphaned_process(project_dir: &str, config_path: &str) {
         remove_pid_file(project_dir, config_path);
         return;
     }
-    // Check if the process is still alive.
-    let alive = unsafe { libc::kill(pid as i32, 0) } == 0;
-    if alive {
+    if pid_is_alive(pid) {
         tracing::info!(
             project_dir = %project_dir,
             config_path = %config_path,
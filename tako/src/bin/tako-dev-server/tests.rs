@@ -1,6 +1,6 @@
 use super::process::{
-    build_spawn_env, forward_child_log_line, handle_wake_on_request, kill_all_app_processes,
-    kill_app_process, push_user_action,
+    build_spawn_env, cleanup_stale_apps, forward_child_log_line, handle_wake_on_request,
+    kill_all_app_processes, kill_app_process, push_user_action, resolve_spawn_command,
 };
 use super::redirect::redirect_location;
 use super::*;
@@ -25,7 +25,7 @@ fn test_workflows() -> Arc<tako_workflows::WorkflowManager> {
     Arc::new(tako_workflows::WorkflowManager::new(&tmp))
 }
 
-async fn query_control_clients(state: Arc<Mutex<State>>) -> u32 {
+async fn query_info(state: Arc<Mutex<State>>) -> protocol::DevInfo {
     let (a, b) = tokio::net::UnixStream::pair().unwrap();
     let h = tokio::spawn(async move { handle_client(a, state).await });
 
@@ -39,11 +39,15 @@ async fn query_control_clients(state: Arc<Mutex<State>>) -> u32 {
     h.await.unwrap().unwrap();
 
     match resp {
-        Response::Info { info } => info.control_clients,
+        Response::Info { info } => info,
         other => panic!("unexpected: {other:?}"),
     }
 }
 
+async fn query_control_clients(state: Arc<Mutex<State>>) -> u32 {
+    query_info(state).await.control_clients
+}
+
 #[tokio::test]
 async fn register_app_roundtrip() {
     let (a, b) = tokio::net::UnixStream::pair().unwrap();
@@ -263,6 +267,45 @@ async fn info_reports_connected_control_clients() {
     assert_eq!(clients, 0);
 }
 
+#[tokio::test]
+async fn info_reports_local_forwarding_probe_results() {
+    let https = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed_addr = closed.local_addr().unwrap();
+    drop(closed);
+
+    let (state, _tmp) = test_state();
+    state.lock().unwrap().local_forwarding_probe = Some(LocalForwardingProbe {
+        https: https.local_addr().unwrap(),
+        http: closed_addr,
+    });
+
+    let info = query_info(state).await;
+    assert!(info.local_https_forwarding);
+    assert!(!info.local_http_redirect_forwarding);
+}
+
+#[tokio::test]
+async fn info_reports_no_forwarding_without_dev_loopback_address() {
+    let (state, _tmp) = test_state();
+    assert_eq!(state.lock().unwrap().local_forwarding_probe, None);
+
+    let info = query_info(state).await;
+    assert!(!info.local_https_forwarding);
+    assert!(!info.local_http_redirect_forwarding);
+}
+
+#[test]
+fn local_forwarding_probe_targets_dev_loopback_ports() {
+    let probe = LocalForwardingProbe::for_advertised_ip(bootstrap::DEV_LOOPBACK_ADDR).unwrap();
+    assert_eq!(probe.https.to_string(), "127.77.0.1:443");
+    assert_eq!(probe.http.to_string(), "127.77.0.1:80");
+    assert_eq!(
+        LocalForwardingProbe::for_advertised_ip("192.168.1.10"),
+        None
+    );
+}
+
 /// Helper: create a test State with a temp SQLite DB and return (state, _tmpdir).
 fn test_state() -> (Arc<Mutex<State>>, tempfile::TempDir) {
     let tmp = tempfile::TempDir::new().unwrap();
@@ -369,6 +412,108 @@ async fn unregister_app_broadcasts_stopped_event_to_subscriber() {
     }
 }
 
+#[tokio::test]
+async fn stale_cleanup_broadcasts_registration_expired() {
+    let (state, tmp) = test_state();
+    let project_dir = tmp.path().join("gone");
+    let project_dir = project_dir.to_str().unwrap();
+    insert_test_app(&state, project_dir, "my-app");
+    let config_path = format!("{project_dir}/tako.toml");
+    state
+        .lock()
+        .unwrap()
+        .db
+        .as_ref()
+        .unwrap()
+        .register(&config_path, project_dir, "my-app", None)
+        .unwrap();
+
+    let mut ev_rx = {
+        let s = state.lock().unwrap();
+        s.events.subscribe()
+    };
+
+    assert_eq!(cleanup_stale_apps(&state), vec![config_path.clone()]);
+    assert!(!state.lock().unwrap().apps.contains_key(&config_path));
+
+    let event = tokio::time::timeout(Duration::from_millis(100), ev_rx.recv())
+        .await
+        .expect("should not time out")
+        .unwrap();
+    assert_eq!(
+        event,
+        Response::Event {
+            event: protocol::DevEvent::RegistrationExpired {
+                config_path,
+                app_name: "my-app".to_string(),
+            },
+        }
+    );
+}
+
+async fn resolve_a(dns_addr: std::net::SocketAddr, host: &str) -> (std::net::Ipv4Addr, u32) {
+    use hickory_proto::op::{Message, Query};
+    use hickory_proto::rr::{Name, RData, RecordType};
+
+    let mut query = Message::new();
+    query.set_id(7);
+    query.add_query(Query::query(Name::from_ascii(host).unwrap(), RecordType::A));
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket
+        .send_to(&query.to_vec().unwrap(), dns_addr)
+        .await
+        .unwrap();
+    let mut buf = [0u8; 512];
+    let len = tokio::time::timeout(Duration::from_secs(2), socket.recv(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    let resp = Message::from_vec(&buf[..len]).unwrap();
+    let answer = &resp.answers()[0];
+    match answer.data() {
+        RData::A(a) => (a.0, answer.ttl()),
+        other => panic!("expected A record, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn set_advertised_ip_changes_local_dns_answers() {
+    let (state, _tmp) = test_state();
+    let answer_ip = local_dns::AnswerIp::new("127.77.0.1".parse().unwrap());
+    let dns = local_dns::start("127.0.0.1:0", answer_ip.clone())
+        .await
+        .unwrap();
+    state.lock().unwrap().dns_answer_ip = Some(answer_ip);
+
+    let (ip, ttl) = resolve_a(dns.listen_addr(), "app.test.").await;
+    assert_eq!(ip.to_string(), "127.77.0.1");
+    assert_eq!(ttl, 1);
+
+    let (a, b) = tokio::net::UnixStream::pair().unwrap();
+    let state_for_handler = state.clone();
+    let h = tokio::spawn(async move { handle_client(a, state_for_handler).await });
+    let (r, mut w) = b.into_split();
+    w.write_all(b"{\"type\":\"SetAdvertisedIp\",\"ip\":\"127.0.0.1\"}\n")
+        .await
+        .unwrap();
+    let mut lines = BufReader::new(r).lines();
+    let line = lines.next_line().await.unwrap().unwrap();
+    let resp: Response = serde_json::from_str(&line).unwrap();
+    assert_eq!(
+        resp,
+        Response::AdvertisedIpSet {
+            ip: "127.0.0.1".to_string()
+        }
+    );
+    drop(w);
+    drop(lines);
+    h.await.unwrap().unwrap();
+
+    let (ip, _) = resolve_a(dns.listen_addr(), "app.test.").await;
+    assert_eq!(ip.to_string(), "127.0.0.1");
+    assert_eq!(state.lock().unwrap().advertised_ip, "127.0.0.1");
+}
+
 #[tokio::test]
 async fn restart_app_responds_with_app_restarting() {
     let (state, _tmp) = test_state();
@@ -402,6 +547,80 @@ async fn restart_app_responds_with_app_restarting() {
     h.await.unwrap().unwrap();
 }
 
+#[tokio::test]
+async fn hello_reports_server_version_and_protocol() {
+    let (state, _tmp) = test_state();
+
+    let (a, b) = tokio::net::UnixStream::pair().unwrap();
+    let h = tokio::spawn(async move { handle_client(a, state).await });
+
+    let (r, mut w) = b.into_split();
+    w.write_all(b"{\"type\":\"Hello\",\"client_version\":\"0.0.1\"}\n")
+        .await
+        .unwrap();
+    let mut lines = BufReader::new(r).lines();
+    let line = lines.next_line().await.unwrap().unwrap();
+    let resp: Response = serde_json::from_str(&line).unwrap();
+    assert_eq!(
+        resp,
+        Response::Hello {
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol: tako::dev::DEV_PROTOCOL_VERSION,
+        }
+    );
+
+    drop(w);
+    drop(lines);
+    h.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn describe_routes_reports_hosts_ports_and_active_flags() {
+    let (state, _tmp) = test_state();
+    insert_test_app(&state, "/web", "web");
+    insert_test_app(&state, "/api", "api");
+    {
+        let s = state.lock().unwrap();
+        s.routes.activate_with_port("reg:/web/tako.toml", 4101);
+        s.routes.set_active("reg:/api/tako.toml", false);
+    }
+
+    let (a, b) = tokio::net::UnixStream::pair().unwrap();
+    let h = tokio::spawn(async move { handle_client(a, state).await });
+
+    let (r, mut w) = b.into_split();
+    w.write_all(b"{\"type\":\"DescribeRoutes\"}\n")
+        .await
+        .unwrap();
+    let mut lines = BufReader::new(r).lines();
+    let line = lines.next_line().await.unwrap().unwrap();
+    let Response::Routes { mut routes } = serde_json::from_str(&line).unwrap() else {
+        panic!("expected Routes, got: {line}");
+    };
+    routes.sort_by(|a, b| a.pattern.cmp(&b.pattern));
+    assert_eq!(
+        routes,
+        vec![
+            protocol::RouteInfo {
+                pattern: "api.test".to_string(),
+                app_id: "reg:/api/tako.toml".to_string(),
+                upstream_port: 3000,
+                active: false,
+            },
+            protocol::RouteInfo {
+                pattern: "web.test".to_string(),
+                app_id: "reg:/web/tako.toml".to_string(),
+                upstream_port: 4101,
+                active: true,
+            },
+        ]
+    );
+
+    drop(w);
+    drop(lines);
+    h.await.unwrap().unwrap();
+}
+
 #[tokio::test]
 async fn subscribe_logs_streams_backlog_and_live_entries() {
     let (state, _tmp) = test_state();
@@ -486,6 +705,54 @@ async fn subscribe_logs_streams_backlog_and_live_entries() {
     h.await.unwrap().unwrap();
 }
 
+#[tokio::test]
+async fn subscribe_logs_without_follow_ends_after_backlog() {
+    let (state, _tmp) = test_state();
+    insert_test_app(&state, "/proj", "my-app");
+    {
+        let s = state.lock().unwrap();
+        let app = s.apps.get("/proj/tako.toml").unwrap();
+        for message in ["line-1", "line-2", "line-3"] {
+            app.log_buffer.push(format!(
+                r#"{{"timestamp":"00:00:01","level":"Info","scope":"app","message":"{message}"}}"#
+            ));
+        }
+    }
+
+    let (a, b) = tokio::net::UnixStream::pair().unwrap();
+    let state_for_handler = state.clone();
+    let h = tokio::spawn(async move { handle_client(a, state_for_handler).await });
+
+    let (r, mut w) = b.into_split();
+    let mut lines = BufReader::new(r).lines();
+
+    let req = serde_json::json!({
+        "type": "SubscribeLogs",
+        "config_path": "/proj/tako.toml",
+        "follow": false,
+    });
+    w.write_all(req.to_string().as_bytes()).await.unwrap();
+    w.write_all(b"\n").await.unwrap();
+
+    let line = lines.next_line().await.unwrap().unwrap();
+    let resp: Response = serde_json::from_str(&line).unwrap();
+    assert!(matches!(resp, Response::LogsSubscribed));
+
+    let mut messages = Vec::new();
+    while let Some(line) = lines.next_line().await.unwrap() {
+        match serde_json::from_str::<Response>(&line).unwrap() {
+            Response::LogEntry { line, .. } => messages.push(line),
+            other => panic!("expected LogEntry, got: {other:?}"),
+        }
+    }
+    assert_eq!(messages.len(), 3);
+    for (line, expected) in messages.iter().zip(["line-1", "line-2", "line-3"]) {
+        assert!(line.contains(expected), "{line}");
+    }
+
+    h.await.unwrap().unwrap();
+}
+
 #[tokio::test]
 async fn subscribe_logs_returns_error_for_unknown_app() {
     let (state, _tmp) = test_state();
@@ -1356,3 +1623,134 @@ fn build_spawn_env_contract_wins_over_user_env() {
     // Unrelated user env passes through untouched.
     assert_eq!(env.get("FOO").map(String::as_str), Some("bar"));
 }
+
+#[test]
+fn resolve_spawn_command_expands_port_to_reserved_concrete_port() {
+    let mut app = runtime_app_with_env("demo", std::collections::HashMap::new());
+    app.command = vec![
+        "run".to_string(),
+        "--port".to_string(),
+        "{port}".to_string(),
+    ];
+
+    let (command, env) = resolve_spawn_command(&app, None).unwrap();
+
+    let port: u16 = command[2]
+        .parse()
+        .expect("port placeholder expands to a number");
+    assert_ne!(port, 0);
+    assert_eq!(command[..2], ["run", "--port"]);
+    assert_eq!(env.get("PORT"), Some(&command[2]));
+}
+
+#[test]
+fn resolve_spawn_command_expands_host_inside_args() {
+    let mut app = runtime_app_with_env("demo", std::collections::HashMap::new());
+    app.command = vec!["serve".to_string(), "--listen={host}".to_string()];
+
+    let (command, env) = resolve_spawn_command(&app, None).unwrap();
+
+    assert_eq!(command, ["serve", "--listen=127.0.0.1"]);
+    // Without `{port}` the app keeps binding an ephemeral port.
+    assert_eq!(env.get("PORT").map(String::as_str), Some("0"));
+}
+
+#[test]
+fn parse_args_reads_idle_exit_delay() {
+    let args = |list: &[&str]| {
+        bootstrap::parse_args_from(list.iter().map(|arg| arg.to_string())).idle_exit_delay
+    };
+    assert_eq!(args(&[]), Some(bootstrap::DEFAULT_IDLE_EXIT_DELAY));
+    assert_eq!(
+        args(&["--idle-exit-ms", "30000"]),
+        Some(std::time::Duration::from_secs(30))
+    );
+    assert_eq!(args(&["--idle-exit-ms", "0"]), None);
+    assert_eq!(
+        args(&["--idle-exit-ms", "soon"]),
+        Some(bootstrap::DEFAULT_IDLE_EXIT_DELAY)
+    );
+}
+
+#[tokio::test]
+async fn zero_idle_exit_delay_keeps_server_running() {
+    let new_state = |idle_exit_delay| {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let mut st = State::new(
+            shutdown_tx,
+            proxy::Routes::default(),
+            EventsHub::default(),
+            true,
+            53535,
+            8443,
+            "127.0.0.1:8443".to_string(),
+            "127.0.0.1".to_string(),
+        );
+        st.idle_exit_delay = idle_exit_delay;
+        (st, shutdown_rx)
+    };
+
+    let (mut disabled, disabled_rx) = new_state(None);
+    let (mut enabled, enabled_rx) = new_state(Some(std::time::Duration::from_millis(10)));
+    disabled.schedule_idle_exit();
+    enabled.schedule_idle_exit();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    assert!(!*disabled_rx.borrow());
+    assert!(*enabled_rx.borrow());
+}
+
+#[tokio::test]
+async fn health_reports_liveness_and_growing_log_age() {
+    let (state, _tmp) = test_state();
+    insert_test_app(&state, "/proj", "my-app");
+
+    let health = |state: Arc<Mutex<State>>| async move {
+        let (a, b) = tokio::net::UnixStream::pair().unwrap();
+        let h = tokio::spawn(async move { handle_client(a, state).await });
+        let (r, mut w) = b.into_split();
+        let mut lines = BufReader::new(r).lines();
+        w.write_all(b"{\"type\":\"Health\"}\n").await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        drop(w);
+        h.await.unwrap().unwrap();
+        match serde_json::from_str::<Response>(&line).unwrap() {
+            Response::Health { mut apps } => apps.remove(0),
+            other => panic!("expected Health, got: {other:?}"),
+        }
+    };
+
+    let app = health(state.clone()).await;
+    assert_eq!(app.app_name, "my-app");
+    assert!(!app.alive);
+    assert_eq!(app.last_log_ms_ago, None);
+
+    {
+        let s = state.lock().unwrap();
+        s.apps
+            .get("/proj/tako.toml")
+            .unwrap()
+            .log_buffer
+            .push("line".to_string());
+    }
+    let first = health(state.clone()).await.last_log_ms_ago.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let second = health(state.clone()).await.last_log_ms_ago.unwrap();
+    assert!(second >= first + 50, "{first} -> {second}");
+
+    let mut child = std::process::Command::new("sleep")
+        .arg("60")
+        .spawn()
+        .unwrap();
+    state
+        .lock()
+        .unwrap()
+        .apps
+        .get_mut("/proj/tako.toml")
+        .unwrap()
+        .pid = Some(child.id());
+    assert!(health(state.clone()).await.alive);
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(!health(state.clone()).await.alive);
+}
@@ -1,6 +1,7 @@
 use std::path::{Component, Path, PathBuf};
 
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use sha2::{Digest, Sha256};
 
 use super::BuildError;
 
@@ -357,9 +358,19 @@ pub fn create_workdir_archive(
     let mut archive = tar::Builder::new(encoder);
     archive.follow_symlinks(false);
 
-    for (full_path, relative_path) in files {
+    for (full_path, relative_path) in &files {
+        if relative_path == Path::new("app.json") {
+            let contents = app_manifest_with_file_digests(full_path, &files)?;
+            let mut header = tar::Header::new_gnu();
+            header.set_metadata(&std::fs::metadata(full_path)?);
+            header.set_size(contents.len() as u64);
+            archive
+                .append_data(&mut header, relative_path, contents.as_slice())
+                .map_err(|e| BuildError::ArchiveError(format!("Failed to add app.json: {}", e)))?;
+            continue;
+        }
         archive
-            .append_path_with_name(&full_path, &relative_path)
+            .append_path_with_name(full_path, relative_path)
             .map_err(|e| {
                 BuildError::ArchiveError(format!("Failed to add {}: {}", full_path.display(), e))
             })?;
@@ -376,6 +387,37 @@ pub fn create_workdir_archive(
     Ok(metadata.len())
 }
 
+/// Return the deploy manifest with a `files` map (archive path → SHA-256 hex)
+/// covering every other archived file, so the server can verify the upload.
+/// A manifest that isn't a JSON object is passed through unchanged.
+fn app_manifest_with_file_digests(
+    manifest_path: &Path,
+    files: &[(PathBuf, PathBuf)],
+) -> Result<Vec<u8>, BuildError> {
+    let raw = std::fs::read(manifest_path)?;
+    let Ok(serde_json::Value::Object(mut manifest)) = serde_json::from_slice(&raw) else {
+        return Ok(raw);
+    };
+
+    let mut digests = serde_json::Map::new();
+    for (full_path, relative_path) in files {
+        if relative_path == Path::new("app.json") {
+            continue;
+        }
+        let mut file = std::fs::File::open(full_path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        digests.insert(
+            relative_path.to_string_lossy().replace('\\', "/"),
+            serde_json::Value::String(hex::encode(hasher.finalize())),
+        );
+    }
+    manifest.insert("files".to_string(), serde_json::Value::Object(digests));
+
+    serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| BuildError::ArchiveError(format!("Failed to encode app.json: {}", e)))
+}
+
 fn should_workdir_force_exclude(relative_path: &Path) -> bool {
     let mut previous_component: Option<&str> = None;
     for component in relative_path.components() {
@@ -561,6 +603,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn workdir_archive_records_file_digests_in_app_json() {
+        let temp = TempDir::new().unwrap();
+        let workdir = temp.path().join("workdir");
+        let archive = temp.path().join("out.tar.zst");
+        let dest = temp.path().join("dest");
+
+        fs::create_dir_all(workdir.join("dist")).unwrap();
+        fs::write(workdir.join("dist/index.js"), "ok").unwrap();
+        fs::write(workdir.join("app.json"), r#"{"main":"index.ts"}"#).unwrap();
+
+        create_workdir_archive(&workdir, &archive, &[], &[]).unwrap();
+
+        BuildExecutor::extract_archive(&archive, &dest).unwrap();
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dest.join("app.json")).unwrap()).unwrap();
+        assert_eq!(manifest["main"], "index.ts");
+        assert_eq!(
+            manifest["files"],
+            serde_json::json!({
+                "dist/index.js": hex::encode(Sha256::digest(b"ok")),
+            })
+        );
+    }
+
     #[test]
     fn workdir_archive_excludes_next_cache_and_turbo() {
         let temp = TempDir::new().unwrap();
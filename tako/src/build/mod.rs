@@ -14,6 +14,7 @@ pub mod go;
 pub mod js;
 mod preset;
 pub(crate) mod preset_cache;
+mod runtime_version;
 mod workdir;
 
 pub use adapter::*;
@@ -22,4 +23,5 @@ pub use cache::*;
 pub use container::*;
 pub use executor::*;
 pub use preset::*;
+pub use runtime_version::*;
 pub use workdir::*;
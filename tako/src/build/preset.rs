@@ -42,6 +42,10 @@ pub struct AppPreset {
     /// Custom dev command (overrides runtime default in `tako dev`).
     #[serde(default)]
     pub dev: Vec<String>,
+    /// Semver requirement for the runtime toolchain (e.g. `"^1.2"`), checked
+    /// against `<runtime> --version` when dev and deploy start.
+    #[serde(default)]
+    pub runtime_version: Option<String>,
     /// Per-runtime overrides keyed by `BuildAdapter::id` (e.g. `"bun"`).
     /// When the active runtime has an entry, its fields take precedence over
     /// the preset defaults above. Missing fields in an override fall through
@@ -60,7 +64,7 @@ pub struct AppPresetRuntimeOverride {
 /// Backward-compatible alias.
 pub type BuildPreset = AppPreset;
 
-const KNOWN_PRESET_FIELDS: &[&str] = &["name", "main", "assets", "dev"];
+const KNOWN_PRESET_FIELDS: &[&str] = &["name", "main", "assets", "dev", "runtime_version"];
 const KNOWN_RUNTIME_OVERRIDE_FIELDS: &[&str] = &["dev"];
 
 #[derive(Debug, Clone, Deserialize)]
@@ -73,6 +77,8 @@ struct AppPresetRaw {
     assets: Vec<String>,
     #[serde(default)]
     dev: Vec<String>,
+    #[serde(default)]
+    runtime_version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -170,6 +176,9 @@ pub(super) fn parse_resolved_preset_from_content(
         PresetReference::OfficialAlias { name, .. } => {
             parse_official_alias_preset_content(name, path, content)
         }
+        PresetReference::Github { .. } => {
+            parse_and_validate_preset(content, &parsed_ref.display_name())
+        }
     }
 }
 
@@ -260,7 +269,7 @@ pub fn parse_and_validate_preset(content: &str, inferred_name: &str) -> Result<A
             continue;
         }
         tracing::warn!(
-            "Preset has unknown field '{key}' — only name, main, assets, dev, and [<runtime>] sub-tables are supported",
+            "Preset has unknown field '{key}' — only name, main, assets, dev, runtime_version, and [<runtime>] sub-tables are supported",
         );
     }
 
@@ -279,11 +288,21 @@ pub fn parse_and_validate_preset(content: &str, inferred_name: &str) -> Result<A
         );
     }
 
+    let runtime_version = raw
+        .runtime_version
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    if let Some(constraint) = &runtime_version {
+        super::parse_runtime_version_constraint(constraint)
+            .map_err(|e| format!("Preset '{name}' has an invalid runtime_version: {e}"))?;
+    }
+
     Ok(AppPreset {
         name,
         main: raw.main,
         assets: raw.assets,
         dev: raw.dev,
+        runtime_version,
         runtime_overrides,
     })
 }
@@ -589,6 +608,27 @@ assets = ["dist/client"]
         assert_eq!(preset.assets, vec!["dist/client".to_string()]);
     }
 
+    #[test]
+    fn parse_and_validate_preset_reads_runtime_version() {
+        let raw = r#"
+name = "bun"
+runtime_version = "^1.2"
+"#;
+        let preset = parse_preset(raw).unwrap();
+        assert_eq!(preset.runtime_version.as_deref(), Some("^1.2"));
+        assert!(preset.runtime_overrides.is_empty());
+    }
+
+    #[test]
+    fn parse_and_validate_preset_rejects_invalid_runtime_version() {
+        let raw = r#"
+name = "bun"
+runtime_version = "latest"
+"#;
+        let err = parse_preset(raw).unwrap_err();
+        assert!(err.contains("invalid runtime_version"));
+    }
+
     #[test]
     fn parse_and_validate_preset_collects_runtime_override_dev() {
         let raw = r#"
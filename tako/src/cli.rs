@@ -23,6 +23,14 @@ fn format_display_version(base: &str, build_sha: Option<&str>) -> String {
     format!("{base}-{short_sha}")
 }
 
+fn print_version() {
+    if crate::output::is_json() {
+        crate::output::json_data("version", display_version());
+    } else {
+        println!("{}", display_version());
+    }
+}
+
 /// Tako - Modern application development, deployment, and runtime platform
 #[derive(Parser)]
 #[command(name = "tako")]
@@ -45,6 +53,15 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub dry_run: bool,
 
+    /// Print one JSON object (status, data, error) to stdout instead of
+    /// formatted output; never prompts
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Refetch build presets instead of reusing the local preset cache
+    #[arg(long, global = true)]
+    pub refresh_presets: bool,
+
     /// Use an explicit config name/path instead of ./tako.toml (`.toml` suffix optional)
     #[arg(short = 'c', long, global = true, value_name = "CONFIG")]
     pub config: Option<std::path::PathBuf>,
@@ -191,7 +208,8 @@ mod tests {
     #[test]
     fn servers_status_without_name_parses() {
         let cli = Cli::try_parse_from(["tako", "servers", "status"]).unwrap();
-        let Commands::Servers(server::ServerCommands::Status) = cli.command.expect("command")
+        let Commands::Servers(server::ServerCommands::Status { server: None }) =
+            cli.command.expect("command")
         else {
             panic!("expected Servers::Status");
         };
@@ -209,6 +227,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn servers_status_accepts_server_flag() {
+        let cli = Cli::try_parse_from(["tako", "servers", "status", "--server", "10.0.0.5:2222"])
+            .unwrap();
+        let Commands::Servers(server::ServerCommands::Status { server }) =
+            cli.command.expect("command")
+        else {
+            panic!("expected Servers::Status");
+        };
+        assert_eq!(server.as_deref(), Some("10.0.0.5:2222"));
+    }
+
+    #[test]
+    fn ca_trust_and_untrust_parse() {
+        let cli = Cli::try_parse_from(["tako", "ca", "trust"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Ca(commands::ca::CaCommands::Trust))
+        ));
+
+        let cli = Cli::try_parse_from(["tako", "ca", "untrust"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Ca(commands::ca::CaCommands::Untrust))
+        ));
+    }
+
+    #[test]
+    fn ca_renew_parses_root_and_yes() {
+        let cli = Cli::try_parse_from(["tako", "ca", "renew"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Ca(commands::ca::CaCommands::Renew {
+                root: false,
+                yes: false
+            }))
+        ));
+
+        let cli = Cli::try_parse_from(["tako", "ca", "renew", "--root", "-y"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Ca(commands::ca::CaCommands::Renew {
+                root: true,
+                yes: true
+            }))
+        ));
+    }
+
+    #[test]
+    fn doctor_fix_flag_defaults_off() {
+        let cli = Cli::try_parse_from(["tako", "doctor"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Doctor { fix: false })));
+
+        let cli = Cli::try_parse_from(["tako", "doctor", "--fix"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Doctor { fix: true })));
+    }
+
     #[test]
     fn servers_setup_wildcard_parses_without_env() {
         let cli = Cli::try_parse_from(["tako", "servers", "setup-wildcard"]).unwrap();
@@ -336,6 +411,49 @@ mod tests {
         assert_eq!(env.as_deref(), Some("staging"));
     }
 
+    #[test]
+    fn deploy_parses_canary_percentage_in_range() {
+        let cli = Cli::try_parse_from(["tako", "deploy", "--canary", "10"]).unwrap();
+        let Some(Commands::Deploy { canary, .. }) = cli.command else {
+            panic!("expected Deploy");
+        };
+        assert_eq!(canary, Some(10));
+
+        assert!(Cli::try_parse_from(["tako", "deploy", "--canary", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tako", "deploy", "--canary", "100"]).is_err());
+    }
+
+    #[test]
+    fn build_parses_output_env_and_target() {
+        let cli = Cli::try_parse_from([
+            "tako",
+            "build",
+            "--output",
+            "dist/release.tar.gz",
+            "--env",
+            "staging",
+            "--target",
+            "aarch64-musl",
+        ])
+        .unwrap();
+        let Some(Commands::Build {
+            output,
+            env,
+            target,
+        }) = cli.command
+        else {
+            panic!("expected Build");
+        };
+        assert_eq!(output, std::path::PathBuf::from("dist/release.tar.gz"));
+        assert_eq!(env.as_deref(), Some("staging"));
+        assert_eq!(target.as_deref(), Some("aarch64-musl"));
+    }
+
+    #[test]
+    fn build_requires_output() {
+        assert!(Cli::try_parse_from(["tako", "build"]).is_err());
+    }
+
     #[test]
     fn scale_parses_instances_and_env() {
         let cli = Cli::try_parse_from(["tako", "scale", "3", "--env", "staging"]).unwrap();
@@ -401,6 +519,15 @@ mod tests {
         assert!(yes);
     }
 
+    #[test]
+    fn config_validate_parses() {
+        let cli = Cli::try_parse_from(["tako", "config", "validate"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Config(commands::config::ConfigCommands::Validate))
+        ));
+    }
+
     #[test]
     fn releases_list_parses() {
         let cli = Cli::try_parse_from(["tako", "releases", "ls"]).unwrap();
@@ -428,11 +555,33 @@ mod tests {
         else {
             panic!("expected Releases::Rollback");
         };
-        assert_eq!(release, "abc1234");
+        assert_eq!(release.as_deref(), Some("abc1234"));
         assert!(env.is_none());
         assert!(yes);
     }
 
+    #[test]
+    fn releases_rollback_without_release_id_targets_previous_release() {
+        let cli = Cli::try_parse_from(["tako", "releases", "rollback"]).unwrap();
+        let Some(Commands::Releases(releases::ReleaseCommands::Rollback { release, .. })) =
+            cli.command
+        else {
+            panic!("expected Releases::Rollback");
+        };
+        assert!(release.is_none());
+    }
+
+    #[test]
+    fn history_parses_env_and_limit() {
+        let cli =
+            Cli::try_parse_from(["tako", "history", "--env", "staging", "--limit", "5"]).unwrap();
+        let Some(Commands::History { env, limit }) = cli.command else {
+            panic!("expected History");
+        };
+        assert_eq!(env.as_deref(), Some("staging"));
+        assert_eq!(limit, Some(5));
+    }
+
     #[test]
     fn delete_without_env_parses_env_as_none() {
         let cli = Cli::try_parse_from(["tako", "delete"]).unwrap();
@@ -539,6 +688,36 @@ mod tests {
         assert_eq!(args.variant.as_deref(), Some("foo"));
     }
 
+    #[test]
+    fn dev_logs_parses_filters() {
+        let cli = Cli::try_parse_from([
+            "tako", "dev", "logs", "--since", "10m", "--grep", "timeout", "--level", "warn",
+            "--scope", "app", "-f",
+        ])
+        .unwrap();
+        let Commands::Dev { command, .. } = cli.command.expect("command") else {
+            panic!("expected Dev");
+        };
+        match command {
+            Some(DevSubcommands::Logs {
+                name,
+                since,
+                grep,
+                level,
+                scope,
+                follow,
+            }) => {
+                assert!(name.is_none());
+                assert_eq!(since.as_deref(), Some("10m"));
+                assert_eq!(grep.as_deref(), Some("timeout"));
+                assert_eq!(level.as_deref(), Some("warn"));
+                assert_eq!(scope.as_deref(), Some("app"));
+                assert!(follow);
+            }
+            other => panic!("expected Logs, got {other:?}"),
+        }
+    }
+
     #[test]
     fn dev_stop_parses() {
         let cli = Cli::try_parse_from(["tako", "dev", "stop"]).unwrap();
@@ -584,6 +763,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dev_restart_parses_optional_name() {
+        for (args, expected) in [
+            (&["tako", "dev", "restart"][..], None),
+            (&["tako", "dev", "restart", "my-app"][..], Some("my-app")),
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            let Commands::Dev { command, .. } = cli.command.expect("command") else {
+                panic!("expected Dev");
+            };
+            match command {
+                Some(DevSubcommands::Restart { name }) => assert_eq!(name.as_deref(), expected),
+                other => panic!("expected Restart, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn dev_clean_parses_certs_and_yes() {
+        let cli = Cli::try_parse_from(["tako", "dev", "clean", "--certs", "-y"]).unwrap();
+        let Commands::Dev { command, .. } = cli.command.expect("command") else {
+            panic!("expected Dev");
+        };
+        assert!(matches!(
+            command,
+            Some(DevSubcommands::Clean {
+                certs: true,
+                yes: true
+            })
+        ));
+    }
+
     #[test]
     fn dev_ls_parses() {
         let cli = Cli::try_parse_from(["tako", "dev", "ls"]).unwrap();
@@ -644,6 +855,31 @@ mod tests {
         assert!(cli.ci);
     }
 
+    #[test]
+    fn json_flag_parses_globally() {
+        let cli = Cli::try_parse_from(["tako", "version", "--json"]).unwrap();
+        assert!(cli.json);
+    }
+
+    #[test]
+    fn version_in_json_mode_reports_version_data() {
+        let _lock = crate::output::test_json_lock();
+        let cli = Cli::try_parse_from(["tako", "--json", "version"]).unwrap();
+        crate::output::set_json(cli.json);
+        let _ = crate::output::take_json_report(Ok(()));
+
+        let result = cli.run().map_err(|e| e.to_string());
+        let report = crate::output::take_json_report(result);
+        crate::output::set_json(false);
+
+        let rendered = serde_json::to_string(&report).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(parsed["status"], "ok");
+        assert_eq!(parsed["data"]["version"], display_version());
+        assert!(parsed["error"].is_null());
+        assert!(parsed["messages"].is_array());
+    }
+
     #[test]
     fn config_flag_parses_globally_before_subcommand() {
         let cli = Cli::try_parse_from(["tako", "--config", "configs/preview", "deploy"]).unwrap();
@@ -763,6 +999,10 @@ pub struct DevArgs {
     /// Run a variant of the app (e.g. --variant foo → myapp-foo.test)
     #[arg(long, visible_alias = "var")]
     pub variant: Option<String>,
+    /// Also run the app for this tako.toml (or its directory) in the same
+    /// session; repeatable
+    #[arg(long = "with", value_name = "CONFIG")]
+    pub with: Vec<std::path::PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -775,9 +1015,43 @@ pub enum DevSubcommands {
         #[arg(long)]
         all: bool,
     },
+    /// Restart a dev app's process
+    Restart {
+        /// App name (defaults to current directory's app)
+        name: Option<String>,
+    },
     /// List registered dev apps
     #[command(visible_alias = "list")]
     Ls,
+    /// Show a dev app's stored logs, optionally filtered
+    Logs {
+        /// App name (defaults to current directory's app)
+        name: Option<String>,
+        /// Only lines since a duration ago (30s, 10m, 2h, 1d) or a local time (14:05)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only lines whose message or scope contains this text (case-insensitive)
+        #[arg(long)]
+        grep: Option<String>,
+        /// Only lines at this level or more severe (debug, info, warn, error, fatal)
+        #[arg(long)]
+        level: Option<String>,
+        /// Only lines from this scope (e.g. app, tako)
+        #[arg(long)]
+        scope: Option<String>,
+        /// Keep printing new lines as they are logged
+        #[arg(short = 'f', long)]
+        follow: bool,
+    },
+    /// Remove system changes made by dev setup (proxy, DNS, loopback alias)
+    Clean {
+        /// Also remove CA trust and the local CA / dev certificates
+        #[arg(long)]
+        certs: bool,
+        /// Skip confirmation prompts
+        #[arg(short = 'y', long = "yes")]
+        yes: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -811,7 +1085,19 @@ pub enum Commands {
     },
 
     /// Print a local diagnostic report
-    Doctor,
+    Doctor {
+        /// Run the setup steps for any failing checks
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Local development CA commands
+    #[command(subcommand)]
+    Ca(commands::ca::CaCommands),
+
+    /// tako.toml commands
+    #[command(subcommand)]
+    Config(commands::config::ConfigCommands),
 
     /// Server management commands
     #[command(subcommand)]
@@ -825,6 +1111,17 @@ pub enum Commands {
     #[command(subcommand)]
     Releases(releases::ReleaseCommands),
 
+    /// Show recent deploy attempts for the current app
+    History {
+        /// Environment to query (defaults to production)
+        #[arg(long)]
+        env: Option<String>,
+
+        /// Number of entries to show per server (default: 10)
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+
     /// Upgrade the local tako CLI to the latest version
     Upgrade,
 
@@ -837,6 +1134,26 @@ pub enum Commands {
         /// Skip confirmation prompts
         #[arg(short = 'y', long = "yes")]
         yes: bool,
+
+        /// Send this percentage of traffic (1-99) to the new build for a
+        /// hold period before it takes over
+        #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(1..=99))]
+        canary: Option<u8>,
+    },
+
+    /// Build a deployable release tarball without deploying it
+    Build {
+        /// Path of the .tar.gz to write
+        #[arg(long, short = 'o', value_name = "TARBALL")]
+        output: std::path::PathBuf,
+
+        /// Environment to build for (defaults to production)
+        #[arg(long)]
+        env: Option<String>,
+
+        /// Linux target, e.g. x86_64-glibc or aarch64-musl (defaults to this machine's architecture on glibc)
+        #[arg(long)]
+        target: Option<String>,
     },
 
     /// Delete a deployed app from a specific environment/server deployment
@@ -891,7 +1208,7 @@ pub enum Commands {
 impl Cli {
     pub fn run(self) -> Result<(), Box<dyn std::error::Error>> {
         if self.version {
-            println!("{}", display_version());
+            print_version();
             return Ok(());
         }
 
@@ -903,7 +1220,7 @@ impl Cli {
 
         match command {
             Commands::Version => {
-                println!("{}", display_version());
+                print_version();
                 Ok(())
             }
             Commands::Init => commands::init::run(self.config.as_deref()),
@@ -918,26 +1235,62 @@ impl Cli {
                         DEV_PUBLIC_PORT,
                         args.variant,
                         self.config.as_deref(),
+                        &args.with,
                     )),
                     Some(DevSubcommands::Stop { name, all }) => {
                         rt.block_on(commands::dev::stop(name, all, self.config.as_deref()))
                     }
+                    Some(DevSubcommands::Restart { name }) => {
+                        rt.block_on(commands::dev::restart(name, self.config.as_deref()))
+                    }
                     Some(DevSubcommands::Ls) => rt.block_on(commands::dev::ls()),
+                    Some(DevSubcommands::Logs {
+                        name,
+                        since,
+                        grep,
+                        level,
+                        scope,
+                        follow,
+                    }) => rt.block_on(commands::dev::logs(
+                        name,
+                        since.as_deref(),
+                        grep,
+                        level.as_deref(),
+                        scope,
+                        follow,
+                        self.config.as_deref(),
+                    )),
+                    Some(DevSubcommands::Clean { certs, yes }) => commands::dev::clean(certs, yes),
                 }
             }
-            Commands::Doctor => {
+            Commands::Doctor { fix } => {
                 let rt = tokio::runtime::Runtime::new()?;
-                rt.block_on(commands::doctor::run())
+                rt.block_on(commands::doctor::run(fix))
             }
+            Commands::Ca(cmd) => commands::ca::run(cmd),
+            Commands::Config(cmd) => commands::config::run(cmd, self.config.as_deref()),
             Commands::Servers(cmd) => server::run(cmd),
             Commands::Secrets(cmd) => secret::run(cmd, self.config.as_deref()),
             Commands::Releases(cmd) => releases::run(cmd, self.config.as_deref()),
+            Commands::History { env, limit } => {
+                commands::history::run(env.as_deref(), limit, self.config.as_deref())
+            }
             Commands::Upgrade => upgrade::run(),
             Commands::Implode { yes } => commands::implode::run(yes),
             Commands::Typegen => commands::typegen::run(self.config.as_deref()),
-            Commands::Deploy { env, yes } => {
-                commands::deploy::run(env.as_deref(), yes, self.config.as_deref())
+            Commands::Deploy { env, yes, canary } => {
+                commands::deploy::run(env.as_deref(), yes, canary, self.config.as_deref())
             }
+            Commands::Build {
+                output,
+                env,
+                target,
+            } => commands::build::run(
+                &output,
+                env.as_deref(),
+                target.as_deref(),
+                self.config.as_deref(),
+            ),
             Commands::Delete { env, server, yes } => delete::run(
                 env.as_deref(),
                 server.as_deref(),
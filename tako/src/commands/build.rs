@@ -0,0 +1,387 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::commands::deploy::{
+    LocalRelease, build_local_release, default_release_target, parse_release_target,
+};
+use crate::output;
+
+/// Release manifest written as the last entry of a `tako build` tarball.
+pub const RELEASE_MANIFEST_NAME: &str = "tako-release.json";
+/// Project-level ignore file (gitignore syntax) applied when packaging.
+pub const TAKOIGNORE_FILE: &str = ".takoignore";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+    pub app: String,
+    pub env: String,
+    pub version: String,
+    pub target: String,
+    /// Number of packaged files (excluding this manifest).
+    pub files: usize,
+    /// `sha256:<hex>` over every packaged file, see [`update_release_digest`].
+    pub digest: String,
+}
+
+pub fn run(
+    output_path: &Path,
+    env: Option<&str>,
+    target: Option<&str>,
+    config_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let target = match target {
+        Some(value) => parse_release_target(value)?,
+        None => default_release_target()?,
+    };
+    let rt = tokio::runtime::Runtime::new()?;
+    let Some(release) = rt.block_on(build_local_release(env, target, config_path))? else {
+        output::dry_run_skip(&format!("Write {}", output_path.display()));
+        return Ok(());
+    };
+
+    let ignore = TakoIgnore::load(&release.project_dir, &release.app_dir)?;
+    let manifest = output::with_spinner_silent("Packaging release", || {
+        write_release_tarball(&release, &ignore, output_path)
+    })?;
+
+    output::success(&format!(
+        "Wrote {} ({} files, {})",
+        output::strong(&output_path.display().to_string()),
+        manifest.files,
+        manifest.digest
+    ));
+    Ok(())
+}
+
+/// `.takoignore` patterns, rooted at the project directory.
+pub struct TakoIgnore {
+    matcher: Option<Gitignore>,
+    /// Project directory relative to the archive root.
+    app_dir: PathBuf,
+}
+
+impl TakoIgnore {
+    pub fn load(project_dir: &Path, app_dir: &str) -> Result<Self, String> {
+        let path = project_dir.join(TAKOIGNORE_FILE);
+        let matcher = if path.is_file() {
+            let mut builder = GitignoreBuilder::new(project_dir);
+            if let Some(err) = builder.add(&path) {
+                return Err(format!("Failed to read {}: {err}", path.display()));
+            }
+            Some(
+                builder
+                    .build()
+                    .map_err(|e| format!("Invalid {}: {e}", path.display()))?,
+            )
+        } else {
+            None
+        };
+        Ok(Self {
+            matcher,
+            app_dir: PathBuf::from(app_dir),
+        })
+    }
+
+    /// Whether an archive entry should be dropped. `app.json` is always kept,
+    /// as are files outside the project directory (shared workspace sources).
+    pub fn is_ignored(&self, archive_path: &Path) -> bool {
+        if archive_path == Path::new("app.json") {
+            return false;
+        }
+        let Some(matcher) = &self.matcher else {
+            return false;
+        };
+        let Ok(relative) = archive_path.strip_prefix(&self.app_dir) else {
+            return false;
+        };
+        matcher
+            .matched_path_or_any_parents(relative, false)
+            .is_ignore()
+    }
+}
+
+/// Feed one packaged file into the release digest: the path, a NUL, the
+/// content length as little-endian u64, then the content. Files are hashed in
+/// archive order.
+pub fn update_release_digest(hasher: &mut Sha256, path: &Path, contents: &[u8]) {
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update([0u8]);
+    hasher.update((contents.len() as u64).to_le_bytes());
+    hasher.update(contents);
+}
+
+/// Repack the deploy artifact (`.tar.zst`) as a `.tar.gz`, dropping
+/// `.takoignore` matches and appending a [`ReleaseManifest`].
+pub fn write_release_tarball(
+    release: &LocalRelease,
+    ignore: &TakoIgnore,
+    output_path: &Path,
+) -> Result<ReleaseManifest, String> {
+    let mut manifest = ReleaseManifest {
+        app: release.app_name.clone(),
+        env: release.env.clone(),
+        version: release.version.clone(),
+        target: release.target.label(),
+        files: 0,
+        digest: String::new(),
+    };
+    repack_artifact(&release.artifact_path, ignore, output_path, &mut manifest)?;
+    Ok(manifest)
+}
+
+fn repack_artifact(
+    artifact_path: &Path,
+    ignore: &TakoIgnore,
+    output_path: &Path,
+    manifest: &mut ReleaseManifest,
+) -> Result<(), String> {
+    let input = File::open(artifact_path)
+        .map_err(|e| format!("Failed to open {}: {e}", artifact_path.display()))?;
+    let decoder = zstd::stream::read::Decoder::new(input)
+        .map_err(|e| format!("Failed to read {}: {e}", artifact_path.display()))?;
+    let mut source = tar::Archive::new(decoder);
+
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let output = File::create(output_path)
+        .map_err(|e| format!("Failed to create {}: {e}", output_path.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(output, Compression::default()));
+
+    let mut hasher = Sha256::new();
+    let mut files = 0usize;
+    let mut dropped = Vec::new();
+    let mut app_manifest = None;
+    let entries = source
+        .entries()
+        .map_err(|e| format!("Failed to read artifact entries: {e}"))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Failed to read artifact entry: {e}"))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()
+            .map_err(|e| format!("Invalid artifact entry path: {e}"))?
+            .into_owned();
+        if path == Path::new(RELEASE_MANIFEST_NAME) {
+            continue;
+        }
+        if ignore.is_ignored(&path) {
+            dropped.push(path.to_string_lossy().to_string());
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        entry
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        // app.json is written after the loop, once its `files` digest map can
+        // drop the ignored paths.
+        if path == Path::new("app.json") {
+            app_manifest = Some((entry.header().clone(), contents));
+            continue;
+        }
+        update_release_digest(&mut hasher, &path, &contents);
+        files += 1;
+
+        let mut header = entry.header().clone();
+        builder
+            .append_data(&mut header, &path, contents.as_slice())
+            .map_err(|e| format!("Failed to add {}: {e}", path.display()))?;
+    }
+
+    if let Some((mut header, contents)) = app_manifest {
+        let contents = without_file_digests(contents, &dropped)?;
+        update_release_digest(&mut hasher, Path::new("app.json"), &contents);
+        files += 1;
+        header.set_size(contents.len() as u64);
+        builder
+            .append_data(&mut header, "app.json", contents.as_slice())
+            .map_err(|e| format!("Failed to add app.json: {e}"))?;
+    }
+
+    manifest.files = files;
+    manifest.digest = format!("sha256:{}", hex::encode(hasher.finalize()));
+    let manifest_bytes = serde_json::to_vec_pretty(manifest).map_err(|e| e.to_string())?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    builder
+        .append_data(
+            &mut header,
+            RELEASE_MANIFEST_NAME,
+            manifest_bytes.as_slice(),
+        )
+        .map_err(|e| format!("Failed to add {RELEASE_MANIFEST_NAME}: {e}"))?;
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|mut file| file.flush())
+        .map_err(|e| format!("Failed to write {}: {e}", output_path.display()))
+}
+
+/// Remove `.takoignore`d paths from the `app.json` `files` digest map so the
+/// server doesn't reject the release for files that were left out on purpose.
+fn without_file_digests(contents: Vec<u8>, dropped: &[String]) -> Result<Vec<u8>, String> {
+    if dropped.is_empty() {
+        return Ok(contents);
+    }
+    let Ok(serde_json::Value::Object(mut manifest)) = serde_json::from_slice(&contents) else {
+        return Ok(contents);
+    };
+    if let Some(serde_json::Value::Object(files)) = manifest.get_mut("files") {
+        for path in dropped {
+            files.remove(path);
+        }
+    }
+    serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to encode app.json: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerTarget;
+    use flate2::read::GzDecoder;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, relative: &str, contents: &str) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn build_artifact(temp: &TempDir, files: &[(&str, &str)]) -> PathBuf {
+        let workdir = temp.path().join("workdir");
+        for (relative, contents) in files {
+            write(&workdir, relative, contents);
+        }
+        let artifact = temp.path().join("artifact.tar.zst");
+        crate::build::create_workdir_archive(&workdir, &artifact, &[], &[]).unwrap();
+        artifact
+    }
+
+    fn release(temp: &TempDir, artifact_path: PathBuf, app_dir: &str) -> LocalRelease {
+        LocalRelease {
+            app_name: "my-app".to_string(),
+            env: "production".to_string(),
+            version: "v1".to_string(),
+            target: ServerTarget::normalized("x86_64", "glibc").unwrap(),
+            app_dir: app_dir.to_string(),
+            project_dir: temp.path().join("project"),
+            artifact_path,
+        }
+    }
+
+    /// Read back (path, contents) for every entry, in archive order.
+    fn read_tarball(path: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(path).unwrap()));
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().to_string();
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents).unwrap();
+                (path, contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn takoignore_filters_project_files_but_keeps_app_json() {
+        let temp = TempDir::new().unwrap();
+        let project_dir = temp.path().join("project");
+        write(&project_dir, TAKOIGNORE_FILE, "docs/\n*.map\napp.json\n");
+        let artifact = build_artifact(
+            &temp,
+            &[
+                ("app.json", "{}"),
+                ("apps/web/index.js", "console.log(1)"),
+                ("apps/web/index.js.map", "{}"),
+                ("apps/web/docs/readme.md", "# docs"),
+                ("packages/shared/docs/keep.md", "# shared"),
+            ],
+        );
+        let ignore = TakoIgnore::load(&project_dir, "apps/web").unwrap();
+        let output_path = temp.path().join("out/release.tar.gz");
+
+        let manifest =
+            write_release_tarball(&release(&temp, artifact, "apps/web"), &ignore, &output_path)
+                .unwrap();
+
+        let entries = read_tarball(&output_path);
+        let paths: Vec<&str> = entries.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "apps/web/index.js",
+                "packages/shared/docs/keep.md",
+                "app.json",
+                RELEASE_MANIFEST_NAME,
+            ]
+        );
+        assert_eq!(manifest.files, 3);
+
+        let app_json: serde_json::Value = serde_json::from_slice(&entries[2].1).unwrap();
+        let mut digested: Vec<&str> = app_json["files"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        digested.sort();
+        assert_eq!(
+            digested,
+            vec!["apps/web/index.js", "packages/shared/docs/keep.md"]
+        );
+    }
+
+    #[test]
+    fn missing_takoignore_keeps_every_file() {
+        let temp = TempDir::new().unwrap();
+        let ignore = TakoIgnore::load(&temp.path().join("project"), "").unwrap();
+        assert!(!ignore.is_ignored(Path::new("docs/readme.md")));
+    }
+
+    #[test]
+    fn manifest_digest_matches_packaged_contents() {
+        let temp = TempDir::new().unwrap();
+        let artifact = build_artifact(
+            &temp,
+            &[("app.json", "{\"main\":\"index.js\"}"), ("index.js", "ok")],
+        );
+        let ignore = TakoIgnore::load(&temp.path().join("project"), "").unwrap();
+        let output_path = temp.path().join("release.tar.gz");
+        let manifest =
+            write_release_tarball(&release(&temp, artifact, ""), &ignore, &output_path).unwrap();
+
+        let entries = read_tarball(&output_path);
+        let (last_path, last_contents) = entries.last().unwrap();
+        assert_eq!(last_path, RELEASE_MANIFEST_NAME);
+        let embedded: ReleaseManifest = serde_json::from_slice(last_contents).unwrap();
+        assert_eq!(embedded, manifest);
+        assert_eq!(embedded.target, "linux-x86_64-glibc");
+
+        let mut hasher = Sha256::new();
+        for (path, contents) in &entries[..entries.len() - 1] {
+            update_release_digest(&mut hasher, Path::new(path), contents);
+        }
+        assert_eq!(
+            embedded.digest,
+            format!("sha256:{}", hex::encode(hasher.finalize()))
+        );
+        assert_eq!(embedded.files, 2);
+    }
+}
@@ -0,0 +1,173 @@
+//! `tako ca`: trust, untrust, or renew the local development CA.
+
+use std::process::{Command, Stdio};
+
+use clap::Subcommand;
+
+use crate::commands::dev::renew_dev_server_tls_material;
+use crate::dev::{LocalCAStore, TrustStore, nss_db_dir, nss_trust_command, nss_untrust_command};
+use crate::output;
+
+#[derive(Subcommand)]
+pub enum CaCommands {
+    /// Install the local development CA into the system trust store
+    Trust,
+    /// Remove the local development CA from the system trust store
+    Untrust,
+    /// Reissue the dev leaf certificate, or replace the root CA with --root
+    Renew {
+        /// Also generate a new root CA (it must be trusted again)
+        #[arg(long)]
+        root: bool,
+        /// Skip the confirmation prompt for --root
+        #[arg(short = 'y', long = "yes")]
+        yes: bool,
+    },
+}
+
+pub fn run(cmd: CaCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let store = LocalCAStore::new()?;
+    match cmd {
+        CaCommands::Trust => trust(&store)?,
+        CaCommands::Untrust => untrust(&store)?,
+        CaCommands::Renew { root, yes } => renew(&store, root, yes)?,
+    }
+    Ok(())
+}
+
+fn trust(store: &LocalCAStore) -> Result<(), Box<dyn std::error::Error>> {
+    store.get_or_create_ca()?;
+    install_trust(store)?;
+    print_ca_identity(store)
+}
+
+fn install_trust(store: &LocalCAStore) -> Result<(), Box<dyn std::error::Error>> {
+    if store.is_ca_trusted() {
+        output::success("Local CA is already trusted");
+    } else {
+        store.install_ca_trust()?;
+        output::success(&format!("Trusted local CA in {}", trust_store_name()));
+    }
+
+    if let Some(db_dir) = nss_db_dir() {
+        let cert_path = store.ca_cert_path().display().to_string();
+        match run_certutil(&nss_trust_command(&db_dir, &cert_path)) {
+            Some(true) => output::success("Trusted local CA in the browser NSS database"),
+            Some(false) => output::warning(&format!(
+                "Could not add local CA to the NSS database at {}",
+                db_dir.display()
+            )),
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn untrust(store: &LocalCAStore) -> Result<(), Box<dyn std::error::Error>> {
+    if !store.ca_cert_path().exists() {
+        output::muted("No local CA to untrust");
+        return Ok(());
+    }
+
+    if store.is_ca_trusted() {
+        store.remove_ca_trust()?;
+        output::success(&format!("Removed local CA from {}", trust_store_name()));
+    } else {
+        output::muted("Local CA is not in the system trust store");
+    }
+
+    // Removing a cert the database doesn't have fails; nothing to report.
+    if let Some(db_dir) = nss_db_dir()
+        && run_certutil(&nss_untrust_command(&db_dir)) == Some(true)
+    {
+        output::success("Removed local CA from the browser NSS database");
+    }
+
+    print_ca_identity(store)
+}
+
+fn renew(store: &LocalCAStore, root: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let ca = if root {
+        if !yes {
+            if !output::is_interactive() {
+                return Err(
+                    "`tako ca renew --root` replaces the root CA; pass --yes to confirm".into(),
+                );
+            }
+            output::warning(
+                "A new root CA must be trusted again; certificates from the old one stop working.",
+            );
+            if !output::confirm("Replace the root CA?", false)? {
+                output::operation_cancelled();
+                return Ok(());
+            }
+        }
+
+        // Untrust the old root first: once replaced, Tako no longer has it
+        // to hand to the trust store.
+        let was_trusted = store.ca_cert_path().exists() && store.is_ca_trusted();
+        if was_trusted {
+            store.remove_ca_trust()?;
+            if let Some(db_dir) = nss_db_dir() {
+                let _ = run_certutil(&nss_untrust_command(&db_dir));
+            }
+        }
+        let ca = store.renew_ca()?;
+        output::success("Generated a new root CA");
+        if was_trusted {
+            install_trust(store)?;
+        } else {
+            output::warning("Run `tako ca trust` to trust the new root CA");
+        }
+        ca
+    } else {
+        store.get_or_create_ca()?
+    };
+
+    let names = renew_dev_server_tls_material(&ca)?;
+    output::success(&format!(
+        "Renewed dev certificate for {} name{}",
+        names.len(),
+        if names.len() == 1 { "" } else { "s" }
+    ));
+
+    if root {
+        let rt = tokio::runtime::Runtime::new()?;
+        if rt.block_on(crate::dev_server_client::is_responding()) {
+            output::warning("Restart `tako dev` so the dev server signs with the new root CA");
+        }
+    } else if !store.is_ca_trusted() {
+        output::warning("Run `tako ca trust` to trust the root CA");
+    }
+
+    print_ca_identity(store)
+}
+
+fn trust_store_name() -> &'static str {
+    match TrustStore::detect() {
+        Some(TrustStore::Keychain) => "the System keychain",
+        _ => "the system trust store",
+    }
+}
+
+fn print_ca_identity(store: &LocalCAStore) -> Result<(), Box<dyn std::error::Error>> {
+    let fingerprint = store.ca_fingerprint()?;
+    output::muted(&format!("Certificate: {}", store.ca_cert_path().display()));
+    output::muted(&format!("SHA-256 fingerprint: {fingerprint}"));
+    output::json_data("ca_cert_path", store.ca_cert_path());
+    output::json_data("fingerprint", &fingerprint);
+    Ok(())
+}
+
+/// Run a `certutil` command. `None` when `certutil` isn't installed.
+fn run_certutil(args: &[String]) -> Option<bool> {
+    let (program, args) = args.split_first()?;
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()
+        .map(|status| status.success())
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use clap::Subcommand;
+
+use crate::build::{BuildAdapter, detect_build_adapter};
+use crate::build::{parse_preset_reference, qualify_runtime_local_preset_ref};
+use crate::commands::project_context;
+use crate::config::TakoToml;
+use crate::output;
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Check tako.toml for unknown keys and invalid values without deploying
+    Validate,
+}
+
+/// Summary of a tako.toml that passed validation.
+#[derive(Debug)]
+pub(crate) struct ValidationReport {
+    pub(crate) environments: Vec<EnvironmentSummary>,
+    pub(crate) warnings: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct EnvironmentSummary {
+    pub(crate) name: String,
+    pub(crate) routes: usize,
+    pub(crate) servers: usize,
+}
+
+pub fn run(
+    cmd: ConfigCommands,
+    config_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match cmd {
+        ConfigCommands::Validate => {
+            let ctx = project_context::resolve_existing(config_path)?;
+            let report = validate_config(&ctx.project_dir, &ctx.config_path)
+                .map_err(|e| format!("{}: {e}", ctx.config_path.display()))?;
+            for warning in &report.warnings {
+                output::warning(warning);
+            }
+            output::success(&format!("{} is valid", ctx.config_path.display()));
+            for env in &report.environments {
+                output::muted(&format!(
+                    "  {}: {} route(s), {} server(s)",
+                    env.name, env.routes, env.servers
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Load and fully validate a tako.toml: schema and unknown keys (via
+/// [`TakoToml::load_from_file`]) plus the preset reference, which normally
+/// only resolves at deploy/dev time.
+pub(crate) fn validate_config(
+    project_dir: &Path,
+    config_path: &Path,
+) -> Result<ValidationReport, String> {
+    let config = TakoToml::load_from_file(config_path).map_err(|e| e.to_string())?;
+
+    if let Some(preset) = config.preset.as_deref() {
+        let runtime = config
+            .runtime
+            .as_deref()
+            .and_then(BuildAdapter::from_id)
+            .unwrap_or_else(|| detect_build_adapter(project_dir));
+        let qualified = qualify_runtime_local_preset_ref(runtime, preset)?;
+        parse_preset_reference(&qualified)?;
+    }
+
+    let environments = config
+        .get_environment_names()
+        .into_iter()
+        .map(|name| EnvironmentSummary {
+            routes: config.get_routes(&name).map(|r| r.len()).unwrap_or(0),
+            servers: config.get_servers_for_env(&name).len(),
+            name,
+        })
+        .collect();
+
+    Ok(ValidationReport {
+        environments,
+        warnings: config.ignored_reserved_var_warnings(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_config(content: &str) -> (TempDir, std::path::PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("tako.toml");
+        std::fs::write(&path, content).unwrap();
+        (temp, path)
+    }
+
+    #[test]
+    fn validate_config_accepts_a_valid_file() {
+        let (temp, path) = write_config(
+            r#"
+name = "my-app"
+runtime = "bun"
+preset = "tanstack-start"
+
+[vars]
+API_URL = "https://api.example.com"
+
+[envs.production]
+routes = ["example.com", "www.example.com"]
+servers = ["la-1"]
+"#,
+        );
+        let report = validate_config(temp.path(), &path).unwrap();
+        assert!(report.warnings.is_empty());
+        assert!(report.environments.contains(&EnvironmentSummary {
+            name: "production".to_string(),
+            routes: 2,
+            servers: 1,
+        }));
+    }
+
+    #[test]
+    fn validate_config_rejects_unknown_keys() {
+        let (temp, path) = write_config(
+            r#"
+[envs.production]
+route = "example.com"
+instance = 2
+"#,
+        );
+        let err = validate_config(temp.path(), &path).unwrap_err();
+        assert!(err.contains("instance"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn validate_config_rejects_preset_without_known_runtime() {
+        let (temp, path) = write_config("preset = \"tanstack-start\"\n");
+        let err = validate_config(temp.path(), &path).unwrap_err();
+        assert!(err.contains("runtime"), "unexpected error: {err}");
+    }
+}
@@ -16,8 +16,8 @@ use super::format::{
     should_use_unified_js_target_process,
 };
 use super::manifest::{
-    build_deploy_archive_manifest, decrypt_deploy_secrets, resolve_deploy_main,
-    resolve_deploy_version_and_source_hash, resolve_git_commit_message,
+    build_deploy_archive_manifest, resolve_deploy_main, resolve_deploy_version_and_source_hash,
+    resolve_git_commit_message,
 };
 use super::task_tree::{ArtifactBuildGroup, DeployTaskTreeController};
 
@@ -112,6 +112,12 @@ pub(super) async fn prepare_build_phase(
         Some(&plugin_ctx),
     )
     .map_err(|e| e.to_string())?;
+    crate::build::check_preset_runtime_version(
+        &build_preset,
+        runtime_adapter.id(),
+        &eff_app_dir,
+        tako_config.runtime_version.as_deref(),
+    )?;
     tracing::debug!(
         "Build preset: {} @ {}",
         resolved_preset.preset_ref,
@@ -154,7 +160,7 @@ pub(super) async fn prepare_build_phase(
             tako_runtime::detect_package_manager(&runtime_proj_root).map(|pm| pm.id().to_string())
         });
 
-    let manifest = build_deploy_archive_manifest(
+    let mut manifest = build_deploy_archive_manifest(
         &app_name,
         &env,
         &version,
@@ -170,7 +176,15 @@ pub(super) async fn prepare_build_phase(
         app_dir,
         install_dir,
     );
-    let deploy_secrets = decrypt_deploy_secrets(&env, &secrets).map_err(|e| e.to_string())?;
+    manifest.access_log_sample_rate = tako_config.get_access_log_sample_rate(&env);
+    manifest.strip_request_headers = tako_config.get_strip_request_headers(&env);
+    manifest.max_restart_attempts = tako_config.get_max_restart_attempts(&env);
+    manifest.max_restart_backoff = tako_config.get_max_restart_backoff(&env);
+    manifest.response_buffer_bytes = tako_config.get_response_buffer_bytes(&env);
+    manifest.max_body_bytes = tako_config.get_max_body_bytes(&env);
+    manifest.max_header_bytes = tako_config.get_max_header_bytes(&env);
+    manifest.rate_limit = tako_config.get_rate_limit(&env);
+    manifest.retry_non_idempotent = tako_config.get_retry_non_idempotent(&env);
 
     let app_json_bytes = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
 
@@ -207,6 +221,7 @@ pub(super) async fn prepare_build_phase(
         &include_patterns,
         &exclude_patterns,
         &asset_roots,
+        &tako_config.build.env,
         tako_config.runtime_version.as_deref(),
         manifest.package_manager.as_deref(),
         task_tree.clone(),
@@ -221,7 +236,6 @@ pub(super) async fn prepare_build_phase(
     Ok(BuildPhaseResult {
         version,
         manifest_main,
-        deploy_secrets,
         use_unified_target_process: should_use_unified_js_target_process(&runtime_tool),
         artifacts_by_target,
     })
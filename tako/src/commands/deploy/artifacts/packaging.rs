@@ -40,6 +40,7 @@ pub(super) async fn build_target_artifacts(
     include_patterns: &[String],
     exclude_patterns: &[String],
     asset_roots: &[String],
+    build_env: &HashMap<String, String>,
     pinned_runtime_version: Option<&str>,
     package_manager_tool: Option<&str>,
     task_tree: Option<DeployTaskTreeController>,
@@ -250,10 +251,7 @@ pub(super) async fn build_target_artifacts(
         } else {
             Vec::new()
         };
-        let extra_envs: Vec<(&str, &str)> = go_cross_envs
-            .iter()
-            .map(|(k, v)| (*k, v.as_str()))
-            .collect();
+        let extra_envs = build_stage_envs(build_env, &go_cross_envs);
 
         let build_result = (|| -> Result<u64, String> {
             let build_label = format_build_artifact_message(display_target_label);
@@ -485,3 +483,18 @@ pub(super) fn package_target_artifact(
 
     Ok(artifact_size)
 }
+
+/// Env vars for build commands: `[build.env]` first (sorted for stable
+/// output), then cross-compile vars, which win on conflict.
+pub(super) fn build_stage_envs<'a>(
+    build_env: &'a HashMap<String, String>,
+    cross_envs: &'a [(&'a str, String)],
+) -> Vec<(&'a str, &'a str)> {
+    let mut envs: Vec<(&str, &str)> = build_env
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    envs.sort();
+    envs.extend(cross_envs.iter().map(|(k, v)| (*k, v.as_str())));
+    envs
+}
@@ -1,5 +1,5 @@
 use super::super::cache::artifact_cache_paths;
-use super::packaging::{merge_assets_locally, package_target_artifact};
+use super::packaging::{build_stage_envs, merge_assets_locally, package_target_artifact};
 use super::runtime_version::{
     RUNTIME_VERSION_OUTPUT_FILE, extract_semver_from_version_output,
     resolve_runtime_version_from_workspace, save_package_manager_version_to_manifest,
@@ -24,6 +24,7 @@ fn build_asset_roots_combines_and_deduplicates_preset_and_project_values() {
         main: None,
         assets: vec!["public".to_string(), "dist/client".to_string()],
         dev: vec![],
+        runtime_version: None,
         runtime_overrides: Default::default(),
     };
     let config = TakoToml {
@@ -236,6 +237,40 @@ fn run_local_build_executes_custom_stages_in_order() {
     assert_eq!(order, "stage-1-run\nstage-2-install\nstage-2-run\n");
 }
 
+#[test]
+fn run_local_build_passes_build_env_to_stage_commands() {
+    let temp = TempDir::new().unwrap();
+    let workspace = temp.path().join("workspace");
+    std::fs::create_dir_all(&workspace).unwrap();
+    let stages = vec![crate::config::BuildStage {
+        name: None,
+        cwd: None,
+        install: None,
+        run: "printf '%s' \"$NPM_TOKEN\" > token.txt".to_string(),
+        exclude: Vec::new(),
+    }];
+    let build_env = HashMap::from([("NPM_TOKEN".to_string(), "secret".to_string())]);
+    let envs = build_stage_envs(&build_env, &[]);
+
+    run_local_build(&workspace, &workspace, &workspace, &stages, &envs).unwrap();
+    let token = std::fs::read_to_string(workspace.join("token.txt")).unwrap();
+    assert_eq!(token, "secret");
+}
+
+#[test]
+fn build_stage_envs_lets_cross_compile_vars_win() {
+    let build_env = HashMap::from([
+        ("GOOS".to_string(), "darwin".to_string()),
+        ("CGO_ENABLED".to_string(), "0".to_string()),
+    ]);
+    let cross = vec![("GOOS", "linux".to_string())];
+    let envs = build_stage_envs(&build_env, &cross);
+    assert_eq!(
+        envs,
+        vec![("CGO_ENABLED", "0"), ("GOOS", "darwin"), ("GOOS", "linux")]
+    );
+}
+
 #[test]
 fn run_local_build_errors_when_stage_working_dir_is_missing() {
     let temp = TempDir::new().unwrap();
@@ -31,6 +31,33 @@ pub(super) struct DeployArchiveManifest {
     /// Empty string means install at the archive root.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub(super) install_dir: String,
+    /// Access log sampling rate (log 1 in N requests; 5xx always logged).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) access_log_sample_rate: Option<u32>,
+    /// Client request headers stripped at the edge before forwarding headers are added.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(super) strip_request_headers: Vec<String>,
+    /// Replacement spawns allowed per restart window before the app is left stopped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) max_restart_attempts: Option<u32>,
+    /// Cap in seconds on the delay before respawning a failed instance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) max_restart_backoff: Option<u32>,
+    /// Response bodies up to this size are buffered at the proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) response_buffer_bytes: Option<u32>,
+    /// Request body limit enforced at the proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) max_body_bytes: Option<u64>,
+    /// Request header limit enforced at the proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) max_header_bytes: Option<u32>,
+    /// Token-bucket request limit enforced at the proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) rate_limit: Option<tako_core::RateLimit>,
+    /// Proxy may retry non-idempotent requests on another instance.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(super) retry_non_idempotent: bool,
 }
 
 pub(super) fn resolve_deploy_version_and_source_hash(
@@ -202,6 +229,15 @@ pub(super) fn build_deploy_archive_manifest(
         git_dirty,
         app_dir,
         install_dir,
+        access_log_sample_rate: None,
+        strip_request_headers: Vec::new(),
+        max_restart_attempts: None,
+        max_restart_backoff: None,
+        response_buffer_bytes: None,
+        max_body_bytes: None,
+        max_header_bytes: None,
+        rate_limit: None,
+        retry_non_idempotent: false,
     }
 }
 
@@ -4,6 +4,7 @@ mod config;
 mod format;
 mod manifest;
 mod preflight;
+mod release;
 mod remote;
 mod task_tree;
 
@@ -44,6 +45,9 @@ use task_tree::{
 };
 
 pub(crate) use manifest::resolve_deploy_main;
+pub(crate) use release::{
+    LocalRelease, build_local_release, default_release_target, parse_release_target,
+};
 
 /// Deployment configuration
 #[derive(Clone)]
@@ -57,6 +61,9 @@ struct DeployConfig {
     secrets_hash: String,
     main: String,
     use_unified_target_process: bool,
+    strategy: tako_core::DeployStrategy,
+    /// Share of traffic (percent) the new build gets before taking over.
+    canary: Option<u8>,
 
     /// Resolved release command (None when no release step). Sent only
     /// to the leader server; followers wait on the result.
@@ -74,6 +81,9 @@ struct ServerDeployTarget {
     server: ServerEntry,
     target_label: String,
     archive_path: PathBuf,
+    archive_sha256: String,
+    /// Sent with the deploy command; see [`tako_core::compute_deploy_idempotency_key`].
+    idempotency_key: String,
 }
 
 struct ServerCheck {
@@ -93,7 +103,6 @@ struct PreflightPhaseResult {
 struct BuildPhaseResult {
     version: String,
     manifest_main: String,
-    deploy_secrets: HashMap<String, String>,
     use_unified_target_process: bool,
     artifacts_by_target: HashMap<String, PathBuf>,
 }
@@ -155,16 +164,18 @@ impl DeployConfig {
 pub fn run(
     env: Option<&str>,
     assume_yes: bool,
+    canary: Option<u8>,
     config_path: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Use tokio runtime for async SSH operations
     let rt = tokio::runtime::Runtime::new()?;
-    rt.block_on(run_async(env, assume_yes, config_path))
+    rt.block_on(run_async(env, assume_yes, canary, config_path))
 }
 
 async fn run_async(
     requested_env: Option<&str>,
     assume_yes: bool,
+    canary: Option<u8>,
     config_path: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let context = project_context::resolve_existing(config_path)?;
@@ -415,10 +426,10 @@ async fn run_async(
     let BuildPhaseResult {
         version,
         manifest_main,
-        deploy_secrets,
         use_unified_target_process: use_unified_js_target_process,
         artifacts_by_target,
     } = build_result.expect("build result should be present");
+    let deploy_secrets = manifest::decrypt_deploy_secrets(&env, &secrets)?;
 
     // ===== Deploy =====
 
@@ -438,12 +449,15 @@ async fn run_async(
         secrets_hash,
         main: manifest_main,
         use_unified_target_process: use_unified_js_target_process,
+        strategy: tako_config.get_deploy_strategy(&env),
+        canary,
         release_command,
         leader_server,
     });
     let target_by_server: HashMap<String, ServerTarget> = server_targets.into_iter().collect();
 
     // Build per-server deploy targets (includes per-server scaling settings)
+    let mut archive_digests: HashMap<PathBuf, String> = HashMap::new();
     let mut targets = Vec::new();
     for server_name in &server_names {
         let server = servers.get(server_name).unwrap().clone();
@@ -460,11 +474,33 @@ async fn run_async(
                 target_label, server_name
             )
         })?;
+        let archive_sha256 = match archive_digests.get(archive_path) {
+            Some(digest) => digest.clone(),
+            None => {
+                let digest = sha256_file(archive_path).map_err(|e| {
+                    format!(
+                        "Failed to hash build artifact {}: {}",
+                        archive_path.display(),
+                        e
+                    )
+                })?;
+                archive_digests.insert(archive_path.clone(), digest.clone());
+                digest
+            }
+        };
+        let idempotency_key = tako_core::compute_deploy_idempotency_key(
+            &version,
+            &archive_sha256,
+            &deploy_config.secrets_hash,
+            &deploy_config.routes,
+        );
         targets.push(ServerDeployTarget {
             name: server_name.clone(),
             server,
             target_label,
             archive_path: archive_path.clone(),
+            archive_sha256,
+            idempotency_key,
         });
     }
     if deploy_task_tree.is_none() && targets.len() > 1 {
@@ -491,6 +527,8 @@ async fn run_async(
         let server_name = target.name.clone();
         let target_label = target.target_label.clone();
         let archive_path = target.archive_path.clone();
+        let archive_sha256 = target.archive_sha256.clone();
+        let idempotency_key = target.idempotency_key.clone();
         let deploy_config = deploy_config.clone();
         let use_spinner = use_per_server_spinners;
         let task_tree = deploy_task_tree.clone();
@@ -512,6 +550,8 @@ async fn run_async(
                     &server_name,
                     &server,
                     &archive_path,
+                    &archive_sha256,
+                    &idempotency_key,
                     &target_label,
                     use_spinner,
                     task_tree,
@@ -529,6 +569,7 @@ async fn run_async(
 
     // Collect results
     let mut errors = Vec::new();
+    let mut server_outcomes = Vec::new();
 
     let deploy_results = if deploy_task_tree.is_none()
         && output::is_interactive()
@@ -559,8 +600,20 @@ async fn run_async(
                     if deploy_task_tree.is_none() {
                         output::bullet(&format_server_deploy_success(&server_name, &server));
                     }
+                    server_outcomes.push(serde_json::json!({
+                        "name": server_name,
+                        "host": server.host,
+                        "status": "deployed",
+                        "error": null,
+                    }));
                 }
                 Err(e) => {
+                    server_outcomes.push(serde_json::json!({
+                        "name": server_name,
+                        "host": server.host,
+                        "status": "failed",
+                        "error": e.to_string(),
+                    }));
                     // When using per-server spinners (single interactive server), the step
                     // spinner already printed the error detail. Skip the duplicate.
                     if deploy_task_tree.is_none() && !use_per_server_spinners {
@@ -580,6 +633,12 @@ async fn run_async(
         }
     }
 
+    output::json_data("app", &app_name);
+    output::json_data("env", &env);
+    output::json_data("version", &version);
+    output::json_data("routes", &routes);
+    output::json_data("servers", server_outcomes);
+
     // ===== Summary =====
     if errors.is_empty() {
         if let Some(task_tree) = &deploy_task_tree {
@@ -612,6 +671,14 @@ async fn run_async(
     }
 }
 
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
 fn git_repo_root(project_dir: &Path) -> Option<PathBuf> {
     let output = std::process::Command::new("git")
         .args(["rev-parse", "--show-toplevel"])
@@ -769,6 +836,8 @@ mod tests {
             secrets_hash: String::new(),
             main: "index.ts".to_string(),
             use_unified_target_process: false,
+            strategy: Default::default(),
+            canary: None,
             release_command: None,
             leader_server: String::new(),
         };
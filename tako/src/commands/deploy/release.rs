@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use crate::app::require_app_name_from_config_path;
+use crate::build::{PresetGroup, js};
+use crate::commands::project_context;
+use crate::config::{SecretsStore, ServerTarget, TakoToml};
+use crate::output;
+use crate::validation::{validate_secrets_for_deployment, validate_tako_toml};
+
+use super::artifacts::prepare_build_phase;
+use super::config::{
+    resolve_build_preset_ref, resolve_deploy_environment, resolve_effective_build_adapter,
+    run_bun_lockfile_preflight, should_run_bun_lockfile_preflight,
+};
+use super::format::should_use_unified_js_target_process;
+use super::source_bundle_root;
+use super::task_tree::build_artifact_target_groups;
+
+/// A deploy artifact built locally for one target, without contacting any
+/// server (`tako build --output`).
+pub(crate) struct LocalRelease {
+    pub app_name: String,
+    pub env: String,
+    pub version: String,
+    pub target: ServerTarget,
+    /// Project directory relative to the archive root (empty when the
+    /// project is the bundle root).
+    pub app_dir: String,
+    pub project_dir: PathBuf,
+    /// The cached `.tar.zst` deploy artifact.
+    pub artifact_path: PathBuf,
+}
+
+/// Parse a `--target` value: `x86_64-glibc`, `linux-aarch64-musl`, or a bare
+/// architecture (glibc is assumed).
+pub(crate) fn parse_release_target(value: &str) -> Result<ServerTarget, String> {
+    let trimmed = value.trim();
+    let without_os = trimmed.strip_prefix("linux-").unwrap_or(trimmed);
+    let (arch, libc) = match without_os.rsplit_once('-') {
+        Some((arch, libc)) if ServerTarget::normalize_libc(libc).is_some() => (arch, libc),
+        _ => (without_os, "glibc"),
+    };
+    ServerTarget::normalized(arch, libc).map_err(|e| format!("Invalid --target '{trimmed}': {e}"))
+}
+
+/// Default release target: the local CPU architecture on glibc.
+pub(crate) fn default_release_target() -> Result<ServerTarget, String> {
+    ServerTarget::normalized(std::env::consts::ARCH, "glibc").map_err(|_| {
+        format!(
+            "Cannot infer a Linux target from this machine ({}); pass --target, e.g. --target x86_64-glibc",
+            std::env::consts::ARCH
+        )
+    })
+}
+
+/// Run the deploy build phase for a single target and return the artifact.
+/// Returns `None` under `--dry-run`.
+pub(crate) async fn build_local_release(
+    requested_env: Option<&str>,
+    target: ServerTarget,
+    config_path: Option<&Path>,
+) -> Result<Option<LocalRelease>, Box<dyn std::error::Error>> {
+    let context = project_context::resolve_existing(config_path)?;
+    let project_dir = context.project_dir;
+
+    let (tako_config, secrets, env) = output::with_spinner_silent(
+        "Validating configuration",
+        || -> Result<(TakoToml, SecretsStore, String), String> {
+            let tako_config =
+                TakoToml::load_from_file(&context.config_path).map_err(|e| e.to_string())?;
+            let secrets = SecretsStore::load_from_dir(&project_dir).map_err(|e| e.to_string())?;
+            let env = resolve_deploy_environment(requested_env, &tako_config)?;
+
+            let config_result = validate_tako_toml(&tako_config);
+            if config_result.has_errors() {
+                return Err(format!(
+                    "Configuration errors:\n  {}",
+                    config_result.errors.join("\n  ")
+                ));
+            }
+            let secrets_result = validate_secrets_for_deployment(&secrets, &env);
+            if secrets_result.has_errors() {
+                return Err(format!(
+                    "Secret errors:\n  {}",
+                    secrets_result.errors.join("\n  ")
+                ));
+            }
+            for warning in config_result
+                .warnings
+                .iter()
+                .chain(&secrets_result.warnings)
+            {
+                output::warning(&format!("Validation: {}", warning));
+            }
+            Ok((tako_config, secrets, env))
+        },
+    )
+    .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+
+    let preset_ref = resolve_build_preset_ref(&project_dir, &tako_config)
+        .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+    let runtime_adapter = resolve_effective_build_adapter(&project_dir, &tako_config, &preset_ref)
+        .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+    let source_root = source_bundle_root(&project_dir, runtime_adapter.id());
+
+    if runtime_adapter.preset_group() == PresetGroup::Js {
+        let _ = js::write_types(&project_dir);
+    }
+    if should_run_bun_lockfile_preflight(runtime_adapter) {
+        output::with_spinner_silent("Checking Bun lockfile", || {
+            run_bun_lockfile_preflight(&source_root)
+        })
+        .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+    }
+
+    let app_name = require_app_name_from_config_path(&context.config_path).map_err(
+        |e| -> Box<dyn std::error::Error> {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()).into()
+        },
+    )?;
+
+    if output::is_dry_run() {
+        output::dry_run_skip("Build");
+        return Ok(None);
+    }
+
+    let server_targets = vec![("local".to_string(), target.clone())];
+    let build_groups = build_artifact_target_groups(
+        &server_targets,
+        should_use_unified_js_target_process(runtime_adapter.id()),
+    );
+    let build = prepare_build_phase(
+        project_dir.clone(),
+        source_root.clone(),
+        project_dir.clone(),
+        app_name.clone(),
+        env.clone(),
+        tako_config,
+        secrets,
+        preset_ref,
+        runtime_adapter,
+        server_targets,
+        build_groups,
+        None,
+    )
+    .await
+    .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+
+    let artifact_path = build
+        .artifacts_by_target
+        .get(&target.label())
+        .cloned()
+        .ok_or_else(|| format!("No artifact was built for {}", target.label()))?;
+    let app_dir = project_dir
+        .strip_prefix(&source_root)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok(Some(LocalRelease {
+        app_name,
+        env,
+        version: build.version,
+        target,
+        app_dir,
+        project_dir,
+        artifact_path,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_release_target_accepts_label_and_short_forms() {
+        let label = |value: &str| parse_release_target(value).unwrap().label();
+        assert_eq!(label("linux-aarch64-musl"), "linux-aarch64-musl");
+        assert_eq!(label("amd64-gnu"), "linux-x86_64-glibc");
+        assert_eq!(label("arm64"), "linux-aarch64-glibc");
+        assert!(parse_release_target("riscv64").is_err());
+    }
+}
@@ -82,50 +82,110 @@ pub(super) async fn cleanup_partial_release(
     Ok(())
 }
 
-pub(super) async fn remote_directory_exists(
-    ssh: &SshClient,
-    path: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    let quoted = shell_single_quote(path);
-    let cmd = format!("if [ -d {quoted} ]; then echo yes; else echo no; fi");
-    let output = ssh.exec(&cmd).await?;
-    if !output.success() {
-        return Err(format!(
-            "Failed to check remote directory existence for {}: {}",
-            path,
-            output.combined().trim()
-        )
-        .into());
+/// Marker kept in a release dir from creation until the release is uploaded
+/// and prepared. A dir that still has it belongs to an interrupted deploy.
+pub(super) const RELEASE_PENDING_MARKER: &str = ".tako-pending";
+
+/// What earlier deploys left in the remote release directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RemoteReleaseState {
+    /// Newly created, or reset after an interrupted deploy.
+    Fresh,
+    /// An interrupted deploy already uploaded this exact archive.
+    ArchiveUploaded,
+    /// Uploaded and prepared by an earlier deploy.
+    Complete,
+}
+
+pub(super) fn release_pending_marker_path(release_dir: &str) -> String {
+    format!("{release_dir}/{RELEASE_PENDING_MARKER}")
+}
+
+/// Prints `missing`, `complete`, or `pending <archive sha256>` (the digest is
+/// empty when no archive was uploaded yet).
+pub(super) fn build_remote_release_probe_command(release_dir: &str) -> String {
+    let release = shell_single_quote(release_dir);
+    let marker = shell_single_quote(&release_pending_marker_path(release_dir));
+    let archive = shell_single_quote(&remote_release_archive_path(release_dir));
+    format!(
+        "if [ ! -d {release} ]; then echo missing; \
+         elif [ ! -e {marker} ]; then echo complete; \
+         else echo \"pending $(sha256sum {archive} 2>/dev/null | cut -d' ' -f1)\"; fi"
+    )
+}
+
+/// Classify probe output. `None` means the dir must be (re)created from
+/// scratch; unrecognized output is an error so a live release is never reset.
+pub(super) fn parse_remote_release_probe(
+    output: &str,
+    archive_sha256: &str,
+) -> Result<Option<RemoteReleaseState>, String> {
+    let output = output.trim();
+    match output
+        .split_once(' ')
+        .map_or((output, ""), |(a, b)| (a, b.trim()))
+    {
+        ("missing", _) => Ok(None),
+        ("complete", _) => Ok(Some(RemoteReleaseState::Complete)),
+        ("pending", digest)
+            if !digest.is_empty() && digest.eq_ignore_ascii_case(archive_sha256) =>
+        {
+            Ok(Some(RemoteReleaseState::ArchiveUploaded))
+        }
+        ("pending", _) => Ok(None),
+        _ => Err(format!(
+            "Unexpected release directory probe output: {output}"
+        )),
     }
-    Ok(output.stdout.trim() == "yes")
 }
 
 pub(super) async fn connect_and_prepare_remote_release_dir(
     ssh: &mut SshClient,
     release_dir: &str,
     shared_dir: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    archive_sha256: &str,
+) -> Result<RemoteReleaseState, Box<dyn std::error::Error + Send + Sync>> {
     ssh.connect().await?;
-    prepare_remote_release_dir(ssh, release_dir, shared_dir).await
+    prepare_remote_release_dir(ssh, release_dir, shared_dir, archive_sha256).await
 }
 
 /// Prepare the remote release directory on an already-connected SSH session.
+///
+/// A finished release dir is reused as-is. An interrupted one keeps its
+/// archive when it matches `archive_sha256` (the upload is skipped);
+/// otherwise it is reset and marked pending until preparation succeeds.
 pub(super) async fn prepare_remote_release_dir(
     ssh: &SshClient,
     release_dir: &str,
     shared_dir: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    let release_dir_preexisted = remote_directory_exists(ssh, release_dir).await?;
-    if !release_dir_preexisted {
-        ssh.exec_checked(&format!(
-            "mkdir -p {} {}",
-            shell_single_quote(release_dir),
-            shell_single_quote(shared_dir)
-        ))
+    archive_sha256: &str,
+) -> Result<RemoteReleaseState, Box<dyn std::error::Error + Send + Sync>> {
+    let output = ssh
+        .exec(&build_remote_release_probe_command(release_dir))
         .await?;
+    if !output.success() {
+        return Err(format!(
+            "Failed to inspect remote release directory {}: {}",
+            release_dir,
+            output.combined().trim()
+        )
+        .into());
+    }
+    if let Some(state) = parse_remote_release_probe(&output.stdout, archive_sha256)? {
+        if state == RemoteReleaseState::ArchiveUploaded {
+            tracing::debug!("Resuming interrupted deploy: archive already uploaded");
+        }
+        return Ok(state);
     }
 
-    Ok(release_dir_preexisted)
+    let release = shell_single_quote(release_dir);
+    ssh.exec_checked(&format!(
+        "rm -rf {release} && mkdir -p {release} {} && touch {}",
+        shell_single_quote(shared_dir),
+        shell_single_quote(&release_pending_marker_path(release_dir))
+    ))
+    .await?;
+    Ok(RemoteReleaseState::Fresh)
 }
 
 pub(super) async fn run_deploy_step<T, E, Fut>(
@@ -272,6 +332,8 @@ pub(super) async fn deploy_to_server(
     server_name: &str,
     server: &crate::config::ServerEntry,
     archive_path: &Path,
+    archive_sha256: &str,
+    idempotency_key: &str,
     target_label: &str,
     use_spinner: bool,
     task_tree: Option<DeployTaskTreeController>,
@@ -283,18 +345,19 @@ pub(super) async fn deploy_to_server(
         output::timed(&format!("Server deploy ({target_label}:{})", server.port));
     let release_dir = config.release_dir();
 
-    let (mut ssh, release_dir_preexisted) = if let Some(ssh) = preconnected_ssh {
+    let (mut ssh, release_state) = if let Some(ssh) = preconnected_ssh {
         // Reuse connection from preflight — "Preflight" is already done.
         // Just prepare the remote release directory.
-        let preexisted = prepare_remote_release_dir(&ssh, &release_dir, &config.shared_dir())
-            .await
-            .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { e })?;
-        (ssh, preexisted)
+        let state =
+            prepare_remote_release_dir(&ssh, &release_dir, &config.shared_dir(), archive_sha256)
+                .await
+                .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { e })?;
+        (ssh, state)
     } else {
         // No pre-connected client — connect now.
         let ssh_config = SshConfig::from_server(&server.host, server.port);
         let mut ssh = SshClient::new(ssh_config);
-        let preexisted = if let Some(task_tree) = &task_tree {
+        let state = if let Some(task_tree) = &task_tree {
             run_task_tree_deploy_step(
                 task_tree,
                 server_name,
@@ -303,6 +366,7 @@ pub(super) async fn deploy_to_server(
                     &mut ssh,
                     &release_dir,
                     &config.shared_dir(),
+                    archive_sha256,
                 ),
             )
             .await?
@@ -315,12 +379,14 @@ pub(super) async fn deploy_to_server(
                     &mut ssh,
                     &release_dir,
                     &config.shared_dir(),
+                    archive_sha256,
                 ),
             )
             .await?
         };
-        (ssh, preexisted)
+        (ssh, state)
     };
+    let release_dir_preexisted = release_state == RemoteReleaseState::Complete;
     let archive_size_bytes = std::fs::metadata(archive_path)?.len();
     tracing::debug!("Archive size: {}", format_size(archive_size_bytes));
     let mut cleaned_partial_release = false;
@@ -328,10 +394,15 @@ pub(super) async fn deploy_to_server(
     let result = async {
         // Upload artifact (skip if release dir already has it from a previous deploy).
         let remote_archive = remote_release_archive_path(&release_dir);
-        if release_dir_preexisted {
-            tracing::debug!("Release dir already exists, skipping upload");
+        if release_state != RemoteReleaseState::Fresh {
+            tracing::debug!("Release archive already on server, skipping upload");
             if let Some(task_tree) = &task_tree {
-                task_tree.skip_deploy_step(server_name, "uploading", "cached");
+                let detail = if release_dir_preexisted {
+                    "cached"
+                } else {
+                    "resumed"
+                };
+                task_tree.skip_deploy_step(server_name, "uploading", detail);
             }
         } else {
             let upload_timer = output::timed(&format!(
@@ -488,6 +559,11 @@ pub(super) async fn deploy_to_server(
                     format_deploy_step_failure("Preparing", &e.to_string()).into()
                 })?;
             }
+            ssh.exec_checked(&format!(
+                "rm -f {}",
+                shell_single_quote(&release_pending_marker_path(&release_dir))
+            ))
+            .await?;
         } else if let Some(task_tree) = &task_tree {
             task_tree.skip_deploy_step(server_name, "preparing", "skipped");
         }
@@ -610,6 +686,9 @@ pub(super) async fn deploy_to_server(
                         path: release_dir.clone(),
                         routes: config.routes.clone(),
                         secrets: deploy_secrets,
+                        idempotency_key: Some(idempotency_key.to_string()),
+                        strategy: config.strategy,
+                        canary: config.canary,
                     };
                     let json = serde_json::to_string(&cmd)
                         .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })?;
@@ -646,6 +725,9 @@ pub(super) async fn deploy_to_server(
                     path: release_dir.clone(),
                     routes: config.routes.clone(),
                     secrets: deploy_secrets,
+                    idempotency_key: Some(idempotency_key.to_string()),
+                    strategy: config.strategy,
+                    canary: config.canary,
                 };
                 let json = serde_json::to_string(&cmd)
                     .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })?;
@@ -793,6 +875,29 @@ mod tests {
         assert!(!deploy_response_has_error(plain_text));
     }
 
+    #[test]
+    fn remote_release_probe_command_quotes_paths() {
+        let cmd = build_remote_release_probe_command("/opt/tako/apps/a'b/releases/v1");
+        assert!(cmd.contains("'/opt/tako/apps/a'\\''b/releases/v1/.tako-pending'"));
+        assert!(cmd.contains("sha256sum '/opt/tako/apps/a'\\''b/releases/v1/artifacts.tar.zst'"));
+    }
+
+    #[test]
+    fn remote_release_probe_resumes_only_matching_uploaded_archive() {
+        assert_eq!(parse_remote_release_probe("missing\n", "abc"), Ok(None));
+        assert_eq!(
+            parse_remote_release_probe("complete\n", "abc"),
+            Ok(Some(RemoteReleaseState::Complete))
+        );
+        assert_eq!(
+            parse_remote_release_probe("pending abc\n", "abc"),
+            Ok(Some(RemoteReleaseState::ArchiveUploaded))
+        );
+        assert_eq!(parse_remote_release_probe("pending def\n", "abc"), Ok(None));
+        assert_eq!(parse_remote_release_probe("pending\n", "abc"), Ok(None));
+        assert!(parse_remote_release_probe("sh: syntax error", "abc").is_err());
+    }
+
     #[test]
     fn remote_release_archive_path_uses_artifacts_tar_zst_name() {
         let path = remote_release_archive_path("/opt/tako/apps/my-app/releases/v1");
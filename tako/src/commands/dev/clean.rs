@@ -0,0 +1,91 @@
+//! `tako dev clean` — undo the system-level setup performed by `tako dev`
+//! (dev proxy / port redirect, DNS resolver config, loopback alias) while
+//! keeping the CLI and its local data. `--certs` also drops CA trust and the
+//! local CA and dev certificate files.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::commands::implode::{
+    SystemTarget, gather_system_targets, has_ca_certs_in_keychain, remove_ca_certs_from_keychain,
+    remove_system_targets,
+};
+use crate::output;
+
+/// Keep only the targets `tako dev clean` should remove.
+pub(super) fn select_clean_targets(
+    targets: Vec<SystemTarget>,
+    include_certs: bool,
+) -> Vec<SystemTarget> {
+    targets
+        .into_iter()
+        .filter(|t| include_certs || !t.ca_cert)
+        .collect()
+}
+
+/// Local CA and generated dev certificate directories under the data dir.
+pub(super) fn local_cert_dirs(data_dir: &Path) -> Vec<PathBuf> {
+    [data_dir.join("ca"), data_dir.join("certs")]
+        .into_iter()
+        .filter(|p| p.exists())
+        .collect()
+}
+
+pub fn clean(include_certs: bool, assume_yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let targets = select_clean_targets(gather_system_targets(), include_certs);
+    let keychain_certs = include_certs && has_ca_certs_in_keychain();
+    let cert_dirs = if include_certs {
+        local_cert_dirs(&crate::paths::tako_data_dir()?)
+    } else {
+        Vec::new()
+    };
+
+    if targets.is_empty() && !keychain_certs && cert_dirs.is_empty() {
+        output::muted("Nothing to clean — no dev system changes found.");
+        return Ok(());
+    }
+
+    output::warning("This will remove Tako dev setup from this machine:");
+    eprintln!();
+    for target in &targets {
+        output::muted(&format!("  {}", target.description));
+    }
+    if keychain_certs {
+        output::muted("  CA certificate(s) in system keychain");
+    }
+    for dir in &cert_dirs {
+        output::muted(&format!("  {}", dir.display()));
+    }
+    eprintln!();
+
+    if !assume_yes && !output::confirm("Remove Tako dev setup?", false)? {
+        output::operation_cancelled();
+        return Ok(());
+    }
+
+    if !targets.is_empty() || keychain_certs {
+        output::warning("Sudo is required to remove system-level components.");
+        let sudo_status = Command::new("sudo")
+            .arg("-v")
+            .status()
+            .map_err(|e| format!("failed to run sudo: {e}"))?;
+        if !sudo_status.success() {
+            return Err("Sudo authentication failed — nothing was removed".into());
+        }
+        remove_system_targets(&targets);
+        if keychain_certs {
+            remove_ca_certs_from_keychain();
+        }
+    }
+
+    for dir in &cert_dirs {
+        match std::fs::remove_dir_all(dir) {
+            Ok(()) => output::success(&format!("Removed {}", dir.display())),
+            Err(e) => output::error(&format!("Failed to remove {}: {e}", dir.display())),
+        }
+    }
+
+    eprintln!();
+    output::success("Dev setup removed. The next `tako dev` will set it up again.");
+    Ok(())
+}
@@ -8,8 +8,8 @@ use tokio::time::timeout;
 
 use super::runner::bootstrap_dev_events;
 use super::{
-    DevEvent, ScopedLog, infer_preset_name_from_ref, load_dev_tako_toml, output,
-    resolve_dev_preset_ref,
+    DevEvent, REGISTRATION_EXPIRED_MESSAGE, ScopedLog, infer_preset_name_from_ref,
+    load_dev_tako_toml, output, resolve_dev_preset_ref,
 };
 
 #[derive(Debug, Clone)]
@@ -94,7 +94,7 @@ pub(super) async fn run_connected_dev_client(
         let config_key = session.config_key.clone();
         let sid = my_client_id;
         tokio::spawn(async move {
-            let mut got_stop = false;
+            let mut stop_message = None;
 
             let connected = async {
                 let ev_rx = crate::dev_server_client::subscribe_events().await.ok()?;
@@ -114,7 +114,13 @@ pub(super) async fn run_connected_dev_client(
                             ref status,
                             ..
                         } if config_path == &config_key && status == "stopped" => {
-                            got_stop = true;
+                            stop_message = Some("stopped by another client");
+                            break;
+                        }
+                        DevServerEvent::RegistrationExpired {
+                            ref config_path, ..
+                        } if config_path == &config_key => {
+                            stop_message = Some(REGISTRATION_EXPIRED_MESSAGE);
                             break;
                         }
                         DevServerEvent::ClientConnected {
@@ -195,11 +201,9 @@ pub(super) async fn run_connected_dev_client(
             }
 
             let _ = stop_tx.send(true);
-            let msg = if got_stop {
-                "stopped by another client".to_string()
-            } else {
-                "disconnected from dev server".to_string()
-            };
+            let msg = stop_message
+                .unwrap_or("disconnected from dev server")
+                .to_string();
             let _ = event_tx.send(DevEvent::ExitWithMessage(msg)).await;
         });
     }
@@ -209,7 +213,7 @@ pub(super) async fn run_connected_dev_client(
         let config_key_for_logs = session.config_key.clone();
         tokio::spawn(async move {
             let Ok(mut rx) =
-                crate::dev_server_client::subscribe_logs(&config_key_for_logs, None).await
+                crate::dev_server_client::subscribe_logs(&config_key_for_logs, None, true).await
             else {
                 return;
             };
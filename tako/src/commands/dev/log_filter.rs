@@ -0,0 +1,213 @@
+use time::{Duration, OffsetDateTime, Time};
+
+use super::types::{LogLevel, ScopedLog, local_offset};
+
+/// Which stored dev log lines to show. Every set condition must match.
+#[derive(Debug, Clone, Default)]
+pub(super) struct LogFilter {
+    /// Only lines logged at or after this Unix millisecond
+    pub(super) since_millis: Option<i64>,
+    /// Only lines whose message or scope contains this (case-insensitive)
+    pub(super) pattern: Option<String>,
+    /// Only lines at least this severe
+    pub(super) min_level: Option<LogLevel>,
+    /// Only lines from this scope, or its `scope:*` children
+    pub(super) scope: Option<String>,
+}
+
+impl LogFilter {
+    pub(super) fn new(
+        since: Option<&str>,
+        pattern: Option<String>,
+        level: Option<&str>,
+        scope: Option<String>,
+    ) -> Result<Self, String> {
+        let since_millis = since
+            .map(|since| parse_since(since, OffsetDateTime::now_utc().to_offset(local_offset())))
+            .transpose()?;
+        let min_level = level.map(parse_level).transpose()?;
+        Ok(Self {
+            since_millis,
+            pattern: pattern.map(|pattern| pattern.to_lowercase()),
+            min_level,
+            scope,
+        })
+    }
+
+    pub(super) fn matches(&self, log: &ScopedLog) -> bool {
+        if self
+            .since_millis
+            .is_some_and(|since| log.unix_millis < since)
+        {
+            return false;
+        }
+        if self.min_level.is_some_and(|min| log.level < min) {
+            return false;
+        }
+        if let Some(scope) = &self.scope
+            && log.scope != *scope
+            && !log
+                .scope
+                .strip_prefix(scope.as_str())
+                .is_some_and(|rest| rest.starts_with(':'))
+        {
+            return false;
+        }
+        if let Some(pattern) = &self.pattern
+            && !log.message.to_lowercase().contains(pattern)
+            && !log.scope.to_lowercase().contains(pattern)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// Parse `--since`: a duration back from `now` (`30s`, `10m`, `2h`, `1d`) or
+/// a local wall-clock time (`14:05`, `14:05:30`). A time later than `now`
+/// means yesterday.
+pub(super) fn parse_since(input: &str, now: OffsetDateTime) -> Result<i64, String> {
+    let input = input.trim();
+    let invalid = || {
+        format!(
+            "Invalid --since '{}': use a duration like 10m, 2h, 1d or a time like 14:05",
+            input
+        )
+    };
+
+    let at = if input.contains(':') {
+        let parts = input
+            .split(':')
+            .map(|part| part.parse::<u8>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (hour, minute, second) = match parts[..] {
+            [hour, minute] => (hour, minute, 0),
+            [hour, minute, second] => (hour, minute, second),
+            _ => return Err(invalid()),
+        };
+        let time = Time::from_hms(hour, minute, second).map_err(|_| invalid())?;
+        let today = now.replace_time(time);
+        if today > now {
+            today - Duration::days(1)
+        } else {
+            today
+        }
+    } else {
+        let split = input
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let (amount, unit) = input.split_at(split);
+        let amount = i64::from(amount.parse::<u32>().map_err(|_| invalid())?);
+        let back = match unit {
+            "s" => Duration::seconds(amount),
+            "m" => Duration::minutes(amount),
+            "h" => Duration::hours(amount),
+            "d" => Duration::days(amount),
+            _ => return Err(invalid()),
+        };
+        now.checked_sub(back).ok_or_else(invalid)?
+    };
+    Ok((at.unix_timestamp_nanos() / 1_000_000) as i64)
+}
+
+fn parse_level(input: &str) -> Result<LogLevel, String> {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "debug" => Ok(LogLevel::Debug),
+        "info" => Ok(LogLevel::Info),
+        "warn" | "warning" => Ok(LogLevel::Warn),
+        "error" => Ok(LogLevel::Error),
+        "fatal" => Ok(LogLevel::Fatal),
+        _ => Err(format!(
+            "Invalid --level '{}': expected debug, info, warn, error, or fatal",
+            input
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(level: LogLevel, scope: &str, message: &str, unix_millis: i64) -> ScopedLog {
+        ScopedLog {
+            timestamp: "12:00:00".to_string(),
+            unix_millis,
+            level,
+            scope: scope.to_string(),
+            message: message.to_string(),
+            fields: None,
+            kind: None,
+        }
+    }
+
+    #[test]
+    fn filters_by_minimum_level() {
+        let filter = LogFilter {
+            min_level: Some(LogLevel::Warn),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&log(LogLevel::Debug, "app", "x", 0)));
+        assert!(!filter.matches(&log(LogLevel::Info, "app", "x", 0)));
+        assert!(filter.matches(&log(LogLevel::Warn, "app", "x", 0)));
+        assert!(filter.matches(&log(LogLevel::Fatal, "app", "x", 0)));
+    }
+
+    #[test]
+    fn filters_by_scope_and_its_children() {
+        let filter = LogFilter {
+            scope: Some("api".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&log(LogLevel::Info, "api", "x", 0)));
+        assert!(filter.matches(&log(LogLevel::Info, "api:worker", "x", 0)));
+        assert!(!filter.matches(&log(LogLevel::Info, "apis", "x", 0)));
+        assert!(!filter.matches(&log(LogLevel::Info, "tako", "x", 0)));
+    }
+
+    #[test]
+    fn filters_by_message_substring_case_insensitively() {
+        let filter = LogFilter::new(None, Some("Timeout".to_string()), None, None).unwrap();
+        assert!(filter.matches(&log(LogLevel::Error, "app", "upstream timeout after 5s", 0)));
+        assert!(!filter.matches(&log(LogLevel::Error, "app", "connection refused", 0)));
+    }
+
+    #[test]
+    fn filters_by_since_and_combines_conditions() {
+        let filter = LogFilter {
+            since_millis: Some(1_000),
+            min_level: Some(LogLevel::Error),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&log(LogLevel::Error, "app", "x", 999)));
+        assert!(filter.matches(&log(LogLevel::Error, "app", "x", 1_000)));
+        assert!(!filter.matches(&log(LogLevel::Info, "app", "x", 2_000)));
+    }
+
+    #[test]
+    fn parse_since_accepts_durations_and_wall_clock_times() {
+        // 2026-03-10 14:30:00 UTC
+        let now = OffsetDateTime::from_unix_timestamp(1773153000).unwrap();
+        let millis = |secs_before_now: i64| (1773153000 - secs_before_now) * 1_000;
+
+        assert_eq!(parse_since("10m", now).unwrap(), millis(10 * 60));
+        assert_eq!(parse_since("2h", now).unwrap(), millis(2 * 3_600));
+        assert_eq!(parse_since("1d", now).unwrap(), millis(86_400));
+        assert_eq!(parse_since("14:05", now).unwrap(), millis(25 * 60));
+        // Later than now: yesterday.
+        assert_eq!(
+            parse_since("23:00:15", now).unwrap(),
+            millis(15 * 3_600 + 29 * 60 + 45)
+        );
+
+        for bad in ["", "10", "10w", "25:00", "1:2:3:4", "abc"] {
+            assert!(parse_since(bad, now).is_err(), "{bad:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn parse_level_accepts_known_levels() {
+        assert_eq!(parse_level("WARN").unwrap(), LogLevel::Warn);
+        assert_eq!(parse_level("warning").unwrap(), LogLevel::Warn);
+        assert!(parse_level("verbose").is_err());
+    }
+}
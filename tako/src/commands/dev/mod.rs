@@ -7,7 +7,9 @@
 //! - Streaming logs, status, and resource monitoring
 //! - Process lifecycle managed by the daemon
 
+mod clean;
 mod client;
+mod log_filter;
 mod output;
 mod output_render;
 pub(crate) mod prepare;
@@ -15,6 +17,7 @@ mod project;
 mod runner;
 mod shared;
 mod types;
+mod ui_state;
 mod watcher;
 
 #[cfg(test)]
@@ -23,12 +26,15 @@ use std::time::Duration;
 use crate::app::resolve_app_name_from_config_path;
 use crate::build::{PresetGroup, apply_adapter_base_runtime_defaults, js};
 use crate::dev::LocalCA;
+pub use clean::clean;
+#[cfg(test)]
+use clean::{local_cert_dirs, select_clean_targets};
 use client::{ConnectedDevClient, parse_log_line, run_connected_dev_client};
 #[cfg(test)]
 use prepare::local::tcp_probe;
 use prepare::local::{local_https_probe_host, wait_for_https_host_reachable_via_ip};
 #[cfg(target_os = "macos")]
-use prepare::macos::ensure_local_dns_resolver_configured;
+pub(crate) use prepare::macos::ensure_local_dns_resolver_configured;
 #[cfg(not(target_os = "macos"))]
 fn ensure_local_dns_resolver_configured(_port: u16) -> Result<bool, Box<dyn std::error::Error>> {
     Ok(true)
@@ -38,12 +44,13 @@ use prepare::macos::{
     local_dns_resolver_contents, local_dns_sudo_action_line, parse_local_dns_resolver,
     sudo_setup_action_items,
 };
-use prepare::tls::ensure_dev_server_tls_material;
 #[cfg(test)]
 use prepare::tls::{
     ca_fingerprint, ca_fingerprint_path_for_home, dev_server_tls_names_path_for_home,
     dev_server_tls_paths_for_home, ensure_dev_server_tls_material_for_home,
+    renew_dev_server_tls_material_for_home,
 };
+pub(crate) use prepare::tls::{ensure_dev_server_tls_material, renew_dev_server_tls_material};
 use project::{
     compute_dev_env, compute_dev_hosts, compute_display_routes, dev_startup_lines, dev_url,
     disambiguate_app_name, has_explicit_dev_preset, infer_preset_name_from_ref,
@@ -64,12 +71,17 @@ use types::{
 };
 
 #[cfg(target_os = "linux")]
-pub(crate) use prepare::linux::{LinuxSetupStatus, status as linux_setup_status};
+pub(crate) use prepare::linux::{
+    LinuxSetupStatus, ensure_installed as ensure_linux_setup_installed,
+    status as linux_setup_status,
+};
 pub(crate) use prepare::local::is_dev_server_unavailable_error_message;
 #[cfg(target_os = "macos")]
 pub(crate) use prepare::macos::local_dns_resolver_values;
 #[cfg(target_os = "macos")]
-pub(crate) use prepare::macos::{DevProxyStatus, status as dev_proxy_status};
+pub(crate) use prepare::macos::{
+    DevProxyStatus, ensure_installed as ensure_dev_proxy_installed, status as dev_proxy_status,
+};
 pub use prepare::tls::setup_local_ca;
 
 #[cfg(test)]
@@ -87,6 +99,9 @@ const LOCALHOST_443_HTTPS_PROBE_ATTEMPTS: usize = 12;
 const LOCALHOST_443_HTTPS_PROBE_TIMEOUT_MS: u64 = 500;
 const LOCALHOST_443_HTTPS_PROBE_RETRY_DELAY_MS: u64 = 150;
 pub(crate) const DEV_LOOPBACK_ADDR: &str = "127.77.0.1";
+/// Shown when the daemon drops this session's app because its config file
+/// was deleted.
+const REGISTRATION_EXPIRED_MESSAGE: &str = "config file removed; dev server dropped this app";
 
 #[cfg(test)]
 fn dev_initial_instance_count() -> usize {
@@ -105,6 +120,6 @@ pub(crate) use shared::{
     load_dev_tako_toml, port_from_listen, restart_required_for_requested_listen,
 };
 
-pub use runner::{ls, run, stop};
+pub use runner::{logs, ls, restart, run, stop};
 #[cfg(test)]
 mod tests;
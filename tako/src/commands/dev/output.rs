@@ -17,7 +17,9 @@ use tokio::sync::mpsc;
 
 use super::output_render::{
     DIM, RESET, format_header, format_keymap, format_lan_block, format_log, format_panel, git_info,
+    keymap_level_label,
 };
+use super::ui_state::DevUiState;
 use super::{DevEvent, LogLevel, ScopedLog};
 
 const METRICS_REFRESH_SECS: u64 = 2;
@@ -240,6 +242,26 @@ fn spawn_key_reader(tx: mpsc::Sender<Event>) {
     });
 }
 
+// ── Log level filter ─────────────────────────────────────────────────────────
+
+/// Whether the TUI shows `log` at minimum level `min_level`. Action dividers
+/// (`kind` lines) always show.
+pub(super) fn shows_log(min_level: LogLevel, log: &ScopedLog) -> bool {
+    log.kind.is_some() || log.level >= min_level
+}
+
+/// The minimum level after pressing `v`: one step more severe, wrapping from
+/// `Fatal` back to showing everything.
+pub(super) fn next_min_level(min_level: LogLevel) -> LogLevel {
+    match min_level {
+        LogLevel::Debug => LogLevel::Info,
+        LogLevel::Info => LogLevel::Warn,
+        LogLevel::Warn => LogLevel::Error,
+        LogLevel::Error => LogLevel::Fatal,
+        LogLevel::Fatal => LogLevel::Debug,
+    }
+}
+
 // ── Footer state ──────────────────────────────────────────────────────────────
 
 struct FooterState {
@@ -250,6 +272,7 @@ struct FooterState {
     status: String,
     cpu: Option<f32>,
     mem_bytes: Option<u64>,
+    min_level: LogLevel,
 }
 
 impl FooterState {
@@ -267,6 +290,7 @@ impl FooterState {
             status: "starting".to_string(),
             cpu: None,
             mem_bytes: None,
+            min_level: LogLevel::Debug,
         }
     }
 
@@ -293,7 +317,7 @@ impl FooterState {
         .lines()
         .map(|l| l.to_string())
         .collect::<Vec<_>>();
-        lines.push(format_keymap());
+        lines.push(format_keymap(self.min_level));
         // Blank line above the panel separates it from the log stream.
         lines.insert(0, String::new());
         lines
@@ -346,6 +370,9 @@ pub async fn run_dev_output(
 
     let mut footer = StickyFooter::new();
     let mut fs = FooterState::new(repo_slug, repo_branch, repo_path, worktree_name);
+    if let Some(min_level) = DevUiState::load().min_log_level {
+        fs.min_level = min_level;
+    }
     fs.refresh(&mut footer, &app_name, &adapter_name, &hosts, port);
 
     let (key_tx, mut key_rx) = mpsc::channel::<Event>(64);
@@ -407,7 +434,9 @@ pub async fn run_dev_output(
                     }
             }
             Some(log) = log_rx.recv() => {
-                footer.println(&format_log(&log));
+                if shows_log(fs.min_level, &log) {
+                    footer.println(&format_log(&log));
+                }
             }
             event = event_rx.recv() => {
                 let Some(event) = event else {
@@ -518,6 +547,17 @@ pub async fn run_dev_output(
                         KeyCode::Char('l') | KeyCode::Char('L') => {
                             let _ = control_tx.send(ControlCmd::ToggleLan).await;
                         }
+                        KeyCode::Char('v') | KeyCode::Char('V') => {
+                            fs.min_level = next_min_level(fs.min_level);
+                            let min_log_level =
+                                (fs.min_level != LogLevel::Debug).then_some(fs.min_level);
+                            DevUiState { min_log_level }.save();
+                            footer.println(&format!(
+                                "{DIM}──── showing {} logs ────{RESET}",
+                                keymap_level_label(fs.min_level)
+                            ));
+                            fs.refresh(&mut footer, &app_name, &adapter_name, &hosts, port);
+                        }
                         _ => {}
                     },
                     Event::Resize(_, _) => {
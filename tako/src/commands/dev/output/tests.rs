@@ -40,6 +40,7 @@ fn collect_process_tree_pids_handles_parent_cycle() {
 fn format_log_fields() {
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "app".to_string(),
         message: "hello".to_string(),
@@ -57,6 +58,7 @@ fn format_log_fields() {
 fn format_log_aligns_continuation_lines_under_message_column() {
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "app".to_string(),
         message: "line1\nline2\nline3".to_string(),
@@ -89,6 +91,7 @@ fn format_log_appends_fields_as_key_value_suffix() {
     fields.insert("ms".to_string(), serde_json::json!(24));
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "worker:broadcast".to_string(),
         message: "Step completed".to_string(),
@@ -105,6 +108,7 @@ fn format_log_appends_fields_as_key_value_suffix() {
 fn format_log_splits_compound_scope_with_dim_separator() {
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "worker:broadcast".to_string(),
         message: "Sleep completed".to_string(),
@@ -135,6 +139,7 @@ fn format_log_renders_error_object_as_message_only() {
     );
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Error,
         scope: "worker:broadcast".to_string(),
         message: "Run failed".to_string(),
@@ -154,6 +159,7 @@ fn format_log_skips_build_and_instance_globals() {
     fields.insert("runId".to_string(), serde_json::json!("t1"));
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "worker:broadcast".to_string(),
         message: "Run started".to_string(),
@@ -170,6 +176,7 @@ fn format_log_skips_build_and_instance_globals() {
 fn format_log_single_line_message_unaffected() {
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "app".to_string(),
         message: "no newline".to_string(),
@@ -457,6 +464,7 @@ fn format_lan_block_omits_wildcard_warning_when_none_present() {
 fn format_log_renders_kind_as_divider_with_humanized_label() {
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "tako".to_string(),
         message: String::new(),
@@ -486,6 +494,7 @@ fn format_log_renders_kind_as_divider_with_humanized_label() {
 fn format_log_without_kind_stays_a_normal_log_line() {
     let log = ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "tako".to_string(),
         message: "restarted".to_string(),
@@ -515,6 +524,7 @@ fn parse_log_line_preserves_kind_from_wire_format() {
 fn format_log_dims_lan_mode_ip_suffix() {
     let enabled = strip_ansi(&format_log(&ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "tako".to_string(),
         message: "LAN mode enabled (192.168.1.2)".to_string(),
@@ -527,6 +537,7 @@ fn format_log_dims_lan_mode_ip_suffix() {
 
     let disabled = strip_ansi(&format_log(&ScopedLog {
         timestamp: "12:34:56".to_string(),
+        unix_millis: 0,
         level: LogLevel::Info,
         scope: "tako".to_string(),
         message: "LAN mode disabled".to_string(),
@@ -661,15 +672,57 @@ fn format_panel_stacked_has_border_and_content() {
 
 #[test]
 fn format_keymap_has_restart_stop_background() {
-    let km = strip_ansi(&format_keymap());
+    let km = strip_ansi(&format_keymap(LogLevel::Debug));
     assert!(km.contains('r'));
     assert!(km.contains("restart"));
     assert!(km.contains("stop"));
     assert!(km.contains('b'));
     assert!(km.contains("background"));
+    assert!(km.contains("v all"));
     assert!(!km.contains("quit"));
 }
 
+#[test]
+fn format_keymap_shows_selected_min_level() {
+    let km = strip_ansi(&format_keymap(LogLevel::Warn));
+    assert!(km.contains("v warn+"), "{km}");
+}
+
+#[test]
+fn shows_log_hides_lines_below_min_level() {
+    let line = |level: LogLevel| ScopedLog::at(level, "app", "hello");
+    assert!(shows_log(LogLevel::Debug, &line(LogLevel::Debug)));
+    assert!(!shows_log(LogLevel::Warn, &line(LogLevel::Info)));
+    assert!(shows_log(LogLevel::Warn, &line(LogLevel::Warn)));
+    assert!(shows_log(LogLevel::Warn, &line(LogLevel::Fatal)));
+    assert!(!shows_log(LogLevel::Fatal, &line(LogLevel::Error)));
+
+    // Action dividers are never filtered out.
+    let mut divider = line(LogLevel::Info);
+    divider.kind = Some("restarted".to_string());
+    assert!(shows_log(LogLevel::Fatal, &divider));
+}
+
+#[test]
+fn next_min_level_cycles_through_every_level() {
+    let mut level = LogLevel::Debug;
+    let mut seen = Vec::new();
+    for _ in 0..5 {
+        level = next_min_level(level);
+        seen.push(level);
+    }
+    assert_eq!(
+        seen,
+        [
+            LogLevel::Info,
+            LogLevel::Warn,
+            LogLevel::Error,
+            LogLevel::Fatal,
+            LogLevel::Debug
+        ]
+    );
+}
+
 #[test]
 fn progress_bar_extremes() {
     let full = strip_ansi(&progress_bar(1.0, 8));
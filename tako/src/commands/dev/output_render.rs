@@ -451,34 +451,31 @@ fn panel_row(c1: &str, c2: &str, c3: &str, col1_w: usize, col2_w: usize) -> Stri
     )
 }
 
-pub(super) fn format_keymap() -> String {
+pub(super) fn format_keymap(min_level: LogLevel) -> String {
     let cols = terminal_cols().max(20);
-    let text = if cols < 60 {
-        format!(
-            "l {}   r {}   b {}   ^c/q {}",
-            muted("lan"),
-            muted("restart"),
-            muted("background"),
-            muted("stop")
-        )
-    } else {
-        format!(
-            "l {}   r {}   b {}   ctrl+c/q {}",
-            muted("lan"),
-            muted("restart"),
-            muted("background"),
-            muted("stop")
-        )
-    };
-    let plain = if cols < 60 {
-        "l lan   r restart   b background   ^c/q stop"
-    } else {
-        "l lan   r restart   b background   ctrl+c/q stop"
-    };
-    let pad = cols.saturating_sub(measure_text_width(plain) + 1);
+    let stop_key = if cols < 60 { "^c/q" } else { "ctrl+c/q" };
+    let level = keymap_level_label(min_level);
+    let text = format!(
+        "v {}   l {}   r {}   b {}   {stop_key} {}",
+        muted(&level),
+        muted("lan"),
+        muted("restart"),
+        muted("background"),
+        muted("stop")
+    );
+    let plain = format!("v {level}   l lan   r restart   b background   {stop_key} stop");
+    let pad = cols.saturating_sub(measure_text_width(&plain) + 1);
     format!("{}{text} ", " ".repeat(pad))
 }
 
+/// Keymap label for the TUI's minimum log level: `all` or e.g. `warn+`.
+pub(super) fn keymap_level_label(min_level: LogLevel) -> String {
+    match min_level {
+        LogLevel::Debug => "all".to_string(),
+        level => format!("{}+", level.to_string().to_lowercase()),
+    }
+}
+
 pub(super) fn fit_scope(scope: &str) -> String {
     let len = scope.len();
     if len <= SCOPE_MAX {
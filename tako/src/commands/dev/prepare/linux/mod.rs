@@ -1,12 +1,15 @@
 //! Linux portless dev mode setup
 //!
-//! Uses iptables redirect rules to transparently forward privileged ports on a
-//! dedicated loopback alias (127.77.0.1) to the unprivileged ports used by
-//! tako-dev-server. One-time sudo, persisted via a systemd oneshot service.
+//! Uses iptables redirect rules (or a managed nftables table on hosts without
+//! iptables) to transparently forward privileged ports on a dedicated loopback
+//! alias (127.77.0.1) to the unprivileged ports used by tako-dev-server.
+//! One-time sudo, persisted via a systemd oneshot service.
 //!
 //! On NixOS, imperative setup would be wiped by `nixos-rebuild`, so we detect
 //! it and print a `configuration.nix` snippet instead.
 
+mod nftables;
+
 #[cfg(target_os = "linux")]
 use super::{DEV_LOOPBACK_ADDR, LOCAL_DNS_PORT};
 
@@ -15,6 +18,14 @@ const DEV_HTTPS_PORT: u16 = 47831;
 #[cfg(target_os = "linux")]
 const DEV_HTTP_PORT: u16 = 47830;
 
+/// `(proto, dport, to_port)` redirects installed on the loopback alias.
+#[cfg(target_os = "linux")]
+const REDIRECT_RULES: [(&str, u16, u16); 3] = [
+    ("tcp", 443, DEV_HTTPS_PORT),
+    ("tcp", 80, DEV_HTTP_PORT),
+    ("udp", 53, LOCAL_DNS_PORT),
+];
+
 #[cfg(target_os = "linux")]
 const DEV_PROXY_SERVICE_NAME: &str = "tako-dev-proxy.service";
 #[cfg(target_os = "linux")]
@@ -45,6 +56,15 @@ pub(crate) struct LinuxSetupStatus {
     pub is_nixos: bool,
 }
 
+/// NAT tool used to install the redirect rules.
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RedirectBackend {
+    Iptables,
+    /// Managed `ip tako_dev` table, for hosts that ship only `nft`.
+    Nftables,
+}
+
 #[cfg(any(target_os = "linux", test))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum LinuxRepairPlan {
@@ -113,6 +133,17 @@ fn has_word(line: &str, needle: &str) -> bool {
     false
 }
 
+/// Pick the redirect backend. iptables wins when present so existing setups
+/// keep their rules; nftables is used only on nft-only hosts.
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn choose_backend(has_iptables: bool, has_nft: bool) -> RedirectBackend {
+    if !has_iptables && has_nft {
+        RedirectBackend::Nftables
+    } else {
+        RedirectBackend::Iptables
+    }
+}
+
 /// Compute the repair plan from the current status.
 #[cfg(any(target_os = "linux", test))]
 pub(crate) fn repair_plan(status: &LinuxSetupStatus) -> LinuxRepairPlan {
@@ -138,10 +169,30 @@ pub(crate) fn repair_plan(status: &LinuxSetupStatus) -> LinuxRepairPlan {
 
 // ─── Content generators ─────────────────────────────────────────────────────
 
-/// systemd oneshot service that restores the loopback alias and iptables rules
+/// systemd oneshot service that restores the loopback alias and redirect rules
 /// at boot.
-#[cfg(any(target_os = "linux", test))]
-pub(crate) fn systemd_service_contents() -> String {
+#[cfg(target_os = "linux")]
+pub(crate) fn systemd_service_contents(backend: RedirectBackend) -> String {
+    if backend == RedirectBackend::Nftables {
+        return format!(
+            "\
+[Unit]
+Description=Tako dev port redirect (127.77.0.1)
+After=network-pre.target
+Before=network.target
+
+[Service]
+Type=oneshot
+RemainAfterExit=yes
+ExecStart=-/sbin/ip addr add 127.77.0.1/8 dev lo
+ExecStart=/usr/sbin/nft -f {ruleset}
+
+[Install]
+WantedBy=multi-user.target
+",
+            ruleset = nftables::NFT_RULESET_PATH,
+        );
+    }
     format!(
         "\
 [Unit]
@@ -267,7 +318,7 @@ pub(crate) fn nixos_config_snippet() -> String {
 
 #[cfg(any(target_os = "linux", test))]
 pub(crate) fn install_action_line() -> &'static str {
-    "Set up iptables port redirect for 127.77.0.1 (443/80/53)"
+    "Set up port redirect for 127.77.0.1 (443/80/53)"
 }
 
 // ─── Imperative setup (Linux runtime only) ──────────────────────────────────
@@ -277,6 +328,14 @@ fn is_nixos() -> bool {
     std::path::Path::new("/etc/NIXOS").exists()
 }
 
+#[cfg(target_os = "linux")]
+fn redirect_backend() -> RedirectBackend {
+    choose_backend(
+        run_command("iptables", &["--version"]).is_some(),
+        run_command("nft", &["--version"]).is_some(),
+    )
+}
+
 #[cfg(target_os = "linux")]
 fn run_command(cmd: &str, args: &[&str]) -> Option<String> {
     std::process::Command::new(cmd)
@@ -342,16 +401,24 @@ fn write_system_file_with_sudo(
 #[cfg(target_os = "linux")]
 pub(crate) fn status() -> LinuxSetupStatus {
     let ip_output = run_command("ip", &["addr", "show", "dev", "lo"]).unwrap_or_default();
-    let iptables_output =
-        run_command("iptables", &["-t", "nat", "-L", "OUTPUT", "-n"]).unwrap_or_default();
-
     let loopback_alias = parse_loopback_alias(&ip_output, DEV_LOOPBACK_ADDR);
-    let redirect_443 =
-        parse_iptables_redirect(&iptables_output, DEV_LOOPBACK_ADDR, 443, DEV_HTTPS_PORT);
-    let redirect_80 =
-        parse_iptables_redirect(&iptables_output, DEV_LOOPBACK_ADDR, 80, DEV_HTTP_PORT);
-    let redirect_dns =
-        parse_iptables_redirect(&iptables_output, DEV_LOOPBACK_ADDR, 53, LOCAL_DNS_PORT);
+
+    let [redirect_443, redirect_80, redirect_dns] = match redirect_backend() {
+        RedirectBackend::Iptables => {
+            let output =
+                run_command("iptables", &["-t", "nat", "-L", "OUTPUT", "-n"]).unwrap_or_default();
+            REDIRECT_RULES.map(|(_, dport, to_port)| {
+                parse_iptables_redirect(&output, DEV_LOOPBACK_ADDR, dport, to_port)
+            })
+        }
+        RedirectBackend::Nftables => {
+            let output = run_command("nft", &["list", "table", "ip", nftables::NFT_TABLE])
+                .unwrap_or_default();
+            REDIRECT_RULES.map(|(proto, dport, to_port)| {
+                nftables::has_redirect(&output, DEV_LOOPBACK_ADDR, proto, dport, to_port)
+            })
+        }
+    };
 
     let dns_configured = std::path::Path::new(RESOLVED_DROP_IN_FILE).exists();
     let service_installed = std::path::Path::new(SYSTEMD_SERVICE_PATH).exists();
@@ -426,14 +493,28 @@ fn setup_loopback_alias() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[cfg(target_os = "linux")]
+fn setup_redirects(backend: RedirectBackend) -> Result<(), Box<dyn std::error::Error>> {
+    match backend {
+        RedirectBackend::Iptables => setup_iptables_redirects(),
+        RedirectBackend::Nftables => setup_nftables_redirects(),
+    }
+}
+
+/// Write the managed ruleset and apply it. Re-applying replaces the table, so
+/// repeated runs never stack duplicate rules.
+#[cfg(target_os = "linux")]
+fn setup_nftables_redirects() -> Result<(), Box<dyn std::error::Error>> {
+    write_system_file_with_sudo(
+        nftables::NFT_RULESET_PATH,
+        &nftables::ruleset(DEV_LOOPBACK_ADDR, &REDIRECT_RULES),
+    )?;
+    sudo_run(&["nft", "-f", nftables::NFT_RULESET_PATH])
+}
+
 #[cfg(target_os = "linux")]
 fn setup_iptables_redirects() -> Result<(), Box<dyn std::error::Error>> {
-    let rules: &[(&str, u16, u16)] = &[
-        ("tcp", 443, DEV_HTTPS_PORT),
-        ("tcp", 80, DEV_HTTP_PORT),
-        ("udp", 53, LOCAL_DNS_PORT),
-    ];
-    for (proto, dport, to_port) in rules {
+    for (proto, dport, to_port) in &REDIRECT_RULES {
         let dport_str = dport.to_string();
         let to_port_str = to_port.to_string();
         // Check first (-C), add only if missing (-A)
@@ -482,8 +563,8 @@ fn setup_iptables_redirects() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[cfg(target_os = "linux")]
-fn install_systemd_service() -> Result<(), Box<dyn std::error::Error>> {
-    write_system_file_with_sudo(SYSTEMD_SERVICE_PATH, &systemd_service_contents())?;
+fn install_systemd_service(backend: RedirectBackend) -> Result<(), Box<dyn std::error::Error>> {
+    write_system_file_with_sudo(SYSTEMD_SERVICE_PATH, &systemd_service_contents(backend))?;
     sudo_run(&["systemctl", "daemon-reload"])?;
     sudo_run(&["systemctl", "enable", SYSTEMD_SERVICE_NAME])?;
     Ok(())
@@ -578,11 +659,12 @@ pub(crate) fn ensure_installed() -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
+    let backend = redirect_backend();
     match plan {
         LinuxRepairPlan::SetupAll => {
             crate::output::info("Setting up loopback alias and port redirects (sudo)…");
             setup_loopback_alias()?;
-            setup_iptables_redirects()?;
+            setup_redirects(backend)?;
 
             if has_systemd_resolved() {
                 setup_dns_resolved()?;
@@ -597,7 +679,7 @@ pub(crate) fn ensure_installed() -> Result<(), Box<dyn std::error::Error>> {
 
             // Persist via systemd if available
             if run_command("systemctl", &["--version"]).is_some() {
-                install_systemd_service()?;
+                install_systemd_service(backend)?;
                 if let Err(e) = install_dev_proxy_service() {
                     crate::output::warning(&format!(
                         "Dev proxy service not installed (LAN mode unavailable): {e}"
@@ -616,7 +698,7 @@ pub(crate) fn ensure_installed() -> Result<(), Box<dyn std::error::Error>> {
         LinuxRepairPlan::RepairRedirects => {
             crate::output::info("Restoring port redirect rules (sudo)…");
             setup_loopback_alias()?;
-            setup_iptables_redirects()?;
+            setup_redirects(backend)?;
             crate::output::success("Port redirect rules restored.");
         }
         LinuxRepairPlan::None | LinuxRepairPlan::NixOsManual => unreachable!(),
@@ -626,7 +708,7 @@ pub(crate) fn ensure_installed() -> Result<(), Box<dyn std::error::Error>> {
     let s = status();
     if !s.loopback_alias || !s.redirect_443 || !s.redirect_80 || !s.redirect_dns {
         return Err(
-            "Port redirect setup verification failed. Check iptables and try again.".into(),
+            "Port redirect setup verification failed. Check iptables/nft and try again.".into(),
         );
     }
 
@@ -636,237 +718,4 @@ pub(crate) fn ensure_installed() -> Result<(), Box<dyn std::error::Error>> {
 // ─── Tests ──────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    // ── parse_loopback_alias ────────────────────────────────────────────
-
-    #[test]
-    fn loopback_alias_present_in_ip_addr_output() {
-        let output = "\
-1: lo: <LOOPBACK,UP,LOWER_UP> mtu 65536 qdisc noqueue state UNKNOWN group default qlen 1000
-    link/loopback 00:00:00:00:00:00 brd 00:00:00:00:00:00
-    inet 127.0.0.1/8 scope host lo
-       valid_lft forever preferred_lft forever
-    inet 127.77.0.1/8 scope host lo
-       valid_lft forever preferred_lft forever
-    inet6 ::1/128 scope host noprefixroute
-       valid_lft forever preferred_lft forever";
-        assert!(parse_loopback_alias(output, "127.77.0.1"));
-    }
-
-    #[test]
-    fn loopback_alias_absent_in_ip_addr_output() {
-        let output = "\
-1: lo: <LOOPBACK,UP,LOWER_UP> mtu 65536 qdisc noqueue state UNKNOWN group default qlen 1000
-    link/loopback 00:00:00:00:00:00 brd 00:00:00:00:00:00
-    inet 127.0.0.1/8 scope host lo
-       valid_lft forever preferred_lft forever
-    inet6 ::1/128 scope host noprefixroute
-       valid_lft forever preferred_lft forever";
-        assert!(!parse_loopback_alias(output, "127.77.0.1"));
-    }
-
-    #[test]
-    fn loopback_alias_ignores_partial_match() {
-        // Should not match 127.77.0.100 when looking for 127.77.0.1
-        let output = "    inet 127.77.0.100/8 scope host lo";
-        assert!(!parse_loopback_alias(output, "127.77.0.1"));
-    }
-
-    // ── parse_iptables_redirect ─────────────────────────────────────────
-
-    #[test]
-    fn iptables_redirect_present() {
-        let output = "\
-Chain OUTPUT (policy ACCEPT)
-target     prot opt source               destination
-REDIRECT   tcp  --  0.0.0.0/0            127.77.0.1           tcp dpt:443 redir ports 47831
-REDIRECT   tcp  --  0.0.0.0/0            127.77.0.1           tcp dpt:80 redir ports 47830
-REDIRECT   udp  --  0.0.0.0/0            127.77.0.1           udp dpt:53 redir ports 53535";
-        assert!(parse_iptables_redirect(output, "127.77.0.1", 443, 47831));
-        assert!(parse_iptables_redirect(output, "127.77.0.1", 80, 47830));
-        assert!(parse_iptables_redirect(output, "127.77.0.1", 53, 53535));
-    }
-
-    #[test]
-    fn iptables_redirect_absent() {
-        let output = "\
-Chain OUTPUT (policy ACCEPT)
-target     prot opt source               destination";
-        assert!(!parse_iptables_redirect(output, "127.77.0.1", 443, 47831));
-    }
-
-    #[test]
-    fn iptables_redirect_wrong_port() {
-        let output = "\
-Chain OUTPUT (policy ACCEPT)
-target     prot opt source               destination
-REDIRECT   tcp  --  0.0.0.0/0            127.77.0.1           tcp dpt:8443 redir ports 47831";
-        assert!(!parse_iptables_redirect(output, "127.77.0.1", 443, 47831));
-    }
-
-    #[test]
-    fn iptables_redirect_rejects_port_prefix_match() {
-        // dpt:80 should NOT match a line for dpt:8080
-        let output = "\
-Chain OUTPUT (policy ACCEPT)
-target     prot opt source               destination
-REDIRECT   tcp  --  0.0.0.0/0            127.77.0.1           tcp dpt:8080 redir ports 47830";
-        assert!(!parse_iptables_redirect(output, "127.77.0.1", 80, 47830));
-    }
-
-    #[test]
-    fn iptables_redirect_wrong_target_port() {
-        let output = "\
-Chain OUTPUT (policy ACCEPT)
-target     prot opt source               destination
-REDIRECT   tcp  --  0.0.0.0/0            127.77.0.1           tcp dpt:443 redir ports 9999";
-        assert!(!parse_iptables_redirect(output, "127.77.0.1", 443, 47831));
-    }
-
-    // ── repair_plan ─────────────────────────────────────────────────────
-
-    #[test]
-    fn repair_plan_none_when_all_ok() {
-        let status = LinuxSetupStatus {
-            loopback_alias: true,
-            redirect_443: true,
-            redirect_80: true,
-            redirect_dns: true,
-            dns_configured: true,
-            service_installed: true,
-            is_nixos: false,
-        };
-        assert_eq!(repair_plan(&status), LinuxRepairPlan::None);
-    }
-
-    #[test]
-    fn repair_plan_setup_all_when_nothing_done() {
-        let status = LinuxSetupStatus {
-            loopback_alias: false,
-            redirect_443: false,
-            redirect_80: false,
-            redirect_dns: false,
-            dns_configured: false,
-            service_installed: false,
-            is_nixos: false,
-        };
-        assert_eq!(repair_plan(&status), LinuxRepairPlan::SetupAll);
-    }
-
-    #[test]
-    fn repair_plan_nixos_manual_when_not_configured() {
-        let status = LinuxSetupStatus {
-            loopback_alias: false,
-            redirect_443: false,
-            redirect_80: false,
-            redirect_dns: false,
-            dns_configured: false,
-            service_installed: false,
-            is_nixos: true,
-        };
-        assert_eq!(repair_plan(&status), LinuxRepairPlan::NixOsManual);
-    }
-
-    #[test]
-    fn repair_plan_nixos_none_when_configured() {
-        // NixOS but everything is set up (user applied the nix config)
-        let status = LinuxSetupStatus {
-            loopback_alias: true,
-            redirect_443: true,
-            redirect_80: true,
-            redirect_dns: true,
-            dns_configured: true,
-            service_installed: true,
-            is_nixos: true,
-        };
-        assert_eq!(repair_plan(&status), LinuxRepairPlan::None);
-    }
-
-    #[test]
-    fn repair_plan_nixos_manual_when_redirects_ok_but_dns_missing() {
-        // NixOS with redirects active but DNS not configured — should still
-        // direct to NixOS manual setup, not fall through to SetupAll.
-        let status = LinuxSetupStatus {
-            loopback_alias: true,
-            redirect_443: true,
-            redirect_80: true,
-            redirect_dns: true,
-            dns_configured: false,
-            service_installed: true,
-            is_nixos: true,
-        };
-        assert_eq!(repair_plan(&status), LinuxRepairPlan::NixOsManual);
-    }
-
-    #[test]
-    fn repair_plan_repair_redirects_when_service_present_but_rules_missing() {
-        let status = LinuxSetupStatus {
-            loopback_alias: false,
-            redirect_443: false,
-            redirect_80: false,
-            redirect_dns: false,
-            dns_configured: true,
-            service_installed: true,
-            is_nixos: false,
-        };
-        assert_eq!(repair_plan(&status), LinuxRepairPlan::RepairRedirects);
-    }
-
-    #[test]
-    fn repair_plan_setup_all_when_dns_missing() {
-        let status = LinuxSetupStatus {
-            loopback_alias: true,
-            redirect_443: true,
-            redirect_80: true,
-            redirect_dns: true,
-            dns_configured: false,
-            service_installed: false,
-            is_nixos: false,
-        };
-        assert_eq!(repair_plan(&status), LinuxRepairPlan::SetupAll);
-    }
-
-    // ── content generators ──────────────────────────────────────────────
-
-    #[test]
-    fn systemd_service_contains_expected_commands() {
-        let content = systemd_service_contents();
-        assert!(content.contains("/sbin/ip addr add 127.77.0.1/8 dev lo"));
-        assert!(content.contains("--dport 443"));
-        assert!(content.contains("--to-port 47831"));
-        assert!(content.contains("--dport 80"));
-        assert!(content.contains("--to-port 47830"));
-        assert!(content.contains("--dport 53"));
-        assert!(content.contains("--to-port 53535"));
-        assert!(content.contains("RemainAfterExit=yes"));
-        assert!(content.contains("[Install]"));
-        assert!(content.contains("WantedBy=multi-user.target"));
-    }
-
-    #[test]
-    fn resolved_drop_in_routes_tako_test() {
-        let content = resolved_drop_in_contents();
-        assert!(content.contains("DNS=127.77.0.1"));
-        assert!(content.contains("Domains=~tako.test ~test"));
-    }
-
-    #[test]
-    fn nixos_snippet_contains_all_pieces() {
-        let snippet = nixos_config_snippet();
-        assert!(snippet.contains("tako-dev-redirect"));
-        assert!(snippet.contains("127.77.0.1"));
-        assert!(snippet.contains("--dport 443"));
-        assert!(snippet.contains("--to-port 47831"));
-        assert!(snippet.contains("tako.test"));
-        assert!(snippet.contains("resolved"));
-    }
-
-    // ── action lines ────────────────────────────────────────────────────
-
-    #[test]
-    fn action_lines_are_nonempty() {
-        assert!(!install_action_line().is_empty());
-    }
-}
+mod tests;
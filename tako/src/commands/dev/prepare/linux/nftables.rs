@@ -0,0 +1,108 @@
+//! nftables backend for the Linux port redirect
+//!
+//! Used when `iptables` is not installed (nft-only distributions). All rules
+//! live in a dedicated `ip tako_dev` table so they can be replaced atomically
+//! with `nft -f` and removed with a single `nft delete table ip tako_dev`.
+
+/// Name of the managed nftables table (family `ip`).
+pub(crate) const NFT_TABLE: &str = "tako_dev";
+
+/// Ruleset file applied at setup and by the boot-time systemd unit.
+pub(crate) const NFT_RULESET_PATH: &str = "/etc/tako/dev-redirect.nft";
+
+/// Build an `nft -f` script that (re)creates the managed table with one
+/// redirect per `(proto, dport, to_port)`.
+///
+/// The leading `table` + `delete table` pair makes the script idempotent:
+/// declaring the table first guarantees the delete succeeds, so applying the
+/// script twice replaces the rules instead of duplicating them.
+pub(crate) fn ruleset(dest_ip: &str, redirects: &[(&str, u16, u16)]) -> String {
+    let mut out = format!("table ip {NFT_TABLE} {{}}\ndelete table ip {NFT_TABLE}\n");
+    out.push_str(&format!("table ip {NFT_TABLE} {{\n"));
+    out.push_str("\tchain output {\n");
+    out.push_str("\t\ttype nat hook output priority -100; policy accept;\n");
+    for (proto, dport, to_port) in redirects {
+        out.push_str(&format!("\t\t{}\n", rule(dest_ip, proto, *dport, *to_port)));
+    }
+    out.push_str("\t}\n}\n");
+    out
+}
+
+/// A single redirect rule as `nft list table` prints it.
+fn rule(dest_ip: &str, proto: &str, dport: u16, to_port: u16) -> String {
+    format!("ip daddr {dest_ip} {proto} dport {dport} redirect to :{to_port}")
+}
+
+/// Parse `nft list table ip tako_dev` output for a specific redirect rule.
+///
+/// Lines are compared token-by-token so `dport 80` never matches `dport 8080`
+/// and trailing annotations (e.g. `# handle 3`) are ignored.
+pub(crate) fn has_redirect(
+    nft_output: &str,
+    dest_ip: &str,
+    proto: &str,
+    dport: u16,
+    to_port: u16,
+) -> bool {
+    let expected = rule(dest_ip, proto, dport, to_port);
+    let expected: Vec<&str> = expected.split_whitespace().collect();
+    nft_output.lines().any(|line| {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        tokens.starts_with(&expected) && tokens.get(expected.len()).is_none_or(|t| *t == "#")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REDIRECTS: &[(&str, u16, u16)] =
+        &[("tcp", 443, 47831), ("tcp", 80, 47830), ("udp", 53, 53535)];
+
+    #[test]
+    fn ruleset_replaces_managed_table() {
+        let script = ruleset("127.77.0.1", REDIRECTS);
+        let lines: Vec<&str> = script.lines().collect();
+        assert_eq!(lines[0], "table ip tako_dev {}");
+        assert_eq!(lines[1], "delete table ip tako_dev");
+        assert_eq!(lines[2], "table ip tako_dev {");
+        assert_eq!(script.matches("delete table").count(), 1);
+        assert!(script.contains("type nat hook output priority -100; policy accept;"));
+        assert!(script.contains("ip daddr 127.77.0.1 tcp dport 443 redirect to :47831"));
+        assert!(script.contains("ip daddr 127.77.0.1 tcp dport 80 redirect to :47830"));
+        assert!(script.contains("ip daddr 127.77.0.1 udp dport 53 redirect to :53535"));
+    }
+
+    #[test]
+    fn ruleset_is_stable_across_applications() {
+        assert_eq!(
+            ruleset("127.77.0.1", REDIRECTS),
+            ruleset("127.77.0.1", REDIRECTS)
+        );
+    }
+
+    #[test]
+    fn has_redirect_matches_listed_rules() {
+        let output = "\
+table ip tako_dev {
+\tchain output {
+\t\ttype nat hook output priority dstnat; policy accept;
+\t\tip daddr 127.77.0.1 tcp dport 443 redirect to :47831 # handle 2
+\t\tip daddr 127.77.0.1 tcp dport 80 redirect to :47830 # handle 3
+\t\tip daddr 127.77.0.1 udp dport 53 redirect to :53535 # handle 4
+\t}
+}";
+        assert!(has_redirect(output, "127.77.0.1", "tcp", 443, 47831));
+        assert!(has_redirect(output, "127.77.0.1", "tcp", 80, 47830));
+        assert!(has_redirect(output, "127.77.0.1", "udp", 53, 53535));
+    }
+
+    #[test]
+    fn has_redirect_rejects_port_prefix_and_wrong_target() {
+        let output = "\t\tip daddr 127.77.0.1 tcp dport 8080 redirect to :47830\n\
+                      \t\tip daddr 127.77.0.1 tcp dport 443 redirect to :9999";
+        assert!(!has_redirect(output, "127.77.0.1", "tcp", 80, 47830));
+        assert!(!has_redirect(output, "127.77.0.1", "tcp", 443, 47831));
+        assert!(!has_redirect(output, "127.77.0.1", "udp", 443, 9999));
+    }
+}
@@ -35,6 +35,7 @@ pub(super) struct DevSession {
     pub dev_hosts: Vec<String>,
     pub env: HashMap<String, String>,
     pub interactive: bool,
+    pub companions: Vec<DevCompanion>,
 }
 
 pub(super) enum PrepareOutcome {
@@ -42,11 +43,41 @@ pub(super) enum PrepareOutcome {
     AlreadyConnected,
 }
 
-pub(super) async fn prepare(
-    public_port: u16,
-    variant: Option<String>,
+/// One project's config resolved for `tako dev`: its app identity plus the
+/// command and environment its process starts with.
+pub(super) struct DevProject {
+    pub config_key: String,
+    pub config_path: PathBuf,
+    pub project_dir: PathBuf,
+    pub base_name: String,
+    pub app_name: String,
+    pub runtime_name: String,
+    pub cfg: crate::config::TakoToml,
+    pub cmd: Vec<String>,
+    pub readiness_failure_hint: Option<String>,
+    pub worker_command: Option<Vec<String>>,
+    pub env: HashMap<String, String>,
+}
+
+/// Another project run alongside the main app in one `tako dev` session
+/// (`--with`). The daemon supervises its process; the session registers it,
+/// relays its logs, and unregisters it on exit.
+pub(super) struct DevCompanion {
+    pub config_key: String,
+    pub project_dir: PathBuf,
+    pub app_name: String,
+    pub hosts: Vec<String>,
+    pub cmd: Vec<String>,
+    pub readiness_failure_hint: Option<String>,
+    pub worker_command: Option<Vec<String>>,
+    pub env: HashMap<String, String>,
+}
+
+pub(super) async fn resolve_dev_project(
     config_path: Option<&Path>,
-) -> Result<PrepareOutcome, Box<dyn std::error::Error>> {
+    variant: Option<&str>,
+    existing_apps: &[(String, String)],
+) -> Result<DevProject, Box<dyn std::error::Error>> {
     let context = crate::commands::project_context::resolve_existing(config_path)?;
     let config_key = context.config_key();
     let project_dir = context.project_dir.clone();
@@ -68,6 +99,15 @@ pub(super) async fn prepare(
     };
     apply_adapter_base_runtime_defaults(&mut build_preset, runtime_adapter, Some(&plugin_ctx))
         .map_err(|e| format!("Failed to apply runtime defaults to preset: {}", e))?;
+    // Dev runs whatever toolchain is installed, so a mismatch only warns.
+    if let Err(mismatch) = crate::build::check_preset_runtime_version(
+        &build_preset,
+        runtime_adapter.id(),
+        &eff_app_dir,
+        None,
+    ) {
+        crate::output::warning(&mismatch);
+    }
     let main = crate::commands::deploy::resolve_deploy_main(
         &eff_app_dir,
         runtime_adapter,
@@ -84,14 +124,135 @@ pub(super) async fn prepare(
 
     let base_name = resolve_app_name_from_config_path(&config_path)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
-    let app_name = if let Some(ref v) = variant {
+    let app_name = if let Some(v) = variant {
         format!("{base_name}-{v}")
     } else {
         base_name.clone()
     };
+    let app_name = disambiguate_app_name(&app_name, &config_key, existing_apps);
+
+    let mut env = compute_dev_env(&cfg);
+    inject_dev_data_dir(&project_dir, &mut env).map_err(|e| e.to_string())?;
+    inject_dev_secrets(&project_dir, &mut env).map_err(|e| e.to_string())?;
+
+    let cmd = resolve_dev_run_command(
+        &cfg,
+        &build_preset,
+        &main,
+        runtime_adapter,
+        has_explicit_dev_preset(&cfg),
+        &project_dir,
+    )
+    .map_err(|e| format!("Invalid dev start command: {}", e))?;
+    let readiness_failure_hint = readiness_failure_hint_for_dev_command(&cmd);
+    let worker_command = resolve_dev_worker_command(&project_dir, runtime_adapter);
+
+    Ok(DevProject {
+        config_key,
+        config_path,
+        project_dir,
+        base_name,
+        app_name,
+        runtime_name,
+        cfg,
+        cmd,
+        readiness_failure_hint,
+        worker_command,
+        env,
+    })
+}
+
+/// Default host for an app: `{app}.test` when the short resolver is active,
+/// `{app}.tako.test` otherwise.
+pub(super) fn dev_app_domain(app_name: &str, short_domain_active: bool) -> String {
+    if short_domain_active {
+        LocalCA::app_short_domain(app_name)
+    } else {
+        LocalCA::app_domain(app_name)
+    }
+}
+
+/// Resolve every `--with` project, giving each an app name that is unique
+/// among registered apps, the main app, and the other companions.
+async fn resolve_companions(
+    configs: &[PathBuf],
+    main_app: (&str, &str),
+    mut existing_apps: Vec<(String, String)>,
+    short_domain_active: bool,
+) -> Result<Vec<DevCompanion>, Box<dyn std::error::Error>> {
+    let (main_app_name, main_config_key) = main_app;
+    existing_apps.push((main_app_name.to_string(), main_config_key.to_string()));
+    let mut companions: Vec<DevCompanion> = Vec::new();
+    for config in configs {
+        let config = companion_config_path(config);
+        let project = resolve_dev_project(Some(&config), None, &existing_apps).await?;
+        if project.config_key == main_config_key
+            || companions
+                .iter()
+                .any(|c| c.config_key == project.config_key)
+        {
+            continue;
+        }
+        existing_apps.push((project.app_name.clone(), project.config_key.clone()));
+        let companion = companion_from_project(project, short_domain_active)?;
+        companions.push(companion);
+    }
+    Ok(companions)
+}
 
+/// `--with` takes a config file or a project directory holding `tako.toml`.
+pub(super) fn companion_config_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join("tako.toml")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+pub(super) fn companion_from_project(
+    project: DevProject,
+    short_domain_active: bool,
+) -> Result<DevCompanion, String> {
+    let domain = dev_app_domain(&project.app_name, short_domain_active);
+    let hosts = compute_dev_hosts(&project.app_name, &project.cfg, &domain, None).map_err(|e| {
+        format!(
+            "invalid development routes in {}: {}",
+            project.config_path.display(),
+            e
+        )
+    })?;
+    Ok(DevCompanion {
+        config_key: project.config_key,
+        project_dir: project.project_dir,
+        app_name: project.app_name,
+        hosts,
+        cmd: project.cmd,
+        readiness_failure_hint: project.readiness_failure_hint,
+        worker_command: project.worker_command,
+        env: project.env,
+    })
+}
+
+pub(super) async fn prepare(
+    public_port: u16,
+    variant: Option<String>,
+    config_path: Option<&Path>,
+    with: &[PathBuf],
+) -> Result<PrepareOutcome, Box<dyn std::error::Error>> {
     let existing_apps = try_list_registered_app_names().await;
-    let app_name = disambiguate_app_name(&app_name, &config_key, &existing_apps);
+    let DevProject {
+        config_key,
+        config_path,
+        project_dir,
+        base_name,
+        app_name,
+        runtime_name,
+        cfg,
+        cmd,
+        readiness_failure_hint,
+        worker_command,
+        env,
+    } = resolve_dev_project(config_path, variant.as_deref(), &existing_apps).await?;
 
     #[cfg(target_os = "macos")]
     macos::explain_pending_sudo_setup(LOCAL_DNS_PORT)?;
@@ -99,23 +260,15 @@ pub(super) async fn prepare(
     linux::explain_pending_sudo_setup()?;
 
     let local_ca = setup_local_ca().await?;
-    let tls_material_updated = ensure_dev_server_tls_material(&local_ca, &app_name)?;
     let short_domain_active = ensure_local_dns_resolver_configured(LOCAL_DNS_PORT)?;
 
-    let domain = if short_domain_active {
-        LocalCA::app_short_domain(&app_name)
-    } else {
-        LocalCA::app_domain(&app_name)
-    };
-    let base_domain = if variant.is_some() {
-        if short_domain_active {
-            Some(LocalCA::app_short_domain(&base_name))
-        } else {
-            Some(LocalCA::app_domain(&base_name))
-        }
-    } else {
-        None
-    };
+    let domain = dev_app_domain(&app_name, short_domain_active);
+    let base_domain = variant
+        .is_some()
+        .then(|| dev_app_domain(&base_name, short_domain_active));
+    let dev_hosts = compute_dev_hosts(&app_name, &cfg, &domain, base_domain.as_deref())
+        .map_err(|e| format!("invalid development routes: {}", e))?;
+    let tls_material_updated = ensure_dev_server_tls_material(&local_ca, &app_name, &dev_hosts)?;
 
     #[cfg(target_os = "macos")]
     macos::ensure_installed()?;
@@ -161,8 +314,6 @@ pub(super) async fn prepare(
         wait_for_dev_server_stopped(&listen_addr).await;
     }
 
-    let dev_hosts = compute_dev_hosts(&app_name, &cfg, &domain, base_domain.as_deref())
-        .map_err(|e| format!("invalid development routes: {}", e))?;
     let primary_host = dev_hosts
         .iter()
         .map(|h| h.split('/').next().unwrap_or(h))
@@ -170,26 +321,6 @@ pub(super) async fn prepare(
         .map(|h| h.to_string())
         .unwrap_or_else(|| domain.clone());
 
-    let mut env = compute_dev_env(&cfg);
-    inject_dev_data_dir(&project_dir, &mut env).map_err(|e| e.to_string())?;
-    inject_dev_secrets(&project_dir, &mut env).map_err(|e| e.to_string())?;
-
-    if runtime_adapter.preset_group() == PresetGroup::Js {
-        let _ = crate::build::js::write_typegen_support_files(&project_dir);
-    }
-
-    let cmd = resolve_dev_run_command(
-        &cfg,
-        &build_preset,
-        &main,
-        runtime_adapter,
-        has_explicit_dev_preset(&cfg),
-        &project_dir,
-    )
-    .map_err(|e| format!("Invalid dev start command: {}", e))?;
-    let readiness_failure_hint = readiness_failure_hint_for_dev_command(&cmd);
-    let worker_command = resolve_dev_worker_command(&project_dir, runtime_adapter);
-
     // Start (or connect to) the dev server daemon.
     if let Err(e) = crate::dev_server_client::ensure_running(&listen_addr, daemon_dns_ip).await {
         return Err(format!("dev server failed to start: {}", e).into());
@@ -237,7 +368,7 @@ pub(super) async fn prepare(
     if final_dns_ip != daemon_dns_ip {
         crate::dev_server_client::stop_server().await?;
         for _ in 0..40 {
-            if crate::dev_server_client::info().await.is_err() {
+            if !crate::dev_server_client::is_responding().await {
                 break;
             }
             tokio::time::sleep(Duration::from_millis(50)).await;
@@ -271,10 +402,23 @@ pub(super) async fn prepare(
             pid: existing.pid,
         };
         let display_hosts = compute_display_routes(&cfg, &domain, base_domain.as_deref());
+        if !with.is_empty() {
+            crate::output::warning(
+                "Attaching to a running session; --with only applies when starting one.",
+            );
+        }
         run_connected_dev_client(&app_name, interactive, session, display_hosts).await?;
         return Ok(PrepareOutcome::AlreadyConnected);
     }
 
+    let companions = resolve_companions(
+        with,
+        (&app_name, &config_key),
+        existing_apps,
+        short_domain_active,
+    )
+    .await?;
+
     Ok(PrepareOutcome::Ready(Box::new(DevSession {
         config_key,
         config_path,
@@ -294,6 +438,7 @@ pub(super) async fn prepare(
         dev_hosts,
         env,
         interactive,
+        companions,
     })))
 }
 
@@ -309,7 +454,7 @@ pub(super) async fn wait_for_dev_server_stopped_with_socket_path(
     socket_path: Option<&std::path::Path>,
 ) {
     for _ in 0..40 {
-        let info_available = crate::dev_server_client::info().await.is_ok();
+        let info_available = crate::dev_server_client::is_responding().await;
         let socket_still_exists = socket_path.is_some_and(|path| path.exists());
         if !info_available && !socket_still_exists {
             break;
@@ -110,6 +110,12 @@ fn default_dev_tls_names_for_app(app_name: &str) -> Vec<String> {
     ]
 }
 
+/// Hostname part of a route pattern like `api.shop.test/v1`.
+fn route_host_name(route: &str) -> Option<String> {
+    let host = route.split('/').next().unwrap_or(route).trim();
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
 fn normalize_tls_names(mut names: Vec<String>) -> Vec<String> {
     names.sort();
     names.dedup();
@@ -138,10 +144,15 @@ fn ca_fingerprint_matches(ca: &LocalCA, home: &Path) -> bool {
     stored.trim() == ca_fingerprint(ca)
 }
 
+/// Make sure the dev leaf certificate covers `app_name`'s default names and
+/// its configured dev `hosts` (route patterns; any path is ignored), on top
+/// of every name it already covered. Returns whether new material was
+/// written.
 pub(crate) fn ensure_dev_server_tls_material_for_home(
     ca: &LocalCA,
     home: &Path,
     app_name: &str,
+    hosts: &[String],
 ) -> Result<bool, Box<dyn std::error::Error>> {
     let (cert_path, key_path) = dev_server_tls_paths_for_home(home);
     let names_path = dev_server_tls_names_path_for_home(home);
@@ -153,6 +164,7 @@ pub(crate) fn ensure_dev_server_tls_material_for_home(
         None
     };
     let mut names = default_dev_tls_names_for_app(app_name);
+    names.extend(hosts.iter().filter_map(|host| route_host_name(host)));
     if let Some(existing) = existing_names.clone() {
         names.extend(existing);
     }
@@ -167,6 +179,55 @@ pub(crate) fn ensure_dev_server_tls_material_for_home(
         return Ok(false);
     }
 
+    write_dev_server_tls_material(ca, home, &names)?;
+    Ok(true)
+}
+
+pub(crate) fn ensure_dev_server_tls_material(
+    ca: &LocalCA,
+    app_name: &str,
+    hosts: &[String],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let data_dir = crate::paths::tako_data_dir()?;
+    ensure_dev_server_tls_material_for_home(ca, &data_dir, app_name, hosts)
+}
+
+/// Reissue the leaf certificate from `ca` for the names it already covers,
+/// replacing whatever is on disk. Without a readable names manifest, the
+/// leaf covers just the shared dev domains until the next `tako dev` adds
+/// its app. Returns the covered names.
+pub(crate) fn renew_dev_server_tls_material_for_home(
+    ca: &LocalCA,
+    home: &Path,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let names =
+        load_dev_tls_names(&dev_server_tls_names_path_for_home(home)).unwrap_or_else(|| {
+            let d = crate::dev::TAKO_DEV_DOMAIN;
+            let s = crate::dev::SHORT_DEV_DOMAIN;
+            normalize_tls_names(vec![
+                format!("*.{s}"),
+                s.to_string(),
+                format!("*.{d}"),
+                d.to_string(),
+            ])
+        });
+    write_dev_server_tls_material(ca, home, &names)?;
+    Ok(names)
+}
+
+pub(crate) fn renew_dev_server_tls_material(
+    ca: &LocalCA,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let data_dir = crate::paths::tako_data_dir()?;
+    renew_dev_server_tls_material_for_home(ca, &data_dir)
+}
+
+fn write_dev_server_tls_material(
+    ca: &LocalCA,
+    home: &Path,
+    names: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (cert_path, key_path) = dev_server_tls_paths_for_home(home);
     if let Some(parent) = cert_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
@@ -175,17 +236,12 @@ pub(crate) fn ensure_dev_server_tls_material_for_home(
     let cert = ca.generate_leaf_cert_for_names(&name_refs)?;
     std::fs::write(&cert_path, cert.cert_pem.as_bytes())?;
     std::fs::write(&key_path, cert.key_pem.as_bytes())?;
-    std::fs::write(&names_path, serde_json::to_string_pretty(&names)?)?;
+    std::fs::write(
+        dev_server_tls_names_path_for_home(home),
+        serde_json::to_string_pretty(names)?,
+    )?;
     std::fs::write(ca_fingerprint_path_for_home(home), ca_fingerprint(ca))?;
-    Ok(true)
-}
-
-pub(crate) fn ensure_dev_server_tls_material(
-    ca: &LocalCA,
-    app_name: &str,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    let data_dir = crate::paths::tako_data_dir()?;
-    ensure_dev_server_tls_material_for_home(ca, &data_dir, app_name)
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,8 +1,8 @@
 use std::path::{Path, PathBuf};
 
 use crate::build::{
-    BuildAdapter, BuildPreset, PresetReference, infer_adapter_from_preset_reference,
-    parse_preset_reference, qualify_runtime_local_preset_ref,
+    BuildAdapter, BuildPreset, infer_adapter_from_preset_reference, parse_preset_reference,
+    qualify_runtime_local_preset_ref,
 };
 use crate::config::TakoToml;
 use crate::validation::validate_dev_route;
@@ -330,6 +330,9 @@ pub(super) fn has_explicit_dev_preset(cfg: &TakoToml) -> bool {
         .is_some_and(|preset| !preset.is_empty())
 }
 
+/// Resolve the dev start command. `{main}` expands here; `{port}` and
+/// `{host}` stay verbatim and are expanded by the dev server at spawn time,
+/// where the app's port and bind address are known.
 pub(super) fn resolve_dev_run_command(
     cfg: &TakoToml,
     preset: &BuildPreset,
@@ -396,7 +399,7 @@ fn command_invokes_vite_dev(command: &[String]) -> bool {
 
 pub(super) fn infer_preset_name_from_ref(preset_ref: &str) -> String {
     match parse_preset_reference(preset_ref) {
-        Ok(PresetReference::OfficialAlias { name, .. }) => name,
+        Ok(reference) => reference.display_name(),
         Err(_) => "preset".to_string(),
     }
 }
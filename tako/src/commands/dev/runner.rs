@@ -6,6 +6,7 @@ use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::sync::watch;
 
+use super::log_filter::LogFilter;
 use super::prepare::{DevSession, PrepareOutcome, prepare};
 use super::*;
 
@@ -68,6 +69,49 @@ pub async fn stop(
     Ok(())
 }
 
+/// Find a registered dev app by name, or the current project's app when no
+/// name is given.
+async fn find_registered_app(
+    name: Option<String>,
+    config_path: Option<&Path>,
+) -> Result<crate::dev_server_client::RegisteredAppInfo, Box<dyn std::error::Error>> {
+    let apps = crate::dev_server_client::list_registered_apps().await?;
+    let app = match name {
+        Some(name) => apps
+            .into_iter()
+            .find(|a| a.app_name == name)
+            .ok_or_else(|| format!("No registered dev app named '{}'", name))?,
+        None => {
+            let context = crate::commands::project_context::resolve(config_path)?;
+            let config_key = context.config_key();
+            apps.into_iter()
+                .find(|a| a.config_path == config_key)
+                .ok_or_else(|| {
+                    format!(
+                        "{} is not registered with the dev server; run `tako dev` first",
+                        context.config_path.display()
+                    )
+                })?
+        }
+    };
+    Ok(app)
+}
+
+/// Restart a registered dev app's process without re-registering it.
+pub async fn restart(
+    name: Option<String>,
+    config_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let app = find_registered_app(name, config_path).await?;
+
+    crate::dev_server_client::restart_app(&app.config_path).await?;
+    crate::output::success(&format!(
+        "Restarting {}",
+        crate::output::strong(&app.app_name)
+    ));
+    Ok(())
+}
+
 pub async fn ls() -> Result<(), Box<dyn std::error::Error>> {
     let apps = match crate::dev_server_client::list_registered_apps().await {
         Ok(apps) => apps,
@@ -101,8 +145,9 @@ pub async fn run(
     public_port: u16,
     variant: Option<String>,
     config_path: Option<&Path>,
+    with: &[std::path::PathBuf],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let session = match prepare(public_port, variant, config_path).await? {
+    let session = match prepare(public_port, variant, config_path, with).await? {
         PrepareOutcome::Ready(s) => *s,
         PrepareOutcome::AlreadyConnected => return Ok(()),
     };
@@ -126,6 +171,7 @@ pub async fn run(
         dev_hosts,
         env,
         interactive,
+        companions,
     } = session;
 
     let hosts_state = Arc::new(tokio::sync::Mutex::new(dev_hosts.clone()));
@@ -181,6 +227,17 @@ pub async fn run(
                                 break;
                             }
                         }
+                        DevServerEvent::RegistrationExpired {
+                            ref config_path, ..
+                        } if config_path == &config_key => {
+                            let _ = event_tx
+                                .send(DevEvent::ExitWithMessage(
+                                    REGISTRATION_EXPIRED_MESSAGE.to_string(),
+                                ))
+                                .await;
+                            let _ = should_exit_tx.send(true);
+                            break;
+                        }
                         DevServerEvent::ClientConnected {
                             ref config_path,
                             client_id,
@@ -288,29 +345,44 @@ pub async fn run(
         })
         .unwrap_or(false);
 
-    {
-        let log_tx = log_tx.clone();
-        let config_key = config_key.clone();
-        tokio::spawn(async move {
-            let Ok(mut rx) = crate::dev_server_client::subscribe_logs(&config_key, None).await
-            else {
-                return;
-            };
-            while let Some(entry) = rx.recv().await {
-                match entry {
-                    crate::dev_server_client::LogStreamEntry::Entry { line, .. } => {
-                        if let Some(log) = parse_log_line(&line) {
-                            let _ = log_tx.send(log).await;
-                        }
-                    }
-                    crate::dev_server_client::LogStreamEntry::Truncated => {
-                        let _ = log_tx
-                            .send(ScopedLog::info("tako", "earlier logs trimmed"))
-                            .await;
-                    }
-                }
+    forward_app_logs(config_key.clone(), None, log_tx.clone());
+
+    for companion in &companions {
+        let registered = crate::dev_server_client::register_app(
+            &companion.config_key,
+            &companion.project_dir.to_string_lossy(),
+            &companion.app_name,
+            None,
+            &companion.hosts,
+            &companion.cmd,
+            &companion.env,
+            companion.readiness_failure_hint.as_deref(),
+            companion.worker_command.as_deref(),
+        )
+        .await;
+        match registered {
+            Ok(url) => {
+                let _ = log_tx
+                    .send(ScopedLog::info(
+                        "tako",
+                        format!("{} running at {}", companion.app_name, url),
+                    ))
+                    .await;
+                forward_app_logs(
+                    companion.config_key.clone(),
+                    Some(companion.app_name.clone()),
+                    log_tx.clone(),
+                );
             }
-        });
+            Err(e) => {
+                let _ = log_tx
+                    .send(ScopedLog::error(
+                        "tako",
+                        format!("failed to start {}: {}", companion.app_name, e),
+                    ))
+                    .await;
+            }
+        }
     }
 
     if reg_hosts.iter().any(|h| {
@@ -703,5 +775,95 @@ pub async fn run(
     }
 
     let _ = crate::dev_server_client::unregister_app(&config_key).await;
+    for companion in &companions {
+        let _ = crate::dev_server_client::unregister_app(&companion.config_key).await;
+    }
+    Ok(())
+}
+
+/// Print a registered dev app's stored logs that pass `filter`, then keep
+/// printing new ones when `follow` is set.
+pub async fn logs(
+    name: Option<String>,
+    since: Option<&str>,
+    grep: Option<String>,
+    level: Option<&str>,
+    scope: Option<String>,
+    follow: bool,
+    config_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let filter = LogFilter::new(since, grep, level, scope)?;
+    let app = find_registered_app(name, config_path).await?;
+    let mut rx = crate::dev_server_client::subscribe_logs(&app.config_path, None, follow).await?;
+
+    let colorize = std::io::IsTerminal::is_terminal(&std::io::stdout());
+    let print = |log: &ScopedLog| {
+        if !filter.matches(log) {
+            return;
+        }
+        if colorize {
+            println!("{}", output_render::format_log(log));
+        } else {
+            println!(
+                "{} {:<5} [{}] {}",
+                log.timestamp, log.level, log.scope, log.message
+            );
+        }
+    };
+
+    loop {
+        let entry = tokio::select! {
+            entry = rx.recv() => entry,
+            _ = tokio::signal::ctrl_c() => None,
+        };
+        match entry {
+            Some(crate::dev_server_client::LogStreamEntry::Entry { line, .. }) => {
+                if let Some(log) = parse_log_line(&line) {
+                    print(&log);
+                }
+            }
+            Some(crate::dev_server_client::LogStreamEntry::Truncated) => {
+                print(&ScopedLog::info("tako", "earlier logs trimmed"));
+            }
+            None => break,
+        }
+    }
     Ok(())
 }
+
+/// Relay an app's daemon log stream into the session output. Lines from a
+/// `--with` app are scoped under its name so they stand apart from the main
+/// app's.
+fn forward_app_logs(config_key: String, app_name: Option<String>, log_tx: mpsc::Sender<ScopedLog>) {
+    tokio::spawn(async move {
+        let Ok(mut rx) = crate::dev_server_client::subscribe_logs(&config_key, None, true).await
+        else {
+            return;
+        };
+        while let Some(entry) = rx.recv().await {
+            match entry {
+                crate::dev_server_client::LogStreamEntry::Entry { line, .. } => {
+                    if let Some(mut log) = parse_log_line(&line) {
+                        if let Some(app_name) = &app_name {
+                            log.scope = companion_scope(app_name, &log.scope);
+                        }
+                        let _ = log_tx.send(log).await;
+                    }
+                }
+                crate::dev_server_client::LogStreamEntry::Truncated => {
+                    let _ = log_tx
+                        .send(ScopedLog::info("tako", "earlier logs trimmed"))
+                        .await;
+                }
+            }
+        }
+    });
+}
+
+pub(super) fn companion_scope(app_name: &str, scope: &str) -> String {
+    if scope == "app" {
+        app_name.to_string()
+    } else {
+        format!("{app_name}:{scope}")
+    }
+}
@@ -4,7 +4,8 @@ use super::*;
 use crate::build::{BuildAdapter, parse_and_validate_preset};
 use crate::config::TakoToml;
 use crate::dev::LocalCA;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tempfile::TempDir;
 
@@ -35,6 +36,42 @@ fn resolve_dev_preset_ref_qualifies_runtime_local_alias() {
     );
 }
 
+#[test]
+fn resolve_dev_preset_ref_passes_pinned_github_ref_through() {
+    let temp = TempDir::new().unwrap();
+    let cfg = TakoToml {
+        runtime: Some("bun".to_string()),
+        preset: Some("acme/presets@v1.2.0:presets/remix.toml".to_string()),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        resolve_dev_preset_ref(temp.path(), &cfg).unwrap(),
+        "acme/presets@v1.2.0:presets/remix.toml"
+    );
+}
+
+#[test]
+fn infer_preset_name_from_ref_handles_aliases_and_pinned_github_refs() {
+    assert_eq!(infer_preset_name_from_ref("bun"), "bun");
+    assert_eq!(
+        infer_preset_name_from_ref("javascript/tanstack-start@abc1234"),
+        "javascript/tanstack-start"
+    );
+    assert_eq!(
+        infer_preset_name_from_ref("acme/presets@abc1234:presets/remix.toml"),
+        "remix"
+    );
+    assert_eq!(
+        infer_preset_name_from_ref("acme/presets@v1.2.0:remix.toml"),
+        "remix"
+    );
+    assert_eq!(
+        infer_preset_name_from_ref("acme/presets:remix.toml"),
+        "preset"
+    );
+}
+
 #[test]
 fn resolve_dev_preset_ref_errors_when_runtime_is_unknown_for_local_alias() {
     let temp = TempDir::new().unwrap();
@@ -148,6 +185,29 @@ main = "src/index.ts"
     assert_eq!(cmd, vec!["vite", "dev"]);
 }
 
+#[test]
+fn resolve_dev_run_command_leaves_port_and_host_for_the_dev_server() {
+    let mut preset = parse_and_validate_preset("", "custom").unwrap();
+    preset.dev = vec![
+        "run".to_string(),
+        "--port".to_string(),
+        "{port}".to_string(),
+        "--host={host}".to_string(),
+    ];
+
+    let cmd = resolve_dev_run_command(
+        &TakoToml::default(),
+        &preset,
+        "src/index.ts",
+        BuildAdapter::Bun,
+        true,
+        Path::new("/project"),
+    )
+    .expect("preset dev command");
+
+    assert_eq!(cmd, vec!["run", "--port", "{port}", "--host={host}"]);
+}
+
 #[test]
 fn tanstack_start_bun_dev_resolves_to_bunx_bun_vite_dev_end_to_end() {
     let _lock = crate::paths::test_tako_home_env_lock();
@@ -444,6 +504,20 @@ LOG_LEVEL = "debug"
     assert_eq!(env.get("LOG_LEVEL").map(String::as_str), Some("debug"));
 }
 
+#[test]
+fn compute_dev_env_excludes_build_env() {
+    let cfg = TakoToml::parse(
+        r#"
+[build.env]
+NPM_TOKEN = "secret"
+"#,
+    )
+    .unwrap();
+
+    let env = compute_dev_env(&cfg);
+    assert!(!env.contains_key("NPM_TOKEN"));
+}
+
 #[tokio::test]
 async fn tcp_probe_detects_open_port() {
     let Ok(listener) = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await else {
@@ -831,7 +905,7 @@ fn dev_server_tls_paths_are_under_certs_dir() {
 fn ensure_dev_server_tls_material_writes_cert_and_key_when_missing() {
     let temp = TempDir::new().unwrap();
     let ca = LocalCA::generate().unwrap();
-    let changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo").unwrap();
+    let changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo", &[]).unwrap();
     assert!(changed);
 
     let (cert_path, key_path) = dev_server_tls_paths_for_home(temp.path());
@@ -875,7 +949,7 @@ fn ensure_dev_server_tls_material_keeps_existing_files() {
     )
     .unwrap();
 
-    let changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo").unwrap();
+    let changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo", &[]).unwrap();
     assert!(!changed);
 
     let cert = std::fs::read_to_string(cert_path).unwrap();
@@ -888,16 +962,16 @@ fn ensure_dev_server_tls_material_keeps_existing_files() {
 fn ensure_dev_server_tls_material_regenerates_when_ca_changes() {
     let temp = TempDir::new().unwrap();
     let ca1 = LocalCA::generate().unwrap();
-    let changed = ensure_dev_server_tls_material_for_home(&ca1, temp.path(), "demo").unwrap();
+    let changed = ensure_dev_server_tls_material_for_home(&ca1, temp.path(), "demo", &[]).unwrap();
     assert!(changed);
 
     // Same CA, same names → no change.
-    let changed = ensure_dev_server_tls_material_for_home(&ca1, temp.path(), "demo").unwrap();
+    let changed = ensure_dev_server_tls_material_for_home(&ca1, temp.path(), "demo", &[]).unwrap();
     assert!(!changed);
 
     // Different CA, same names → must regenerate.
     let ca2 = LocalCA::generate().unwrap();
-    let changed = ensure_dev_server_tls_material_for_home(&ca2, temp.path(), "demo").unwrap();
+    let changed = ensure_dev_server_tls_material_for_home(&ca2, temp.path(), "demo", &[]).unwrap();
     assert!(changed);
 }
 
@@ -910,7 +984,7 @@ fn ensure_dev_server_tls_material_regenerates_files_without_names_manifest() {
     std::fs::write(&key_path, "existing-key").unwrap();
 
     let ca = LocalCA::generate().unwrap();
-    let changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo").unwrap();
+    let changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo", &[]).unwrap();
     assert!(changed);
 
     let cert = std::fs::read_to_string(&cert_path).unwrap();
@@ -925,10 +999,10 @@ fn ensure_dev_server_tls_material_regenerates_files_without_names_manifest() {
 fn ensure_dev_server_tls_material_merges_names_for_multiple_apps() {
     let temp = TempDir::new().unwrap();
     let ca = LocalCA::generate().unwrap();
-    let first_changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "alpha")
+    let first_changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "alpha", &[])
         .expect("first cert write");
     assert!(first_changed);
-    let second_changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "beta")
+    let second_changed = ensure_dev_server_tls_material_for_home(&ca, temp.path(), "beta", &[])
         .expect("second cert write");
     assert!(second_changed);
 
@@ -937,6 +1011,89 @@ fn ensure_dev_server_tls_material_merges_names_for_multiple_apps() {
     assert!(names.contains("*.beta.test"));
 }
 
+#[test]
+fn ensure_dev_server_tls_material_adds_configured_dev_hosts_as_sans() {
+    let temp = TempDir::new().unwrap();
+    let ca = LocalCA::generate().unwrap();
+    let hosts = vec![
+        "demo.test".to_string(),
+        "api.shop.test/v1".to_string(),
+        "*.admin.shop.test".to_string(),
+    ];
+    let changed =
+        ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo", &hosts).unwrap();
+    assert!(changed);
+
+    let (cert_path, _) = dev_server_tls_paths_for_home(temp.path());
+    let cert_pem = std::fs::read_to_string(cert_path).unwrap();
+    let (_, pem) = x509_parser::pem::parse_x509_pem(cert_pem.as_bytes()).unwrap();
+    let cert = pem.parse_x509().unwrap();
+    let san = cert
+        .subject_alternative_name()
+        .unwrap()
+        .expect("leaf should have a SAN extension");
+    let dns_names: Vec<&str> = san
+        .value
+        .general_names
+        .iter()
+        .filter_map(|name| match name {
+            x509_parser::extensions::GeneralName::DNSName(name) => Some(*name),
+            _ => None,
+        })
+        .collect();
+    assert!(dns_names.contains(&"api.shop.test"), "{dns_names:?}");
+    assert!(dns_names.contains(&"*.admin.shop.test"), "{dns_names:?}");
+    assert!(dns_names.contains(&"*.demo.test"), "{dns_names:?}");
+
+    // Same hosts again: nothing to regenerate.
+    let changed =
+        ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo", &hosts).unwrap();
+    assert!(!changed);
+}
+
+#[test]
+fn renew_dev_server_tls_material_overwrites_existing_files() {
+    let temp = TempDir::new().unwrap();
+    let ca = LocalCA::generate().unwrap();
+    ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo", &[]).unwrap();
+    let (cert_path, key_path) = dev_server_tls_paths_for_home(temp.path());
+    let old_cert = std::fs::read_to_string(&cert_path).unwrap();
+    let old_key = std::fs::read_to_string(&key_path).unwrap();
+
+    let names = renew_dev_server_tls_material_for_home(&ca, temp.path()).unwrap();
+
+    // Same CA and names, so `ensure` would have kept these files.
+    assert!(names.contains(&"*.demo.test".to_string()));
+    let cert = std::fs::read_to_string(&cert_path).unwrap();
+    let key = std::fs::read_to_string(&key_path).unwrap();
+    assert!(cert.contains("BEGIN CERTIFICATE"));
+    assert_ne!(cert, old_cert);
+    assert_ne!(key, old_key);
+    assert!(!ensure_dev_server_tls_material_for_home(&ca, temp.path(), "demo", &[]).unwrap());
+}
+
+#[test]
+fn renew_dev_server_tls_material_replaces_corrupt_files_and_follows_new_ca() {
+    let temp = TempDir::new().unwrap();
+    let (cert_path, key_path) = dev_server_tls_paths_for_home(temp.path());
+    std::fs::create_dir_all(cert_path.parent().unwrap()).unwrap();
+    std::fs::write(&cert_path, "garbage").unwrap();
+    std::fs::write(&key_path, "garbage").unwrap();
+
+    let ca = LocalCA::generate().unwrap();
+    let names = renew_dev_server_tls_material_for_home(&ca, temp.path()).unwrap();
+    assert_eq!(names, vec!["*.tako.test", "*.test", "tako.test", "test"]);
+    assert!(
+        std::fs::read_to_string(&key_path)
+            .unwrap()
+            .contains("BEGIN PRIVATE KEY")
+    );
+    assert_eq!(
+        std::fs::read_to_string(ca_fingerprint_path_for_home(temp.path())).unwrap(),
+        ca_fingerprint(&ca)
+    );
+}
+
 #[cfg(target_os = "macos")]
 #[test]
 fn parse_local_dns_resolver_extracts_nameserver_and_port() {
@@ -1307,3 +1464,91 @@ fn re_registration_after_disambiguation_is_idempotent() {
     let result = disambiguate_app_name("api", "/repo/packages/payments/tako.toml", &existing);
     assert_eq!(result, "api-payments");
 }
+
+#[test]
+fn companion_apps_get_distinct_names_and_hosts() {
+    let project = |dir: &str, existing: &[(String, String)]| {
+        let config_key = format!("/{dir}/web/tako.toml");
+        prepare::DevProject {
+            app_name: disambiguate_app_name("web", &config_key, existing),
+            config_path: PathBuf::from(&config_key),
+            project_dir: PathBuf::from(format!("/{dir}/web")),
+            config_key,
+            base_name: "web".to_string(),
+            runtime_name: "bun".to_string(),
+            cfg: TakoToml::default(),
+            cmd: vec!["bun".to_string(), "run".to_string(), "index.ts".to_string()],
+            readiness_failure_hint: None,
+            worker_command: None,
+            env: HashMap::new(),
+        }
+    };
+
+    let main = project("a", &[]);
+    let existing = vec![(main.app_name.clone(), main.config_key.clone())];
+    let companion =
+        prepare::companion_from_project(project("b", &existing), true).expect("companion");
+
+    assert_eq!(main.app_name, "web");
+    assert_ne!(companion.app_name, main.app_name);
+    assert_eq!(
+        companion.hosts,
+        vec![prepare::dev_app_domain(&companion.app_name, true)]
+    );
+    assert_ne!(companion.hosts, vec![prepare::dev_app_domain("web", true)]);
+}
+
+#[test]
+fn companion_config_path_accepts_project_directory() {
+    let temp = TempDir::new().unwrap();
+    assert_eq!(
+        prepare::companion_config_path(temp.path()),
+        temp.path().join("tako.toml")
+    );
+    let file = temp.path().join("tako.staging.toml");
+    assert_eq!(prepare::companion_config_path(&file), file);
+}
+
+#[test]
+fn companion_logs_are_scoped_under_app_name() {
+    assert_eq!(runner::companion_scope("api", "app"), "api");
+    assert_eq!(runner::companion_scope("api", "worker"), "api:worker");
+}
+
+fn system_target(description: &str, ca_cert: bool) -> crate::commands::implode::SystemTarget {
+    crate::commands::implode::SystemTarget {
+        description: description.to_string(),
+        commands: vec![vec!["true".to_string()]],
+        ca_cert,
+    }
+}
+
+#[test]
+fn clean_keeps_ca_trust_unless_certs_requested() {
+    let targets = || {
+        vec![
+            system_target("Dev proxy", false),
+            system_target("CA certificate", true),
+        ]
+    };
+
+    let kept: Vec<String> = select_clean_targets(targets(), false)
+        .into_iter()
+        .map(|t| t.description)
+        .collect();
+    assert_eq!(kept, vec!["Dev proxy"]);
+
+    assert_eq!(select_clean_targets(targets(), true).len(), 2);
+}
+
+#[test]
+fn clean_lists_existing_local_cert_dirs() {
+    let temp = TempDir::new().unwrap();
+    assert!(local_cert_dirs(temp.path()).is_empty());
+
+    std::fs::create_dir_all(temp.path().join("certs")).unwrap();
+    assert_eq!(
+        local_cert_dirs(temp.path()),
+        vec![temp.path().join("certs")]
+    );
+}
@@ -3,7 +3,8 @@ use std::sync::OnceLock;
 use serde::{Deserialize, Serialize};
 use time::{OffsetDateTime, UtcOffset};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Declared in order of severity, so `>=` means "at least as severe".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LogLevel {
     Debug,
@@ -29,6 +30,10 @@ impl std::fmt::Display for LogLevel {
 #[derive(Debug, Clone, Serialize)]
 pub struct ScopedLog {
     pub timestamp: String,
+    /// When the line was logged, as Unix milliseconds; comparable where
+    /// `timestamp` (local `HH:MM:SS`) is not.
+    #[serde(skip)]
+    pub unix_millis: i64,
     pub level: LogLevel,
     pub scope: String,
     pub message: String,
@@ -75,6 +80,7 @@ impl<'de> Deserialize<'de> for ScopedLog {
         let raw = ScopedLogSerde::deserialize(deserializer)?;
         Ok(Self {
             timestamp: hms_from_unix_millis(raw.ts),
+            unix_millis: raw.ts,
             level: raw.level,
             scope: raw.scope,
             message: raw.msg,
@@ -86,7 +92,7 @@ impl<'de> Deserialize<'de> for ScopedLog {
 
 static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
 
-fn local_offset() -> UtcOffset {
+pub(super) fn local_offset() -> UtcOffset {
     *LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC))
 }
 
@@ -95,6 +101,7 @@ impl ScopedLog {
         let now = OffsetDateTime::now_utc().to_offset(local_offset());
         Self {
             timestamp: hms_timestamp(now.hour() as u8, now.minute() as u8, now.second() as u8),
+            unix_millis: (now.unix_timestamp_nanos() / 1_000_000) as i64,
             level,
             scope: scope.into(),
             message: message.into(),
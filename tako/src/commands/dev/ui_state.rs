@@ -0,0 +1,87 @@
+//! Dev TUI choices remembered across `tako dev` sessions.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::LogLevel;
+
+const UI_STATE_FILE: &str = "dev-ui.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct DevUiState {
+    /// Least severe log level the TUI shows; `None` shows everything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) min_log_level: Option<LogLevel>,
+}
+
+impl DevUiState {
+    fn path() -> Option<PathBuf> {
+        crate::paths::tako_data_dir()
+            .ok()
+            .map(|dir| dir.join(UI_STATE_FILE))
+    }
+
+    /// Saved state, or the defaults when there is none or it can't be read.
+    pub(super) fn load() -> Self {
+        Self::path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    pub(super) fn load_from(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    /// Best effort: a failed write only means the choice isn't remembered.
+    pub(super) fn save(&self) {
+        if let Some(path) = Self::path() {
+            let _ = self.save_to(&path);
+        }
+    }
+
+    pub(super) fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let raw = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn round_trips_min_log_level() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("nested").join(UI_STATE_FILE);
+
+        let state = DevUiState {
+            min_log_level: Some(LogLevel::Warn),
+        };
+        state.save_to(&path).unwrap();
+        assert_eq!(DevUiState::load_from(&path), state);
+
+        DevUiState::default().save_to(&path).unwrap();
+        assert_eq!(DevUiState::load_from(&path).min_log_level, None);
+    }
+
+    #[test]
+    fn missing_or_corrupt_state_falls_back_to_defaults() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(UI_STATE_FILE);
+        assert_eq!(DevUiState::load_from(&path), DevUiState::default());
+
+        std::fs::write(&path, "{not json").unwrap();
+        assert_eq!(DevUiState::load_from(&path), DevUiState::default());
+
+        std::fs::write(&path, r#"{"min_log_level":"loud"}"#).unwrap();
+        assert_eq!(DevUiState::load_from(&path), DevUiState::default());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::output;
 
-pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run(fix: bool) -> Result<(), Box<dyn std::error::Error>> {
     // ── Gather all data upfront ──────────────────────────────────────────
 
     let config_dir = crate::paths::tako_config_dir()
@@ -16,6 +16,10 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let apps = crate::dev_server_client::list_apps()
         .await
         .unwrap_or_default();
+    let health = crate::dev_server_client::health().await.unwrap_or_default();
+    let routes = crate::dev_server_client::describe_routes()
+        .await
+        .unwrap_or_default();
 
     #[cfg(target_os = "macos")]
     let macos_data = gather_macos_data(&dev_info, &apps);
@@ -35,7 +39,8 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(target_os = "linux")]
     format_linux_sections(&mut buf, &linux_data);
 
-    format_apps(&mut buf, &apps);
+    format_apps(&mut buf, &apps, &health);
+    format_routes(&mut buf, &routes);
 
     #[cfg(target_os = "macos")]
     format_local_dns(&mut buf, &dev_info, &apps, &macos_data);
@@ -46,6 +51,158 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         eprintln!("{line}");
     }
 
+    if fix {
+        #[cfg(target_os = "macos")]
+        let findings = Findings::gather(&ca_status, &macos_data);
+        #[cfg(target_os = "linux")]
+        let findings = Findings::gather(&ca_status, &linux_data);
+        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        let findings = Findings::gather(&ca_status);
+
+        run_repairs(&plan_repairs(&findings), &apps, dev_info.is_ok()).await?;
+    }
+
+    Ok(())
+}
+
+// ─── Repairs ─────────────────────────────────────────────────────────────────
+
+/// Which of the checks `--fix` knows how to repair passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Findings {
+    ca_ok: bool,
+    resolver_ok: bool,
+    forwarding_ok: bool,
+}
+
+impl Findings {
+    /// A CA store that can't be opened counts as ok: setup can't fix it.
+    fn ca_ok(ca: &CaStatus) -> bool {
+        !matches!(ca, CaStatus::NotCreated | CaStatus::Untrusted)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn gather(ca: &CaStatus, macos: &MacosData) -> Self {
+        let proxy = &macos.dev_proxy;
+        Self {
+            ca_ok: Self::ca_ok(ca),
+            resolver_ok: macos
+                .resolver_values
+                .as_ref()
+                .is_some_and(|(nameserver, port)| {
+                    nameserver == "127.0.0.1" && *port == macos.local_dns_port
+                }),
+            forwarding_ok: proxy.installed
+                && proxy.bootstrap_loaded
+                && proxy.alias_ready
+                && proxy.launchd_loaded
+                && macos.https_tcp_ok
+                && macos.http_tcp_ok,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn gather(ca: &CaStatus, linux: &LinuxData) -> Self {
+        let status = &linux.status;
+        Self {
+            ca_ok: Self::ca_ok(ca),
+            resolver_ok: status.dns_configured,
+            forwarding_ok: status.loopback_alias
+                && status.redirect_443
+                && status.redirect_80
+                && status.redirect_dns,
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    fn gather(ca: &CaStatus) -> Self {
+        Self {
+            ca_ok: Self::ca_ok(ca),
+            resolver_ok: true,
+            forwarding_ok: true,
+        }
+    }
+}
+
+/// A `tako dev` setup step that `tako doctor --fix` can rerun.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repair {
+    /// Create or trust the local CA and refresh the dev certificates
+    LocalCa,
+    /// Point *.tako.test lookups at the dev server's DNS
+    Resolver,
+    /// Forward the loopback address's ports 80/443 to the dev server
+    Forwarding,
+}
+
+impl Repair {
+    fn prompt(self) -> &'static str {
+        match self {
+            Repair::LocalCa => "Set up the local CA and dev certificates?",
+            Repair::Resolver => "Configure the local DNS resolver?",
+            Repair::Forwarding => "Set up port 80/443 forwarding?",
+        }
+    }
+}
+
+/// Repairs for the failing checks, in the order `tako dev` sets them up.
+fn plan_repairs(findings: &Findings) -> Vec<Repair> {
+    [
+        (findings.ca_ok, Repair::LocalCa),
+        (findings.resolver_ok, Repair::Resolver),
+        (findings.forwarding_ok, Repair::Forwarding),
+    ]
+    .into_iter()
+    .filter(|(ok, _)| !ok)
+    .map(|(_, repair)| repair)
+    .collect()
+}
+
+async fn run_repairs(
+    repairs: &[Repair],
+    apps: &[crate::dev_server_client::ListedApp],
+    dev_server_running: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    eprintln!();
+    if repairs.is_empty() {
+        output::success("Nothing to fix");
+        return Ok(());
+    }
+
+    for &repair in repairs {
+        if output::is_interactive() && !output::confirm(repair.prompt(), true)? {
+            output::muted("Skipped");
+            continue;
+        }
+        match repair {
+            Repair::LocalCa => {
+                let ca = super::dev::setup_local_ca().await?;
+                let mut updated = false;
+                for app in apps {
+                    updated |=
+                        super::dev::ensure_dev_server_tls_material(&ca, &app.app_name, &app.hosts)?;
+                }
+                if updated && dev_server_running {
+                    output::warning("Restart `tako dev` to serve the new certificate");
+                }
+            }
+            Repair::Resolver => {
+                #[cfg(target_os = "macos")]
+                super::dev::ensure_local_dns_resolver_configured(super::dev::LOCAL_DNS_PORT)?;
+                // The redirect setup also writes the systemd-resolved drop-in.
+                #[cfg(target_os = "linux")]
+                super::dev::ensure_linux_setup_installed()?;
+            }
+            Repair::Forwarding => {
+                #[cfg(target_os = "macos")]
+                super::dev::ensure_dev_proxy_installed()?;
+                #[cfg(target_os = "linux")]
+                super::dev::ensure_linux_setup_installed()?;
+            }
+        }
+    }
+
+    output::success("Done; run `tako doctor` again to check");
     Ok(())
 }
 
@@ -365,7 +522,11 @@ fn format_macos_sections(
     );
 }
 
-fn format_apps(buf: &mut Vec<String>, apps: &[crate::dev_server_client::ListedApp]) {
+fn format_apps(
+    buf: &mut Vec<String>,
+    apps: &[crate::dev_server_client::ListedApp],
+    health: &[crate::dev_server_client::AppHealth],
+) {
     if apps.is_empty() {
         return;
     }
@@ -380,16 +541,64 @@ fn format_apps(buf: &mut Vec<String>, apps: &[crate::dev_server_client::ListedAp
             .pid
             .map(|p| format!("  {}", output::theme_muted(format!("pid {p}"))))
             .unwrap_or_default();
+        let health_str = health
+            .iter()
+            .find(|h| h.app_name == a.app_name)
+            .map(|h| format!("  {}", format_app_health(h)))
+            .unwrap_or_default();
         buf.push(format!(
-            "  {}  {}  port {}{}",
+            "  {}  {}  port {}{}{}",
             output::strong(&a.app_name),
             output::theme_muted(&hosts),
             a.upstream_port,
             pid_str,
+            health_str,
+        ));
+    }
+}
+
+/// The daemon's route table in match order: the first pattern matching a
+/// request's host and path wins.
+fn format_routes(buf: &mut Vec<String>, routes: &[crate::dev_server_client::DevRoute]) {
+    if routes.is_empty() {
+        return;
+    }
+    heading(buf, "Routes");
+    for route in routes {
+        let state = if route.active {
+            output::theme_success("active")
+        } else {
+            output::theme_muted("idle")
+        };
+        let owner = route.app_id.strip_prefix("reg:").unwrap_or(&route.app_id);
+        buf.push(format!(
+            "  {}  {} port {}  {}  {}",
+            output::strong(&route.pattern),
+            output::theme_muted("→"),
+            route.upstream_port,
+            state,
+            output::theme_muted(owner),
         ));
     }
 }
 
+/// `running`, `idle` (no process), or `crashed` (a pid that no longer
+/// exists), followed by how long ago the app last logged.
+fn format_app_health(health: &crate::dev_server_client::AppHealth) -> String {
+    let status = match (health.pid, health.alive) {
+        (Some(_), true) => output::theme_success("running"),
+        (Some(_), false) => output::theme_error("crashed"),
+        (None, _) => output::theme_muted("idle"),
+    };
+    match health.last_log_ms_ago {
+        Some(ms) => format!(
+            "{status}  {}",
+            output::theme_muted(format!("last log {}s ago", ms / 1000))
+        ),
+        None => status,
+    }
+}
+
 #[cfg(target_os = "macos")]
 fn format_local_dns(
     buf: &mut Vec<String>,
@@ -737,6 +946,22 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn format_app_health_flags_crashed_process() {
+        let health = |pid, alive, last_log_ms_ago| crate::dev_server_client::AppHealth {
+            app_name: "web".to_string(),
+            pid,
+            alive,
+            last_log_ms_ago,
+        };
+
+        assert!(format_app_health(&health(Some(42), true, None)).contains("running"));
+        assert!(format_app_health(&health(None, false, None)).contains("idle"));
+        let crashed = format_app_health(&health(Some(42), false, Some(12_500)));
+        assert!(crashed.contains("crashed"), "{crashed}");
+        assert!(crashed.contains("last log 12s ago"), "{crashed}");
+    }
+
     #[test]
     fn format_dev_server_uses_single_status_hint_for_unavailable_state() {
         let mut buf = Vec::new();
@@ -882,3 +1107,72 @@ mod tests {
         );
     }
 }
+
+#[cfg(test)]
+mod repair_tests {
+    use super::*;
+
+    fn findings(ca_ok: bool, resolver_ok: bool, forwarding_ok: bool) -> Findings {
+        Findings {
+            ca_ok,
+            resolver_ok,
+            forwarding_ok,
+        }
+    }
+
+    #[test]
+    fn plan_repairs_is_empty_when_every_check_passes() {
+        assert!(plan_repairs(&findings(true, true, true)).is_empty());
+    }
+
+    #[test]
+    fn plan_repairs_maps_each_failing_check_to_its_setup_step() {
+        assert_eq!(
+            plan_repairs(&findings(false, true, true)),
+            vec![Repair::LocalCa]
+        );
+        assert_eq!(
+            plan_repairs(&findings(true, false, true)),
+            vec![Repair::Resolver]
+        );
+        assert_eq!(
+            plan_repairs(&findings(true, true, false)),
+            vec![Repair::Forwarding]
+        );
+        assert_eq!(
+            plan_repairs(&findings(false, false, false)),
+            vec![Repair::LocalCa, Repair::Resolver, Repair::Forwarding]
+        );
+    }
+
+    #[test]
+    fn ca_store_errors_are_not_repaired() {
+        assert!(Findings::ca_ok(&CaStatus::Trusted));
+        assert!(Findings::ca_ok(&CaStatus::Error("keychain locked".into())));
+        assert!(!Findings::ca_ok(&CaStatus::NotCreated));
+        assert!(!Findings::ca_ok(&CaStatus::Untrusted));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_findings_split_redirects_from_resolver() {
+        let status = super::super::dev::LinuxSetupStatus {
+            loopback_alias: true,
+            redirect_443: true,
+            redirect_80: false,
+            redirect_dns: true,
+            dns_configured: true,
+            service_installed: true,
+            is_nixos: false,
+        };
+        let linux = LinuxData {
+            status,
+            advertised_ip: "127.77.0.1".to_string(),
+            host_dns_results: Vec::new(),
+        };
+        assert_eq!(
+            Findings::gather(&CaStatus::Trusted, &linux),
+            findings(true, true, false)
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::app::require_app_name_from_config_path;
+use crate::commands::project_context;
+use crate::commands::releases::{
+    format_unix_timestamp_local, resolve_env_name, resolve_server_names_for_env,
+};
+use crate::config::{ServerEntry, ServersToml, TakoToml};
+use crate::output;
+use crate::ssh::SshClient;
+use tako_core::{Command, DeployHistoryEntry, DeployHistoryResponse, DeployResult, Response};
+use tracing::Instrument;
+
+/// Deploy attempts recorded by one server.
+#[derive(Debug, Serialize)]
+struct ServerHistory {
+    name: String,
+    entries: Vec<DeployHistoryEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn run(
+    env: Option<&str>,
+    limit: Option<u32>,
+    config_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(run_async(env, limit, config_path))
+}
+
+async fn run_async(
+    env: Option<&str>,
+    limit: Option<u32>,
+    config_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let context = project_context::resolve_existing(config_path)?;
+    let app_name = require_app_name_from_config_path(&context.config_path)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    let tako_config = TakoToml::load_from_file(&context.config_path)?;
+    let servers = ServersToml::load()?;
+    let env = resolve_env_name(env, &tako_config)?;
+    let server_names = resolve_server_names_for_env(&tako_config, &servers, &env)?;
+    let remote_app_name = tako_core::deployment_app_id(&app_name, &env);
+
+    output::section("Deploy history");
+    output::info(&format!(
+        "{} ({})",
+        output::strong(&app_name),
+        output::strong(&env)
+    ));
+
+    let mut tasks = Vec::new();
+    for server_name in &server_names {
+        let Some(server) = servers.get(server_name) else {
+            continue;
+        };
+        let server_name = server_name.clone();
+        let server = server.clone();
+        let remote_app_name = remote_app_name.clone();
+        let span = output::scope(&server_name);
+        tasks.push(tokio::spawn(
+            async move {
+                let result = fetch_history_for_server(&server, &remote_app_name, limit).await;
+                (server_name, result)
+            }
+            .instrument(span),
+        ));
+    }
+
+    let task_results = output::with_spinner_async_simple(
+        &format!(
+            "Loading deploy history from {} server(s)",
+            server_names.len()
+        ),
+        async {
+            let mut results = Vec::new();
+            for task in tasks {
+                results.push(task.await);
+            }
+            results
+        },
+    )
+    .await;
+
+    let mut histories = Vec::new();
+    for task in task_results {
+        let (name, result) = task?;
+        histories.push(match result {
+            Ok(entries) => ServerHistory {
+                name,
+                entries,
+                error: None,
+            },
+            Err(error) => ServerHistory {
+                name,
+                entries: Vec::new(),
+                error: Some(error),
+            },
+        });
+    }
+    output::json_data("app", &app_name);
+    output::json_data("env", &env);
+    output::json_data("servers", &histories);
+
+    if histories.iter().all(|history| history.error.is_some()) {
+        return Err("Failed to query deploy history from all target servers".into());
+    }
+
+    for history in &histories {
+        output::heading(&history.name);
+        if let Some(error) = &history.error {
+            output::warning(&format!("failed to load deploy history ({})", error));
+            continue;
+        }
+        if history.entries.is_empty() {
+            output::muted("No deploys recorded.");
+            continue;
+        }
+        for entry in &history.entries {
+            output_history_lines(entry);
+        }
+    }
+
+    Ok(())
+}
+
+async fn fetch_history_for_server(
+    server: &ServerEntry,
+    app_name: &str,
+    limit: Option<u32>,
+) -> Result<Vec<DeployHistoryEntry>, String> {
+    let _t = output::timed(&format!("Fetch deploy history for {app_name}"));
+    let mut ssh = SshClient::connect_to(&server.host, server.port)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let cmd = serde_json::to_string(&Command::History {
+        app: app_name.to_string(),
+        limit,
+    })
+    .map_err(|e| e.to_string())?;
+    let response = ssh.tako_command(&cmd).await.map_err(|e| e.to_string())?;
+    let _ = ssh.disconnect().await;
+    parse_history_response(&response)
+}
+
+fn parse_history_response(raw: &str) -> Result<Vec<DeployHistoryEntry>, String> {
+    let response: Response = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    match response {
+        Response::Ok { data } => {
+            let parsed: DeployHistoryResponse = serde_json::from_value(data)
+                .map_err(|e| format!("invalid history response: {}", e))?;
+            Ok(parsed.entries)
+        }
+        Response::Error { message } => Err(message),
+    }
+}
+
+fn output_history_lines(entry: &DeployHistoryEntry) {
+    let deployed =
+        format_unix_timestamp_local(entry.deployed_at_unix_secs).unwrap_or_else(|| "-".to_string());
+    output::info(&format!(
+        "{} {} {}",
+        output::strong(&entry.version),
+        format_history_result(&entry.result),
+        output::theme_muted(&deployed)
+    ));
+    output::muted(&format_history_detail(entry));
+}
+
+fn format_history_result(result: &DeployResult) -> &'static str {
+    match result {
+        DeployResult::Succeeded => "succeeded",
+        DeployResult::Failed => "failed",
+        DeployResult::RolledBack => "rolled back",
+    }
+}
+
+fn format_history_detail(entry: &DeployHistoryEntry) -> String {
+    let counts = format!(
+        "{} new, {} replaced instance(s)",
+        entry.new_instances, entry.old_instances
+    );
+    match entry
+        .error
+        .as_deref()
+        .filter(|error| !error.trim().is_empty())
+    {
+        Some(error) => format!("{counts}: {error}"),
+        None => counts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(result: DeployResult, error: Option<&str>) -> DeployHistoryEntry {
+        DeployHistoryEntry {
+            version: "abc1234".to_string(),
+            deployed_at_unix_secs: 1_700_000_000,
+            result,
+            new_instances: 2,
+            old_instances: 1,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn history_detail_includes_counts_and_error() {
+        assert_eq!(
+            format_history_detail(&entry(DeployResult::Succeeded, None)),
+            "2 new, 1 replaced instance(s)"
+        );
+        assert_eq!(
+            format_history_detail(&entry(
+                DeployResult::RolledBack,
+                Some("health check timed out")
+            )),
+            "2 new, 1 replaced instance(s): health check timed out"
+        );
+    }
+
+    #[test]
+    fn parse_history_response_reads_entries_or_server_error() {
+        let ok = serde_json::json!({
+            "status": "ok",
+            "data": {
+                "app": "my-app/production",
+                "entries": [entry(DeployResult::Failed, Some("boom"))],
+            },
+        })
+        .to_string();
+        let entries = parse_history_response(&ok).unwrap();
+        assert_eq!(entries, vec![entry(DeployResult::Failed, Some("boom"))]);
+
+        let err = r#"{"status":"error","message":"App not found: my-app/production"}"#;
+        assert_eq!(
+            parse_history_response(err).unwrap_err(),
+            "App not found: my-app/production"
+        );
+    }
+}
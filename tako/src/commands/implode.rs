@@ -145,13 +145,15 @@ async fn stop_dev_server() -> Result<(), Box<dyn std::error::Error>> {
 // System-level cleanup (requires sudo)
 // ---------------------------------------------------------------------------
 
-struct SystemTarget {
-    description: String,
-    commands: Vec<Vec<String>>,
+pub(crate) struct SystemTarget {
+    pub(crate) description: String,
+    pub(crate) commands: Vec<Vec<String>>,
+    /// Trusts the local dev CA; `tako dev clean` keeps these unless `--certs`.
+    pub(crate) ca_cert: bool,
 }
 
 /// Detect which system-level Tako artifacts exist on this machine.
-fn gather_system_targets() -> Vec<SystemTarget> {
+pub(crate) fn gather_system_targets() -> Vec<SystemTarget> {
     let mut targets = Vec::new();
 
     #[cfg(target_os = "macos")]
@@ -207,6 +209,7 @@ fn gather_macos_system_targets() -> Vec<SystemTarget> {
                     "/Library/Application Support/Tako".into(),
                 ],
             ],
+            ca_cert: false,
         });
     }
 
@@ -222,6 +225,7 @@ fn gather_macos_system_targets() -> Vec<SystemTarget> {
                 "-f".into(),
                 crate::commands::dev::TAKO_RESOLVER_FILE.into(),
             ]],
+            ca_cert: false,
         });
     }
 
@@ -239,6 +243,7 @@ fn gather_macos_system_targets() -> Vec<SystemTarget> {
                 "-alias".into(),
                 "127.77.0.1".into(),
             ]],
+            ca_cert: false,
         });
     }
 
@@ -258,7 +263,7 @@ const TAKO_CA_COMMON_NAMES: &[&str] = &[
 ];
 
 #[cfg(target_os = "macos")]
-fn has_ca_certs_in_keychain() -> bool {
+pub(crate) fn has_ca_certs_in_keychain() -> bool {
     TAKO_CA_COMMON_NAMES.iter().any(|cn| {
         Command::new("security")
             .args([
@@ -277,7 +282,7 @@ fn has_ca_certs_in_keychain() -> bool {
 }
 
 #[cfg(target_os = "linux")]
-fn has_ca_certs_in_keychain() -> bool {
+pub(crate) fn has_ca_certs_in_keychain() -> bool {
     Path::new("/usr/local/share/ca-certificates/tako-ca.crt").exists()
         || Path::new("/etc/pki/ca-trust/source/anchors/tako-ca.crt").exists()
 }
@@ -286,7 +291,7 @@ fn has_ca_certs_in_keychain() -> bool {
 /// `delete-certificate -c` fails when multiple certs share the same CN, so we
 /// find each cert's hash individually and delete by `-Z <hash>` in a loop.
 #[cfg(target_os = "macos")]
-fn remove_ca_certs_from_keychain() {
+pub(crate) fn remove_ca_certs_from_keychain() {
     let mut removed = 0u32;
     loop {
         // Find the SHA-1 hash of the first matching certificate under any
@@ -357,7 +362,7 @@ fn remove_ca_certs_from_keychain() {
 
 /// On Linux the CA cert is removed as a regular SystemTarget (file delete + update-ca-certificates).
 #[cfg(target_os = "linux")]
-fn remove_ca_certs_from_keychain() {
+pub(crate) fn remove_ca_certs_from_keychain() {
     // Handled by SystemTarget commands in gather_linux_system_targets.
 }
 
@@ -398,6 +403,7 @@ fn gather_linux_system_targets() -> Vec<SystemTarget> {
                 ],
                 vec!["systemctl".into(), "daemon-reload".into()],
             ],
+            ca_cert: false,
         });
     }
 
@@ -432,6 +438,7 @@ fn gather_linux_system_targets() -> Vec<SystemTarget> {
                 ],
                 vec!["systemctl".into(), "daemon-reload".into()],
             ],
+            ca_cert: false,
         });
     }
 
@@ -451,6 +458,7 @@ fn gather_linux_system_targets() -> Vec<SystemTarget> {
                     "systemd-resolved".into(),
                 ],
             ],
+            ca_cert: false,
         });
     }
 
@@ -466,6 +474,7 @@ fn gather_linux_system_targets() -> Vec<SystemTarget> {
                 ],
                 vec!["update-ca-certificates".into()],
             ],
+            ca_cert: true,
         });
     }
 
@@ -481,6 +490,29 @@ fn gather_linux_system_targets() -> Vec<SystemTarget> {
                 ],
                 vec!["update-ca-trust".into()],
             ],
+            ca_cert: true,
+        });
+    }
+
+    // Managed nftables table (hosts without iptables)
+    if Path::new("/etc/tako/dev-redirect.nft").exists() {
+        targets.push(SystemTarget {
+            description: "nftables redirect table (tako_dev)".into(),
+            commands: vec![
+                vec![
+                    "nft".into(),
+                    "delete".into(),
+                    "table".into(),
+                    "ip".into(),
+                    "tako_dev".into(),
+                ],
+                vec![
+                    "rm".into(),
+                    "-f".into(),
+                    "/etc/tako/dev-redirect.nft".into(),
+                ],
+            ],
+            ca_cert: false,
         });
     }
 
@@ -549,6 +581,7 @@ fn gather_linux_system_targets() -> Vec<SystemTarget> {
                     "lo".into(),
                 ],
             ],
+            ca_cert: false,
         });
     }
 
@@ -572,7 +605,7 @@ fn loopback_alias_exists_linux() -> bool {
 
 /// Run each system target's commands with sudo, best-effort.
 /// Sudo credential cache should already be warm from a prior `sudo -v` call.
-fn remove_system_targets(targets: &[SystemTarget]) {
+pub(crate) fn remove_system_targets(targets: &[SystemTarget]) {
     for target in targets {
         let mut any_failed = false;
         for cmd_args in &target.commands {
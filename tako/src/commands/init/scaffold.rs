@@ -222,6 +222,10 @@ name = "{app_name}"
 # include = ["dist/**", ".output/**"]
 {exclude_line}
 
+# Env vars for build commands only (never set at runtime).
+# [build.env]
+# NPM_TOKEN = "..."
+
 # Multi-stage build (mutually exclusive with [build].run).
 # [[build_stages]]
 # name = "frontend-assets"
@@ -1,8 +1,12 @@
+pub mod build;
+pub mod ca;
+pub mod config;
 pub mod delete;
 pub mod deploy;
 pub mod dev;
 pub mod doctor;
 pub mod helpers;
+pub mod history;
 pub mod implode;
 pub mod init;
 pub mod logs;
@@ -28,8 +28,8 @@ pub enum ReleaseCommands {
 
     /// Roll back the current app/environment to a previous release/build id
     Rollback {
-        /// Target release/build id
-        release: String,
+        /// Target release/build id (defaults to the previously deployed release)
+        release: Option<String>,
 
         /// Environment to roll back (defaults to production)
         #[arg(long)]
@@ -68,12 +68,23 @@ async fn run_async(
         ReleaseCommands::Rollback { release, env, yes } => {
             let env = resolve_env_name(env.as_deref(), &tako_config)?;
             let server_names = resolve_server_names_for_env(&tako_config, &servers, &env)?;
-            rollback_release(&app_name, &release, &env, yes, &server_names, &servers).await
+            rollback_release(
+                &app_name,
+                release.as_deref(),
+                &env,
+                yes,
+                &server_names,
+                &servers,
+            )
+            .await
         }
     }
 }
 
-fn resolve_env_name(requested_env: Option<&str>, tako_config: &TakoToml) -> Result<String, String> {
+pub(crate) fn resolve_env_name(
+    requested_env: Option<&str>,
+    tako_config: &TakoToml,
+) -> Result<String, String> {
     let env = super::helpers::resolve_env(requested_env);
     if !tako_config.envs.contains_key(env.as_str()) {
         let mut available: Vec<String> = tako_config.envs.keys().cloned().collect();
@@ -91,7 +102,7 @@ fn resolve_env_name(requested_env: Option<&str>, tako_config: &TakoToml) -> Resu
     Ok(env)
 }
 
-fn resolve_server_names_for_env(
+pub(crate) fn resolve_server_names_for_env(
     tako_config: &TakoToml,
     servers: &ServersToml,
     env: &str,
@@ -184,6 +195,9 @@ async fn list_releases(
             .cmp(&a.deployed_at_unix_secs)
             .then_with(|| b.version.cmp(&a.version))
     });
+    output::json_data("app", app_name);
+    output::json_data("env", env);
+    output::json_data("releases", &releases);
 
     if releases.is_empty() {
         output::muted("No releases found.");
@@ -199,20 +213,21 @@ async fn list_releases(
 
 async fn rollback_release(
     app_name: &str,
-    release: &str,
+    release: Option<&str>,
     env: &str,
     assume_yes: bool,
     server_names: &[String],
     servers: &ServersToml,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let remote_app_name = tako_core::deployment_app_id(app_name, env);
+    let target_label = release.unwrap_or("the previous release");
     if should_confirm_production_rollback(env, assume_yes, output::is_interactive()) {
         let confirmed = output::confirm(
             &format!(
                 "Rollback {} in {} to {}?",
                 output::strong(app_name),
                 output::strong(env),
-                output::strong(release)
+                output::strong(target_label)
             ),
             false,
         )?;
@@ -226,7 +241,7 @@ async fn rollback_release(
         "{} ({}) -> {}",
         output::strong(app_name),
         output::strong(env),
-        output::strong(release)
+        output::strong(target_label)
     ));
 
     let mut tasks = Vec::new();
@@ -237,11 +252,12 @@ async fn rollback_release(
         let server_name = server_name.clone();
         let server = server.clone();
         let remote_app_name = remote_app_name.clone();
-        let release = release.to_string();
+        let release = release.map(str::to_string);
         let span = output::scope(&server_name);
         tasks.push(tokio::spawn(
             async move {
-                let result = rollback_server_release(&server, &remote_app_name, &release).await;
+                let result =
+                    rollback_server_release(&server, &remote_app_name, release.as_deref()).await;
                 (server_name, server, result)
             }
             .instrument(span),
@@ -285,7 +301,7 @@ async fn rollback_release(
         output::info(&format!(
             "Rolled back {} to {} on {} server(s)",
             output::strong(app_name),
-            output::strong(release),
+            output::strong(target_label),
             success_count
         ));
         Ok(())
@@ -333,16 +349,19 @@ async fn fetch_releases_for_server(
 async fn rollback_server_release(
     server: &ServerEntry,
     app_name: &str,
-    release: &str,
+    release: Option<&str>,
 ) -> Result<(), String> {
-    let _t = output::timed(&format!("Rollback {app_name} to {release}"));
+    let _t = output::timed(&format!(
+        "Rollback {app_name} to {}",
+        release.unwrap_or("previous release")
+    ));
     let mut ssh = SshClient::connect_to(&server.host, server.port)
         .await
         .map_err(|e| e.to_string())?;
 
     let cmd = serde_json::to_string(&Command::Rollback {
         app: app_name.to_string(),
-        version: release.to_string(),
+        version: release.map(str::to_string),
     })
     .map_err(|e| e.to_string())?;
     let response = ssh.tako_command(&cmd).await.map_err(|e| e.to_string())?;
@@ -447,7 +466,7 @@ fn local_offset() -> UtcOffset {
     *LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC))
 }
 
-fn format_unix_timestamp_local(unix_secs: i64) -> Option<String> {
+pub(crate) fn format_unix_timestamp_local(unix_secs: i64) -> Option<String> {
     format_unix_timestamp_with_date_command(unix_secs)
         .or_else(|| format_unix_timestamp_with_offset(unix_secs, local_offset()))
 }
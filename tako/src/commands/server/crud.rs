@@ -118,6 +118,22 @@ pub(super) async fn list_servers() -> Result<(), Box<dyn std::error::Error>> {
 
     let servers = ServersToml::load()?;
 
+    let mut names = servers.names();
+    names.sort_unstable();
+    let listed: Vec<serde_json::Value> = names
+        .iter()
+        .filter_map(|name| {
+            let entry = servers.get(name)?;
+            Some(serde_json::json!({
+                "name": name,
+                "host": entry.host,
+                "port": entry.port,
+                "description": entry.description,
+            }))
+        })
+        .collect();
+    output::json_data("servers", listed);
+
     if servers.is_empty() {
         tracing::warn!("No servers configured");
         output::warning("No servers configured");
@@ -128,9 +144,6 @@ pub(super) async fn list_servers() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    let mut names = servers.names();
-    names.sort_unstable();
-
     for name in &names {
         let entry = match servers.get(name) {
             Some(e) => e,
@@ -72,7 +72,12 @@ pub enum ServerCommands {
 
     /// Show global deployment status across configured servers
     #[command(visible_alias = "info")]
-    Status,
+    Status {
+        /// Only query this server: a configured name, its host, or an
+        /// unconfigured `host[:port]`
+        #[arg(long, value_name = "NAME|HOST")]
+        server: Option<String>,
+    },
 
     /// Configure DNS-01 wildcard certificate support
     SetupWildcard {
@@ -129,7 +134,7 @@ async fn run_async(cmd: ServerCommands) -> Result<(), Box<dyn std::error::Error>
         ServerCommands::Restart { name, force } => crud::restart_server(&name, force).await,
         ServerCommands::Upgrade { name } => upgrade::upgrade_servers(name.as_deref()).await,
         ServerCommands::Implode { name, yes } => implode_server_cmd(name.as_deref(), yes).await,
-        ServerCommands::Status => crate::commands::status::run().await,
+        ServerCommands::Status { server } => crate::commands::status::run(server.as_deref()).await,
         ServerCommands::SetupWildcard { env } => dns::setup_wildcard(env.as_deref()).await,
     }
 }
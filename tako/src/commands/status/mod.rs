@@ -3,7 +3,7 @@ mod render;
 mod time;
 
 use crate::commands::server;
-use crate::config::ServersToml;
+use crate::config::{ServerEntry, ServersToml};
 use crate::output;
 use std::collections::HashMap;
 use tako_core::AppStatus;
@@ -19,7 +19,7 @@ use remote::{
     parse_server_env_from_tako_toml, sort_global_apps,
 };
 #[cfg(test)]
-use render::{app_state_summary, service_status_display};
+use render::{app_state_summary, global_status_json, service_status_display};
 #[cfg(test)]
 use tako_core::{AppState, InstanceState, Response};
 #[cfg(test)]
@@ -61,9 +61,17 @@ struct GlobalServerStatusResult {
 #[cfg(test)]
 static LOCAL_OFFSET: std::sync::OnceLock<::time::UtcOffset> = std::sync::OnceLock::new();
 
-pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run(server: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
     let mut servers = ServersToml::load()?;
 
+    if let Some(server) = server {
+        let (name, servers) = resolve_status_target(&servers, server)?;
+        let server_names = vec![name];
+        let mut results = collect_global_status_results(&servers, &server_names).await?;
+        render_global_status(&servers, &server_names, &mut results);
+        return Ok(());
+    }
+
     if servers.is_empty()
         && server::prompt_to_add_server(
             "No servers configured yet. Add one now to see deployment status.",
@@ -79,6 +87,7 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
 async fn run_global_status(servers: &ServersToml) -> Result<(), Box<dyn std::error::Error>> {
     if servers.is_empty() {
+        output::json_data("servers", Vec::<serde_json::Value>::new());
         output::warning("No servers configured.");
         output::hint(&format!(
             "Run {} to add one.",
@@ -95,6 +104,59 @@ async fn run_global_status(servers: &ServersToml) -> Result<(), Box<dyn std::err
     Ok(())
 }
 
+/// Resolve `--server` to the one server to query: a configured name, the
+/// host of a configured server, or an unconfigured `host[:port]` (SSH port
+/// 22 by default). Returns the display name and an inventory holding it.
+fn resolve_status_target(
+    servers: &ServersToml,
+    input: &str,
+) -> Result<(String, ServersToml), String> {
+    let input = input.trim();
+    if servers.contains(input) {
+        return Ok((input.to_string(), servers.clone()));
+    }
+    if let Some(name) = servers.find_by_host(input) {
+        return Ok((name.to_string(), servers.clone()));
+    }
+
+    let parse_port = |port: &str| {
+        port.parse::<u16>()
+            .ok()
+            .filter(|port| *port > 0)
+            .ok_or_else(|| format!("Invalid SSH port in '{}'", input))
+    };
+    let (host, port) = if let Some(rest) = input.strip_prefix('[') {
+        // `[v6-address]` or `[v6-address]:port`
+        match rest.split_once(']') {
+            Some((host, "")) => (host, 22),
+            Some((host, port)) => match port.strip_prefix(':') {
+                Some(port) => (host, parse_port(port)?),
+                None => return Err(format!("Invalid server address '{}'", input)),
+            },
+            None => return Err(format!("Invalid server address '{}'", input)),
+        }
+    } else {
+        match input.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => (host, parse_port(port)?),
+            _ => (input, 22),
+        }
+    };
+    if host.is_empty() {
+        return Err("--server requires a server name or host".to_string());
+    }
+
+    let mut adhoc = ServersToml::default();
+    adhoc.servers.insert(
+        input.to_string(),
+        ServerEntry {
+            host: host.to_string(),
+            port,
+            description: None,
+        },
+    );
+    Ok((input.to_string(), adhoc))
+}
+
 fn sorted_server_names(servers: &ServersToml) -> Vec<String> {
     let mut server_names: Vec<String> = servers.names().into_iter().map(str::to_string).collect();
     server_names.sort();
@@ -271,6 +333,8 @@ mod tests {
                             pid: Some(111),
                             uptime_secs: 10,
                             requests_total: 0,
+                            secrets_generation: 0,
+                            usage: None,
                         }],
                     },
                     BuildStatus {
@@ -282,11 +346,16 @@ mod tests {
                             pid: Some(222),
                             uptime_secs: 12,
                             requests_total: 0,
+                            secrets_generation: 0,
+                            usage: None,
                         }],
                     },
                 ],
                 state: AppState::Deploying,
                 last_error: None,
+                secrets_generation: 0,
+                secrets_stale: false,
+                usage: Default::default(),
             }),
             deployed_at_unix_secs: None,
             error: None,
@@ -492,6 +561,8 @@ servers = ["second"]
                         pid: Some(111),
                         uptime_secs: 10,
                         requests_total: 0,
+                        secrets_generation: 0,
+                        usage: None,
                     },
                     InstanceStatus {
                         id: "abc2".to_string(),
@@ -499,6 +570,8 @@ servers = ["second"]
                         pid: Some(112),
                         uptime_secs: 10,
                         requests_total: 0,
+                        secrets_generation: 0,
+                        usage: None,
                     },
                     InstanceStatus {
                         id: "abc3".to_string(),
@@ -506,11 +579,16 @@ servers = ["second"]
                         pid: Some(113),
                         uptime_secs: 1,
                         requests_total: 0,
+                        secrets_generation: 0,
+                        usage: None,
                     },
                 ],
                 builds: vec![],
                 state: AppState::Running,
                 last_error: None,
+                secrets_generation: 0,
+                secrets_stale: false,
+                usage: Default::default(),
             }),
             deployed_at_unix_secs: None,
             error: None,
@@ -532,6 +610,9 @@ servers = ["second"]
                 builds: vec![],
                 state: AppState::Deploying,
                 last_error: None,
+                secrets_generation: 0,
+                secrets_stale: false,
+                usage: Default::default(),
             }),
             deployed_at_unix_secs: None,
             error: None,
@@ -541,10 +622,114 @@ servers = ["second"]
         assert_eq!(summary, "deploying");
     }
 
+    #[test]
+    fn global_status_json_reports_servers_in_requested_order() {
+        let app_status = ServerStatusResult {
+            service_status: "active".to_string(),
+            server_version: Some("0.1.0".to_string()),
+            app_status: None,
+            deployed_at_unix_secs: Some(1772798400),
+            error: None,
+        };
+        let results = HashMap::from([
+            (
+                "eu".to_string(),
+                GlobalServerStatusResult {
+                    service_status: "active".to_string(),
+                    server_version: Some("0.1.0".to_string()),
+                    server_uptime: Some("1d 2h".to_string()),
+                    process_uptime: None,
+                    routes: vec![("web".to_string(), "example.com".to_string())],
+                    apps: vec![GlobalAppStatusResult {
+                        app_name: "web".to_string(),
+                        env_name: "production".to_string(),
+                        status: app_status,
+                    }],
+                    error: None,
+                },
+            ),
+            (
+                "us".to_string(),
+                GlobalServerStatusResult {
+                    service_status: "unknown".to_string(),
+                    server_version: None,
+                    server_uptime: None,
+                    process_uptime: None,
+                    routes: Vec::new(),
+                    apps: Vec::new(),
+                    error: Some("SSH connection failed".to_string()),
+                },
+            ),
+        ]);
+
+        let report = global_status_json(&["us".to_string(), "eu".to_string()], &results);
+        let parsed: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        assert_eq!(parsed[0]["name"], "us");
+        assert_eq!(parsed[0]["error"], "SSH connection failed");
+        assert_eq!(parsed[1]["name"], "eu");
+        assert_eq!(parsed[1]["server_version"], "0.1.0");
+        assert_eq!(
+            parsed[1]["routes"],
+            serde_json::json!([{ "app": "web", "route": "example.com" }])
+        );
+        assert_eq!(parsed[1]["apps"][0]["app"], "web");
+        assert_eq!(parsed[1]["apps"][0]["env"], "production");
+        assert_eq!(parsed[1]["apps"][0]["deployed_at_unix_secs"], 1772798400);
+    }
+
     #[test]
     fn format_deployed_at_formats_epoch_in_local_time() {
         let ts = 1772798400;
         let formatted = format_deployed_at(ts).unwrap();
         assert!(formatted.starts_with("Mar 6, 2026"));
     }
+
+    #[test]
+    fn resolve_status_target_prefers_configured_servers() {
+        let mut servers = ServersToml::default();
+        servers.servers.insert(
+            "prod".to_string(),
+            ServerEntry {
+                host: "10.0.0.5".to_string(),
+                port: 2222,
+                description: None,
+            },
+        );
+
+        let (name, inventory) = resolve_status_target(&servers, "prod").unwrap();
+        assert_eq!(name, "prod");
+        assert_eq!(inventory.get("prod").unwrap().port, 2222);
+
+        let (name, _) = resolve_status_target(&servers, "10.0.0.5").unwrap();
+        assert_eq!(name, "prod");
+    }
+
+    #[test]
+    fn resolve_status_target_accepts_unconfigured_hosts() {
+        let servers = ServersToml::default();
+        let target = |input: &str| {
+            let (name, inventory) = resolve_status_target(&servers, input).unwrap();
+            let entry = inventory.get(&name).unwrap();
+            (entry.host.clone(), entry.port)
+        };
+
+        assert_eq!(target("example.com"), ("example.com".to_string(), 22));
+        assert_eq!(
+            target("example.com:2222"),
+            ("example.com".to_string(), 2222)
+        );
+        assert_eq!(target("::1"), ("::1".to_string(), 22));
+        assert_eq!(
+            target("[2001:db8::1]:2200"),
+            ("2001:db8::1".to_string(), 2200)
+        );
+
+        for bad in ["", "example.com:ssh", "example.com:0", "[::1", "[::1]x"] {
+            assert!(
+                resolve_status_target(&servers, bad).is_err(),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
 }
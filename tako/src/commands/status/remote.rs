@@ -4,7 +4,7 @@ use crate::config::TakoToml;
 use crate::output;
 use crate::shell::shell_single_quote;
 use crate::ssh::{SshClient, SshConfig};
-use tako_core::{AppStatus, Response};
+use tako_core::{AppStatus, ResourceUsage, Response};
 use time::OffsetDateTime;
 
 pub(super) async fn query_global_server_status(
@@ -322,6 +322,12 @@ pub(super) fn expand_status_by_running_builds(
                 builds: Vec::new(),
                 state: build.state,
                 last_error: app_status.last_error.clone(),
+                secrets_generation: app_status.secrets_generation,
+                secrets_stale: build
+                    .instances
+                    .iter()
+                    .any(|i| i.secrets_generation < app_status.secrets_generation),
+                usage: ResourceUsage::total(&build.instances),
             }),
             deployed_at_unix_secs: status.deployed_at_unix_secs,
             error: status.error.clone(),
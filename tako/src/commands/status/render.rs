@@ -3,6 +3,7 @@ use super::time::format_deployed_at;
 use super::{GlobalServerStatusResult, ServerStatusResult};
 use crate::config::ServersToml;
 use crate::output;
+use serde_json::{Value, json};
 use tako_core::{AppState, InstanceState};
 
 enum CardEntry {
@@ -38,6 +39,11 @@ pub(super) fn render_global_status(
     server_names: &[String],
     server_results: &mut std::collections::HashMap<String, GlobalServerStatusResult>,
 ) {
+    if output::is_json() {
+        output::json_data("servers", global_status_json(server_names, server_results));
+        return;
+    }
+
     struct Card {
         header: String,
         entries: Vec<CardEntry>,
@@ -150,6 +156,14 @@ pub(super) fn render_global_status(
                     if !app_status.version.is_empty() {
                         children.push(("  Release".into(), app_status.version.clone(), None));
                     }
+
+                    if app_status.secrets_stale {
+                        children.push((
+                            "  Secrets".into(),
+                            "changed; some instances still use old values".into(),
+                            Some(CardColor::Warning),
+                        ));
+                    }
                 }
 
                 if let Some(unix_secs) = app.status.deployed_at_unix_secs
@@ -246,6 +260,49 @@ pub(super) fn render_global_status(
     }
 }
 
+/// The `--json` form of the status report, one entry per queried server in
+/// `server_names` order.
+pub(super) fn global_status_json(
+    server_names: &[String],
+    server_results: &std::collections::HashMap<String, GlobalServerStatusResult>,
+) -> Vec<Value> {
+    server_names
+        .iter()
+        .filter_map(|name| {
+            let global = server_results.get(name.as_str())?;
+            let mut apps = global.apps.clone();
+            sort_global_apps(&mut apps);
+            let apps: Vec<Value> = apps
+                .iter()
+                .map(|app| {
+                    json!({
+                        "app": app.app_name,
+                        "env": app.env_name,
+                        "deployed_at_unix_secs": app.status.deployed_at_unix_secs,
+                        "status": app.status.app_status,
+                        "error": app.status.error,
+                    })
+                })
+                .collect();
+            let routes: Vec<Value> = global
+                .routes
+                .iter()
+                .map(|(app, route)| json!({ "app": app, "route": route }))
+                .collect();
+            Some(json!({
+                "name": name,
+                "service_status": global.service_status,
+                "server_version": global.server_version,
+                "server_uptime": global.server_uptime,
+                "process_uptime": global.process_uptime,
+                "routes": routes,
+                "apps": apps,
+                "error": global.error,
+            }))
+        })
+        .collect()
+}
+
 pub(super) fn service_status_display(status: &str) -> (String, CardColor) {
     match status {
         "active" => ("active".into(), CardColor::Success),
@@ -1,3 +1,4 @@
+use super::interpolate::interpolate_vars;
 use super::schema::*;
 use std::collections::HashMap;
 use std::path::Path;
@@ -63,8 +64,95 @@ impl Config {
             .unwrap_or_else(default_idle_timeout)
     }
 
+    /// Get access log sampling rate (1 in N) for an environment, if configured.
+    pub fn get_access_log_sample_rate(&self, env_name: &str) -> Option<u32> {
+        self.envs
+            .get(env_name)
+            .and_then(|env| env.access_log_sample_rate)
+    }
+
+    /// Get the client request headers stripped at the edge for an environment.
+    pub fn get_strip_request_headers(&self, env_name: &str) -> Vec<String> {
+        self.envs
+            .get(env_name)
+            .map(|env| env.strip_request_headers.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the restart limit for an environment, if configured.
+    pub fn get_max_restart_attempts(&self, env_name: &str) -> Option<u32> {
+        self.envs
+            .get(env_name)
+            .and_then(|env| env.max_restart_attempts)
+    }
+
+    /// Get the restart backoff cap (seconds) for an environment, if configured.
+    pub fn get_max_restart_backoff(&self, env_name: &str) -> Option<u32> {
+        self.envs
+            .get(env_name)
+            .and_then(|env| env.max_restart_backoff)
+    }
+
+    /// Get the deploy strategy for an environment (rolling when unset).
+    pub fn get_deploy_strategy(&self, env_name: &str) -> tako_core::DeployStrategy {
+        self.envs
+            .get(env_name)
+            .and_then(|env| env.deploy_strategy)
+            .unwrap_or_default()
+    }
+
+    /// Get the response buffering limit for an environment, if configured.
+    pub fn get_response_buffer_bytes(&self, env_name: &str) -> Option<u32> {
+        self.envs
+            .get(env_name)
+            .and_then(|env| env.response_buffer_bytes)
+    }
+
+    /// Get the request body limit for an environment, if configured.
+    pub fn get_max_body_bytes(&self, env_name: &str) -> Option<u64> {
+        self.envs.get(env_name).and_then(|env| env.max_body_bytes)
+    }
+
+    /// Get the request header limit for an environment, if configured.
+    pub fn get_max_header_bytes(&self, env_name: &str) -> Option<u32> {
+        self.envs.get(env_name).and_then(|env| env.max_header_bytes)
+    }
+
+    /// Get the proxy rate limit for an environment, if configured.
+    pub fn get_rate_limit(&self, env_name: &str) -> Option<tako_core::RateLimit> {
+        self.envs.get(env_name).and_then(|env| env.rate_limit)
+    }
+
+    /// Whether the proxy may retry non-idempotent requests for an environment.
+    pub fn get_retry_non_idempotent(&self, env_name: &str) -> bool {
+        self.envs
+            .get(env_name)
+            .and_then(|env| env.retry_non_idempotent)
+            .unwrap_or(false)
+    }
+
     /// Get merged vars for an environment (global + per-env)
     pub fn get_merged_vars(&self, env_name: &str) -> HashMap<String, String> {
+        let mut merged = self.merged_vars_uninterpolated(env_name);
+        // Bad references are rejected by `validate`; if one slips through
+        // (e.g. a config built in code), keep the raw values.
+        let _ = interpolate_vars(&mut merged);
+        merged
+    }
+
+    /// Like [`Self::get_merged_vars`] but fails on undefined or cyclic
+    /// `${NAME}` references.
+    pub(super) fn try_merged_vars(
+        &self,
+        env_name: &str,
+    ) -> Result<HashMap<String, String>, String> {
+        let mut merged = self.merged_vars_uninterpolated(env_name);
+        interpolate_vars(&mut merged)?;
+        Ok(merged)
+    }
+
+    /// `[vars]` overlaid with `[vars.<env_name>]`, minus reserved names.
+    fn merged_vars_uninterpolated(&self, env_name: &str) -> HashMap<String, String> {
         let mut merged = self.vars.clone();
         if let Some(env_vars) = self.vars_per_env.get(env_name) {
             merged.extend(env_vars.iter().map(|(k, v)| (k.clone(), v.clone())));
@@ -0,0 +1,72 @@
+//! `${NAME}` references between `[vars]` entries.
+//!
+//! References resolve against the merged map for one environment, so an
+//! environment value may build on a base value (or vice versa). `$${` is a
+//! literal `${`.
+
+use std::collections::HashMap;
+
+/// Replace every `${NAME}` in `vars` with the resolved value of `NAME`.
+///
+/// Fails on a reference to a name that is not in `vars` or on a reference
+/// cycle; the message names the variables involved.
+pub(super) fn interpolate_vars(vars: &mut HashMap<String, String>) -> Result<(), String> {
+    let mut resolved: HashMap<String, String> = HashMap::new();
+    let mut names: Vec<&String> = vars.keys().collect();
+    names.sort();
+    for name in names {
+        let mut stack = Vec::new();
+        resolve(name, vars, &mut resolved, &mut stack)?;
+    }
+    *vars = resolved;
+    Ok(())
+}
+
+fn resolve(
+    name: &str,
+    raw: &HashMap<String, String>,
+    resolved: &mut HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<String, String> {
+    if let Some(value) = resolved.get(name) {
+        return Ok(value.clone());
+    }
+    if let Some(start) = stack.iter().position(|n| n == name) {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(name.to_string());
+        return Err(format!("variable reference cycle: {}", cycle.join(" -> ")));
+    }
+    let Some(template) = raw.get(name) else {
+        let referrer = stack.last().map(String::as_str).unwrap_or(name);
+        return Err(format!(
+            "{referrer} references undefined variable ${{{name}}}"
+        ));
+    };
+
+    stack.push(name.to_string());
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(idx) = rest.find('$') {
+        out.push_str(&rest[..idx]);
+        let tail = &rest[idx..];
+        if let Some(after) = tail.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("${") {
+            let Some(end) = after.find('}') else {
+                return Err(format!("{name} has an unterminated ${{ reference"));
+            };
+            let value = resolve(&after[..end], raw, resolved, stack)?;
+            out.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+    stack.pop();
+
+    resolved.insert(name.to_string(), out.clone());
+    Ok(out)
+}
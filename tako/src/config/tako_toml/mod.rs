@@ -1,5 +1,6 @@
 mod accessors;
 mod edit;
+mod interpolate;
 mod parse;
 mod schema;
 mod validation;
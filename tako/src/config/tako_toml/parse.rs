@@ -212,6 +212,7 @@ fn parse_build_config(raw: &toml::Value) -> Result<BuildConfig> {
     let cwd = parse_optional_string(&table_value, "cwd")?;
     let include = parse_string_array(&table_value, "include")?.unwrap_or_default();
     let exclude = parse_string_array(&table_value, "exclude")?.unwrap_or_default();
+    let env = parse_build_env(table)?;
 
     Ok(BuildConfig {
         run,
@@ -219,14 +220,34 @@ fn parse_build_config(raw: &toml::Value) -> Result<BuildConfig> {
         cwd,
         include,
         exclude,
+        env,
     })
 }
 
+fn parse_build_env(table: &toml::value::Table) -> Result<HashMap<String, String>> {
+    let Some(value) = table.get("env") else {
+        return Ok(HashMap::new());
+    };
+    let env = value.as_table().ok_or_else(|| {
+        ConfigError::Validation("'build.env' must be a table ([build.env])".to_string())
+    })?;
+    env.iter()
+        .map(|(key, value)| {
+            value
+                .as_str()
+                .map(|s| (key.clone(), s.to_string()))
+                .ok_or_else(|| {
+                    ConfigError::Validation(format!("'build.env.{key}' must be a string"))
+                })
+        })
+        .collect()
+}
+
 fn validate_build_keys(table: &toml::value::Table) -> Result<()> {
     for key in table.keys() {
         if !matches!(
             key.as_str(),
-            "run" | "install" | "cwd" | "include" | "exclude"
+            "run" | "install" | "cwd" | "include" | "exclude" | "env"
         ) {
             return Err(ConfigError::Validation(format!(
                 "Unknown key 'build.{key}'"
@@ -88,6 +88,11 @@ pub struct BuildConfig {
     /// File globs to exclude from the deploy artifact.
     #[serde(default)]
     pub exclude: Vec<String>,
+
+    /// Env vars set only for build commands (e.g. a private registry token),
+    /// never for the app process.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
 }
 
 /// Custom build stage from [[build_stages]].
@@ -132,6 +137,46 @@ pub struct EnvConfig {
     #[serde(default = "default_idle_timeout")]
     pub idle_timeout: u32,
 
+    /// Access log sampling: log 1 in N requests (5xx are always logged).
+    /// Unset logs every request.
+    pub access_log_sample_rate: Option<u32>,
+
+    /// Client request headers removed at the edge before Tako adds its own
+    /// forwarding headers (e.g. `X-Forwarded-Host`).
+    #[serde(default)]
+    pub strip_request_headers: Vec<String>,
+
+    /// Replacement spawns allowed within 5 minutes before the server stops
+    /// restarting a crashing app. Unset uses the server default (5).
+    pub max_restart_attempts: Option<u32>,
+
+    /// Cap in seconds on the delay before respawning a failed instance. The
+    /// delay starts at 1s and doubles per consecutive failure. Unset uses the
+    /// server default (30).
+    pub max_restart_backoff: Option<u32>,
+
+    /// How deploys replace running instances: `rolling` (default) or
+    /// `blue_green`, which starts the full new build before switching traffic.
+    pub deploy_strategy: Option<tako_core::DeployStrategy>,
+
+    /// Buffer app responses up to this many bytes so slow clients don't hold
+    /// instances; larger responses stream. Unset streams every response.
+    pub response_buffer_bytes: Option<u32>,
+
+    /// Reject requests with larger bodies (413) at the proxy. Unset uses the
+    /// server-wide limit (128 MiB), which is also the ceiling.
+    pub max_body_bytes: Option<u64>,
+
+    /// Reject requests whose header fields exceed this size (431) at the proxy.
+    pub max_header_bytes: Option<u32>,
+
+    /// Token-bucket request limit at the proxy; requests over it get 429.
+    pub rate_limit: Option<tako_core::RateLimit>,
+
+    /// Let the proxy retry POST/PATCH requests on another instance when the
+    /// first fails before responding. Idempotent methods always retry.
+    pub retry_non_idempotent: Option<bool>,
+
     /// Per-environment release command override. An empty string explicitly
     /// clears the top-level `release` command for this environment.
     pub release: Option<String>,
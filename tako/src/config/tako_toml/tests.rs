@@ -227,6 +227,36 @@ exclude = ["**/*.map"]
     assert!(config.build_stages.is_empty());
 }
 
+#[test]
+fn test_parse_build_env() {
+    let toml = r#"
+[build]
+run = "bun run build"
+
+[build.env]
+NPM_TOKEN = "secret"
+NODE_OPTIONS = "--max-old-space-size=4096"
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(config.build.env.len(), 2);
+    assert_eq!(
+        config.build.env.get("NPM_TOKEN").map(String::as_str),
+        Some("secret")
+    );
+    assert!(config.vars.is_empty());
+    assert!(config.get_merged_vars("production").is_empty());
+}
+
+#[test]
+fn test_parse_build_env_rejects_non_string_values() {
+    let toml = r#"
+[build.env]
+JOBS = 4
+"#;
+    let err = Config::parse(toml).unwrap_err();
+    assert!(err.to_string().contains("build.env.JOBS"));
+}
+
 #[test]
 fn test_parse_build_stages() {
     let toml = r#"
@@ -713,6 +743,44 @@ fn test_validate_route_pattern_valid() {
     assert!(validate_route_pattern("*.example.com").is_ok());
     assert!(validate_route_pattern("example.com/api/*").is_ok());
     assert!(validate_route_pattern("*.example.com/admin/*").is_ok());
+    assert!(validate_route_pattern("example.com/api/* -> strip").is_ok());
+    assert!(validate_route_pattern("example.com/* -> prepend /v1").is_ok());
+    assert!(validate_route_pattern("example.com/api/* -> strip -> prepend /v1/internal").is_ok());
+}
+
+#[test]
+fn test_validate_route_pattern_strip_requires_path() {
+    assert!(validate_route_pattern("example.com -> strip").is_err());
+    assert!(validate_route_pattern("example.com/api/* -> rewrite").is_err());
+}
+
+#[test]
+fn test_validate_route_pattern_prepend_rules() {
+    assert!(validate_route_pattern("example.com -> prepend /v1").is_err());
+    assert!(validate_route_pattern("example.com/* -> prepend v1").is_err());
+    assert!(validate_route_pattern("example.com/* -> prepend /").is_err());
+    assert!(validate_route_pattern("example.com/* -> prepend /v1/../admin").is_err());
+    assert!(validate_route_pattern("example.com/* -> prepend /v1 -> prepend /v2").is_err());
+    assert!(validate_route_pattern("example.com/api/* -> strip -> strip").is_err());
+}
+
+#[test]
+fn test_validate_route_pattern_reports_scheme_and_port() {
+    let err = validate_route_pattern("https://example.com").unwrap_err();
+    assert!(
+        err.to_string().contains("must not include a scheme"),
+        "{err}"
+    );
+    let err = validate_route_pattern("example.com:8443 -> strip").unwrap_err();
+    assert!(err.to_string().contains("requires a path prefix"), "{err}");
+    let err = validate_route_pattern("example.com:8443/api/*").unwrap_err();
+    assert!(err.to_string().contains("must not include a port"), "{err}");
+}
+
+#[test]
+fn test_parse_error_reports_line_and_column() {
+    let err = TakoToml::parse("name = \"app\"\nruntime = \n").unwrap_err();
+    assert!(err.to_string().contains("line 2, column"), "{err}");
 }
 
 #[test]
@@ -752,6 +820,193 @@ idle_timeout = 0
     assert!(Config::parse(toml).is_err());
 }
 
+#[test]
+fn test_parse_env_access_log_sample_rate() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+access_log_sample_rate = 10
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(config.get_access_log_sample_rate("production"), Some(10));
+    assert_eq!(config.get_access_log_sample_rate("staging"), None);
+}
+
+#[test]
+fn test_validate_access_log_sample_rate_cannot_be_zero() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+access_log_sample_rate = 0
+"#;
+    let err = Config::parse(toml).unwrap_err();
+    assert!(err.to_string().contains("access_log_sample_rate 0"));
+}
+
+#[test]
+fn test_parse_env_max_restart_attempts() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+max_restart_attempts = 3
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(config.get_max_restart_attempts("production"), Some(3));
+    assert_eq!(config.get_max_restart_attempts("staging"), None);
+}
+
+#[test]
+fn test_validate_max_restart_attempts_cannot_be_zero() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+max_restart_attempts = 0
+"#;
+    let err = Config::parse(toml).unwrap_err();
+    assert!(err.to_string().contains("max_restart_attempts 0"));
+}
+
+#[test]
+fn test_parse_env_max_restart_backoff() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+max_restart_backoff = 10
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(config.get_max_restart_backoff("production"), Some(10));
+    assert_eq!(config.get_max_restart_backoff("staging"), None);
+
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+max_restart_backoff = 0
+"#;
+    let err = Config::parse(toml).unwrap_err();
+    assert!(err.to_string().contains("max_restart_backoff 0"));
+}
+
+#[test]
+fn test_parse_env_deploy_strategy() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+deploy_strategy = "blue_green"
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(
+        config.get_deploy_strategy("production"),
+        tako_core::DeployStrategy::BlueGreen
+    );
+    assert_eq!(
+        config.get_deploy_strategy("staging"),
+        tako_core::DeployStrategy::Rolling
+    );
+
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+deploy_strategy = "canary"
+"#;
+    assert!(Config::parse(toml).is_err());
+}
+
+#[test]
+fn test_parse_env_response_buffer_bytes() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+response_buffer_bytes = 1048576
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(
+        config.get_response_buffer_bytes("production"),
+        Some(1048576)
+    );
+    assert_eq!(config.get_response_buffer_bytes("staging"), None);
+
+    let err = Config::parse(&toml.replace("1048576", "0")).unwrap_err();
+    assert!(err.to_string().contains("response_buffer_bytes 0"));
+}
+
+#[test]
+fn test_parse_env_request_limits() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+max_body_bytes = 10485760
+max_header_bytes = 16384
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(config.get_max_body_bytes("production"), Some(10485760));
+    assert_eq!(config.get_max_header_bytes("production"), Some(16384));
+    assert_eq!(config.get_max_body_bytes("staging"), None);
+
+    let err = Config::parse(&toml.replace("10485760", "0")).unwrap_err();
+    assert!(err.to_string().contains("max_body_bytes 0"));
+    let err = Config::parse(&toml.replace("16384", "0")).unwrap_err();
+    assert!(err.to_string().contains("max_header_bytes 0"));
+}
+
+#[test]
+fn test_parse_env_retry_non_idempotent() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+retry_non_idempotent = true
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert!(config.get_retry_non_idempotent("production"));
+    assert!(!config.get_retry_non_idempotent("staging"));
+}
+
+#[test]
+fn test_parse_env_rate_limit() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+rate_limit = { requests_per_second = 20, burst = 40, per_client_ip = true }
+"#;
+    let config = Config::parse(toml).unwrap();
+    let rate_limit = config.get_rate_limit("production").unwrap();
+    assert_eq!(rate_limit.requests_per_second, 20);
+    assert_eq!(rate_limit.burst(), 40);
+    assert!(rate_limit.per_client_ip);
+    assert_eq!(config.get_rate_limit("staging"), None);
+
+    let err = Config::parse(&toml.replace("= 20", "= 0")).unwrap_err();
+    assert!(err.to_string().contains("rate_limit.requests_per_second 0"));
+    let err = Config::parse(&toml.replace("= 40", "= 0")).unwrap_err();
+    assert!(err.to_string().contains("rate_limit.burst 0"));
+    assert!(Config::parse(&toml.replace("per_client_ip", "per_ip")).is_err());
+}
+
+#[test]
+fn test_parse_env_strip_request_headers() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+strip_request_headers = ["X-Forwarded-Host", "X-Real-IP"]
+"#;
+    let config = Config::parse(toml).unwrap();
+    assert_eq!(
+        config.get_strip_request_headers("production"),
+        vec!["X-Forwarded-Host", "X-Real-IP"]
+    );
+    assert!(config.get_strip_request_headers("staging").is_empty());
+}
+
+#[test]
+fn test_validate_strip_request_headers_rejects_invalid_name() {
+    let toml = r#"
+[envs.production]
+route = "api.example.com"
+strip_request_headers = ["X Forwarded"]
+"#;
+    let err = Config::parse(toml).unwrap_err();
+    assert!(err.to_string().contains("strip_request_headers"));
+}
+
 #[test]
 fn test_validate_assets_rejects_absolute_path() {
     let toml = r#"
@@ -1089,6 +1344,55 @@ DATABASE_URL = "postgres://prod"
     );
 }
 
+#[test]
+fn test_get_merged_vars_env_overrides_base_before_interpolation() {
+    let toml = r#"
+[vars]
+HOST = "localhost"
+API_URL = "https://${HOST}/api"
+LITERAL = "$${HOST}"
+
+[vars.production]
+HOST = "example.com"
+"#;
+    let config = Config::parse(toml).unwrap();
+
+    let production = config.get_merged_vars("production");
+    assert_eq!(production["API_URL"], "https://example.com/api");
+    assert_eq!(production["LITERAL"], "${HOST}");
+
+    let staging = config.get_merged_vars("staging");
+    assert_eq!(staging["API_URL"], "https://localhost/api");
+}
+
+#[test]
+fn test_vars_reject_undefined_reference() {
+    let toml = r#"
+[vars]
+API_URL = "https://${API_HOST}/api"
+"#;
+    let err = Config::parse(toml).unwrap_err().to_string();
+    assert!(
+        err.contains("API_URL references undefined variable ${API_HOST}"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_vars_reject_reference_cycle() {
+    let toml = r#"
+[vars]
+A = "${B}"
+B = "base"
+
+[vars.production]
+B = "${A}"
+"#;
+    let err = Config::parse(toml).unwrap_err().to_string();
+    assert!(err.contains("environment 'production'"), "{err}");
+    assert!(err.contains("cycle: A -> B -> A"), "{err}");
+}
+
 #[test]
 fn test_get_merged_vars_nonexistent_env() {
     let toml = r#"
@@ -151,11 +151,79 @@ impl Config {
                     env_name
                 )));
             }
+            if env_config.access_log_sample_rate == Some(0) {
+                return Err(ConfigError::Validation(format!(
+                    "Environment '{}' has invalid access_log_sample_rate 0",
+                    env_name
+                )));
+            }
+            if env_config.max_restart_attempts == Some(0) {
+                return Err(ConfigError::Validation(format!(
+                    "Environment '{}' has invalid max_restart_attempts 0",
+                    env_name
+                )));
+            }
+            if env_config.max_restart_backoff == Some(0) {
+                return Err(ConfigError::Validation(format!(
+                    "Environment '{}' has invalid max_restart_backoff 0",
+                    env_name
+                )));
+            }
+            if env_config.response_buffer_bytes == Some(0) {
+                return Err(ConfigError::Validation(format!(
+                    "Environment '{}' has invalid response_buffer_bytes 0",
+                    env_name
+                )));
+            }
+            if env_config.max_body_bytes == Some(0) {
+                return Err(ConfigError::Validation(format!(
+                    "Environment '{}' has invalid max_body_bytes 0",
+                    env_name
+                )));
+            }
+            if env_config.max_header_bytes == Some(0) {
+                return Err(ConfigError::Validation(format!(
+                    "Environment '{}' has invalid max_header_bytes 0",
+                    env_name
+                )));
+            }
+            if let Some(rate_limit) = env_config.rate_limit {
+                if rate_limit.requests_per_second == 0 {
+                    return Err(ConfigError::Validation(format!(
+                        "Environment '{}' has invalid rate_limit.requests_per_second 0",
+                        env_name
+                    )));
+                }
+                if rate_limit.burst == Some(0) {
+                    return Err(ConfigError::Validation(format!(
+                        "Environment '{}' has invalid rate_limit.burst 0",
+                        env_name
+                    )));
+                }
+            }
+            for header in &env_config.strip_request_headers {
+                validate_header_name(header, env_name)?;
+            }
             for server_name in &env_config.servers {
                 validate_server_name(server_name)?;
             }
         }
 
+        let mut var_envs: Vec<&str> = self
+            .envs
+            .keys()
+            .chain(self.vars_per_env.keys())
+            .map(String::as_str)
+            .chain(["development"])
+            .collect();
+        var_envs.sort();
+        var_envs.dedup();
+        for env_name in var_envs {
+            self.try_merged_vars(env_name).map_err(|e| {
+                ConfigError::Validation(format!("[vars] for environment '{env_name}': {e}"))
+            })?;
+        }
+
         Ok(())
     }
 }
@@ -406,6 +474,21 @@ fn validate_workflow_worker_name(name: &str) -> Result<()> {
 }
 
 /// Validate route pattern format
+fn validate_header_name(name: &str, env_name: &str) -> Result<()> {
+    // RFC 9110 field-name token characters.
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+    if !valid {
+        return Err(ConfigError::Validation(format!(
+            "Environment '{}' has invalid strip_request_headers entry '{}'",
+            env_name, name
+        )));
+    }
+    Ok(())
+}
+
 pub(super) fn validate_route_pattern(pattern: &str) -> Result<()> {
     if pattern.is_empty() {
         return Err(ConfigError::InvalidRoutePattern(
@@ -418,38 +501,66 @@ pub(super) fn validate_route_pattern(pattern: &str) -> Result<()> {
     // - Wildcard subdomain: *.example.com
     // - Path-based: example.com/api/*
     // - Combined: *.example.com/admin/*
-
-    // Check for invalid characters
-    for c in pattern.chars() {
-        if !c.is_ascii_alphanumeric() && c != '.' && c != '-' && c != '*' && c != '/' {
+    // - Prefix-stripped: example.com/api/* -> strip
+    // - Prefix-prepended: example.com/* -> prepend /v1
+    // - Both (strip first): example.com/api/* -> strip -> prepend /v1
+
+    let mut parts = pattern.split(" -> ");
+    let pattern = parts.next().unwrap_or(pattern);
+    let mut seen_strip = false;
+    let mut seen_prepend = false;
+    for directive in parts {
+        let (name, example, seen) = if directive == "strip" {
+            ("strip", "example.com/api/* -> strip", &mut seen_strip)
+        } else if let Some(prefix) = directive.strip_prefix("prepend ") {
+            validate_prepend_path(prefix)?;
+            ("prepend", "example.com/* -> prepend /v1", &mut seen_prepend)
+        } else {
             return Err(ConfigError::InvalidRoutePattern(format!(
-                "Invalid character in route pattern: '{}'",
-                c
+                "Unknown route directive '-> {}' (expected '-> strip' or '-> prepend /path')",
+                directive
+            )));
+        };
+        if std::mem::replace(seen, true) {
+            return Err(ConfigError::InvalidRoutePattern(format!(
+                "'-> {}' may only appear once per route",
+                name
+            )));
+        }
+        if !pattern.contains('/') {
+            return Err(ConfigError::InvalidRoutePattern(format!(
+                "'-> {}' requires a path prefix (e.g., {})",
+                name, example
             )));
         }
     }
 
-    // Wildcard must be at start of a segment
-    if pattern.contains('*') {
-        let parts: Vec<&str> = pattern.split('/').collect();
-        let hostname = parts[0];
+    crate::validation::parse_route(pattern)
+        .map_err(|e| ConfigError::InvalidRoutePattern(e.to_string()))?;
 
-        // Check hostname wildcards
-        if hostname.contains('*') && !hostname.starts_with("*.") {
-            return Err(ConfigError::InvalidRoutePattern(
-                "Wildcard in hostname must be at the start (e.g., *.example.com)".to_string(),
-            ));
-        }
+    Ok(())
+}
 
-        // Check path wildcards
-        for part in parts.iter().skip(1) {
-            if part.contains('*') && *part != "*" {
-                return Err(ConfigError::InvalidRoutePattern(
-                    "Wildcard in path must be a complete segment (e.g., /api/*)".to_string(),
-                ));
-            }
-        }
+/// Validate the path given to a `-> prepend` route directive.
+fn validate_prepend_path(prefix: &str) -> Result<()> {
+    let segments = prefix.strip_prefix('/').map(|p| p.trim_end_matches('/'));
+    let valid = segments.is_some_and(|p| {
+        !p.is_empty()
+            && p.split('/').all(|segment| {
+                !segment.is_empty()
+                    && segment != "."
+                    && segment != ".."
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~'))
+            })
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidRoutePattern(format!(
+            "Invalid '-> prepend' path '{}' (expected an absolute path such as /v1)",
+            prefix
+        )))
     }
-
-    Ok(())
 }
@@ -18,6 +18,7 @@ use rcgen::{
     BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa,
     Issuer, KeyPair, KeyUsagePurpose, SanType,
 };
+use sha2::Digest;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -39,6 +40,12 @@ const CA_COMMON_NAME: &str = "Tako Development CA";
 const CA_ORGANIZATION: &str = "Tako";
 const LOCAL_CA_CERT_FILENAME: &str = "ca.crt";
 
+const MACOS_SYSTEM_KEYCHAIN: &str = "/Library/Keychains/System.keychain";
+const DEBIAN_CA_DIR: &str = "/usr/local/share/ca-certificates";
+const DEBIAN_CA_ANCHOR: &str = "/usr/local/share/ca-certificates/tako-ca.crt";
+const FEDORA_CA_DIR: &str = "/etc/pki/ca-trust/source/anchors";
+const FEDORA_CA_ANCHOR: &str = "/etc/pki/ca-trust/source/anchors/tako-ca.crt";
+
 /// Errors that can occur during CA operations
 #[derive(Debug, Error)]
 pub enum CaError {
@@ -332,6 +339,14 @@ impl LocalCAStore {
         }
     }
 
+    /// Replace the CA with a freshly generated root. Leaf certs signed by
+    /// the old root stop validating, and the new root must be trusted again.
+    pub fn renew_ca(&self) -> Result<LocalCA> {
+        let ca = LocalCA::generate()?;
+        self.save_ca(&ca)?;
+        Ok(ca)
+    }
+
     /// Load the existing CA, verifying that the cert and key form a
     /// valid pair. A mismatch returns `CaError::Validation` so callers
     /// can regenerate rather than silently sign with a broken keypair.
@@ -425,108 +440,63 @@ impl LocalCAStore {
     /// Checks both Debian/Ubuntu and Fedora/RHEL trust store paths.
     #[cfg(not(target_os = "macos"))]
     pub fn is_ca_trusted(&self) -> bool {
-        // Debian/Ubuntu path
-        if PathBuf::from("/usr/local/share/ca-certificates/tako-ca.crt").exists() {
-            return true;
-        }
-        // Fedora/RHEL/SUSE path
-        if PathBuf::from("/etc/pki/ca-trust/source/anchors/tako-ca.crt").exists() {
-            return true;
-        }
-        false
+        Path::new(DEBIAN_CA_ANCHOR).exists() || Path::new(FEDORA_CA_ANCHOR).exists()
     }
 
     /// Install CA in system trust store (requires sudo)
-    #[cfg(target_os = "macos")]
     pub fn install_ca_trust(&self) -> Result<()> {
-        let cert_path = self.ca_cert_path.clone();
-        if !cert_path.exists() {
-            return Err(CaError::Validation(
-                "CA certificate not found. Run get_or_create_ca() first.".to_string(),
-            ));
-        }
+        let cert_str = self.existing_cert_path_str()?;
 
-        // Skip if this exact cert is already trusted (avoid duplicate entries).
-        if self.is_ca_trusted() {
+        // Skip if this exact cert is already trusted (avoid duplicate
+        // keychain entries).
+        if cfg!(target_os = "macos") && self.is_ca_trusted() {
             return Ok(());
         }
 
-        let output = Command::new("sudo")
-            .args([
-                "security",
-                "add-trusted-cert",
-                "-d",
-                "-r",
-                "trustRoot",
-                "-k",
-                "/Library/Keychains/System.keychain",
-                cert_path.to_str().unwrap_or(""),
-            ])
-            .output()
-            .map_err(|e| CaError::TrustStore(format!("Failed to run security command: {}", e)))?;
-
-        if !output.status.success() {
-            let detail = String::from_utf8_lossy(&output.stderr).trim().to_string();
-            return Err(CaError::TrustStore(if detail.is_empty() {
-                "Failed to install CA in trust store".to_string()
-            } else {
-                format!("Failed to install CA in trust store: {detail}")
-            }));
+        let store = TrustStore::detect().ok_or_else(|| {
+            CaError::TrustStore(format!(
+                "Could not find system CA trust store. Manually trust the CA at: {cert_str}"
+            ))
+        })?;
+        for command in store.trust_commands(cert_str) {
+            run_sudo_captured(&command, "Failed to install CA in trust store")?;
         }
         Ok(())
     }
 
-    /// Install CA in system trust store - Linux
-    ///
-    /// Detects distro family and uses the appropriate trust store path:
-    /// - Debian/Ubuntu: /usr/local/share/ca-certificates/ + update-ca-certificates
-    /// - Fedora/RHEL/SUSE: /etc/pki/ca-trust/source/anchors/ + update-ca-trust
-    #[cfg(not(target_os = "macos"))]
-    pub fn install_ca_trust(&self) -> Result<()> {
-        let cert_path = self.ca_cert_path.clone();
-        if !cert_path.exists() {
-            return Err(CaError::Validation(
-                "CA certificate not found. Run get_or_create_ca() first.".to_string(),
-            ));
+    /// Remove the CA from the system trust store (requires sudo). The CA
+    /// itself stays on disk.
+    pub fn remove_ca_trust(&self) -> Result<()> {
+        let cert_str = self.existing_cert_path_str()?;
+        let store = TrustStore::detect().ok_or_else(|| {
+            CaError::TrustStore("Could not find system CA trust store".to_string())
+        })?;
+        for command in store.untrust_commands(cert_str) {
+            run_sudo_captured(&command, "Failed to remove CA from trust store")?;
         }
+        Ok(())
+    }
 
-        let cert_str = cert_path.to_str().unwrap_or("");
-
-        // Try Debian/Ubuntu first (most common)
-        let debian_dir = PathBuf::from("/usr/local/share/ca-certificates");
-        let fedora_dir = PathBuf::from("/etc/pki/ca-trust/source/anchors");
-
-        if debian_dir.exists() {
-            let dest = "/usr/local/share/ca-certificates/tako-ca.crt";
-            run_sudo_captured(
-                &["cp", cert_str, dest],
-                "Failed to copy CA to system directory",
-            )?;
-            run_sudo_captured(
-                &["update-ca-certificates"],
-                "Failed to update system CA certificates",
-            )?;
-        } else if fedora_dir.exists() {
-            let dest = "/etc/pki/ca-trust/source/anchors/tako-ca.crt";
-            run_sudo_captured(
-                &["cp", cert_str, dest],
-                "Failed to copy CA to system directory",
-            )?;
-            run_sudo_captured(&["update-ca-trust"], "Failed to update system CA trust")?;
-        } else {
-            return Err(CaError::TrustStore(
-                "Could not find system CA trust store. Manually trust the CA at: ".to_string()
-                    + cert_str,
+    fn existing_cert_path_str(&self) -> Result<&str> {
+        if !self.ca_cert_path.exists() {
+            return Err(CaError::Validation(
+                "CA certificate not found. Run get_or_create_ca() first.".to_string(),
             ));
         }
+        Ok(self.ca_cert_path.to_str().unwrap_or(""))
+    }
 
-        Ok(())
+    /// SHA-256 fingerprint of the CA certificate on disk; see
+    /// [`cert_fingerprint`].
+    pub fn ca_fingerprint(&self) -> Result<String> {
+        let cert_pem = fs::read_to_string(&self.ca_cert_path)
+            .map_err(|e| CaError::FileRead(self.ca_cert_path.clone(), e))?;
+        cert_fingerprint(&cert_pem)
     }
 
     /// Delete the CA from disk. The cert remains in the system trust
-    /// store until explicitly removed via `security delete-certificate`
-    /// (macOS) or `update-ca-trust` (Linux) — that's a sudo operation
-    /// and not done here.
+    /// store until explicitly removed (see `remove_ca_trust`) — that's a
+    /// sudo operation and not done here.
     pub fn delete_ca(&self) -> Result<()> {
         if self.ca_cert_path.exists() {
             fs::remove_file(&self.ca_cert_path)
@@ -542,8 +512,7 @@ impl LocalCAStore {
 
 /// Run a sudo command with stdout/stderr captured. Surfaces captured stderr
 /// in the error message on failure so diagnostics aren't lost.
-#[cfg(not(target_os = "macos"))]
-fn run_sudo_captured(args: &[&str], failure_message: &str) -> Result<()> {
+fn run_sudo_captured(args: &[String], failure_message: &str) -> Result<()> {
     let output = Command::new("sudo")
         .args(args)
         .output()
@@ -559,6 +528,127 @@ fn run_sudo_captured(args: &[&str], failure_message: &str) -> Result<()> {
     Ok(())
 }
 
+/// System trust store the root CA is installed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustStore {
+    /// macOS System keychain, via `security`
+    Keychain,
+    /// Debian/Ubuntu, via `update-ca-certificates`
+    Debian,
+    /// Fedora/RHEL/SUSE, via `update-ca-trust`
+    Fedora,
+}
+
+impl TrustStore {
+    /// The trust store on this machine, if Tako knows how to manage it.
+    pub fn detect() -> Option<Self> {
+        if cfg!(target_os = "macos") {
+            Some(Self::Keychain)
+        } else if Path::new(DEBIAN_CA_DIR).exists() {
+            Some(Self::Debian)
+        } else if Path::new(FEDORA_CA_DIR).exists() {
+            Some(Self::Fedora)
+        } else {
+            None
+        }
+    }
+
+    /// Commands (each run with sudo) that trust the cert at `cert_path`.
+    pub fn trust_commands(self, cert_path: &str) -> Vec<Vec<String>> {
+        match self {
+            Self::Keychain => vec![command_args(&[
+                "security",
+                "add-trusted-cert",
+                "-d",
+                "-r",
+                "trustRoot",
+                "-k",
+                MACOS_SYSTEM_KEYCHAIN,
+                cert_path,
+            ])],
+            Self::Debian => vec![
+                command_args(&["cp", cert_path, DEBIAN_CA_ANCHOR]),
+                command_args(&["update-ca-certificates"]),
+            ],
+            Self::Fedora => vec![
+                command_args(&["cp", cert_path, FEDORA_CA_ANCHOR]),
+                command_args(&["update-ca-trust"]),
+            ],
+        }
+    }
+
+    /// Commands (each run with sudo) that undo [`Self::trust_commands`].
+    pub fn untrust_commands(self, cert_path: &str) -> Vec<Vec<String>> {
+        match self {
+            Self::Keychain => vec![command_args(&[
+                "security",
+                "remove-trusted-cert",
+                "-d",
+                cert_path,
+            ])],
+            Self::Debian => vec![
+                command_args(&["rm", "-f", DEBIAN_CA_ANCHOR]),
+                command_args(&["update-ca-certificates"]),
+            ],
+            Self::Fedora => vec![
+                command_args(&["rm", "-f", FEDORA_CA_ANCHOR]),
+                command_args(&["update-ca-trust"]),
+            ],
+        }
+    }
+}
+
+/// The per-user NSS database Chromium-based browsers read on Linux, when it
+/// exists. Firefox keeps a database per profile and is not covered.
+pub fn nss_db_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        return None;
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".pki").join("nssdb"))
+        .filter(|dir| dir.is_dir())
+}
+
+/// `certutil` command that trusts the cert at `cert_path` in `db_dir`.
+pub fn nss_trust_command(db_dir: &Path, cert_path: &str) -> Vec<String> {
+    let db = format!("sql:{}", db_dir.display());
+    command_args(&[
+        "certutil",
+        "-d",
+        &db,
+        "-A",
+        "-t",
+        "C,,",
+        "-n",
+        CA_COMMON_NAME,
+        "-i",
+        cert_path,
+    ])
+}
+
+/// `certutil` command that removes the Tako CA from `db_dir`.
+pub fn nss_untrust_command(db_dir: &Path) -> Vec<String> {
+    let db = format!("sql:{}", db_dir.display());
+    command_args(&["certutil", "-d", &db, "-D", "-n", CA_COMMON_NAME])
+}
+
+fn command_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// SHA-256 fingerprint of a PEM certificate's DER bytes, as colon-separated
+/// uppercase hex — the form Keychain Access and browsers display.
+pub fn cert_fingerprint(cert_pem: &str) -> Result<String> {
+    let parsed =
+        pem::parse(cert_pem.as_bytes()).map_err(|e| CaError::Parse(format!("certificate: {e}")))?;
+    let digest = sha2::Sha256::digest(parsed.contents());
+    Ok(digest
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":"))
+}
+
 /// Verify that the CA cert's public key matches the private key.
 ///
 /// Without this check, a cert/key divergence (e.g. the user regenerated
@@ -707,6 +797,45 @@ mod tests {
         assert!(leaf.key_pem.contains("BEGIN PRIVATE KEY"));
     }
 
+    #[test]
+    fn generated_keys_are_ecdsa_p256_and_leaf_validates_against_ca() {
+        use openssl::nid::Nid;
+        use openssl::pkey::PKey;
+        use openssl::x509::X509;
+
+        let p256_curve = |key_pem: &str| {
+            let key = PKey::private_key_from_pem(key_pem.as_bytes()).unwrap();
+            key.ec_key()
+                .expect("key should be ECDSA")
+                .group()
+                .curve_name()
+        };
+
+        let ca = LocalCA::generate().unwrap();
+        let leaf = ca
+            .generate_leaf_cert_for_names(&["*.demo.test", "demo.test"])
+            .unwrap();
+        assert!(leaf.key_pem.contains("BEGIN PRIVATE KEY"));
+        assert_eq!(p256_curve(&ca.ca_key_pem), Some(Nid::X9_62_PRIME256V1));
+        assert_eq!(p256_curve(&leaf.key_pem), Some(Nid::X9_62_PRIME256V1));
+
+        let ca_cert = X509::from_pem(ca.ca_cert_pem.as_bytes()).unwrap();
+        let leaf_cert = X509::from_pem(leaf.cert_pem.as_bytes()).unwrap();
+        assert!(leaf_cert.verify(&ca_cert.public_key().unwrap()).unwrap());
+        assert_eq!(
+            leaf_cert.issuer_name().to_der().unwrap(),
+            ca_cert.subject_name().to_der().unwrap()
+        );
+
+        // The leaf's key is the one its certificate was issued for.
+        let leaf_key = PKey::private_key_from_pem(leaf.key_pem.as_bytes()).unwrap();
+        assert!(leaf_cert.public_key().unwrap().public_eq(&leaf_key));
+
+        // A different CA did not sign it.
+        let other = X509::from_pem(LocalCA::generate().unwrap().ca_cert_pem.as_bytes()).unwrap();
+        assert!(!leaf_cert.verify(&other.public_key().unwrap()).unwrap());
+    }
+
     #[test]
     fn test_generate_multiple_leaf_certs() {
         let ca = LocalCA::generate().unwrap();
@@ -757,6 +886,145 @@ mod tests {
         assert_eq!(ca.ca_key_pem, loaded.ca_key_pem);
     }
 
+    #[test]
+    fn ca_fingerprint_is_sha256_of_cert_der() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LocalCAStore {
+            ca_cert_path: temp_dir.path().join("ca").join("ca.crt"),
+        };
+        let ca = store.get_or_create_ca().unwrap();
+
+        let fingerprint = store.ca_fingerprint().unwrap();
+        assert_eq!(fingerprint, cert_fingerprint(&ca.ca_cert_pem).unwrap());
+
+        let der = pem::parse(ca.ca_cert_pem.as_bytes()).unwrap();
+        let expected = hex::encode_upper(sha2::Sha256::digest(der.contents()));
+        assert_eq!(fingerprint.replace(':', ""), expected);
+        assert_eq!(fingerprint.len(), 32 * 3 - 1);
+        assert!(fingerprint.split(':').all(|byte| byte.len() == 2));
+    }
+
+    #[test]
+    fn renew_ca_replaces_root_with_new_serial_and_fingerprint() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LocalCAStore {
+            ca_cert_path: temp_dir.path().join("ca").join("ca.crt"),
+        };
+        let serial = |cert_pem: &str| {
+            let (_, pem) = x509_parser::pem::parse_x509_pem(cert_pem.as_bytes()).unwrap();
+            pem.parse_x509().unwrap().tbs_certificate.serial.clone()
+        };
+
+        let old = store.get_or_create_ca().unwrap();
+        let old_fingerprint = store.ca_fingerprint().unwrap();
+
+        let renewed = store.renew_ca().unwrap();
+        assert_ne!(store.ca_fingerprint().unwrap(), old_fingerprint);
+        assert_ne!(serial(&renewed.ca_cert_pem), serial(&old.ca_cert_pem));
+
+        // The renewed pair is what's on disk now.
+        let loaded = store.load_ca().unwrap();
+        assert_eq!(loaded.ca_cert_pem, renewed.ca_cert_pem);
+        assert_eq!(loaded.ca_key_pem, renewed.ca_key_pem);
+    }
+
+    #[test]
+    fn ca_fingerprint_errors_without_cert() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LocalCAStore {
+            ca_cert_path: temp_dir.path().join("ca.crt"),
+        };
+        assert!(matches!(
+            store.ca_fingerprint(),
+            Err(CaError::FileRead(_, _))
+        ));
+        assert!(matches!(
+            cert_fingerprint("not a pem"),
+            Err(CaError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn trust_store_commands_install_and_remove_the_cert() {
+        let cert = "/data/ca/ca.crt";
+        assert_eq!(
+            TrustStore::Keychain.trust_commands(cert),
+            vec![vec![
+                "security",
+                "add-trusted-cert",
+                "-d",
+                "-r",
+                "trustRoot",
+                "-k",
+                "/Library/Keychains/System.keychain",
+                cert,
+            ]]
+        );
+        assert_eq!(
+            TrustStore::Keychain.untrust_commands(cert),
+            vec![vec!["security", "remove-trusted-cert", "-d", cert]]
+        );
+        assert_eq!(
+            TrustStore::Debian.trust_commands(cert),
+            vec![
+                vec!["cp", cert, "/usr/local/share/ca-certificates/tako-ca.crt"],
+                vec!["update-ca-certificates"],
+            ]
+        );
+        assert_eq!(
+            TrustStore::Debian.untrust_commands(cert),
+            vec![
+                vec!["rm", "-f", "/usr/local/share/ca-certificates/tako-ca.crt"],
+                vec!["update-ca-certificates"],
+            ]
+        );
+        assert_eq!(
+            TrustStore::Fedora.trust_commands(cert),
+            vec![
+                vec!["cp", cert, "/etc/pki/ca-trust/source/anchors/tako-ca.crt"],
+                vec!["update-ca-trust"],
+            ]
+        );
+        assert_eq!(
+            TrustStore::Fedora.untrust_commands(cert),
+            vec![
+                vec!["rm", "-f", "/etc/pki/ca-trust/source/anchors/tako-ca.crt"],
+                vec!["update-ca-trust"],
+            ]
+        );
+    }
+
+    #[test]
+    fn nss_commands_target_the_sql_database() {
+        let db = Path::new("/home/dev/.pki/nssdb");
+        assert_eq!(
+            nss_trust_command(db, "/data/ca/ca.crt"),
+            vec![
+                "certutil",
+                "-d",
+                "sql:/home/dev/.pki/nssdb",
+                "-A",
+                "-t",
+                "C,,",
+                "-n",
+                "Tako Development CA",
+                "-i",
+                "/data/ca/ca.crt",
+            ]
+        );
+        assert_eq!(
+            nss_untrust_command(db),
+            vec![
+                "certutil",
+                "-d",
+                "sql:/home/dev/.pki/nssdb",
+                "-D",
+                "-n",
+                "Tako Development CA",
+            ]
+        );
+    }
+
     #[test]
     fn test_ca_store_get_or_create() {
         let temp_dir = TempDir::new().unwrap();
@@ -8,5 +8,12 @@
 mod ca;
 mod domain;
 
-pub use ca::{CaError, Certificate, LocalCA, LocalCAStore};
+pub use ca::{
+    CaError, Certificate, LocalCA, LocalCAStore, TrustStore, cert_fingerprint, nss_db_dir,
+    nss_trust_command, nss_untrust_command,
+};
 pub use domain::{SHORT_DEV_DOMAIN, TAKO_DEV_DOMAIN, get_short_domain, get_tako_domain};
+
+/// Version of the `tako` <-> `tako-dev-server` control protocol, exchanged in
+/// the `Hello` handshake. Bump it when a request or response changes shape.
+pub const DEV_PROTOCOL_VERSION: u32 = 1;
@@ -10,6 +10,10 @@ use tokio::net::UnixStream;
 const DEV_SERVER_STARTUP_WAIT_ATTEMPTS: usize = 300;
 const DEV_SERVER_STARTUP_WAIT_INTERVAL_MS: u64 = 50;
 const DEV_SERVER_CONNECTION_CLOSED_MESSAGE: &str = "dev-server closed connection";
+// A daemon that has just been spawned (or is restarting) can refuse
+// connections for a moment; requests retry for ~150ms before giving up.
+const DEV_SERVER_CONNECT_ATTEMPTS: usize = 4;
+const DEV_SERVER_CONNECT_INITIAL_BACKOFF_MS: u64 = 20;
 
 fn socket_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(crate::paths::tako_data_dir()?.join("dev-server.sock"))
@@ -88,6 +92,44 @@ impl LineClient {
     }
 }
 
+/// Connect to the daemon socket for one request.
+async fn connect() -> Result<LineClient, Box<dyn std::error::Error>> {
+    let sock = socket_path()?;
+    let stream = connect_with_retry(
+        DEV_SERVER_CONNECT_ATTEMPTS,
+        Duration::from_millis(DEV_SERVER_CONNECT_INITIAL_BACKOFF_MS),
+        || UnixStream::connect(&sock),
+    )
+    .await?;
+    Ok(LineClient::new(stream))
+}
+
+/// Run `connect` up to `attempts` times, doubling the delay after each
+/// refused connection. Other errors (e.g. no socket at all, meaning no
+/// daemon) are returned right away.
+async fn connect_with_retry<T, F, Fut>(
+    attempts: usize,
+    initial_backoff: Duration,
+    mut connect: F,
+) -> std::io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = std::io::Result<T>>,
+{
+    let mut backoff = initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match connect().await {
+            Err(e) if attempt < attempts && e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct ListedApp {
@@ -98,6 +140,15 @@ pub struct ListedApp {
     pub pid: Option<u32>,
 }
 
+/// Whether an app's process is alive, and how long since it last logged.
+#[derive(Debug, Clone)]
+pub struct AppHealth {
+    pub app_name: String,
+    pub pid: Option<u32>,
+    pub alive: bool,
+    pub last_log_ms_ago: Option<u64>,
+}
+
 pub async fn ensure_running(
     listen_addr: &str,
     dns_ip: &str,
@@ -107,8 +158,14 @@ pub async fn ensure_running(
 
     if let Ok(stream) = UnixStream::connect(&sock).await {
         let mut c = LineClient::new(stream);
-        ping(&mut c).await?;
-        return Ok(());
+        let Some(mismatch) = handshake_mismatch(&hello(&mut c).await?) else {
+            return Ok(());
+        };
+        drop(c);
+        if !crate::output::confirm(&format!("{mismatch}. Restart it?"), true)? {
+            return Err(format!("{mismatch}; restart it to continue").into());
+        }
+        stop_and_wait(listen_addr).await?;
     }
 
     // If we can't connect to the daemon, we're about to spawn one. Avoid noisy
@@ -125,8 +182,12 @@ pub async fn ensure_running(
         tokio::time::sleep(Duration::from_millis(DEV_SERVER_STARTUP_WAIT_INTERVAL_MS)).await;
         if let Ok(stream) = UnixStream::connect(&sock).await {
             let mut c = LineClient::new(stream);
-            ping(&mut c).await?;
-            return Ok(());
+            // A freshly spawned daemon that still mismatches is a stale
+            // binary; restarting it again would not help.
+            return match handshake_mismatch(&hello(&mut c).await?) {
+                Some(mismatch) => Err(mismatch.into()),
+                None => Ok(()),
+            };
         }
         if let Some(status) = child.try_wait()? {
             return Err(format_dev_server_connect_error(&log_path, Some(status)).into());
@@ -246,13 +307,70 @@ fn repo_local_dev_server_build_args() -> [&'static str; 5] {
     ["build", "-p", "tako", "--bin", "tako-dev-server"]
 }
 
-async fn ping(c: &mut LineClient) -> Result<(), Box<dyn std::error::Error>> {
-    c.send_line(r#"{"type":"Ping"}"#).await?;
+/// What a daemon reported in the `Hello` handshake. Daemons that predate the
+/// handshake answer with an error, which leaves both fields empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DevServerHello {
+    pub server_version: Option<String>,
+    pub protocol: Option<u32>,
+}
+
+async fn hello(c: &mut LineClient) -> Result<DevServerHello, Box<dyn std::error::Error>> {
+    let req = serde_json::json!({
+        "type": "Hello",
+        "client_version": env!("CARGO_PKG_VERSION"),
+    });
+    c.send_line(&req.to_string()).await?;
     let line = c.read_line().await?;
-    if line.trim() == r#"{"type":"Pong"}"# {
-        return Ok(());
+    parse_hello_line(&line)
+}
+
+fn parse_hello_line(line: &str) -> Result<DevServerHello, Box<dyn std::error::Error>> {
+    let v: serde_json::Value = serde_json::from_str(line)?;
+    match v.get("type").and_then(|t| t.as_str()) {
+        Some("Hello") => Ok(DevServerHello {
+            server_version: v
+                .get("server_version")
+                .and_then(|s| s.as_str())
+                .map(str::to_string),
+            protocol: v
+                .get("protocol")
+                .and_then(|p| p.as_u64())
+                .and_then(|p| u32::try_from(p).ok()),
+        }),
+        Some("Error") => Ok(DevServerHello::default()),
+        _ => Err(format!("unexpected response: {}", line).into()),
+    }
+}
+
+/// Why this CLI can't talk to the daemon that answered `hello`, if it can't.
+pub(crate) fn handshake_mismatch(hello: &DevServerHello) -> Option<String> {
+    if hello.protocol == Some(crate::dev::DEV_PROTOCOL_VERSION) {
+        return None;
+    }
+    let server = hello
+        .server_version
+        .as_deref()
+        .map_or("an older tako-dev-server".to_string(), |version| {
+            format!("tako-dev-server {version}")
+        });
+    Some(format!(
+        "The running dev server ({server}) is not compatible with tako {}",
+        env!("CARGO_PKG_VERSION")
+    ))
+}
+
+/// Stop the running daemon and wait until its socket and listen address are
+/// free.
+async fn stop_and_wait(listen_addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+    stop_server().await?;
+    for _ in 0..DEV_SERVER_STARTUP_WAIT_ATTEMPTS {
+        if !is_responding().await && std::net::TcpListener::bind(listen_addr).is_ok() {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(DEV_SERVER_STARTUP_WAIT_INTERVAL_MS)).await;
     }
-    Err(format!("unexpected response: {}", line).into())
+    Err("the running dev server did not stop".into())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -317,6 +435,10 @@ pub enum DevServerEvent {
         app_name: String,
         message: String,
     },
+    RegistrationExpired {
+        config_path: String,
+        app_name: String,
+    },
 }
 
 fn parse_event_line(line: &str) -> Option<DevServerEvent> {
@@ -392,15 +514,17 @@ fn parse_event_line(line: &str) -> Option<DevServerEvent> {
             app_name: event.get("app_name")?.as_str()?.to_string(),
             message: event.get("message")?.as_str()?.to_string(),
         }),
+        "RegistrationExpired" => Some(DevServerEvent::RegistrationExpired {
+            config_path: event.get("config_path")?.as_str()?.to_string(),
+            app_name: event.get("app_name")?.as_str()?.to_string(),
+        }),
         _ => None,
     }
 }
 
 pub async fn subscribe_events()
 -> Result<tokio::sync::mpsc::UnboundedReceiver<DevServerEvent>, Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     c.send_line(r#"{"type":"SubscribeEvents"}"#).await?;
 
     // Wait for Subscribed.
@@ -432,10 +556,73 @@ pub async fn subscribe_events()
     Ok(rx)
 }
 
+pub async fn health() -> Result<Vec<AppHealth>, Box<dyn std::error::Error>> {
+    let mut c = connect().await?;
+    c.send_line(r#"{"type":"Health"}"#).await?;
+    let line = c.read_line().await?;
+    let v: serde_json::Value = serde_json::from_str(&line)?;
+    if v.get("type").and_then(|t| t.as_str()) != Some("Health") {
+        return Err(format!("unexpected response: {}", line).into());
+    }
+    let apps = v
+        .get("apps")
+        .and_then(|a| a.as_array())
+        .cloned()
+        .unwrap_or_default();
+    Ok(apps
+        .into_iter()
+        .filter_map(|a| {
+            Some(AppHealth {
+                app_name: a.get("app_name")?.as_str()?.to_string(),
+                pid: a.get("pid").and_then(|p| p.as_u64()).map(|p| p as u32),
+                alive: a.get("alive")?.as_bool()?,
+                last_log_ms_ago: a.get("last_log_ms_ago").and_then(|v| v.as_u64()),
+            })
+        })
+        .collect())
+}
+
+/// One entry of the daemon's proxy route table, in match order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevRoute {
+    pub pattern: String,
+    pub app_id: String,
+    pub upstream_port: u16,
+    pub active: bool,
+}
+
+pub async fn describe_routes() -> Result<Vec<DevRoute>, Box<dyn std::error::Error>> {
+    let mut c = connect().await?;
+    c.send_line(r#"{"type":"DescribeRoutes"}"#).await?;
+    let line = c.read_line().await?;
+    parse_routes_line(&line)
+}
+
+fn parse_routes_line(line: &str) -> Result<Vec<DevRoute>, Box<dyn std::error::Error>> {
+    let v: serde_json::Value = serde_json::from_str(line)?;
+    if v.get("type").and_then(|t| t.as_str()) != Some("Routes") {
+        return Err(format!("unexpected response: {}", line).into());
+    }
+    let routes = v
+        .get("routes")
+        .and_then(|r| r.as_array())
+        .cloned()
+        .unwrap_or_default();
+    Ok(routes
+        .into_iter()
+        .filter_map(|r| {
+            Some(DevRoute {
+                pattern: r.get("pattern")?.as_str()?.to_string(),
+                app_id: r.get("app_id")?.as_str()?.to_string(),
+                upstream_port: u16::try_from(r.get("upstream_port")?.as_u64()?).ok()?,
+                active: r.get("active")?.as_bool()?,
+            })
+        })
+        .collect())
+}
+
 pub async fn list_apps() -> Result<Vec<ListedApp>, Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     c.send_line(r#"{"type":"ListApps"}"#).await?;
     let line = c.read_line().await?;
     let v: serde_json::Value = serde_json::from_str(&line)?;
@@ -498,9 +685,7 @@ pub async fn register_app(
     readiness_failure_hint: Option<&str>,
     worker_command: Option<&[String]>,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     let mut req = serde_json::json!({
         "type": "RegisterApp",
         "config_path": config_path,
@@ -535,9 +720,7 @@ pub async fn register_app(
 }
 
 pub async fn unregister_app(config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     let req = serde_json::json!({
         "type": "UnregisterApp",
         "config_path": config_path,
@@ -553,9 +736,7 @@ pub async fn unregister_app(config_path: &str) -> Result<(), Box<dyn std::error:
 }
 
 pub async fn restart_app(config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     let req = serde_json::json!({
         "type": "RestartApp",
         "config_path": config_path,
@@ -573,9 +754,7 @@ pub async fn restart_app(config_path: &str) -> Result<(), Box<dyn std::error::Er
 pub async fn toggle_lan(
     enabled: bool,
 ) -> Result<(bool, Option<String>, Option<String>), Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     let req = serde_json::json!({
         "type": "ToggleLan",
         "enabled": enabled,
@@ -607,9 +786,7 @@ pub async fn connect_client(
     config_path: &str,
     client_id: u32,
 ) -> Result<LineClient, Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     let req = serde_json::json!({
         "type": "ConnectClient",
         "config_path": config_path,
@@ -625,9 +802,7 @@ pub async fn connect_client(
 }
 
 pub async fn list_registered_apps() -> Result<Vec<RegisteredAppInfo>, Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     c.send_line(r#"{"type":"ListRegisteredApps"}"#).await?;
     let line = c.read_line().await?;
     let v: serde_json::Value = serde_json::from_str(&line)?;
@@ -676,10 +851,21 @@ pub async fn list_registered_apps() -> Result<Vec<RegisteredAppInfo>, Box<dyn st
         .collect())
 }
 
-pub async fn info() -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
+/// Whether the daemon answers right now, without retrying. For loops that
+/// wait for it to go away.
+pub async fn is_responding() -> bool {
+    let Ok(sock) = socket_path() else {
+        return false;
+    };
+    let Ok(stream) = UnixStream::connect(&sock).await else {
+        return false;
+    };
     let mut c = LineClient::new(stream);
+    c.send_line(r#"{"type":"Info"}"#).await.is_ok() && c.read_line().await.is_ok()
+}
+
+pub async fn info() -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut c = connect().await?;
     c.send_line(r#"{"type":"Info"}"#).await?;
     let line = c.read_line().await?;
     Ok(serde_json::from_str(&line)?)
@@ -692,17 +878,19 @@ pub enum LogStreamEntry {
     Truncated,
 }
 
+/// Stream an app's buffered log lines, then (with `follow`) new ones as they
+/// arrive.
 pub async fn subscribe_logs(
     config_path: &str,
     after: Option<u64>,
+    follow: bool,
 ) -> Result<tokio::sync::mpsc::UnboundedReceiver<LogStreamEntry>, Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     let req = serde_json::json!({
         "type": "SubscribeLogs",
         "config_path": config_path,
         "after": after,
+        "follow": follow,
     });
     c.send_line(&req.to_string()).await?;
 
@@ -760,9 +948,7 @@ pub async fn subscribe_logs(
 }
 
 pub async fn stop_server() -> Result<(), Box<dyn std::error::Error>> {
-    let sock = socket_path()?;
-    let stream = UnixStream::connect(&sock).await?;
-    let mut c = LineClient::new(stream);
+    let mut c = connect().await?;
     c.send_line(r#"{"type":"StopServer"}"#).await?;
     let line = c.read_line().await?;
     let v: serde_json::Value = serde_json::from_str(&line)?;
@@ -847,6 +1033,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_event_line_parses_registration_expired_event() {
+        let line = r#"{"type":"Event","event":{"type":"RegistrationExpired","config_path":"/proj/tako.toml","app_name":"web"}}"#;
+        assert_eq!(
+            parse_event_line(line),
+            Some(DevServerEvent::RegistrationExpired {
+                config_path: "/proj/tako.toml".to_string(),
+                app_name: "web".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_routes_line_reads_route_table() {
+        let line = r#"{"type":"Routes","routes":[{"pattern":"api.test/v1/*","app_id":"reg:/api/tako.toml","upstream_port":4000,"active":false},{"pattern":"web.test","app_id":"reg:/web/tako.toml","upstream_port":3000,"active":true}]}"#;
+        assert_eq!(
+            parse_routes_line(line).unwrap(),
+            vec![
+                DevRoute {
+                    pattern: "api.test/v1/*".to_string(),
+                    app_id: "reg:/api/tako.toml".to_string(),
+                    upstream_port: 4000,
+                    active: false,
+                },
+                DevRoute {
+                    pattern: "web.test".to_string(),
+                    app_id: "reg:/web/tako.toml".to_string(),
+                    upstream_port: 3000,
+                    active: true,
+                },
+            ]
+        );
+        assert!(parse_routes_line(r#"{"type":"Error","message":"invalid request"}"#).is_err());
+    }
+
     #[test]
     fn parse_event_line_rejects_request_started_without_path() {
         let line = r#"{"type":"Event","event":{"type":"RequestStarted","host":"a.test"}}"#;
@@ -949,4 +1170,112 @@ mod tests {
             "unexpected error: {err}"
         );
     }
+
+    #[tokio::test]
+    async fn connect_with_retry_succeeds_on_a_later_attempt() {
+        let calls = std::cell::Cell::new(0);
+        let result = connect_with_retry(4, Duration::from_millis(1), || {
+            calls.set(calls.get() + 1);
+            let call = calls.get();
+            async move {
+                if call < 3 {
+                    Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+                } else {
+                    Ok(call)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn connect_with_retry_gives_up_after_the_attempt_bound() {
+        let calls = std::cell::Cell::new(0);
+        let result: std::io::Result<()> = connect_with_retry(4, Duration::from_millis(1), || {
+            calls.set(calls.get() + 1);
+            async { Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused)) }
+        })
+        .await;
+
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::ConnectionRefused
+        );
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[tokio::test]
+    async fn connect_with_retry_does_not_retry_a_missing_socket() {
+        let calls = std::cell::Cell::new(0);
+        let result: std::io::Result<()> = connect_with_retry(4, Duration::from_millis(1), || {
+            calls.set(calls.get() + 1);
+            async { Err(std::io::Error::from(std::io::ErrorKind::NotFound)) }
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn hello_sends_client_version_and_reads_daemon_reply() {
+        let (client_stream, server_stream) = UnixStream::pair().unwrap();
+        let daemon = tokio::spawn(async move {
+            let (r, mut w) = server_stream.into_split();
+            let mut request = String::new();
+            BufReader::new(r).read_line(&mut request).await.unwrap();
+            w.write_all(b"{\"type\":\"Hello\",\"server_version\":\"9.9.9\",\"protocol\":7}\n")
+                .await
+                .unwrap();
+            request
+        });
+
+        let mut client = LineClient::new(client_stream);
+        let reply = hello(&mut client).await.unwrap();
+
+        let request: serde_json::Value = serde_json::from_str(&daemon.await.unwrap()).unwrap();
+        assert_eq!(request["type"], "Hello");
+        assert_eq!(request["client_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            reply,
+            DevServerHello {
+                server_version: Some("9.9.9".to_string()),
+                protocol: Some(7),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_hello_line_treats_errors_as_a_pre_handshake_daemon() {
+        let reply = parse_hello_line(
+            r#"{"type":"Error","message":"invalid request: unknown variant `Hello`"}"#,
+        )
+        .unwrap();
+        assert_eq!(reply, DevServerHello::default());
+
+        assert!(parse_hello_line(r#"{"type":"Pong"}"#).is_err());
+        assert!(parse_hello_line("not json").is_err());
+    }
+
+    #[test]
+    fn handshake_mismatch_accepts_only_the_current_protocol() {
+        let current = DevServerHello {
+            server_version: Some("0.0.1".to_string()),
+            protocol: Some(crate::dev::DEV_PROTOCOL_VERSION),
+        };
+        assert_eq!(handshake_mismatch(&current), None);
+
+        let newer = DevServerHello {
+            server_version: Some("9.9.9".to_string()),
+            protocol: Some(crate::dev::DEV_PROTOCOL_VERSION + 1),
+        };
+        let message = handshake_mismatch(&newer).unwrap();
+        assert!(message.contains("tako-dev-server 9.9.9"), "{message}");
+
+        let message = handshake_mismatch(&DevServerHello::default()).unwrap();
+        assert!(message.contains("an older tako-dev-server"), "{message}");
+    }
 }
@@ -28,6 +28,8 @@ fn main() {
     crate::output::set_verbose(cli.verbose);
     crate::output::set_ci(cli.ci);
     crate::output::set_dry_run(cli.dry_run);
+    crate::output::set_json(cli.json);
+    crate::build::preset_cache::set_refresh(cli.refresh_presets);
 
     // Hide cursor for the entire process lifetime when running in interactive
     // pretty mode. Individual prompts (text fields) temporarily show it while
@@ -67,6 +69,17 @@ fn main() {
             .init();
     }
 
+    if cli.json {
+        let result = {
+            let _redirect = crate::output::StdoutToStderr::redirect();
+            cli.run()
+        };
+        let failed = result.is_err();
+        let report = crate::output::take_json_report(result.map_err(|e| e.to_string()));
+        println!("{report}");
+        std::process::exit(i32::from(failed));
+    }
+
     // Run the command
     if let Err(e) = cli.run() {
         crate::output::restore_cursor();
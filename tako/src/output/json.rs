@@ -0,0 +1,151 @@
+//! `--json` mode: commands print one JSON object to stdout instead of
+//! human-formatted output.
+//!
+//! Output helpers record their messages here rather than drawing anything;
+//! commands can attach structured results with [`json_data`]. `main` prints
+//! the report once the command finishes.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static REPORT: Mutex<JsonReport> = Mutex::new(JsonReport::new());
+
+struct JsonReport {
+    data: Option<Map<String, Value>>,
+    messages: Vec<Value>,
+}
+
+impl JsonReport {
+    const fn new() -> Self {
+        Self {
+            data: None,
+            messages: Vec::new(),
+        }
+    }
+}
+
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Attach a structured result under `key` in the report's `data`.
+pub fn json_data<T: Serialize>(key: &str, value: T) {
+    let value = serde_json::to_value(value).unwrap_or(Value::Null);
+    REPORT
+        .lock()
+        .unwrap()
+        .data
+        .get_or_insert_with(Map::new)
+        .insert(key.to_string(), value);
+}
+
+/// Record a human-facing message (`success`, `warning`, …) in the report.
+pub(super) fn record(level: &str, message: &str) {
+    REPORT
+        .lock()
+        .unwrap()
+        .messages
+        .push(json!({ "level": level, "message": message }));
+}
+
+/// Take the report for a finished command:
+/// `{"status": "ok"|"error", "data": {…}, "messages": […], "error": null|"…"}`.
+pub fn take_json_report(result: Result<(), String>) -> Value {
+    let report = std::mem::replace(&mut *REPORT.lock().unwrap(), JsonReport::new());
+    let (status, error) = match result {
+        Ok(()) => ("ok", Value::Null),
+        Err(message) => ("error", Value::String(message)),
+    };
+    json!({
+        "status": status,
+        "data": report.data.unwrap_or_default(),
+        "messages": report.messages,
+        "error": error,
+    })
+}
+
+/// Sends anything written to stdout to stderr until dropped, so stray prints
+/// can't break the single JSON object `main` writes afterwards.
+pub struct StdoutToStderr {
+    #[cfg(unix)]
+    saved: Option<libc::c_int>,
+}
+
+impl StdoutToStderr {
+    pub fn redirect() -> Self {
+        #[cfg(unix)]
+        {
+            use std::io::Write;
+            let _ = std::io::stdout().flush();
+            // SAFETY: plain fd duplication on the process's own stdio fds.
+            let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+            if saved < 0 || unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } < 0 {
+                return Self { saved: None };
+            }
+            Self { saved: Some(saved) }
+        }
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+}
+
+impl Drop for StdoutToStderr {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(saved) = self.saved.take() {
+            use std::io::Write;
+            let _ = std::io::stdout().flush();
+            // SAFETY: `saved` is the stdout fd duplicated in `redirect`.
+            unsafe {
+                libc::dup2(saved, libc::STDOUT_FILENO);
+                libc::close(saved);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_json_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_has_status_data_and_error() {
+        let _lock = test_json_lock();
+        let _ = take_json_report(Ok(()));
+
+        json_data("app", "web");
+        json_data("instances", 3);
+        record("success", "Deployed");
+        let report = take_json_report(Ok(()));
+        assert_eq!(report["status"], "ok");
+        assert_eq!(report["data"], json!({ "app": "web", "instances": 3 }));
+        assert_eq!(
+            report["messages"],
+            json!([{ "level": "success", "message": "Deployed" }])
+        );
+        assert!(report["error"].is_null());
+
+        // Taking the report resets it.
+        let report = take_json_report(Err("boom".to_string()));
+        assert_eq!(report["status"], "error");
+        assert_eq!(report["data"], json!({}));
+        assert_eq!(report["messages"], json!([]));
+        assert_eq!(report["error"], "boom");
+    }
+}
@@ -1,4 +1,5 @@
 pub mod cursor;
+mod json;
 mod prompts;
 pub mod spinners;
 mod tracing_fmt;
@@ -20,6 +21,11 @@ pub use spinners::{
 // Re-export tracing types
 pub use tracing_fmt::{ScopeFormat, ScopeLayer, scope, timed};
 
+// Re-export JSON mode
+#[cfg(test)]
+pub(crate) use json::test_json_lock;
+pub use json::{StdoutToStderr, is_json, json_data, set_json, take_json_report};
+
 // Re-export cursor management
 pub use cursor::{clear_interrupt_output, restore_cursor, set_cursor_globally_hidden};
 
@@ -248,7 +254,7 @@ pub fn is_interactive() -> bool {
 
     #[cfg(not(test))]
     {
-        !is_ci() && std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+        !is_ci() && !is_json() && std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
     }
 }
 
@@ -272,9 +278,10 @@ pub fn is_root() -> bool {
 }
 
 /// True when pretty output should render (normal interactive mode).
-/// False in verbose or CI mode, where tracing handles all output.
+/// False in verbose or CI mode, where tracing handles all output, and in
+/// JSON mode, where messages go into the report.
 pub fn is_pretty() -> bool {
-    !is_verbose() && !is_ci()
+    !is_verbose() && !is_ci() && !is_json()
 }
 
 // ── Logo ───────────────────────────────────────────────────────────────────
@@ -372,6 +379,10 @@ pub fn heading(title: &str) {
 }
 
 pub fn info(message: &str) {
+    if is_json() {
+        json::record("info", message);
+        return;
+    }
     if is_pretty() {
         emit(&padded(&theme_fg(message)));
     }
@@ -381,12 +392,20 @@ pub fn info(message: &str) {
 /// summary blocks that render on their own (no co-located spinners or
 /// symbol-prefixed lines to align with).
 pub fn line(message: &str) {
+    if is_json() {
+        json::record("info", message);
+        return;
+    }
     if is_pretty() {
         emit(&theme_fg(message));
     }
 }
 
 pub fn bullet(message: &str) {
+    if is_json() {
+        json::record("info", message);
+        return;
+    }
     if is_pretty() {
         emit(&format!("  - {}", theme_fg(message)));
     }
@@ -409,6 +428,10 @@ fn format_warning_bullet_line(message: &str) -> String {
 }
 
 pub fn success(message: &str) {
+    if is_json() {
+        json::record("success", message);
+        return;
+    }
     if is_pretty() {
         emit(&format!("{} {}", theme_success("✔"), theme_fg(message)));
     } else {
@@ -417,6 +440,10 @@ pub fn success(message: &str) {
 }
 
 pub fn success_with_elapsed(message: &str, elapsed: Duration) {
+    if is_json() {
+        json::record("success", message);
+        return;
+    }
     let time = format_elapsed(elapsed);
     if is_pretty() {
         let line = if time.is_empty() {
@@ -438,6 +465,10 @@ pub fn success_with_elapsed(message: &str, elapsed: Duration) {
 }
 
 pub fn warning(message: &str) {
+    if is_json() {
+        json::record("warning", message);
+        return;
+    }
     if is_pretty() {
         emit(&format!(
             "{} {}",
@@ -476,6 +507,10 @@ pub fn error_stderr(message: &str) {
 
 /// Print a wrapped red error message without prompt chrome.
 pub fn error_block(message: &str) {
+    if is_json() {
+        json::record("error", message);
+        return;
+    }
     if !is_pretty() {
         tracing::error!("{}", message);
         return;
@@ -563,6 +598,10 @@ pub fn muted(message: &str) {
 
 /// Print a hint line in default text color (not muted).
 pub fn hint(message: &str) {
+    if is_json() {
+        json::record("hint", message);
+        return;
+    }
     if is_pretty() {
         emit(&padded(&theme_dim(message)));
     } else {
@@ -386,6 +386,66 @@ l4QMs5cmnWfrM0GQ==\n\
         assert!(command.contains("| head -n 1"));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn socket_request_command_reads_response_from_fake_server() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixListener;
+        use std::process::{Command as ProcessCommand, Stdio};
+
+        let has_nc = ProcessCommand::new("sh")
+            .args(["-c", "command -v nc"])
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if !has_nc || !can_bind_unix_socket() {
+            return;
+        }
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let socket_path = dir.path().join("tako.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            BufReader::new(&stream).read_line(&mut request).unwrap();
+            let mut stream = stream;
+            // A second line must not reach the caller.
+            stream
+                .write_all(b"{\"status\":\"ok\",\"data\":{\"apps\":[\"web\"]}}\n{\"extra\":1}\n")
+                .unwrap();
+            request
+        });
+
+        let command = SshClient::socket_request_command_on_path(socket_path.to_str().unwrap());
+        let mut child = ProcessCommand::new("sh")
+            .args(["-c", &command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(b"{\"command\":\"list\"}\n")
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert_eq!(server.join().unwrap().trim(), r#"{"command":"list"}"#);
+        let stdout = SshClient::extract_socket_stdout(CommandOutput {
+            exit_code: output.status.code().unwrap_or(255) as u32,
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+        .unwrap();
+        let response: Response = serde_json::from_str(&stdout).unwrap();
+        let Response::Ok { data } = response else {
+            panic!("expected ok response, got {response:?}");
+        };
+        assert_eq!(data["apps"][0], "web");
+    }
+
     #[test]
     fn tako_restart_command_uses_service_helper_with_root_or_sudo() {
         let command = SshClient::tako_restart_command();
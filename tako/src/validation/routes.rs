@@ -4,6 +4,8 @@
 //! - Path-only routes are INVALID (e.g., `/api/*`) - must include hostname
 //! - Routes must include hostname (exact or wildcard): `api.example.com`, `*.api.example.com`
 //! - Optional path suffix allowed: `api.example.com/admin/*`
+//! - No scheme (`https://`) or port (`:8443`); `*` only as the leading host
+//!   label or as a whole path segment
 //!
 //! Development-Specific Rules (`[envs.development]`):
 //! - Routes are optional in config; if omitted, `tako dev` defaults to `{app-name}.test`
@@ -34,32 +36,139 @@ pub enum RouteValidationError {
 
     #[error("Invalid hostname in route: '{0}'")]
     InvalidHostname(String),
+
+    #[error("Route must not include a scheme: '{0}'. Use '{1}' instead")]
+    SchemeNotAllowed(String, String),
+
+    #[error("Route must not include a port: '{0}'. Tako serves routes on ports 80/443")]
+    PortNotAllowed(String),
+
+    #[error("Invalid host label '{label}' in route '{route}': {reason}")]
+    InvalidHostLabel {
+        route: String,
+        label: String,
+        reason: &'static str,
+    },
+
+    #[error("Invalid path in route '{route}': {reason}")]
+    InvalidPathGlob { route: String, reason: String },
 }
 
 /// Result type for route validation
 pub type RouteResult<T> = Result<T, RouteValidationError>;
 
-/// Validates a route pattern for any environment
+/// A route pattern split into its host and optional path glob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedRoute {
+    /// Exact host or `*.`-prefixed wildcard host.
+    pub host: String,
+    /// Path part including the leading `/`, e.g. `/api/*`.
+    pub path_glob: Option<String>,
+}
+
+/// Parses a route pattern (without `-> strip` / `-> prepend` directives).
 ///
-/// Rules:
-/// - Must not be empty
-/// - Must not start with `/` (path-only)
-/// - Must have a valid hostname
-pub fn validate_route(route: &str) -> RouteResult<()> {
+/// Rejects schemes, ports, malformed host labels, and wildcards that are not
+/// the leading host label or a whole path segment.
+pub fn parse_route(route: &str) -> RouteResult<ParsedRoute> {
     if route.is_empty() {
         return Err(RouteValidationError::EmptyRoute);
     }
-
-    // Check for path-only routes (starts with /)
+    if let Some((_, rest)) = route.split_once("://") {
+        return Err(RouteValidationError::SchemeNotAllowed(
+            route.to_string(),
+            rest.to_string(),
+        ));
+    }
     if route.starts_with('/') {
         return Err(RouteValidationError::PathOnlyRoute(route.to_string()));
     }
 
-    // Parse hostname and optional path
-    let (hostname, _path) = split_route(route);
+    let (host, path) = split_route(route);
+    if host.contains(':') {
+        return Err(RouteValidationError::PortNotAllowed(route.to_string()));
+    }
+
+    let labels = host.strip_prefix("*.").unwrap_or(host);
+    if labels.is_empty() {
+        return Err(RouteValidationError::InvalidHostname(host.to_string()));
+    }
+    for label in labels.split('.') {
+        validate_host_label(route, label)?;
+    }
 
-    // Validate hostname
-    validate_hostname(hostname)?;
+    if let Some(path) = path {
+        validate_path_glob(route, path)?;
+    }
+
+    Ok(ParsedRoute {
+        host: host.to_string(),
+        path_glob: path.map(str::to_string),
+    })
+}
+
+fn validate_host_label(route: &str, label: &str) -> RouteResult<()> {
+    let reason = if label.is_empty() {
+        Some("labels cannot be empty")
+    } else if label.contains('*') {
+        Some("'*' is only allowed as the first label (e.g., *.example.com)")
+    } else if label.len() > 63 {
+        Some("labels are limited to 63 characters")
+    } else if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        Some("only letters, digits, and '-' are allowed")
+    } else if label.starts_with('-') || label.ends_with('-') {
+        Some("labels cannot start or end with '-'")
+    } else {
+        None
+    };
+    match reason {
+        Some(reason) => Err(RouteValidationError::InvalidHostLabel {
+            route: route.to_string(),
+            label: label.to_string(),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn validate_path_glob(route: &str, path: &str) -> RouteResult<()> {
+    let invalid = |reason: String| RouteValidationError::InvalidPathGlob {
+        route: route.to_string(),
+        reason,
+    };
+    if let Some(c) = path
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '.' | '-' | '*' | '/'))
+    {
+        return Err(invalid(format!("invalid character '{c}'")));
+    }
+    if path
+        .split('/')
+        .any(|segment| segment.contains('*') && segment != "*")
+    {
+        return Err(invalid(
+            "'*' must be a complete path segment (e.g., /api/*)".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Validates a route pattern for any environment
+///
+/// Rules:
+/// - Everything `parse_route` checks (directives after ` -> ` are ignored)
+/// - Hostname must include a TLD
+pub fn validate_route(route: &str) -> RouteResult<()> {
+    let pattern = route.split(" -> ").next().unwrap_or(route);
+    let parsed = parse_route(pattern)?;
+
+    let hostname = parsed.host.strip_prefix("*.").unwrap_or(&parsed.host);
+    if !hostname.contains('.') {
+        return Err(RouteValidationError::InvalidPattern(
+            hostname.to_string(),
+            "Hostname must include a TLD (e.g., 'example.com' not 'example')".to_string(),
+        ));
+    }
 
     Ok(())
 }
@@ -101,67 +210,6 @@ fn split_route(route: &str) -> (&str, Option<&str>) {
     }
 }
 
-/// Validates a hostname (with optional wildcard prefix)
-fn validate_hostname(hostname: &str) -> RouteResult<()> {
-    if hostname.is_empty() {
-        return Err(RouteValidationError::InvalidHostname(hostname.to_string()));
-    }
-
-    // Handle wildcard prefix
-    let hostname = hostname.strip_prefix("*.").unwrap_or(hostname);
-
-    // Check for empty after stripping wildcard
-    if hostname.is_empty() {
-        return Err(RouteValidationError::InvalidHostname("*".to_string()));
-    }
-
-    // Basic hostname validation
-    // Must contain at least one dot (TLD required)
-    if !hostname.contains('.') {
-        return Err(RouteValidationError::InvalidPattern(
-            hostname.to_string(),
-            "Hostname must include a TLD (e.g., 'example.com' not 'example')".to_string(),
-        ));
-    }
-
-    // Validate each label
-    for label in hostname.split('.') {
-        if label.is_empty() {
-            return Err(RouteValidationError::InvalidHostname(hostname.to_string()));
-        }
-
-        // Labels must start with alphanumeric
-        if !label
-            .chars()
-            .next()
-            .map(|c| c.is_alphanumeric())
-            .unwrap_or(false)
-        {
-            return Err(RouteValidationError::InvalidHostname(hostname.to_string()));
-        }
-
-        // Labels can only contain alphanumeric and hyphens
-        if !label.chars().all(|c| c.is_alphanumeric() || c == '-') {
-            return Err(RouteValidationError::InvalidHostname(hostname.to_string()));
-        }
-
-        // Labels cannot end with hyphen
-        if label.ends_with('-') {
-            return Err(RouteValidationError::InvalidHostname(hostname.to_string()));
-        }
-
-        // Labels have max length of 63
-        if label.len() > 63 {
-            return Err(RouteValidationError::InvalidPattern(
-                hostname.to_string(),
-                "Label exceeds 63 character limit".to_string(),
-            ));
-        }
-    }
-
-    Ok(())
-}
-
 /// Checks if a hostname matches a route pattern
 ///
 /// Supports:
@@ -358,6 +406,68 @@ mod tests {
         validate_route("*.example.com/api/*").unwrap();
     }
 
+    #[test]
+    fn test_parse_route_splits_host_and_wildcard_path() {
+        let parsed = parse_route("*.example.com/api/*").unwrap();
+        assert_eq!(parsed.host, "*.example.com");
+        assert_eq!(parsed.path_glob.as_deref(), Some("/api/*"));
+        assert_eq!(parse_route("example.com").unwrap().path_glob, None);
+    }
+
+    #[test]
+    fn test_parse_route_rejects_scheme() {
+        let err = parse_route("https://example.com/api").unwrap_err();
+        assert!(
+            matches!(err, RouteValidationError::SchemeNotAllowed(_, ref fix) if fix == "example.com/api")
+        );
+    }
+
+    #[test]
+    fn test_parse_route_rejects_port() {
+        let err = parse_route("example.com:8443/api").unwrap_err();
+        assert!(matches!(err, RouteValidationError::PortNotAllowed(_)));
+    }
+
+    #[test]
+    fn test_parse_route_rejects_invalid_host_labels() {
+        for (route, label) in [
+            ("exa_mple.com", "exa_mple"),
+            ("-api.example.com", "-api"),
+            ("api..example.com", ""),
+            ("api.*.example.com", "*"),
+        ] {
+            match parse_route(route).unwrap_err() {
+                RouteValidationError::InvalidHostLabel { label: got, .. } => {
+                    assert_eq!(got, label, "{route}")
+                }
+                other => panic!("{route}: unexpected {other:?}"),
+            }
+        }
+        let long = format!("{}.com", "a".repeat(64));
+        assert!(matches!(
+            parse_route(&long).unwrap_err(),
+            RouteValidationError::InvalidHostLabel { .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_route_rejects_bad_path_glob() {
+        for route in ["example.com/api*", "example.com/a b", "example.com/*/x?"] {
+            assert!(
+                matches!(
+                    parse_route(route).unwrap_err(),
+                    RouteValidationError::InvalidPathGlob { .. }
+                ),
+                "{route}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_route_ignores_directives() {
+        validate_route("api.example.com/v1/* -> strip").unwrap();
+    }
+
     #[test]
     fn test_validate_dev_route_requires_tako_domain() {
         let err = validate_dev_route("api.example.com", "my-app").unwrap_err();
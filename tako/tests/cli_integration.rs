@@ -1945,6 +1945,57 @@ mod output_modes {
         // Verbose log lines should be on stderr, not stdout
         assert!(!err.is_empty(), "Verbose output should appear on stderr");
     }
+
+    #[test]
+    fn json_flag_reports_listed_servers_as_data() {
+        let temp = TempDir::new().unwrap();
+        let project_dir = temp.path().join("project");
+        let home = temp.path().join("home");
+        let tako_home = temp.path().join("tako-home");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(&home).unwrap();
+        fs::create_dir_all(&tako_home).unwrap();
+
+        let add = run_tako_with_env(
+            &[
+                "servers",
+                "add",
+                "10.0.0.2",
+                "--name",
+                "eu-edge",
+                "--port",
+                "2222",
+                "--description",
+                "EU Edge",
+                "--no-test",
+            ],
+            &project_dir,
+            &home,
+            &tako_home,
+        );
+        assert!(add.status.success(), "add should succeed");
+
+        let ls = run_tako_with_env(
+            &["--json", "servers", "ls"],
+            &project_dir,
+            &home,
+            &tako_home,
+        );
+        assert!(ls.status.success(), "{}", stderr_str(&ls));
+
+        let report: serde_json::Value =
+            serde_json::from_str(stdout_str(&ls).trim()).expect("stdout is one JSON object");
+        assert_eq!(report["status"], "ok");
+        assert_eq!(
+            report["data"]["servers"],
+            serde_json::json!([{
+                "name": "eu-edge",
+                "host": "10.0.0.2",
+                "port": 2222,
+                "description": "EU Edge",
+            }])
+        );
+    }
 }
 
 mod implode_commands {